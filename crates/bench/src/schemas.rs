@@ -70,9 +70,9 @@ impl BenchTable for Person {
     fn into_product_value(self) -> sats::ProductValue {
         sats::ProductValue {
             elements: vec![
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::U32(self.id)),
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::String(self.name)),
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::U64(self.age)),
+                sats::AlgebraicValue::U32(self.id),
+                sats::AlgebraicValue::String(self.name.into()),
+                sats::AlgebraicValue::U64(self.age),
             ],
         }
     }
@@ -110,9 +110,9 @@ impl BenchTable for Location {
     fn into_product_value(self) -> sats::ProductValue {
         sats::ProductValue {
             elements: vec![
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::U32(self.id)),
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::U64(self.x)),
-                sats::AlgebraicValue::Builtin(sats::BuiltinValue::U64(self.y)),
+                sats::AlgebraicValue::U32(self.id),
+                sats::AlgebraicValue::U64(self.x),
+                sats::AlgebraicValue::U64(self.y),
             ],
         }
     }
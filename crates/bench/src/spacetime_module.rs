@@ -144,9 +144,9 @@ impl BenchDatabase for SpacetimeModule {
 
     fn insert_bulk<T: BenchTable>(&mut self, table_id: &Self::TableId, rows: Vec<T>) -> ResultBench<()> {
         let args = ProductValue {
-            elements: vec![AlgebraicValue::Builtin(spacetimedb_lib::sats::BuiltinValue::Array {
-                val: ArrayValue::Product(rows.into_iter().map(|row| row.into_product_value()).collect()),
-            })],
+            elements: vec![AlgebraicValue::Array(ArrayValue::Product(
+                rows.into_iter().map(|row| row.into_product_value()).collect(),
+            ))],
         };
         let SpacetimeModule { runtime, module } = self;
         let module = module.as_mut().unwrap();
@@ -190,19 +190,19 @@ impl BenchDatabase for SQLite {
 
         begin.execute(())?;
         match value {
-            AlgebraicValue::Builtin(sats::BuiltinValue::String(value)) => {
+            AlgebraicValue::String(value) => {
                 for _ in stmt.query_map((value,), |row| {
                     black_box(row);
                     Ok(())
                 })? {}
             }
-            AlgebraicValue::Builtin(sats::BuiltinValue::U32(value)) => {
+            AlgebraicValue::U32(value) => {
                 for _ in stmt.query_map((value,), |row| {
                     black_box(row);
                     Ok(())
                 })? {}
             }
-            AlgebraicValue::Builtin(sats::BuiltinValue::U64(value)) => {
+            AlgebraicValue::U64(value) => {
                 for _ in stmt.query_map((value,), |row| {
                     black_box(row);
                     Ok(())
@@ -237,7 +237,8 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
                         fn visit_seq_product<A: #spacetimedb_lib::de::SeqProductAccess<'de>>(self, mut tup: A) -> Result<Self::Output, A::Error> {
                             Ok(#name {
                                 #(#field_names:
-                                    tup.next_element::<#field_types>()?
+                                    tup.next_element::<#field_types>()
+                                        .map_err(|e| #spacetimedb_lib::de::Error::in_field(e, #iter_n, Some(#field_strings)))?
                                         .ok_or_else(|| #spacetimedb_lib::de::Error::invalid_product_length(#iter_n, &self))?,)*
                             })
                         }
@@ -251,7 +252,8 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
                                         if #field_names.is_some() {
                                             return Err(#spacetimedb_lib::de::Error::duplicate_field(#iter_n2, Some(#field_strings), &self))
                                         }
-                                        #field_names = Some(#spacetimedb_lib::de::NamedProductAccess::get_field_value(&mut __prod)?)
+                                        #field_names = Some(#spacetimedb_lib::de::NamedProductAccess::get_field_value(&mut __prod)
+                                            .map_err(|e| #spacetimedb_lib::de::Error::in_field(e, #iter_n2, Some(#field_strings)))?)
                                     })*
                                 }
                             }
@@ -269,6 +271,10 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
                             names.extend::<&[&str]>(&[#(#field_strings),*])
                         }
 
+                        fn is_field_name(&self, name: &str) -> bool {
+                            <[&str]>::contains(&[#(#field_strings),*], &name)
+                        }
+
                         fn visit<__E: #spacetimedb_lib::de::Error>(self, name: &str) -> Result<Self::Output, __E> {
                             match name {
                                 #(#field_strings => Ok(__ProductFieldIdent::#field_names),)*
@@ -286,6 +292,7 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
         }
         SatsTypeData::Sum(variants) => {
             let variant_names = variants.iter().map(|var| &*var.name).collect::<Vec<_>>();
+            let n_variants = variants.len();
             let variant_idents = variants.iter().map(|var| var.ident).collect::<Vec<_>>();
             let tags = 0u8..;
             let arms = variants.iter().map(|var| {
@@ -325,6 +332,10 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
                             Some(#tuple_name)
                         }
 
+                        fn variant_count(&self) -> Option<usize> {
+                            Some(#n_variants)
+                        }
+
                         fn visit_sum<A: #spacetimedb_lib::de::SumAccess<'de>>(self, __data: A) -> Result<Self::Output, A::Error> {
                             let (__variant, __access) = __data.variant(self)?;
                             match __variant {
@@ -375,6 +386,10 @@ pub(crate) fn derive_serialize(ty: &SatsType) -> TokenStream {
             let fieldnamestrings = fields.iter().map(|field| field.name.as_ref().unwrap());
             let nfields = fields.len();
             quote! {
+                let mut __serializer = __serializer;
+                if let Some(__size) = <Self as #spacetimedb_lib::ser::Serialize>::__BSATN_STATIC_SIZE {
+                    #spacetimedb_lib::ser::Serializer::hint_total_size(&mut __serializer, __size);
+                }
                 let mut __prod = __serializer.serialize_named_product(#nfields)?;
                 #(#spacetimedb_lib::ser::SerializeNamedProduct::serialize_element::<#tys>(&mut __prod, Some(#fieldnamestrings), &self.#fieldnames)?;)*
                 #spacetimedb_lib::ser::SerializeNamedProduct::end(__prod)
@@ -390,7 +405,7 @@ pub(crate) fn derive_serialize(ty: &SatsType) -> TokenStream {
                     }
                 } else {
                     quote! {
-                        Self::#name => __serializer.serialize_variant(#tag, Some(#name_str), &()),
+                        Self::#name => __serializer.serialize_unit_variant(#tag, Some(#name_str)),
                     }
                 }
             });
@@ -400,8 +415,22 @@ pub(crate) fn derive_serialize(ty: &SatsType) -> TokenStream {
             })
         }
     };
+    // A product has a static size when all of its fields do.
+    let static_size = match &ty.data {
+        SatsTypeData::Product(fields) => {
+            let tys = fields.iter().map(|f| &f.ty);
+            quote! {
+                const __BSATN_STATIC_SIZE: Option<usize> = #spacetimedb_lib::ser::__sum_static_sizes(&[
+                    #(<#tys as #spacetimedb_lib::ser::Serialize>::__BSATN_STATIC_SIZE,)*
+                ]);
+            }
+        }
+        SatsTypeData::Sum(_) => quote!(),
+    };
     quote! {
         impl #impl_generics #spacetimedb_lib::ser::Serialize for #name #ty_generics #where_clause {
+            #static_size
+
             fn serialize<S: #spacetimedb_lib::ser::Serializer>(&self, __serializer: S) -> Result<S::Ok, S::Error> {
                 #body
             }
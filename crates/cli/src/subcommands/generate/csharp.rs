@@ -126,6 +126,7 @@ fn ty_fmt<'a>(ctx: &'a GenCtx, ty: &'a AlgebraicType, namespace: &'a str) -> imp
                 }
             }
         }
+        AlgebraicType::Newtype(nt) => write!(f, "{}", ty_fmt(ctx, &nt.inner, namespace)),
     })
 }
 
@@ -278,6 +279,11 @@ fn convert_type<'a>(
                 }
             }
         }
+        AlgebraicType::Newtype(nt) => write!(
+            f,
+            "{}",
+            convert_type(ctx, vecnest, &nt.inner, value.to_string(), namespace)
+        ),
     })
 }
 
@@ -333,6 +339,7 @@ fn convert_algebraic_type<'a>(ctx: &'a GenCtx, ty: &'a AlgebraicType, namespace:
                 }
             }
         }
+        AlgebraicType::Newtype(nt) => write!(f, "{}", convert_algebraic_type(ctx, &nt.inner, namespace)),
     })
 }
 
@@ -987,8 +994,8 @@ fn autogen_csharp_access_funcs_for_struct(
                     continue;
                 }
             }
-            AlgebraicType::Ref(_) => {
-                // TODO: We don't allow filtering on enums or tuples right now;
+            AlgebraicType::Ref(_) | AlgebraicType::Newtype(_) => {
+                // TODO: We don't allow filtering on enums, tuples, or newtypes right now;
                 //       it's possible we may consider it for the future.
                 continue;
             }
@@ -1279,7 +1286,7 @@ pub fn autogen_csharp_reducer(ctx: &GenCtx, reducer: &ReducerDef, namespace: &st
                 AlgebraicType::Product(_) => {
                     json_args.push_str(arg_name.as_str());
                 }
-                Builtin(_) => {
+                Builtin(_) | AlgebraicType::Newtype(_) => {
                     json_args.push_str(arg_name.as_str());
                 }
                 AlgebraicType::Ref(type_ref) => {
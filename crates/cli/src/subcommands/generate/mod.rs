@@ -216,6 +216,10 @@ impl GenItem {
         }
     }
 
+    /// Generates the file for this item in `lang`, returning its name and contents.
+    ///
+    /// A type alias of a newtype is generated as the type the newtype wraps,
+    /// as the client languages have no notion of the label.
     fn generate(&self, ctx: &GenCtx, lang: Language, namespace: &str) -> Option<(String, String)> {
         match lang {
             Language::Csharp => self.generate_csharp(ctx, namespace),
@@ -232,7 +236,7 @@ impl GenItem {
                 Some((rust::rust_type_file_name(&table.name), code))
             }
             GenItem::TypeAlias(TypeAlias { name, ty }) => {
-                let code = match &ctx.typespace[*ty] {
+                let code = match ctx.typespace[*ty].unwrap_newtype() {
                     AlgebraicType::Sum(sum) => rust::autogen_rust_sum(ctx, name, sum),
                     AlgebraicType::Product(prod) => rust::autogen_rust_tuple(ctx, name, prod),
                     _ => todo!(),
@@ -253,7 +257,7 @@ impl GenItem {
                 let name = table.name.to_case(Case::Snake);
                 Some((name + ".py", code))
             }
-            GenItem::TypeAlias(TypeAlias { name, ty }) => match &ctx.typespace[*ty] {
+            GenItem::TypeAlias(TypeAlias { name, ty }) => match ctx.typespace[*ty].unwrap_newtype() {
                 AlgebraicType::Sum(sum) => {
                     let filename = name.replace('.', "").to_case(Case::Snake);
                     let code = python::autogen_python_sum(ctx, name, sum);
@@ -266,6 +270,7 @@ impl GenItem {
                 }
                 AlgebraicType::Builtin(_) => todo!(),
                 AlgebraicType::Ref(_) => todo!(),
+                AlgebraicType::Newtype(_) => unreachable!("newtypes were stripped"),
             },
             GenItem::Reducer(reducer) => {
                 let code = python::autogen_python_reducer(ctx, reducer);
@@ -282,7 +287,7 @@ impl GenItem {
                 let name = table.name.to_case(Case::Snake);
                 Some((name + ".ts", code))
             }
-            GenItem::TypeAlias(TypeAlias { name, ty }) => match &ctx.typespace[*ty] {
+            GenItem::TypeAlias(TypeAlias { name, ty }) => match ctx.typespace[*ty].unwrap_newtype() {
                 AlgebraicType::Sum(sum) => {
                     let filename = name.replace('.', "").to_case(Case::Snake);
                     let code = typescript::autogen_typescript_sum(ctx, name, sum);
@@ -295,6 +300,7 @@ impl GenItem {
                 }
                 AlgebraicType::Builtin(_) => todo!(),
                 AlgebraicType::Ref(_) => todo!(),
+                AlgebraicType::Newtype(_) => unreachable!("newtypes were stripped"),
             },
             GenItem::Reducer(reducer) => {
                 let code = typescript::autogen_typescript_reducer(ctx, reducer);
@@ -310,7 +316,7 @@ impl GenItem {
                 let code = csharp::autogen_csharp_table(ctx, table, namespace);
                 Some((table.name.clone() + ".cs", code))
             }
-            GenItem::TypeAlias(TypeAlias { name, ty }) => match &ctx.typespace[*ty] {
+            GenItem::TypeAlias(TypeAlias { name, ty }) => match ctx.typespace[*ty].unwrap_newtype() {
                 AlgebraicType::Sum(sum) => {
                     let filename = name.replace('.', "");
                     let code = csharp::autogen_csharp_sum(ctx, name, sum, namespace);
@@ -322,6 +328,7 @@ impl GenItem {
                 }
                 AlgebraicType::Builtin(_) => todo!(),
                 AlgebraicType::Ref(_) => todo!(),
+                AlgebraicType::Newtype(_) => unreachable!("newtypes were stripped"),
             },
             GenItem::Reducer(reducer) => {
                 let code = csharp::autogen_csharp_reducer(ctx, reducer, namespace);
@@ -93,6 +93,11 @@ fn convert_type<'a>(
                 }
             }
         }
+        AlgebraicType::Newtype(nt) => write!(
+            f,
+            "{}",
+            convert_type(ctx, vecnest, &nt.inner, value.to_string(), ref_prefix)
+        ),
     })
 }
 
@@ -130,6 +135,7 @@ fn ty_fmt<'a>(ctx: &'a GenCtx, ty: &'a AlgebraicType, ref_prefix: &'a str) -> im
             }
         },
         AlgebraicType::Ref(r) => write!(f, "{}{}", ref_prefix, python_typename(ctx, *r)),
+        AlgebraicType::Newtype(nt) => write!(f, "{}", ty_fmt(ctx, &nt.inner, ref_prefix)),
     })
 }
 
@@ -299,8 +305,8 @@ fn autogen_python_product_table_common(
                             continue;
                         }
                     }
-                    AlgebraicType::Ref(_) => {
-                        // TODO: We don't allow filtering on enums or tuples right now, its possible we may consider it for the future.
+                    AlgebraicType::Ref(_) | AlgebraicType::Newtype(_) => {
+                        // TODO: We don't allow filtering on enums, tuples, or newtypes right now, its possible we may consider it for the future.
                         continue;
                     }
                     AlgebraicType::Builtin(b) => match maybe_primitive(b) {
@@ -388,7 +394,7 @@ fn autogen_python_product_table_common(
                     AlgebraicType::Product(_) => {
                         reducer_args.push(format!("self.{python_field_name}"));
                     }
-                    Builtin(_) => {
+                    Builtin(_) | AlgebraicType::Newtype(_) => {
                         reducer_args.push(format!("self.{python_field_name}"));
                     }
                     AlgebraicType::Ref(type_ref) => {
@@ -521,6 +527,11 @@ pub fn encode_type<'a>(
                 }
             }
         }
+        AlgebraicType::Newtype(nt) => write!(
+            f,
+            "{}",
+            encode_type(ctx, vecnest, &nt.inner, value.to_string(), ref_prefix)
+        ),
     })
 }
 
@@ -102,6 +102,7 @@ pub fn write_type<W: Write>(ctx: &impl Fn(AlgebraicTypeRef) -> String, out: &mut
         AlgebraicType::Ref(r) => {
             write!(out, "{}", ctx(*r)).unwrap();
         }
+        AlgebraicType::Newtype(nt) => write_type(ctx, out, &nt.inner),
     }
 }
 
@@ -73,6 +73,7 @@ fn ty_fmt<'a>(ctx: &'a GenCtx, ty: &'a AlgebraicType, ref_prefix: &'a str) -> im
             }
         },
         AlgebraicType::Ref(r) => write!(f, "{}{}", ref_prefix, typescript_typename(ctx, *r)),
+        AlgebraicType::Newtype(nt) => write!(f, "{}", ty_fmt(ctx, &nt.inner, ref_prefix)),
     })
 }
 fn typescript_as_type(b: &BuiltinType) -> &str {
@@ -208,6 +209,11 @@ fn convert_type<'a>(
             let name = typescript_typename(ctx, *r);
             write!(f, "{ref_prefix}{name}.fromValue({value})",)
         }
+        AlgebraicType::Newtype(nt) => write!(
+            f,
+            "{}",
+            convert_type(ctx, vecnest, &nt.inner, value.to_string(), ref_prefix)
+        ),
     })
 }
 
@@ -245,6 +251,7 @@ fn convert_algebraic_type<'a>(ctx: &'a GenCtx, ty: &'a AlgebraicType, ref_prefix
             MaybePrimitive::Map(_) => todo!(),
         },
         AlgebraicType::Ref(r) => write!(f, "{ref_prefix}{}.getAlgebraicType()", typescript_typename(ctx, *r)),
+        AlgebraicType::Newtype(nt) => write!(f, "{}", convert_algebraic_type(ctx, &nt.inner, ref_prefix)),
     })
 }
 
@@ -323,6 +330,7 @@ fn serialize_type<'a>(
             let typename = typescript_typename(ctx, *r);
             write!(f, "{prefix}{typename}.serialize({value})",)
         }
+        AlgebraicType::Newtype(nt) => write!(f, "{}", serialize_type(ctx, &nt.inner, value, prefix)),
     })
 }
 
@@ -1002,8 +1010,8 @@ fn autogen_typescript_access_funcs_for_struct(
                     continue;
                 }
             }
-            AlgebraicType::Ref(_) | AlgebraicType::Sum(_) => {
-                // TODO: We don't allow filtering on enums or tuples right now, its possible we may consider it for the future.
+            AlgebraicType::Ref(_) | AlgebraicType::Sum(_) | AlgebraicType::Newtype(_) => {
+                // TODO: We don't allow filtering on enums, tuples, or newtypes right now, its possible we may consider it for the future.
                 continue;
             }
             AlgebraicType::Builtin(b) => match maybe_primitive(b) {
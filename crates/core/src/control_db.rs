@@ -528,7 +528,9 @@ impl ControlDb {
                 }
             };
             let Ok(arr) = <[u8; 16]>::try_from(balance_entry.1.as_ref()) else {
-                return Err(Error::DecodingError(bsatn::DecodeError::BufferLength));
+                return Err(Error::DecodingError(bsatn::DecodeError::new(balance_len_error(
+                    &balance_entry.1,
+                ))));
             };
             let balance = i128::from_be_bytes(arr);
             let energy_balance = EnergyBalance {
@@ -548,7 +550,7 @@ impl ControlDb {
         let value = tree.get(identity.as_bytes())?;
         if let Some(value) = value {
             let Ok(arr) = <[u8; 16]>::try_from(value.as_ref()) else {
-                return Err(Error::DecodingError(bsatn::DecodeError::BufferLength));
+                return Err(Error::DecodingError(bsatn::DecodeError::new(balance_len_error(&value))));
             };
             let balance = i128::from_be_bytes(arr);
             Ok(Some(EnergyQuanta(balance)))
@@ -644,3 +646,11 @@ fn cas_u64(
         new.map(|x| x.to_be_bytes()).as_ref(),
     )
 }
+
+/// The error for a stored energy balance that isn't the 16 bytes of an `i128`.
+fn balance_len_error(bytes: &[u8]) -> bsatn::ErrorKind {
+    bsatn::ErrorKind::WrongKind {
+        expected: "a 16 byte balance".into(),
+        found: format!("{} bytes", bytes.len()),
+    }
+}
@@ -54,7 +54,7 @@ use spacetimedb_lib::{
     DataKey, Hash,
 };
 use spacetimedb_sats::{
-    AlgebraicType, AlgebraicValue, BuiltinType, BuiltinValue, ProductType, ProductTypeElement, ProductValue,
+    AlgebraicType, AlgebraicValue, BuiltinType, ProductType, ProductTypeElement, ProductValue,
 };
 use thiserror::Error;
 
@@ -701,7 +701,7 @@ impl Inner {
         self.iter_by_col_eq(
             &ST_SEQUENCES_ID,
             &seq_name_col,
-            AlgebraicValue::String(seq_name.to_owned()),
+            AlgebraicValue::String(seq_name.into()),
         )
         .map(|mut iter| {
             iter.next()
@@ -949,7 +949,7 @@ impl Inner {
         self.iter_by_col_eq(
             &ST_TABLES_ID,
             &table_name_col,
-            AlgebraicValue::String(table_name.to_owned()),
+            AlgebraicValue::String(table_name.into()),
         )
         .map(|mut iter| {
             iter.next()
@@ -1110,7 +1110,7 @@ impl Inner {
         self.iter_by_col_eq(
             &ST_INDEXES_ID,
             &index_name_col,
-            AlgebraicValue::String(index_name.to_owned()),
+            AlgebraicValue::String(index_name.into()),
         )
         .map(|mut iter| {
             iter.next()
@@ -1178,22 +1178,19 @@ impl Inner {
 
     /// Check if the value is one of the `numeric` types and is `0`.
     fn can_replace_with_sequence(value: &AlgebraicValue) -> bool {
-        match value.as_builtin() {
-            Some(x) => match x {
-                BuiltinValue::I8(x) => *x == 0,
-                BuiltinValue::U8(x) => *x == 0,
-                BuiltinValue::I16(x) => *x == 0,
-                BuiltinValue::U16(x) => *x == 0,
-                BuiltinValue::I32(x) => *x == 0,
-                BuiltinValue::U32(x) => *x == 0,
-                BuiltinValue::I64(x) => *x == 0,
-                BuiltinValue::U64(x) => *x == 0,
-                BuiltinValue::I128(x) => *x == 0,
-                BuiltinValue::U128(x) => *x == 0,
-                BuiltinValue::F32(x) => *x == 0.0,
-                BuiltinValue::F64(x) => *x == 0.0,
-                _ => false,
-            },
+        match value {
+            AlgebraicValue::I8(x) => *x == 0,
+            AlgebraicValue::U8(x) => *x == 0,
+            AlgebraicValue::I16(x) => *x == 0,
+            AlgebraicValue::U16(x) => *x == 0,
+            AlgebraicValue::I32(x) => *x == 0,
+            AlgebraicValue::U32(x) => *x == 0,
+            AlgebraicValue::I64(x) => *x == 0,
+            AlgebraicValue::U64(x) => *x == 0,
+            AlgebraicValue::I128(x) => *x == 0,
+            AlgebraicValue::U128(x) => *x == 0,
+            AlgebraicValue::F32(x) => *x == 0.0,
+            AlgebraicValue::F64(x) => *x == 0.0,
             _ => false,
         }
     }
@@ -2583,7 +2580,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -2595,7 +2592,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2610,7 +2607,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
         ]);
         assert!(datastore.insert_mut_tx(&mut tx, table_id, row).is_err());
         let rows = datastore
@@ -2630,7 +2627,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -2644,7 +2641,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2659,7 +2656,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(15), // A number which will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.commit_mut_tx(tx)?;
@@ -2684,7 +2681,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -2692,7 +2689,7 @@ mod tests {
         let mut tx = datastore.begin_mut_tx();
         let created_row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(1),
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         let num_deleted = datastore.delete_by_rel_mut_tx(&mut tx, table_id, vec![created_row])?;
@@ -2704,7 +2701,7 @@ mod tests {
         assert_eq!(rows.len(), 0);
         let created_row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(1),
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(19),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, created_row)?;
@@ -2716,7 +2713,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(19),
             ])
         ]);
@@ -2731,14 +2728,14 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
         for _ in 0..2 {
             let created_row = ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ]);
             let num_deleted = datastore.delete_by_rel_mut_tx(&mut tx, table_id, vec![created_row.clone()])?;
@@ -2757,7 +2754,7 @@ mod tests {
             assert_eq!(rows, vec![
                 ProductValue::from_iter(vec![
                     AlgebraicValue::U32(1),
-                    AlgebraicValue::String("Foo".to_string()),
+                    AlgebraicValue::String("Foo".into()),
                     AlgebraicValue::U32(18),
                 ])
             ]);
@@ -2773,7 +2770,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row.clone())?;
@@ -2795,7 +2792,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2810,7 +2807,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row.clone())?;
@@ -2834,7 +2831,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2851,7 +2848,7 @@ mod tests {
         let mut tx = datastore.begin_mut_tx();
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row.clone())?;
@@ -2866,7 +2863,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(2),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2883,7 +2880,7 @@ mod tests {
         let mut tx = datastore.begin_mut_tx();
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -2915,7 +2912,7 @@ mod tests {
         ]);
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Bar".to_string()),
+            AlgebraicValue::String("Bar".into()),
             AlgebraicValue::U32(18),
         ]);
         let result = datastore.insert_mut_tx(&mut tx, table_id, row);
@@ -2936,7 +2933,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -2951,7 +2948,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -2986,7 +2983,7 @@ mod tests {
 
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Bar".to_string()),
+            AlgebraicValue::String("Bar".into()),
             AlgebraicValue::U32(18),
         ]);
         let result = datastore.insert_mut_tx(&mut tx, table_id, row);
@@ -3007,7 +3004,7 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -3022,7 +3019,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -3055,7 +3052,7 @@ mod tests {
         ]);
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Bar".to_string()),
+            AlgebraicValue::String("Bar".into()),
             AlgebraicValue::U32(18),
         ]);
         datastore.insert_mut_tx(&mut tx, table_id, row)?;
@@ -3067,12 +3064,12 @@ mod tests {
         assert_eq!(rows, vec![
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(1),
-                AlgebraicValue::String("Foo".to_string()),
+                AlgebraicValue::String("Foo".into()),
                 AlgebraicValue::U32(18),
             ]),
             ProductValue::from_iter(vec![
                 AlgebraicValue::U32(2),
-                AlgebraicValue::String("Bar".to_string()),
+                AlgebraicValue::String("Bar".into()),
                 AlgebraicValue::U32(18),
             ])
         ]);
@@ -3089,7 +3086,7 @@ mod tests {
         let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
         let row = ProductValue::from_iter(vec![
             AlgebraicValue::U32(0), // 0 will be ignored.
-            AlgebraicValue::String("Foo".to_string()),
+            AlgebraicValue::String("Foo".into()),
             AlgebraicValue::U32(18),
         ]);
         // Because of autoinc columns, we will get a slightly different
@@ -679,7 +679,7 @@ impl<Name: AsRef<str>> From<&StTableRow<Name>> for ProductValue {
     fn from(x: &StTableRow<Name>) -> Self {
         product![
             AlgebraicValue::U32(x.table_id),
-            AlgebraicValue::String(x.table_name.as_ref().to_owned()),
+            AlgebraicValue::String(x.table_name.as_ref().into()),
             AlgebraicValue::String(x.table_type.as_str().into()),
             AlgebraicValue::String(x.table_access.as_str().into())
         ]
@@ -738,7 +738,7 @@ impl<Name: AsRef<str>> From<&StColumnRow<Name>> for ProductValue {
             AlgebraicValue::U32(x.table_id),
             AlgebraicValue::U32(x.col_id),
             AlgebraicValue::Bytes(bytes),
-            AlgebraicValue::String(x.col_name.as_ref().to_owned()),
+            AlgebraicValue::String(x.col_name.as_ref().into()),
             AlgebraicValue::Bool(x.is_autoinc),
         ]
     }
@@ -799,7 +799,7 @@ impl<Name: AsRef<str>> From<&StIndexRow<Name>> for ProductValue {
             AlgebraicValue::U32(x.index_id),
             AlgebraicValue::U32(x.table_id),
             AlgebraicValue::ArrayOf(x.cols.clone()),
-            AlgebraicValue::String(x.index_name.as_ref().to_string()),
+            AlgebraicValue::String(x.index_name.as_ref().into()),
             AlgebraicValue::Bool(x.is_unique)
         ]
     }
@@ -864,7 +864,7 @@ impl<Name: AsRef<str>> From<&StSequenceRow<Name>> for ProductValue {
     fn from(x: &StSequenceRow<Name>) -> Self {
         product![
             AlgebraicValue::U32(x.sequence_id),
-            AlgebraicValue::String(x.sequence_name.as_ref().to_string()),
+            AlgebraicValue::String(x.sequence_name.as_ref().into()),
             AlgebraicValue::U32(x.table_id),
             AlgebraicValue::U32(x.col_id),
             AlgebraicValue::I128(x.increment),
@@ -942,7 +942,7 @@ impl<Name: AsRef<str>> From<&StConstraintRow<Name>> for ProductValue {
     fn from(x: &StConstraintRow<Name>) -> Self {
         product![
             AlgebraicValue::U32(x.constraint_id),
-            AlgebraicValue::String(x.constraint_name.as_ref().to_string()),
+            AlgebraicValue::String(x.constraint_name.as_ref().into()),
             AlgebraicValue::U8(x.kind.bits()),
             AlgebraicValue::U32(x.table_id),
             AlgebraicValue::ArrayOf(x.columns.clone())
@@ -1,7 +1,7 @@
 use std::fmt::{self, Write};
 use std::ops::Deref;
 
-use crate::buffer::{BufReader, BufWriter, DecodeError};
+use crate::buffer::{BufReader, BufWriter, DecodeError, ErrorKind};
 use crate::hash::hash_bytes;
 
 #[derive(Debug, Copy, Clone, PartialEq, Ord, PartialOrd, Eq, Hash)]
@@ -106,7 +106,11 @@ impl DataKey {
         if is_hash {
             // future-proof it, ish
             if header != IS_HASH_BIT {
-                return Err(DecodeError::InvalidTag);
+                return Err(ErrorKind::WrongKind {
+                    expected: format!("the hash header {IS_HASH_BIT:#04x}"),
+                    found: format!("{header:#04x}"),
+                }
+                .into());
             }
             let hash = super::hash::Hash {
                 data: bytes.get_array()?,
@@ -115,7 +119,11 @@ impl DataKey {
         } else {
             let len = header;
             if len as usize > MAX_INLINE {
-                return Err(DecodeError::BufferLength);
+                return Err(ErrorKind::WrongKind {
+                    expected: format!("an inline length of at most {MAX_INLINE}"),
+                    found: len.to_string(),
+                }
+                .into());
             }
             let mut buf = [0; MAX_INLINE];
             let data = bytes.get_slice(len as usize)?;
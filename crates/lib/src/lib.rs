@@ -247,15 +247,9 @@ impl TryFrom<u8> for ColumnIndexAttribute {
     }
 }
 
-impl<'de> de::Deserialize<'de> for ColumnIndexAttribute {
-    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        Self::from_bits(deserializer.deserialize_u8()?)
-            .ok_or_else(|| de::Error::custom("invalid bitflags for ColumnIndexAttribute"))
-    }
-}
+spacetimedb_sats::impl_deserialize!([] ColumnIndexAttribute, (deserializer) => {
+    Self::from_bits(deserializer.deserialize_u8()?)
+        .ok_or_else(|| de::Error::custom("invalid bitflags for ColumnIndexAttribute"))
+});
 
-impl ser::Serialize for ColumnIndexAttribute {
-    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_u8(self.bits())
-    }
-}
+spacetimedb_sats::impl_serialize!([] ColumnIndexAttribute, (self, serializer) => serializer.serialize_u8(self.bits()));
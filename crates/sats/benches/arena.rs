@@ -0,0 +1,50 @@
+use bumpalo::Bump;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::arena::AlgebraicValueIn;
+use spacetimedb_sats::{
+    bsatn, product, AlgebraicType, AlgebraicValue, ProductType, ProductTypeElement, ProductValue, Typespace,
+    WithTypespace,
+};
+
+fn arena(c: &mut Criterion) {
+    let ty = ProductType::new(vec![
+        ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+        ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::U32), "scores"),
+    ]);
+    let rows = (0..100_000u64)
+        .map(|i| {
+            let row = product![i, format!("player #{i}"), AlgebraicValue::ArrayOf(vec![i as u32; 4])];
+            bsatn::to_vec(&row).unwrap()
+        })
+        .collect::<Vec<_>>();
+    let ts = Typespace::default();
+
+    let mut group = c.benchmark_group("decode_and_drop_100k_rows");
+    group.bench_function("heap", |b| {
+        b.iter(|| {
+            let decoded = black_box(&rows)
+                .iter()
+                .map(|bytes| ProductValue::decode(&ty, &mut &**bytes).unwrap())
+                .collect::<Vec<_>>();
+            drop(black_box(decoded));
+        })
+    });
+    let mut bump = Bump::new();
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            let decoded = black_box(&rows)
+                .iter()
+                .map(|bytes| {
+                    AlgebraicValueIn::decode_product(&bump, WithTypespace::new(&ts, &ty), &mut &**bytes).unwrap()
+                })
+                .collect::<Vec<_>>();
+            drop(black_box(decoded));
+            bump.reset();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, arena);
+criterion_main!(benches);
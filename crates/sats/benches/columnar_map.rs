@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::algebraic_value::bytes_codec::{decode_map_columnar, encode_map_columnar};
+use spacetimedb_sats::bsatn::compression::{compress, CompressionAlgo};
+use spacetimedb_sats::{bsatn, AlgebraicType, AlgebraicValue, MapType, MapValue, Typespace};
+
+/// Prints the sizes of `map` compressed in the columnar encoding and in BSATN, for each algorithm.
+#[allow(clippy::disallowed_macros)]
+fn report_compressed_sizes(name: &str, map: &MapValue, ty: &MapType) {
+    let columnar = encode_map_columnar(map, ty, &Typespace::default());
+    let rows = bsatn::to_vec(map).unwrap();
+    for algo in [CompressionAlgo::Lz4, CompressionAlgo::Snappy] {
+        let columnar = compress(&columnar, algo).unwrap().len();
+        let rows = compress(&rows, algo).unwrap().len();
+        let ratio = columnar as f64 / rows as f64;
+        println!("{name}, {algo:?}: columnar {columnar} bytes, bsatn {rows} bytes, ratio {ratio:.3}");
+    }
+}
+
+fn columnar_map(c: &mut Criterion) {
+    let ty = MapType::new(AlgebraicType::String, AlgebraicType::U32);
+    let map = (0..1000u32)
+        .map(|i| (format!("player-{i:04}").into(), AlgebraicValue::U32(i * 7)))
+        .collect::<MapValue>();
+    let random_values = (0..1000u32)
+        .map(|i| {
+            (
+                format!("player-{i:04}").into(),
+                AlgebraicValue::U32(i.wrapping_mul(0x9e37_79b9)),
+            )
+        })
+        .collect::<MapValue>();
+    report_compressed_sizes("map_1000_string_to_u32", &map, &ty);
+    report_compressed_sizes("map_1000_string_to_random_u32", &random_values, &ty);
+
+    let ts = Typespace::default();
+    let columnar = encode_map_columnar(&map, &ty, &ts);
+    let rows = bsatn::to_vec(&map).unwrap();
+    let map_ty = AlgebraicType::map(AlgebraicType::String, AlgebraicType::U32);
+
+    let mut group = c.benchmark_group("map_1000_string_to_u32");
+    group.bench_function("encode_columnar", |b| {
+        b.iter(|| encode_map_columnar(black_box(&map), &ty, &ts))
+    });
+    group.bench_function("encode_bsatn", |b| b.iter(|| bsatn::to_vec(black_box(&map)).unwrap()));
+    group.bench_function("decode_columnar", |b| {
+        b.iter(|| decode_map_columnar(black_box(&columnar), &ty, &ts).unwrap())
+    });
+    group.bench_function("decode_bsatn", |b| {
+        b.iter(|| AlgebraicValue::decode(&map_ty, &mut black_box(&*rows)).unwrap())
+    });
+    group.bench_function("encode_columnar_lz4", |b| {
+        b.iter(|| compress(&encode_map_columnar(black_box(&map), &ty, &ts), CompressionAlgo::Lz4).unwrap())
+    });
+    group.bench_function("encode_bsatn_lz4", |b| {
+        b.iter(|| compress(&bsatn::to_vec(black_box(&map)).unwrap(), CompressionAlgo::Lz4).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, columnar_map);
+criterion_main!(benches);
@@ -0,0 +1,93 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use spacetimedb_sats::de::{DeserializeSeed, ValueSeed};
+use spacetimedb_sats::{
+    bsatn, product, AlgebraicType, AlgebraicValue, ProductType, ProductTypeElement, ProductValue, Typespace,
+    WithTypespace,
+};
+
+fn decode(c: &mut Criterion) {
+    let ty = ProductType::new(vec![
+        ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+        ProductTypeElement::new_named(AlgebraicType::U64, "owner"),
+        ProductTypeElement::new_named(AlgebraicType::I64, "balance"),
+        ProductTypeElement::new_named(AlgebraicType::Bool, "active"),
+        ProductTypeElement::new_named(AlgebraicType::F64, "score"),
+        ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::U32), "parent"),
+    ]);
+    let rows = (0..100_000u32)
+        .map(|i| {
+            let row = product![
+                i,
+                u64::from(i) << 8,
+                -i64::from(i),
+                i % 2 == 0,
+                AlgebraicValue::F64(f64::from(i).into()),
+                format!("{i:08}"),
+                AlgebraicValue::OptionSome(AlgebraicValue::U32(i / 2))
+            ];
+            bsatn::to_vec(&row).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    c.bench_function("decode_100k_mixed_rows", |b| {
+        b.iter(|| {
+            for bytes in black_box(&rows) {
+                black_box(ProductValue::decode(&ty, &mut &**bytes).unwrap());
+            }
+        })
+    });
+}
+
+/// Decodes 1M rows of the same shape one after another,
+/// into fresh values and into the storage of one seed, which allocates nothing after the first row.
+fn decode_in_place(c: &mut Criterion) {
+    const ROWS: u32 = 1_000_000;
+    let ty = ProductType::from_iter([
+        AlgebraicType::U32,
+        AlgebraicType::I64,
+        AlgebraicType::String,
+        AlgebraicType::array(AlgebraicType::U16),
+        AlgebraicType::option(AlgebraicType::String),
+    ]);
+    let mut bytes = Vec::new();
+    for i in 0..ROWS {
+        let tag = AlgebraicValue::String(format!("tag {:04}", i % 10_000).into());
+        let row = product![
+            i,
+            -i64::from(i),
+            format!("{i:08}"),
+            AlgebraicValue::ArrayOf(vec![i as u16; 3]),
+            AlgebraicValue::OptionSome(tag)
+        ];
+        bsatn::to_writer(&mut bytes, &row).unwrap();
+    }
+    let ts = Typespace::default();
+    let ty = WithTypespace::new(&ts, &ty);
+
+    let mut group = c.benchmark_group("decode_1m_rows");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(ROWS.into()));
+    group.bench_function("fresh", |b| {
+        b.iter(|| {
+            let mut reader = black_box(&*bytes);
+            for _ in 0..ROWS {
+                black_box(ty.deserialize(bsatn::Deserializer::new(&mut reader)).unwrap());
+            }
+        })
+    });
+    group.bench_function("in_place", |b| {
+        let mut seed = ValueSeed::new(ty, ProductValue::new(&[]));
+        b.iter(|| {
+            let mut reader = black_box(&*bytes);
+            for _ in 0..ROWS {
+                bsatn::from_reader_seeded(&mut reader, &mut seed).unwrap();
+                black_box(seed.value());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode, decode_in_place);
+criterion_main!(benches);
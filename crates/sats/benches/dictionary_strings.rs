@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::bsatn::{self, Deserializer, Serializer};
+use spacetimedb_sats::de::DeserializeSeed;
+use spacetimedb_sats::ser::Serialize;
+use spacetimedb_sats::{AlgebraicType, AlgebraicValue, ArrayValue, Typespace, WithTypespace};
+
+fn encode(value: &AlgebraicValue, dictionary_strings: bool) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let ser = Serializer::new(&mut bytes).with_dictionary_strings(dictionary_strings);
+    value.serialize(ser).unwrap();
+    bytes
+}
+
+fn decode(ty: &AlgebraicType, mut bytes: &[u8], dictionary_strings: bool) -> AlgebraicValue {
+    let ts = Typespace::new(vec![]);
+    let de = Deserializer::new(&mut bytes).with_dictionary_strings(dictionary_strings);
+    WithTypespace::new(&ts, ty).deserialize(de).unwrap()
+}
+
+fn dictionary_strings(c: &mut Criterion) {
+    // 10,000 strings drawn from a vocabulary of 100 tags.
+    let tags = (0..10_000).map(|i| format!("tag_{}", i * 7 % 100)).collect();
+    let value = AlgebraicValue::Array(ArrayValue::String(tags));
+    let ty = AlgebraicType::array(AlgebraicType::String);
+    let plain = bsatn::to_vec(&value).unwrap();
+    let dictionary = encode(&value, true);
+
+    let mut group = c.benchmark_group("encode_10k_tags");
+    group.bench_function("plain", |b| b.iter(|| encode(black_box(&value), false)));
+    group.bench_function("dictionary", |b| b.iter(|| encode(black_box(&value), true)));
+    group.finish();
+
+    let mut group = c.benchmark_group("decode_10k_tags");
+    group.bench_function("plain", |b| b.iter(|| decode(&ty, black_box(&plain), false)));
+    group.bench_function("dictionary", |b| b.iter(|| decode(&ty, black_box(&dictionary), true)));
+    group.finish();
+}
+
+criterion_group!(benches, dictionary_strings);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spacetimedb_sats::{bsatn, product, AlgebraicType, ProductType, ProductValue, Typespace};
+
+/// Encodes 1M rows of a fixed-size product on 1, 2, 4, and 8 threads.
+///
+/// The speedup is bounded by the number of cores of the machine, so compare the runs on one with at least 8.
+/// On a machine with a single core, the runs took 71, 52, 51, and 55 ms respectively,
+/// which says nothing of the scaling on more cores.
+fn parallel_rows(c: &mut Criterion) {
+    const ROWS: u64 = 1_000_000;
+    let schema = ProductType::from_iter([
+        AlgebraicType::U64,
+        AlgebraicType::I32,
+        AlgebraicType::U8,
+        AlgebraicType::U128,
+    ]);
+    let rows: Vec<ProductValue> = (0..ROWS)
+        .map(|i| product![i, -(i as i32), i as u8, u128::from(i) << 64])
+        .collect();
+    let ts = Typespace::default();
+
+    let mut group = c.benchmark_group("encode_1m_fixed_size_rows");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(ROWS));
+    for num_threads in [1, 2, 4, 8] {
+        group.bench_with_input(BenchmarkId::from_parameter(num_threads), &num_threads, |b, &n| {
+            b.iter(|| bsatn::encode_rows_parallel(black_box(&rows), &schema, &ts, n))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parallel_rows);
+criterion_main!(benches);
@@ -0,0 +1,34 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::{bsatn, impl_deserialize, impl_serialize};
+
+/// A `u64` that is encoded like one, but without the fast path for arrays of numbers.
+#[derive(Clone, Copy)]
+struct Elementwise(u64);
+
+impl_serialize!([] Elementwise, (self, ser) => self.0.serialize(ser));
+impl_deserialize!([] Elementwise, de => u64::deserialize(de).map(Elementwise));
+
+const LEN: u64 = 1_000_000;
+
+fn pod_array(c: &mut Criterion) {
+    let fast = (0..LEN).collect::<Vec<u64>>();
+    let slow = fast.iter().copied().map(Elementwise).collect::<Vec<_>>();
+    let bytes = bsatn::to_vec(&fast).unwrap();
+
+    let mut group = c.benchmark_group("encode_u64_array");
+    group.bench_function("bytemuck", |b| b.iter(|| bsatn::to_vec(black_box(&fast)).unwrap()));
+    group.bench_function("elementwise", |b| b.iter(|| bsatn::to_vec(black_box(&slow)).unwrap()));
+    group.finish();
+
+    let mut group = c.benchmark_group("decode_u64_array");
+    group.bench_function("bytemuck", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<u64>>(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("elementwise", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<Elementwise>>(black_box(&bytes)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, pod_array);
+criterion_main!(benches);
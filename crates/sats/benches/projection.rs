@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::bsatn::{self, Deserializer};
+use spacetimedb_sats::de::DeserializeSeed;
+use spacetimedb_sats::{
+    AlgebraicType, AlgebraicValue, ProductType, ProductTypeElement, ProductValue, Typespace, WithTypespace,
+};
+
+/// A row of 40 columns, alternating between fixed size and variable size types.
+fn wide_row() -> (ProductType, ProductValue) {
+    let (elements, values) = (0..40u32)
+        .map(|i| match i % 4 {
+            0 => (AlgebraicType::U64, AlgebraicValue::U64(i.into())),
+            1 => (
+                AlgebraicType::String,
+                AlgebraicValue::String(format!("column number {i}").into()),
+            ),
+            2 => (AlgebraicType::I32, AlgebraicValue::I32(-(i as i32))),
+            _ => (
+                AlgebraicType::array(AlgebraicType::U32),
+                AlgebraicValue::ArrayOf((0..i).collect::<Vec<_>>()),
+            ),
+        })
+        .map(|(ty, val)| (ProductTypeElement::new(ty, None), val))
+        .unzip();
+    (ProductType::new(elements), ProductValue { elements: values })
+}
+
+fn projection(c: &mut Criterion) {
+    let ts = Typespace::default();
+    let (ty, row) = wide_row();
+    let bytes = bsatn::to_vec(&row).unwrap();
+
+    let mut group = c.benchmark_group("decode_wide_row");
+    group.bench_function("full", |b| {
+        b.iter(|| {
+            let row: ProductValue = WithTypespace::new(&ts, &ty)
+                .deserialize(Deserializer::new(&mut black_box(&*bytes)))
+                .unwrap();
+            row
+        })
+    });
+    for wanted in [&[1, 38][..], &[0, 2], &[39]] {
+        group.bench_function(format!("fields={wanted:?}"), |b| {
+            b.iter(|| bsatn::decode_fields(black_box(&bytes), &ty, &ts, wanted).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, projection);
+criterion_main!(benches);
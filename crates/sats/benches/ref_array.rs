@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::{
+    bsatn, product, AlgebraicType, AlgebraicValue, ProductTypeElement, ProductValue, Typespace, WithTypespace,
+};
+
+fn ref_array(c: &mut Criterion) {
+    let row_ty = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+        ProductTypeElement::new_named(AlgebraicType::U64, "value"),
+    ]);
+    let mut ts = Typespace::default();
+    let r = ts.add(row_ty.clone());
+    let rows = (0..1_000_000u32)
+        .map(|i| product![i, u64::from(i) * 3])
+        .collect::<Vec<ProductValue>>();
+    let value = AlgebraicValue::ArrayOf(rows);
+
+    // Serializing through the `Ref` should cost the same as through the type it resolves to,
+    // as the element type is resolved once per array rather than once per element.
+    let mut group = c.benchmark_group("serialize_1m_products");
+    for (name, elem_ty) in [("inline", row_ty), ("ref", AlgebraicType::Ref(r))] {
+        let ty = AlgebraicType::array(elem_ty);
+        let typed = WithTypespace::new(&ts, &ty).with_value(&value);
+        group.bench_function(name, |b| b.iter(|| bsatn::to_vec(black_box(&typed)).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ref_array);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::builtin_value::PackedStrings;
+use spacetimedb_sats::de::DeserializeSeed;
+use spacetimedb_sats::{bsatn, impl_deserialize, AlgebraicType, AlgebraicValue, Typespace, WithTypespace};
+
+/// A `String` that is decoded like one, but element by element in arrays.
+struct Elementwise(#[allow(dead_code)] String);
+
+impl_deserialize!([] Elementwise, de => String::deserialize(de).map(Elementwise));
+
+fn string_array(c: &mut Criterion) {
+    let strings = (0..100_000)
+        .map(|i| format!("message #{i} \u{1f4ac}"))
+        .collect::<Vec<_>>();
+    let bytes = bsatn::to_vec(&strings).unwrap();
+    let ty = AlgebraicType::array(AlgebraicType::String);
+
+    let mut group = c.benchmark_group("decode_100k_strings");
+    group.bench_function("bulk", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<String>>(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("borrowed", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<&str>>(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("elementwise", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<Elementwise>>(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("array_value", |b| {
+        b.iter(|| AlgebraicValue::decode(&ty, &mut black_box(&*bytes)).unwrap())
+    });
+    group.bench_function("packed", |b| {
+        b.iter(|| bsatn::from_slice::<PackedStrings>(black_box(&bytes)).unwrap())
+    });
+    let ts = Typespace::default();
+    group.bench_function("packed_array_value", |b| {
+        b.iter(|| {
+            let mut reader = black_box(&*bytes);
+            let de = bsatn::Deserializer::new(&mut reader).with_packed_strings(true);
+            WithTypespace::new(&ts, &ty).deserialize(de).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, string_array);
+criterion_main!(benches);
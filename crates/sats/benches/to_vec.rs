@@ -0,0 +1,48 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::{bsatn, AlgebraicValue, ProductValue};
+
+/// A row of 64 numeric columns of varying widths.
+fn numeric_row() -> ProductValue {
+    (0..64u32)
+        .map(|i| match i % 4 {
+            0 => AlgebraicValue::U64(i.into()),
+            1 => AlgebraicValue::I32(-(i as i32)),
+            2 => AlgebraicValue::F64(f64::from(i).into()),
+            _ => AlgebraicValue::U8(i as u8),
+        })
+        .collect()
+}
+
+/// A row of 64 columns, every fourth of them a short string rather than a number.
+fn mixed_row() -> ProductValue {
+    numeric_row()
+        .elements
+        .into_iter()
+        .enumerate()
+        .map(|(i, col)| match i % 4 {
+            3 => AlgebraicValue::String(format!("col {i}").into()),
+            _ => col,
+        })
+        .collect()
+}
+
+fn to_vec(c: &mut Criterion) {
+    for (name, row) in [("encode_numeric_row", numeric_row()), ("encode_mixed_row", mixed_row())] {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("growing", |b| {
+            b.iter(|| {
+                let mut bytes = Vec::new();
+                bsatn::to_writer(&mut bytes, black_box(&row)).unwrap();
+                bytes
+            })
+        });
+        group.bench_function("to_vec", |b| b.iter(|| bsatn::to_vec(black_box(&row)).unwrap()));
+        group.bench_function("to_vec_exact", |b| {
+            b.iter(|| bsatn::to_vec_exact(black_box(&row)).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, to_vec);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::bsatn::writer_pool::BsatnBufferPool;
+use spacetimedb_sats::{bsatn, product, ProductValue};
+
+fn writer_pool(c: &mut Criterion) {
+    let rows: Vec<ProductValue> = (0..10_000u32).map(|i| product![i, u64::from(i) * 3, true]).collect();
+    let pool = BsatnBufferPool::new();
+
+    let mut group = c.benchmark_group("encode_10k_small_rows");
+    group.bench_function("to_vec", |b| {
+        b.iter(|| {
+            for row in black_box(&rows) {
+                black_box(bsatn::to_vec(row).unwrap());
+            }
+        })
+    });
+    group.bench_function("pooled", |b| {
+        b.iter(|| {
+            for row in black_box(&rows) {
+                black_box(pool.to_buffer(row).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, writer_pool);
+criterion_main!(benches);
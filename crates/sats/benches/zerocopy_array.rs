@@ -0,0 +1,53 @@
+use std::borrow::Cow;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use spacetimedb_sats::bsatn::zerocopy::{decode_slice, try_cast_to_u32_slice};
+use spacetimedb_sats::{bsatn, impl_deserialize, impl_serialize};
+use zerocopy::IntoBytes;
+
+/// A `u32` that is encoded like one, but without the fast path for arrays of numbers.
+#[derive(Clone, Copy)]
+struct Elementwise(u32);
+
+impl_serialize!([] Elementwise, (self, ser) => self.0.serialize(ser));
+impl_deserialize!([] Elementwise, de => u32::deserialize(de).map(Elementwise));
+
+const LEN: u32 = 1_000_000;
+
+fn zerocopy_array(c: &mut Criterion) {
+    let bytes = bsatn::to_vec(&(0..LEN).collect::<Vec<u32>>()).unwrap();
+    // The encoding in a buffer aligned for `u32`s, so that the elements after the 4-byte length are too.
+    let aligned = bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    let aligned_bytes = aligned.as_bytes();
+    let elem_bytes = &aligned_bytes[4..];
+
+    let mut group = c.benchmark_group("decode_u32_array");
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            let slice = bsatn::Deserializer::new(&mut black_box(aligned_bytes))
+                .deserialize_slice::<u32>()
+                .unwrap();
+            assert!(matches!(slice, Cow::Borrowed(_)));
+            slice.len()
+        })
+    });
+    group.bench_function("copied", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<u32>>(black_box(aligned_bytes)).unwrap())
+    });
+    group.bench_function("elementwise", |b| {
+        b.iter(|| bsatn::from_slice::<Vec<Elementwise>>(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("in_place", |b| {
+        b.iter(|| try_cast_to_u32_slice(black_box(elem_bytes)).unwrap().len())
+    });
+    group.bench_function("decode_slice", |b| {
+        b.iter(|| decode_slice::<u32>(black_box(elem_bytes)).unwrap().len())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, zerocopy_array);
+criterion_main!(benches);
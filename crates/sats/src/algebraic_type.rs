@@ -1,12 +1,16 @@
+pub mod builder;
 pub mod fmt;
 pub mod map_notation;
+pub mod rename;
+pub mod subset;
 
 use crate::algebraic_value::de::{ValueDeserializeError, ValueDeserializer};
 use crate::algebraic_value::ser::ValueSerializer;
 use crate::meta_type::MetaType;
 use crate::{de::Deserialize, ser::Serialize, MapType};
 use crate::{
-    AlgebraicTypeRef, AlgebraicValue, ArrayType, BuiltinType, ProductType, ProductTypeElement, SumType, SumTypeVariant,
+    AlgebraicTypeRef, AlgebraicValue, ArrayType, BuiltinType, NewtypeType, ProductType, ProductTypeElement, SumType,
+    SumTypeVariant, Typespace, WithTypespace,
 };
 use enum_as_inner::EnumAsInner;
 
@@ -113,6 +117,12 @@ pub enum AlgebraicType {
     /// This should not be conflated with reference and pointer types in languages like Rust,
     /// In other words, this is not `&T` or `*const T`.
     Ref(AlgebraicTypeRef),
+    /// A nominal type wrapping another type, e.g., `UserId` wrapping `U64`.
+    ///
+    /// This is the one nominal construct in the type system.
+    /// The name is only a label for humans and tools;
+    /// values of the type are values of the wrapped type and are encoded as such.
+    Newtype(NewtypeType),
 }
 
 #[allow(non_upper_case_globals)]
@@ -178,6 +188,7 @@ impl MetaType for AlgebraicType {
             SumTypeVariant::new_named(ProductType::meta_type(), "product"),
             SumTypeVariant::new_named(BuiltinType::meta_type(), "builtin"),
             SumTypeVariant::new_named(AlgebraicTypeRef::meta_type(), "ref"),
+            SumTypeVariant::new_named(NewtypeType::meta_type(), "newtype"),
         ])
     }
 }
@@ -195,6 +206,13 @@ impl AlgebraicType {
         )
     }
 
+    /// The type of timestamps, as the number of milliseconds since the Unix epoch, i.e., `I64`.
+    ///
+    /// With the `chrono` feature, this is the type `chrono::DateTime<Utc>` is encoded as.
+    pub const fn timestamp_ms() -> Self {
+        Self::I64
+    }
+
     /// Returns a sum type with the given `variants`.
     pub const fn sum(variants: Vec<SumTypeVariant>) -> Self {
         AlgebraicType::Sum(SumType { variants })
@@ -224,6 +242,38 @@ impl AlgebraicType {
         AlgebraicType::Builtin(BuiltinType::Map(value))
     }
 
+    /// Returns a newtype named `name` wrapping the type `inner`.
+    pub fn newtype(name: impl Into<String>, inner: Self) -> Self {
+        AlgebraicType::Newtype(NewtypeType::new(name, inner))
+    }
+
+    /// Returns the type wrapped by any layers of newtypes around it.
+    ///
+    /// For types that aren't newtypes, this is the type itself.
+    pub fn unwrap_newtype(&self) -> &Self {
+        let mut ty = self;
+        while let AlgebraicType::Newtype(nt) = ty {
+            ty = &nt.inner;
+        }
+        ty
+    }
+
+    /// Returns whether every value of this type, with refs resolved in `ts`,
+    /// is encoded in the same number of bytes, as by [`fixed_size_bytes`](Self::fixed_size_bytes).
+    pub fn is_fixed_size(&self, ts: &Typespace) -> bool {
+        self.fixed_size_bytes(ts).is_some()
+    }
+
+    /// Returns the number of bytes every value of this type, with refs resolved in `ts`,
+    /// is encoded in with BSATN, e.g., to store values of the type in columns with random access.
+    ///
+    /// This is `None` for a type that is or contains a `String`, `Array`, or `Map`,
+    /// for a sum type with variants of different sizes, and for a recursive type.
+    /// Sum types of unit variants, i.e., C-style enums, are of a fixed size, the size of their tag.
+    pub fn fixed_size_bytes(&self, ts: &Typespace) -> Option<usize> {
+        crate::bsatn::skip::fixed_size(WithTypespace::new(ts, self))
+    }
+
     /// Returns a sum type of unit variants with names taken from `var_names`.
     pub fn simple_enum<'a>(var_names: impl Iterator<Item = &'a str>) -> Self {
         Self::sum(var_names.into_iter().map(SumTypeVariant::unit).collect())
@@ -247,7 +297,7 @@ mod tests {
         algebraic_type::fmt::fmt_algebraic_type, algebraic_type::map_notation::fmt_algebraic_type as fmt_map,
         algebraic_type_ref::AlgebraicTypeRef, product_type_element::ProductTypeElement, typespace::Typespace,
     };
-    use crate::{ValueWithType, WithTypespace};
+    use crate::{bsatn, AlgebraicValue, ValueWithType, WithTypespace};
 
     #[test]
     fn never() {
@@ -298,7 +348,7 @@ mod tests {
     fn algebraic_type() {
         let algebraic_type = AlgebraicType::meta_type();
         assert_eq!(
-            "(sum: (variants: Array<(name: (some: String | none: ()), algebraic_type: &0)>) | product: (elements: Array<(name: (some: String | none: ()), algebraic_type: &0)>) | builtin: (bool: () | i8: () | u8: () | i16: () | u16: () | i32: () | u32: () | i64: () | u64: () | i128: () | u128: () | f32: () | f64: () | string: () | array: &0 | map: (key_ty: &0, ty: &0)) | ref: U32)",
+            "(sum: (variants: Array<(name: (some: String | none: ()), algebraic_type: &0)>) | product: (elements: Array<(name: (some: String | none: ()), algebraic_type: &0)>) | builtin: (bool: () | i8: () | u8: () | i16: () | u16: () | i32: () | u32: () | i64: () | u64: () | i128: () | u128: () | f32: () | f64: () | string: () | array: &0 | map: (key_ty: &0, ty: &0)) | ref: U32 | newtype: (name: String, inner: &0))",
             fmt_algebraic_type(&algebraic_type).to_string()
         );
     }
@@ -307,7 +357,7 @@ mod tests {
     fn algebraic_type_map() {
         let algebraic_type = AlgebraicType::meta_type();
         assert_eq!(
-            "{ ty_: Sum, sum: { ty_: Product, variants: { ty_: Builtin, 0: Array, 1: { ty_: Product, name: { ty_: Sum, some: { ty_: Builtin, 0: String }, none: { ty_: Product } }, algebraic_type: { ty_: Ref, 0: 0 } } } }, product: { ty_: Product, elements: { ty_: Builtin, 0: Array, 1: { ty_: Product, name: { ty_: Sum, some: { ty_: Builtin, 0: String }, none: { ty_: Product } }, algebraic_type: { ty_: Ref, 0: 0 } } } }, builtin: { ty_: Sum, bool: { ty_: Product }, i8: { ty_: Product }, u8: { ty_: Product }, i16: { ty_: Product }, u16: { ty_: Product }, i32: { ty_: Product }, u32: { ty_: Product }, i64: { ty_: Product }, u64: { ty_: Product }, i128: { ty_: Product }, u128: { ty_: Product }, f32: { ty_: Product }, f64: { ty_: Product }, string: { ty_: Product }, array: { ty_: Ref, 0: 0 }, map: { ty_: Product, key_ty: { ty_: Ref, 0: 0 }, ty: { ty_: Ref, 0: 0 } } }, ref: { ty_: Builtin, 0: U32 } }",
+            "{ ty_: Sum, sum: { ty_: Product, variants: { ty_: Builtin, 0: Array, 1: { ty_: Product, name: { ty_: Sum, some: { ty_: Builtin, 0: String }, none: { ty_: Product } }, algebraic_type: { ty_: Ref, 0: 0 } } } }, product: { ty_: Product, elements: { ty_: Builtin, 0: Array, 1: { ty_: Product, name: { ty_: Sum, some: { ty_: Builtin, 0: String }, none: { ty_: Product } }, algebraic_type: { ty_: Ref, 0: 0 } } } }, builtin: { ty_: Sum, bool: { ty_: Product }, i8: { ty_: Product }, u8: { ty_: Product }, i16: { ty_: Product }, u16: { ty_: Product }, i32: { ty_: Product }, u32: { ty_: Product }, i64: { ty_: Product }, u64: { ty_: Product }, i128: { ty_: Product }, u128: { ty_: Product }, f32: { ty_: Product }, f64: { ty_: Product }, string: { ty_: Product }, array: { ty_: Ref, 0: 0 }, map: { ty_: Product, key_ty: { ty_: Ref, 0: 0 }, ty: { ty_: Ref, 0: 0 } } }, ref: { ty_: Builtin, 0: U32 }, newtype: { ty_: Product, name: { ty_: Builtin, 0: String }, inner: { ty_: Ref, 0: 0 } } }",
             fmt_map(&algebraic_type).to_string()
         );
     }
@@ -372,7 +422,7 @@ mod tests {
         let typespace = Typespace::new(vec![algebraic_type.clone()]);
         let at_ref = AlgebraicType::Ref(AlgebraicTypeRef(0));
         assert_eq!(
-            r#"(sum = (variants = [(name = (some = "sum"), algebraic_type = (product = (elements = [(name = (some = "variants"), algebraic_type = (builtin = (array = (product = (elements = [(name = (some = "name"), algebraic_type = (sum = (variants = [(name = (some = "some"), algebraic_type = (builtin = (string = ()))), (name = (some = "none"), algebraic_type = (product = (elements = [])))]))), (name = (some = "algebraic_type"), algebraic_type = (ref = 0))])))))]))), (name = (some = "product"), algebraic_type = (product = (elements = [(name = (some = "elements"), algebraic_type = (builtin = (array = (product = (elements = [(name = (some = "name"), algebraic_type = (sum = (variants = [(name = (some = "some"), algebraic_type = (builtin = (string = ()))), (name = (some = "none"), algebraic_type = (product = (elements = [])))]))), (name = (some = "algebraic_type"), algebraic_type = (ref = 0))])))))]))), (name = (some = "builtin"), algebraic_type = (sum = (variants = [(name = (some = "bool"), algebraic_type = (product = (elements = []))), (name = (some = "i8"), algebraic_type = (product = (elements = []))), (name = (some = "u8"), algebraic_type = (product = (elements = []))), (name = (some = "i16"), algebraic_type = (product = (elements = []))), (name = (some = "u16"), algebraic_type = (product = (elements = []))), (name = (some = "i32"), algebraic_type = (product = (elements = []))), (name = (some = "u32"), algebraic_type = (product = (elements = []))), (name = (some = "i64"), algebraic_type = (product = (elements = []))), (name = (some = "u64"), algebraic_type = (product = (elements = []))), (name = (some = "i128"), algebraic_type = (product = (elements = []))), (name = (some = "u128"), algebraic_type = (product = (elements = []))), (name = (some = "f32"), algebraic_type = (product = (elements = []))), (name = (some = "f64"), algebraic_type = (product = (elements = []))), (name = (some = "string"), algebraic_type = (product = (elements = []))), (name = (some = "array"), algebraic_type = (ref = 0)), (name = (some = "map"), algebraic_type = (product = (elements = [(name = (some = "key_ty"), algebraic_type = (ref = 0)), (name = (some = "ty"), algebraic_type = (ref = 0))])))]))), (name = (some = "ref"), algebraic_type = (builtin = (u32 = ())))]))"#,
+            r#"(sum = (variants = [(name = (some = "sum"), algebraic_type = (product = (elements = [(name = (some = "variants"), algebraic_type = (builtin = (array = (product = (elements = [(name = (some = "name"), algebraic_type = (sum = (variants = [(name = (some = "some"), algebraic_type = (builtin = (string = ()))), (name = (some = "none"), algebraic_type = (product = (elements = [])))]))), (name = (some = "algebraic_type"), algebraic_type = (ref = 0))])))))]))), (name = (some = "product"), algebraic_type = (product = (elements = [(name = (some = "elements"), algebraic_type = (builtin = (array = (product = (elements = [(name = (some = "name"), algebraic_type = (sum = (variants = [(name = (some = "some"), algebraic_type = (builtin = (string = ()))), (name = (some = "none"), algebraic_type = (product = (elements = [])))]))), (name = (some = "algebraic_type"), algebraic_type = (ref = 0))])))))]))), (name = (some = "builtin"), algebraic_type = (sum = (variants = [(name = (some = "bool"), algebraic_type = (product = (elements = []))), (name = (some = "i8"), algebraic_type = (product = (elements = []))), (name = (some = "u8"), algebraic_type = (product = (elements = []))), (name = (some = "i16"), algebraic_type = (product = (elements = []))), (name = (some = "u16"), algebraic_type = (product = (elements = []))), (name = (some = "i32"), algebraic_type = (product = (elements = []))), (name = (some = "u32"), algebraic_type = (product = (elements = []))), (name = (some = "i64"), algebraic_type = (product = (elements = []))), (name = (some = "u64"), algebraic_type = (product = (elements = []))), (name = (some = "i128"), algebraic_type = (product = (elements = []))), (name = (some = "u128"), algebraic_type = (product = (elements = []))), (name = (some = "f32"), algebraic_type = (product = (elements = []))), (name = (some = "f64"), algebraic_type = (product = (elements = []))), (name = (some = "string"), algebraic_type = (product = (elements = []))), (name = (some = "array"), algebraic_type = (ref = 0)), (name = (some = "map"), algebraic_type = (product = (elements = [(name = (some = "key_ty"), algebraic_type = (ref = 0)), (name = (some = "ty"), algebraic_type = (ref = 0))])))]))), (name = (some = "ref"), algebraic_type = (builtin = (u32 = ()))), (name = (some = "newtype"), algebraic_type = (product = (elements = [(name = (some = "name"), algebraic_type = (builtin = (string = ()))), (name = (some = "inner"), algebraic_type = (ref = 0))])))]))"#,
             in_space(&typespace, &at_ref, &algebraic_type.as_value()).to_satn()
         );
     }
@@ -394,4 +444,92 @@ mod tests {
         let algebraic_type = AlgebraicType::meta_type();
         AlgebraicType::from_value(&algebraic_type.as_value()).expect("No errors.");
     }
+
+    #[test]
+    fn newtype() {
+        let user_id = AlgebraicType::newtype("UserId", AlgebraicType::U64);
+        assert!(user_id.is_newtype());
+        assert!(!AlgebraicType::U64.is_newtype());
+        assert_eq!(user_id.unwrap_newtype(), &AlgebraicType::U64);
+        assert_eq!(AlgebraicType::U64.unwrap_newtype(), &AlgebraicType::U64);
+
+        let nested = AlgebraicType::newtype("AdminId", user_id.clone());
+        assert_eq!(nested.unwrap_newtype(), &AlgebraicType::U64);
+        assert_eq!("AdminId(UserId(U64))", fmt_algebraic_type(&nested).to_string());
+
+        assert_eq!(AlgebraicType::from_value(&user_id.as_value()).unwrap(), user_id);
+    }
+
+    #[test]
+    fn newtype_is_wire_compatible() {
+        let ts = Typespace::default();
+        let user_id = AlgebraicType::newtype("UserId", AlgebraicType::U64);
+        let val = AlgebraicValue::U64(42);
+
+        let plain = bsatn::to_vec(&in_space(&ts, &AlgebraicType::U64, &val)).unwrap();
+        let wrapped = bsatn::to_vec(&in_space(&ts, &user_id, &val)).unwrap();
+        assert_eq!(plain, wrapped);
+
+        let decoded = AlgebraicValue::decode(&user_id, &mut &*wrapped).unwrap();
+        assert_eq!(decoded, val);
+
+        // Also when nested in other types.
+        let row_ty = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(user_id.clone(), "id"),
+            ProductTypeElement::new_named(AlgebraicType::array(user_id.clone()), "friends"),
+        ]);
+        let row = AlgebraicValue::product([val.clone(), AlgebraicValue::ArrayOf(vec![1u64, 2])].into());
+        let bytes = bsatn::to_vec(&in_space(&ts, &row_ty, &row)).unwrap();
+        assert_eq!(bytes, bsatn::to_vec(&row).unwrap());
+        assert_eq!(AlgebraicValue::decode(&row_ty, &mut &*bytes).unwrap(), row);
+    }
+
+    #[test]
+    fn fixed_size() {
+        let ts = Typespace::default();
+        let field = |name: &str, ty| ProductTypeElement::new_named(ty, name);
+        let point = AlgebraicType::product(vec![field("x", AlgebraicType::U32), field("y", AlgebraicType::F64)]);
+        assert!(point.is_fixed_size(&ts));
+        assert_eq!(point.fixed_size_bytes(&ts), Some(12));
+
+        let named = AlgebraicType::product(vec![field("x", AlgebraicType::U32), field("s", AlgebraicType::String)]);
+        assert!(!named.is_fixed_size(&ts));
+        assert_eq!(named.fixed_size_bytes(&ts), None);
+        assert!(!AlgebraicType::bytes().is_fixed_size(&ts));
+        assert!(!AlgebraicType::map(AlgebraicType::U8, AlgebraicType::U8).is_fixed_size(&ts));
+
+        // A C-style enum is its tag, but an option has variants of different sizes.
+        let color = AlgebraicType::simple_enum(["red", "green", "blue"].into_iter());
+        assert_eq!(color.fixed_size_bytes(&ts), Some(1));
+        assert!(!AlgebraicType::option(AlgebraicType::U32).is_fixed_size(&ts));
+        assert_eq!(AlgebraicType::UNIT_TYPE.fixed_size_bytes(&ts), Some(0));
+        assert_eq!(
+            AlgebraicType::newtype("Id", AlgebraicType::U128).fixed_size_bytes(&ts),
+            Some(16)
+        );
+    }
+
+    #[test]
+    fn fixed_size_through_refs() {
+        let mut ts = Typespace::default();
+        let point = ts.add(AlgebraicType::product(vec![
+            ProductTypeElement::new(AlgebraicType::I16, None),
+            ProductTypeElement::new(AlgebraicType::I16, None),
+        ]));
+        let line = AlgebraicType::product(vec![
+            ProductTypeElement::new(AlgebraicType::Ref(point), None),
+            ProductTypeElement::new(AlgebraicType::Ref(point), None),
+        ]);
+        assert_eq!(line.fixed_size_bytes(&ts), Some(8));
+
+        // A recursive type, directly or through another type, is never of a fixed size.
+        let node = ts.add(AlgebraicType::UNIT_TYPE);
+        let edge = ts.add(AlgebraicType::product(vec![ProductTypeElement::new(
+            AlgebraicType::Ref(node),
+            None,
+        )]));
+        ts.types[node.idx()] = AlgebraicType::product(vec![ProductTypeElement::new(AlgebraicType::Ref(edge), None)]);
+        assert!(!AlgebraicType::Ref(node).is_fixed_size(&ts));
+        assert!(!AlgebraicType::Ref(edge).is_fixed_size(&ts));
+    }
 }
@@ -0,0 +1,237 @@
+//! Builders for product and sum types, to construct them field by field and variant by variant.
+
+use std::collections::HashSet;
+
+use super::AlgebraicType;
+use crate::{AlgebraicTypeRef, ProductType, ProductTypeElement, SumType, SumTypeVariant};
+
+/// An error that occurs when building a type that has two fields or variants with the same name.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Duplicate name {name:?} at position {index}")]
+pub struct DuplicateName {
+    /// The name that occurs more than once.
+    pub name: String,
+    /// The position of the field or variant that repeats the name.
+    pub index: usize,
+}
+
+/// Checks that no two of `names` are the same name.
+fn check_unique<'a>(names: impl Iterator<Item = Option<&'a str>>) -> Result<(), DuplicateName> {
+    let mut seen = HashSet::new();
+    for (index, name) in names.enumerate() {
+        if let Some(name) = name.filter(|name| !seen.insert(*name)) {
+            let name = name.to_owned();
+            return Err(DuplicateName { name, index });
+        }
+    }
+    Ok(())
+}
+
+/// A builder for a [`ProductType`], adding its fields one by one, e.g.:
+/// ```
+/// # use spacetimedb_sats::{algebraic_type::builder::ProductTypeBuilder, AlgebraicType};
+/// let point = ProductTypeBuilder::new()
+///     .field("x", AlgebraicType::F32)
+///     .field("y", AlgebraicType::F32)
+///     .build();
+/// assert_eq!(point.elements.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProductTypeBuilder {
+    /// The fields added so far.
+    elements: Vec<ProductTypeElement>,
+}
+
+impl ProductTypeBuilder {
+    /// Returns a builder for a product type without any fields yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field `name` of type `ty`.
+    pub fn field(&mut self, name: &str, ty: AlgebraicType) -> &mut Self {
+        self.elements.push(ProductTypeElement::new_named(ty, name));
+        self
+    }
+
+    /// Adds an unnamed field of type `ty`.
+    pub fn anon_field(&mut self, ty: AlgebraicType) -> &mut Self {
+        self.elements.push(ProductTypeElement::new(ty, None));
+        self
+    }
+
+    /// Adds a field `name` of the type `r` refers to.
+    pub fn field_ref(&mut self, name: &str, r: AlgebraicTypeRef) -> &mut Self {
+        self.field(name, AlgebraicType::Ref(r))
+    }
+
+    /// Returns the product type of the fields added, in order, leaving the builder without any.
+    ///
+    /// Panics if two fields have the same name. See [`ProductTypeBuilder::try_build`] to handle that.
+    pub fn build(&mut self) -> ProductType {
+        self.try_build().unwrap_or_else(|e| panic!("invalid product type: {e}"))
+    }
+
+    /// Returns the product type of the fields added, in order, leaving the builder without any,
+    /// or an error, leaving the builder as it was, if two fields have the same name.
+    pub fn try_build(&mut self) -> Result<ProductType, DuplicateName> {
+        check_unique(self.elements.iter().map(ProductTypeElement::name))?;
+        Ok(ProductType::new(std::mem::take(&mut self.elements)))
+    }
+}
+
+/// A builder for a [`SumType`], adding its variants one by one, e.g.:
+/// ```
+/// # use spacetimedb_sats::{algebraic_type::builder::SumTypeBuilder, AlgebraicType};
+/// let shape = SumTypeBuilder::new()
+///     .variant("circle", AlgebraicType::F32)
+///     .unit_variant("empty")
+///     .build();
+/// assert_eq!(shape.variants.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SumTypeBuilder {
+    /// The variants added so far.
+    variants: Vec<SumTypeVariant>,
+}
+
+impl SumTypeBuilder {
+    /// Returns a builder for a sum type without any variants yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a variant `name` with a payload of type `ty`.
+    pub fn variant(&mut self, name: &str, ty: AlgebraicType) -> &mut Self {
+        self.variants.push(SumTypeVariant::new_named(ty, name));
+        self
+    }
+
+    /// Adds an unnamed variant with a payload of type `ty`.
+    pub fn anon_variant(&mut self, ty: AlgebraicType) -> &mut Self {
+        self.variants.push(SumTypeVariant::new(ty, None));
+        self
+    }
+
+    /// Adds a variant `name` with a payload of the type `r` refers to.
+    pub fn variant_ref(&mut self, name: &str, r: AlgebraicTypeRef) -> &mut Self {
+        self.variant(name, AlgebraicType::Ref(r))
+    }
+
+    /// Adds a variant `name` without a payload.
+    pub fn unit_variant(&mut self, name: &str) -> &mut Self {
+        self.variants.push(SumTypeVariant::unit(name));
+        self
+    }
+
+    /// Returns the sum type of the variants added, in order, leaving the builder without any.
+    ///
+    /// Panics if two variants have the same name. See [`SumTypeBuilder::try_build`] to handle that.
+    pub fn build(&mut self) -> SumType {
+        self.try_build().unwrap_or_else(|e| panic!("invalid sum type: {e}"))
+    }
+
+    /// Returns the sum type of the variants added, in order, leaving the builder without any,
+    /// or an error, leaving the builder as it was, if two variants have the same name.
+    pub fn try_build(&mut self) -> Result<SumType, DuplicateName> {
+        check_unique(self.variants.iter().map(SumTypeVariant::name))?;
+        Ok(SumType::new(std::mem::take(&mut self.variants)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn product_builder_matches_elements() {
+        let built = ProductTypeBuilder::new()
+            .field("x", AlgebraicType::U32)
+            .field("y", AlgebraicType::F64)
+            .build();
+        let manual = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "x"),
+            ProductTypeElement::new_named(AlgebraicType::F64, "y"),
+        ]);
+        assert_eq!(built, manual);
+
+        let r = AlgebraicTypeRef(3);
+        let mut builder = ProductTypeBuilder::new();
+        builder.anon_field(AlgebraicType::Bool).field_ref("next", r);
+        let built = builder.build();
+        let manual = ProductType::new(vec![
+            ProductTypeElement::new(AlgebraicType::Bool, None),
+            ProductTypeElement::new_named(AlgebraicType::Ref(r), "next"),
+        ]);
+        assert_eq!(built, manual);
+        // Building takes the fields, so the builder starts afresh.
+        assert_eq!(builder.build(), ProductType::new(Vec::new()));
+    }
+
+    #[test]
+    fn sum_builder_matches_variants() {
+        let built = SumTypeBuilder::new()
+            .variant("some", AlgebraicType::String)
+            .unit_variant("none")
+            .build();
+        assert_eq!(
+            AlgebraicType::Sum(built.clone()),
+            AlgebraicType::option(AlgebraicType::String)
+        );
+        assert_eq!(built.as_option(), Some(&AlgebraicType::String));
+
+        let r = AlgebraicTypeRef(0);
+        let built = SumTypeBuilder::new()
+            .anon_variant(AlgebraicType::U8)
+            .variant_ref("tree", r)
+            .build();
+        let manual = SumType::new(vec![
+            SumTypeVariant::new(AlgebraicType::U8, None),
+            SumTypeVariant::new_named(AlgebraicType::Ref(r), "tree"),
+        ]);
+        assert_eq!(built, manual);
+    }
+
+    #[test]
+    fn duplicate_names_are_errors() {
+        let mut builder = ProductTypeBuilder::new();
+        builder
+            .field("x", AlgebraicType::U32)
+            .anon_field(AlgebraicType::U8)
+            .anon_field(AlgebraicType::U8)
+            .field("x", AlgebraicType::F64);
+        let err = builder.try_build().unwrap_err();
+        assert_eq!(
+            err,
+            DuplicateName {
+                name: "x".into(),
+                index: 3
+            }
+        );
+        // The builder keeps its fields, so they can still be inspected.
+        assert_eq!(builder.try_build().unwrap_err(), err);
+
+        let err = SumTypeBuilder::new()
+            .unit_variant("a")
+            .unit_variant("b")
+            .variant("a", AlgebraicType::U8)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            DuplicateName {
+                name: "a".into(),
+                index: 2
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic = "invalid product type: Duplicate name \"x\" at position 1"]
+    fn build_panics_on_duplicate_names() {
+        ProductTypeBuilder::new()
+            .field("x", AlgebraicType::U32)
+            .field("x", AlgebraicType::U32)
+            .build();
+    }
+}
@@ -1,5 +1,6 @@
 use super::{AlgebraicType, BuiltinType, ProductType, SumType};
 use crate::de::fmt_fn;
+use crate::typespace::names::NameRegistry;
 use std::fmt::Display;
 
 /// Wraps the algebraic `ty` into a `Display`able.
@@ -12,16 +13,31 @@ use std::fmt::Display;
 /// represents an algebraic type and format it that way. It's just more
 /// convenient to format it from the Rust type.
 pub fn fmt_algebraic_type(ty: &AlgebraicType) -> impl '_ + Display {
+    fmt_type(ty, None)
+}
+
+/// Wraps the algebraic `ty` into a `Display`able, as [`fmt_algebraic_type`] does,
+/// but with the `Ref`s to named types written as their names in `names`, e.g., `Player` rather than `&2`.
+pub fn fmt_algebraic_type_named<'a>(ty: &'a AlgebraicType, names: &'a NameRegistry) -> impl 'a + Display {
+    fmt_type(ty, Some(names))
+}
+
+/// Wraps the algebraic `ty` into a `Display`able, with refs named per `names`, if any.
+fn fmt_type<'a>(ty: &'a AlgebraicType, names: Option<&'a NameRegistry>) -> impl 'a + Display {
     fmt_fn(move |f| match ty {
-        AlgebraicType::Sum(ty) => write!(f, "{}", fmt_sum_type(ty)),
-        AlgebraicType::Product(ty) => write!(f, "{}", fmt_product_type(ty)),
-        AlgebraicType::Builtin(p) => write!(f, "{}", fmt_builtin_type(p)),
-        AlgebraicType::Ref(r) => write!(f, "{}", r),
+        AlgebraicType::Sum(ty) => write!(f, "{}", fmt_sum_type(ty, names)),
+        AlgebraicType::Product(ty) => write!(f, "{}", fmt_product_type(ty, names)),
+        AlgebraicType::Builtin(p) => write!(f, "{}", fmt_builtin_type(p, names)),
+        AlgebraicType::Ref(r) => match names.and_then(|names| names.name_of(*r)) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{}", r),
+        },
+        AlgebraicType::Newtype(nt) => write!(f, "{}({})", nt.name, fmt_type(&nt.inner, names)),
     })
 }
 
 /// Wraps the builtin `ty` into a `Display`able.
-fn fmt_product_type(ty: &ProductType) -> impl '_ + Display {
+fn fmt_product_type<'a>(ty: &'a ProductType, names: Option<&'a NameRegistry>) -> impl 'a + Display {
     fmt_fn(move |f| {
         write!(f, "(")?;
         for (i, e) in ty.elements.iter().enumerate() {
@@ -31,7 +47,7 @@ fn fmt_product_type(ty: &ProductType) -> impl '_ + Display {
                 write!(f, "{}", i)?;
             }
             write!(f, ": ")?;
-            write!(f, "{}", fmt_algebraic_type(&e.algebraic_type))?;
+            write!(f, "{}", fmt_type(&e.algebraic_type, names))?;
             if i < ty.elements.len() - 1 {
                 write!(f, ", ")?;
             }
@@ -41,7 +57,7 @@ fn fmt_product_type(ty: &ProductType) -> impl '_ + Display {
 }
 
 /// Wraps the builtin `ty` into a `Display`able.
-fn fmt_sum_type(ty: &SumType) -> impl '_ + Display {
+fn fmt_sum_type<'a>(ty: &'a SumType, names: Option<&'a NameRegistry>) -> impl 'a + Display {
     fmt_fn(move |f| {
         if ty.variants.is_empty() {
             return write!(f, "(|)");
@@ -52,7 +68,7 @@ fn fmt_sum_type(ty: &SumType) -> impl '_ + Display {
                 write!(f, "{}", name)?;
                 write!(f, ": ")?;
             }
-            write!(f, "{}", fmt_algebraic_type(&e.algebraic_type))?;
+            write!(f, "{}", fmt_type(&e.algebraic_type, names))?;
             if i < ty.variants.len() - 1 {
                 write!(f, " | ")?;
             }
@@ -62,8 +78,8 @@ fn fmt_sum_type(ty: &SumType) -> impl '_ + Display {
 }
 
 /// Wraps the builtin `ty` into a `Display`able.
-fn fmt_builtin_type(ty: &BuiltinType) -> impl '_ + Display {
-    use fmt_algebraic_type as fmt;
+fn fmt_builtin_type<'a>(ty: &'a BuiltinType, names: Option<&'a NameRegistry>) -> impl 'a + Display {
+    let fmt = move |ty: &'a AlgebraicType| fmt_type(ty, names);
 
     fmt_fn(move |f| match ty {
         BuiltinType::Bool => write!(f, "Bool"),
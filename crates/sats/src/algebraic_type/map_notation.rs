@@ -54,5 +54,6 @@ pub fn fmt_algebraic_type(ty: &AlgebraicType) -> impl '_ + fmt::Display {
             write!(f, " }}")
         }
         AlgebraicType::Ref(r) => write!(f, "{{ ty_: Ref, 0: {} }}", r.0),
+        AlgebraicType::Newtype(nt) => write!(f, "{{ ty_: Newtype, name: {}, inner: {} }}", nt.name, fmt(&nt.inner)),
     })
 }
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use super::AlgebraicType;
+use crate::{
+    AlgebraicTypeRef, ArrayType, BuiltinType, MapType, NewtypeType, ProductType, ProductTypeElement, SumType,
+    SumTypeVariant, Typespace,
+};
+
+/// Returns `ty` with the names of all product fields and sum variants within it renamed per `map`.
+///
+/// Names not in `map`, and unnamed fields and variants, are left unchanged.
+///
+/// `AlgebraicType::Ref`s are resolved in `ts` and the renaming applied to a copy of the type referred to,
+/// so the result has them inlined, even if the type is also referred to from elsewhere.
+/// `ts` itself is not changed.
+/// The exceptions are a `Ref` back to a type that is being renamed,
+/// as inlining a recursive type would never end,
+/// and a `Ref` not in `ts`, both of which are left as they are.
+pub fn rename_fields(ty: &AlgebraicType, ts: &Typespace, map: &HashMap<String, String>) -> AlgebraicType {
+    Renamer {
+        ts,
+        map,
+        stack: Vec::new(),
+    }
+    .rename(ty)
+}
+
+/// The state of [`rename_fields`].
+struct Renamer<'a> {
+    /// The typespace to resolve `Ref`s in.
+    ts: &'a Typespace,
+    /// The renames to apply.
+    map: &'a HashMap<String, String>,
+    /// The `Ref`s currently being inlined, to detect recursive types.
+    stack: Vec<AlgebraicTypeRef>,
+}
+
+impl Renamer<'_> {
+    fn rename(&mut self, ty: &AlgebraicType) -> AlgebraicType {
+        match ty {
+            AlgebraicType::Sum(sum) => AlgebraicType::Sum(SumType::new(
+                (sum.variants.iter())
+                    .map(|var| SumTypeVariant::new(self.rename(&var.algebraic_type), self.name(&var.name)))
+                    .collect(),
+            )),
+            AlgebraicType::Product(prod) => AlgebraicType::Product(ProductType::new(
+                (prod.elements.iter())
+                    .map(|elem| ProductTypeElement::new(self.rename(&elem.algebraic_type), self.name(&elem.name)))
+                    .collect(),
+            )),
+            AlgebraicType::Builtin(BuiltinType::Array(ArrayType { elem_ty })) => {
+                AlgebraicType::array(self.rename(elem_ty))
+            }
+            AlgebraicType::Builtin(BuiltinType::Map(MapType { key_ty, ty })) => {
+                AlgebraicType::map(self.rename(key_ty), self.rename(ty))
+            }
+            AlgebraicType::Builtin(_) => ty.clone(),
+            &AlgebraicType::Ref(r) => match self.ts.get(r) {
+                Some(target) if !self.stack.contains(&r) => {
+                    self.stack.push(r);
+                    let renamed = self.rename(target);
+                    self.stack.pop();
+                    renamed
+                }
+                _ => ty.clone(),
+            },
+            AlgebraicType::Newtype(nt) => {
+                AlgebraicType::Newtype(NewtypeType::new(nt.name.clone(), self.rename(&nt.inner)))
+            }
+        }
+    }
+
+    /// Returns `name` renamed per the map.
+    fn name(&self, name: &Option<String>) -> Option<String> {
+        name.as_ref().map(|name| self.map.get(name).unwrap_or(name).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renames() -> HashMap<String, String> {
+        [
+            ("userId", "user_id"),
+            ("createdAt", "created_at"),
+            ("notFound", "not_found"),
+        ]
+        .into_iter()
+        .map(|(from, to)| (from.to_owned(), to.to_owned()))
+        .collect()
+    }
+
+    /// Returns `ty` with all names of fields and variants removed.
+    fn strip_names(ty: &AlgebraicType) -> AlgebraicType {
+        match ty {
+            AlgebraicType::Sum(sum) => AlgebraicType::sum(
+                (sum.variants.iter())
+                    .map(|v| SumTypeVariant::new(strip_names(&v.algebraic_type), None))
+                    .collect(),
+            ),
+            AlgebraicType::Product(prod) => AlgebraicType::product(
+                (prod.elements.iter())
+                    .map(|e| ProductTypeElement::new(strip_names(&e.algebraic_type), None))
+                    .collect(),
+            ),
+            AlgebraicType::Builtin(BuiltinType::Array(a)) => AlgebraicType::array(strip_names(&a.elem_ty)),
+            AlgebraicType::Builtin(BuiltinType::Map(m)) => {
+                AlgebraicType::map(strip_names(&m.key_ty), strip_names(&m.ty))
+            }
+            _ => ty.clone(),
+        }
+    }
+
+    /// Returns the names of all fields and variants in `ty`, depth first.
+    fn names(ty: &AlgebraicType) -> Vec<Option<String>> {
+        let mut out = Vec::new();
+        let mut visit = |name: &Option<String>, ty| {
+            out.push(name.clone());
+            out.extend(names(ty));
+        };
+        match ty {
+            AlgebraicType::Sum(sum) => sum.variants.iter().for_each(|v| visit(&v.name, &v.algebraic_type)),
+            AlgebraicType::Product(prod) => prod.elements.iter().for_each(|e| visit(&e.name, &e.algebraic_type)),
+            AlgebraicType::Builtin(BuiltinType::Array(a)) => out.extend(names(&a.elem_ty)),
+            AlgebraicType::Builtin(BuiltinType::Map(m)) => {
+                out.extend(names(&m.key_ty));
+                out.extend(names(&m.ty));
+            }
+            _ => {}
+        }
+        out
+    }
+
+    fn named(pairs: &[(&str, AlgebraicType)]) -> AlgebraicType {
+        AlgebraicType::product(
+            (pairs.iter())
+                .map(|(name, ty)| ProductTypeElement::new_named(ty.clone(), *name))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn renames_every_occurrence() {
+        let ts = Typespace::new(vec![]);
+        let ty = named(&[
+            ("userId", AlgebraicType::U64),
+            ("name", AlgebraicType::String),
+            (
+                "events",
+                AlgebraicType::array(named(&[
+                    ("userId", AlgebraicType::U64),
+                    ("createdAt", AlgebraicType::U64),
+                ])),
+            ),
+            (
+                "status",
+                AlgebraicType::sum(vec![
+                    SumTypeVariant::new_named(AlgebraicType::U8, "createdAt"),
+                    SumTypeVariant::unit("deleted"),
+                    AlgebraicType::Bool.into(),
+                ]),
+            ),
+        ]);
+        let renamed = rename_fields(&ty, &ts, &renames());
+
+        let names = names(&renamed);
+        let expected = [
+            Some("user_id"),
+            Some("name"),
+            Some("events"),
+            Some("user_id"),
+            Some("created_at"),
+            Some("status"),
+            Some("created_at"),
+            Some("deleted"),
+            None,
+        ];
+        assert_eq!(names, expected.map(|n| n.map(str::to_owned)));
+        assert_eq!(strip_names(&renamed), strip_names(&ty));
+
+        // Nothing to rename leaves the type as it was.
+        assert_eq!(rename_fields(&ty, &ts, &HashMap::new()), ty);
+    }
+
+    #[test]
+    fn inlines_refs() {
+        let mut ts = Typespace::new(vec![]);
+        let shared = named(&[("userId", AlgebraicType::U32), ("createdAt", AlgebraicType::I64)]);
+        let r = ts.add(shared.clone());
+        let ty = named(&[
+            ("owner", AlgebraicType::Ref(r)),
+            ("editor", AlgebraicType::option(AlgebraicType::Ref(r))),
+        ]);
+        let renamed = rename_fields(&ty, &ts, &renames());
+
+        let inlined = named(&[("user_id", AlgebraicType::U32), ("created_at", AlgebraicType::I64)]);
+        let expected = named(&[("owner", inlined.clone()), ("editor", AlgebraicType::option(inlined))]);
+        assert_eq!(renamed, expected);
+        // The shared type in the typespace is untouched.
+        assert_eq!(ts[r], shared);
+    }
+
+    #[test]
+    fn keeps_recursive_refs() {
+        // `&0 = { userId: U32, next: Option<&0> }`.
+        let mut ts = Typespace::new(vec![]);
+        let r = ts.add(AlgebraicType::UNIT_TYPE);
+        ts[r] = named(&[
+            ("userId", AlgebraicType::U32),
+            ("next", AlgebraicType::option(AlgebraicType::Ref(r))),
+        ]);
+        let renamed = rename_fields(&AlgebraicType::Ref(r), &ts, &renames());
+        let expected = named(&[
+            ("user_id", AlgebraicType::U32),
+            ("next", AlgebraicType::option(AlgebraicType::Ref(r))),
+        ]);
+        assert_eq!(renamed, expected);
+    }
+}
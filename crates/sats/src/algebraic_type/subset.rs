@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::schema::normalize::map_refs;
+use crate::{AlgebraicType, AlgebraicTypeRef, Typespace};
+
+/// Returns the types of `full` reachable from `root`, i.e., `root` and the types its `Ref`s refer to, however deep,
+/// in a fresh typespace, along with the mapping from each of their refs in `full` to their refs in the result.
+///
+/// The types are numbered in the order they're first reached by a depth-first search from `root`,
+/// so `root` is always `&0` in the result,
+/// and all `Ref`s in the result are rewritten to refer to types in the result.
+/// The types kept keep their names.
+///
+/// This is, e.g., for exporting the schema of a single table.
+///
+/// # Panics
+///
+/// Panics if `root`, or any `Ref` reachable from it, is not in `full`.
+pub fn reachable_typespace(
+    root: AlgebraicTypeRef,
+    full: &Typespace,
+) -> (Typespace, HashMap<AlgebraicTypeRef, AlgebraicTypeRef>) {
+    let mut mapping = HashMap::new();
+    // The types reached but not yet copied, each with its ref in `full`.
+    let mut stack = vec![root];
+    mapping.insert(root, AlgebraicTypeRef(0));
+    let mut types = vec![None];
+    while let Some(old) = stack.pop() {
+        let ty = full
+            .get(old)
+            .unwrap_or_else(|| panic!("{old:?} is not in the typespace"));
+        let copy = map_refs(ty, &mut |r| {
+            *mapping.entry(r).or_insert_with(|| {
+                stack.push(r);
+                types.push(None);
+                AlgebraicTypeRef((types.len() - 1) as u32)
+            })
+        });
+        types[mapping[&old].idx()] = Some(copy);
+    }
+    let types = types.into_iter().map(Option::unwrap).collect::<Vec<AlgebraicType>>();
+    let names = (full.names())
+        .remap(|r| mapping.get(&r).copied())
+        .expect("types are never merged");
+    (Typespace::new(types).with_names(names), mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::DeserializeSeed;
+    use crate::{bsatn, product, AlgebraicValue, ProductTypeElement, ValueWithType, WithTypespace};
+
+    fn r(i: u32) -> AlgebraicType {
+        AlgebraicType::Ref(AlgebraicTypeRef(i))
+    }
+
+    fn field(name: &str, ty: AlgebraicType) -> ProductTypeElement {
+        ProductTypeElement::new_named(ty, name)
+    }
+
+    /// A typespace of a table `&3` referring to `&1` and, through it, to the recursive `&4`,
+    /// with the unrelated `&0` and `&2`.
+    fn full() -> Typespace {
+        Typespace::new(vec![
+            AlgebraicType::product(vec![field("unrelated", AlgebraicType::U8)]),
+            AlgebraicType::product(vec![field("x", AlgebraicType::I32), field("chain", r(4))]),
+            AlgebraicType::product(vec![field("also_unrelated", r(0))]),
+            AlgebraicType::product(vec![
+                field("id", AlgebraicType::U64),
+                field("pos", r(1)),
+                field("tags", AlgebraicType::array(r(1))),
+            ]),
+            AlgebraicType::product(vec![field("next", AlgebraicType::option(r(4)))]),
+        ])
+    }
+
+    /// Returns every ref within `ty`.
+    fn refs(ty: &AlgebraicType) -> Vec<AlgebraicTypeRef> {
+        let mut refs = Vec::new();
+        map_refs(ty, &mut |r| {
+            refs.push(r);
+            r
+        });
+        refs
+    }
+
+    #[test]
+    fn only_reachable_types() {
+        let full = full();
+        let (subset, mapping) = reachable_typespace(AlgebraicTypeRef(3), &full);
+
+        assert_eq!(subset.types.len(), 3);
+        assert_eq!(mapping[&AlgebraicTypeRef(3)], AlgebraicTypeRef(0));
+        let mut old = mapping.keys().map(|r| r.0).collect::<Vec<_>>();
+        old.sort();
+        assert_eq!(old, [1, 3, 4]);
+        for (old, new) in &mapping {
+            assert_eq!(
+                subset.types[new.idx()],
+                map_refs(&full.types[old.idx()], &mut |r| mapping[&r])
+            );
+        }
+        assert!(subset.types.iter().flat_map(refs).all(|r| r.idx() < subset.types.len()));
+
+        // The names of the types kept move with them, and those of the others are dropped.
+        let mut named = full.clone();
+        named.register_name("Table", AlgebraicTypeRef(3)).unwrap();
+        named.register_name("Chain", AlgebraicTypeRef(4)).unwrap();
+        named.register_name("Unrelated", AlgebraicTypeRef(0)).unwrap();
+        let (subset, mapping) = reachable_typespace(AlgebraicTypeRef(3), &named);
+        assert_eq!(subset.name_of(AlgebraicTypeRef(0)), Some("Table"));
+        assert_eq!(subset.resolve_name("Chain"), Some(mapping[&AlgebraicTypeRef(4)]));
+        assert_eq!(subset.resolve_name("Unrelated"), None);
+        assert_eq!(subset.names().len(), 2);
+
+        // A type without refs is on its own.
+        let (subset, mapping) = reachable_typespace(AlgebraicTypeRef(0), &full);
+        assert_eq!(subset.types, full.types[..1]);
+        assert_eq!(mapping, [(AlgebraicTypeRef(0), AlgebraicTypeRef(0))].into());
+    }
+
+    #[test]
+    fn same_bytes_as_full_typespace() {
+        let full = full();
+        let (subset, mapping) = reachable_typespace(AlgebraicTypeRef(3), &full);
+        let end = AlgebraicValue::OptionNone();
+        let chain = product![AlgebraicValue::OptionSome(product![end].into())];
+        let pos = product![-5i32, chain];
+        let row: AlgebraicValue = product![9u64, pos.clone(), AlgebraicValue::ArrayOf(vec![pos.clone(), pos])].into();
+
+        let encode = |ts: &Typespace, r: AlgebraicTypeRef| {
+            let ty = AlgebraicType::Ref(r);
+            bsatn::to_vec(&ValueWithType::new(WithTypespace::new(ts, &ty), &row)).unwrap()
+        };
+        let bytes = encode(&full, AlgebraicTypeRef(3));
+        assert_eq!(encode(&subset, mapping[&AlgebraicTypeRef(3)]), bytes);
+
+        let ty = AlgebraicType::Ref(AlgebraicTypeRef(0));
+        let decoded = WithTypespace::new(&subset, &ty)
+            .deserialize(bsatn::Deserializer::new(&mut &*bytes))
+            .unwrap();
+        assert_eq!(decoded, row);
+    }
+
+    #[test]
+    #[should_panic = "AlgebraicTypeRef(7) is not in the typespace"]
+    fn dangling_ref() {
+        let full = Typespace::new(vec![AlgebraicType::product(vec![field("dangling", r(7))])]);
+        reachable_typespace(AlgebraicTypeRef(0), &full);
+    }
+}
@@ -1,11 +1,20 @@
+pub mod accessor;
+#[cfg(feature = "columnar")]
+pub mod bytes_codec;
+pub mod cmp;
+pub mod coerce;
 pub mod de;
+pub mod interning;
+pub mod pattern_match;
 pub mod ser;
+pub mod sql;
 use std::collections::BTreeMap;
 use std::ops::{Bound, RangeBounds};
 
 use crate::builtin_value::{F32, F64};
-use crate::{AlgebraicType, ArrayValue, BuiltinType, BuiltinValue, ProductValue, SumValue};
-use enum_as_inner::EnumAsInner;
+use crate::{
+    AlgebraicType, ArrayValue, BuiltinType, MapValue, ProductType, ProductValue, SumValue, ValueWithType, WithTypespace,
+};
 
 /// A value in SATS typed at some [`AlgebraicType`].
 ///
@@ -16,7 +25,7 @@ use enum_as_inner::EnumAsInner;
 /// These are only values and not expressions.
 /// That is, they are canonical and cannot be simplified further by some evaluation.
 /// So forms like `42 + 24` are not represented in an `AlgebraicValue`.
-#[derive(EnumAsInner, Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum AlgebraicValue {
     /// A structural sum value.
     ///
@@ -34,8 +43,114 @@ pub enum AlgebraicValue {
     /// and where `T_i` denotes the type the field stores,
     /// a product value stores a value `v_i` of type `T_i` for each field `N_i`.
     Product(ProductValue),
-    /// A builtin value that has a builtin type.
-    Builtin(BuiltinValue),
+    /// A [`bool`] value of type [`BuiltinType::Bool`].
+    Bool(bool),
+    /// An [`i8`] value of type [`BuiltinType::I8`].
+    I8(i8),
+    /// A [`u8`] value of type [`BuiltinType::U8`].
+    U8(u8),
+    /// An [`i16`] value of type [`BuiltinType::I16`].
+    I16(i16),
+    /// A [`u16`] value of type [`BuiltinType::U16`].
+    U16(u16),
+    /// An [`i32`] value of type [`BuiltinType::I32`].
+    I32(i32),
+    /// A [`u32`] value of type [`BuiltinType::U32`].
+    U32(u32),
+    /// An [`i64`] value of type [`BuiltinType::I64`].
+    I64(i64),
+    /// A [`u64`] value of type [`BuiltinType::U64`].
+    U64(u64),
+    /// An [`i128`] value of type [`BuiltinType::I128`].
+    I128(i128),
+    /// A [`u128`] value of type [`BuiltinType::U128`].
+    U128(u128),
+    /// A totally ordered [`F32`] value of type [`BuiltinType::F32`].
+    ///
+    /// All floating point values defined in IEEE-754 are supported.
+    /// However, unlike the primitive [`f32`], a [total order] is established.
+    ///
+    /// [total order]: https://docs.rs/decorum/0.3.1/decorum/#total-ordering
+    F32(F32),
+    /// A totally ordered [`F64`] value of type [`BuiltinType::F64`].
+    ///
+    /// All floating point values defined in IEEE-754 are supported.
+    /// However, unlike the primitive [`f64`], a [total order] is established.
+    ///
+    /// [total order]: https://docs.rs/decorum/0.3.1/decorum/#total-ordering
+    F64(F64),
+    /// A UTF-8 string value of type [`BuiltinType::String`].
+    ///
+    /// Stored as a `Box<str>` rather than a `String`,
+    /// as string values are never grown in place.
+    /// This saves the capacity word and guarantees that no excess capacity is held onto.
+    /// A `Box<str>` can't be built in a `const` context, though, so neither can a string value.
+    String(Box<str>),
+    /// A homogeneous array of `AlgebraicValue`s.
+    /// The array has the type [`BuiltinType::Array(elem_ty)`].
+    ///
+    /// The contained values are stored packed in a representation appropriate for their type.
+    /// See [`ArrayValue`] for details on the representation.
+    Array(ArrayValue),
+    /// An ordered map value of `key: AlgebraicValue`s mapped to `value: AlgebraicValue`s.
+    /// Each `key` must be of the same [`AlgebraicType`] as all the others
+    /// and the same applies to each `value`.
+    /// A map as a whole has the type [`BuiltinType::Map(key_ty, val_ty)`].
+    ///
+    /// Maps are implemented internally as [`BTreeMap<AlgebraicValue, AlgebraicValue>`].
+    /// This implies that key/values are ordered first by key and then value
+    /// as if they were a sorted slice `[(key, value)]`.
+    /// This order is observable as maps are exposed both directly
+    /// and indirectly via `Ord for `[`AlgebraicValue`].
+    /// The latter lets us observe that e.g., `{ a: 42 } < { b: 42 }`.
+    /// However, we cannot observe any difference between `{ a: 0, b: 0 }` and `{ b: 0, a: 0 }`,
+    /// as the natural order is used as opposed to insertion order.
+    /// Where insertion order is relevant,
+    /// an [`AlgebraicValue::Array`] with `(key, value)` pairs can be used instead.
+    Map(MapValue),
+}
+
+// Flattening the builtin values into `AlgebraicValue` must not make it any larger.
+const _: () = assert!(std::mem::size_of::<AlgebraicValue>() <= 32);
+
+/// Implements `is_$name`, `as_$name`, `as_$name_mut`, and `into_$name` for each `$variant`.
+macro_rules! impl_accessors {
+    ($($variant:ident, $name:ident, $is:ident, $as:ident, $as_mut:ident, $into:ident: $ty:ty;)*) => {
+        $(
+            #[doc = concat!("Returns whether the value is a `", stringify!($name), "` value.")]
+            #[inline]
+            pub fn $is(&self) -> bool {
+                matches!(self, Self::$variant(_))
+            }
+
+            #[doc = concat!("Interpret the value as a `", stringify!($ty), "` or `None` if it isn't a `", stringify!($name), "` value.")]
+            #[inline]
+            pub fn $as(&self) -> Option<&$ty> {
+                match self {
+                    Self::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+
+            #[doc = concat!("Interpret the value as a mutable `", stringify!($ty), "` or `None` if it isn't a `", stringify!($name), "` value.")]
+            #[inline]
+            pub fn $as_mut(&mut self) -> Option<&mut $ty> {
+                match self {
+                    Self::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+
+            #[doc = concat!("Convert the value into a `", stringify!($ty), "` or `Err(self)` if it isn't a `", stringify!($name), "` value.")]
+            #[inline]
+            pub fn $into(self) -> Result<$ty, Self> {
+                match self {
+                    Self::$variant(v) => Ok(v),
+                    _ => Err(self),
+                }
+            }
+        )*
+    };
 }
 
 #[allow(non_snake_case)]
@@ -45,304 +160,117 @@ impl AlgebraicValue {
     /// The type of `UNIT` is `()`.
     pub const UNIT: Self = Self::product(Vec::new());
 
-    /// Interpret the value as a `bool` or `None` if it isn't a `bool` value.
-    #[inline]
-    pub fn as_bool(&self) -> Option<&bool> {
-        self.as_builtin()?.as_bool()
-    }
-
-    /// Interpret the value as an `i8` or `None` if it isn't a `i8` value.
-    #[inline]
-    pub fn as_i8(&self) -> Option<&i8> {
-        self.as_builtin()?.as_i8()
-    }
-
-    /// Interpret the value as a `u8` or `None` if it isn't a `u8` value.
-    #[inline]
-    pub fn as_u8(&self) -> Option<&u8> {
-        self.as_builtin()?.as_u8()
-    }
-
-    /// Interpret the value as an `i16` or `None` if it isn't an `i16` value.
-    #[inline]
-    pub fn as_i16(&self) -> Option<&i16> {
-        self.as_builtin()?.as_i16()
-    }
-
-    /// Interpret the value as a `u16` or `None` if it isn't a `u16` value.
-    #[inline]
-    pub fn as_u16(&self) -> Option<&u16> {
-        self.as_builtin()?.as_u16()
-    }
-
-    /// Interpret the value as an `i32` or `None` if it isn't an `i32` value.
-    #[inline]
-    pub fn as_i32(&self) -> Option<&i32> {
-        self.as_builtin()?.as_i32()
-    }
-
-    /// Interpret the value as a `u32` or `None` if it isn't a `u32` value.
-    #[inline]
-    pub fn as_u32(&self) -> Option<&u32> {
-        self.as_builtin()?.as_u32()
-    }
-
-    /// Interpret the value as an `i64` or `None` if it isn't an `i64` value.
-    #[inline]
-    pub fn as_i64(&self) -> Option<&i64> {
-        self.as_builtin()?.as_i64()
-    }
-
-    /// Interpret the value as a `u64` or `None` if it isn't a `u64` value.
-    #[inline]
-    pub fn as_u64(&self) -> Option<&u64> {
-        self.as_builtin()?.as_u64()
-    }
-
-    /// Interpret the value as an `i128` or `None` if it isn't an `i128` value.
-    #[inline]
-    pub fn as_i128(&self) -> Option<&i128> {
-        self.as_builtin()?.as_i128()
-    }
-
-    /// Interpret the value as a `u128` or `None` if it isn't a `u128` value.
-    #[inline]
-    pub fn as_u128(&self) -> Option<&u128> {
-        self.as_builtin()?.as_u128()
-    }
-
-    /// Interpret the value as a `f32` or `None` if it isn't a `f32` value.
-    #[inline]
-    pub fn as_f32(&self) -> Option<&F32> {
-        self.as_builtin()?.as_f32()
-    }
-
-    /// Interpret the value as a `f64` or `None` if it isn't a `f64` value.
-    #[inline]
-    pub fn as_f64(&self) -> Option<&F64> {
-        self.as_builtin()?.as_f64()
-    }
-
-    /// Interpret the value as a `String` or `None` if it isn't a `String` value.
-    #[inline]
-    pub fn as_string(&self) -> Option<&String> {
-        self.as_builtin()?.as_string()
-    }
-
-    /// Interpret the value as a `Vec<u8>` or `None` if it isn't a `Vec<u8>` value.
-    #[inline]
-    pub fn as_bytes(&self) -> Option<&Vec<u8>> {
-        self.as_builtin()?.as_bytes()
-    }
-
-    /// Interpret the value as an `ArrayValue` or `None` if it isn't an `ArrayValue` value.
-    #[inline]
-    pub fn as_array(&self) -> Option<&ArrayValue> {
-        self.as_builtin()?.as_array()
-    }
-
-    /// Interpret the value as a map or `None` if it isn't a map value.
-    #[inline]
-    pub fn as_map(&self) -> Option<&BTreeMap<Self, Self>> {
-        self.as_builtin()?.as_map()
-    }
-
-    /// Convert the value into a `bool` or `Err(self)` if it isn't a `bool` value.
-    #[inline]
-    pub fn into_bool(self) -> Result<bool, Self> {
-        self.into_builtin()?.into_bool().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an `i8` or `Err(self)` if it isn't an `i8` value.
-    #[inline]
-    pub fn into_i8(self) -> Result<i8, Self> {
-        self.into_builtin()?.into_i8().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `u8` or `Err(self)` if it isn't a `u8` value.
-    #[inline]
-    pub fn into_u8(self) -> Result<u8, Self> {
-        self.into_builtin()?.into_u8().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an `i16` or `Err(self)` if it isn't an `i16` value.
-    #[inline]
-    pub fn into_i16(self) -> Result<i16, Self> {
-        self.into_builtin()?.into_i16().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `u16` or `Err(self)` if it isn't a `u16` value.
-    #[inline]
-    pub fn into_u16(self) -> Result<u16, Self> {
-        self.into_builtin()?.into_u16().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an `i32` or `Err(self)` if it isn't an `i32` value.
-    #[inline]
-    pub fn into_i32(self) -> Result<i32, Self> {
-        self.into_builtin()?.into_i32().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `u32` or `Err(self)` if it isn't a `u32` value.
-    #[inline]
-    pub fn into_u32(self) -> Result<u32, Self> {
-        self.into_builtin()?.into_u32().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an `i64` or `Err(self)` if it isn't an `i64` value.
-    #[inline]
-    pub fn into_i64(self) -> Result<i64, Self> {
-        self.into_builtin()?.into_i64().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `u64` or `Err(self)` if it isn't a `u64` value.
-    #[inline]
-    pub fn into_u64(self) -> Result<u64, Self> {
-        self.into_builtin()?.into_u64().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an `i128` or `Err(self)` if it isn't an `i128` value.
-    #[inline]
-    pub fn into_i128(self) -> Result<i128, Self> {
-        self.into_builtin()?.into_i128().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `u128` or `Err(self)` if it isn't a `u128` value.
+    impl_accessors! {
+        Sum, sum, is_sum, as_sum, as_sum_mut, into_sum: SumValue;
+        Product, product, is_product, as_product, as_product_mut, into_product: ProductValue;
+        Bool, bool, is_bool, as_bool, as_bool_mut, into_bool: bool;
+        I8, i8, is_i8, as_i8, as_i8_mut, into_i8: i8;
+        U8, u8, is_u8, as_u8, as_u8_mut, into_u8: u8;
+        I16, i16, is_i16, as_i16, as_i16_mut, into_i16: i16;
+        U16, u16, is_u16, as_u16, as_u16_mut, into_u16: u16;
+        I32, i32, is_i32, as_i32, as_i32_mut, into_i32: i32;
+        U32, u32, is_u32, as_u32, as_u32_mut, into_u32: u32;
+        I64, i64, is_i64, as_i64, as_i64_mut, into_i64: i64;
+        U64, u64, is_u64, as_u64, as_u64_mut, into_u64: u64;
+        I128, i128, is_i128, as_i128, as_i128_mut, into_i128: i128;
+        U128, u128, is_u128, as_u128, as_u128_mut, into_u128: u128;
+        F32, f32, is_f32, as_f32, as_f32_mut, into_f32: F32;
+        F64, f64, is_f64, as_f64, as_f64_mut, into_f64: F64;
+        Array, array, is_array, as_array, as_array_mut, into_array: ArrayValue;
+        Map, map, is_map, as_map, as_map_mut, into_map: MapValue;
+    }
+
+    /// Returns whether the value is a `String` value.
+    #[inline]
+    pub fn is_string(&self) -> bool {
+        matches!(self, Self::String(_))
+    }
+
+    /// Interpret the value as a `str` or `None` if it isn't a `String` value.
+    ///
+    /// This returned an `Option<&String>` before string values were stored as `Box<str>`s.
     #[inline]
-    pub fn into_u128(self) -> Result<u128, Self> {
-        self.into_builtin()?.into_u128().map_err(Self::Builtin)
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
     }
 
-    /// Convert the value into a `f32` or `Err(self)` if it isn't a `f32` value.
+    /// Interpret the value as a `str` or `None` if it isn't a `String` value.
+    ///
+    /// This is the same as [`as_string`](Self::as_string).
     #[inline]
-    pub fn into_f32(self) -> Result<F32, Self> {
-        self.into_builtin()?.into_f32().map_err(Self::Builtin)
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_string()
     }
 
-    /// Convert the value into a `f64` or `Err(self)` if it isn't a `f64` value.
+    /// Interpret the value as a mutable `Box<str>` or `None` if it isn't a `String` value.
     #[inline]
-    pub fn into_f64(self) -> Result<F64, Self> {
-        self.into_builtin()?.into_f64().map_err(Self::Builtin)
+    pub fn as_string_mut(&mut self) -> Option<&mut Box<str>> {
+        match self {
+            Self::String(v) => Some(v),
+            _ => None,
+        }
     }
 
     /// Convert the value into a `String` or `Err(self)` if it isn't a `String` value.
     #[inline]
     pub fn into_string(self) -> Result<String, Self> {
-        self.into_builtin()?.into_string().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a `Vec<u8>` or `Err(self)` if it isn't a `Vec<u8>` value.
-    #[inline]
-    pub fn into_bytes(self) -> Result<Vec<u8>, Self> {
-        self.into_builtin()?.into_bytes().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into an [`ArrayValue`] or `Err(self)` if it isn't an [`ArrayValue`] value.
-    #[inline]
-    pub fn into_array(self) -> Result<ArrayValue, Self> {
-        self.into_builtin()?.into_array().map_err(Self::Builtin)
-    }
-
-    /// Convert the value into a map or `Err(self)` if it isn't a map value.
-    #[inline]
-    pub fn into_map(self) -> Result<BTreeMap<Self, Self>, Self> {
-        self.into_builtin()?.into_map().map_err(Self::Builtin)
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: bool`.
-    #[inline]
-    pub const fn Bool(v: bool) -> Self {
-        Self::Builtin(BuiltinValue::Bool(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: i8`.
-    #[inline]
-    pub const fn I8(v: i8) -> Self {
-        Self::Builtin(BuiltinValue::I8(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: u8`.
-    #[inline]
-    pub const fn U8(v: u8) -> Self {
-        Self::Builtin(BuiltinValue::U8(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: i16`.
-    #[inline]
-    pub const fn I16(v: i16) -> Self {
-        Self::Builtin(BuiltinValue::I16(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: u16`.
-    #[inline]
-    pub const fn U16(v: u16) -> Self {
-        Self::Builtin(BuiltinValue::U16(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: i32`.
-    #[inline]
-    pub const fn I32(v: i32) -> Self {
-        Self::Builtin(BuiltinValue::I32(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: u32`.
-    #[inline]
-    pub const fn U32(v: u32) -> Self {
-        Self::Builtin(BuiltinValue::U32(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: i64`.
-    #[inline]
-    pub const fn I64(v: i64) -> Self {
-        Self::Builtin(BuiltinValue::I64(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: u64`.
-    #[inline]
-    pub const fn U64(v: u64) -> Self {
-        Self::Builtin(BuiltinValue::U64(v))
-    }
-
-    /// Returns an [`AlgebraicValue`] representing `v: i128`.
-    #[inline]
-    pub const fn I128(v: i128) -> Self {
-        Self::Builtin(BuiltinValue::I128(v))
+        match self {
+            Self::String(v) => Ok(v.into()),
+            _ => Err(self),
+        }
     }
 
-    /// Returns an [`AlgebraicValue`] representing `v: u128`.
+    /// Returns whether the value is an array of `u8`s, i.e., of bytes.
     #[inline]
-    pub const fn U128(v: u128) -> Self {
-        Self::Builtin(BuiltinValue::U128(v))
+    pub fn is_bytes(&self) -> bool {
+        matches!(self, Self::Array(ArrayValue::U8(_)))
     }
 
-    /// Returns an [`AlgebraicValue`] representing `v: f32`.
+    /// Interpret the value as a `[u8]` or `None` if it isn't an array of `u8`s.
+    ///
+    /// Arrays of other elements are not bytes, even when their elements are all small numbers.
     #[inline]
-    pub const fn F32(v: F32) -> Self {
-        Self::Builtin(BuiltinValue::F32(v))
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Array(ArrayValue::U8(v)) => Some(v),
+            _ => None,
+        }
     }
 
-    /// Returns an [`AlgebraicValue`] representing `v: f64`.
+    /// Convert the value into a `Vec<u8>` or `Err(self)` if it isn't a `Vec<u8>` value.
     #[inline]
-    pub const fn F64(v: F64) -> Self {
-        Self::Builtin(BuiltinValue::F64(v))
+    pub fn into_bytes(self) -> Result<Vec<u8>, Self> {
+        match self {
+            Self::Array(ArrayValue::U8(v)) => Ok(v),
+            _ => Err(self),
+        }
     }
 
-    /// Returns an [`AlgebraicValue`] representing `v: String`.
+    /// Returns `v` unchanged.
+    ///
+    /// Builtin values used to be wrapped in this variant.
+    #[deprecated(note = "builtin values are now variants of `AlgebraicValue` directly")]
     #[inline]
-    pub const fn String(v: String) -> Self {
-        Self::Builtin(BuiltinValue::String(v))
+    pub const fn Builtin(v: Self) -> Self {
+        v
     }
 
     /// Returns an [`AlgebraicValue`] representing `v: Vec<u8>`.
+    ///
+    /// Bytes have no variant of their own, but are an [`ArrayValue::U8`],
+    /// so there is exactly one value for any byte blob, and it compares and hashes as such.
     #[inline]
     pub const fn Bytes(v: Vec<u8>) -> Self {
-        Self::Builtin(BuiltinValue::Bytes(v))
+        Self::Array(ArrayValue::U8(v))
     }
 
     /// Returns an [`AlgebraicValue`] for a `val` which can be converted into an [`ArrayValue`].
     #[inline]
     pub fn ArrayOf(val: impl Into<ArrayValue>) -> Self {
-        Self::Builtin(BuiltinValue::Array { val: val.into() })
+        Self::Array(val.into())
     }
 
     /// Returns an [`AlgebraicValue`] for `some: v`.
@@ -374,7 +302,29 @@ impl AlgebraicValue {
 
     /// Returns an [`AlgebraicValue`] representing a map value defined by the given `map`.
     pub const fn map(map: BTreeMap<Self, Self>) -> Self {
-        Self::Builtin(BuiltinValue::Map { val: map })
+        Self::Map(map)
+    }
+
+    /// Returns the fields of the product `self` of type `schema`
+    /// for which `predicate`, given the name, if any, and the value of the field, returns `true`,
+    /// together with the type of the fields kept.
+    ///
+    /// If `self` is not a product, or not one with as many fields as `schema`,
+    /// both the value and the type returned are the empty product.
+    pub fn filter_product_fields(
+        &self,
+        predicate: impl Fn(Option<&str>, &AlgebraicValue) -> bool,
+        schema: &ProductType,
+    ) -> (ProductValue, ProductType) {
+        let Some(Ok(fields)) = self.as_product().map(|val| val.iter_named(schema)) else {
+            return (ProductValue::new(&[]), ProductType::new(Vec::new()));
+        };
+        let (elements, types) = fields
+            .zip(&schema.elements)
+            .filter(|((name, val), _)| predicate(*name, val))
+            .map(|((_, val), ty)| (val.clone(), ty.clone()))
+            .unzip();
+        (ProductValue { elements }, ProductType::new(types))
     }
 
     /// Returns the [`AlgebraicType`] of the sum value `x`.
@@ -416,24 +366,63 @@ impl AlgebraicValue {
         match self {
             AlgebraicValue::Sum(x) => Self::type_of_sum(x),
             AlgebraicValue::Product(x) => Self::type_of_product(x),
-            AlgebraicValue::Builtin(x) => match x {
-                BuiltinValue::Bool(_) => AlgebraicType::Bool,
-                BuiltinValue::I8(_) => AlgebraicType::I8,
-                BuiltinValue::U8(_) => AlgebraicType::U8,
-                BuiltinValue::I16(_) => AlgebraicType::I16,
-                BuiltinValue::U16(_) => AlgebraicType::U16,
-                BuiltinValue::I32(_) => AlgebraicType::I32,
-                BuiltinValue::U32(_) => AlgebraicType::U32,
-                BuiltinValue::I64(_) => AlgebraicType::I64,
-                BuiltinValue::U64(_) => AlgebraicType::U64,
-                BuiltinValue::I128(_) => AlgebraicType::I128,
-                BuiltinValue::U128(_) => AlgebraicType::U128,
-                BuiltinValue::F32(_) => AlgebraicType::F32,
-                BuiltinValue::F64(_) => AlgebraicType::F64,
-                BuiltinValue::String(_) => AlgebraicType::String,
-                BuiltinValue::Array { val } => AlgebraicType::Builtin(BuiltinType::Array(val.type_of())),
-                BuiltinValue::Map { val } => Self::type_of_map(val),
-            },
+            AlgebraicValue::Bool(_) => AlgebraicType::Bool,
+            AlgebraicValue::I8(_) => AlgebraicType::I8,
+            AlgebraicValue::U8(_) => AlgebraicType::U8,
+            AlgebraicValue::I16(_) => AlgebraicType::I16,
+            AlgebraicValue::U16(_) => AlgebraicType::U16,
+            AlgebraicValue::I32(_) => AlgebraicType::I32,
+            AlgebraicValue::U32(_) => AlgebraicType::U32,
+            AlgebraicValue::I64(_) => AlgebraicType::I64,
+            AlgebraicValue::U64(_) => AlgebraicType::U64,
+            AlgebraicValue::I128(_) => AlgebraicType::I128,
+            AlgebraicValue::U128(_) => AlgebraicType::U128,
+            AlgebraicValue::F32(_) => AlgebraicType::F32,
+            AlgebraicValue::F64(_) => AlgebraicType::F64,
+            AlgebraicValue::String(_) => AlgebraicType::String,
+            AlgebraicValue::Array(val) => AlgebraicType::Builtin(BuiltinType::Array(val.type_of())),
+            AlgebraicValue::Map(val) => Self::type_of_map(val),
+        }
+    }
+
+    /// Returns the name of the variant of the value, e.g., `"String"`, to say what it is in error messages.
+    ///
+    /// Unlike [`type_of`](Self::type_of), this doesn't look inside the value, so it's cheap.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AlgebraicValue::Sum(_) => "Sum",
+            AlgebraicValue::Product(_) => "Product",
+            AlgebraicValue::Bool(_) => "Bool",
+            AlgebraicValue::I8(_) => "I8",
+            AlgebraicValue::U8(_) => "U8",
+            AlgebraicValue::I16(_) => "I16",
+            AlgebraicValue::U16(_) => "U16",
+            AlgebraicValue::I32(_) => "I32",
+            AlgebraicValue::U32(_) => "U32",
+            AlgebraicValue::I64(_) => "I64",
+            AlgebraicValue::U64(_) => "U64",
+            AlgebraicValue::I128(_) => "I128",
+            AlgebraicValue::U128(_) => "U128",
+            AlgebraicValue::F32(_) => "F32",
+            AlgebraicValue::F64(_) => "F64",
+            AlgebraicValue::String(_) => "String",
+            AlgebraicValue::Array(_) => "Array",
+            AlgebraicValue::Map(_) => "Map",
+        }
+    }
+
+    /// Returns an estimate of the number of bytes `self` owns on the heap.
+    ///
+    /// This does not include `size_of::<AlgebraicValue>()` for `self` itself,
+    /// and counts the capacity rather than the length of strings and vectors.
+    pub fn heap_size_bytes(&self) -> usize {
+        match self {
+            AlgebraicValue::Sum(x) => x.heap_size_bytes(),
+            AlgebraicValue::Product(x) => x.heap_size_bytes(),
+            AlgebraicValue::String(s) => s.len(),
+            AlgebraicValue::Array(val) => val.heap_size_bytes(),
+            AlgebraicValue::Map(val) => crate::builtin_value::map_heap_size_bytes(val),
+            _ => 0,
         }
     }
 }
@@ -458,13 +447,46 @@ impl RangeBounds<AlgebraicValue> for AlgebraicValue {
     }
 }
 
+/// An error taking a value and its type out of their typespace,
+/// as the type is recursive or refers to a type not in the typespace, so it has no `Ref`-free form.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("The type {ty} is recursive or refers to a type not in its typespace")]
+pub struct UnresolvableTypeError {
+    /// The type, as printed.
+    pub ty: String,
+}
+
+impl ValueWithType<'_, AlgebraicValue> {
+    /// Returns the value and its type, both owned,
+    /// with all `AlgebraicType::Ref`s in the type resolved through the typespace,
+    /// so that the pair no longer borrows from anything nor needs the typespace to be understood.
+    ///
+    /// Returns an error if the type is recursive or refers to a type not in the typespace,
+    /// as it then has no `Ref`-free form.
+    pub fn into_owned(self) -> Result<(AlgebraicValue, AlgebraicType), UnresolvableTypeError> {
+        let ty = WithTypespace::new(self.typespace(), self.ty())
+            .resolve_refs()
+            .ok_or_else(|| UnresolvableTypeError {
+                ty: crate::algebraic_type::fmt::fmt_algebraic_type(self.ty()).to_string(),
+            })?;
+        Ok((self.value().clone(), ty))
+    }
+
+    /// Returns the value and its type, both owned, as [`into_owned`](Self::into_owned) does.
+    pub fn clone_owned(&self) -> Result<(AlgebraicValue, AlgebraicType), UnresolvableTypeError> {
+        self.into_owned()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
 
+    use super::UnresolvableTypeError;
     use crate::satn::Satn;
     use crate::{
-        AlgebraicType, AlgebraicValue, ArrayValue, ProductTypeElement, Typespace, ValueWithType, WithTypespace,
+        product, AlgebraicType, AlgebraicValue, ArrayValue, ProductTypeElement, ProductValue, Typespace, ValueWithType,
+        WithTypespace,
     };
 
     fn in_space<'a, T: crate::Value>(ts: &'a Typespace, ty: &'a T::Type, val: &'a T) -> ValueWithType<'a, T> {
@@ -539,4 +561,326 @@ mod tests {
         let typespace = Typespace::new(vec![]);
         assert_eq!(in_space(&typespace, &map, &value).to_satn(), "[2: 3]");
     }
+
+    #[test]
+    fn array_of_refs() {
+        let mut typespace = Typespace::new(vec![]);
+        let r = typespace.add(AlgebraicType::product(vec![ProductTypeElement::new_named(
+            AlgebraicType::U8,
+            "x",
+        )]));
+        let array = AlgebraicType::array(AlgebraicType::Ref(r));
+        let elems: Vec<ProductValue> = vec![product![1u8], product![2u8]];
+        let value = AlgebraicValue::ArrayOf(elems);
+        assert_eq!(in_space(&typespace, &array, &value).to_satn(), "[(x = 1), (x = 2)]");
+    }
+
+    #[test]
+    fn map_of_refs() {
+        let mut typespace = Typespace::new(vec![]);
+        let r = typespace.add(AlgebraicType::U8);
+        let map = AlgebraicType::map(AlgebraicType::Ref(r), AlgebraicType::Ref(r));
+        let value = AlgebraicValue::map([(AlgebraicValue::U8(2), AlgebraicValue::U8(3))].into());
+        assert_eq!(in_space(&typespace, &map, &value).to_satn(), "[2: 3]");
+    }
+
+    #[test]
+    #[should_panic(expected = "mismatched value and schema")]
+    fn mismatched_ref_panics() {
+        let mut typespace = Typespace::new(vec![]);
+        let r = typespace.add(AlgebraicType::U8);
+        let value = AlgebraicValue::Bool(true);
+        in_space(&typespace, &AlgebraicType::Ref(r), &value).to_satn();
+    }
+
+    #[test]
+    fn accessors() {
+        use crate::builtin_value::{F32, F64};
+        use crate::SumValue;
+        use std::fmt;
+
+        let values = [
+            AlgebraicValue::sum(1, AlgebraicValue::U8(2)),
+            AlgebraicValue::product(vec![AlgebraicValue::Bool(false)]),
+            AlgebraicValue::Bool(true),
+            AlgebraicValue::I8(-8),
+            AlgebraicValue::U8(8),
+            AlgebraicValue::I16(-16),
+            AlgebraicValue::U16(16),
+            AlgebraicValue::I32(-32),
+            AlgebraicValue::U32(32),
+            AlgebraicValue::I64(-64),
+            AlgebraicValue::U64(64),
+            AlgebraicValue::I128(-128),
+            AlgebraicValue::U128(128),
+            AlgebraicValue::F32(0.5.into()),
+            AlgebraicValue::F64(0.25.into()),
+            AlgebraicValue::String("str".into()),
+            AlgebraicValue::Bytes(vec![1, 2]),
+            AlgebraicValue::ArrayOf(vec![1u16, 2]),
+            AlgebraicValue::map([(AlgebraicValue::U8(1), AlgebraicValue::U8(2))].into()),
+        ];
+        // Checks that exactly the value at `index` in `values` matches an accessor,
+        // and that it gets `expected` out of it.
+        fn check<T: PartialEq + fmt::Debug + ?Sized>(
+            values: &[AlgebraicValue],
+            index: usize,
+            is: fn(&AlgebraicValue) -> bool,
+            get: fn(&AlgebraicValue) -> Option<&T>,
+            expected: &T,
+        ) {
+            for (i, value) in values.iter().enumerate() {
+                assert_eq!(is(value), i == index, "{value:?}");
+                assert_eq!(get(value), (i == index).then_some(expected), "{value:?}");
+            }
+        }
+
+        let sum = SumValue {
+            tag: 1,
+            value: Box::new(AlgebraicValue::U8(2)),
+        };
+        check(&values, 0, AlgebraicValue::is_sum, AlgebraicValue::as_sum, &sum);
+        check(
+            &values,
+            1,
+            AlgebraicValue::is_product,
+            AlgebraicValue::as_product,
+            &product![false],
+        );
+        check(&values, 2, AlgebraicValue::is_bool, AlgebraicValue::as_bool, &true);
+        check(&values, 3, AlgebraicValue::is_i8, AlgebraicValue::as_i8, &-8);
+        check(&values, 4, AlgebraicValue::is_u8, AlgebraicValue::as_u8, &8);
+        check(&values, 5, AlgebraicValue::is_i16, AlgebraicValue::as_i16, &-16);
+        check(&values, 6, AlgebraicValue::is_u16, AlgebraicValue::as_u16, &16);
+        check(&values, 7, AlgebraicValue::is_i32, AlgebraicValue::as_i32, &-32);
+        check(&values, 8, AlgebraicValue::is_u32, AlgebraicValue::as_u32, &32);
+        check(&values, 9, AlgebraicValue::is_i64, AlgebraicValue::as_i64, &-64);
+        check(&values, 10, AlgebraicValue::is_u64, AlgebraicValue::as_u64, &64);
+        check(&values, 11, AlgebraicValue::is_i128, AlgebraicValue::as_i128, &-128);
+        check(&values, 12, AlgebraicValue::is_u128, AlgebraicValue::as_u128, &128);
+        check(
+            &values,
+            13,
+            AlgebraicValue::is_f32,
+            AlgebraicValue::as_f32,
+            &F32::from(0.5),
+        );
+        check(
+            &values,
+            14,
+            AlgebraicValue::is_f64,
+            AlgebraicValue::as_f64,
+            &F64::from(0.25),
+        );
+        check(&values, 15, AlgebraicValue::is_string, AlgebraicValue::as_string, "str");
+        check(&values, 15, AlgebraicValue::is_string, AlgebraicValue::as_str, "str");
+        check(
+            &values,
+            16,
+            AlgebraicValue::is_bytes,
+            AlgebraicValue::as_bytes,
+            &[1, 2][..],
+        );
+        let map = [(AlgebraicValue::U8(1), AlgebraicValue::U8(2))].into();
+        check(&values, 18, AlgebraicValue::is_map, AlgebraicValue::as_map, &map);
+
+        // Both bytes and other arrays are arrays.
+        let arrays = values.iter().filter_map(AlgebraicValue::as_array).collect::<Vec<_>>();
+        assert_eq!(arrays, [&ArrayValue::U8(vec![1, 2]), &ArrayValue::U16(vec![1, 2])]);
+        assert!(values.iter().filter(|v| v.is_array()).eq(&values[16..18]));
+    }
+
+    #[test]
+    fn into_moves_out_of_the_value() {
+        // The heap allocations of the contents are moved out, not cloned.
+        let string = String::from("moved");
+        let ptr = string.as_ptr();
+        let value = AlgebraicValue::String(string.into());
+        assert_eq!(value.type_name(), "String");
+        assert_eq!(value.into_string().unwrap().as_ptr(), ptr);
+
+        let bytes = vec![1u8, 2, 3];
+        let ptr = bytes.as_ptr();
+        assert_eq!(AlgebraicValue::Bytes(bytes).into_bytes().unwrap().as_ptr(), ptr);
+
+        let elements = vec![AlgebraicValue::U8(1)];
+        let ptr = elements.as_ptr();
+        let value = AlgebraicValue::product(elements);
+        assert_eq!(value.into_product().unwrap().elements.as_ptr(), ptr);
+
+        let payload = Box::new(AlgebraicValue::U8(1));
+        let ptr: *const _ = &*payload;
+        let value = AlgebraicValue::Sum(crate::SumValue { tag: 0, value: payload });
+        assert!(std::ptr::eq(&*value.into_sum().unwrap().value, ptr));
+
+        let array = vec![1u16, 2];
+        let ptr = array.as_ptr();
+        let ArrayValue::U16(array) = AlgebraicValue::ArrayOf(array).into_array().unwrap() else {
+            panic!("not an array of `u16`s")
+        };
+        assert_eq!(array.as_ptr(), ptr);
+
+        let map = AlgebraicValue::map([(AlgebraicValue::U8(1), AlgebraicValue::UNIT)].into());
+        assert_eq!(map.into_map().unwrap().len(), 1);
+        assert_eq!(AlgebraicValue::U64(7).into_u64(), Ok(7));
+    }
+
+    #[test]
+    fn into_mismatch_returns_the_value() {
+        let string = String::from("kept");
+        let ptr = string.as_ptr();
+        let value = AlgebraicValue::String(string.into());
+        let value = value.into_bytes().unwrap_err();
+        let value = value.into_product().unwrap_err();
+        let value = value.into_u32().unwrap_err();
+        assert_eq!(value.type_name(), "String");
+        assert_eq!(value.as_string().unwrap().as_ptr(), ptr);
+
+        // Arrays of other elements are not bytes, even though they are arrays.
+        let value = AlgebraicValue::ArrayOf(vec![1u16]).into_bytes().unwrap_err();
+        assert_eq!(value, AlgebraicValue::ArrayOf(vec![1u16]));
+        assert_eq!(value.type_name(), "Array");
+        assert!(value.into_array().is_ok());
+
+        let names = [
+            (AlgebraicValue::UNIT, "Product"),
+            (AlgebraicValue::OptionNone(), "Sum"),
+            (AlgebraicValue::Bool(true), "Bool"),
+            (AlgebraicValue::I128(-1), "I128"),
+            (AlgebraicValue::F64(1.0.into()), "F64"),
+            (AlgebraicValue::map(BTreeMap::new()), "Map"),
+        ];
+        for (value, name) in names {
+            assert_eq!(value.type_name(), name);
+            assert_eq!(value.clone().into_string(), Err(value));
+        }
+    }
+
+    #[test]
+    fn heap_size_of_primitive_is_zero() {
+        let value = AlgebraicValue::U32(5);
+        assert_eq!(value.heap_size_bytes(), 0);
+    }
+
+    #[test]
+    fn heap_size_counts_capacity() {
+        let value = AlgebraicValue::String("x".repeat(100).into());
+        assert_eq!(value.heap_size_bytes(), 100);
+        // The excess capacity of a string is released when it becomes a value.
+        let value = AlgebraicValue::String(String::with_capacity(100).into());
+        assert_eq!(value.heap_size_bytes(), 0);
+
+        let value = AlgebraicValue::ArrayOf(Vec::<u64>::with_capacity(10));
+        assert_eq!(value.heap_size_bytes(), 80);
+    }
+
+    #[test]
+    fn heap_size_of_composites_recurses() {
+        let name = "x".repeat(50);
+        let row = ProductValue {
+            elements: vec![AlgebraicValue::U8(1), AlgebraicValue::String(name.into())],
+        };
+        let elems = 2 * std::mem::size_of::<AlgebraicValue>();
+        assert_eq!(row.heap_size_bytes(), elems + 50);
+
+        // Boxing the product into a sum adds one more `AlgebraicValue`.
+        let sum = AlgebraicValue::sum(0, AlgebraicValue::Product(row));
+        assert_eq!(
+            sum.heap_size_bytes(),
+            elems + std::mem::size_of::<AlgebraicValue>() + 50
+        );
+    }
+
+    #[test]
+    fn into_owned_resolves_refs() {
+        let mut typespace = Typespace::new(vec![]);
+        let point = typespace.add(AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U8, "x"),
+            ProductTypeElement::new_named(AlgebraicType::U8, "y"),
+        ]));
+        let points = typespace.add(AlgebraicType::array(AlgebraicType::Ref(point)));
+        let ty = AlgebraicType::option(AlgebraicType::Ref(points));
+        let value = AlgebraicValue::OptionSome(AlgebraicValue::ArrayOf(vec![product![1u8, 2u8], product![3u8, 4u8]]));
+        let borrowed = in_space(&typespace, &ty, &value);
+
+        let (owned_value, owned_ty) = borrowed.into_owned().unwrap();
+        assert_eq!(owned_value, value);
+        // Without `Ref`s, the type resolves the same in an empty typespace.
+        let empty = Typespace::new(vec![]);
+        assert_eq!(
+            WithTypespace::new(&empty, &owned_ty).resolve_refs().as_ref(),
+            Some(&owned_ty)
+        );
+        assert_eq!(
+            owned_ty,
+            AlgebraicType::option(AlgebraicType::array(typespace[point].clone()))
+        );
+        assert_eq!(borrowed.clone_owned(), Ok((owned_value.clone(), owned_ty.clone())));
+
+        let owned = in_space(&empty, &owned_ty, &owned_value);
+        assert_eq!(
+            crate::bsatn::to_vec(&owned).unwrap(),
+            crate::bsatn::to_vec(&borrowed).unwrap()
+        );
+        assert_eq!(owned.to_satn(), borrowed.to_satn());
+    }
+
+    #[test]
+    fn into_owned_of_recursive_type_is_an_error() {
+        let mut typespace = Typespace::new(vec![]);
+        let list = typespace.add(AlgebraicType::UNIT_TYPE);
+        typespace[list] = AlgebraicType::option(AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U8, "head"),
+            ProductTypeElement::new_named(AlgebraicType::Ref(list), "tail"),
+        ]));
+        let value = AlgebraicValue::OptionNone();
+        let err = in_space(&typespace, &AlgebraicType::Ref(list), &value).into_owned();
+        assert_eq!(err, Err(UnresolvableTypeError { ty: "&0".into() }));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "The type &0 is recursive or refers to a type not in its typespace"
+        );
+
+        let dangling = AlgebraicType::array(AlgebraicType::Ref(crate::AlgebraicTypeRef(7)));
+        let empty = AlgebraicValue::ArrayOf(Vec::<u8>::new());
+        let err = in_space(&typespace, &dangling, &empty).clone_owned().unwrap_err();
+        assert_eq!(err.ty, "Array<&7>");
+    }
+
+    #[test]
+    fn filter_product_fields() {
+        let schema = crate::ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "user_id"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "user_name"),
+            ProductTypeElement::new(AlgebraicType::Bool, None),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::U64), "score"),
+        ]);
+        let row = AlgebraicValue::product(vec![
+            AlgebraicValue::U32(7),
+            AlgebraicValue::OptionSome(AlgebraicValue::String("Alice".into())),
+            AlgebraicValue::Bool(true),
+            AlgebraicValue::OptionNone(),
+        ]);
+        let fields = &row.as_product().unwrap().elements;
+
+        let by_prefix = |name: Option<&str>, _: &AlgebraicValue| name.map_or(false, |n| n.starts_with("user_"));
+        let (val, ty) = row.filter_product_fields(by_prefix, &schema);
+        assert_eq!(val.elements.len(), ty.elements.len());
+        assert_eq!(ty.elements[..], schema.elements[..2]);
+        assert_eq!(val.elements[..], fields[..2]);
+
+        let (val, ty) = row.filter_product_fields(|_, val| *val != AlgebraicValue::OptionNone(), &schema);
+        assert_eq!(val.elements.len(), ty.elements.len());
+        assert_eq!(ty.elements[..], schema.elements[..3]);
+        assert_eq!(val.elements[..], fields[..3]);
+
+        // Anything but a product of the schema has no fields to keep.
+        let empty = (ProductValue::new(&[]), crate::ProductType::new(vec![]));
+        assert_eq!(
+            AlgebraicValue::U32(7).filter_product_fields(|_, _| true, &schema),
+            empty
+        );
+        let short = AlgebraicValue::product(vec![AlgebraicValue::U32(7)]);
+        assert_eq!(short.filter_product_fields(|_, _| true, &schema), empty);
+    }
 }
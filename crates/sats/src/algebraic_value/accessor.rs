@@ -0,0 +1,513 @@
+//! Access to the values nested in an [`AlgebraicValue`] by paths of field names and array indices,
+//! e.g., `user.addresses[0].zip`, resolved against the type of the value.
+
+use std::fmt;
+
+use super::cmp::{conform, resolve_value_head, TypeError};
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::{AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, ProductValue, SumValue, Typespace, WithTypespace};
+
+/// A segment of a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The field of a product, or the variant of a sum, of this name.
+    Field(String),
+    /// The element of an array at this index.
+    Index(usize),
+}
+
+/// A path to a value nested in another, e.g., `user.addresses[0].zip`.
+///
+/// The empty path is that of the value itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FieldPath(pub Vec<PathSegment>);
+
+impl FieldPath {
+    /// Returns the empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the path extended with the field, or variant, `name`.
+    pub fn field(mut self, name: impl Into<String>) -> Self {
+        self.0.push(PathSegment::Field(name.into()));
+        self
+    }
+
+    /// Returns the path extended with the array index `index`.
+    pub fn index(mut self, index: usize) -> Self {
+        self.0.push(PathSegment::Index(index));
+        self
+    }
+}
+
+impl From<Vec<PathSegment>> for FieldPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_segments(&self.0, f)
+    }
+}
+
+/// Writes `segments` as the path, e.g., `user.addresses[0].zip`.
+fn fmt_segments(segments: &[PathSegment], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            PathSegment::Field(name) if i == 0 => f.write_str(name)?,
+            PathSegment::Field(name) => write!(f, ".{name}")?,
+            PathSegment::Index(index) => write!(f, "[{index}]")?,
+        }
+    }
+    Ok(())
+}
+
+/// Returns the first `len` segments of `path` as a string, for errors.
+fn path_prefix(path: &FieldPath, len: usize) -> String {
+    struct Prefix<'a>(&'a [PathSegment]);
+    impl fmt::Display for Prefix<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt_segments(self.0, f)
+        }
+    }
+    Prefix(&path.0[..len]).to_string()
+}
+
+/// An error following a [`FieldPath`] into a value.
+///
+/// The `path` of each error is the path up to and including the segment that could not be followed.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The type has no field or variant of the name.
+    #[error("No field or variant `{path}` in the type {ty}")]
+    UnknownField { path: String, ty: String },
+    /// A field name was applied to a type that isn't a product or sum, or an index to a type that isn't an array.
+    #[error("The path `{path}` does not apply to a value of the type {ty}")]
+    WrongKind { path: String, ty: String },
+    /// The index is past the end of the array.
+    #[error("The index of `{path}` is out of range for an array of length {len}")]
+    IndexOutOfRange { path: String, index: usize, len: usize },
+    /// The sum value holds another variant than the one named.
+    #[error("The sum value at `{path}` holds another variant")]
+    InactiveVariant { path: String },
+    /// The value is not stored as an `AlgebraicValue` in its array, so it can't be borrowed as one.
+    #[error("The value at `{path}` is stored unboxed in its array and can only be cloned")]
+    Unboxed { path: String },
+    /// A ref in a type doesn't resolve, a value along the path doesn't conform to its type,
+    /// or the value set doesn't conform to the type at the path.
+    #[error(transparent)]
+    Type(#[from] TypeError),
+}
+
+/// A segment of a path resolved against the type it applies to.
+struct Step<'t> {
+    /// The type of the value the segment applies to, with refs and newtypes resolved.
+    ty: &'t AlgebraicType,
+    /// What the segment selects in that value.
+    kind: StepKind,
+}
+
+/// What a segment of a path selects in a value.
+#[derive(Clone, Copy)]
+enum StepKind {
+    /// The field at this position in a product.
+    Field(usize),
+    /// The payload of a sum, if it has this tag.
+    Variant(u8),
+    /// The element at this index in an array.
+    Index(usize),
+}
+
+/// Resolves `path` against `schema` in `ts`, returning the steps it takes and the type at its end.
+fn resolve<'t>(
+    path: &FieldPath,
+    schema: &'t AlgebraicType,
+    ts: &'t Typespace,
+) -> Result<(Vec<Step<'t>>, WithTypespace<'t, AlgebraicType>), PathError> {
+    let mut ty = WithTypespace::new(ts, schema);
+    let mut steps = Vec::with_capacity(path.0.len());
+    for (i, segment) in path.0.iter().enumerate() {
+        let head = resolve_value_head(ty)?;
+        let unknown = || PathError::UnknownField {
+            path: path_prefix(path, i + 1),
+            ty: fmt_algebraic_type(head.ty()).to_string(),
+        };
+        let (kind, next) = match (segment, head.ty()) {
+            (PathSegment::Field(name), AlgebraicType::Product(pty)) => {
+                let pos = pty.elements.iter().position(|e| e.has_name(name)).ok_or_else(unknown)?;
+                (StepKind::Field(pos), &pty.elements[pos].algebraic_type)
+            }
+            (PathSegment::Field(name), AlgebraicType::Sum(sty)) => {
+                let pos = sty.variants.iter().position(|v| v.has_name(name)).ok_or_else(unknown)?;
+                (StepKind::Variant(pos as u8), &sty.variants[pos].algebraic_type)
+            }
+            (&PathSegment::Index(index), AlgebraicType::Builtin(BuiltinType::Array(aty))) => {
+                (StepKind::Index(index), &*aty.elem_ty)
+            }
+            (_, ty) => {
+                return Err(PathError::WrongKind {
+                    path: path_prefix(path, i + 1),
+                    ty: fmt_algebraic_type(ty).to_string(),
+                })
+            }
+        };
+        steps.push(Step { ty: head.ty(), kind });
+        ty = head.with(next);
+    }
+    Ok((steps, ty))
+}
+
+/// A value reached along a path, which, for an element of an array, might not be an `AlgebraicValue`.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Value(&'a AlgebraicValue),
+    Product(&'a ProductValue),
+    Sum(&'a SumValue),
+    Array(&'a ArrayValue),
+    /// The element at the index of an array of primitives, strings, or maps.
+    Unboxed(&'a ArrayValue, usize),
+}
+
+impl Node<'_> {
+    /// Returns a clone of the value.
+    fn to_value(self) -> AlgebraicValue {
+        match self {
+            Node::Value(val) => val.clone(),
+            Node::Product(val) => val.clone().into(),
+            Node::Sum(val) => AlgebraicValue::Sum(val.clone()),
+            Node::Array(val) => AlgebraicValue::Array(val.clone()),
+            Node::Unboxed(arr, index) => arr
+                .slice(index..index + 1)
+                .ok()
+                .and_then(|elem| elem.into_iter().next())
+                .expect("the index of an unboxed element is in bounds"),
+        }
+    }
+}
+
+/// Returns an error for `node` not conforming to the type `ty`.
+fn non_conforming(ty: &AlgebraicType, node: Node<'_>) -> PathError {
+    PathError::Type(TypeError::NonConforming {
+        value: format!("{:?}", node.to_value()),
+        ty: fmt_algebraic_type(ty).to_string(),
+    })
+}
+
+/// Returns an error if `index` is out of range for `arr`.
+fn check_index(path: &FieldPath, i: usize, arr: &ArrayValue, index: usize) -> Result<(), PathError> {
+    let len = arr.len();
+    if index < len {
+        return Ok(());
+    }
+    Err(PathError::IndexOutOfRange {
+        path: path_prefix(path, i + 1),
+        index,
+        len,
+    })
+}
+
+/// Follows `steps` of `path` into `root`.
+fn walk<'a>(root: &'a AlgebraicValue, path: &FieldPath, steps: &[Step<'_>]) -> Result<Node<'a>, PathError> {
+    let mut node = Node::Value(root);
+    for (i, step) in steps.iter().enumerate() {
+        node = match (step.kind, node) {
+            (StepKind::Field(pos), Node::Value(AlgebraicValue::Product(val)) | Node::Product(val))
+                if pos < val.elements.len() =>
+            {
+                Node::Value(&val.elements[pos])
+            }
+            (StepKind::Variant(tag), Node::Value(AlgebraicValue::Sum(val)) | Node::Sum(val)) => {
+                if val.tag != tag {
+                    return Err(PathError::InactiveVariant {
+                        path: path_prefix(path, i + 1),
+                    });
+                }
+                Node::Value(&val.value)
+            }
+            (StepKind::Index(index), Node::Value(AlgebraicValue::Array(arr)) | Node::Array(arr)) => {
+                check_index(path, i, arr, index)?;
+                match arr {
+                    ArrayValue::Sum(v) => Node::Sum(&v[index]),
+                    ArrayValue::Product(v) => Node::Product(&v[index]),
+                    ArrayValue::Array(v) => Node::Array(&v[index]),
+                    _ => Node::Unboxed(arr, index),
+                }
+            }
+            (_, node) => return Err(non_conforming(step.ty, node)),
+        };
+    }
+    Ok(node)
+}
+
+/// Returns the value at `path` in `root`, a value of the type `schema`, with refs resolved in `ts`.
+///
+/// A field segment selects the field of that name in a product,
+/// or the payload of the variant of that name in a sum, which must be the variant the sum holds.
+/// An index segment selects the element at that index in an array.
+///
+/// The elements of arrays of primitives, strings, and maps aren't stored as `AlgebraicValue`s,
+/// so a path to one of those is a [`PathError::Unboxed`]. See [`get_field_path_cloned`] for those.
+pub fn get_field_path<'a>(
+    root: &'a AlgebraicValue,
+    path: &FieldPath,
+    schema: &AlgebraicType,
+    ts: &Typespace,
+) -> Result<&'a AlgebraicValue, PathError> {
+    let (steps, _) = resolve(path, schema, ts)?;
+    match walk(root, path, &steps)? {
+        Node::Value(val) => Ok(val),
+        _ => Err(PathError::Unboxed { path: path.to_string() }),
+    }
+}
+
+/// Returns a clone of the value at `path` in `root`, as [`get_field_path`] does,
+/// but also for the elements of arrays that aren't stored as `AlgebraicValue`s.
+pub fn get_field_path_cloned(
+    root: &AlgebraicValue,
+    path: &FieldPath,
+    schema: &AlgebraicType,
+    ts: &Typespace,
+) -> Result<AlgebraicValue, PathError> {
+    let (steps, _) = resolve(path, schema, ts)?;
+    walk(root, path, &steps).map(Node::to_value)
+}
+
+/// A value reached along a path, mutably, as in [`Node`].
+enum NodeMut<'a> {
+    Value(&'a mut AlgebraicValue),
+    Product(&'a mut ProductValue),
+    Sum(&'a mut SumValue),
+    Array(&'a mut ArrayValue),
+}
+
+impl NodeMut<'_> {
+    /// Returns the value, immutably.
+    fn as_node(&self) -> Node<'_> {
+        match self {
+            NodeMut::Value(val) => Node::Value(val),
+            NodeMut::Product(val) => Node::Product(val),
+            NodeMut::Sum(val) => Node::Sum(val),
+            NodeMut::Array(val) => Node::Array(val),
+        }
+    }
+}
+
+/// Replaces the value at `path` in `root`, a value of the type `schema`, with refs resolved in `ts`, by `val`.
+///
+/// The path is followed as by [`get_field_path`], including into the elements of any array.
+/// `val` must conform to the type at the end of the path.
+/// On an error, `root` is left as it was.
+pub fn set_field_path(
+    root: &mut AlgebraicValue,
+    path: &FieldPath,
+    val: AlgebraicValue,
+    schema: &AlgebraicType,
+    ts: &Typespace,
+) -> Result<(), PathError> {
+    let (steps, ty) = resolve(path, schema, ts)?;
+    conform(ty, &val)?;
+    let mut node = NodeMut::Value(root);
+    for (i, step) in steps.iter().enumerate() {
+        node = match (step.kind, node) {
+            (StepKind::Field(pos), NodeMut::Value(AlgebraicValue::Product(prod)) | NodeMut::Product(prod)) => {
+                if pos >= prod.elements.len() {
+                    return Err(non_conforming(step.ty, Node::Product(prod)));
+                }
+                NodeMut::Value(&mut prod.elements[pos])
+            }
+            (StepKind::Variant(tag), NodeMut::Value(AlgebraicValue::Sum(sum)) | NodeMut::Sum(sum)) => {
+                if sum.tag != tag {
+                    return Err(PathError::InactiveVariant {
+                        path: path_prefix(path, i + 1),
+                    });
+                }
+                NodeMut::Value(&mut sum.value)
+            }
+            (StepKind::Index(index), NodeMut::Value(AlgebraicValue::Array(arr)) | NodeMut::Array(arr)) => {
+                check_index(path, i, arr, index)?;
+                match arr {
+                    ArrayValue::Sum(v) => NodeMut::Sum(&mut v[index]),
+                    ArrayValue::Product(v) => NodeMut::Product(&mut v[index]),
+                    ArrayValue::Array(v) => NodeMut::Array(&mut v[index]),
+                    // The other elements have nothing in them to follow a path into.
+                    _ if i + 1 == steps.len() => {
+                        return arr
+                            .set(index, val)
+                            .map_err(|_| non_conforming(step.ty, Node::Array(arr)))
+                    }
+                    _ => return Err(non_conforming(step.ty, Node::Array(arr))),
+                }
+            }
+            (_, node) => return Err(non_conforming(step.ty, node.as_node())),
+        };
+    }
+    match (node, val) {
+        (NodeMut::Value(old), val) => *old = val,
+        (NodeMut::Product(old), AlgebraicValue::Product(val)) => *old = val,
+        (NodeMut::Sum(old), AlgebraicValue::Sum(val)) => *old = val,
+        (NodeMut::Array(old), AlgebraicValue::Array(val)) => *old = val,
+        (old, _) => return Err(non_conforming(ty.ty(), old.as_node())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_type::builder::ProductTypeBuilder;
+    use crate::{product, AlgebraicTypeRef};
+
+    /// Returns a typespace with an address type, and a user type with addresses, a location and scores.
+    fn user_type() -> (Typespace, AlgebraicType) {
+        let address = ProductTypeBuilder::new()
+            .field("street", AlgebraicType::String)
+            .field("zip", AlgebraicType::String)
+            .build();
+        let ts = Typespace::new(vec![AlgebraicType::Product(address)]);
+        let geo = ProductTypeBuilder::new()
+            .field("lat", AlgebraicType::F64)
+            .field("lon", AlgebraicType::F64)
+            .build();
+        let location = ProductTypeBuilder::new()
+            .field("city", AlgebraicType::String)
+            .field("geo", AlgebraicType::Product(geo))
+            .build();
+        let user = ProductTypeBuilder::new()
+            .field("name", AlgebraicType::String)
+            .field(
+                "addresses",
+                AlgebraicType::array(AlgebraicType::Ref(AlgebraicTypeRef(0))),
+            )
+            .field("location", AlgebraicType::Product(location))
+            .field("scores", AlgebraicType::array(AlgebraicType::U32))
+            .field("nickname", AlgebraicType::option(AlgebraicType::String))
+            .build();
+        (ts, AlgebraicType::Product(user))
+    }
+
+    fn user() -> AlgebraicValue {
+        let address = |street: &str, zip: &str| product![street.to_owned(), zip.to_owned()];
+        let addresses = vec![address("1 Main St", "10001"), address("2 Side St", "94105")];
+        AlgebraicValue::Product(product![
+            "ana".to_owned(),
+            AlgebraicValue::ArrayOf(addresses),
+            product!["Lyon".to_owned(), product![45.76f64, 4.84f64]],
+            AlgebraicValue::ArrayOf(vec![3u32, 5, 8]),
+            AlgebraicValue::OptionNone()
+        ])
+    }
+
+    #[test]
+    fn get_nested_fields_and_elements() {
+        let (ts, ty) = user_type();
+        let user = user();
+        let get = |path: &FieldPath| get_field_path(&user, path, &ty, &ts);
+
+        let lat = FieldPath::new().field("location").field("geo").field("lat");
+        assert_eq!(lat.to_string(), "location.geo.lat");
+        assert_eq!(get(&lat), Ok(&AlgebraicValue::F64(45.76.into())));
+        let zip = FieldPath::new().field("addresses").index(1).field("zip");
+        assert_eq!(zip.to_string(), "addresses[1].zip");
+        assert_eq!(get(&zip), Ok(&AlgebraicValue::from("94105")));
+        assert_eq!(get(&FieldPath::new()), Ok(&user));
+        assert_eq!(
+            get(&FieldPath::new().field("nickname").field("none")),
+            Ok(&AlgebraicValue::UNIT)
+        );
+
+        // Elements of primitive arrays can only be cloned.
+        let score = FieldPath::new().field("scores").index(2);
+        let err = get(&score).unwrap_err();
+        assert_eq!(
+            err,
+            PathError::Unboxed {
+                path: "scores[2]".into()
+            }
+        );
+        assert_eq!(
+            get_field_path_cloned(&user, &score, &ty, &ts),
+            Ok(AlgebraicValue::U32(8))
+        );
+        let address = FieldPath::new().field("addresses").index(0);
+        let cloned = get_field_path_cloned(&user, &address, &ty, &ts).unwrap();
+        assert_eq!(
+            cloned.as_product().unwrap().elements[0],
+            AlgebraicValue::from("1 Main St")
+        );
+    }
+
+    #[test]
+    fn invalid_paths_are_errors() {
+        let (ts, ty) = user_type();
+        let user = user();
+        let get = |path: FieldPath| get_field_path(&user, &path, &ty, &ts).unwrap_err();
+
+        let err = get(FieldPath::new().field("addresses").index(2).field("zip"));
+        assert_eq!(
+            err,
+            PathError::IndexOutOfRange {
+                path: "addresses[2]".into(),
+                index: 2,
+                len: 2
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "The index of `addresses[2]` is out of range for an array of length 2"
+        );
+        let err = get(FieldPath::new().field("location").field("country"));
+        assert!(matches!(err, PathError::UnknownField { path, .. } if path == "location.country"));
+        let err = get(FieldPath::new().field("name").index(0));
+        assert!(matches!(err, PathError::WrongKind { path, ty } if path == "name[0]" && ty == "String"));
+        let err = get(FieldPath::new().field("nickname").field("some"));
+        assert_eq!(
+            err,
+            PathError::InactiveVariant {
+                path: "nickname.some".into()
+            }
+        );
+        // A value that doesn't conform to the schema.
+        let err = get_field_path(&AlgebraicValue::U8(1), &FieldPath::new().field("name"), &ty, &ts).unwrap_err();
+        assert!(matches!(err, PathError::Type(TypeError::NonConforming { .. })));
+    }
+
+    #[test]
+    fn set_nested_fields_and_elements() {
+        let (ts, ty) = user_type();
+        let mut user = user();
+        let mut set = |path: &FieldPath, val: AlgebraicValue| set_field_path(&mut user, path, val, &ty, &ts);
+
+        let zip = FieldPath::new().field("addresses").index(0).field("zip");
+        set(&zip, AlgebraicValue::from("10002")).unwrap();
+        let lon = FieldPath::new().field("location").field("geo").field("lon");
+        set(&lon, AlgebraicValue::F64(4.85.into())).unwrap();
+        let score = FieldPath::new().field("scores").index(1);
+        set(&score, AlgebraicValue::U32(13)).unwrap();
+        let nickname = FieldPath::new().field("nickname");
+        set(&nickname, AlgebraicValue::OptionSome(AlgebraicValue::from("an"))).unwrap();
+        set(&nickname.clone().field("some"), AlgebraicValue::from("ani")).unwrap();
+
+        // Values of other types, and paths past the end of an array, leave the value as it was.
+        assert!(matches!(
+            set(&zip, AlgebraicValue::U32(10002)),
+            Err(PathError::Type(TypeError::NonConforming { .. }))
+        ));
+        let past_end = FieldPath::new().field("scores").index(3);
+        assert!(matches!(
+            set(&past_end, AlgebraicValue::U32(0)),
+            Err(PathError::IndexOutOfRange { index: 3, len: 3, .. })
+        ));
+
+        let get = |path: &FieldPath| get_field_path_cloned(&user, path, &ty, &ts).unwrap();
+        assert_eq!(get(&zip), AlgebraicValue::from("10002"));
+        assert_eq!(get(&lon), AlgebraicValue::F64(4.85.into()));
+        assert_eq!(
+            get(&FieldPath::new().field("scores")),
+            AlgebraicValue::ArrayOf(vec![3u32, 13, 8])
+        );
+        assert_eq!(get(&nickname), AlgebraicValue::OptionSome(AlgebraicValue::from("ani")));
+    }
+}
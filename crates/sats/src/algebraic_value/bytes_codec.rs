@@ -0,0 +1,163 @@
+//! A column-oriented encoding of [`MapValue`]s.
+//!
+//! The BSATN encoding of a map interleaves its keys and values as `len, k0, v0, k1, v1, ..`.
+//! The columnar encoding instead writes `len, k0, k1, .., v0, v1, ..`,
+//! with each key and value encoded in BSATN and `len` a little-endian `u32`.
+//! The output is exactly as long as the BSATN encoding of the map, as it holds the same bytes in another order.
+//! Keys sit next to keys and values next to values, for compressors and other consumers that exploit that,
+//! but that doesn't make it compress better with the byte-oriented LZ4 or Snappy of
+//! `bsatn::compression`, under the `compress` feature.
+//! For 1000 keys like `player-0042` mapped to `u32`s, the columnar output compresses to 55% more with LZ4,
+//! as the zero high bytes of small values and the length prefix of the next key form long matches in BSATN,
+//! while a column of 4-byte integers hardly has any matches of LZ4's minimum length of 4 bytes.
+//! Only with values of random bytes does the columnar output compress to less, 8% less with LZ4.
+//! See the `columnar_map` benchmark.
+//!
+//! As a `MapValue` is ordered by key, the keys are always written in strictly ascending order,
+//! which the decoder insists on, so that each map has exactly one encoding.
+
+use crate::bsatn::{self, Deserializer};
+use crate::buffer::{BufReader, BufWriter, DecodeError, ErrorKind};
+use crate::de::DeserializeSeed;
+use crate::{AlgebraicType, AlgebraicValue, MapType, MapValue, Typespace, ValueWithType, WithTypespace};
+
+/// Encodes `val`, of type `ty` in the typespace `ts`, in the columnar encoding.
+///
+/// Panics if `val` does not match `ty`, or has more than `u32::MAX` entries.
+pub fn encode_map_columnar(val: &MapValue, ty: &MapType, ts: &Typespace) -> Vec<u8> {
+    let len = u32::try_from(val.len()).expect("map has more than `u32::MAX` entries");
+    let mut buf = Vec::new();
+    buf.put_u32(len);
+    let mut put_column = |col_ty: &AlgebraicType, col: &mut dyn Iterator<Item = &AlgebraicValue>| {
+        for elem in col {
+            let elem = ValueWithType::new(WithTypespace::new(ts, col_ty), elem);
+            bsatn::to_writer(&mut buf, &elem).expect("encoding into a `Vec` can't fail");
+        }
+    };
+    put_column(&ty.key_ty, &mut val.keys());
+    put_column(&ty.ty, &mut val.values());
+    buf
+}
+
+/// Decodes a map of type `ty` in the typespace `ts` from `bytes`, its columnar encoding in full.
+///
+/// Errors in the keys and values are reported at the paths `keys[i]` and `values[i]` respectively.
+pub fn decode_map_columnar(bytes: &[u8], ty: &MapType, ts: &Typespace) -> Result<MapValue, DecodeError> {
+    let reader = &mut &*bytes;
+    let len = reader.get_u32()?;
+    let keys = decode_column(reader, len, WithTypespace::new(ts, &ty.key_ty), (0, "keys"))?;
+    let values = decode_column(reader, len, WithTypespace::new(ts, &ty.ty), (1, "values"))?;
+    if !reader.is_empty() {
+        let err = ErrorKind::Custom(format!("{} bytes left over after the map", reader.len()));
+        return Err(DecodeError::from(err).with_excerpt(reader));
+    }
+
+    if let Some(idx) = keys.windows(2).position(|pair| pair[0] >= pair[1]) {
+        let err = ErrorKind::Custom("keys are not in strictly ascending order".into());
+        return Err(DecodeError::from(err).in_element(idx + 1).in_field(0, Some("keys")));
+    }
+    Ok(keys.into_iter().zip(values).collect())
+}
+
+/// Decodes `len` values of type `ty` from `reader`,
+/// reporting errors as occurring within the field `(index, name)`.
+fn decode_column<'de>(
+    reader: &mut impl BufReader<'de>,
+    len: u32,
+    ty: WithTypespace<'_, AlgebraicType>,
+    (index, name): (usize, &str),
+) -> Result<Vec<AlgebraicValue>, DecodeError> {
+    // Don't trust `len` for the capacity, as a corrupt one could be huge.
+    let mut column = Vec::new();
+    for i in 0..len as usize {
+        let elem = ty
+            .deserialize(Deserializer::new(reader))
+            .map_err(|err| err.in_element(i).in_field(index, Some(name)))?;
+        column.push(elem);
+    }
+    Ok(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, ProductTypeElement};
+
+    fn names_to_scores() -> (MapValue, MapType) {
+        let map = (0..1000u32)
+            .map(|i| (format!("player-{i:04}").into(), AlgebraicValue::U32(i * 7)))
+            .collect();
+        let ty = MapType::new(AlgebraicType::String, AlgebraicType::U32);
+        (map, ty)
+    }
+
+    #[test]
+    fn round_trip_is_byte_exact() {
+        let ts = Typespace::default();
+        let (map, ty) = names_to_scores();
+        let bytes = encode_map_columnar(&map, &ty, &ts);
+
+        // All the keys come first, then all the values.
+        assert_eq!(bytes[..4], 1000u32.to_le_bytes());
+        assert_eq!(bytes[4..8], 11u32.to_le_bytes());
+        assert_eq!(&bytes[8..19], b"player-0000");
+        assert_eq!(bytes[bytes.len() - 4..], (999u32 * 7).to_le_bytes());
+        // Just as long as the row-oriented BSATN encoding.
+        assert_eq!(bytes.len(), bsatn::to_vec(&map).unwrap().len());
+
+        let decoded = decode_map_columnar(&bytes, &ty, &ts).unwrap();
+        assert_eq!(decoded, map);
+        assert_eq!(encode_map_columnar(&decoded, &ty, &ts), bytes);
+
+        let empty = MapValue::new();
+        let bytes = encode_map_columnar(&empty, &ty, &ts);
+        assert_eq!(bytes, [0; 4]);
+        assert_eq!(decode_map_columnar(&bytes, &ty, &ts).unwrap(), empty);
+    }
+
+    #[test]
+    fn round_trip_through_refs() {
+        let mut ts = Typespace::default();
+        let point = ts.add(AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::I16, "x"),
+            ProductTypeElement::new_named(AlgebraicType::I16, "y"),
+        ]));
+        let ty = MapType::new(AlgebraicType::Ref(point), AlgebraicType::option(AlgebraicType::String));
+        let map = [
+            (product![1i16, -1i16].into(), AlgebraicValue::OptionSome("a".into())),
+            (product![0i16, 5i16].into(), AlgebraicValue::OptionNone()),
+        ]
+        .into();
+        let bytes = encode_map_columnar(&map, &ty, &ts);
+        let decoded = decode_map_columnar(&bytes, &ty, &ts).unwrap();
+        assert_eq!(decoded, map);
+        assert_eq!(encode_map_columnar(&decoded, &ty, &ts), bytes);
+    }
+
+    #[test]
+    fn rejects_invalid_encodings() {
+        let ts = Typespace::default();
+        let ty = MapType::new(AlgebraicType::U8, AlgebraicType::Bool);
+        let map = [(1u8.into(), true.into()), (2u8.into(), false.into())].into();
+        let bytes = encode_map_columnar(&map, &ty, &ts);
+        assert_eq!(bytes, [2, 0, 0, 0, 1, 2, 1, 0]);
+
+        let err = decode_map_columnar(&bytes[..7], &ty, &ts).unwrap_err();
+        assert_eq!(err.to_string(), "data too short: needed 1 bytes, had 0 in `values[1]`");
+        let err = decode_map_columnar(&[2, 0, 0, 0, 1, 2, 1, 3], &ty, &ts).unwrap_err();
+        assert!(err.to_string().contains(" in `values[1]`"), "{err}");
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        let err = decode_map_columnar(&extra, &ty, &ts).unwrap_err();
+        assert_eq!(err.to_string(), "1 bytes left over after the map (bytes: 00)");
+
+        // Keys out of order, or repeated, would not re-encode to the same bytes.
+        for keys in [[2, 1], [1, 1]] {
+            let err = decode_map_columnar(&[2, 0, 0, 0, keys[0], keys[1], 1, 0], &ty, &ts).unwrap_err();
+            assert_eq!(err.to_string(), "keys are not in strictly ascending order in `keys[1]`");
+        }
+        // A huge length with little data is just truncated.
+        assert!(decode_map_columnar(&[255, 255, 255, 255, 1], &ty, &ts).is_err());
+    }
+}
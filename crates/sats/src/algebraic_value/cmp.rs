@@ -0,0 +1,450 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::typespace::UnresolvedRef;
+use crate::{
+    AlgebraicType, AlgebraicTypeRef, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, MapType, MapValue,
+    ProductType, ProductValue, SumType, SumValue, ValueWithType, WithTypespace,
+};
+
+/// Compares the values `a` and `b`, both of the type `ty`, in the context of a typespace.
+///
+/// Products are compared lexicographically by their fields,
+/// sums first by their tags and then by their payloads,
+/// arrays lexicographically by their elements, and maps by their entries in key order.
+/// Any `AlgebraicType::Ref`s encountered along the way are resolved in the typespace,
+/// and newtypes are compared as the types they wrap.
+///
+/// Where the values do not fit the type `ty`,
+/// the comparison falls back to the structural `Ord for AlgebraicValue`.
+pub fn values_cmp(ty: WithTypespace<'_, AlgebraicType>, a: &AlgebraicValue, b: &AlgebraicValue) -> Ordering {
+    match (ty.ty(), a, b) {
+        (AlgebraicType::Ref(r), _, _) => values_cmp(ty.resolve(*r), a, b),
+        (AlgebraicType::Newtype(nt), _, _) => values_cmp(ty.with(&*nt.inner), a, b),
+        (AlgebraicType::Product(pty), AlgebraicValue::Product(a), AlgebraicValue::Product(b)) => {
+            products_cmp(ty.with(pty), a, b)
+        }
+        (AlgebraicType::Sum(sty), AlgebraicValue::Sum(a), AlgebraicValue::Sum(b)) => sums_cmp(ty.with(sty), a, b),
+        (AlgebraicType::Builtin(BuiltinType::Array(aty)), AlgebraicValue::Array(a), AlgebraicValue::Array(b)) => {
+            arrays_cmp(ty.with(aty), a, b)
+        }
+        (AlgebraicType::Builtin(BuiltinType::Map(mty)), AlgebraicValue::Map(a), AlgebraicValue::Map(b)) => {
+            maps_cmp(ty.with(mty), a, b)
+        }
+        _ => a.cmp(b),
+    }
+}
+
+/// Compares the products `a` and `b` of the type `ty`, per [`values_cmp`].
+pub(crate) fn products_cmp(ty: WithTypespace<'_, ProductType>, a: &ProductValue, b: &ProductValue) -> Ordering {
+    let pty = ty.ty();
+    if pty.elements.len() != a.elements.len() || pty.elements.len() != b.elements.len() {
+        return a.cmp(b);
+    }
+    pty.elements
+        .iter()
+        .zip(a.elements.iter().zip(&b.elements))
+        .map(|(e, (a, b))| values_cmp(ty.with(&e.algebraic_type), a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compares the sums `a` and `b` of the type `ty`, per [`values_cmp`].
+pub(crate) fn sums_cmp(ty: WithTypespace<'_, SumType>, a: &SumValue, b: &SumValue) -> Ordering {
+    match a.tag.cmp(&b.tag) {
+        Ordering::Equal => match ty.ty().variants.get(a.tag as usize) {
+            Some(var) => values_cmp(ty.with(&var.algebraic_type), &a.value, &b.value),
+            None => a.value.cmp(&b.value),
+        },
+        ord => ord,
+    }
+}
+
+/// Compares the arrays `a` and `b` of the type `ty`, per [`values_cmp`].
+pub(crate) fn arrays_cmp(ty: WithTypespace<'_, ArrayType>, a: &ArrayValue, b: &ArrayValue) -> Ordering {
+    let elem_ty = ty.with(&*ty.ty().elem_ty);
+    cmp_by(a.iter_cloned(), b.iter_cloned(), |a, b| values_cmp(elem_ty, a, b))
+}
+
+/// Compares the maps `a` and `b` of the type `ty`, per [`values_cmp`].
+pub(crate) fn maps_cmp(ty: WithTypespace<'_, MapType>, a: &MapValue, b: &MapValue) -> Ordering {
+    let (key_ty, val_ty) = (ty.with(&*ty.ty().key_ty), ty.with(&*ty.ty().ty));
+    cmp_by(a.iter(), b.iter(), |(ak, av), (bk, bv)| {
+        values_cmp(key_ty, ak, bk).then_with(|| values_cmp(val_ty, av, bv))
+    })
+}
+
+/// Lexicographically compares the sequences `a` and `b` using `cmp` for the elements.
+fn cmp_by<T>(
+    mut a: impl Iterator<Item = T>,
+    mut b: impl Iterator<Item = T>,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> Ordering {
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match cmp(&x, &y) {
+                Ordering::Equal => {}
+                ord => return ord,
+            },
+        }
+    }
+}
+
+/// An error comparing two [`ValueWithType`]s that aren't of the same type,
+/// or where a value doesn't conform to its type.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TypeError {
+    /// The two sides are of structurally different types.
+    #[error("Cannot compare values of the different types {left} and {right}")]
+    Mismatch { left: String, right: String },
+    /// A value does not conform to its type.
+    #[error("The value {value} does not conform to its type {ty}")]
+    NonConforming { value: String, ty: String },
+    /// A `Ref` in a type is not in its typespace, or only refers to other `Ref`s in a cycle.
+    #[error("The type ref {0} does not resolve in its typespace")]
+    UnresolvedRef(AlgebraicTypeRef),
+}
+
+impl ValueWithType<'_, AlgebraicValue> {
+    /// Returns whether `self` and `other` are equal, per [`cmp_typed`](Self::cmp_typed).
+    pub fn eq_typed(&self, other: &ValueWithType<'_, AlgebraicValue>) -> Result<bool, TypeError> {
+        self.cmp_typed(other).map(Ordering::is_eq)
+    }
+
+    /// Compares `self` and `other` at their type,
+    /// after checking that their types are structurally the same, through `Ref`s in their typespaces,
+    /// and that the values conform to them.
+    ///
+    /// The order is that of [`values_cmp`].
+    /// Floats are compared by their total order,
+    /// in which all NaNs are equal to each other and greater than any other float.
+    /// Empty arrays are always equal, whichever kind of element they were built with,
+    /// e.g., `AlgebraicValue::ArrayOf(Vec::<u8>::new())` and `AlgebraicValue::ArrayOf(Vec::<ProductValue>::new())`.
+    ///
+    /// Mismatched types, or non-conforming values, are an error rather than unequal.
+    pub fn cmp_typed(&self, other: &ValueWithType<'_, AlgebraicValue>) -> Result<Ordering, TypeError> {
+        let (left, right) = (self.ty, other.ty);
+        if !same_type(left, right, &mut Vec::new())? {
+            return Err(TypeError::Mismatch {
+                left: fmt_algebraic_type(left.ty()).to_string(),
+                right: fmt_algebraic_type(right.ty()).to_string(),
+            });
+        }
+        conform(left, self.value())?;
+        conform(right, other.value())?;
+        Ok(values_cmp(left, self.value(), other.value()))
+    }
+}
+
+/// Returns `ty` with all the `Ref`s at its head resolved in its typespace.
+fn resolve_head(ty: WithTypespace<'_, AlgebraicType>) -> Result<WithTypespace<'_, AlgebraicType>, TypeError> {
+    let head = ty.typespace().resolve_head(ty.ty());
+    head.map(|head| ty.with(head))
+        .map_err(|UnresolvedRef(r)| TypeError::UnresolvedRef(r))
+}
+
+/// Returns whether `a` and `b` are structurally the same type,
+/// i.e., equal after replacing all `Ref`s by the types they refer to,
+/// assuming the pairs of refs in `assumed` to be the same already.
+fn same_type(
+    a: WithTypespace<'_, AlgebraicType>,
+    b: WithTypespace<'_, AlgebraicType>,
+    assumed: &mut Vec<(AlgebraicTypeRef, AlgebraicTypeRef)>,
+) -> Result<bool, TypeError> {
+    let all_same = |assumed: &mut _, pairs: Vec<(&AlgebraicType, &AlgebraicType)>| {
+        for (x, y) in pairs {
+            if !same_type(a.with(x), b.with(y), assumed)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    };
+    Ok(match (a.ty(), b.ty()) {
+        // Recursive types are the same if they are the same assuming their refs are the same.
+        (&AlgebraicType::Ref(ra), &AlgebraicType::Ref(rb)) => {
+            if assumed.contains(&(ra, rb)) {
+                return Ok(true);
+            }
+            assumed.push((ra, rb));
+            let (ta, tb) = (a.typespace().get(ra), b.typespace().get(rb));
+            let ta = ta.ok_or(TypeError::UnresolvedRef(ra))?;
+            let tb = tb.ok_or(TypeError::UnresolvedRef(rb))?;
+            same_type(a.with(ta), b.with(tb), assumed)?
+        }
+        (AlgebraicType::Ref(_), _) => same_type(resolve_head(a)?, b, assumed)?,
+        (_, AlgebraicType::Ref(_)) => same_type(a, resolve_head(b)?, assumed)?,
+        (AlgebraicType::Sum(x), AlgebraicType::Sum(y)) => {
+            x.variants.len() == y.variants.len()
+                && x.variants.iter().zip(&y.variants).all(|(x, y)| x.name == y.name)
+                && all_same(
+                    assumed,
+                    (x.variants.iter().zip(&y.variants))
+                        .map(|(x, y)| (&x.algebraic_type, &y.algebraic_type))
+                        .collect(),
+                )?
+        }
+        (AlgebraicType::Product(x), AlgebraicType::Product(y)) => {
+            x.elements.len() == y.elements.len()
+                && x.elements.iter().zip(&y.elements).all(|(x, y)| x.name == y.name)
+                && all_same(
+                    assumed,
+                    (x.elements.iter().zip(&y.elements))
+                        .map(|(x, y)| (&x.algebraic_type, &y.algebraic_type))
+                        .collect(),
+                )?
+        }
+        (AlgebraicType::Builtin(BuiltinType::Array(x)), AlgebraicType::Builtin(BuiltinType::Array(y))) => {
+            same_type(a.with(&*x.elem_ty), b.with(&*y.elem_ty), assumed)?
+        }
+        (AlgebraicType::Builtin(BuiltinType::Map(x)), AlgebraicType::Builtin(BuiltinType::Map(y))) => {
+            all_same(assumed, vec![(&*x.key_ty, &*y.key_ty), (&*x.ty, &*y.ty)])?
+        }
+        (AlgebraicType::Newtype(x), AlgebraicType::Newtype(y)) => {
+            x.name == y.name && same_type(a.with(&*x.inner), b.with(&*y.inner), assumed)?
+        }
+        (x, y) => x == y,
+    })
+}
+
+/// Returns `ty` with all the `Ref`s and newtypes at its head resolved.
+pub(crate) fn resolve_value_head(
+    ty: WithTypespace<'_, AlgebraicType>,
+) -> Result<WithTypespace<'_, AlgebraicType>, TypeError> {
+    let head = ty.typespace().resolve_value_head(ty.ty());
+    head.map(|head| ty.with(head))
+        .map_err(|UnresolvedRef(r)| TypeError::UnresolvedRef(r))
+}
+
+/// Returns an error for `val` not conforming to `ty`.
+fn non_conforming(val: &impl fmt::Debug, ty: &AlgebraicType) -> TypeError {
+    TypeError::NonConforming {
+        value: format!("{val:?}"),
+        ty: fmt_algebraic_type(ty).to_string(),
+    }
+}
+
+/// Checks that `val` conforms to the type `ty`.
+pub(crate) fn conform(ty: WithTypespace<'_, AlgebraicType>, val: &AlgebraicValue) -> Result<(), TypeError> {
+    let ty = resolve_value_head(ty)?;
+    let conforms = match (ty.ty(), val) {
+        (AlgebraicType::Sum(sty), AlgebraicValue::Sum(val)) => return conform_sum(ty.with(sty), val),
+        (AlgebraicType::Product(pty), AlgebraicValue::Product(val)) => return conform_product(ty.with(pty), val),
+        (AlgebraicType::Builtin(BuiltinType::Array(aty)), AlgebraicValue::Array(val)) => {
+            return conform_array(ty.with(aty), val)
+        }
+        (AlgebraicType::Builtin(BuiltinType::Map(mty)), AlgebraicValue::Map(val)) => {
+            return conform_map(ty.with(mty), val)
+        }
+        (AlgebraicType::Builtin(b), _) => matches!(
+            (b, val),
+            (BuiltinType::Bool, AlgebraicValue::Bool(_))
+                | (BuiltinType::I8, AlgebraicValue::I8(_))
+                | (BuiltinType::U8, AlgebraicValue::U8(_))
+                | (BuiltinType::I16, AlgebraicValue::I16(_))
+                | (BuiltinType::U16, AlgebraicValue::U16(_))
+                | (BuiltinType::I32, AlgebraicValue::I32(_))
+                | (BuiltinType::U32, AlgebraicValue::U32(_))
+                | (BuiltinType::I64, AlgebraicValue::I64(_))
+                | (BuiltinType::U64, AlgebraicValue::U64(_))
+                | (BuiltinType::I128, AlgebraicValue::I128(_))
+                | (BuiltinType::U128, AlgebraicValue::U128(_))
+                | (BuiltinType::F32, AlgebraicValue::F32(_))
+                | (BuiltinType::F64, AlgebraicValue::F64(_))
+                | (BuiltinType::String, AlgebraicValue::String(_))
+        ),
+        _ => false,
+    };
+    conforms.then_some(()).ok_or_else(|| non_conforming(val, ty.ty()))
+}
+
+/// Checks that the sum `val` conforms to the type `ty`.
+fn conform_sum(ty: WithTypespace<'_, SumType>, val: &SumValue) -> Result<(), TypeError> {
+    match ty.ty().variants.get(val.tag as usize) {
+        Some(var) => conform(ty.with(&var.algebraic_type), &val.value),
+        None => Err(non_conforming(val, &AlgebraicType::Sum(ty.ty().clone()))),
+    }
+}
+
+/// Checks that the product `val` conforms to the type `ty`.
+fn conform_product(ty: WithTypespace<'_, ProductType>, val: &ProductValue) -> Result<(), TypeError> {
+    let elems = &ty.ty().elements;
+    if elems.len() != val.elements.len() {
+        return Err(non_conforming(val, &AlgebraicType::Product(ty.ty().clone())));
+    }
+    (elems.iter().zip(&val.elements)).try_for_each(|(el, val)| conform(ty.with(&el.algebraic_type), val))
+}
+
+/// Checks that the elements of the array `val` conform to the element type of `ty`.
+///
+/// Empty arrays conform to any array type, whichever kind of element they were built with.
+fn conform_array(ty: WithTypespace<'_, ArrayType>, val: &ArrayValue) -> Result<(), TypeError> {
+    let elem_ty = resolve_value_head(ty.with(&*ty.ty().elem_ty))?;
+    let conforms = match (elem_ty.ty(), val) {
+        _ if val.is_empty() => true,
+        (AlgebraicType::Sum(sty), ArrayValue::Sum(vals)) => {
+            return vals.iter().try_for_each(|v| conform_sum(elem_ty.with(sty), v))
+        }
+        (AlgebraicType::Product(pty), ArrayValue::Product(vals)) => {
+            return vals.iter().try_for_each(|v| conform_product(elem_ty.with(pty), v))
+        }
+        (AlgebraicType::Builtin(BuiltinType::Array(aty)), ArrayValue::Array(vals)) => {
+            return vals.iter().try_for_each(|v| conform_array(elem_ty.with(aty), v))
+        }
+        (AlgebraicType::Builtin(BuiltinType::Map(mty)), ArrayValue::Map(vals)) => {
+            return vals.iter().try_for_each(|v| conform_map(elem_ty.with(mty), v))
+        }
+        (AlgebraicType::Builtin(b), _) => matches!(
+            (b, val),
+            (BuiltinType::Bool, ArrayValue::Bool(_))
+                | (BuiltinType::I8, ArrayValue::I8(_))
+                | (BuiltinType::U8, ArrayValue::U8(_))
+                | (BuiltinType::I16, ArrayValue::I16(_))
+                | (BuiltinType::U16, ArrayValue::U16(_))
+                | (BuiltinType::I32, ArrayValue::I32(_))
+                | (BuiltinType::U32, ArrayValue::U32(_))
+                | (BuiltinType::I64, ArrayValue::I64(_))
+                | (BuiltinType::U64, ArrayValue::U64(_))
+                | (BuiltinType::I128, ArrayValue::I128(_))
+                | (BuiltinType::U128, ArrayValue::U128(_))
+                | (BuiltinType::F32, ArrayValue::F32(_))
+                | (BuiltinType::F64, ArrayValue::F64(_))
+                | (BuiltinType::String, ArrayValue::String(_) | ArrayValue::StringPacked(_))
+        ),
+        _ => false,
+    };
+    let array_ty = || AlgebraicType::Builtin(BuiltinType::Array(ty.ty().clone()));
+    conforms.then_some(()).ok_or_else(|| non_conforming(val, &array_ty()))
+}
+
+/// Checks that the keys and values of the map `val` conform to the key and value types of `ty`.
+fn conform_map(ty: WithTypespace<'_, MapType>, val: &MapValue) -> Result<(), TypeError> {
+    let (key_ty, val_ty) = (ty.with(&*ty.ty().key_ty), ty.with(&*ty.ty().ty));
+    val.iter()
+        .try_for_each(|(k, v)| conform(key_ty, k).and_then(|()| conform(val_ty, v)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, ProductTypeElement, SumTypeVariant, Typespace};
+
+    fn cmp(ty: &AlgebraicType, a: &AlgebraicValue, b: &AlgebraicValue) -> Result<Ordering, TypeError> {
+        let ts = Typespace::default();
+        let ty = WithTypespace::new(&ts, ty);
+        ty.with_value(a).cmp_typed(&ty.with_value(b))
+    }
+
+    #[test]
+    fn sums_compare_payloads_after_tags() {
+        let ty = AlgebraicType::sum(vec![
+            SumTypeVariant::new_named(AlgebraicType::U32, "small"),
+            SumTypeVariant::new_named(AlgebraicType::String, "big"),
+        ]);
+        let small = |v: u32| AlgebraicValue::sum(0, v.into());
+        let big = |v: &str| AlgebraicValue::sum(1, v.into());
+        assert_eq!(cmp(&ty, &small(9), &big("a")), Ok(Ordering::Less));
+        assert_eq!(cmp(&ty, &small(9), &small(10)), Ok(Ordering::Less));
+        assert_eq!(cmp(&ty, &big("b"), &big("a")), Ok(Ordering::Greater));
+        // A payload not of its variant's type doesn't conform.
+        let err = cmp(&ty, &small(1), &AlgebraicValue::sum(0, "x".into())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"The value String("x") does not conform to its type U32"#
+        );
+        let err = cmp(&ty, &small(1), &AlgebraicValue::sum(2, "x".into())).unwrap_err();
+        assert!(matches!(err, TypeError::NonConforming { .. }), "{err}");
+    }
+
+    #[test]
+    fn floats_and_empty_arrays() {
+        let f64s = |v: f64| AlgebraicValue::F64(v.into());
+        assert_eq!(
+            cmp(&AlgebraicType::F64, &f64s(f64::NAN), &f64s(-f64::NAN)),
+            Ok(Ordering::Equal)
+        );
+        assert_eq!(
+            cmp(&AlgebraicType::F64, &f64s(f64::INFINITY), &f64s(f64::NAN)),
+            Ok(Ordering::Less)
+        );
+        assert_eq!(cmp(&AlgebraicType::F64, &f64s(-1.5), &f64s(1.0)), Ok(Ordering::Less));
+
+        // Empty arrays are equal however they were built, unlike in `Ord for AlgebraicValue`.
+        let ty = AlgebraicType::array(AlgebraicType::product(vec![AlgebraicType::U8.into()]));
+        let empty_products = AlgebraicValue::ArrayOf(Vec::<ProductValue>::new());
+        let empty_bytes = AlgebraicValue::Bytes(Vec::new());
+        assert_ne!(empty_products, empty_bytes);
+        assert_eq!(cmp(&ty, &empty_products, &empty_bytes), Ok(Ordering::Equal));
+        let ts = Typespace::default();
+        let ty = WithTypespace::new(&ts, &ty);
+        assert_eq!(
+            ty.with_value(&empty_products).eq_typed(&ty.with_value(&empty_bytes)),
+            Ok(true)
+        );
+        // But a non-empty array must have elements of its type.
+        let err = ty
+            .with_value(&empty_products)
+            .eq_typed(&ty.with_value(&AlgebraicValue::Bytes(vec![1])))
+            .unwrap_err();
+        assert!(matches!(err, TypeError::NonConforming { .. }), "{err}");
+    }
+
+    #[test]
+    fn types_must_be_the_same() {
+        // The same recursive list type, at different refs in different typespaces.
+        let list = |r| {
+            AlgebraicType::option(AlgebraicType::product(vec![
+                ProductTypeElement::new_named(AlgebraicType::U8, "head"),
+                ProductTypeElement::new_named(AlgebraicType::Ref(r), "tail"),
+            ]))
+        };
+        let left_ts = Typespace::new(vec![list(AlgebraicTypeRef(0))]);
+        let right_ts = Typespace::new(vec![AlgebraicType::String, list(AlgebraicTypeRef(1))]);
+        let (left_ty, right_ty) = (AlgebraicType::Ref(AlgebraicTypeRef(0)), list(AlgebraicTypeRef(1)));
+        let (left, right) = (
+            WithTypespace::new(&left_ts, &left_ty),
+            WithTypespace::new(&right_ts, &right_ty),
+        );
+
+        let cons = |head: u8, tail| AlgebraicValue::OptionSome(product![head, tail].into());
+        let one_two = cons(1, cons(2, AlgebraicValue::OptionNone()));
+        let one = cons(1, AlgebraicValue::OptionNone());
+        // The tails differ, and `some` has the lower tag.
+        assert_eq!(
+            left.with_value(&one_two).cmp_typed(&right.with_value(&one)),
+            Ok(Ordering::Less)
+        );
+        assert_eq!(left.with_value(&one).eq_typed(&right.with_value(&one)), Ok(true));
+
+        // A different field name makes for a different type, so the values can't be compared.
+        let renamed = AlgebraicType::option(AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U8, "first"),
+            ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(1)), "tail"),
+        ]));
+        let err = left
+            .with_value(&one)
+            .eq_typed(&WithTypespace::new(&right_ts, &renamed).with_value(&one))
+            .unwrap_err();
+        assert!(matches!(err, TypeError::Mismatch { .. }), "{err}");
+        assert_eq!(cmp(&AlgebraicType::U8, &1u8.into(), &1u8.into()), Ok(Ordering::Equal));
+        let ts = Typespace::default();
+        let err = WithTypespace::new(&ts, &AlgebraicType::U8)
+            .with_value(&1u8.into())
+            .eq_typed(&WithTypespace::new(&ts, &AlgebraicType::U16).with_value(&1u16.into()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot compare values of the different types U8 and U16"
+        );
+
+        let dangling = AlgebraicType::Ref(AlgebraicTypeRef(3));
+        let err = WithTypespace::new(&ts, &dangling)
+            .with_value(&1u8.into())
+            .eq_typed(&WithTypespace::new(&ts, &AlgebraicType::U8).with_value(&1u8.into()))
+            .unwrap_err();
+        assert_eq!(err, TypeError::UnresolvedRef(AlgebraicTypeRef(3)));
+    }
+}
@@ -0,0 +1,547 @@
+//! Coercion of values to a target type, e.g., to bind the literals of a SQL query to the columns they're compared with.
+//!
+//! Literals come as integers, floats, or strings of whatever type was convenient to parse them as,
+//! while the columns may be of any numeric type, so [`bind`] converts between them by one matrix:
+//!
+//! | From \ To      | Integer                 | Float                   | Other          |
+//! |----------------|-------------------------|-------------------------|----------------|
+//! | Integer        | if in range             | if exact [^inexact]     | error          |
+//! | Float          | if exact [^inexact]     | if exact [^inexact]     | error          |
+//! | Numeric string | if parsing [^strings]   | if parsing [^strings]   | error          |
+//! | Other          | error                   | error                   | if same kind   |
+//!
+//! [^inexact]: With [`CoerceOptions::allow_inexact`], floats are truncated toward zero when converted to integers,
+//!     and values are rounded to the nearest float when converted to floats, rather than being rejected.
+//!     A value out of the range of the target is rejected either way.
+//!
+//! [^strings]: Strings of numbers are only parsed, as integers or floats, with [`CoerceOptions::parse_strings`],
+//!     and are then converted as the number they hold.
+//!
+//! Values of other kinds, i.e., bools, strings, sums, products, and maps, are only bound to types of the same kind,
+//! and are kept as they are.
+//! Arrays are coerced element by element to the element type of an array target.
+//! Targets that are `Ref`s are resolved in the typespace, and newtypes are coerced to as the types they wrap.
+//!
+//! Maps can be probed with keys of another numeric kind than theirs through [`CoercedLookup`].
+
+use std::fmt;
+
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::typespace::UnresolvedRef;
+use crate::{AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, MapValue, Typespace};
+
+/// Options for [`bind_with`], by default those of [`bind`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoerceOptions {
+    /// Whether conversions between integers and floats may lose precision, by rounding or truncation.
+    pub allow_inexact: bool,
+    /// Whether strings of numbers may be bound to numeric types.
+    pub parse_strings: bool,
+}
+
+/// An error coercing a value to a target type.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CoerceError {
+    /// The value is of a kind that can't be coerced to the target at all.
+    #[error("Can't coerce a {from} value to {to}")]
+    Mismatch { from: &'static str, to: String },
+    /// The number is outside of the range of the numeric target.
+    #[error("The number {value} is out of range for {to}")]
+    OutOfRange { value: String, to: String },
+    /// The number can't be represented exactly by the numeric target.
+    #[error("The number {value} can't be represented exactly as {to}")]
+    Inexact { value: String, to: String },
+    /// The string to parse for a numeric target is not a number.
+    #[error("The string {value:?} is not a number to coerce to {to}")]
+    NotANumber { value: String, to: String },
+    /// The target refers to a type not in the typespace, or only to refs in a cycle.
+    #[error("The type ref &{0} is not in the typespace")]
+    UnresolvedRef(u32),
+    /// An element of an array couldn't be coerced to the element type.
+    #[error("Element {index}: {source}")]
+    Element { index: usize, source: Box<CoerceError> },
+}
+
+/// Coerces `value` to the type `target`, with the `Ref`s in it resolved in `ts`, by the matrix of the [module](self),
+/// rejecting inexact conversions and strings for numeric types.
+pub fn bind(value: AlgebraicValue, target: &AlgebraicType, ts: &Typespace) -> Result<AlgebraicValue, CoerceError> {
+    bind_with(value, target, ts, CoerceOptions::default())
+}
+
+/// Coerces `value` to the type `target`, as [`bind`] does, but with the `options`.
+pub fn bind_with<'a>(
+    value: AlgebraicValue,
+    target: &'a AlgebraicType,
+    ts: &'a Typespace,
+    options: CoerceOptions,
+) -> Result<AlgebraicValue, CoerceError> {
+    let target = ts
+        .resolve_value_head(target)
+        .map_err(|UnresolvedRef(r)| CoerceError::UnresolvedRef(r.0))?;
+    let mismatch = |value: &AlgebraicValue| CoerceError::Mismatch {
+        from: value.type_name(),
+        to: fmt_algebraic_type(target).to_string(),
+    };
+
+    let builtin = match target {
+        AlgebraicType::Builtin(builtin) => builtin,
+        AlgebraicType::Sum(_) if value.is_sum() => return Ok(value),
+        AlgebraicType::Product(_) if value.is_product() => return Ok(value),
+        _ => return Err(mismatch(&value)),
+    };
+    match builtin {
+        BuiltinType::Bool if value.is_bool() => return Ok(value),
+        BuiltinType::String if value.is_string() => return Ok(value),
+        BuiltinType::Map(_) if value.is_map() => return Ok(value),
+        BuiltinType::Array(ty) => {
+            let array = value.into_array().map_err(|value| mismatch(&value))?;
+            return bind_array(array, &ty.elem_ty, ts, options).map(AlgebraicValue::Array);
+        }
+        _ => {}
+    }
+
+    let num = match &value {
+        AlgebraicValue::String(s) if options.parse_strings => Num::parse(s).ok_or_else(|| CoerceError::NotANumber {
+            value: s.to_string(),
+            to: fmt_algebraic_type(target).to_string(),
+        })?,
+        _ => Num::of(&value).ok_or_else(|| mismatch(&value))?,
+    };
+    let err = |failure| {
+        let (value, to) = (num.to_string(), fmt_algebraic_type(target).to_string());
+        match failure {
+            Failure::OutOfRange => CoerceError::OutOfRange { value, to },
+            Failure::Inexact => CoerceError::Inexact { value, to },
+        }
+    };
+    let inexact = options.allow_inexact;
+    Ok(match builtin {
+        BuiltinType::I8 => AlgebraicValue::I8(num.to_int(inexact).map_err(err)?),
+        BuiltinType::U8 => AlgebraicValue::U8(num.to_int(inexact).map_err(err)?),
+        BuiltinType::I16 => AlgebraicValue::I16(num.to_int(inexact).map_err(err)?),
+        BuiltinType::U16 => AlgebraicValue::U16(num.to_int(inexact).map_err(err)?),
+        BuiltinType::I32 => AlgebraicValue::I32(num.to_int(inexact).map_err(err)?),
+        BuiltinType::U32 => AlgebraicValue::U32(num.to_int(inexact).map_err(err)?),
+        BuiltinType::I64 => AlgebraicValue::I64(num.to_int(inexact).map_err(err)?),
+        BuiltinType::U64 => AlgebraicValue::U64(num.to_int(inexact).map_err(err)?),
+        BuiltinType::I128 => AlgebraicValue::I128(num.to_int(inexact).map_err(err)?),
+        BuiltinType::U128 => AlgebraicValue::U128(num.to_int(inexact).map_err(err)?),
+        BuiltinType::F32 => AlgebraicValue::F32(num.to_f32(inexact).map_err(err)?.into()),
+        BuiltinType::F64 => AlgebraicValue::F64(num.to_f64(inexact).map_err(err)?.into()),
+        _ => return Err(mismatch(&value)),
+    })
+}
+
+/// Coerces the elements of `array` to `elem_ty`.
+fn bind_array(
+    array: ArrayValue,
+    elem_ty: &AlgebraicType,
+    ts: &Typespace,
+    options: CoerceOptions,
+) -> Result<ArrayValue, CoerceError> {
+    let len = array.len();
+    let mut out = ArrayValue::default();
+    for (index, elem) in array.into_iter().enumerate() {
+        let elem = bind_with(elem, elem_ty, ts, options).map_err(|source| CoerceError::Element {
+            index,
+            source: Box::new(source),
+        })?;
+        out.push(elem, Some(len))
+            .expect("elements coerced to the same type should be of the same kind");
+    }
+    Ok(out)
+}
+
+/// A number of any numeric kind, widened so that every value of that kind fits.
+#[derive(Clone, Copy)]
+enum Num {
+    Int(i128),
+    /// An integer too large for an `i128`.
+    BigUint(u128),
+    Float(f64),
+}
+
+/// Why a [`Num`] can't be converted to a numeric type.
+enum Failure {
+    OutOfRange,
+    Inexact,
+}
+
+impl Num {
+    /// Returns the number in `value`, if it is one.
+    fn of(value: &AlgebraicValue) -> Option<Self> {
+        Some(match *value {
+            AlgebraicValue::I8(x) => Self::Int(x.into()),
+            AlgebraicValue::U8(x) => Self::Int(x.into()),
+            AlgebraicValue::I16(x) => Self::Int(x.into()),
+            AlgebraicValue::U16(x) => Self::Int(x.into()),
+            AlgebraicValue::I32(x) => Self::Int(x.into()),
+            AlgebraicValue::U32(x) => Self::Int(x.into()),
+            AlgebraicValue::I64(x) => Self::Int(x.into()),
+            AlgebraicValue::U64(x) => Self::Int(x.into()),
+            AlgebraicValue::I128(x) => Self::Int(x),
+            AlgebraicValue::U128(x) => i128::try_from(x).map_or(Self::BigUint(x), Self::Int),
+            AlgebraicValue::F32(x) => Self::Float(f32::from(x).into()),
+            AlgebraicValue::F64(x) => Self::Float(x.into()),
+            _ => return None,
+        })
+    }
+
+    /// Parses `s` as an integer, or failing that, as a float.
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        (s.parse().ok().map(Self::Int))
+            .or_else(|| s.parse().ok().map(Self::BigUint))
+            .or_else(|| s.parse().ok().map(Self::Float))
+    }
+
+    /// Converts the number to the integer type `T`, truncating floats toward zero if `inexact`.
+    fn to_int<T: TryFrom<i128> + TryFrom<u128>>(self, inexact: bool) -> Result<T, Failure> {
+        /// `2^127`, the least float beyond the range of an `i128`.
+        const I128_END: f64 = 170141183460469231731687303715884105728.0;
+        let int = match self {
+            Self::Int(x) => T::try_from(x).ok(),
+            Self::BigUint(x) => T::try_from(x).ok(),
+            Self::Float(x) if !x.is_finite() => None,
+            Self::Float(x) if x.trunc() != x && !inexact => return Err(Failure::Inexact),
+            // The casts are exact, as the truncated float is an integer within range.
+            Self::Float(x) if (-I128_END..I128_END).contains(&x.trunc()) => T::try_from(x.trunc() as i128).ok(),
+            Self::Float(x) if (0.0..2.0 * I128_END).contains(&x) => T::try_from(x.trunc() as u128).ok(),
+            Self::Float(_) => None,
+        };
+        int.ok_or(Failure::OutOfRange)
+    }
+
+    /// Returns the magnitude of an integer, or `None` for a float.
+    fn magnitude(self) -> Option<u128> {
+        match self {
+            Self::Int(x) => Some(x.unsigned_abs()),
+            Self::BigUint(x) => Some(x),
+            Self::Float(_) => None,
+        }
+    }
+
+    /// Returns whether the number is an integer that a float with `mantissa_bits` bits of precision can't represent.
+    fn is_inexact_int(self, mantissa_bits: u32) -> bool {
+        self.magnitude()
+            .is_some_and(|m| m != 0 && u128::BITS - m.leading_zeros() - m.trailing_zeros() > mantissa_bits)
+    }
+
+    /// Converts the number to an `f64`, rounding to the nearest if `inexact`.
+    fn to_f64(self, inexact: bool) -> Result<f64, Failure> {
+        if !inexact && self.is_inexact_int(f64::MANTISSA_DIGITS) {
+            return Err(Failure::Inexact);
+        }
+        Ok(match self {
+            Self::Int(x) => x as f64,
+            Self::BigUint(x) => x as f64,
+            Self::Float(x) => x,
+        })
+    }
+
+    /// Converts the number to an `f32`, rounding to the nearest if `inexact`.
+    fn to_f32(self, inexact: bool) -> Result<f32, Failure> {
+        if !inexact && self.is_inexact_int(f32::MANTISSA_DIGITS) {
+            return Err(Failure::Inexact);
+        }
+        let (float, exact) = match self {
+            Self::Int(x) => (x as f32, true),
+            Self::BigUint(x) => (x as f32, true),
+            Self::Float(x) => (x as f32, x.is_nan() || f64::from(x as f32) == x),
+        };
+        let finite = match self {
+            Self::Float(x) => x.is_finite(),
+            _ => true,
+        };
+        if finite && !float.is_finite() {
+            return Err(Failure::OutOfRange);
+        }
+        if !exact && !inexact {
+            return Err(Failure::Inexact);
+        }
+        Ok(float)
+    }
+}
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(x) => write!(f, "{x}"),
+            Self::BigUint(x) => write!(f, "{x}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+        }
+    }
+}
+
+/// An extension trait for [`MapValue`] looking up keys coerced to the key type of the map first,
+/// so that, e.g., a `u32` literal finds the entry of an equal `u64` key.
+pub trait CoercedLookup {
+    /// Returns the value for `key` after [binding](bind) it to the key type `key_ty`,
+    /// with the `Ref`s in it resolved in `ts`,
+    /// or an error if `key` can't be bound to `key_ty`, rather than a miss.
+    fn get_coerced(
+        &self,
+        key: &AlgebraicValue,
+        key_ty: &AlgebraicType,
+        ts: &Typespace,
+    ) -> Result<Option<&AlgebraicValue>, CoerceError>;
+
+    /// Returns whether the map has an entry for `key` bound to the key type `key_ty`,
+    /// as [`get_coerced`](CoercedLookup::get_coerced) finds it.
+    fn contains_key_coerced(
+        &self,
+        key: &AlgebraicValue,
+        key_ty: &AlgebraicType,
+        ts: &Typespace,
+    ) -> Result<bool, CoerceError> {
+        self.get_coerced(key, key_ty, ts).map(|val| val.is_some())
+    }
+}
+
+impl CoercedLookup for MapValue {
+    fn get_coerced(
+        &self,
+        key: &AlgebraicValue,
+        key_ty: &AlgebraicType,
+        ts: &Typespace,
+    ) -> Result<Option<&AlgebraicValue>, CoerceError> {
+        Ok(self.get(&bind(key.clone(), key_ty, ts)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_value::F32;
+    use crate::{AlgebraicTypeRef, ProductTypeElement};
+
+    const EXACT: CoerceOptions = CoerceOptions {
+        allow_inexact: false,
+        parse_strings: false,
+    };
+    const INEXACT: CoerceOptions = CoerceOptions {
+        allow_inexact: true,
+        parse_strings: false,
+    };
+    const STRINGS: CoerceOptions = CoerceOptions {
+        allow_inexact: false,
+        parse_strings: true,
+    };
+
+    /// What coercing a value should result in.
+    #[derive(Debug, PartialEq)]
+    enum Expect {
+        Ok(AlgebraicValue),
+        Mismatch,
+        OutOfRange,
+        Inexact,
+        NotANumber,
+    }
+    use Expect::*;
+
+    fn run(value: AlgebraicValue, target: &AlgebraicType, options: CoerceOptions) -> Expect {
+        match bind_with(value, target, &Typespace::default(), options) {
+            Result::Ok(value) => Ok(value),
+            Err(CoerceError::Mismatch { .. }) => Mismatch,
+            Err(CoerceError::OutOfRange { .. }) => OutOfRange,
+            Err(CoerceError::Inexact { .. }) => Inexact,
+            Err(CoerceError::NotANumber { .. }) => NotANumber,
+            Err(e) => panic!("unexpected error {e}"),
+        }
+    }
+
+    fn f32(x: f32) -> AlgebraicValue {
+        AlgebraicValue::F32(x.into())
+    }
+
+    fn f64(x: f64) -> AlgebraicValue {
+        AlgebraicValue::F64(x.into())
+    }
+
+    fn string(s: &str) -> AlgebraicValue {
+        AlgebraicValue::String(s.into())
+    }
+
+    #[test]
+    fn matrix() {
+        use AlgebraicType as T;
+        use AlgebraicValue as V;
+
+        #[rustfmt::skip]
+        let table = [
+            // Integers to wider and narrower integers.
+            (V::U8(200), T::U64, EXACT, Ok(V::U64(200))),
+            (V::I8(-5), T::I128, EXACT, Ok(V::I128(-5))),
+            (V::U64(u64::MAX), T::U128, EXACT, Ok(V::U128(u64::MAX.into()))),
+            (V::I64(300), T::U16, EXACT, Ok(V::U16(300))),
+            (V::I64(255), T::U8, EXACT, Ok(V::U8(255))),
+            (V::I64(256), T::U8, EXACT, OutOfRange),
+            (V::I64(-1), T::U8, EXACT, OutOfRange),
+            (V::I64(-1), T::U8, INEXACT, OutOfRange),
+            (V::I64(-128), T::I8, EXACT, Ok(V::I8(-128))),
+            (V::I64(-129), T::I8, EXACT, OutOfRange),
+            (V::U128(u128::MAX), T::I128, EXACT, OutOfRange),
+            (V::U128(u128::MAX), T::U128, EXACT, Ok(V::U128(u128::MAX))),
+            (V::I128(i128::MIN), T::I64, EXACT, OutOfRange),
+            // Integers to floats.
+            (V::I64(3), T::F64, EXACT, Ok(f64(3.0))),
+            (V::I64(-(1 << 53)), T::F64, EXACT, Ok(f64(-9007199254740992.0))),
+            (V::I64((1 << 53) + 1), T::F64, EXACT, Inexact),
+            (V::U64(u64::MAX), T::F64, EXACT, Inexact),
+            (V::U64(u64::MAX), T::F64, INEXACT, Ok(f64(18446744073709551615.0))),
+            // Powers of two are exact however large.
+            (V::U128(1 << 127), T::F64, EXACT, Ok(f64(170141183460469231731687303715884105728.0))),
+            (V::I32((1 << 24) + 1), T::F32, EXACT, Inexact),
+            (V::I32(1 << 24), T::F32, EXACT, Ok(f32(16777216.0))),
+            (V::U128(u128::MAX), T::F32, INEXACT, OutOfRange),
+            // Floats to integers.
+            (f64(42.0), T::U8, EXACT, Ok(V::U8(42))),
+            (f64(-3.0), T::I32, EXACT, Ok(V::I32(-3))),
+            (f64(2.5), T::I32, EXACT, Inexact),
+            (f64(2.5), T::I32, INEXACT, Ok(V::I32(2))),
+            (f64(-2.5), T::I32, INEXACT, Ok(V::I32(-2))),
+            (f64(-1.0), T::U8, EXACT, OutOfRange),
+            (f64(256.0), T::U8, INEXACT, OutOfRange),
+            (f64(1e30), T::U128, EXACT, Ok(V::U128(1000000000000000019884624838656))),
+            (f64(1e40), T::U128, EXACT, OutOfRange),
+            (f64(f64::NAN), T::I64, INEXACT, OutOfRange),
+            (f64(f64::INFINITY), T::I64, INEXACT, OutOfRange),
+            (f32(7.0), T::I8, EXACT, Ok(V::I8(7))),
+            // Floats to floats.
+            (f32(0.1), T::F64, EXACT, Ok(f64(f64::from(0.1f32)))),
+            (f64(0.5), T::F32, EXACT, Ok(f32(0.5))),
+            (f64(0.1), T::F32, EXACT, Inexact),
+            (f64(0.1), T::F32, INEXACT, Ok(f32(0.1))),
+            (f64(1e300), T::F32, INEXACT, OutOfRange),
+            (f64(f64::INFINITY), T::F32, EXACT, Ok(f32(f32::INFINITY))),
+            (f64(f64::NAN), T::F32, EXACT, Ok(f32(f32::NAN))),
+            // Numeric strings, only when parsing them.
+            (string("42"), T::U8, EXACT, Mismatch),
+            (string("42"), T::U8, STRINGS, Ok(V::U8(42))),
+            (string(" -7 "), T::I16, STRINGS, Ok(V::I16(-7))),
+            (string("2.5"), T::F64, STRINGS, Ok(f64(2.5))),
+            (string("2.5"), T::U8, STRINGS, Inexact),
+            (string("300"), T::U8, STRINGS, OutOfRange),
+            (string("340282366920938463463374607431768211455"), T::U128, STRINGS, Ok(V::U128(u128::MAX))),
+            (string("forty-two"), T::U8, STRINGS, NotANumber),
+            (string("42"), T::String, STRINGS, Ok(string("42"))),
+            // Everything else only binds to the same kind.
+            (V::Bool(true), T::Bool, EXACT, Ok(V::Bool(true))),
+            (V::Bool(true), T::U8, EXACT, Mismatch),
+            (V::U8(1), T::Bool, EXACT, Mismatch),
+            (V::U8(1), T::String, STRINGS, Mismatch),
+            (string("a"), T::Bool, EXACT, Mismatch),
+            (V::UNIT, T::UNIT_TYPE, EXACT, Ok(V::UNIT)),
+            (V::UNIT, T::U8, EXACT, Mismatch),
+            (V::OptionNone(), T::option(T::U8), EXACT, Ok(V::OptionNone())),
+            (V::U8(1), T::option(T::U8), EXACT, Mismatch),
+            (V::map(Default::default()), T::map(T::U8, T::U8), EXACT, Ok(V::map(Default::default()))),
+            (V::U8(1), T::array(T::U8), EXACT, Mismatch),
+        ];
+        for (value, target, options, expected) in table {
+            let actual = run(value.clone(), &target, options);
+            assert_eq!(
+                actual,
+                expected,
+                "{value:?} to {} with {options:?}",
+                fmt_algebraic_type(&target)
+            );
+        }
+    }
+
+    #[test]
+    fn arrays_coerce_element_wise() {
+        let ts = Typespace::default();
+        let ints = AlgebraicValue::ArrayOf(vec![1i64, 2, 3]);
+        assert_eq!(
+            bind(ints.clone(), &AlgebraicType::array(AlgebraicType::U8), &ts),
+            Result::Ok(AlgebraicValue::ArrayOf(vec![1u8, 2, 3]))
+        );
+        assert_eq!(
+            bind(ints, &AlgebraicType::array(AlgebraicType::F32), &ts),
+            Result::Ok(AlgebraicValue::ArrayOf(vec![F32::from(1.0), 2.0.into(), 3.0.into()]))
+        );
+        let nested = AlgebraicValue::ArrayOf(vec![ArrayValue::I64(vec![1]), ArrayValue::I64(vec![])]);
+        let target = AlgebraicType::array(AlgebraicType::array(AlgebraicType::I8));
+        assert_eq!(
+            bind(nested, &target, &ts),
+            Result::Ok(AlgebraicValue::ArrayOf(vec![
+                ArrayValue::I8(vec![1]),
+                ArrayValue::default()
+            ]))
+        );
+
+        let err = bind(
+            AlgebraicValue::ArrayOf(vec![1i64, -1]),
+            &AlgebraicType::array(AlgebraicType::U32),
+            &ts,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Element 1: The number -1 is out of range for U32");
+    }
+
+    #[test]
+    fn targets_are_resolved() {
+        let mut ts = Typespace::default();
+        let r = ts.add(AlgebraicType::U16);
+        assert_eq!(
+            bind(AlgebraicValue::I64(9), &AlgebraicType::Ref(r), &ts),
+            Result::Ok(AlgebraicValue::U16(9))
+        );
+        let newtype = AlgebraicType::Newtype(crate::NewtypeType::new("Id", AlgebraicType::Ref(r)));
+        assert_eq!(
+            bind(AlgebraicValue::U8(9), &newtype, &ts),
+            Result::Ok(AlgebraicValue::U16(9))
+        );
+        assert_eq!(
+            bind(AlgebraicValue::U8(9), &AlgebraicType::Ref(AlgebraicTypeRef(5)), &ts),
+            Err(CoerceError::UnresolvedRef(5))
+        );
+    }
+
+    #[test]
+    fn errors_name_both_kinds() {
+        let ts = Typespace::default();
+        let product = AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::U8, "x")]);
+        let err = bind(string("x"), &product, &ts).unwrap_err();
+        assert_eq!(err.to_string(), "Can't coerce a String value to (x: U8)");
+        let err = bind(AlgebraicValue::U64(u64::MAX), &AlgebraicType::F64, &ts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The number 18446744073709551615 can't be represented exactly as F64"
+        );
+    }
+
+    #[test]
+    fn coerced_map_lookups() {
+        let ts = Typespace::default();
+        let scores = MapValue::from([
+            (AlgebraicValue::U64(1), AlgebraicValue::from("one")),
+            (AlgebraicValue::U64(u64::MAX), AlgebraicValue::from("max")),
+        ]);
+        let probe = AlgebraicValue::U32(1);
+        assert_eq!(scores.get(&probe), None);
+        let found = scores.get_coerced(&probe, &AlgebraicType::U64, &ts).unwrap();
+        assert_eq!(found, Some(&AlgebraicValue::from("one")));
+        let found = scores.contains_key_coerced(&AlgebraicValue::I8(2), &AlgebraicType::U64, &ts);
+        assert!(!found.unwrap());
+
+        // A key that can't be of the key type is an error, not a miss.
+        let err = scores
+            .contains_key_coerced(&AlgebraicValue::I32(-1), &AlgebraicType::U64, &ts)
+            .unwrap_err();
+        assert!(matches!(err, CoerceError::OutOfRange { .. }), "{err}");
+        let err = scores
+            .get_coerced(&AlgebraicValue::from("1"), &AlgebraicType::U64, &ts)
+            .unwrap_err();
+        assert!(matches!(err, CoerceError::Mismatch { .. }), "{err}");
+
+        // Strings are only found by equal strings.
+        let names = MapValue::from([(AlgebraicValue::from("alice"), AlgebraicValue::U32(7))]);
+        let name = |s: &str| AlgebraicValue::from(s);
+        let found = names.get_coerced(&name("alice"), &AlgebraicType::String, &ts).unwrap();
+        assert_eq!(found, Some(&AlgebraicValue::U32(7)));
+        let found = names.contains_key_coerced(&name("Alice"), &AlgebraicType::String, &ts);
+        assert!(!found.unwrap());
+    }
+}
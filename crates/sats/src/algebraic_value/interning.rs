@@ -0,0 +1,190 @@
+//! Interning of strings, so that equal strings, e.g., of enum-like columns, share one allocation.
+//!
+//! An [`InternedString`] is encoded exactly as a [`String`] is, so interning is invisible on the wire.
+//! Strings are interned by a [`StringInterner`], either directly or while decoding them,
+//! as a `&mut StringInterner` is a [`DeserializeSeed`] for [`InternedString`]s.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::de::{DeserializeSeed, Deserializer, Error, SliceVisitor};
+use crate::{impl_deserialize, impl_serialize, impl_st, AlgebraicType, AlgebraicValue};
+
+/// A string shared with all equal strings interned by the same [`StringInterner`].
+///
+/// Cloning an interned string only bumps a reference count.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InternedString(Arc<str>);
+
+impl InternedString {
+    /// Returns the string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns whether `self` and `other` share the same allocation,
+    /// as equal strings interned by the same interner do.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for InternedString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for InternedString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for InternedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Converts the interned string into an [`AlgebraicValue::String`], which has an allocation of its own.
+impl From<InternedString> for AlgebraicValue {
+    fn from(s: InternedString) -> Self {
+        Self::String(s.as_str().into())
+    }
+}
+
+impl_serialize!([] InternedString, (self, ser) => ser.serialize_str(&self.0));
+impl_deserialize!([] InternedString, de => <Arc<str>>::deserialize(de).map(InternedString));
+impl_st!([] InternedString, _ts => AlgebraicType::String);
+
+/// A set of interned strings, handing out the same [`InternedString`] for equal strings.
+///
+/// Strings stay interned as long as the interner does, even once no `InternedString` refers to them anymore.
+#[derive(Debug, Default)]
+pub struct StringInterner {
+    /// The interned strings.
+    strings: HashSet<Arc<str>>,
+}
+
+impl StringInterner {
+    /// Returns an interner without any strings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the interned string equal to `s`, interning `s` first if no such string is interned yet.
+    pub fn intern(&mut self, s: &str) -> InternedString {
+        if let Some(interned) = self.strings.get(s) {
+            return InternedString(interned.clone());
+        }
+        let interned = Arc::<str>::from(s);
+        self.strings.insert(interned.clone());
+        InternedString(interned)
+    }
+
+    /// Returns the number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns whether no strings are interned.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// Decodes a string, interning it in the interner.
+impl<'de> DeserializeSeed<'de> for &mut StringInterner {
+    type Output = InternedString;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error> {
+        deserializer.deserialize_str(InternVisitor(self))
+    }
+}
+
+/// A visitor interning the string it visits.
+struct InternVisitor<'a>(&'a mut StringInterner);
+
+impl<'de> SliceVisitor<'de, str> for InternVisitor<'_> {
+    type Output = InternedString;
+
+    fn visit<E: Error>(self, slice: &str) -> Result<Self::Output, E> {
+        Ok(self.0.intern(slice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_value::ser::ValueSerializer;
+    use crate::bsatn;
+    use crate::ser::Serialize;
+
+    #[test]
+    fn equal_strings_share_an_allocation() {
+        let mut interner = StringInterner::new();
+        let a = interner.intern("online");
+        let b = interner.intern(&String::from("online"));
+        let c = interner.intern("offline");
+        assert!(a.ptr_eq(&b));
+        assert!(!a.ptr_eq(&c));
+        assert_eq!(a, b);
+        assert_eq!(&*a, "online");
+        assert_eq!(interner.len(), 2);
+
+        // Strings interned by different interners are equal, but not shared.
+        let other = StringInterner::new().intern("online");
+        assert_eq!(a, other);
+        assert!(!a.ptr_eq(&other));
+    }
+
+    #[test]
+    fn interned_strings_encode_as_strings() {
+        let mut interner = StringInterner::new();
+        let strings = ["online", "away", "online"];
+        let interned = strings.map(|s| interner.intern(s));
+        assert_eq!(bsatn::to_vec(&interned).unwrap(), bsatn::to_vec(&strings).unwrap());
+        assert_eq!(
+            interned[0].serialize(ValueSerializer).unwrap(),
+            AlgebraicValue::String("online".into())
+        );
+        assert_eq!(
+            AlgebraicValue::from(interned[1].clone()),
+            AlgebraicValue::String("away".into())
+        );
+        assert_eq!(
+            bsatn::from_slice::<InternedString>(&bsatn::to_vec("away").unwrap()).unwrap(),
+            interned[1]
+        );
+    }
+
+    #[test]
+    fn decoding_interns() {
+        let mut interner = StringInterner::new();
+        let existing = interner.intern("online");
+        let bytes = ["online", "away", "away"]
+            .iter()
+            .flat_map(|s| bsatn::to_vec(s).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut reader = &bytes[..];
+        let decoded = (0..3)
+            .map(|_| (&mut interner).deserialize(bsatn::Deserializer::new(&mut reader)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(reader.is_empty());
+        assert!(decoded[0].ptr_eq(&existing));
+        assert!(decoded[1].ptr_eq(&decoded[2]));
+        assert_eq!(interner.len(), 2);
+
+        assert!((&mut interner)
+            .deserialize(bsatn::Deserializer::new(&mut &[1][..]))
+            .is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{AlgebraicType, AlgebraicValue, BuiltinType, Typespace};
+
+/// A pattern describing the shape of an [`AlgebraicValue`] tree,
+/// to be tested against values with [`matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Matches any value.
+    Any,
+    /// Matches only a value equal to the given one.
+    Exact(AlgebraicValue),
+    /// Matches a product value whose fields, looked up by name, match the given patterns.
+    ///
+    /// Fields of the product not mentioned in the pattern are ignored.
+    Product(Vec<(String, Pattern)>),
+    /// Matches a sum value of the variant `tag` whose payload matches `inner`.
+    Sum {
+        /// The tag of the variant to match.
+        tag: u8,
+        /// The pattern the payload of the variant must match.
+        inner: Box<Pattern>,
+    },
+    /// Matches an array value where every element matches `elem`.
+    Array {
+        /// If provided, the array must have exactly this many elements.
+        len: Option<usize>,
+        /// The pattern every element must match.
+        elem: Box<Pattern>,
+    },
+}
+
+/// Returns whether `value`, of the type `schema` in the typespace `ts`, matches `pattern`.
+///
+/// Any `AlgebraicType::Ref`s encountered along the way are resolved in `ts`,
+/// and newtypes are matched as the types they wrap.
+/// A value that does not fit its type, e.g., a product lacking a field named in the pattern,
+/// does not match any pattern other than [`Pattern::Any`] and [`Pattern::Exact`].
+pub fn matches(pattern: &Pattern, value: &AlgebraicValue, schema: &AlgebraicType, ts: &Typespace) -> bool {
+    let schema = match schema {
+        &AlgebraicType::Ref(r) => {
+            return ts.get(r).map_or(false, |schema| matches(pattern, value, schema, ts));
+        }
+        AlgebraicType::Newtype(nt) => return matches(pattern, value, &nt.inner, ts),
+        schema => schema,
+    };
+
+    match (pattern, value, schema) {
+        (Pattern::Any, _, _) => true,
+        (Pattern::Exact(expected), _, _) => value == expected,
+        (Pattern::Product(fields), AlgebraicValue::Product(val), AlgebraicType::Product(ty))
+            if val.elements.len() == ty.elements.len() =>
+        {
+            fields.iter().all(|(name, pat)| {
+                ty.elements
+                    .iter()
+                    .position(|e| e.name.as_deref() == Some(name))
+                    .map_or(false, |i| {
+                        matches(pat, &val.elements[i], &ty.elements[i].algebraic_type, ts)
+                    })
+            })
+        }
+        (Pattern::Sum { tag, inner }, AlgebraicValue::Sum(val), AlgebraicType::Sum(ty)) if val.tag == *tag => {
+            let var = ty.variants.get(*tag as usize);
+            var.map_or(false, |var| matches(inner, &val.value, &var.algebraic_type, ts))
+        }
+        (Pattern::Array { len, elem }, AlgebraicValue::Array(val), AlgebraicType::Builtin(BuiltinType::Array(ty))) => {
+            len.map_or(true, |len| len == val.len()) && val.iter_cloned().all(|v| matches(elem, &v, &ty.elem_ty, ts))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, ProductTypeElement, SumTypeVariant};
+
+    fn status() -> AlgebraicType {
+        AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "status"),
+        ])
+    }
+
+    fn exact_status(s: &str) -> Pattern {
+        Pattern::Product(vec![("status".into(), Pattern::Exact(s.into()))])
+    }
+
+    #[test]
+    fn product_field_by_name() {
+        let ts = Typespace::new(vec![]);
+        let ty = status();
+        let active = product![7u32, "active"].into();
+        let inactive = product![7u32, "inactive"].into();
+
+        assert!(matches(&exact_status("active"), &active, &ty, &ts));
+        assert!(!matches(&exact_status("active"), &inactive, &ty, &ts));
+        // A field the product doesn't have never matches.
+        let missing = Pattern::Product(vec![("state".into(), Pattern::Any)]);
+        assert!(!matches(&missing, &active, &ty, &ts));
+
+        // Refs are followed.
+        let mut ts = Typespace::new(vec![]);
+        let r = ts.add(ty);
+        assert!(matches(&exact_status("active"), &active, &AlgebraicType::Ref(r), &ts));
+    }
+
+    #[test]
+    fn any_matches_everything() {
+        let ts = Typespace::new(vec![]);
+        assert!(matches(&Pattern::Any, &AlgebraicValue::U8(1), &AlgebraicType::U8, &ts));
+        assert!(matches(&Pattern::Any, &product![1u32, "x"].into(), &status(), &ts));
+
+        let ty = AlgebraicType::array(AlgebraicType::U8);
+        let val = AlgebraicValue::ArrayOf(vec![1u8, 2, 3]);
+        let any_elems = |len| Pattern::Array {
+            len,
+            elem: Box::new(Pattern::Any),
+        };
+        assert!(matches(&any_elems(None), &val, &ty, &ts));
+        assert!(matches(&any_elems(Some(3)), &val, &ty, &ts));
+        assert!(!matches(&any_elems(Some(2)), &val, &ty, &ts));
+    }
+
+    #[test]
+    fn nested_sum() {
+        let ts = Typespace::new(vec![]);
+        // `Option<{ id: u32, status: String }>`.
+        let ty = AlgebraicType::sum(vec![
+            SumTypeVariant::new_named(status(), "some"),
+            SumTypeVariant::new_named(AlgebraicType::UNIT_TYPE, "none"),
+        ]);
+        let some_active = |s| Pattern::Sum {
+            tag: 0,
+            inner: Box::new(exact_status(s)),
+        };
+
+        let val = AlgebraicValue::OptionSome(product![1u32, "active"].into());
+        assert!(matches(&some_active("active"), &val, &ty, &ts));
+        assert!(!matches(&some_active("done"), &val, &ty, &ts));
+        assert!(!matches(
+            &some_active("active"),
+            &AlgebraicValue::OptionNone(),
+            &ty,
+            &ts
+        ));
+    }
+}
@@ -40,7 +40,7 @@ impl ser::Serializer for ValueSerializer {
     method!(serialize_f64 -> f64);
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        Ok(AlgebraicValue::String(v.to_owned()))
+        Ok(AlgebraicValue::String(v.into()))
     }
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
         Ok(AlgebraicValue::Bytes(v.to_owned()))
@@ -53,12 +53,23 @@ impl ser::Serializer for ValueSerializer {
         })
     }
 
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        Ok(SerializeArrayValue {
+            len: None,
+            array: Default::default(),
+        })
+    }
+
     fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
         Ok(SerializeMapValue {
             entries: Vec::with_capacity(len),
         })
     }
 
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeMapValue { entries: Vec::new() })
+    }
+
     fn serialize_seq_product(self, len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
         Ok(SerializeProductValue {
             elements: Vec::with_capacity(len),
@@ -0,0 +1,288 @@
+use std::fmt::Write;
+
+use super::cmp::{conform, TypeError};
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::{AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, Typespace};
+
+/// An error rendering an [`AlgebraicValue`] as a SQL literal.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SqlLiteralError {
+    /// Values of this kind have no SQL literal form.
+    #[error("{kind} values have no SQL literal form")]
+    NoLiteral { kind: &'static str },
+    /// SQL has no literal for NaN or the infinities.
+    #[error("The float {value} has no SQL literal form")]
+    NonFinite { value: String },
+    /// The value does not fit the type it was to be rendered at.
+    #[error("The value does not fit the type {ty}")]
+    Mismatch { ty: String },
+}
+
+impl AlgebraicValue {
+    /// Renders the value as a SQL literal, e.g., for error messages, `EXPLAIN` output, or generated queries.
+    ///
+    /// Strings become single quoted literals with any `'` doubled, so they can't end the literal early.
+    /// Other characters, including newlines, are kept as they are within the quotes.
+    /// Byte arrays become hex literals like `X'ABCD'`,
+    /// integers decimal literals, and bools `TRUE` or `FALSE`.
+    /// Floats are rendered such that parsing the literal yields the exact same float,
+    /// always with a `.` so the literal isn't taken for an integer.
+    ///
+    /// When `ty` is provided, the value is checked against it,
+    /// and option values become `NULL` or the literal of their payload.
+    /// Newtypes are rendered as the types they wrap,
+    /// but `AlgebraicType::Ref`s can't be resolved and are rejected.
+    ///
+    /// Products, other sums, maps, other arrays, and non-finite floats have no literal form
+    /// and are rejected rather than rendered in some lossy way.
+    pub fn to_sql_literal(&self, ty: Option<&AlgebraicType>) -> Result<String, SqlLiteralError> {
+        let mut out = String::new();
+        write_sql_literal(&mut out, self, ty)?;
+        Ok(out)
+    }
+}
+
+/// Writes `value`, of the type `ty` if provided, to `out` as a SQL literal.
+fn write_sql_literal(
+    out: &mut String,
+    value: &AlgebraicValue,
+    ty: Option<&AlgebraicType>,
+) -> Result<(), SqlLiteralError> {
+    let mismatch = |ty: &AlgebraicType| SqlLiteralError::Mismatch {
+        ty: fmt_algebraic_type(ty).to_string(),
+    };
+
+    if let Some(mut ty) = ty {
+        while let AlgebraicType::Newtype(nt) = ty {
+            ty = &nt.inner;
+        }
+        match (ty, value) {
+            (AlgebraicType::Sum(sum), AlgebraicValue::Sum(val)) if sum.as_option().is_some() => {
+                if val.tag == 0 {
+                    return write_sql_literal(out, &val.value, sum.as_option());
+                }
+                out.push_str("NULL");
+                return Ok(());
+            }
+            // Arrays and maps are checked element by element, whether or not they have a literal form.
+            (AlgebraicType::Builtin(BuiltinType::Array(_)), AlgebraicValue::Array(_))
+            | (AlgebraicType::Builtin(BuiltinType::Map(_)), AlgebraicValue::Map(_)) => {
+                let typespace = Typespace::default();
+                match conform(typespace.with_type(ty), value) {
+                    Ok(()) => {}
+                    Err(TypeError::UnresolvedRef(_)) => {
+                        return Err(SqlLiteralError::NoLiteral { kind: "Unresolved ref" })
+                    }
+                    Err(_) => return Err(mismatch(ty)),
+                }
+                // An empty array conforms whichever kind of element it was built with.
+                if ty.is_bytes() && value.as_array().map_or(false, ArrayValue::is_empty) {
+                    out.push_str("X''");
+                    return Ok(());
+                }
+            }
+            (AlgebraicType::Builtin(BuiltinType::Array(_) | BuiltinType::Map(_)), _) => return Err(mismatch(ty)),
+            (AlgebraicType::Builtin(_), _) if value.type_of() == *ty => {}
+            (AlgebraicType::Builtin(_), _) => return Err(mismatch(ty)),
+            // No literal form either way, but say so about the value if it fits.
+            (AlgebraicType::Sum(_), AlgebraicValue::Sum(_))
+            | (AlgebraicType::Product(_), AlgebraicValue::Product(_)) => {}
+            (AlgebraicType::Ref(_), _) => return Err(SqlLiteralError::NoLiteral { kind: "Unresolved ref" }),
+            _ => return Err(mismatch(ty)),
+        }
+    }
+
+    // A `ty` of `Option<T>` was handled above, so any sum left has no literal form.
+    match value {
+        AlgebraicValue::Sum(_) | AlgebraicValue::Product(_) | AlgebraicValue::Map(_) => {
+            return Err(SqlLiteralError::NoLiteral {
+                kind: value.type_name(),
+            })
+        }
+        AlgebraicValue::Bool(v) => out.push_str(if *v { "TRUE" } else { "FALSE" }),
+        AlgebraicValue::I8(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::U8(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::I16(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::U16(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::I32(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::U32(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::I64(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::U64(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::I128(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::U128(v) => write!(out, "{v}").unwrap(),
+        AlgebraicValue::F32(v) => write_float(out, f32::from(*v).is_finite(), format!("{:?}", f32::from(*v)))?,
+        AlgebraicValue::F64(v) => write_float(out, f64::from(*v).is_finite(), format!("{:?}", f64::from(*v)))?,
+        AlgebraicValue::String(s) => {
+            out.push('\'');
+            for c in s.chars() {
+                if c == '\'' {
+                    out.push('\'');
+                }
+                out.push(c);
+            }
+            out.push('\'');
+        }
+        AlgebraicValue::Array(ArrayValue::U8(bytes)) => {
+            out.push_str("X'");
+            for byte in bytes {
+                write!(out, "{byte:02X}").unwrap();
+            }
+            out.push('\'');
+        }
+        AlgebraicValue::Array(_) => {
+            return Err(SqlLiteralError::NoLiteral {
+                kind: value.type_name(),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `Debug` rendering `repr` of a float, which is the shortest that round-trips, to `out`,
+/// inserting a `.0` into exponents like `1e300` to mark the literal as a float.
+fn write_float(out: &mut String, is_finite: bool, repr: String) -> Result<(), SqlLiteralError> {
+    if !is_finite {
+        return Err(SqlLiteralError::NonFinite { value: repr });
+    }
+    match repr.split_once('e') {
+        Some((mantissa, exp)) if !mantissa.contains('.') => write!(out, "{mantissa}.0e{exp}").unwrap(),
+        _ => out.push_str(&repr),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product;
+
+    fn lit(value: impl Into<AlgebraicValue>) -> Result<String, SqlLiteralError> {
+        value.into().to_sql_literal(None)
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(lit("plain").unwrap(), "'plain'");
+        assert_eq!(lit("").unwrap(), "''");
+        assert_eq!(lit("it's").unwrap(), "'it''s'");
+        assert_eq!(lit("'; DROP TABLE t; --").unwrap(), "'''; DROP TABLE t; --'");
+        assert_eq!(lit("''").unwrap(), "''''''");
+        assert_eq!(lit("two\nlines\r\n").unwrap(), "'two\nlines\r\n'");
+    }
+
+    #[test]
+    fn numbers_and_bools() {
+        assert_eq!(lit(true).unwrap(), "TRUE");
+        assert_eq!(lit(false).unwrap(), "FALSE");
+        assert_eq!(lit(-128i8).unwrap(), "-128");
+        assert_eq!(lit(u128::MAX).unwrap(), u128::MAX.to_string());
+        assert_eq!(lit(i64::MIN).unwrap(), "-9223372036854775808");
+
+        assert_eq!(lit(1.0f64).unwrap(), "1.0");
+        assert_eq!(lit(-0.0f64).unwrap(), "-0.0");
+        assert_eq!(lit(0.1f32).unwrap(), "0.1");
+        assert_eq!(lit(1e300f64).unwrap(), "1.0e300");
+        assert_eq!(lit(1.5e-7f64).unwrap(), "1.5e-7");
+        for f in [0.1f64, 1.0 / 3.0, f64::MAX, f64::MIN_POSITIVE, 5e-324, 123456789.125] {
+            assert_eq!(lit(f).unwrap().parse::<f64>().unwrap().to_bits(), f.to_bits());
+        }
+        for f in [f32::MAX, f32::EPSILON, 1.0 / 3.0] {
+            assert_eq!(lit(f).unwrap().parse::<f32>().unwrap().to_bits(), f.to_bits());
+        }
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        for f in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(lit(f), Err(SqlLiteralError::NonFinite { .. })), "{f}");
+        }
+        assert_eq!(
+            lit(f32::NEG_INFINITY).unwrap_err().to_string(),
+            "The float -inf has no SQL literal form"
+        );
+    }
+
+    #[test]
+    fn byte_arrays() {
+        assert_eq!(
+            AlgebraicValue::Bytes(vec![0xab, 0xcd, 0x01])
+                .to_sql_literal(None)
+                .unwrap(),
+            "X'ABCD01'"
+        );
+        assert_eq!(AlgebraicValue::Bytes(vec![]).to_sql_literal(None).unwrap(), "X''");
+        let bytes = AlgebraicType::bytes();
+        assert_eq!(
+            AlgebraicValue::Bytes(vec![0]).to_sql_literal(Some(&bytes)).unwrap(),
+            "X'00'"
+        );
+        // Other arrays have no literal form.
+        let err = AlgebraicValue::ArrayOf(vec![1u32]).to_sql_literal(None).unwrap_err();
+        assert_eq!(err, SqlLiteralError::NoLiteral { kind: "Array" });
+
+        // With a type, arrays and maps must fit it.
+        let mismatch = SqlLiteralError::Mismatch { ty: "Array<U8>".into() };
+        let strings = AlgebraicValue::ArrayOf(vec!["a".to_owned()]);
+        assert_eq!(strings.to_sql_literal(Some(&bytes)).unwrap_err(), mismatch);
+        assert_eq!(
+            AlgebraicValue::String("ab".into())
+                .to_sql_literal(Some(&bytes))
+                .unwrap_err(),
+            mismatch
+        );
+        let empty = AlgebraicValue::ArrayOf(Vec::<String>::new());
+        assert_eq!(empty.to_sql_literal(Some(&bytes)).unwrap(), "X''");
+        let u32s = AlgebraicType::array(AlgebraicType::U32);
+        let err = AlgebraicValue::Bytes(vec![1]).to_sql_literal(Some(&u32s)).unwrap_err();
+        assert_eq!(err.to_string(), "The value does not fit the type Array<U32>");
+        let err = AlgebraicValue::ArrayOf(vec![1u32])
+            .to_sql_literal(Some(&u32s))
+            .unwrap_err();
+        assert_eq!(err, SqlLiteralError::NoLiteral { kind: "Array" });
+        let map = AlgebraicType::map(AlgebraicType::U32, AlgebraicType::String);
+        let err = AlgebraicValue::U32(1).to_sql_literal(Some(&map)).unwrap_err();
+        assert!(matches!(err, SqlLiteralError::Mismatch { .. }), "{err}");
+        let err = AlgebraicValue::map(Default::default())
+            .to_sql_literal(Some(&map))
+            .unwrap_err();
+        assert_eq!(err, SqlLiteralError::NoLiteral { kind: "Map" });
+    }
+
+    #[test]
+    fn typed() {
+        let option = AlgebraicType::option(AlgebraicType::String);
+        let some = AlgebraicValue::OptionSome("x".into());
+        assert_eq!(some.to_sql_literal(Some(&option)).unwrap(), "'x'");
+        assert_eq!(
+            AlgebraicValue::OptionNone().to_sql_literal(Some(&option)).unwrap(),
+            "NULL"
+        );
+        // Without the type, an option is just a sum.
+        assert_eq!(
+            some.to_sql_literal(None).unwrap_err(),
+            SqlLiteralError::NoLiteral { kind: "Sum" }
+        );
+
+        let err = AlgebraicValue::U8(1)
+            .to_sql_literal(Some(&AlgebraicType::I64))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "The value does not fit the type I64");
+        let err = some
+            .to_sql_literal(Some(&AlgebraicType::option(AlgebraicType::U32)))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "The value does not fit the type U32");
+    }
+
+    #[test]
+    fn no_literal_form() {
+        let err = AlgebraicValue::from(product![1u8, "a"])
+            .to_sql_literal(None)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Product values have no SQL literal form");
+        let map = AlgebraicValue::map([(AlgebraicValue::U8(1), AlgebraicValue::U8(2))].into());
+        assert_eq!(
+            map.to_sql_literal(None).unwrap_err(),
+            SqlLiteralError::NoLiteral { kind: "Map" }
+        );
+    }
+}
@@ -0,0 +1,643 @@
+//! Decoding values into a [`Bump`] arena rather than onto the heap.
+//!
+//! Rows that are decoded, inspected, and then dropped en masse,
+//! as during query execution, spend much of their time on freeing
+//! every string, product, and array separately.
+//! The values here are instead allocated in a caller-provided arena,
+//! and are all freed at once when it is reset or dropped.
+//!
+//! Every value borrows the arena it was decoded into,
+//! so it cannot outlive the arena:
+//!
+//! ```compile_fail
+//! # use bumpalo::Bump;
+//! # use spacetimedb_sats::{arena::AlgebraicValueIn, bsatn, AlgebraicType, Typespace, WithTypespace};
+//! let bytes = bsatn::to_vec("escapee").unwrap();
+//! let ts = Typespace::default();
+//! let value = {
+//!     let bump = Bump::new();
+//!     AlgebraicValueIn::decode(&bump, WithTypespace::new(&ts, &AlgebraicType::String), &mut &*bytes).unwrap()
+//! };
+//! println!("{value:?}");
+//! ```
+//!
+//! Nor can the arena be reset while any of its values are still in use:
+//!
+//! ```compile_fail
+//! # use bumpalo::Bump;
+//! # use spacetimedb_sats::{arena::AlgebraicValueIn, bsatn, AlgebraicType, Typespace, WithTypespace};
+//! let bytes = bsatn::to_vec("escapee").unwrap();
+//! let ts = Typespace::default();
+//! let mut bump = Bump::new();
+//! let value = AlgebraicValueIn::decode(&bump, WithTypespace::new(&ts, &AlgebraicType::String), &mut &*bytes).unwrap();
+//! bump.reset();
+//! println!("{value:?}");
+//! ```
+//!
+//! Values can be copied out of the arena with [`AlgebraicValueIn::to_value`].
+
+use std::marker::PhantomData;
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+
+use crate::buffer::{BufReader, DecodeError};
+use crate::builtin_value::{F32, F64};
+use crate::de::{
+    ArrayAccess, ArrayVisitor, Deserialize, DeserializeSeed, Deserializer, Error, MapAccess, MapVisitor,
+    NamedProductAccess, ProductVisitor, SeqProductAccess, SliceVisitor, SumAccess, SumVisitor, TupleNameVisitor,
+    VariantAccess,
+};
+use crate::{
+    bsatn, AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, MapType, MapValue, ProductType,
+    ProductValue, SumType, SumValue, WithTypespace,
+};
+
+/// An [`AlgebraicValue`] allocated in the arena `'a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlgebraicValueIn<'a> {
+    /// A value of a sum type.
+    Sum(SumValueIn<'a>),
+    /// A value of a product type, i.e., its fields in order.
+    Product(&'a [AlgebraicValueIn<'a>]),
+    /// A [`bool`] value.
+    Bool(bool),
+    /// An [`i8`] value.
+    I8(i8),
+    /// A [`u8`] value.
+    U8(u8),
+    /// An [`i16`] value.
+    I16(i16),
+    /// A [`u16`] value.
+    U16(u16),
+    /// An [`i32`] value.
+    I32(i32),
+    /// A [`u32`] value.
+    U32(u32),
+    /// An [`i64`] value.
+    I64(i64),
+    /// A [`u64`] value.
+    U64(u64),
+    /// An [`i128`] value.
+    I128(i128),
+    /// A [`u128`] value.
+    U128(u128),
+    /// A totally ordered [`F32`] value.
+    F32(F32),
+    /// A totally ordered [`F64`] value.
+    F64(F64),
+    /// A UTF-8 string value.
+    String(&'a str),
+    /// A homogeneous array value.
+    Array(ArrayValueIn<'a>),
+    /// A map value, as its key/value pairs.
+    Map(MapValueIn<'a>),
+}
+
+/// A [`SumValue`] allocated in the arena `'a`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SumValueIn<'a> {
+    /// The tag of the chosen variant.
+    pub tag: u8,
+    /// The value of the chosen variant.
+    pub value: &'a AlgebraicValueIn<'a>,
+}
+
+/// A [`MapValue`] allocated in the arena `'a`, as its key/value pairs.
+///
+/// The pairs are kept in the order they were decoded in,
+/// which, for maps encoded from a `MapValue`, is sorted by key.
+pub type MapValueIn<'a> = &'a [(AlgebraicValueIn<'a>, AlgebraicValueIn<'a>)];
+
+/// An [`ArrayValue`] allocated in the arena `'a`.
+///
+/// Like `ArrayValue`, the elements are stored packed in a representation appropriate for their type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrayValueIn<'a> {
+    /// An array of sum values.
+    Sum(&'a [SumValueIn<'a>]),
+    /// An array of product values.
+    Product(&'a [&'a [AlgebraicValueIn<'a>]]),
+    /// An array of [`bool`]s.
+    Bool(&'a [bool]),
+    /// An array of [`i8`]s.
+    I8(&'a [i8]),
+    /// An array of [`u8`]s.
+    U8(&'a [u8]),
+    /// An array of [`i16`]s.
+    I16(&'a [i16]),
+    /// An array of [`u16`]s.
+    U16(&'a [u16]),
+    /// An array of [`i32`]s.
+    I32(&'a [i32]),
+    /// An array of [`u32`]s.
+    U32(&'a [u32]),
+    /// An array of [`i64`]s.
+    I64(&'a [i64]),
+    /// An array of [`u64`]s.
+    U64(&'a [u64]),
+    /// An array of [`i128`]s.
+    I128(&'a [i128]),
+    /// An array of [`u128`]s.
+    U128(&'a [u128]),
+    /// An array of totally ordered [`F32`]s.
+    F32(&'a [F32]),
+    /// An array of totally ordered [`F64`]s.
+    F64(&'a [F64]),
+    /// An array of UTF-8 strings.
+    String(&'a [&'a str]),
+    /// An array of arrays.
+    Array(&'a [ArrayValueIn<'a>]),
+    /// An array of maps.
+    Map(&'a [MapValueIn<'a>]),
+}
+
+impl<'a> AlgebraicValueIn<'a> {
+    /// Decodes a value of type `ty` from the BSATN format in `bytes` into the arena `bump`.
+    pub fn decode<'de>(
+        bump: &'a Bump,
+        ty: WithTypespace<'_, AlgebraicType>,
+        bytes: &mut impl BufReader<'de>,
+    ) -> Result<Self, DecodeError> {
+        InArena::new(bump, ty).deserialize(bsatn::Deserializer::new(bytes))
+    }
+
+    /// Decodes a product value, e.g., a row, of type `ty`
+    /// from the BSATN format in `bytes` into the arena `bump`.
+    pub fn decode_product<'de>(
+        bump: &'a Bump,
+        ty: WithTypespace<'_, ProductType>,
+        bytes: &mut impl BufReader<'de>,
+    ) -> Result<&'a [Self], DecodeError> {
+        InArena::new(bump, ty).deserialize(bsatn::Deserializer::new(bytes))
+    }
+
+    /// Copies the value out of the arena onto the heap.
+    pub fn to_value(&self) -> AlgebraicValue {
+        match *self {
+            Self::Sum(sum) => AlgebraicValue::Sum(sum.to_value()),
+            Self::Product(fields) => AlgebraicValue::Product(product_to_value(fields)),
+            Self::Bool(v) => AlgebraicValue::Bool(v),
+            Self::I8(v) => AlgebraicValue::I8(v),
+            Self::U8(v) => AlgebraicValue::U8(v),
+            Self::I16(v) => AlgebraicValue::I16(v),
+            Self::U16(v) => AlgebraicValue::U16(v),
+            Self::I32(v) => AlgebraicValue::I32(v),
+            Self::U32(v) => AlgebraicValue::U32(v),
+            Self::I64(v) => AlgebraicValue::I64(v),
+            Self::U64(v) => AlgebraicValue::U64(v),
+            Self::I128(v) => AlgebraicValue::I128(v),
+            Self::U128(v) => AlgebraicValue::U128(v),
+            Self::F32(v) => AlgebraicValue::F32(v),
+            Self::F64(v) => AlgebraicValue::F64(v),
+            Self::String(v) => AlgebraicValue::String(v.into()),
+            Self::Array(v) => AlgebraicValue::Array(v.to_value()),
+            Self::Map(v) => AlgebraicValue::Map(map_to_value(v)),
+        }
+    }
+}
+
+impl SumValueIn<'_> {
+    /// Copies the value out of the arena onto the heap.
+    pub fn to_value(&self) -> SumValue {
+        SumValue {
+            tag: self.tag,
+            value: Box::new(self.value.to_value()),
+        }
+    }
+}
+
+impl ArrayValueIn<'_> {
+    /// Copies the value out of the arena onto the heap.
+    pub fn to_value(&self) -> ArrayValue {
+        match *self {
+            Self::Sum(v) => ArrayValue::Sum(v.iter().map(SumValueIn::to_value).collect()),
+            Self::Product(v) => ArrayValue::Product(v.iter().map(|fields| product_to_value(fields)).collect()),
+            Self::Bool(v) => ArrayValue::Bool(v.to_vec()),
+            Self::I8(v) => ArrayValue::I8(v.to_vec()),
+            Self::U8(v) => ArrayValue::U8(v.to_vec()),
+            Self::I16(v) => ArrayValue::I16(v.to_vec()),
+            Self::U16(v) => ArrayValue::U16(v.to_vec()),
+            Self::I32(v) => ArrayValue::I32(v.to_vec()),
+            Self::U32(v) => ArrayValue::U32(v.to_vec()),
+            Self::I64(v) => ArrayValue::I64(v.to_vec()),
+            Self::U64(v) => ArrayValue::U64(v.to_vec()),
+            Self::I128(v) => ArrayValue::I128(v.to_vec()),
+            Self::U128(v) => ArrayValue::U128(v.to_vec()),
+            Self::F32(v) => ArrayValue::F32(v.to_vec()),
+            Self::F64(v) => ArrayValue::F64(v.to_vec()),
+            Self::String(v) => ArrayValue::String(v.iter().map(|&s| s.to_owned()).collect()),
+            Self::Array(v) => ArrayValue::Array(v.iter().map(ArrayValueIn::to_value).collect()),
+            Self::Map(v) => ArrayValue::Map(v.iter().map(|map| map_to_value(map)).collect()),
+        }
+    }
+}
+
+/// Copies the product value of `fields` out of the arena onto the heap.
+fn product_to_value(fields: &[AlgebraicValueIn<'_>]) -> ProductValue {
+    fields.iter().map(AlgebraicValueIn::to_value).collect()
+}
+
+/// Copies the map value of `pairs` out of the arena onto the heap.
+fn map_to_value(pairs: &[(AlgebraicValueIn<'_>, AlgebraicValueIn<'_>)]) -> MapValue {
+    pairs.iter().map(|(k, v)| (k.to_value(), v.to_value())).collect()
+}
+
+/// A seed deserializing a value of the type `ty` into the arena `bump`.
+///
+/// The types `T` supported are those that [`WithTypespace`] is a seed for,
+/// e.g., `AlgebraicType` and `ProductType`.
+pub struct InArena<'a, 'ts, T: ?Sized> {
+    /// The arena to allocate in.
+    bump: &'a Bump,
+    /// The type of the value to deserialize.
+    ty: WithTypespace<'ts, T>,
+}
+
+impl<T: ?Sized> Clone for InArena<'_, '_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for InArena<'_, '_, T> {}
+
+impl<'a, 'ts, T: ?Sized> InArena<'a, 'ts, T> {
+    /// Returns a seed deserializing a value of type `ty` into `bump`.
+    pub fn new(bump: &'a Bump, ty: WithTypespace<'ts, T>) -> Self {
+        Self { bump, ty }
+    }
+
+    /// Returns a seed deserializing a value of type `ty`, in the same context, into the same arena.
+    fn with<U>(self, ty: &'ts U) -> InArena<'a, 'ts, U> {
+        InArena::new(self.bump, self.ty.with(ty))
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, AlgebraicType> {
+    type Output = AlgebraicValueIn<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        match self.ty.ty() {
+            AlgebraicType::Sum(sum) => self.with(sum).deserialize(de).map(AlgebraicValueIn::Sum),
+            AlgebraicType::Product(prod) => self.with(prod).deserialize(de).map(AlgebraicValueIn::Product),
+            AlgebraicType::Builtin(b) => self.with(b).deserialize(de),
+            AlgebraicType::Ref(r) => InArena::new(self.bump, self.ty.resolve(*r)).deserialize(de),
+            AlgebraicType::Newtype(nt) => self.with(&*nt.inner).deserialize(de),
+        }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, BuiltinType> {
+    type Output = AlgebraicValueIn<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        Ok(match self.ty.ty() {
+            BuiltinType::Bool => AlgebraicValueIn::Bool(bool::deserialize(de)?),
+            BuiltinType::I8 => AlgebraicValueIn::I8(i8::deserialize(de)?),
+            BuiltinType::U8 => AlgebraicValueIn::U8(u8::deserialize(de)?),
+            BuiltinType::I16 => AlgebraicValueIn::I16(i16::deserialize(de)?),
+            BuiltinType::U16 => AlgebraicValueIn::U16(u16::deserialize(de)?),
+            BuiltinType::I32 => AlgebraicValueIn::I32(i32::deserialize(de)?),
+            BuiltinType::U32 => AlgebraicValueIn::U32(u32::deserialize(de)?),
+            BuiltinType::I64 => AlgebraicValueIn::I64(i64::deserialize(de)?),
+            BuiltinType::U64 => AlgebraicValueIn::U64(u64::deserialize(de)?),
+            BuiltinType::I128 => AlgebraicValueIn::I128(i128::deserialize(de)?),
+            BuiltinType::U128 => AlgebraicValueIn::U128(u128::deserialize(de)?),
+            BuiltinType::F32 => AlgebraicValueIn::F32(f32::deserialize(de)?.into()),
+            BuiltinType::F64 => AlgebraicValueIn::F64(f64::deserialize(de)?.into()),
+            BuiltinType::String => AlgebraicValueIn::String(StrIn(self.bump).deserialize(de)?),
+            BuiltinType::Array(ty) => AlgebraicValueIn::Array(self.with(ty).deserialize(de)?),
+            BuiltinType::Map(ty) => AlgebraicValueIn::Map(self.with(ty).deserialize(de)?),
+        })
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, SumType> {
+    type Output = SumValueIn<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        de.deserialize_sum(self)
+    }
+}
+
+impl<'de, 'a> SumVisitor<'de> for InArena<'a, '_, SumType> {
+    type Output = SumValueIn<'a>;
+
+    fn sum_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn variant_count(&self) -> Option<usize> {
+        Some(self.ty.ty().variants.len())
+    }
+
+    fn is_option(&self) -> bool {
+        self.ty.ty().as_option().is_some()
+    }
+
+    fn visit_sum<A: SumAccess<'de>>(self, data: A) -> Result<Self::Output, A::Error> {
+        // Let the typespace-aware visitor check the tag against the variants.
+        let (tag, data) = data.variant(self.ty)?;
+        let value = data.deserialize_seed(self.with(&self.ty.ty().variants[tag as usize].algebraic_type))?;
+        Ok(SumValueIn {
+            tag,
+            value: self.bump.alloc(value),
+        })
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, ProductType> {
+    type Output = &'a [AlgebraicValueIn<'a>];
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        de.deserialize_product(self)
+    }
+}
+
+impl<'de, 'a> ProductVisitor<'de> for InArena<'a, '_, ProductType> {
+    type Output = &'a [AlgebraicValueIn<'a>];
+
+    fn product_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn product_len(&self) -> usize {
+        self.ty.ty().elements.len()
+    }
+
+    fn visit_seq_product<A: SeqProductAccess<'de>>(self, mut tup: A) -> Result<Self::Output, A::Error> {
+        let elems = &self.ty.ty().elements;
+        let mut fields = BumpVec::with_capacity_in(elems.len(), self.bump);
+        for (i, el) in elems.iter().enumerate() {
+            let field = tup
+                .next_element_seed(self.with(&el.algebraic_type))
+                .map_err(|e| e.in_field(i, el.name()))?
+                .ok_or_else(|| Error::invalid_product_length(i, &self))?;
+            fields.push(field);
+        }
+        Ok(fields.into_bump_slice())
+    }
+
+    fn visit_named_product<A: NamedProductAccess<'de>>(self, mut tup: A) -> Result<Self::Output, A::Error> {
+        let elems = &*self.ty.ty().elements;
+        let mut fields = vec![None; elems.len()];
+        let kind = self.product_kind();
+
+        // Like `visit_named_product` for heap values, fields may come in any order.
+        for _ in 0..elems.len() {
+            let index = tup.get_field_ident(TupleNameVisitor { elems, kind })?.ok_or_else(|| {
+                let missing = fields.iter().position(|field| field.is_none()).unwrap();
+                Error::missing_field(missing, elems[missing].name(), &self)
+            })?;
+            let element = &elems[index];
+            let slot = &mut fields[index];
+            if slot.is_some() {
+                return Err(Error::duplicate_field(index, element.name(), &self));
+            }
+            *slot = Some(
+                tup.get_field_value_seed(self.with(&element.algebraic_type))
+                    .map_err(|e| e.in_field(index, element.name()))?,
+            );
+        }
+
+        let fields = fields
+            .into_iter()
+            .map(|field| field.unwrap_or_else(|| unreachable!("visit_named_product")));
+        Ok(self.bump.alloc_slice_fill_iter(fields))
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, ArrayType> {
+    type Output = ArrayValueIn<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        let bump = self.bump;
+        let mut ty = &*self.ty.ty().elem_ty;
+
+        // Loop, resolving `Ref`s and unwrapping newtypes, until we reach a structural type.
+        loop {
+            break match ty {
+                AlgebraicType::Ref(r) => {
+                    ty = self.ty.resolve(*r).ty();
+                    continue;
+                }
+                AlgebraicType::Newtype(nt) => {
+                    ty = &nt.inner;
+                    continue;
+                }
+                AlgebraicType::Sum(ty) => SliceIn(bump, self.with(ty)).deserialize(de).map(ArrayValueIn::Sum),
+                AlgebraicType::Product(ty) => SliceIn(bump, self.with(ty)).deserialize(de).map(ArrayValueIn::Product),
+                AlgebraicType::Builtin(BuiltinType::Bool) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::Bool)
+                }
+                AlgebraicType::Builtin(BuiltinType::I8) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::I8)
+                }
+                AlgebraicType::Builtin(BuiltinType::U8) => de.deserialize_bytes(BytesIn(bump)).map(ArrayValueIn::U8),
+                AlgebraicType::Builtin(BuiltinType::I16) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::I16)
+                }
+                AlgebraicType::Builtin(BuiltinType::U16) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::U16)
+                }
+                AlgebraicType::Builtin(BuiltinType::I32) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::I32)
+                }
+                AlgebraicType::Builtin(BuiltinType::U32) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::U32)
+                }
+                AlgebraicType::Builtin(BuiltinType::I64) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::I64)
+                }
+                AlgebraicType::Builtin(BuiltinType::U64) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::U64)
+                }
+                AlgebraicType::Builtin(BuiltinType::I128) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::I128)
+                }
+                AlgebraicType::Builtin(BuiltinType::U128) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::U128)
+                }
+                AlgebraicType::Builtin(BuiltinType::F32) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::F32)
+                }
+                AlgebraicType::Builtin(BuiltinType::F64) => {
+                    SliceIn(bump, PhantomData).deserialize(de).map(ArrayValueIn::F64)
+                }
+                AlgebraicType::Builtin(BuiltinType::String) => {
+                    SliceIn(bump, StrIn(bump)).deserialize(de).map(ArrayValueIn::String)
+                }
+                AlgebraicType::Builtin(BuiltinType::Array(ty)) => {
+                    SliceIn(bump, self.with(ty)).deserialize(de).map(ArrayValueIn::Array)
+                }
+                AlgebraicType::Builtin(BuiltinType::Map(ty)) => {
+                    SliceIn(bump, self.with(ty)).deserialize(de).map(ArrayValueIn::Map)
+                }
+            };
+        }
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for InArena<'a, '_, MapType> {
+    type Output = MapValueIn<'a>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        let MapType { key_ty, ty } = self.ty.ty();
+        de.deserialize_map_seed(self, self.with(&**key_ty), self.with(&**ty))
+    }
+}
+
+impl<'de, 'a> MapVisitor<'de, AlgebraicValueIn<'a>, AlgebraicValueIn<'a>> for InArena<'a, '_, MapType> {
+    type Output = MapValueIn<'a>;
+
+    fn visit<A: MapAccess<'de, Key = AlgebraicValueIn<'a>, Value = AlgebraicValueIn<'a>>>(
+        self,
+        mut map: A,
+    ) -> Result<Self::Output, A::Error> {
+        let mut pairs = BumpVec::with_capacity_in(map.size_hint().unwrap_or(0), self.bump);
+        while let Some(pair) = map.next_entry()? {
+            pairs.push(pair);
+        }
+        Ok(pairs.into_bump_slice())
+    }
+}
+
+/// A seed deserializing an array of elements, each with the seed `S`, into the arena `'a`.
+struct SliceIn<'a, S>(&'a Bump, S);
+
+impl<'de, 'a, S: DeserializeSeed<'de> + Clone> DeserializeSeed<'de> for SliceIn<'a, S>
+where
+    S::Output: 'a,
+{
+    type Output = &'a [S::Output];
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        de.deserialize_array_seed(ArrayIn(self.0), self.1)
+    }
+}
+
+/// The visitor collects the elements of an array into the arena `'a`.
+struct ArrayIn<'a>(&'a Bump);
+
+impl<'de, 'a, T: 'a> ArrayVisitor<'de, T> for ArrayIn<'a> {
+    type Output = &'a [T];
+
+    fn visit<A: ArrayAccess<'de, Element = T>>(self, mut array: A) -> Result<Self::Output, A::Error> {
+        let mut elems = BumpVec::with_capacity_in(array.size_hint().unwrap_or(0), self.0);
+        while let Some(elem) = array.next_element()? {
+            elems.push(elem);
+        }
+        Ok(elems.into_bump_slice())
+    }
+}
+
+/// A seed, and a visitor, copying a string into the arena `'a`.
+#[derive(Clone, Copy)]
+struct StrIn<'a>(&'a Bump);
+
+impl<'de, 'a> DeserializeSeed<'de> for StrIn<'a> {
+    type Output = &'a str;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Output, D::Error> {
+        de.deserialize_str(self)
+    }
+}
+
+impl<'a> SliceVisitor<'_, str> for StrIn<'a> {
+    type Output = &'a str;
+
+    fn visit<E: Error>(self, slice: &str) -> Result<Self::Output, E> {
+        Ok(self.0.alloc_str(slice))
+    }
+}
+
+/// The visitor copies a byte string into the arena `'a`.
+struct BytesIn<'a>(&'a Bump);
+
+impl<'a> SliceVisitor<'_, [u8]> for BytesIn<'a> {
+    type Output = &'a [u8];
+
+    fn visit<E: Error>(self, slice: &[u8]) -> Result<Self::Output, E> {
+        Ok(self.0.alloc_slice_copy(slice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, ProductTypeElement, SumTypeVariant, Typespace};
+
+    fn schema() -> ProductType {
+        ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::U32), "scores"),
+            ProductTypeElement::new_named(AlgebraicType::bytes(), "blob"),
+            ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::String), "tags"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "nick"),
+            ProductTypeElement::new_named(AlgebraicType::map(AlgebraicType::U8, AlgebraicType::String), "by_rank"),
+            ProductTypeElement::new_named(
+                AlgebraicType::array(AlgebraicType::sum(vec![
+                    SumTypeVariant::new_named(AlgebraicType::I32, "num"),
+                    SumTypeVariant::unit("none"),
+                ])),
+                "mixed",
+            ),
+        ])
+    }
+
+    fn row(i: u64) -> ProductValue {
+        let by_rank = [(2u8, "second"), (1, "first")]
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect::<MapValue>();
+        let mixed = vec![SumValue {
+            tag: 0,
+            value: Box::new(AlgebraicValue::I32(-1)),
+        }];
+        product![
+            i,
+            format!("row {i}"),
+            AlgebraicValue::ArrayOf(vec![1u32, 2, 3]),
+            AlgebraicValue::Bytes(vec![0xde, 0xad]),
+            AlgebraicValue::ArrayOf(vec!["a".to_owned(), "b".to_owned()]),
+            AlgebraicValue::OptionSome(AlgebraicValue::String("nick".into())),
+            AlgebraicValue::Map(by_rank),
+            AlgebraicValue::ArrayOf(mixed)
+        ]
+    }
+
+    #[test]
+    fn decodes_like_heap_values() {
+        let ts = Typespace::default();
+        let bump = Bump::new();
+        let ty = schema();
+        for i in 0..3 {
+            let bytes = bsatn::to_vec(&row(i)).unwrap();
+            let fields = AlgebraicValueIn::decode_product(&bump, WithTypespace::new(&ts, &ty), &mut &*bytes).unwrap();
+            assert_eq!(product_to_value(fields), row(i));
+            assert_eq!(fields[1], AlgebraicValueIn::String(&format!("row {i}")));
+        }
+    }
+
+    #[test]
+    fn decodes_through_refs() {
+        let ts = Typespace::new(vec![AlgebraicType::String]);
+        let ty = AlgebraicType::array(AlgebraicType::Ref(crate::AlgebraicTypeRef(0)));
+        let bump = Bump::new();
+        let bytes = bsatn::to_vec(&vec!["x".to_owned(), "y".to_owned()]).unwrap();
+        let value = AlgebraicValueIn::decode(&bump, WithTypespace::new(&ts, &ty), &mut &*bytes).unwrap();
+        assert_eq!(value, AlgebraicValueIn::Array(ArrayValueIn::String(&["x", "y"])));
+    }
+
+    #[test]
+    fn reports_errors() {
+        let ts = Typespace::default();
+        let ty = schema();
+        let bump = Bump::new();
+        let bytes = bsatn::to_vec(&row(0)).unwrap();
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(AlgebraicValueIn::decode_product(&bump, WithTypespace::new(&ts, &ty), &mut &*truncated).is_err());
+    }
+}
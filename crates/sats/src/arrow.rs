@@ -0,0 +1,570 @@
+//! Converting tables of [`ProductValue`]s to and from Arrow [`RecordBatch`]es,
+//! e.g., to hand query results to analytics tools like polars or DataFusion.
+//!
+//! The columns of a batch are the fields of the rows' product type,
+//! with their types mapped as follows:
+//!
+//! | SATS type       | Arrow type                            |
+//! | --------------- | ------------------------------------- |
+//! | `Bool`          | `Boolean`                             |
+//! | `I8` to `U64`   | `Int8` to `UInt64`                    |
+//! | `I128`, `U128`  | `FixedSizeBinary(16)`, little-endian  |
+//! | `F32`, `F64`    | `Float32`, `Float64`                  |
+//! | `String`        | `Utf8`                                |
+//! | `Array<U8>`     | `Binary`                              |
+//! | `Option<T>`     | `T`, nullable                         |
+//! | products        | `Struct`                              |
+//!
+//! Any `AlgebraicType::Ref`s are resolved in a typespace,
+//! and newtypes are mapped as the types they wrap.
+//! Other sums, other arrays, and maps are not supported yet.
+
+use std::sync::Arc;
+
+use ::arrow::array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeBinaryArray, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, StringArray, StructArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use ::arrow::buffer::NullBuffer;
+use ::arrow::datatypes::{DataType, Field, Fields, Schema};
+use ::arrow::error::ArrowError;
+use ::arrow::record_batch::{RecordBatch, RecordBatchOptions};
+
+use crate::typespace::UnresolvedRef;
+use crate::{AlgebraicType, AlgebraicTypeRef, AlgebraicValue, BuiltinType, ProductType, ProductValue, Typespace};
+
+/// An error converting between [`ProductValue`]s and a [`RecordBatch`].
+///
+/// Columns are named by their path of field names, e.g., `"address.city"`.
+#[derive(thiserror::Error, Debug)]
+pub enum ConvertError {
+    /// The type of a column has no supported counterpart in Arrow.
+    #[error("Column {column:?}: Arrow conversion of {what} is unsupported")]
+    Unsupported { column: String, what: &'static str },
+    /// The type of a column refers to a type that isn't in the typespace, or only to refs in a cycle.
+    #[error("Column {column:?}: type reference {r} not found in the typespace")]
+    UnresolvedRef { column: String, r: AlgebraicTypeRef },
+    /// A row had a different number of fields than the product type.
+    #[error("Row {row} has {found} fields, expected {expected}")]
+    RowLength { row: usize, expected: usize, found: usize },
+    /// A value in a column didn't fit the column's type.
+    #[error("Column {column:?}: value does not fit the column's type")]
+    MismatchedValue { column: String },
+    /// The record batch had a different number of columns than the product type.
+    #[error("Record batch has {found} columns, expected {expected}")]
+    ColumnCount { expected: usize, found: usize },
+    /// An Arrow array was of a different type than the one the column's type maps to.
+    #[error("Column {column:?}: Arrow array is of type {found}, expected {expected}")]
+    MismatchedArray {
+        column: String,
+        expected: DataType,
+        found: DataType,
+    },
+    /// An Arrow array had a null for a value that isn't an option.
+    #[error("Column {column:?}: null in a column that is not an option")]
+    UnexpectedNull { column: String },
+    /// Arrow rejected the arrays built.
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+}
+
+/// Returns the Arrow schema of a batch with rows of type `ty` in the typespace `ts`.
+pub fn to_schema(ty: &ProductType, ts: &Typespace) -> Result<Schema, ConvertError> {
+    fields(ty, ts, "").map(Schema::new)
+}
+
+/// Converts `rows`, of type `ty` in the typespace `ts`, into a [`RecordBatch`] with a column per field.
+pub fn to_record_batch(rows: &[ProductValue], ty: &ProductType, ts: &Typespace) -> Result<RecordBatch, ConvertError> {
+    let schema = Arc::new(to_schema(ty, ts)?);
+    let expected = ty.elements.len();
+    if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.elements.len() != expected) {
+        let found = r.elements.len();
+        return Err(ConvertError::RowLength { row, expected, found });
+    }
+
+    let columns = (ty.elements.iter().zip(schema.fields()).enumerate())
+        .map(|(i, (elem, field))| {
+            let values = rows.iter().map(|r| Some(&r.elements[i])).collect::<Vec<_>>();
+            build_column(field, &elem.algebraic_type, ts, &values, field.name())
+        })
+        .collect::<Result<_, _>>()?;
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(schema, columns, &options)?)
+}
+
+/// Converts `batch` back into rows of type `ty` in the typespace `ts`.
+///
+/// The columns of `batch` are matched to the fields of `ty` by position.
+/// Nulls are accepted only in columns of option type,
+/// but any column may be marked as nullable in the schema of `batch`.
+pub fn from_record_batch(
+    batch: &RecordBatch,
+    ty: &ProductType,
+    ts: &Typespace,
+) -> Result<Vec<ProductValue>, ConvertError> {
+    let (expected, found) = (ty.elements.len(), batch.num_columns());
+    if expected != found {
+        return Err(ConvertError::ColumnCount { expected, found });
+    }
+
+    let schema = to_schema(ty, ts)?;
+    let columns = (ty.elements.iter().zip(schema.fields()).zip(batch.columns()))
+        .map(|((elem, field), array)| read_column(field, &elem.algebraic_type, ts, array, field.name()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let names = schema.fields().iter().map(|f| f.name().as_str()).collect::<Vec<_>>();
+    let rows = rows_of(columns, batch.num_rows(), &names, |_| true)?;
+    Ok(rows.into_iter().flatten().collect())
+}
+
+/// Returns the field name of the `i`th element of a product type, or `i` itself if the element is unnamed.
+fn field_name(i: usize, name: Option<&str>) -> String {
+    name.map_or_else(|| i.to_string(), str::to_owned)
+}
+
+/// Returns the path of the field `name` within the column at `path`.
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+/// Returns the type `ty` after following any `Ref`s and newtypes around it in `ts`.
+fn resolve<'a>(ts: &'a Typespace, ty: &'a AlgebraicType, path: &str) -> Result<&'a AlgebraicType, ConvertError> {
+    ts.resolve_value_head(ty)
+        .map_err(|UnresolvedRef(r)| ConvertError::UnresolvedRef {
+            column: path.to_owned(),
+            r,
+        })
+}
+
+/// Returns the type of the `some` variant of `ty` if `ty` is an option type.
+fn option_inner(ty: &AlgebraicType) -> Option<&AlgebraicType> {
+    match ty {
+        AlgebraicType::Sum(sum) => sum.as_option(),
+        _ => None,
+    }
+}
+
+/// Returns the Arrow fields for the elements of the product type `ty` at `path`.
+fn fields(ty: &ProductType, ts: &Typespace, path: &str) -> Result<Fields, ConvertError> {
+    (ty.elements.iter().enumerate())
+        .map(|(i, elem)| {
+            let name = field_name(i, elem.name.as_deref());
+            let path = join(path, &name);
+            let ty = resolve(ts, &elem.algebraic_type, &path)?;
+            Ok(match option_inner(ty) {
+                Some(inner) => {
+                    let inner = resolve(ts, inner, &path)?;
+                    if option_inner(inner).is_some() {
+                        return Err(unsupported(&path, "nested options"));
+                    }
+                    Field::new(name, data_type(inner, ts, &path)?, true)
+                }
+                None => Field::new(name, data_type(ty, ts, &path)?, false),
+            })
+        })
+        .collect()
+}
+
+/// Returns the Arrow type for values of the resolved type `ty` at `path`.
+fn data_type(ty: &AlgebraicType, ts: &Typespace, path: &str) -> Result<DataType, ConvertError> {
+    Ok(match ty {
+        AlgebraicType::Builtin(builtin) => match builtin {
+            BuiltinType::Bool => DataType::Boolean,
+            BuiltinType::I8 => DataType::Int8,
+            BuiltinType::U8 => DataType::UInt8,
+            BuiltinType::I16 => DataType::Int16,
+            BuiltinType::U16 => DataType::UInt16,
+            BuiltinType::I32 => DataType::Int32,
+            BuiltinType::U32 => DataType::UInt32,
+            BuiltinType::I64 => DataType::Int64,
+            BuiltinType::U64 => DataType::UInt64,
+            BuiltinType::I128 | BuiltinType::U128 => DataType::FixedSizeBinary(16),
+            BuiltinType::F32 => DataType::Float32,
+            BuiltinType::F64 => DataType::Float64,
+            BuiltinType::String => DataType::Utf8,
+            BuiltinType::Array(_) if ty.is_bytes() => DataType::Binary,
+            BuiltinType::Array(_) => return Err(unsupported(path, "arrays other than byte arrays")),
+            BuiltinType::Map(_) => return Err(unsupported(path, "maps")),
+        },
+        // Arrow can't tell the length of a struct array without any children.
+        AlgebraicType::Product(ty) if ty.elements.is_empty() => return Err(unsupported(path, "empty products")),
+        AlgebraicType::Product(ty) => DataType::Struct(fields(ty, ts, path)?),
+        AlgebraicType::Sum(_) => return Err(unsupported(path, "sums other than options")),
+        AlgebraicType::Ref(_) | AlgebraicType::Newtype(_) => data_type(resolve(ts, ty, path)?, ts, path)?,
+    })
+}
+
+fn unsupported(path: &str, what: &'static str) -> ConvertError {
+    let column = path.to_owned();
+    ConvertError::Unsupported { column, what }
+}
+
+fn mismatched_value(path: &str) -> ConvertError {
+    let column = path.to_owned();
+    ConvertError::MismatchedValue { column }
+}
+
+/// Builds the Arrow array for the `values` of type `ty` in the column `field` at `path`.
+///
+/// A `None` in `values` is a value missing as its parent struct is null.
+fn build_column(
+    field: &Field,
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    values: &[Option<&AlgebraicValue>],
+    path: &str,
+) -> Result<ArrayRef, ConvertError> {
+    let ty = resolve(ts, ty, path)?;
+    match option_inner(ty) {
+        Some(inner) => {
+            let values = (values.iter())
+                .map(|v| match v.map(|v| v.as_sum()) {
+                    None => Ok(None),
+                    Some(Some(sum)) if sum.tag == 0 => Ok(Some(&*sum.value)),
+                    Some(Some(sum)) if sum.tag == 1 => Ok(None),
+                    Some(_) => Err(mismatched_value(path)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            build_values(field.data_type(), resolve(ts, inner, path)?, ts, &values, path)
+        }
+        None => build_values(field.data_type(), ty, ts, values, path),
+    }
+}
+
+/// Builds the Arrow array of type `data_type` for the `values` of the resolved type `ty` at `path`,
+/// where a `None` is a null.
+fn build_values(
+    data_type: &DataType,
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    values: &[Option<&AlgebraicValue>],
+    path: &str,
+) -> Result<ArrayRef, ConvertError> {
+    /// Collects the `values` into an array of type `$arr`, extracting each with `$get`.
+    macro_rules! collect {
+        ($arr:ty, |$v:ident| $get:expr) => {{
+            let array = (values.iter())
+                .map(|v| v.map(|$v| $get.ok_or_else(|| mismatched_value(path))).transpose())
+                .collect::<Result<$arr, _>>()?;
+            Arc::new(array)
+        }};
+    }
+
+    Ok(match data_type {
+        DataType::Boolean => collect!(BooleanArray, |v| v.as_bool().copied()),
+        DataType::Int8 => collect!(Int8Array, |v| v.as_i8().copied()),
+        DataType::UInt8 => collect!(UInt8Array, |v| v.as_u8().copied()),
+        DataType::Int16 => collect!(Int16Array, |v| v.as_i16().copied()),
+        DataType::UInt16 => collect!(UInt16Array, |v| v.as_u16().copied()),
+        DataType::Int32 => collect!(Int32Array, |v| v.as_i32().copied()),
+        DataType::UInt32 => collect!(UInt32Array, |v| v.as_u32().copied()),
+        DataType::Int64 => collect!(Int64Array, |v| v.as_i64().copied()),
+        DataType::UInt64 => collect!(UInt64Array, |v| v.as_u64().copied()),
+        DataType::Float32 => collect!(Float32Array, |v| v.as_f32().map(|&x| f32::from(x))),
+        DataType::Float64 => collect!(Float64Array, |v| v.as_f64().map(|&x| f64::from(x))),
+        DataType::Utf8 => collect!(StringArray, |v| v.as_string()),
+        DataType::Binary => collect!(BinaryArray, |v| v.as_bytes()),
+        DataType::FixedSizeBinary(16) => {
+            let bytes = (values.iter())
+                .map(|v| {
+                    v.map(|v| match (ty, v) {
+                        (AlgebraicType::Builtin(BuiltinType::I128), AlgebraicValue::I128(x)) => Ok(x.to_le_bytes()),
+                        (AlgebraicType::Builtin(BuiltinType::U128), AlgebraicValue::U128(x)) => Ok(x.to_le_bytes()),
+                        _ => Err(mismatched_value(path)),
+                    })
+                    .transpose()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                bytes.into_iter(),
+                16,
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let AlgebraicType::Product(ty) = ty else {
+                return Err(mismatched_value(path));
+            };
+            let products = (values.iter())
+                .map(|v| {
+                    v.map(|v| match v.as_product() {
+                        Some(prod) if prod.elements.len() == ty.elements.len() => Ok(prod),
+                        _ => Err(mismatched_value(path)),
+                    })
+                    .transpose()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let children = (ty.elements.iter().zip(fields).enumerate())
+                .map(|(i, (elem, field))| {
+                    let values = products.iter().map(|p| p.map(|p| &p.elements[i])).collect::<Vec<_>>();
+                    build_column(field, &elem.algebraic_type, ts, &values, &join(path, field.name()))
+                })
+                .collect::<Result<_, _>>()?;
+            let nulls = values
+                .iter()
+                .any(Option::is_none)
+                .then(|| NullBuffer::from(values.iter().map(Option::is_some).collect::<Vec<_>>()));
+            Arc::new(StructArray::try_new(fields.clone(), children, nulls)?)
+        }
+        // `data_type` was derived from `ty` by `fields`, which only produces the types above.
+        _ => unreachable!("unexpected Arrow type {data_type}"),
+    })
+}
+
+/// Reads the values of type `ty` in the column `field` at `path` from `array`.
+///
+/// A `None` in the result is a null that isn't an option, e.g., a field of a null struct.
+fn read_column(
+    field: &Field,
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    array: &dyn Array,
+    path: &str,
+) -> Result<Vec<Option<AlgebraicValue>>, ConvertError> {
+    let ty = resolve(ts, ty, path)?;
+    match option_inner(ty) {
+        Some(inner) => {
+            let values = read_values(field.data_type(), resolve(ts, inner, path)?, ts, array, path)?;
+            let options = values.into_iter().map(|v| match v {
+                Some(v) => AlgebraicValue::OptionSome(v),
+                None => AlgebraicValue::OptionNone(),
+            });
+            Ok(options.map(Some).collect())
+        }
+        None => read_values(field.data_type(), ty, ts, array, path),
+    }
+}
+
+/// Reads the values of the resolved type `ty` from `array`, which should be of type `data_type`,
+/// where a null becomes `None`.
+fn read_values(
+    data_type: &DataType,
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    array: &dyn Array,
+    path: &str,
+) -> Result<Vec<Option<AlgebraicValue>>, ConvertError> {
+    let mismatched_array = || ConvertError::MismatchedArray {
+        column: path.to_owned(),
+        expected: data_type.clone(),
+        found: array.data_type().clone(),
+    };
+
+    /// Reads every element of `array` as an `$arr`, converting each with `$conv`.
+    macro_rules! read {
+        ($arr:ty, $conv:expr) => {{
+            let array = array.as_any().downcast_ref::<$arr>().ok_or_else(mismatched_array)?;
+            array.iter().map(|v| v.map($conv)).collect()
+        }};
+    }
+
+    Ok(match data_type {
+        DataType::Boolean => read!(BooleanArray, AlgebraicValue::Bool),
+        DataType::Int8 => read!(Int8Array, AlgebraicValue::I8),
+        DataType::UInt8 => read!(UInt8Array, AlgebraicValue::U8),
+        DataType::Int16 => read!(Int16Array, AlgebraicValue::I16),
+        DataType::UInt16 => read!(UInt16Array, AlgebraicValue::U16),
+        DataType::Int32 => read!(Int32Array, AlgebraicValue::I32),
+        DataType::UInt32 => read!(UInt32Array, AlgebraicValue::U32),
+        DataType::Int64 => read!(Int64Array, AlgebraicValue::I64),
+        DataType::UInt64 => read!(UInt64Array, AlgebraicValue::U64),
+        DataType::Float32 => read!(Float32Array, |x: f32| AlgebraicValue::F32(x.into())),
+        DataType::Float64 => read!(Float64Array, |x: f64| AlgebraicValue::F64(x.into())),
+        DataType::Utf8 => read!(StringArray, |s: &str| AlgebraicValue::String(s.into())),
+        DataType::Binary => read!(BinaryArray, |b: &[u8]| AlgebraicValue::Bytes(b.to_vec())),
+        DataType::FixedSizeBinary(16) => {
+            let array = (array.as_any().downcast_ref::<FixedSizeBinaryArray>())
+                .filter(|a| a.value_length() == 16)
+                .ok_or_else(mismatched_array)?;
+            let signed = matches!(ty, AlgebraicType::Builtin(BuiltinType::I128));
+            array
+                .iter()
+                .map(|v| {
+                    v.map(|b| {
+                        let b = b.try_into().unwrap();
+                        if signed {
+                            AlgebraicValue::I128(i128::from_le_bytes(b))
+                        } else {
+                            AlgebraicValue::U128(u128::from_le_bytes(b))
+                        }
+                    })
+                })
+                .collect()
+        }
+        DataType::Struct(fields) => {
+            let AlgebraicType::Product(ty) = ty else {
+                return Err(mismatched_array());
+            };
+            let array = (array.as_any().downcast_ref::<StructArray>())
+                .filter(|a| a.num_columns() == fields.len())
+                .ok_or_else(mismatched_array)?;
+            let names = fields.iter().map(|f| join(path, f.name())).collect::<Vec<_>>();
+            let children = (ty.elements.iter().zip(fields).zip(array.columns()).zip(&names))
+                .map(|(((elem, field), child), path)| read_column(field, &elem.algebraic_type, ts, child, path))
+                .collect::<Result<Vec<_>, _>>()?;
+            let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+            let products = rows_of(children, array.len(), &names, |row| array.is_valid(row))?;
+            products.into_iter().map(|prod| prod.map(Into::into)).collect()
+        }
+        // `data_type` was derived from `ty` by `fields`, which only produces the types above.
+        _ => unreachable!("unexpected Arrow type {data_type}"),
+    })
+}
+
+/// Zips the `columns`, each of `len` values and named by `names`, into products,
+/// or `None` for the rows that aren't `valid`, e.g., as their struct is null.
+fn rows_of(
+    mut columns: Vec<Vec<Option<AlgebraicValue>>>,
+    len: usize,
+    names: &[&str],
+    valid: impl Fn(usize) -> bool,
+) -> Result<Vec<Option<ProductValue>>, ConvertError> {
+    (0..len)
+        .map(|row| {
+            if !valid(row) {
+                return Ok(None);
+            }
+            (columns.iter_mut().zip(names))
+                .map(|(column, name)| {
+                    column[row].take().ok_or_else(|| ConvertError::UnexpectedNull {
+                        column: (*name).to_owned(),
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map(Some)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, ProductTypeElement, SumTypeVariant};
+
+    /// Returns the typespace and the type of a table with nulls, bytes, and a nested struct,
+    /// where the struct is behind a `Ref`.
+    fn table_type() -> (Typespace, ProductType) {
+        let mut ts = Typespace::new(vec![]);
+        let address = ts.add(AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::String, "city"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::U32), "zip"),
+        ]));
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::F64), "score"),
+            ProductTypeElement::new_named(AlgebraicType::I128, "balance"),
+            ProductTypeElement::new_named(AlgebraicType::bytes(), "avatar"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::Ref(address)), "address"),
+        ]);
+        (ts, ty)
+    }
+
+    #[test]
+    fn schema_mapping() {
+        let (ts, ty) = table_type();
+        let address = Fields::from(vec![
+            Field::new("city", DataType::Utf8, false),
+            Field::new("zip", DataType::UInt32, true),
+        ]);
+        let expected = Schema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("score", DataType::Float64, true),
+            Field::new("balance", DataType::FixedSizeBinary(16), false),
+            Field::new("avatar", DataType::Binary, false),
+            Field::new("address", DataType::Struct(address), true),
+        ]);
+        assert_eq!(to_schema(&ty, &ts).unwrap(), expected);
+
+        // Unnamed fields are named by their position.
+        let unnamed = ProductType::new(vec![AlgebraicType::Bool.into()]);
+        let schema = to_schema(&unnamed, &ts).unwrap();
+        assert_eq!(schema.field(0), &Field::new("0", DataType::Boolean, false));
+    }
+
+    #[test]
+    fn round_trip() {
+        let (ts, ty) = table_type();
+        let address = |city: &str, zip: Option<u32>| {
+            let zip = zip.map_or_else(AlgebraicValue::OptionNone, |z| AlgebraicValue::OptionSome(z.into()));
+            AlgebraicValue::OptionSome(product![city, zip].into())
+        };
+        let rows = vec![
+            product![
+                1u64,
+                "alice",
+                AlgebraicValue::OptionSome(2.5f64.into()),
+                -7i128,
+                AlgebraicValue::Bytes(vec![1, 2, 3]),
+                address("Stockholm", Some(11122))
+            ],
+            product![
+                2u64,
+                "",
+                AlgebraicValue::OptionNone(),
+                i128::MAX,
+                AlgebraicValue::Bytes(vec![]),
+                AlgebraicValue::OptionNone()
+            ],
+            product![
+                3u64,
+                "carol",
+                AlgebraicValue::OptionSome((-0.0f64).into()),
+                0i128,
+                AlgebraicValue::Bytes(vec![0; 5]),
+                address("", None)
+            ],
+        ];
+
+        let batch = to_record_batch(&rows, &ty, &ts).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.column(2).null_count(), 1);
+        assert_eq!(batch.column(5).null_count(), 1);
+        let names = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(names.value(1), "");
+        assert!(names.is_valid(1));
+
+        assert_eq!(from_record_batch(&batch, &ty, &ts).unwrap(), rows);
+        assert_eq!(to_record_batch(&[], &ty, &ts).unwrap().num_rows(), 0);
+    }
+
+    #[test]
+    fn unsupported_and_mismatched() {
+        let ts = Typespace::new(vec![]);
+        let sum = AlgebraicType::sum(vec![
+            SumTypeVariant::new_named(AlgebraicType::U8, "a"),
+            SumTypeVariant::new_named(AlgebraicType::String, "b"),
+        ]);
+        let ty = ProductType::new(vec![ProductTypeElement::new_named(
+            AlgebraicType::product(vec![ProductTypeElement::new_named(sum, "choice")]),
+            "outer",
+        )]);
+        let err = to_schema(&ty, &ts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Column "outer.choice": Arrow conversion of sums other than options is unsupported"#
+        );
+        let map = ProductType::new(vec![AlgebraicType::map(AlgebraicType::U8, AlgebraicType::U8).into()]);
+        assert!(matches!(
+            to_schema(&map, &ts),
+            Err(ConvertError::Unsupported { what: "maps", .. })
+        ));
+
+        // A value that doesn't fit its column's type.
+        let ty = ProductType::new(vec![ProductTypeElement::new_named(AlgebraicType::U32, "n")]);
+        let err = to_record_batch(&[product!["not a number"]], &ty, &ts).unwrap_err();
+        assert!(matches!(err, ConvertError::MismatchedValue { column } if column == "n"));
+
+        // A batch whose column has a type other than the one expected.
+        let batch = to_record_batch(&[product![1u32]], &ty, &ts).unwrap();
+        let other = ProductType::new(vec![ProductTypeElement::new_named(AlgebraicType::String, "n")]);
+        let err = from_record_batch(&batch, &other, &ts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Column "n": Arrow array is of type UInt32, expected Utf8"#
+        );
+    }
+}
@@ -1,15 +1,55 @@
 use crate::buffer::{BufReader, BufWriter};
-use crate::de::{Deserialize, DeserializeSeed};
+use crate::de::error::DeserializeError;
+use crate::de::{Deserialize, DeserializeSeed, Seed, ValueSeed};
 use crate::ser::Serialize;
-use crate::Typespace;
+use crate::{AlgebraicType, AlgebraicValue, ProductType, ProductValue, Typespace, Value, WithTypespace};
 
+#[cfg(feature = "compress")]
+pub mod compression;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
 pub mod de;
+#[cfg(feature = "compress")]
+pub mod dictionary_encode;
+#[cfg(feature = "frame")]
+pub mod frame;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "bytemuck")]
+mod pod;
 pub mod ser;
+mod size;
+pub(crate) mod skip;
+#[cfg(any(feature = "hex", feature = "base64"))]
+pub mod text;
+#[cfg(feature = "varint")]
+pub mod varint;
+pub mod writer_pool;
+#[cfg(feature = "zerocopy")]
+pub mod zerocopy;
 
+#[cfg(feature = "compress")]
+pub use compression::{decode_compressed, decode_compressed_with_limit, encode_compressed, CompressionAlgo};
+#[cfg(feature = "concurrent")]
+pub use concurrent::{DecoderState, SchemaHash, SharedDecoderCache};
 pub use de::Deserializer;
+#[cfg(feature = "compress")]
+pub use dictionary_encode::{dictionary_decode_strings, dictionary_encode_strings};
+#[cfg(feature = "rayon")]
+pub use parallel::{array_to_vec_parallel, encode_rows_parallel, encode_rows_parallel_in, to_vec_parallel};
 pub use ser::Serializer;
+#[cfg(any(feature = "hex", feature = "base64"))]
+pub use text::TextDecodeError;
+#[cfg(feature = "base64")]
+pub use text::{from_base64, to_base64, Base64Bsatn};
+#[cfg(feature = "hex")]
+pub use text::{from_hex, to_hex, HexBsatn};
+#[cfg(feature = "varint")]
+pub use varint::{to_vec_varint, to_writer_varint, VarintBsatnSerializer};
 
-pub use crate::buffer::DecodeError;
+pub use crate::buffer::{DecodeError, ErrorKind, PathSegment};
 
 /// Serialize `value` into the buffered writer `w` in the BSATN format.
 #[tracing::instrument(skip_all)]
@@ -18,8 +58,25 @@ pub fn to_writer<W: BufWriter, T: Serialize + ?Sized>(w: &mut W, value: &T) -> R
 }
 
 /// Serialize `value` into a `Vec<u8>` in the BSATN format.
+///
+/// When the length of the encoding can be computed cheaply,
+/// i.e., when `value` has no arrays or maps with elements to visit one by one,
+/// e.g., a row of numbers and strings, the vector is allocated once with exactly that capacity.
+/// Otherwise, the vector grows as the value is written.
+/// See [`to_vec_exact`] to allocate it exactly once regardless.
 pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ser::BsatnError> {
-    let mut v = Vec::new();
+    let mut v = Vec::with_capacity(size::static_encoded_len(value).unwrap_or(0));
+    to_writer(&mut v, value)?;
+    Ok(v)
+}
+
+/// Serialize `value` into a `Vec<u8>` in the BSATN format, allocating it exactly once.
+///
+/// Unlike [`to_vec`], this traverses `value` twice,
+/// first to compute the length of the encoding and then to write it,
+/// which pays off for large values whose vector would otherwise be reallocated many times.
+pub fn to_vec_exact<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, ser::BsatnError> {
+    let mut v = Vec::with_capacity(size::encoded_len(value).unwrap_or(0));
     to_writer(&mut v, value)?;
     Ok(v)
 }
@@ -35,6 +92,215 @@ pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, Decod
     from_reader(&mut &*bytes)
 }
 
+/// Deserialize from the BSATN format in the buffered `reader` into the storage of `seed`.
+///
+/// See [`Seed`] for the reuse of allocations this enables.
+#[tracing::instrument(skip_all)]
+pub fn from_reader_seeded<'de, S: Seed<'de>>(
+    reader: &mut impl BufReader<'de>,
+    seed: &mut S,
+) -> Result<(), DecodeError> {
+    seed.deserialize_in_place(Deserializer::new(reader))
+}
+
+/// Deserialize from the BSATN format in `bytes` into the storage of `seed`.
+pub fn from_slice_seeded<'de, S: Seed<'de>>(bytes: &'de [u8], seed: &mut S) -> Result<(), DecodeError> {
+    from_reader_seeded(&mut &*bytes, seed)
+}
+
+/// Decode a value of type `ty` from the BSATN format in `bytes` into `out`.
+///
+/// The allocations of the old value in `out` are reused where its shape matches the new value,
+/// e.g., for products of the same arity, arrays of the same variant, and strings of the same length.
+/// Where the shapes differ, the relevant parts of `out` are replaced.
+///
+/// On error, `out` is reset to [`AlgebraicValue::UNIT`] rather than being left partially overwritten.
+pub fn decode_into(
+    bytes: &[u8],
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    out: &mut AlgebraicValue,
+) -> Result<(), DecodeError> {
+    decode_value_into(bytes, WithTypespace::new(ts, ty), out, AlgebraicValue::UNIT)
+}
+
+/// Decode a value of type `ty`, with any `Ref`s resolved in `ts`, from the BSATN format in `bytes`.
+///
+/// Unlike a [`DecodeError`], the error tells which type was expected where decoding failed.
+pub fn decode_value(bytes: &[u8], ty: &AlgebraicType, ts: &Typespace) -> Result<AlgebraicValue, DeserializeError> {
+    let mut out = AlgebraicValue::UNIT;
+    decode_into(bytes, ty, ts, &mut out)
+        .map_err(|err| DeserializeError::from_decode(err, WithTypespace::new(ts, ty)))?;
+    Ok(out)
+}
+
+/// Decode a product value of type `ty` from the BSATN format in `bytes` into `out`.
+///
+/// This is [`decode_into`] specialized to product values, e.g., rows,
+/// except that on error, `out` is reset to the empty product.
+pub fn decode_product_into(
+    bytes: &[u8],
+    ty: &ProductType,
+    ts: &Typespace,
+    out: &mut ProductValue,
+) -> Result<(), DecodeError> {
+    decode_value_into(bytes, WithTypespace::new(ts, ty), out, ProductValue::new(&[]))
+}
+
+/// Decode a value of type `ty` from `bytes` into `out`, resetting `out` to `reset` on error.
+fn decode_value_into<'de, T: Value>(
+    bytes: &'de [u8],
+    ty: WithTypespace<'_, T::Type>,
+    out: &mut T,
+    reset: T,
+) -> Result<(), DecodeError>
+where
+    for<'a> ValueSeed<'a, T>: Seed<'de>,
+{
+    let mut seed = ValueSeed::new(ty, std::mem::replace(out, reset));
+    from_slice_seeded(bytes, &mut seed)?;
+    *out = seed.into_value();
+    Ok(())
+}
+
+/// Decode only the fields at the indices `wanted` of a product value of type `ty`
+/// from the BSATN format in `bytes`.
+///
+/// The values are returned in the order of `wanted`, which may repeat indices.
+/// Fields that are not wanted are skipped without being decoded,
+/// and the encoding is not read past the last wanted field.
+///
+/// Fails up front with `ErrorKind::Custom` if any index in `wanted` is out of range for `ty`.
+pub fn decode_fields(
+    bytes: &[u8],
+    ty: &ProductType,
+    ts: &Typespace,
+    wanted: &[usize],
+) -> Result<Vec<AlgebraicValue>, DecodeError> {
+    let mut values = vec![None; ty.elements.len()];
+    walk_fields(bytes, WithTypespace::new(ts, ty), wanted, |idx, ty, reader| {
+        values[idx] = Some(ty.deserialize(Deserializer::new(reader))?);
+        Ok(())
+    })?;
+
+    Ok(wanted
+        .iter()
+        .enumerate()
+        .map(|(pos, &idx)| {
+            // Only clone values that are wanted again later on.
+            let val = &mut values[idx];
+            let val = if wanted[pos + 1..].contains(&idx) {
+                val.clone()
+            } else {
+                val.take()
+            };
+            val.expect("wanted field should have been decoded")
+        })
+        .collect())
+}
+
+/// Decode only the fields at the indices `wanted` of a product value of type `ty`
+/// from the BSATN format in `bytes`, as a `T`.
+///
+/// The wanted fields are deserialized as if they were a product of just those fields,
+/// in the order of `wanted`.
+/// For example, a struct deriving `Deserialize` with one field per index in `wanted` can be used for `T`.
+///
+/// See [`decode_fields`] for more details.
+pub fn decode_fields_as<'de, T: Deserialize<'de>>(
+    bytes: &'de [u8],
+    ty: &ProductType,
+    ts: &Typespace,
+    wanted: &[usize],
+) -> Result<T, DecodeError> {
+    let mut spans: Vec<&'de [u8]> = vec![&[]; ty.elements.len()];
+    walk_fields(bytes, WithTypespace::new(ts, ty), wanted, |idx, ty, reader| {
+        let start = *reader;
+        skip::skip_value(reader, ty)?;
+        spans[idx] = &start[..start.len() - reader.len()];
+        Ok(())
+    })?;
+
+    let mut reader = SpansReader {
+        current: &[],
+        rest: wanted.iter().map(|&idx| spans[idx]).collect::<Vec<_>>().into_iter(),
+    };
+    from_reader(&mut reader)
+}
+
+/// Walks the encoding in `bytes` of a product value of type `ty`,
+/// calling `visit` once for each distinct field index in `wanted`, in field order,
+/// with the reader positioned at the start of that field.
+///
+/// Runs of fields of a fixed size that are not wanted are skipped in one step.
+fn walk_fields<'de>(
+    mut bytes: &'de [u8],
+    ty: WithTypespace<'_, ProductType>,
+    wanted: &[usize],
+    mut visit: impl FnMut(usize, WithTypespace<'_, AlgebraicType>, &mut &'de [u8]) -> Result<(), DecodeError>,
+) -> Result<(), DecodeError> {
+    let elements = &ty.ty().elements;
+    if let Some(idx) = wanted.iter().find(|&&idx| idx >= elements.len()) {
+        return Err(ErrorKind::Custom(format!(
+            "field index {idx} out of range for a product with {} fields",
+            elements.len()
+        ))
+        .into());
+    }
+    let Some(&last) = wanted.iter().max() else {
+        return Ok(());
+    };
+    let mut is_wanted = vec![false; last + 1];
+    for &idx in wanted {
+        is_wanted[idx] = true;
+    }
+
+    // The number of bytes of fixed size fields yet to be skipped.
+    let mut pending = 0usize;
+    for (idx, elem) in elements[..=last].iter().enumerate() {
+        let elem_ty = ty.with(&elem.algebraic_type);
+        let size = skip::fixed_size(elem_ty);
+        if let (Some(size), false) = (size, is_wanted[idx]) {
+            pending = pending
+                .checked_add(size)
+                .ok_or_else(|| DecodeError::truncated(usize::MAX, bytes))?;
+            continue;
+        }
+
+        bytes.get_slice(std::mem::take(&mut pending))?;
+        if is_wanted[idx] {
+            visit(idx, elem_ty, &mut bytes)?;
+        } else {
+            skip::skip_value(&mut bytes, elem_ty)?;
+        }
+    }
+    Ok(())
+}
+
+/// A reader over a sequence of byte slices, read one after the other.
+///
+/// No single read may straddle two slices,
+/// which holds when each slice is the complete encoding of one value.
+struct SpansReader<'de> {
+    /// The slice currently being read.
+    current: &'de [u8],
+    /// The slices yet to be read.
+    rest: std::vec::IntoIter<&'de [u8]>,
+}
+
+impl<'de> BufReader<'de> for SpansReader<'de> {
+    fn get_slice(&mut self, size: usize) -> Result<&'de [u8], DecodeError> {
+        while self.current.is_empty() && size > 0 {
+            self.current = self.rest.next().ok_or_else(|| DecodeError::truncated(size, &[]))?;
+        }
+        self.current.get_slice(size)
+    }
+
+    fn remaining(&self) -> usize {
+        self.current.len() + self.rest.as_slice().iter().map(|s| s.len()).sum::<usize>()
+    }
+}
+
 macro_rules! codec_funcs {
     ($ty:ty) => {
         impl $ty {
@@ -74,4 +340,592 @@ codec_funcs!(crate::SumTypeVariant);
 codec_funcs!(val: crate::AlgebraicValue);
 codec_funcs!(val: crate::ProductValue);
 codec_funcs!(val: crate::SumValue);
-codec_funcs!(val: crate::BuiltinValue);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, AlgebraicTypeRef, ProductTypeElement, SumTypeVariant};
+
+    /// A wide row mixing fixed size and variable size fields,
+    /// in the context of [`wide_typespace`].
+    fn wide_row() -> (ProductType, ProductValue) {
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::Bool, "flag"),
+            ProductTypeElement::new_named(AlgebraicType::I32, "n"),
+            ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::U32), "ids"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "nick"),
+            ProductTypeElement::new_named(AlgebraicType::F64, "score"),
+            ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(0)), "pos"),
+            ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::String), "tags"),
+            ProductTypeElement::new_named(AlgebraicType::U8, "last"),
+        ]);
+        let row = product![
+            7u64,
+            "Alice".to_owned(),
+            true,
+            -3i32,
+            AlgebraicValue::ArrayOf(vec![1u32, 2, 3]),
+            AlgebraicValue::OptionSome(AlgebraicValue::String("al".into())),
+            AlgebraicValue::F64(1.5.into()),
+            product![4u16, -5i64],
+            AlgebraicValue::ArrayOf(vec!["a".to_owned(), "bc".to_owned()]),
+            9u8
+        ];
+        (ty, row)
+    }
+
+    /// The typespace with the type of the `pos` field of [`wide_row`].
+    fn wide_typespace() -> Typespace {
+        Typespace::new(vec![AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U16, "x"),
+            ProductTypeElement::new_named(AlgebraicType::I64, "y"),
+        ])])
+    }
+
+    #[test]
+    fn decode_fields_matches_full_decode() {
+        let (ty, row) = wide_row();
+        let ts = wide_typespace();
+        let bytes = to_vec(&row).unwrap();
+        let full: ProductValue = WithTypespace::new(&ts, &ty)
+            .deserialize(Deserializer::new(&mut &*bytes))
+            .unwrap();
+        assert_eq!(full, row);
+
+        let wanted_sets: &[&[usize]] = &[
+            &[],
+            &[0],
+            &[9],
+            &[1, 8],
+            &[8, 1],
+            &[2, 3, 6],
+            &[7, 5, 4],
+            &[3, 3, 1, 3],
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        ];
+        for wanted in wanted_sets {
+            let projected = decode_fields(&bytes, &ty, &ts, wanted).unwrap();
+            let expected = wanted.iter().map(|&i| row.elements[i].clone()).collect::<Vec<_>>();
+            assert_eq!(projected, expected, "wanted = {wanted:?}");
+        }
+    }
+
+    #[test]
+    fn encoded_len_matches_encoding() {
+        let (_, row) = wide_row();
+        let bytes = to_vec_exact(&row).unwrap();
+        assert_eq!(bytes.capacity(), bytes.len());
+        assert_eq!(bytes, to_vec(&row).unwrap());
+        assert_eq!(size::encoded_len(&row), Some(bytes.len()));
+        // The `tags` field is an array of strings, which static sizing gives up on.
+        assert_eq!(size::static_encoded_len(&row), None);
+        let scalars = ProductValue::from_iter(row.elements[..4].iter().cloned());
+        assert_eq!(
+            size::static_encoded_len(&scalars),
+            Some(to_vec(&scalars).unwrap().len())
+        );
+    }
+
+    #[test]
+    fn encoded_len_follows_the_serializer() {
+        use crate::ser::elision::DefaultElidingSerializer;
+
+        let strings = vec!["repeated".to_owned(); 50];
+        let packed = crate::builtin_value::PackedStrings::from(&*strings);
+        let values = [
+            AlgebraicValue::ArrayOf(strings),
+            AlgebraicValue::Array(crate::ArrayValue::StringPacked(packed.into())),
+            AlgebraicValue::ArrayOf(vec![1u32, 2, 3]),
+        ];
+        for value in &values {
+            let bytes = to_vec(value).unwrap();
+            assert_eq!(size::encoded_len(value), Some(bytes.len()));
+            assert_eq!(to_vec_exact(value).unwrap(), bytes);
+
+            #[cfg(feature = "compress")]
+            {
+                let (mut bytes, mut counter) = (Vec::new(), size::SizeCounter::default());
+                value
+                    .serialize(Serializer::new(&mut bytes).with_dictionary_strings(true))
+                    .unwrap();
+                value
+                    .serialize(Serializer::new(&mut counter).with_dictionary_strings(true))
+                    .unwrap();
+                assert_eq!(counter.len, bytes.len());
+            }
+        }
+
+        // Default markers are sized as the single byte they are written as.
+        let baseline = AlgebraicValue::from(product![1u32, "same".to_owned()]);
+        for value in [baseline.clone(), product![1u32, "other".to_owned()].into()] {
+            let (mut bytes, mut counter) = (Vec::new(), size::SizeCounter::default());
+            value
+                .serialize(DefaultElidingSerializer::new(&baseline, Serializer::new(&mut bytes)))
+                .unwrap();
+            value
+                .serialize(DefaultElidingSerializer::new(&baseline, Serializer::new(&mut counter)))
+                .unwrap();
+            assert_eq!(counter.len, bytes.len());
+        }
+    }
+
+    #[test]
+    fn decode_fields_out_of_range() {
+        let (ty, row) = wide_row();
+        let ts = wide_typespace();
+        let bytes = to_vec(&row).unwrap();
+        assert!(decode_fields(&bytes, &ty, &ts, &[0, 10]).is_err());
+        // The index is checked before reading anything.
+        assert!(decode_fields(&[], &ty, &ts, &[10]).is_err());
+    }
+
+    #[test]
+    fn decode_fields_stops_at_last_wanted() {
+        let (ty, row) = wide_row();
+        let ts = wide_typespace();
+        let bytes = to_vec(&row).unwrap();
+        // Cutting off the `last` field doesn't matter when it isn't wanted...
+        let cut = &bytes[..bytes.len() - 1];
+        let projected = decode_fields(cut, &ty, &ts, &[8, 0]).unwrap();
+        assert_eq!(projected, [row.elements[8].clone(), row.elements[0].clone()]);
+        // ...but does when it is.
+        assert!(matches!(
+            decode_fields(cut, &ty, &ts, &[9]).unwrap_err().kind(),
+            ErrorKind::Truncated { needed: 1, had: 0 }
+        ));
+    }
+
+    #[test]
+    fn decode_fields_as_typed() {
+        #[derive(crate::de::Deserialize, Debug, PartialEq)]
+        #[sats(crate = crate)]
+        struct Projection<'a> {
+            tags: Vec<String>,
+            name: &'a str,
+            score: f64,
+            id: u64,
+        }
+
+        let (ty, row) = wide_row();
+        let ts = wide_typespace();
+        let bytes = to_vec(&row).unwrap();
+        let projected: Projection = decode_fields_as(&bytes, &ty, &ts, &[8, 1, 6, 0]).unwrap();
+        assert_eq!(
+            projected,
+            Projection {
+                tags: vec!["a".into(), "bc".into()],
+                name: "Alice",
+                score: 1.5,
+                id: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn decode_into_reuses_slot() {
+        let ts = Typespace::default();
+        let ty = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ]);
+
+        let mut slot = AlgebraicValue::UNIT;
+        for name in ["medium", "a much, much longer name", "", "short"] {
+            let row = AlgebraicValue::Product(product![42u64, name.to_owned()]);
+            decode_into(&to_vec(&row).unwrap(), &ty, &ts, &mut slot).unwrap();
+            assert_eq!(slot, row);
+        }
+
+        // The string buffer is reused as the new name has the same length.
+        let name_ptr = |slot: &AlgebraicValue| slot.as_product().unwrap().elements[1].as_string().unwrap().as_ptr();
+        let ptr = name_ptr(&slot);
+        let row = AlgebraicValue::Product(product![7u64, "other".to_owned()]);
+        decode_into(&to_vec(&row).unwrap(), &ty, &ts, &mut slot).unwrap();
+        assert_eq!(slot, row);
+        assert_eq!(name_ptr(&slot), ptr);
+    }
+
+    #[test]
+    fn decode_into_sum_variant_flip() {
+        let ts = Typespace::default();
+        let ty = AlgebraicType::sum(vec![
+            SumTypeVariant::new_named(AlgebraicType::String, "name"),
+            SumTypeVariant::new_named(AlgebraicType::array(AlgebraicType::U32), "ids"),
+            SumTypeVariant::unit("none"),
+        ]);
+
+        let mut slot = AlgebraicValue::UNIT;
+        for val in [
+            AlgebraicValue::sum(0, AlgebraicValue::String("hello".into())),
+            AlgebraicValue::sum(1, AlgebraicValue::ArrayOf(vec![1u32, 2, 3])),
+            AlgebraicValue::sum(2, AlgebraicValue::UNIT),
+            AlgebraicValue::sum(0, AlgebraicValue::String("again".into())),
+        ] {
+            decode_into(&to_vec(&val).unwrap(), &ty, &ts, &mut slot).unwrap();
+            assert_eq!(slot, val);
+        }
+    }
+
+    #[test]
+    fn decode_into_error_mid_decode() {
+        let ts = Typespace::default();
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::String, "a"),
+            ProductTypeElement::new_named(AlgebraicType::String, "b"),
+        ]);
+
+        let mut slot = product!["old a".to_owned(), "old b".to_owned()];
+        let new = product!["new a".to_owned(), "new b".to_owned()];
+        let bytes = to_vec(&new).unwrap();
+
+        // Cut the input short in the middle of the second field.
+        let err = decode_product_into(&bytes[..bytes.len() - 2], &ty, &ts, &mut slot);
+        assert!(err.is_err());
+        // The slot is not left half old and half new.
+        assert_eq!(slot, ProductValue::new(&[]));
+
+        // The slot is still usable afterwards.
+        decode_product_into(&bytes, &ty, &ts, &mut slot).unwrap();
+        assert_eq!(slot, new);
+    }
+
+    #[test]
+    fn invalid_tag_error() {
+        let err = from_slice::<Option<u32>>(&[2]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidTag { got: 2, max: Some(1) }));
+        assert_eq!(err.excerpt(), [2]);
+
+        let ty = AlgebraicType::sum(vec![
+            SumTypeVariant::unit("a"),
+            SumTypeVariant::unit("b"),
+            SumTypeVariant::unit("c"),
+        ]);
+        let err = AlgebraicValue::decode(&ty, &mut &[7u8][..]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidTag { got: 7, max: Some(2) }));
+        assert_eq!(err.to_string(), "invalid tag 7 for sum, expected at most 2 (bytes: 07)");
+    }
+
+    #[test]
+    fn wrong_kind_error() {
+        let err = from_slice::<bool>(&[2]).unwrap_err();
+        let ErrorKind::WrongKind { expected, found } = err.kind() else {
+            panic!("unexpected error {err}")
+        };
+        assert_eq!((&**expected, &**found), ("bool", "byte 0x02"));
+    }
+
+    #[test]
+    fn utf8_error() {
+        let bytes = to_vec(&b"ab\xffc".to_vec()).unwrap();
+        let err = from_slice::<String>(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Utf8 { offset: 2 }));
+        assert_eq!(err.excerpt(), [0xff, b'c']);
+    }
+
+    #[test]
+    fn string_arrays() {
+        let strings = vec![
+            "".to_owned(),
+            "h\u{e9}llo".to_owned(),
+            "\u{1f980} crab".to_owned(),
+            "\u{4e2d}".to_owned(),
+        ];
+        let bytes = to_vec(&strings).unwrap();
+        assert_eq!(from_slice::<Vec<String>>(&bytes).unwrap(), strings);
+        assert_eq!(from_slice::<Vec<&str>>(&bytes).unwrap(), strings);
+        let cows = from_slice::<Vec<std::borrow::Cow<'_, str>>>(&bytes).unwrap();
+        assert!(cows.iter().all(|s| matches!(s, std::borrow::Cow::Borrowed(_))));
+
+        let ty = AlgebraicType::array(AlgebraicType::String);
+        let value = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+        assert_eq!(value, AlgebraicValue::ArrayOf(strings));
+    }
+
+    #[test]
+    fn string_array_utf8_error() {
+        // A truncated multi-byte sequence in the third string.
+        let strings = vec![b"ok".to_vec(), "\u{e9}".as_bytes().to_vec(), b"ab\xe2\x82".to_vec()];
+        let bytes = to_vec(&strings).unwrap();
+        let err = from_slice::<Vec<String>>(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Utf8 { offset: 2 }));
+        assert_eq!(err.path().cloned().collect::<Vec<_>>(), [PathSegment::Element(2)]);
+        assert_eq!(err.excerpt(), [0xe2, 0x82]);
+
+        // A lone continuation byte in the first string.
+        let bytes = to_vec(&vec![b"\x80".to_vec(), b"fine".to_vec()]).unwrap();
+        let err = from_slice::<Vec<&str>>(&bytes).unwrap_err();
+        assert_eq!(err.path().cloned().collect::<Vec<_>>(), [PathSegment::Element(0)]);
+
+        // Running out of input while reading the strings also reports the element.
+        let bytes = to_vec(&vec!["a".to_owned(), "bc".to_owned()]).unwrap();
+        let err = from_slice::<Vec<String>>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { .. }));
+        assert_eq!(err.path().cloned().collect::<Vec<_>>(), [PathSegment::Element(1)]);
+    }
+
+    #[test]
+    fn truncated_error() {
+        let err = from_slice::<u32>(&[1, 2]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { needed: 4, had: 2 }));
+        assert_eq!(err.excerpt(), [1, 2]);
+        assert_eq!(err.path().len(), 0);
+    }
+
+    #[test]
+    fn error_path() {
+        let tag_ty = AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::String, "name")]);
+        let ty = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::array(tag_ty), "tags"),
+        ]);
+        let row = product![
+            1u32,
+            AlgebraicValue::ArrayOf(vec![product!["ok".to_owned()], product!["\u{e9}".to_owned()]])
+        ];
+        let mut bytes = to_vec(&row).unwrap();
+        // Corrupt the first byte of the second tag's name.
+        let at = bytes.len() - 2;
+        bytes[at] = 0xff;
+
+        let err = AlgebraicValue::decode(&ty, &mut &bytes[..]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Utf8 { offset: 0 }));
+        let path = err.path().cloned().collect::<Vec<_>>();
+        let field = |index, name: &str| PathSegment::Field {
+            index,
+            name: Some(name.into()),
+        };
+        assert_eq!(path, [field(1, "tags"), PathSegment::Element(1), field(0, "name")]);
+        assert_eq!(
+            err.to_string(),
+            "invalid utf8 at byte 0 in `tags[1].name` (bytes: ff a9)"
+        );
+    }
+
+    #[test]
+    fn derived_error_path() {
+        #[derive(crate::de::Deserialize, Debug)]
+        #[sats(crate = crate)]
+        struct Inner {
+            #[allow(dead_code)]
+            flag: bool,
+        }
+        #[derive(crate::de::Deserialize, Debug)]
+        #[sats(crate = crate)]
+        struct Outer {
+            #[allow(dead_code)]
+            id: u8,
+            #[allow(dead_code)]
+            inner: Inner,
+        }
+
+        let err = from_slice::<Outer>(&[1, 5]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::WrongKind { .. }));
+        assert_eq!(err.to_string(), "expected bool, found byte 0x05 in `inner.flag`");
+    }
+
+    #[test]
+    fn io_error_source() {
+        use std::error::Error as _;
+
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "disk went away");
+        let err = DecodeError::from(io);
+        assert!(matches!(err.kind(), ErrorKind::Io(_)));
+        let source = err.source().unwrap().downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(source.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(from_slice::<u8>(&[]).unwrap_err().source().is_none());
+    }
+
+    /// A product with the fields `schema`, of which `fields` are serialized by name in the given order,
+    /// as a bridge from a format without field order, like JSON objects, would.
+    struct FieldsInAnyOrder<'a> {
+        schema: &'a [&'a str],
+        fields: &'a [(&'a str, AlgebraicValue)],
+    }
+
+    impl Serialize for FieldsInAnyOrder<'_> {
+        fn serialize<S: crate::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use crate::ser::SerializeNamedProduct as _;
+
+            let mut prod = serializer.serialize_named_product(self.schema.len())?;
+            for (name, value) in self.fields {
+                let index = self.schema.iter().position(|field| field == name).unwrap();
+                prod.serialize_element_at(index, Some(name), value)?;
+            }
+            prod.end()
+        }
+    }
+
+    #[test]
+    fn fields_out_of_order() {
+        let schema = ["id", "name", "score", "flag"];
+        let fields = [
+            ("score", AlgebraicValue::U32(70)),
+            ("flag", AlgebraicValue::Bool(true)),
+            ("id", AlgebraicValue::U64(1)),
+            ("name", AlgebraicValue::String("ada".into())),
+        ];
+        let in_order = product![1u64, "ada", 70u32, true];
+        let reordered = FieldsInAnyOrder {
+            schema: &schema,
+            fields: &fields,
+        };
+        assert_eq!(to_vec(&reordered).unwrap(), to_vec(&in_order).unwrap());
+        assert_eq!(to_vec_exact(&reordered).unwrap(), to_vec(&in_order).unwrap());
+        let value = reordered
+            .serialize(crate::algebraic_value::ser::ValueSerializer)
+            .unwrap();
+        assert_eq!(value, in_order.into());
+
+        // A missing field is caught at the end, as is a repeated one.
+        let missing = FieldsInAnyOrder {
+            schema: &schema,
+            fields: &[fields[0].clone(), fields[2].clone(), fields[3].clone()],
+        };
+        let err = to_vec(&missing).unwrap_err();
+        assert_eq!(err.to_string(), "field 3 of the product was never serialized");
+        let repeated = FieldsInAnyOrder {
+            schema: &schema,
+            fields: &[fields[2].clone(), fields[2].clone()],
+        };
+        let err = to_vec(&repeated).unwrap_err();
+        assert_eq!(err.to_string(), "field `id` (0) serialized twice");
+    }
+
+    #[derive(crate::ser::Serialize, Debug, Clone, Copy)]
+    #[sats(crate = crate)]
+    enum Light {
+        Red,
+        Green,
+        Dimmed(u8),
+    }
+
+    /// A unit variant serialized the old way, with its payload as a product of no fields.
+    struct OldUnitVariant(u8, &'static str);
+
+    struct OldUnit;
+
+    impl Serialize for OldUnit {
+        fn serialize<S: crate::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            crate::ser::SerializeSeqProduct::end(serializer.serialize_seq_product(0)?)
+        }
+    }
+
+    impl Serialize for OldUnitVariant {
+        fn serialize<S: crate::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_variant(self.0, Some(self.1), &OldUnit)
+        }
+    }
+
+    #[test]
+    fn unit_variants_encode_as_before() {
+        let cases = [
+            (to_vec(&Light::Red), to_vec(&OldUnitVariant(0, "Red"))),
+            (to_vec(&Light::Green), to_vec(&OldUnitVariant(1, "Green"))),
+            (to_vec(&None::<u32>), to_vec(&OldUnitVariant(1, "none"))),
+            (to_vec(&()), to_vec(&OldUnit)),
+            (
+                to_vec_exact(&[Light::Green, Light::Dimmed(3)]),
+                Ok(vec![2, 0, 0, 0, 1, 2, 3]),
+            ),
+        ];
+        for (new, old) in cases {
+            assert_eq!(new.unwrap(), old.unwrap());
+        }
+        assert_eq!(to_vec(&Light::Green).unwrap(), [1]);
+        assert_eq!(size::encoded_len(&Light::Red), Some(1));
+        #[cfg(feature = "varint")]
+        assert_eq!(
+            to_vec_varint(&Light::Green).unwrap(),
+            to_vec_varint(&OldUnitVariant(1, "Green")).unwrap()
+        );
+
+        // Formats that don't override the unit conveniences keep the old path.
+        use crate::algebraic_value::ser::ValueSerializer;
+        assert_eq!(
+            Light::Green.serialize(ValueSerializer),
+            OldUnitVariant(1, "Green").serialize(ValueSerializer)
+        );
+        #[cfg(feature = "serde")]
+        {
+            use crate::ser::serde::SerializeWrapper;
+            assert_eq!(
+                serde_json::to_string(SerializeWrapper::from_ref(&Light::Green)).unwrap(),
+                serde_json::to_string(SerializeWrapper::from_ref(&OldUnitVariant(1, "Green"))).unwrap()
+            );
+        }
+    }
+
+    /// The strings of `tags` that aren't empty, serialized without collecting them first.
+    struct NonEmpty<'a>(&'a [&'a str]);
+
+    impl Serialize for NonEmpty<'_> {
+        fn serialize<S: crate::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            crate::ser::serialize_iter(serializer, self.0.iter().filter(|tag| !tag.is_empty()))
+        }
+    }
+
+    #[test]
+    fn arrays_of_unknown_length() {
+        use crate::algebraic_value::ser::ValueSerializer;
+
+        for len in [0, 1, 10_000] {
+            let evens = || (0..2 * len as u64).filter(|x| x % 2 == 0);
+            let collected = evens().collect::<Vec<_>>();
+            assert_eq!(collected.len(), len);
+            let mut bytes = Vec::new();
+            crate::ser::serialize_iter(Serializer::new(&mut bytes), evens()).unwrap();
+            assert_eq!(bytes, to_vec(&collected).unwrap());
+            let value = crate::ser::serialize_iter(ValueSerializer, evens()).unwrap();
+            assert_eq!(value, collected.serialize(ValueSerializer).unwrap());
+        }
+
+        // Elements of varying size, sized exactly up front.
+        let tags = NonEmpty(&["a", "", "bc", ""]);
+        let expected = to_vec(&["a", "bc"]).unwrap();
+        assert_eq!(to_vec_exact(&tags).unwrap(), expected);
+        assert_eq!(to_vec(&[tags]).unwrap()[4..], expected);
+    }
+
+    #[cfg(feature = "varint")]
+    #[test]
+    fn arrays_of_unknown_length_unsupported() {
+        let err = to_vec_varint(&NonEmpty(&["a"])).unwrap_err();
+        assert_eq!(err.to_string(), "arrays of unknown length are not supported");
+    }
+
+    #[test]
+    fn maps_of_unknown_length() {
+        use std::collections::BTreeMap;
+
+        for len in [0, 1, 1000] {
+            let squares = || (0..2 * len as u32).filter(|x| x % 2 == 0).map(|x| (x, x * x));
+            let collected = squares().collect::<BTreeMap<_, _>>();
+            assert_eq!(collected.len(), len);
+            for strict in [false, true] {
+                let mut bytes = Vec::new();
+                crate::ser::serialize_map_iter(Serializer::new(&mut bytes), squares(), strict).unwrap();
+                assert_eq!(bytes, to_vec(&collected).unwrap());
+            }
+        }
+
+        // Out of order keys are only an error when strict.
+        let unordered = || [(2u8, "b"), (1, "a")].into_iter().filter(|_| true);
+        let mut bytes = Vec::new();
+        crate::ser::serialize_map_iter(Serializer::new(&mut bytes), unordered(), false).unwrap();
+        assert_eq!(bytes, [2, 0, 0, 0, 2, 1, 0, 0, 0, b'b', 1, 1, 0, 0, 0, b'a']);
+        let err = crate::ser::serialize_map_iter(Serializer::new(&mut Vec::new()), unordered(), true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "key 1 of the map is not greater than the key before it"
+        );
+        // Repeated keys too.
+        let repeated = [(1u8, "a"), (1, "b")];
+        let err = crate::ser::serialize_map_iter(Serializer::new(&mut Vec::new()), repeated, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "key 1 of the map is not greater than the key before it"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Compression of BSATN encodings, for large payloads such as the rows of a full table scan.
+//!
+//! A compressed payload is a tag byte, that of its [`CompressionAlgo`],
+//! followed by the BSATN encoding of the value compressed with that algorithm.
+//! With [`CompressionAlgo::None`], the encoding follows the tag as is.
+//! LZ4 payloads are LZ4 blocks prefixed by their uncompressed length as a little-endian `u32`,
+//! and Snappy payloads are in the raw Snappy format, without framing.
+//!
+//! As a payload declares its own decompressed length, and a small payload can declare a huge one,
+//! that length is checked against a maximum before anything is allocated for it.
+
+use std::borrow::Cow;
+
+use crate::buffer::DecodeError;
+use crate::ser::Error as _;
+use crate::{AlgebraicType, AlgebraicValue, Typespace, WithTypespace};
+
+use super::ser::BsatnError;
+use super::{decode_into, to_vec};
+
+/// The maximum decompressed length of payloads decoded by [`decode_compressed`], 256 MiB.
+pub const DEFAULT_MAX_DECOMPRESSED_LEN: usize = 256 << 20;
+
+/// An algorithm to compress BSATN payloads with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CompressionAlgo {
+    /// The payload is not compressed.
+    None = 0,
+    /// The payload is compressed with LZ4, which is fast, but compresses less.
+    Lz4 = 1,
+    /// The payload is compressed with Snappy.
+    Snappy = 2,
+}
+
+impl CompressionAlgo {
+    /// Returns the tag byte of payloads compressed with `self`.
+    pub const fn tag(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the algorithm with the tag byte `tag`, if any.
+    pub const fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Self::None),
+            1 => Some(Self::Lz4),
+            2 => Some(Self::Snappy),
+            _ => None,
+        }
+    }
+}
+
+/// An error compressing or decompressing a payload.
+#[derive(thiserror::Error, Debug)]
+pub enum CompressionError {
+    /// The payload is empty, so it doesn't even have a tag.
+    #[error("compressed payload is empty")]
+    Empty,
+    /// The tag of the payload is not that of any [`CompressionAlgo`].
+    #[error("unknown compression algorithm tag {0}")]
+    UnknownAlgo(u8),
+    /// The compressed payload is corrupt.
+    #[error("could not decompress {algo:?} payload: {message}")]
+    Decompress { algo: CompressionAlgo, message: String },
+    /// The compressed payload declares a decompressed length over the maximum.
+    #[error("{algo:?} payload decompresses to {len} bytes, exceeding the maximum of {max}")]
+    TooLarge {
+        algo: CompressionAlgo,
+        len: usize,
+        max: usize,
+    },
+    /// The decompressed payload is not a valid BSATN encoding of the expected type.
+    #[error("invalid decompressed payload: {0}")]
+    Decode(#[from] DecodeError),
+    /// The value could not be encoded or compressed.
+    #[error("could not compress payload: {0}")]
+    Encode(#[from] BsatnError),
+}
+
+/// Encodes `val` of type `ty`, with any `Ref`s resolved in `ts`, in BSATN, compressed with `algo`.
+pub fn encode_compressed(
+    val: &AlgebraicValue,
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    algo: CompressionAlgo,
+) -> Result<Vec<u8>, CompressionError> {
+    let bsatn = to_vec(&WithTypespace::new(ts, ty).with_value(val))?;
+    compress(&bsatn, algo)
+}
+
+/// Compresses `bytes`, e.g., an encoding other than plain BSATN, into a payload with `algo`.
+///
+/// When `bytes` is the BSATN encoding of a value, the payload is as by [`encode_compressed`].
+pub fn compress(bytes: &[u8], algo: CompressionAlgo) -> Result<Vec<u8>, CompressionError> {
+    let compressed = match algo {
+        CompressionAlgo::None => Cow::Borrowed(bytes),
+        CompressionAlgo::Lz4 => lz4_flex::compress_prepend_size(bytes).into(),
+        CompressionAlgo::Snappy => snap::raw::Encoder::new()
+            .compress_vec(bytes)
+            .map_err(BsatnError::custom)?
+            .into(),
+    };
+    let mut out = Vec::with_capacity(1 + compressed.len());
+    out.push(algo.tag());
+    out.extend_from_slice(&compressed);
+    Ok(out)
+}
+
+/// Decodes a value of type `ty`, with any `Ref`s resolved in `ts`,
+/// from `bytes` compressed by [`encode_compressed`] with any algorithm,
+/// decompressing to at most [`DEFAULT_MAX_DECOMPRESSED_LEN`] bytes.
+pub fn decode_compressed(bytes: &[u8], ty: &AlgebraicType, ts: &Typespace) -> Result<AlgebraicValue, CompressionError> {
+    decode_compressed_with_limit(bytes, ty, ts, DEFAULT_MAX_DECOMPRESSED_LEN)
+}
+
+/// Decodes a value as [`decode_compressed`] does,
+/// but rejects payloads declaring a decompressed length over `max_decompressed_len` bytes.
+pub fn decode_compressed_with_limit(
+    bytes: &[u8],
+    ty: &AlgebraicType,
+    ts: &Typespace,
+    max_decompressed_len: usize,
+) -> Result<AlgebraicValue, CompressionError> {
+    let (&tag, payload) = bytes.split_first().ok_or(CompressionError::Empty)?;
+    let algo = CompressionAlgo::from_tag(tag).ok_or(CompressionError::UnknownAlgo(tag))?;
+    let decompress_err = |message: String| CompressionError::Decompress { algo, message };
+
+    // Both decompressors allocate the declared length up front, so check it first.
+    let declared_len = match algo {
+        CompressionAlgo::None => payload.len(),
+        CompressionAlgo::Lz4 => match payload.get(..4) {
+            Some(len) => u32::from_le_bytes(len.try_into().unwrap()) as usize,
+            None => return Err(decompress_err("missing uncompressed length".into())),
+        },
+        CompressionAlgo::Snappy => snap::raw::decompress_len(payload).map_err(|e| decompress_err(e.to_string()))?,
+    };
+    if algo != CompressionAlgo::None && declared_len > max_decompressed_len {
+        return Err(CompressionError::TooLarge {
+            algo,
+            len: declared_len,
+            max: max_decompressed_len,
+        });
+    }
+
+    let bsatn = match algo {
+        CompressionAlgo::None => Cow::Borrowed(payload),
+        CompressionAlgo::Lz4 => lz4_flex::decompress_size_prepended(payload)
+            .map_err(|e| decompress_err(e.to_string()))?
+            .into(),
+        CompressionAlgo::Snappy => snap::raw::Decoder::new()
+            .decompress_vec(payload)
+            .map_err(|e| decompress_err(e.to_string()))?
+            .into(),
+    };
+    let mut out = AlgebraicValue::UNIT;
+    decode_into(&bsatn, ty, ts, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_value::F64;
+    use crate::{bsatn, product, ProductTypeElement};
+
+    const ALGOS: [CompressionAlgo; 3] = [CompressionAlgo::None, CompressionAlgo::Lz4, CompressionAlgo::Snappy];
+
+    /// Returns values of all kinds, each with its type.
+    fn values() -> Vec<(AlgebraicValue, AlgebraicType)> {
+        let row = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::F64), "score"),
+        ]);
+        let rows = (0..1000u32)
+            .map(|i| {
+                let score = AlgebraicValue::OptionSome(AlgebraicValue::F64(F64::from(f64::from(i) / 4.0)));
+                product![i, format!("player {}", i % 10), score]
+            })
+            .collect::<Vec<_>>();
+        vec![
+            (AlgebraicValue::UNIT, AlgebraicType::UNIT_TYPE),
+            (AlgebraicValue::Bool(true), AlgebraicType::Bool),
+            (AlgebraicValue::I128(-7 << 100), AlgebraicType::I128),
+            (AlgebraicValue::String("".into()), AlgebraicType::String),
+            (AlgebraicValue::OptionNone(), AlgebraicType::option(AlgebraicType::U8)),
+            (AlgebraicValue::Bytes(vec![0; 4096]), AlgebraicType::bytes()),
+            (
+                AlgebraicValue::map([(AlgebraicValue::U8(1), AlgebraicValue::from("one"))].into()),
+                AlgebraicType::map(AlgebraicType::U8, AlgebraicType::String),
+            ),
+            (AlgebraicValue::ArrayOf(rows), AlgebraicType::array(row)),
+        ]
+    }
+
+    #[test]
+    fn round_trips() {
+        let ts = Typespace::default();
+        for (val, ty) in values() {
+            let plain = bsatn::to_vec(&val).unwrap();
+            for algo in ALGOS {
+                let bytes = encode_compressed(&val, &ty, &ts, algo).unwrap();
+                assert_eq!(bytes[0], algo.tag());
+                assert_eq!(compress(&plain, algo).unwrap(), bytes, "{algo:?}");
+                assert_eq!(decode_compressed(&bytes, &ty, &ts).unwrap(), val, "{algo:?}");
+                if algo == CompressionAlgo::None {
+                    // Past the tag, an uncompressed payload is the plain encoding.
+                    assert_eq!(bytes[1..], plain);
+                } else if plain.len() > 1000 {
+                    assert!(
+                        bytes.len() < plain.len() / 2,
+                        "{algo:?}: {} of {}",
+                        bytes.len(),
+                        plain.len()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_payloads_are_errors() {
+        let ts = Typespace::default();
+        let ty = AlgebraicType::String;
+        let val = AlgebraicValue::String("compressible ".repeat(20).into());
+
+        assert!(matches!(decode_compressed(&[], &ty, &ts), Err(CompressionError::Empty)));
+        let err = decode_compressed(&[3, 0], &ty, &ts).unwrap_err();
+        assert!(matches!(err, CompressionError::UnknownAlgo(3)), "{err}");
+
+        for algo in [CompressionAlgo::Lz4, CompressionAlgo::Snappy] {
+            let bytes = encode_compressed(&val, &ty, &ts, algo).unwrap();
+            let err = decode_compressed(&bytes[..bytes.len() / 2], &ty, &ts).unwrap_err();
+            assert!(
+                matches!(err, CompressionError::Decompress { algo: a, .. } if a == algo),
+                "{err}"
+            );
+        }
+
+        // A payload claiming to decompress to more than the maximum isn't decompressed.
+        let mut bomb = vec![CompressionAlgo::Lz4.tag()];
+        bomb.extend_from_slice(&u32::MAX.to_le_bytes());
+        let err = decode_compressed(&bomb, &ty, &ts).unwrap_err();
+        assert!(
+            matches!(err, CompressionError::TooLarge { len, max: DEFAULT_MAX_DECOMPRESSED_LEN, .. } if len == u32::MAX as usize),
+            "{err}"
+        );
+        for algo in [CompressionAlgo::Lz4, CompressionAlgo::Snappy] {
+            let bytes = encode_compressed(&val, &ty, &ts, algo).unwrap();
+            let len = bsatn::to_vec(&val).unwrap().len();
+            assert!(decode_compressed_with_limit(&bytes, &ty, &ts, len).is_ok());
+            let err = decode_compressed_with_limit(&bytes, &ty, &ts, len - 1).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "{algo:?} payload decompresses to {len} bytes, exceeding the maximum of {}",
+                    len - 1
+                )
+            );
+        }
+
+        // An intact payload of another type is a decode error.
+        let bytes = encode_compressed(&val, &ty, &ts, CompressionAlgo::Lz4).unwrap();
+        let err = decode_compressed(&bytes, &AlgebraicType::Bool, &ts).unwrap_err();
+        assert!(matches!(err, CompressionError::Decode(_)), "{err}");
+    }
+}
@@ -0,0 +1,229 @@
+//! A cache of decoder state shared between threads,
+//! so that the schema of a message format is parsed once rather than by every thread decoding it.
+//!
+//! Schemas are keyed by a [`SchemaHash`], a digest of the schema computed by the caller,
+//! and are given as the BSATN encoding of a [`Typespace`].
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::buffer::DecodeError;
+use crate::de::DeserializeSeed;
+use crate::{AlgebraicType, AlgebraicTypeRef, AlgebraicValue, Typespace, WithTypespace};
+
+use super::de::Deserializer;
+use super::from_slice;
+
+/// The digest of a schema, identifying it in a [`SharedDecoderCache`].
+///
+/// The cache trusts that equal hashes are of equal schemas,
+/// so the hash should be a cryptographic digest of the schema bytes, e.g., their BLAKE3 or SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SchemaHash(pub [u8; 32]);
+
+/// The state for decoding messages of one schema, i.e., its parsed typespace.
+#[derive(Debug)]
+pub struct DecoderState {
+    /// The typespace of the schema.
+    typespace: Typespace,
+}
+
+impl DecoderState {
+    /// Parses the BSATN encoding of a [`Typespace`] in `schema_bytes`.
+    pub fn new(schema_bytes: &[u8]) -> Result<Self, DecodeError> {
+        let typespace = from_slice(schema_bytes)?;
+        Ok(Self { typespace })
+    }
+
+    /// Returns the typespace of the schema.
+    pub fn typespace(&self) -> &Typespace {
+        &self.typespace
+    }
+
+    /// Decodes a value of the type `root` of the schema from the BSATN format in `bytes`.
+    pub fn decode(&self, root: AlgebraicTypeRef, mut bytes: &[u8]) -> Result<AlgebraicValue, DecodeError> {
+        let ty = AlgebraicType::Ref(root);
+        WithTypespace::new(&self.typespace, &ty).deserialize(Deserializer::new(&mut bytes))
+    }
+}
+
+/// An entry of a [`SharedDecoderCache`].
+struct Entry {
+    state: Arc<DecoderState>,
+    /// The tick of the cache at which the entry was last used.
+    last_used: AtomicU64,
+}
+
+/// A cache of [`DecoderState`]s by [`SchemaHash`], shared by reference between threads.
+///
+/// A bounded cache evicts its least recently used entries to stay within its capacity.
+/// Evicted states remain valid for those already holding them.
+pub struct SharedDecoderCache {
+    entries: DashMap<SchemaHash, Entry>,
+    /// The maximum number of entries, or `None` for an unbounded cache.
+    capacity: Option<usize>,
+    /// A counter ordering the uses of entries.
+    clock: AtomicU64,
+}
+
+impl Default for SharedDecoderCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedDecoderCache {
+    /// Returns an empty, unbounded cache.
+    pub fn new() -> Self {
+        Self {
+            entries: DashMap::new(),
+            capacity: None,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns an empty cache holding at most `capacity` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "a decoder cache needs room for at least one entry");
+        Self {
+            entries: DashMap::with_capacity(capacity),
+            capacity: Some(capacity),
+            ..Self::new()
+        }
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the decoder state for the schema `hash`,
+    /// parsing it from `schema_bytes` if it isn't cached yet.
+    ///
+    /// The state is built at most once per hash while it stays cached,
+    /// even when several threads ask for it at the same time:
+    /// the others wait for the first to build it, then share its state.
+    /// A parse error is returned to the caller building the state, and nothing is cached.
+    pub fn get_or_build(&self, hash: SchemaHash, schema_bytes: &[u8]) -> Result<Arc<DecoderState>, DecodeError> {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let state = {
+            let entry = self.entries.entry(hash).or_try_insert_with(|| {
+                Ok::<_, DecodeError>(Entry {
+                    state: Arc::new(DecoderState::new(schema_bytes)?),
+                    last_used: AtomicU64::new(tick),
+                })
+            })?;
+            entry.last_used.fetch_max(tick, Ordering::Relaxed);
+            entry.state.clone()
+        };
+        self.evict(hash);
+        Ok(state)
+    }
+
+    /// Evicts the least recently used entries, other than `keep`, until the cache is within its capacity.
+    fn evict(&self, keep: SchemaHash) {
+        let Some(capacity) = self.capacity else { return };
+        while self.entries.len() > capacity {
+            let oldest = (self.entries.iter())
+                .filter(|entry| *entry.key() != keep)
+                .map(|entry| (entry.last_used.load(Ordering::Relaxed), *entry.key()))
+                .min();
+            let Some((last_used, hash)) = oldest else { return };
+            // Another thread may have used the entry since, making it no longer the oldest.
+            self.entries
+                .remove_if(&hash, |_, entry| entry.last_used.load(Ordering::Relaxed) == last_used);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn::to_vec;
+    use crate::ProductTypeElement;
+    use std::sync::Barrier;
+    use std::thread;
+
+    fn hash(n: u8) -> SchemaHash {
+        SchemaHash([n; 32])
+    }
+
+    /// Returns the encoding of a typespace of one product type with a field of type `ty`.
+    fn schema(ty: AlgebraicType) -> Vec<u8> {
+        let ts = Typespace::new(vec![AlgebraicType::product(vec![ProductTypeElement::new_named(
+            ty, "x",
+        )])]);
+        to_vec(&ts).unwrap()
+    }
+
+    #[test]
+    fn threads_share_a_state() {
+        let cache = SharedDecoderCache::new();
+        let bytes = schema(AlgebraicType::U32);
+        let barrier = Barrier::new(8);
+        let states = thread::scope(|s| {
+            let threads = (0..8)
+                .map(|_| {
+                    s.spawn(|| {
+                        barrier.wait();
+                        cache.get_or_build(hash(0), &bytes).unwrap()
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads.into_iter().map(|t| t.join().unwrap()).collect::<Vec<_>>()
+        });
+        assert!(states.iter().all(|state| Arc::ptr_eq(state, &states[0])));
+        assert_eq!(cache.len(), 1);
+
+        let value = states[0].decode(AlgebraicTypeRef(0), &to_vec(&7u32).unwrap()).unwrap();
+        assert_eq!(value, AlgebraicValue::Product(crate::product![7u32]));
+    }
+
+    #[test]
+    fn distinct_hashes_get_distinct_states() {
+        let cache = SharedDecoderCache::new();
+        let a = cache.get_or_build(hash(0), &schema(AlgebraicType::U32)).unwrap();
+        let b = cache.get_or_build(hash(1), &schema(AlgebraicType::String)).unwrap();
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert_eq!(cache.len(), 2);
+        assert_ne!(a.typespace().types, b.typespace().types);
+
+        // A cached hash is not parsed again, whatever the bytes.
+        let again = cache.get_or_build(hash(0), &[]).unwrap();
+        assert!(Arc::ptr_eq(&a, &again));
+
+        // Invalid schemas are not cached.
+        assert!(cache.get_or_build(hash(2), &[0xff]).is_err());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = SharedDecoderCache::with_capacity(2);
+        let bytes = schema(AlgebraicType::Bool);
+        let first = cache.get_or_build(hash(0), &bytes).unwrap();
+        cache.get_or_build(hash(1), &bytes).unwrap();
+        // Using `hash(0)` makes `hash(1)` the least recently used.
+        cache.get_or_build(hash(0), &bytes).unwrap();
+        cache.get_or_build(hash(2), &bytes).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        assert!(Arc::ptr_eq(&first, &cache.get_or_build(hash(0), &[]).unwrap()));
+        assert!(
+            cache.get_or_build(hash(1), &[]).is_err(),
+            "`hash(1)` should have been evicted"
+        );
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -1,33 +1,203 @@
-use crate::buffer::{BufReader, DecodeError};
+use std::marker::PhantomData;
 
-use crate::de::{self, SeqProductAccess, SumAccess, VariantAccess};
+use crate::buffer::{BufReader, DecodeError, ErrorKind};
+
+use crate::builtin_value::PackedStrings;
+use crate::de::{self, Deserialize, SeqProductAccess, SumAccess, VariantAccess};
+use crate::ser::InvalidPath;
+use crate::typespace::type_of;
+use crate::{AlgebraicType, ArrayValue, SpacetimeType, Typespace};
+
+use super::skip;
 
 /// Deserializer from the BSATN data format.
 pub struct Deserializer<'a, R> {
     // The input to deserialize.
     reader: &'a mut R,
+    /// Whether the string arrays of `ArrayValue`s are dictionary encoded.
+    #[cfg(feature = "compress")]
+    dictionary_strings: bool,
+    /// Whether the string arrays of `ArrayValue`s are decoded as packed strings.
+    packed_strings: bool,
 }
 
 impl<'a, 'de, R: BufReader<'de>> Deserializer<'a, R> {
     /// Returns a deserializer using the given `reader`.
     pub fn new(reader: &'a mut R) -> Self {
-        Self { reader }
+        Self {
+            reader,
+            #[cfg(feature = "compress")]
+            dictionary_strings: false,
+            packed_strings: false,
+        }
+    }
+
+    /// Sets whether the string arrays of [`ArrayValue`](crate::ArrayValue)s are decoded
+    /// as [`ArrayValue::StringPacked`](crate::ArrayValue::StringPacked),
+    /// taking a few allocations per array rather than one per string.
+    ///
+    /// This changes only the representation of the decoded arrays, not the encoding read.
+    pub fn with_packed_strings(self, packed_strings: bool) -> Self {
+        Self { packed_strings, ..self }
+    }
+
+    /// Sets whether the string arrays of [`ArrayValue`](crate::ArrayValue)s are [dictionary encoded](super::dictionary_encode),
+    /// as they were by the [`Serializer`](super::Serializer) encoding them.
+    #[cfg(feature = "compress")]
+    pub fn with_dictionary_strings(self, dictionary_strings: bool) -> Self {
+        Self {
+            dictionary_strings,
+            ..self
+        }
+    }
+
+    /// Deserializes an array of fixed-width numbers, e.g., a `Vec<u32>`, as a slice of them,
+    /// borrowed from the input where its elements are aligned for `T` on a little-endian target,
+    /// and copied into a vector otherwise.
+    ///
+    /// See [`decode_slice`](super::zerocopy::decode_slice) for when the elements are borrowed.
+    #[cfg(feature = "zerocopy")]
+    pub fn deserialize_slice<T>(self) -> Result<std::borrow::Cow<'de, [T]>, DecodeError>
+    where
+        T: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable + Clone,
+    {
+        let len = get_len(self.reader)?;
+        let size = len.checked_mul(std::mem::size_of::<T>()).ok_or(ErrorKind::Truncated {
+            needed: usize::MAX,
+            had: self.reader.remaining(),
+        })?;
+        super::zerocopy::decode_slice(self.reader.get_slice(size)?)
     }
 
     /// Reborrows the deserializer.
     #[inline]
     fn reborrow(&mut self) -> Deserializer<'_, R> {
-        Deserializer { reader: self.reader }
+        Deserializer {
+            reader: self.reader,
+            #[cfg(feature = "compress")]
+            dictionary_strings: self.dictionary_strings,
+            packed_strings: self.packed_strings,
+        }
+    }
+
+    /// Reads the length prefix of an array of `T`s
+    /// and returns an iterator lazily deserializing its elements on demand.
+    ///
+    /// Unlike deserializing a `Vec<T>`, the elements are never all kept in memory.
+    /// A length prefix claiming more elements than the rest of the input could hold is rejected up front.
+    /// See [`ArrayIter`] for what happens when the iterator is dropped early.
+    pub fn deserialize_array_iter<T: Deserialize<'de> + SpacetimeType>(
+        self,
+    ) -> Result<ArrayIter<'a, 'de, R, T>, DecodeError> {
+        let remaining = get_len(self.reader)?;
+        let (typespace, ty) = type_of::<T>();
+        let fixed_size = skip::fixed_size(typespace.with_type(&ty));
+        // Every element of a type of varying size takes up at least a byte.
+        let min_size = remaining.checked_mul(fixed_size.unwrap_or(1));
+        if min_size.map_or(true, |size| size > self.reader.remaining()) {
+            return Err(ErrorKind::Truncated {
+                needed: min_size.unwrap_or(usize::MAX),
+                had: self.reader.remaining(),
+            }
+            .into());
+        }
+        Ok(ArrayIter {
+            reader: self.reader,
+            remaining,
+            typespace,
+            ty,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// A lazy iterator over the elements of an array in the BSATN format.
+///
+/// When dropped before being exhausted,
+/// the remaining elements are skipped, without being decoded, so that the `reader` is positioned right after the array.
+/// Any error while skipping is swallowed, so to observe it, use [`ArrayIter::finish`] instead.
+pub struct ArrayIter<'a, 'de, R: BufReader<'de>, T: Deserialize<'de>> {
+    /// The input to deserialize elements from.
+    reader: &'a mut R,
+    /// The number of elements yet to be deserialized.
+    remaining: usize,
+    /// The typing context of `ty`.
+    typespace: Typespace,
+    /// The type of the elements, to skip over them by.
+    ty: AlgebraicType,
+    _marker: PhantomData<fn(&'de ()) -> T>,
+}
+
+impl<'de, R: BufReader<'de>, T: Deserialize<'de>> ArrayIter<'_, 'de, R, T> {
+    /// Skips any remaining elements, positioning the `reader` right after the array.
+    pub fn finish(mut self) -> Result<(), DecodeError> {
+        self.skip_remaining()
+    }
+
+    /// Skips over any remaining elements without decoding them,
+    /// in one step when the elements are of a fixed size.
+    fn skip_remaining(&mut self) -> Result<(), DecodeError> {
+        let remaining = std::mem::take(&mut self.remaining);
+        skip::skip_many(self.reader, remaining, &[self.typespace.with_type(&self.ty)])
+    }
+}
+
+impl<'de, R: BufReader<'de>, T: Deserialize<'de>> Iterator for ArrayIter<'_, 'de, R, T> {
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let elem = T::deserialize(Deserializer::new(self.reader));
+        // Stop after an error, as the position in the `reader` is then unknown.
+        self.remaining = if elem.is_ok() { self.remaining - 1 } else { 0 };
+        Some(elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<'de, R: BufReader<'de>, T: Deserialize<'de>> Drop for ArrayIter<'_, 'de, R, T> {
+    fn drop(&mut self) {
+        let _ = self.skip_remaining();
     }
 }
 
 impl de::Error for DecodeError {
     fn custom(msg: impl std::fmt::Display) -> Self {
-        DecodeError::Other(msg.to_string())
+        ErrorKind::Custom(msg.to_string()).into()
+    }
+
+    fn wrong_kind(expected: impl std::fmt::Display, found: impl std::fmt::Display) -> Self {
+        let (expected, found) = (expected.to_string(), found.to_string());
+        ErrorKind::WrongKind { expected, found }.into()
+    }
+
+    fn duplicate_element(index: usize) -> Self {
+        DecodeError::from(ErrorKind::DuplicateElement).in_element(index)
     }
 
-    fn unknown_variant_tag<'de, T: de::SumVisitor<'de>>(_tag: u8, _expected: &T) -> Self {
-        DecodeError::InvalidTag
+    fn invalid_path(err: InvalidPath) -> Self {
+        ErrorKind::InvalidPath(err).into()
+    }
+
+    fn in_field(self, index: usize, field_name: Option<&str>) -> Self {
+        DecodeError::in_field(self, index, field_name)
+    }
+
+    fn in_element(self, index: usize) -> Self {
+        DecodeError::in_element(self, index)
+    }
+
+    fn unknown_variant_tag<'de, T: de::SumVisitor<'de>>(tag: u8, expected: &T) -> Self {
+        let max = expected
+            .variant_count()
+            .and_then(|n| n.checked_sub(1))
+            .map(|max| max as u8);
+        DecodeError::new(ErrorKind::InvalidTag { got: tag, max }).with_excerpt(&[tag])
     }
 }
 
@@ -36,6 +206,16 @@ fn get_len<'de>(reader: &mut impl BufReader<'de>) -> Result<usize, DecodeError>
     Ok(reader.get_u32()? as usize)
 }
 
+/// Validates that `bytes` are UTF-8, returning them as a string slice.
+fn str_from_utf8(bytes: &[u8]) -> Result<&str, DecodeError> {
+    // The faster validation doesn't say where the error is, so redo the validation for that.
+    #[cfg(feature = "simdutf8")]
+    if let Ok(s) = simdutf8::basic::from_utf8(bytes) {
+        return Ok(s);
+    }
+    core::str::from_utf8(bytes).map_err(|e| DecodeError::utf8(e, bytes))
+}
+
 /// Read a byte slice from the `reader`.
 fn read_bytes<'a, 'de: 'a>(reader: &'a mut impl BufReader<'de>) -> Result<&'de [u8], DecodeError> {
     let len = get_len(reader)?;
@@ -54,7 +234,11 @@ impl<'de, 'a, R: BufReader<'de>> de::Deserializer<'de> for Deserializer<'a, R> {
     }
 
     fn deserialize_bool(self) -> Result<bool, Self::Error> {
-        self.reader.get_u8().map(|x| x != 0)
+        match self.reader.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(de::Error::wrong_kind("bool", format_args!("byte {b:#04x}"))),
+        }
     }
     fn deserialize_u8(self) -> Result<u8, DecodeError> {
         self.reader.get_u8()
@@ -95,7 +279,7 @@ impl<'de, 'a, R: BufReader<'de>> de::Deserializer<'de> for Deserializer<'a, R> {
 
     fn deserialize_str<V: de::SliceVisitor<'de, str>>(self, visitor: V) -> Result<V::Output, Self::Error> {
         let slice = read_bytes(self.reader)?;
-        let slice = core::str::from_utf8(slice)?;
+        let slice = str_from_utf8(slice)?;
         visitor.visit_borrowed(slice)
     }
 
@@ -128,6 +312,43 @@ impl<'de, 'a, R: BufReader<'de>> de::Deserializer<'de> for Deserializer<'a, R> {
         let seeds = itertools::repeat_n((kseed, vseed), len);
         visitor.visit(MapAccess { de: self, seeds })
     }
+
+    fn __deserialize_str_vec<T: Deserialize<'de>>(self, from_str: fn(&'de str) -> T) -> Result<Vec<T>, Self::Error> {
+        // Each string is validated as it's read, straight out of the input and into the output,
+        // so no string is allocated until all those before it are valid.
+        let len = get_len(self.reader)?;
+        // Every string takes at least its 4-byte length prefix, which bounds a bogus `len`.
+        let mut strs = Vec::with_capacity(len.min(self.reader.remaining() / 4));
+        for i in 0..len {
+            let bytes = read_bytes(self.reader).map_err(|e| e.in_element(i))?;
+            strs.push(from_str(str_from_utf8(bytes).map_err(|e| e.in_element(i))?));
+        }
+        Ok(strs)
+    }
+
+    fn __deserialize_string_array_value(self) -> Result<ArrayValue, Self::Error> {
+        #[cfg(feature = "compress")]
+        if self.dictionary_strings {
+            use super::dictionary_encode::{dictionary_decode_strings, get_string_array};
+            return Ok(if self.packed_strings {
+                let decode = |dict: &[String], codes: &[u16]| codes.iter().map(|&c| &dict[usize::from(c)]).collect();
+                get_string_array::<_, PackedStrings>(self.reader, decode)?.into()
+            } else {
+                get_string_array(self.reader, dictionary_decode_strings)?.into()
+            });
+        }
+        if self.packed_strings {
+            PackedStrings::deserialize(self).map(Into::into)
+        } else {
+            Vec::deserialize(self).map(ArrayValue::String)
+        }
+    }
+
+    #[cfg(feature = "bytemuck")]
+    fn __deserialize_pod_vec<T: Deserialize<'de> + bytemuck::Pod>(self) -> Result<Vec<T>, Self::Error> {
+        let len = get_len(self.reader)?;
+        super::pod::get_pod_vec(self.reader, len)
+    }
 }
 
 impl<'de, 'a, R: BufReader<'de>> SeqProductAccess<'de> for Deserializer<'a, R> {
@@ -206,3 +427,99 @@ impl<'de, 'a, R: BufReader<'de>, K: de::DeserializeSeed<'de> + Clone, V: de::Des
         Some(self.seeds.len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Deserializer;
+    use crate::bsatn::to_vec;
+    use crate::buffer::{BufReader, ErrorKind};
+    use crate::de::Deserialize;
+
+    #[test]
+    fn sum_large_array_lazily() {
+        let n = 1_000_000u64;
+        let bytes = to_vec(&(0..n).collect::<Vec<u64>>()).unwrap();
+        let mut reader = &bytes[..];
+        let iter = Deserializer::new(&mut reader).deserialize_array_iter::<u64>().unwrap();
+        let sum = iter.map(Result::unwrap).sum::<u64>();
+        assert_eq!(sum, n * (n - 1) / 2);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn early_drop_skips_remainder() {
+        let row = (vec!["a".to_owned(), "bc".to_owned(), "def".to_owned()], 42u32);
+        let mut bytes = to_vec(&row.0).unwrap();
+        bytes.extend(to_vec(&row.1).unwrap());
+
+        let mut reader = &bytes[..];
+        let mut iter = Deserializer::new(&mut reader)
+            .deserialize_array_iter::<String>()
+            .unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), "a");
+        drop(iter);
+
+        // The next field decodes correctly after the partially consumed array.
+        assert_eq!(u32::deserialize(Deserializer::new(&mut reader)).unwrap(), 42);
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn truncated_array() {
+        // An array of fixed-size elements too long for the input is rejected up front.
+        let bytes = to_vec(&[1u32, 2, 3][..]).unwrap();
+        let mut reader = &bytes[..bytes.len() - 1];
+        let err = Deserializer::new(&mut reader)
+            .deserialize_array_iter::<u32>()
+            .err()
+            .unwrap();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { needed: 12, had: 11 }));
+
+        // Otherwise, the element cut short errors.
+        let bytes = to_vec(&["a", "bc", "def"][..]).unwrap();
+        let mut reader = &bytes[..bytes.len() - 1];
+        let iter = Deserializer::new(&mut reader)
+            .deserialize_array_iter::<String>()
+            .unwrap();
+        let elems = iter.collect::<Vec<_>>();
+        assert!(matches!(&elems[..], [Ok(a), Ok(bc), Err(_)] if a == "a" && bc == "bc"));
+        let err = elems[2].as_ref().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { needed: 3, had: 2 }));
+
+        // The error is reported by `finish` too, when skipping over the element.
+        let mut reader = &bytes[..bytes.len() - 1];
+        let iter = Deserializer::new(&mut reader)
+            .deserialize_array_iter::<String>()
+            .unwrap();
+        let err = iter.finish().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { needed: 3, had: 2 }));
+    }
+
+    #[test]
+    fn huge_length_prefix_is_rejected() {
+        let mut bytes = u32::MAX.to_le_bytes().to_vec();
+        bytes.extend([0; 16]);
+        let err = Deserializer::new(&mut &bytes[..])
+            .deserialize_array_iter::<String>()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Truncated { needed, had: 16 } if *needed == u32::MAX as usize
+        ));
+    }
+
+    #[test]
+    fn finish_skips_fixed_size_elements_at_once() {
+        let mut bytes = to_vec(&vec![1u64..2; 1000]).unwrap();
+        bytes.extend(to_vec(&42u32).unwrap());
+        let mut reader = &bytes[..];
+        let mut iter = Deserializer::new(&mut reader)
+            .deserialize_array_iter::<std::ops::Range<u64>>()
+            .unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 1..2);
+        iter.finish().unwrap();
+        assert_eq!(u32::deserialize(Deserializer::new(&mut reader)).unwrap(), 42);
+        assert_eq!(reader.remaining(), 0);
+    }
+}
@@ -0,0 +1,273 @@
+//! Dictionary encoding of string arrays, for arrays with many repeated strings,
+//! e.g., an array of enum-like string tags.
+//!
+//! A dictionary encoded array is stored as the array of its distinct strings, the dictionary,
+//! followed by an array of `u16` codes, one per element, each the index of the element in the dictionary.
+//!
+//! The [`Serializer`](super::Serializer) and [`Deserializer`](super::Deserializer)
+//! use this for the string arrays of [`ArrayValue`](crate::ArrayValue)s
+//! when [`with_dictionary_strings`](super::Serializer::with_dictionary_strings) is set.
+//! Such an array is then prefixed by a tag byte,
+//! [`PLAIN`], for an array in the usual encoding, or [`DICTIONARY`], for a dictionary encoded array,
+//! so an array with too many distinct strings for a dictionary can still be encoded,
+//! as can one that dictionary encoding would not make any smaller, e.g., of mostly distinct strings.
+
+use std::collections::HashMap;
+
+use crate::buffer::{BufReader, BufWriter, DecodeError, ErrorKind};
+use crate::de::Deserialize;
+use crate::ser::Serialize;
+
+use super::de::Deserializer;
+use super::ser::{BsatnError, Serializer};
+
+/// The tag of a string array in the usual encoding.
+pub const PLAIN: u8 = 0;
+
+/// The tag of a dictionary encoded string array.
+pub const DICTIONARY: u8 = 1;
+
+/// Returns the dictionary of the distinct strings in `vals`, in the order they first occur,
+/// and the code of each of `vals`, its index in the dictionary.
+///
+/// # Panics
+///
+/// Panics if `vals` has more distinct strings than a `u16` code can index, i.e., more than 65536.
+pub fn dictionary_encode_strings(vals: &[String]) -> (Vec<String>, Vec<u16>) {
+    try_dictionary_encode(vals.iter().map(String::as_str)).expect("too many distinct strings for a dictionary")
+}
+
+/// Returns the strings of `dict` at each of `codes`.
+///
+/// # Panics
+///
+/// Panics if any of `codes` is out of bounds for `dict`.
+pub fn dictionary_decode_strings(dict: &[String], codes: &[u16]) -> Vec<String> {
+    codes.iter().map(|&code| dict[usize::from(code)].clone()).collect()
+}
+
+/// Dictionary encodes `vals`, as by [`dictionary_encode_strings`],
+/// or returns `None` if they have too many distinct strings.
+fn try_dictionary_encode<'a>(vals: impl IntoIterator<Item = &'a str>) -> Option<(Vec<String>, Vec<u16>)> {
+    let mut dict = Vec::new();
+    let mut codes_by_str = HashMap::new();
+    let codes = vals
+        .into_iter()
+        .map(|val| match codes_by_str.get(val) {
+            Some(&code) => Some(code),
+            None => {
+                let code = u16::try_from(dict.len()).ok()?;
+                codes_by_str.insert(val, code);
+                dict.push(val.to_owned());
+                Some(code)
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((dict, codes))
+}
+
+/// Returns the lengths of the usual encoding and of the dictionary encoding
+/// of the strings dictionary encoded as `dict` and `codes`, saturating on overflow.
+fn encoded_lens(dict: &[String], codes: &[u16]) -> (usize, usize) {
+    // Each array, and each string, is prefixed by its length.
+    let str_len = |s: &String| 4usize.saturating_add(s.len());
+    let plain = (codes.iter().map(|&code| str_len(&dict[code as usize]))).fold(4, usize::saturating_add);
+    let dict_len = dict.iter().map(str_len).fold(4, usize::saturating_add);
+    (plain, dict_len.saturating_add(4).saturating_add(2 * codes.len()))
+}
+
+/// Writes the string array `vals`, of the strings `strs`, to `writer`,
+/// dictionary encoded if it can be and that is smaller than the usual encoding.
+pub(crate) fn put_string_array<'a, W: BufWriter, V: Serialize + ?Sized>(
+    writer: &mut W,
+    vals: &V,
+    strs: impl IntoIterator<Item = &'a str>,
+) -> Result<(), BsatnError> {
+    let encoded = try_dictionary_encode(strs).filter(|(dict, codes)| {
+        let (plain, dictionary) = encoded_lens(dict, codes);
+        dictionary < plain
+    });
+    match encoded {
+        Some((dict, codes)) => {
+            writer.put_u8(DICTIONARY);
+            dict.serialize(Serializer::new(writer))?;
+            codes.serialize(Serializer::new(writer))
+        }
+        None => {
+            writer.put_u8(PLAIN);
+            vals.serialize(Serializer::new(writer))
+        }
+    }
+}
+
+/// Reads a string array written by [`put_string_array`] from `reader`,
+/// as a `T`, into which `decode` turns the dictionary and codes of a dictionary encoded array.
+pub(crate) fn get_string_array<'de, R: BufReader<'de>, T: Deserialize<'de>>(
+    reader: &mut R,
+    decode: impl FnOnce(&[String], &[u16]) -> T,
+) -> Result<T, DecodeError> {
+    match reader.get_u8()? {
+        PLAIN => T::deserialize(Deserializer::new(reader)),
+        DICTIONARY => {
+            let dict = Vec::<String>::deserialize(Deserializer::new(reader))?;
+            let codes = Vec::<u16>::deserialize(Deserializer::new(reader))?;
+            if let Some(i) = codes.iter().position(|&code| usize::from(code) >= dict.len()) {
+                let msg = format!(
+                    "code {} is out of bounds for a dictionary of {} strings",
+                    codes[i],
+                    dict.len()
+                );
+                return Err(DecodeError::from(ErrorKind::Custom(msg)).in_element(i));
+            }
+            Ok(decode(&dict, &codes))
+        }
+        got => Err(DecodeError::new(ErrorKind::InvalidTag {
+            got,
+            max: Some(DICTIONARY),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_value::PackedStrings;
+    use crate::de::DeserializeSeed;
+    use crate::{AlgebraicType, AlgebraicValue, ArrayValue, ProductTypeElement, Typespace, WithTypespace};
+
+    /// Returns `n` strings drawn from a vocabulary of `vocab` tags,
+    /// all of them distinct as long as `n <= vocab` and `vocab` is coprime to 7.
+    fn tags(n: usize, vocab: usize) -> Vec<String> {
+        (0..n).map(|i| format!("tag_{}", i * 7 % vocab)).collect()
+    }
+
+    fn encode(value: &AlgebraicValue) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        value
+            .serialize(Serializer::new(&mut bytes).with_dictionary_strings(true))
+            .unwrap();
+        bytes
+    }
+
+    fn decode(ty: &AlgebraicType, mut bytes: &[u8]) -> Result<AlgebraicValue, DecodeError> {
+        let ts = Typespace::new(vec![]);
+        let value =
+            WithTypespace::new(&ts, ty).deserialize(Deserializer::new(&mut bytes).with_dictionary_strings(true));
+        assert!(value.is_err() || bytes.is_empty());
+        value
+    }
+
+    #[test]
+    fn encode_and_decode() {
+        let vals = ["b", "a", "b", "c", "a"].map(String::from);
+        let (dict, codes) = dictionary_encode_strings(&vals);
+        assert_eq!(dict, ["b", "a", "c"]);
+        assert_eq!(codes, [0, 1, 0, 2, 1]);
+        assert_eq!(dictionary_decode_strings(&dict, &codes), vals);
+        assert_eq!(dictionary_encode_strings(&[]), (vec![], vec![]));
+    }
+
+    #[test]
+    fn round_trip_in_bsatn() {
+        let strings = AlgebraicValue::Array(ArrayValue::String(tags(50, 5)));
+        let row = AlgebraicValue::Product(crate::product![3u8, strings.clone()]);
+        let ty = AlgebraicType::product(vec![
+            ProductTypeElement::new(AlgebraicType::U8, None),
+            ProductTypeElement::new(AlgebraicType::array(AlgebraicType::String), None),
+        ]);
+        let bytes = encode(&row);
+        assert_eq!(bytes[1], DICTIONARY);
+        assert_eq!(decode(&ty, &bytes).unwrap(), row);
+
+        // Without the flag, the usual encoding is used.
+        assert_eq!(super::super::to_vec(&row).unwrap()[1..5], 50u32.to_le_bytes());
+    }
+
+    #[test]
+    fn shrinks_repeated_strings() {
+        let strings = AlgebraicValue::Array(ArrayValue::String(tags(10_000, 100)));
+        let plain = super::super::to_vec(&strings).unwrap();
+        let dictionary = encode(&strings);
+        assert!(
+            dictionary.len() * 10 <= plain.len() * 3,
+            "{} bytes down to only {}",
+            plain.len(),
+            dictionary.len()
+        );
+        assert_eq!(
+            decode(&AlgebraicType::array(AlgebraicType::String), &dictionary).unwrap(),
+            strings
+        );
+    }
+
+    #[test]
+    fn packed_strings_encode_the_same() {
+        let ty = AlgebraicType::array(AlgebraicType::String);
+        for strings in [tags(1000, 10), tags(70_000, 70_001)] {
+            let unpacked = AlgebraicValue::Array(ArrayValue::String(strings.clone()));
+            let packed = AlgebraicValue::Array(PackedStrings::from(&*strings).into());
+            let bytes = encode(&unpacked);
+            assert_eq!(encode(&packed), bytes);
+
+            let mut reader = &*bytes;
+            let de = Deserializer::new(&mut reader)
+                .with_dictionary_strings(true)
+                .with_packed_strings(true);
+            let decoded = WithTypespace::new(&Typespace::new(vec![]), &ty)
+                .deserialize(de)
+                .unwrap();
+            assert!(matches!(decoded, AlgebraicValue::Array(ArrayValue::StringPacked(_))));
+            assert_eq!(decoded, unpacked);
+        }
+    }
+
+    #[test]
+    fn too_many_distinct_strings() {
+        let strings = AlgebraicValue::Array(ArrayValue::String(tags(70_000, 70_001)));
+        let bytes = encode(&strings);
+        assert_eq!(bytes[0], PLAIN);
+        assert_eq!(bytes[1..], super::super::to_vec(&strings).unwrap());
+        assert_eq!(
+            decode(&AlgebraicType::array(AlgebraicType::String), &bytes).unwrap(),
+            strings
+        );
+    }
+
+    #[test]
+    fn no_larger_than_plain() {
+        // Distinct strings would only gain the codes in a dictionary.
+        for strings in [tags(100, 101), vec![], vec!["once".to_owned()]] {
+            let strings = AlgebraicValue::Array(ArrayValue::String(strings));
+            let bytes = encode(&strings);
+            assert_eq!(bytes[0], PLAIN);
+            assert_eq!(bytes[1..], super::super::to_vec(&strings).unwrap());
+            assert_eq!(
+                decode(&AlgebraicType::array(AlgebraicType::String), &bytes).unwrap(),
+                strings
+            );
+        }
+        // A string repeated is smaller in a dictionary only once the repeats outweigh the codes,
+        // i.e., not at 4 + 2 * 5 bytes plain against 4 + 5 + 4 + 2 * 2 bytes in a dictionary,
+        // but at 4 + 3 * 8 bytes plain against 4 + 8 + 4 + 3 * 2 bytes.
+        let twice = AlgebraicValue::Array(ArrayValue::String(vec!["a".to_owned(); 2]));
+        assert_eq!(encode(&twice)[0], PLAIN);
+        let thrice = AlgebraicValue::Array(ArrayValue::String(vec!["abcd".to_owned(); 3]));
+        assert_eq!(encode(&thrice)[0], DICTIONARY);
+    }
+
+    #[test]
+    fn invalid_codes() {
+        let ty = AlgebraicType::array(AlgebraicType::String);
+        let mut bytes = vec![DICTIONARY];
+        bytes.extend(super::super::to_vec(&["a".to_owned()]).unwrap());
+        bytes.extend(super::super::to_vec(&[0u16, 1]).unwrap());
+        let err = decode(&ty, &bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "code 1 is out of bounds for a dictionary of 1 strings in `[1]`"
+        );
+
+        let err = decode(&ty, &[7]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidTag { got: 7, .. }), "{err}");
+    }
+}
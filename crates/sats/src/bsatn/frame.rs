@@ -0,0 +1,265 @@
+//! A checksummed, length-prefixed framing of BSATN encodings, for durable logs.
+//!
+//! Each frame is laid out as `[len: u32][crc32c: u32][payload]`,
+//! with both integers in little-endian, `len` the length of the payload in bytes,
+//! and `crc32c` the CRC-32C checksum of the payload.
+//! Frames are written one after the other, so a torn write at the end of a log
+//! shows up as an [`UnexpectedEof`](FrameError::UnexpectedEof),
+//! while bit rot anywhere shows up as a [`Corrupt`](FrameError::Corrupt) frame.
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use crate::buffer::DecodeError;
+use crate::de::DeserializeOwned;
+use crate::ser::{Error as _, Serialize};
+
+use super::ser::BsatnError;
+use super::{from_slice, to_vec};
+
+/// The length of the header of a frame, i.e., the length prefix and the checksum.
+pub const HEADER_LEN: usize = 8;
+
+/// The maximum length of the payload of a frame, 256 MiB.
+///
+/// Longer frames are refused when writing,
+/// and a longer length read is taken to be corrupt rather than allocated for.
+pub const MAX_FRAME_LEN: u32 = 256 << 20;
+
+/// An error writing or reading a frame.
+#[derive(thiserror::Error, Debug)]
+pub enum FrameError {
+    /// The input ended within a frame, having `had` of the `needed` bytes.
+    ///
+    /// At the end of a log, this is the expected result of a torn write,
+    /// and log recovery can treat it as the end of the log.
+    #[error("frame truncated: needed {needed} bytes, had {had}")]
+    UnexpectedEof { needed: usize, had: usize },
+    /// The frame is corrupt.
+    #[error("corrupt frame: {0}")]
+    Corrupt(CorruptFrame),
+    /// The payload of a frame, while intact, is not a valid BSATN encoding of the expected type.
+    #[error("invalid payload in frame: {0}")]
+    Decode(#[from] DecodeError),
+    /// A value could not be encoded as the payload of a frame.
+    #[error("could not encode frame: {0}")]
+    Encode(#[from] BsatnError),
+    /// Reading or writing failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The ways a [`FrameError::Corrupt`] frame can be corrupt.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CorruptFrame {
+    /// The length prefix exceeds [`MAX_FRAME_LEN`].
+    #[error("length {len} exceeds the maximum of {MAX_FRAME_LEN}")]
+    TooLong { len: u32 },
+    /// The checksum of the payload is not the one stored in the header.
+    #[error("checksum {computed:#010x} does not match the stored {stored:#010x}")]
+    Checksum { stored: u32, computed: u32 },
+}
+
+/// Writes `value` encoded in BSATN as one frame to `w`.
+pub fn write_frame<W: Write, T: Serialize + ?Sized>(w: &mut W, value: &T) -> Result<(), FrameError> {
+    let payload = to_vec(value)?;
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|&len| len <= MAX_FRAME_LEN)
+        .ok_or_else(|| {
+            BsatnError::custom(format_args!(
+                "payload of {} bytes exceeds the maximum frame length of {MAX_FRAME_LEN}",
+                payload.len()
+            ))
+        })?;
+    let mut header = [0; HEADER_LEN];
+    header[..4].copy_from_slice(&len.to_le_bytes());
+    header[4..].copy_from_slice(&crc32c::crc32c(&payload).to_le_bytes());
+    w.write_all(&header)?;
+    w.write_all(&payload)?;
+    Ok(())
+}
+
+/// Reads one frame from `r`, returning its validated payload,
+/// or `None` if `r` is at its end before the frame starts.
+///
+/// On success, `r` is positioned at the start of the next frame.
+/// On error, the position of `r` is somewhere within the frame.
+pub fn read_frame<R: Read>(r: &mut R) -> Result<Option<Vec<u8>>, FrameError> {
+    let mut header = [0; HEADER_LEN];
+    match read_full(r, &mut header)? {
+        0 => return Ok(None),
+        HEADER_LEN => {}
+        had => {
+            return Err(FrameError::UnexpectedEof {
+                needed: HEADER_LEN,
+                had,
+            })
+        }
+    }
+    let [l0, l1, l2, l3, c0, c1, c2, c3] = header;
+    let len = u32::from_le_bytes([l0, l1, l2, l3]);
+    let stored = u32::from_le_bytes([c0, c1, c2, c3]);
+    if len > MAX_FRAME_LEN {
+        return Err(FrameError::Corrupt(CorruptFrame::TooLong { len }));
+    }
+
+    // Grow the payload as it is read, as the input may end long before `len` bytes.
+    let mut payload = Vec::new();
+    r.take(len.into()).read_to_end(&mut payload)?;
+    if payload.len() < len as usize {
+        let (needed, had) = (HEADER_LEN + len as usize, HEADER_LEN + payload.len());
+        return Err(FrameError::UnexpectedEof { needed, had });
+    }
+    let computed = crc32c::crc32c(&payload);
+    if computed != stored {
+        return Err(FrameError::Corrupt(CorruptFrame::Checksum { stored, computed }));
+    }
+    Ok(Some(payload))
+}
+
+/// Reads from `r` into `buf` until `buf` is full or `r` ends, returning the number of bytes read.
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// An iterator over the values in consecutive frames read from `R`, decoded as `T`s.
+///
+/// The iterator ends at the end of the input, or after yielding the first error.
+pub struct FrameIter<R, T> {
+    /// The input to read frames from, or `None` once the iterator has ended.
+    reader: Option<R>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T: DeserializeOwned> FrameIter<R, T> {
+    /// Returns an iterator over the frames in `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: Some(reader),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Reads and decodes the next frame.
+    fn read_next(reader: &mut R) -> Result<Option<T>, FrameError> {
+        match read_frame(reader)? {
+            Some(payload) => Ok(Some(from_slice(&payload)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for FrameIter<R, T> {
+    type Item = Result<T, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = Self::read_next(self.reader.as_mut()?).transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.reader = None;
+        }
+        next
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> std::iter::FusedIterator for FrameIter<R, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a log of three frames, of the strings `"one"`, `"two"` and `"three"`,
+    /// as well as the offsets at which the frames start.
+    fn three_frames_log() -> (Vec<u8>, Vec<usize>) {
+        let mut log = Vec::new();
+        let mut starts = Vec::new();
+        for s in ["one", "two", "three"] {
+            starts.push(log.len());
+            write_frame(&mut log, s).unwrap();
+        }
+        (log, starts)
+    }
+
+    fn read_all(log: &[u8]) -> Vec<Result<String, FrameError>> {
+        FrameIter::new(log).collect()
+    }
+
+    #[test]
+    fn three_frames() {
+        let (log, starts) = three_frames_log();
+        // Each frame is the header, then the length prefixed string.
+        assert_eq!(starts, [0, 15, 30]);
+        assert_eq!(log[..4], [7, 0, 0, 0]);
+        assert_eq!(log[4..HEADER_LEN], crc32c::crc32c(&log[HEADER_LEN..15]).to_le_bytes());
+
+        let values = read_all(&log).into_iter().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(values, ["one", "two", "three"]);
+
+        let mut reader = &log[..];
+        for start in &starts[1..] {
+            read_frame(&mut reader).unwrap().unwrap();
+            assert_eq!(log.len() - reader.len(), *start);
+        }
+        read_frame(&mut reader).unwrap().unwrap();
+        assert!(read_frame(&mut reader).unwrap().is_none());
+        assert!(read_all(&[]).is_empty());
+    }
+
+    #[test]
+    fn flipped_bit() {
+        let (mut log, starts) = three_frames_log();
+        // Flip a bit in the payload of the middle frame.
+        log[starts[1] + HEADER_LEN + 5] ^= 0x10;
+
+        let mut frames = read_all(&log).into_iter();
+        assert_eq!(frames.next().unwrap().unwrap(), "one");
+        let err = frames.next().unwrap().unwrap_err();
+        assert!(
+            matches!(err, FrameError::Corrupt(CorruptFrame::Checksum { .. })),
+            "{err}"
+        );
+        // The iterator ends after the error.
+        assert!(frames.next().is_none());
+
+        // A flipped bit in a length prefix can make it too long.
+        let (mut log, starts) = three_frames_log();
+        log[starts[1] + 3] ^= 0x80;
+        let err = read_all(&log).pop().unwrap().unwrap_err();
+        assert!(
+            matches!(err, FrameError::Corrupt(CorruptFrame::TooLong { .. })),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn half_written_final_frame() {
+        let (log, starts) = three_frames_log();
+        // Cut the log short within the payload of the last frame, then within its header.
+        for end in [log.len() - 3, starts[2] + 5] {
+            let frames = read_all(&log[..end]);
+            assert_eq!(frames.len(), 3);
+            assert_eq!(frames[1].as_ref().unwrap(), "two");
+            let err = frames[2].as_ref().unwrap_err();
+            assert!(matches!(err, FrameError::UnexpectedEof { .. }), "{err}");
+        }
+        let err = read_frame(&mut &log[starts[2]..log.len() - 3]).unwrap_err();
+        assert_eq!(err.to_string(), "frame truncated: needed 17 bytes, had 14");
+    }
+
+    #[test]
+    fn invalid_payload() {
+        let mut log = Vec::new();
+        write_frame(&mut log, &2u8).unwrap();
+        let err = FrameIter::<_, bool>::new(&log[..]).next().unwrap().unwrap_err();
+        assert!(matches!(err, FrameError::Decode(_)), "{err}");
+    }
+}
@@ -0,0 +1,231 @@
+//! Reading files of BSATN-encoded rows through a memory map,
+//! without loading the whole file into memory.
+//!
+//! Such a file consists of, with all integers in little-endian:
+//! 1. the magic bytes [`MAGIC`],
+//! 2. the number of rows `n` as a `u64`,
+//! 3. an index of `n + 1` offsets, each a `u64`,
+//!    where row `i` spans the bytes `offsets[i]..offsets[i + 1]` of the data,
+//! 4. the data, i.e., the BSATN encodings of the rows back to back.
+//!
+//! Files in this format are written by [`write_rows`].
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use super::ser::BsatnError;
+use super::{to_writer, DecodeError, Deserializer};
+use crate::de::DeserializeSeed;
+use crate::ser::Error as _;
+use crate::{ProductType, ProductValue, Typespace, WithTypespace};
+
+/// The magic bytes every file of BSATN rows starts with.
+pub const MAGIC: [u8; 8] = *b"BSATNROW";
+
+/// The length of the header preceding the offset index.
+const HEADER_LEN: usize = MAGIC.len() + 8;
+
+/// Writes `rows` to `w` in the format read by [`MmapBsatnFile`].
+pub fn write_rows(w: &mut impl io::Write, rows: &[ProductValue]) -> io::Result<()> {
+    let mut data = Vec::new();
+    let mut offsets = Vec::with_capacity(rows.len() + 1);
+    offsets.push(0u64);
+    for row in rows {
+        to_writer(&mut data, row).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        offsets.push(data.len() as u64);
+    }
+
+    w.write_all(&MAGIC)?;
+    w.write_all(&(rows.len() as u64).to_le_bytes())?;
+    for offset in offsets {
+        w.write_all(&offset.to_le_bytes())?;
+    }
+    w.write_all(&data)
+}
+
+/// A memory-mapped file of BSATN-encoded rows, all of the same product type.
+pub struct MmapBsatnFile {
+    /// The contents of the file.
+    mmap: Mmap,
+    /// The type of every row.
+    schema: ProductType,
+    /// The typespace `schema` is resolved in.
+    typespace: Typespace,
+    /// The number of rows in the file.
+    row_count: usize,
+}
+
+impl MmapBsatnFile {
+    /// Memory-maps the file at `path` with rows of type `schema` in the context of `ts`.
+    ///
+    /// The header and offset index are validated,
+    /// but the rows themselves are only decoded on demand.
+    pub fn open(path: &Path, schema: &ProductType, ts: &Typespace) -> Result<Self, io::Error> {
+        let file = File::open(path)?;
+        // SAFETY: Modifying the file while it is mapped is undefined behavior.
+        // There is no way to rule this out, so like any user of `memmap2`,
+        // we rely on the file not being modified by other processes.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let row_count = validate(&mmap).map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+        Ok(Self {
+            mmap,
+            schema: schema.clone(),
+            typespace: ts.clone(),
+            row_count,
+        })
+    }
+
+    /// Returns the number of rows in the file.
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Returns the encoding of the row at `idx`.
+    ///
+    /// Errors if `idx` is out of bounds.
+    pub fn get_row(&self, idx: usize) -> Result<BsatnSlice<'_>, BsatnError> {
+        if idx >= self.row_count {
+            return Err(BsatnError::custom(format_args!(
+                "row index {idx} out of bounds for a file of {} rows",
+                self.row_count
+            )));
+        }
+        let data = &self.mmap[data_start(self.row_count)..];
+        let (start, end) = (offset(&self.mmap, idx), offset(&self.mmap, idx + 1));
+        Ok(BsatnSlice {
+            bytes: &data[start..end],
+            schema: &self.schema,
+            typespace: &self.typespace,
+        })
+    }
+}
+
+/// The BSATN encoding of a row borrowed from a [`MmapBsatnFile`].
+#[derive(Clone, Copy)]
+pub struct BsatnSlice<'a> {
+    /// The encoded row.
+    bytes: &'a [u8],
+    /// The type of the row.
+    schema: &'a ProductType,
+    /// The typespace `schema` is resolved in.
+    typespace: &'a Typespace,
+}
+
+impl<'a> BsatnSlice<'a> {
+    /// Returns the encoded row.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the row.
+    pub fn decode(&self) -> Result<ProductValue, DecodeError> {
+        WithTypespace::new(self.typespace, self.schema).deserialize(Deserializer::new(&mut &*self.bytes))
+    }
+}
+
+/// Returns where the data begins in a file of `row_count` rows.
+fn data_start(row_count: usize) -> usize {
+    HEADER_LEN + (row_count + 1) * 8
+}
+
+/// Returns the `idx`th offset of the index in `file`.
+fn offset(file: &[u8], idx: usize) -> usize {
+    let at = HEADER_LEN + idx * 8;
+    u64::from_le_bytes(file[at..at + 8].try_into().unwrap()) as usize
+}
+
+/// Validates the header and offset index of `file`, returning the number of rows.
+fn validate(file: &[u8]) -> Result<usize, String> {
+    if file.len() < HEADER_LEN || file[..MAGIC.len()] != MAGIC {
+        return Err("not a file of BSATN rows".into());
+    }
+    let row_count = u64::from_le_bytes(file[MAGIC.len()..HEADER_LEN].try_into().unwrap());
+    let data_len = usize::try_from(row_count)
+        .ok()
+        .and_then(|n| n.checked_add(1)?.checked_mul(8)?.checked_add(HEADER_LEN))
+        .and_then(|start| file.len().checked_sub(start))
+        .ok_or_else(|| format!("file too short for an index of {row_count} rows"))?;
+    let row_count = row_count as usize;
+
+    if offset(file, 0) != 0 {
+        return Err("the first row does not start at the beginning of the data".into());
+    }
+    for idx in 0..row_count {
+        if offset(file, idx) > offset(file, idx + 1) {
+            return Err(format!("the offsets of row {idx} are out of order"));
+        }
+    }
+    if offset(file, row_count) != data_len {
+        return Err(format!("the rows do not span the {data_len} bytes of data"));
+    }
+    Ok(row_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, AlgebraicType, ProductTypeElement};
+    use std::io::Write;
+
+    fn schema() -> ProductType {
+        ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ])
+    }
+
+    fn rows() -> Vec<ProductValue> {
+        (0..10u32).map(|i| product![i, format!("row {i}")]).collect()
+    }
+
+    #[test]
+    fn reads_written_rows() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write_rows(&mut file, &rows()).unwrap();
+        file.flush().unwrap();
+
+        let ts = Typespace::default();
+        let mmap = MmapBsatnFile::open(file.path(), &schema(), &ts).unwrap();
+        assert_eq!(mmap.row_count(), 10);
+        assert_eq!(mmap.get_row(0).unwrap().decode().unwrap(), rows()[0]);
+        assert_eq!(mmap.get_row(9).unwrap().decode().unwrap(), rows()[9]);
+        assert!(mmap.get_row(10).is_err());
+    }
+
+    #[test]
+    fn empty_file_of_rows() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write_rows(&mut file, &[]).unwrap();
+        file.flush().unwrap();
+
+        let mmap = MmapBsatnFile::open(file.path(), &schema(), &Typespace::default()).unwrap();
+        assert_eq!(mmap.row_count(), 0);
+        assert!(mmap.get_row(0).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        let mut bytes = Vec::new();
+        write_rows(&mut bytes, &rows()).unwrap();
+
+        let open = |bytes: &[u8]| {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(bytes).unwrap();
+            file.flush().unwrap();
+            MmapBsatnFile::open(file.path(), &schema(), &Typespace::default()).map(drop)
+        };
+        assert!(open(&bytes).is_ok());
+        // Wrong magic.
+        assert!(open(&[b"BSATNROX", &bytes[8..]].concat()).is_err());
+        // Truncated data and index.
+        assert!(open(&bytes[..bytes.len() - 1]).is_err());
+        assert!(open(&bytes[..HEADER_LEN + 8]).is_err());
+        // Absurd row count.
+        let mut huge = bytes.clone();
+        huge[MAGIC.len()..HEADER_LEN].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(open(&huge).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Encoding large arrays in the BSATN format on several threads at once.
+//!
+//! The elements of a BSATN array follow its `u32` length back to back without any further framing.
+//! So chunks of the elements can be encoded independently and concatenated afterwards,
+//! or, when each element has a fixed width, written straight to their offsets in the output.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+use super::ser::{put_len, BsatnError};
+use super::to_writer;
+use crate::ser::Serialize;
+use crate::{ArrayValue, ProductType, ProductValue, Typespace};
+
+/// The fewest elements a thread is handed at once,
+/// so that small arrays aren't split into chunks too small to be worth the overhead.
+const MIN_CHUNK_LEN: usize = 1024;
+
+/// Returns the number of elements per chunk when splitting `len` elements between threads.
+fn chunk_len(len: usize) -> usize {
+    // Give each thread a few chunks so that uneven elements even out.
+    (len / (4 * rayon::current_num_threads())).max(MIN_CHUNK_LEN)
+}
+
+/// Serialize `rows` as an array in the BSATN format,
+/// encoding chunks of the rows into separate buffers in parallel.
+///
+/// The output is identical to that of [`to_vec(rows)`](super::to_vec).
+pub fn to_vec_parallel<T: Serialize + Sync>(rows: &[T]) -> Result<Vec<u8>, BsatnError> {
+    let chunks = rows
+        .par_chunks(chunk_len(rows.len()))
+        .map(|chunk| {
+            let mut buf = Vec::new();
+            for row in chunk {
+                to_writer(&mut buf, row)?;
+            }
+            Ok(buf)
+        })
+        .collect::<Result<Vec<_>, BsatnError>>()?;
+    concat_array(rows.len(), chunks)
+}
+
+/// Serialize `rows`, each of type `schema`, as an array in the BSATN format,
+/// encoding chunks of the rows in parallel on `num_threads` threads, as [`to_vec_parallel`] does.
+///
+/// The threads are those of a pool kept between calls for each `num_threads`,
+/// so that repeatedly encoding tables doesn't spawn threads each time.
+/// A `num_threads` of `0` uses as many threads as rayon does by default.
+/// To use a pool of one's own, see [`encode_rows_parallel_in`].
+///
+/// The output is identical to that of [`to_vec`](super::to_vec) of the rows typed by `schema`.
+///
+/// Panics if a row does not match `schema`, or there are more than `u32::MAX` rows.
+pub fn encode_rows_parallel(
+    rows: &[ProductValue],
+    schema: &ProductType,
+    ts: &Typespace,
+    num_threads: usize,
+) -> Vec<u8> {
+    encode_rows_parallel_in(rows, schema, ts, &pool_of(num_threads)).expect("rows could not be encoded")
+}
+
+/// Serialize `rows`, each of type `schema`, as an array in the BSATN format,
+/// encoding chunks of the rows in parallel on the threads of `pool`, as [`to_vec_parallel`] does.
+///
+/// The output is identical to that of [`to_vec`](super::to_vec) of the rows typed by `schema`.
+pub fn encode_rows_parallel_in(
+    rows: &[ProductValue],
+    schema: &ProductType,
+    ts: &Typespace,
+    pool: &ThreadPool,
+) -> Result<Vec<u8>, BsatnError> {
+    let schema = ts.with_type(schema);
+    let rows = rows.iter().map(|row| schema.with_value(row)).collect::<Vec<_>>();
+    pool.install(|| to_vec_parallel(&rows))
+}
+
+/// Returns the pool of `num_threads` threads used by [`encode_rows_parallel`], building it on first use.
+fn pool_of(num_threads: usize) -> Arc<ThreadPool> {
+    static POOLS: Mutex<Vec<(usize, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
+    let mut pools = POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, pool)) = pools.iter().find(|(n, _)| *n == num_threads) {
+        return pool.clone();
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .expect("could not spawn the threads of a pool");
+    let pool = Arc::new(pool);
+    pools.push((num_threads, pool.clone()));
+    pool
+}
+
+/// Returns the BSATN array of `len` elements whose encodings are split between `chunks`, in order.
+fn concat_array(len: usize, chunks: Vec<Vec<u8>>) -> Result<Vec<u8>, BsatnError> {
+    let mut out = Vec::with_capacity(4 + chunks.iter().map(Vec::len).sum::<usize>());
+    put_len(&mut out, len)?;
+    for chunk in chunks {
+        out.extend_from_slice(&chunk);
+    }
+    Ok(out)
+}
+
+/// Serialize `arr` in the BSATN format, encoding its elements in parallel.
+///
+/// Arrays of fixed-width elements, i.e., of bools and numbers,
+/// are written in place as the offset of each element is known up front.
+/// Other arrays are encoded as by [`to_vec_parallel`].
+///
+/// The output is identical to that of [`to_vec(arr)`](super::to_vec).
+pub fn array_to_vec_parallel(arr: &ArrayValue) -> Result<Vec<u8>, BsatnError> {
+    match arr {
+        ArrayValue::Sum(v) => to_vec_parallel(v),
+        ArrayValue::Product(v) => to_vec_parallel(v),
+        ArrayValue::Bool(v) => fixed_width(v, |&x| [x as u8]),
+        ArrayValue::I8(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::U8(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::I16(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::U16(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::I32(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::U32(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::I64(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::U64(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::I128(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::U128(v) => fixed_width(v, |x| x.to_le_bytes()),
+        ArrayValue::F32(v) => fixed_width(v, |&x| f32::from(x).to_bits().to_le_bytes()),
+        ArrayValue::F64(v) => fixed_width(v, |&x| f64::from(x).to_bits().to_le_bytes()),
+        ArrayValue::String(v) => to_vec_parallel(v),
+        // Packed strings are contiguous already, so they're encoded in one quick pass.
+        ArrayValue::StringPacked(v) => super::to_vec(v),
+        ArrayValue::Array(v) => to_vec_parallel(v),
+        ArrayValue::Map(v) => to_vec_parallel(v),
+    }
+}
+
+/// Serialize `elems` as an array in the BSATN format,
+/// where `encode` provides the `W` bytes of each element.
+fn fixed_width<T: Sync, const W: usize>(
+    elems: &[T],
+    encode: impl Fn(&T) -> [u8; W] + Sync,
+) -> Result<Vec<u8>, BsatnError> {
+    let mut out = Vec::with_capacity(4 + elems.len() * W);
+    put_len(&mut out, elems.len())?;
+    let start = out.len();
+    out.resize(start + elems.len() * W, 0);
+
+    let chunk_len = chunk_len(elems.len());
+    out[start..]
+        .par_chunks_mut(chunk_len * W)
+        .zip(elems.par_chunks(chunk_len))
+        .for_each(|(bytes, elems)| {
+            for (bytes, elem) in bytes.chunks_exact_mut(W).zip(elems) {
+                bytes.copy_from_slice(&encode(elem));
+            }
+        });
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_value::{F32, F64};
+    use crate::{bsatn, product, AlgebraicType, AlgebraicValue, ProductValue};
+
+    /// A table of `len` rows of varying widths.
+    fn table(len: u32) -> Vec<ProductValue> {
+        (0..len)
+            .map(|i| {
+                product![
+                    i,
+                    "x".repeat(i as usize % 17),
+                    AlgebraicValue::OptionSome(AlgebraicValue::U64(u64::from(i) << 3)),
+                    AlgebraicValue::ArrayOf(vec![i as u16; i as usize % 5])
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_match_serial_encoding() {
+        for len in [0, 1, MIN_CHUNK_LEN as u32 - 1, 100_003] {
+            let rows = table(len);
+            assert_eq!(
+                to_vec_parallel(&rows).unwrap(),
+                bsatn::to_vec(&rows).unwrap(),
+                "len = {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn typed_rows_match_serial_encoding() {
+        let schema = ProductType::from_iter([
+            AlgebraicType::U32,
+            AlgebraicType::String,
+            AlgebraicType::option(AlgebraicType::U64),
+            AlgebraicType::array(AlgebraicType::U16),
+        ]);
+        let ts = Typespace::default();
+        let pools = [1, 3, 8].map(|num_threads| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(num_threads).build();
+            pool.unwrap()
+        });
+        for len in [0, 1, 7, 100_003] {
+            let rows = table(len);
+            let serial = bsatn::to_vec(&rows).unwrap();
+            for pool in &pools {
+                assert_eq!(
+                    encode_rows_parallel_in(&rows, &schema, &ts, pool).unwrap(),
+                    serial,
+                    "len = {len}, num_threads = {}",
+                    pool.current_num_threads()
+                );
+            }
+            for num_threads in [0, 1, 3, 8] {
+                assert_eq!(
+                    encode_rows_parallel(&rows, &schema, &ts, num_threads),
+                    serial,
+                    "len = {len}, num_threads = {num_threads}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pools_are_kept_between_calls() {
+        let pool = pool_of(5);
+        assert_eq!(pool.current_num_threads(), 5);
+        assert!(Arc::ptr_eq(&pool, &pool_of(5)));
+        assert!(!Arc::ptr_eq(&pool, &pool_of(6)));
+    }
+
+    #[test]
+    fn arrays_match_serial_encoding() {
+        let len = 100_003u32;
+        let arrays = [
+            ArrayValue::from(table(len)),
+            ArrayValue::from((0..len).map(|i| i % 3 == 0).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| i as u8).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| (i as i16).wrapping_neg()).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| i.wrapping_mul(2654435761)).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| -i64::from(i) << 20).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| u128::from(i) << 90).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| F32::from(i as f32 / 7.0)).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| F64::from(f64::from(i) * -0.5)).collect::<Vec<_>>()),
+            ArrayValue::from((0..len).map(|i| i.to_string()).collect::<Vec<_>>()),
+            ArrayValue::from(Vec::<u64>::new()),
+        ];
+        for arr in &arrays {
+            assert_eq!(array_to_vec_parallel(arr).unwrap(), bsatn::to_vec(arr).unwrap());
+        }
+    }
+}
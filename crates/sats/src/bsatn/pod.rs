@@ -0,0 +1,150 @@
+//! Encoding arrays of fixed-width numbers in the BSATN format with a single copy.
+//!
+//! BSATN stores the elements of such an array as their little-endian bytes back to back,
+//! which on little-endian targets is exactly their in-memory representation.
+
+use std::mem;
+
+use bytemuck::Pod;
+
+use crate::buffer::{BufReader, BufWriter, DecodeError, ErrorKind};
+
+/// Writes the elements of `v` to `writer`, each in little-endian byte order.
+pub(crate) fn put_pod_slice<T: Pod>(writer: &mut impl BufWriter, v: &[T]) {
+    let bytes: &[u8] = bytemuck::cast_slice(v);
+    if cfg!(target_endian = "little") {
+        writer.put_slice(bytes);
+    } else {
+        let mut bytes = bytes.to_vec();
+        swap_elem_bytes(&mut bytes, mem::size_of::<T>());
+        writer.put_slice(&bytes);
+    }
+}
+
+/// Reads `len` elements, each in little-endian byte order, from `reader`.
+pub(crate) fn get_pod_vec<'de, T: Pod>(reader: &mut impl BufReader<'de>, len: usize) -> Result<Vec<T>, DecodeError> {
+    let size = len.checked_mul(mem::size_of::<T>()).ok_or(ErrorKind::Truncated {
+        needed: usize::MAX,
+        had: reader.remaining(),
+    })?;
+    decode_pod_slice(reader.get_slice(size)?)
+}
+
+/// Decodes `bytes`, the little-endian encodings of `T`s back to back, into a vector.
+///
+/// Errors if the length of `bytes` is not a multiple of the size of `T`.
+pub(crate) fn decode_pod_slice<T: Pod>(bytes: &[u8]) -> Result<Vec<T>, DecodeError> {
+    let width = mem::size_of::<T>();
+    if bytes.len() % width != 0 {
+        return Err(ErrorKind::WrongKind {
+            expected: format!("a multiple of {width} bytes"),
+            found: format!("{} bytes", bytes.len()),
+        }
+        .into());
+    }
+    let mut vec = vec![T::zeroed(); bytes.len() / width];
+    let out: &mut [u8] = bytemuck::cast_slice_mut(&mut vec);
+    out.copy_from_slice(bytes);
+    if cfg!(target_endian = "big") {
+        swap_elem_bytes(out, width);
+    }
+    Ok(vec)
+}
+
+/// Reverses the bytes within each `width`-sized chunk of `bytes`,
+/// converting `width`-byte numbers between little-endian and big-endian byte order.
+pub(crate) fn swap_elem_bytes(bytes: &mut [u8], width: usize) {
+    if width > 1 {
+        bytes.chunks_exact_mut(width).for_each(<[u8]>::reverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn;
+
+    /// Encodes `v` element by element, as BSATN does without the `bytemuck` feature.
+    fn slow_bytes<T>(v: &[T], put: impl Fn(&mut Vec<u8>, &T)) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.put_u32(v.len() as u32);
+        v.iter().for_each(|x| put(&mut bytes, x));
+        bytes
+    }
+
+    /// Asserts that the fast path encodes `v` like the slow path does and decodes it back.
+    fn check<T>(v: Vec<T>, put: impl Fn(&mut Vec<u8>, &T))
+    where
+        T: Pod + PartialEq + std::fmt::Debug + crate::ser::Serialize + for<'de> crate::de::Deserialize<'de>,
+    {
+        let slow = slow_bytes(&v, put);
+        let fast = bsatn::to_vec(&v).unwrap();
+        assert_eq!(fast, slow);
+        assert_eq!(bsatn::from_slice::<Vec<T>>(&fast).unwrap(), v);
+    }
+
+    #[test]
+    fn identical_to_slow_path() {
+        check(vec![0u16, 1, 0xbeef, u16::MAX], |b, x| b.put_u16(*x));
+        check(vec![0u32, 7, 0xdead_beef], |b, x| b.put_u32(*x));
+        check(vec![0u64, 42, u64::MAX - 1], |b, x| b.put_u64(*x));
+        check(vec![1u128 << 100, 3], |b, x| b.put_u128(*x));
+        check(vec![-1i8, 2, i8::MIN], |b, x| b.put_i8(*x));
+        check(vec![-1i16, i16::MAX], |b, x| b.put_i16(*x));
+        check(vec![-1i32, i32::MIN, 5], |b, x| b.put_i32(*x));
+        check(vec![-1i64, i64::MAX], |b, x| b.put_i64(*x));
+        check(vec![-1i128, i128::MIN], |b, x| b.put_i128(*x));
+        check(vec![1.5f32, -0.0, f32::INFINITY], |b, x| b.put_u32(x.to_bits()));
+        check(vec![1.5f64, f64::MIN_POSITIVE], |b, x| b.put_u64(x.to_bits()));
+        check(Vec::<u64>::new(), |b, x| b.put_u64(*x));
+    }
+
+    #[test]
+    fn float_wrappers_and_array_values() {
+        use crate::builtin_value::F64;
+        use crate::{AlgebraicType, AlgebraicValue, ArrayValue};
+
+        let floats: Vec<F64> = vec![1.5.into(), (-2.0).into()];
+        let bytes = bsatn::to_vec(&floats).unwrap();
+        assert_eq!(bytes, bsatn::to_vec(&[1.5f64, -2.0]).unwrap());
+        assert_eq!(bsatn::from_slice::<Vec<F64>>(&bytes).unwrap(), floats);
+
+        let ty = AlgebraicType::array(AlgebraicType::U32);
+        let value = AlgebraicValue::ArrayOf(vec![1u32, 2, 3]);
+        let bytes = bsatn::to_vec(&value).unwrap();
+        let decoded = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+        assert_eq!(decoded.as_array(), Some(&ArrayValue::U32(vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn big_endian_swap() {
+        let v = [0x0102u16, 0xa0b0, 0xffee];
+        let mut be: Vec<u8> = v.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let le: Vec<u8> = v.iter().flat_map(|x| x.to_le_bytes()).collect();
+        swap_elem_bytes(&mut be, 2);
+        assert_eq!(be, le);
+
+        let v = [0x0102_0304_0506_0708u64, 9];
+        let mut be: Vec<u8> = v.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let le: Vec<u8> = v.iter().flat_map(|x| x.to_le_bytes()).collect();
+        swap_elem_bytes(&mut be, 8);
+        assert_eq!(be, le);
+
+        // Single bytes are left alone.
+        let mut bytes = vec![1, 2, 3];
+        swap_elem_bytes(&mut bytes, 1);
+        assert_eq!(bytes, [1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_ragged_and_truncated_input() {
+        let err = decode_pod_slice::<u32>(&[0; 6]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::WrongKind { .. }));
+
+        let mut bytes = Vec::new();
+        bytes.put_u32(3);
+        bytes.put_u64(1);
+        let err = bsatn::from_slice::<Vec<u64>>(&bytes).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { needed: 24, had: 8 }));
+    }
+}
@@ -2,61 +2,106 @@ use std::fmt;
 
 use crate::buffer::BufWriter;
 
-use crate::ser::{self, Error, ForwardNamedToSeqProduct, Serialize, SerializeArray, SerializeMap, SerializeSeqProduct};
+use crate::ser::{
+    self, Error, ForwardNamedToSeqProduct, InvalidPath, Serialize, SerializeArray, SerializeMap, SerializeSeqProduct,
+};
 
 /// Defines the BSATN serialization data format.
 pub struct Serializer<'a, W> {
     writer: &'a mut W,
+    /// Whether the string arrays of `ArrayValue`s are dictionary encoded.
+    #[cfg(feature = "compress")]
+    dictionary_strings: bool,
+    /// Whether to give up on arrays and maps with elements to visit one by one,
+    /// for [sizing](super::size::static_encoded_len) only values whose length is cheap to compute.
+    static_only: bool,
 }
 
 impl<'a, W> Serializer<'a, W> {
     /// Returns a serializer using the given `writer`.
     pub fn new(writer: &'a mut W) -> Self {
-        Self { writer }
+        Self {
+            writer,
+            #[cfg(feature = "compress")]
+            dictionary_strings: false,
+            static_only: false,
+        }
+    }
+
+    /// Sets the serializer to give up on arrays and maps with elements to visit one by one.
+    pub(crate) fn static_only(self) -> Self {
+        Self {
+            static_only: true,
+            ..self
+        }
+    }
+
+    /// Returns an error if the serializer is [`static_only`](Self::static_only)
+    /// and so gives up on a container of `len` elements, `None` meaning unknown.
+    fn check_static(&self, len: Option<usize>) -> Result<(), BsatnError> {
+        if self.static_only && len != Some(0) {
+            return Err(BsatnError::custom("the value is not statically sized"));
+        }
+        Ok(())
+    }
+
+    /// Sets whether the string arrays of [`ArrayValue`](crate::ArrayValue)s are [dictionary encoded](super::dictionary_encode),
+    /// which must be matched by the [`Deserializer`](super::Deserializer) decoding them.
+    #[cfg(feature = "compress")]
+    pub fn with_dictionary_strings(self, dictionary_strings: bool) -> Self {
+        Self {
+            dictionary_strings,
+            ..self
+        }
     }
 
     /// Reborrows the serializer.
     #[inline]
     fn reborrow(&mut self) -> Serializer<'_, W> {
-        Serializer { writer: self.writer }
+        Serializer {
+            writer: self.writer,
+            #[cfg(feature = "compress")]
+            dictionary_strings: self.dictionary_strings,
+            static_only: self.static_only,
+        }
     }
 }
 
 /// An error during BSATN serialization.
-#[derive(Debug)]
-pub struct BsatnError {
-    /// The error message for the BSATN error.
-    custom: String,
+#[derive(thiserror::Error, Debug)]
+pub enum BsatnError {
+    /// A path can't be serialized as a string.
+    #[error(transparent)]
+    InvalidPath(#[from] InvalidPath),
+    /// Any other error, with its message.
+    #[error("{0}")]
+    Custom(String),
 }
 
-impl fmt::Display for BsatnError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.custom)
-    }
-}
-impl std::error::Error for BsatnError {}
-
 impl Error for BsatnError {
     fn custom<T: fmt::Display>(msg: T) -> Self {
-        let custom = msg.to_string();
-        Self { custom }
+        Self::Custom(msg.to_string())
+    }
+
+    fn invalid_path(err: InvalidPath) -> Self {
+        err.into()
     }
 }
 
 /// Writes `len` converted to a `u32` to `writer`.
 ///
 /// Errors if `len` would not fit in a `u32`.
-fn put_len(writer: &mut impl BufWriter, len: usize) -> Result<(), BsatnError> {
+pub(crate) fn put_len(writer: &mut impl BufWriter, len: usize) -> Result<(), BsatnError> {
     let len = len.try_into().map_err(|_| BsatnError::custom("len too long"))?;
     writer.put_u32(len);
     Ok(())
 }
 
-impl<W: BufWriter> ser::Serializer for Serializer<'_, W> {
+impl<'a, W: BufWriter> ser::Serializer for Serializer<'a, W> {
     type Ok = ();
     type Error = BsatnError;
-    type SerializeArray = Self;
-    type SerializeMap = Self;
+    type SerializeArray = ArraySerializer<'a, W>;
+    type SerializeMap = MapSerializer<'a, W>;
     type SerializeSeqProduct = Self;
     type SerializeNamedProduct = ForwardNamedToSeqProduct<Self>;
 
@@ -121,19 +166,47 @@ impl<W: BufWriter> ser::Serializer for Serializer<'_, W> {
         Ok(())
     }
     fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error> {
+        self.check_static(Some(len))?;
         put_len(self.writer, len)?; // N.B. `len > u32::MAX` isn't allowed.
-        Ok(self)
+        Ok(ArraySerializer {
+            ser: self,
+            len,
+            buffer: None,
+        })
+    }
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        self.check_static(None)?;
+        // The length prefix comes first, so buffer the elements until the length is known.
+        Ok(ArraySerializer {
+            ser: self,
+            len: 0,
+            buffer: Some(Vec::new()),
+        })
     }
     fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        self.check_static(Some(len))?;
         put_len(self.writer, len)?; // N.B. `len > u32::MAX` isn't allowed.
-        Ok(self)
+        Ok(MapSerializer {
+            ser: self,
+            len,
+            buffer: None,
+        })
+    }
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        self.check_static(None)?;
+        // As for arrays, buffer the entries until the length is known.
+        Ok(MapSerializer {
+            ser: self,
+            len: 0,
+            buffer: Some(Vec::new()),
+        })
     }
     fn serialize_seq_product(self, _len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
         Ok(self)
     }
     fn serialize_named_product(self, len: usize) -> Result<Self::SerializeNamedProduct, Self::Error> {
         // Serialize named like unnamed.
-        self.serialize_seq_product(len).map(ForwardNamedToSeqProduct::new)
+        ForwardNamedToSeqProduct::forward(self, len)
     }
     fn serialize_variant<T: super::Serialize + ?Sized>(
         self,
@@ -144,22 +217,106 @@ impl<W: BufWriter> ser::Serializer for Serializer<'_, W> {
         self.writer.put_u8(tag);
         value.serialize(self)
     }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        // The empty product is encoded as nothing at all.
+        Ok(())
+    }
+    fn serialize_unit_variant(self, tag: u8, _name: Option<&str>) -> Result<Self::Ok, Self::Error> {
+        self.writer.put_u8(tag);
+        Ok(())
+    }
+    fn hint_total_size(&mut self, size: usize) {
+        self.writer.reserve(size);
+    }
+
+    fn serialize_default_marker(self) -> Result<Self::Ok, Self::Error> {
+        // A single byte, which no value marked as present starts with.
+        self.writer.put_u8(crate::ser::elision::DEFAULT_TAG);
+        Ok(())
+    }
+
+    #[cfg(feature = "compress")]
+    fn __serialize_string_array_value(self, v: &[String]) -> Result<Self::Ok, Self::Error> {
+        if !self.dictionary_strings {
+            return v.serialize(self);
+        }
+        self.check_static(Some(v.len()))?;
+        super::dictionary_encode::put_string_array(self.writer, v, v.iter().map(String::as_str))
+    }
+
+    #[cfg(feature = "compress")]
+    fn __serialize_packed_strings(self, v: &crate::builtin_value::PackedStrings) -> Result<Self::Ok, Self::Error> {
+        if !self.dictionary_strings {
+            return v.serialize(self);
+        }
+        self.check_static(Some(v.len()))?;
+        super::dictionary_encode::put_string_array(self.writer, v, v)
+    }
+
+    #[cfg(feature = "bytemuck")]
+    fn __serialize_pod_array<T: Serialize + bytemuck::Pod>(self, v: &[T]) -> Result<Self::Ok, Self::Error> {
+        put_len(self.writer, v.len())?; // N.B. `v.len() > u32::MAX` isn't allowed.
+        super::pod::put_pod_slice(self.writer, v);
+        Ok(())
+    }
+}
+
+/// Serializes the elements of an array in the BSATN format.
+pub struct ArraySerializer<'a, W> {
+    /// The serializer of the elements.
+    ser: Serializer<'a, W>,
+    /// The number of elements in the array,
+    /// or, when its length was unknown, the number serialized so far.
+    len: usize,
+    /// When the length of the array was unknown, the elements serialized so far,
+    /// to be written after the length prefix once the array ends.
+    buffer: Option<Vec<u8>>,
 }
 
-impl<W: BufWriter> SerializeArray for Serializer<'_, W> {
+impl<W: BufWriter> SerializeArray for ArraySerializer<'_, W> {
     type Ok = ();
     type Error = BsatnError;
 
     fn serialize_element<T: super::Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
-        elem.serialize(self.reborrow())
+        let Some(buffer) = &mut self.buffer else {
+            return elem.serialize(self.ser.reborrow());
+        };
+        elem.serialize(Serializer {
+            writer: buffer,
+            #[cfg(feature = "compress")]
+            dictionary_strings: self.ser.dictionary_strings,
+            static_only: self.ser.static_only,
+        })?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn hint_fixed_element_size(&mut self, size: usize) {
+        self.ser.writer.reserve(self.len.saturating_mul(size));
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if let Some(buffer) = self.buffer {
+            put_len(self.ser.writer, self.len)?; // N.B. `len > u32::MAX` isn't allowed.
+            self.ser.writer.put_slice(&buffer);
+        }
         Ok(())
     }
 }
 
-impl<W: BufWriter> SerializeMap for Serializer<'_, W> {
+/// Serializes the entries of a map in the BSATN format.
+pub struct MapSerializer<'a, W> {
+    /// The serializer of the keys and values.
+    ser: Serializer<'a, W>,
+    /// The number of entries in the map,
+    /// or, when its length was unknown, the number serialized so far.
+    len: usize,
+    /// When the length of the map was unknown, the entries serialized so far,
+    /// to be written after the length prefix once the map ends.
+    buffer: Option<Vec<u8>>,
+}
+
+impl<W: BufWriter> SerializeMap for MapSerializer<'_, W> {
     type Ok = ();
     type Error = BsatnError;
 
@@ -168,11 +325,27 @@ impl<W: BufWriter> SerializeMap for Serializer<'_, W> {
         key: &K,
         value: &V,
     ) -> Result<(), Self::Error> {
-        key.serialize(self.reborrow())?;
-        value.serialize(self.reborrow())
+        let Some(buffer) = &mut self.buffer else {
+            key.serialize(self.ser.reborrow())?;
+            return value.serialize(self.ser.reborrow());
+        };
+        let mut ser = Serializer {
+            writer: buffer,
+            #[cfg(feature = "compress")]
+            dictionary_strings: self.ser.dictionary_strings,
+            static_only: self.ser.static_only,
+        };
+        key.serialize(ser.reborrow())?;
+        value.serialize(ser)?;
+        self.len += 1;
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if let Some(buffer) = self.buffer {
+            put_len(self.ser.writer, self.len)?; // N.B. `len > u32::MAX` isn't allowed.
+            self.ser.writer.put_slice(&buffer);
+        }
         Ok(())
     }
 }
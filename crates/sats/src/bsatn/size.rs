@@ -0,0 +1,43 @@
+//! Computing the length of the BSATN encoding of a value without writing it.
+
+use crate::buffer::BufWriter;
+use crate::ser::Serialize;
+
+use super::Serializer;
+
+/// Returns the number of bytes `value` occupies in the BSATN format,
+/// or `None` if its serialization failed.
+///
+/// The value is sized by running the BSATN [`Serializer`] itself over a [`SizeCounter`],
+/// so that every encoding it makes, e.g., of length prefixes, default markers, or arrays of numbers,
+/// is sized exactly as it is written.
+pub(crate) fn encoded_len<T: Serialize + ?Sized>(value: &T) -> Option<usize> {
+    let mut counter = SizeCounter::default();
+    value.serialize(Serializer::new(&mut counter)).ok()?;
+    Some(counter.len)
+}
+
+/// Returns the number of bytes `value` occupies in the BSATN format,
+/// or `None` if its serialization failed or `value` has arrays or maps with elements to visit one by one.
+///
+/// Strings, byte strings, and, with the `bytemuck` feature, arrays of numbers
+/// have a length known upfront, so the cost of this is bounded by the number of fields,
+/// rather than of elements, before the first such array or map.
+pub(crate) fn static_encoded_len<T: Serialize + ?Sized>(value: &T) -> Option<usize> {
+    let mut counter = SizeCounter::default();
+    value.serialize(Serializer::new(&mut counter).static_only()).ok()?;
+    Some(counter.len)
+}
+
+/// A [`BufWriter`] that counts the bytes written to it rather than keeping them.
+#[derive(Default)]
+pub(crate) struct SizeCounter {
+    /// The number of bytes written so far.
+    pub(crate) len: usize,
+}
+
+impl BufWriter for SizeCounter {
+    fn put_slice(&mut self, slice: &[u8]) {
+        self.len = self.len.saturating_add(slice.len());
+    }
+}
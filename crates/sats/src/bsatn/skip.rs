@@ -0,0 +1,115 @@
+//! Skipping over values in the BSATN format without decoding them.
+
+use crate::buffer::{BufReader, DecodeError, ErrorKind};
+use crate::{AlgebraicType, AlgebraicTypeRef, BuiltinType, WithTypespace};
+
+/// Advances the `reader` past one value of type `ty` without decoding it.
+///
+/// Strings are skipped without validating that they are UTF-8.
+pub(crate) fn skip_value<'de>(
+    reader: &mut impl BufReader<'de>,
+    ty: WithTypespace<'_, AlgebraicType>,
+) -> Result<(), DecodeError> {
+    if let Some(size) = fixed_size(ty) {
+        return reader.get_slice(size).map(drop);
+    }
+    match ty.ty() {
+        AlgebraicType::Ref(r) => skip_value(reader, ty.resolve(*r)),
+        AlgebraicType::Newtype(nt) => skip_value(reader, ty.with(&*nt.inner)),
+        AlgebraicType::Sum(sum) => {
+            let tag = reader.get_u8()?;
+            let variant = sum.variants.get(tag as usize).ok_or_else(|| ErrorKind::InvalidTag {
+                got: tag,
+                max: sum.variants.len().checked_sub(1).map(|max| max as u8),
+            })?;
+            skip_value(reader, ty.with(&variant.algebraic_type))
+        }
+        AlgebraicType::Product(prod) => prod
+            .elements
+            .iter()
+            .try_for_each(|elem| skip_value(reader, ty.with(&elem.algebraic_type))),
+        AlgebraicType::Builtin(BuiltinType::String) => {
+            let len = reader.get_u32()? as usize;
+            reader.get_slice(len).map(drop)
+        }
+        AlgebraicType::Builtin(BuiltinType::Array(arr)) => {
+            let elem_ty = ty.with(&*arr.elem_ty);
+            let len = reader.get_u32()? as usize;
+            skip_many(reader, len, &[elem_ty])
+        }
+        AlgebraicType::Builtin(BuiltinType::Map(map)) => {
+            let (key_ty, val_ty) = (ty.with(&*map.key_ty), ty.with(&*map.ty));
+            let len = reader.get_u32()? as usize;
+            skip_many(reader, len, &[key_ty, val_ty])
+        }
+        // All other builtins are of a fixed size and handled above.
+        AlgebraicType::Builtin(_) => unreachable!(),
+    }
+}
+
+/// Advances the `reader` past `len` repetitions of values of the types in `tys`.
+///
+/// When all of `tys` are of a fixed size, this is done in one step.
+pub(crate) fn skip_many<'de>(
+    reader: &mut impl BufReader<'de>,
+    len: usize,
+    tys: &[WithTypespace<'_, AlgebraicType>],
+) -> Result<(), DecodeError> {
+    let entry_size = tys.iter().try_fold(0usize, |acc, ty| acc.checked_add(fixed_size(*ty)?));
+    match entry_size {
+        Some(size) => {
+            let total = size.checked_mul(len).ok_or(ErrorKind::Truncated {
+                needed: usize::MAX,
+                had: reader.remaining(),
+            })?;
+            reader.get_slice(total).map(drop)
+        }
+        None => (0..len).try_for_each(|_| tys.iter().try_for_each(|ty| skip_value(reader, *ty))),
+    }
+}
+
+/// Returns the number of bytes every value of type `ty` occupies in the BSATN format,
+/// or `None` if the size of the encoding varies between values.
+pub(crate) fn fixed_size(ty: WithTypespace<'_, AlgebraicType>) -> Option<usize> {
+    fixed_size_in(ty, &mut Vec::new())
+}
+
+/// Computes [`fixed_size`] where `visiting` are the references currently being resolved.
+///
+/// A recursive type is never of a fixed size, so revisiting a reference yields `None`.
+fn fixed_size_in(ty: WithTypespace<'_, AlgebraicType>, visiting: &mut Vec<AlgebraicTypeRef>) -> Option<usize> {
+    match ty.ty() {
+        AlgebraicType::Ref(r) => {
+            if visiting.contains(r) {
+                return None;
+            }
+            visiting.push(*r);
+            let size = fixed_size_in(ty.resolve(*r), visiting);
+            visiting.pop();
+            size
+        }
+        AlgebraicType::Newtype(nt) => fixed_size_in(ty.with(&*nt.inner), visiting),
+        AlgebraicType::Sum(sum) => {
+            // The tag is followed by the payload, so the variants must all agree on their size.
+            let (first, rest) = sum.variants.split_first()?;
+            let size = fixed_size_in(ty.with(&first.algebraic_type), visiting)?;
+            for var in rest {
+                if fixed_size_in(ty.with(&var.algebraic_type), visiting)? != size {
+                    return None;
+                }
+            }
+            size.checked_add(1)
+        }
+        AlgebraicType::Product(prod) => prod.elements.iter().try_fold(0usize, |acc, elem| {
+            acc.checked_add(fixed_size_in(ty.with(&elem.algebraic_type), visiting)?)
+        }),
+        AlgebraicType::Builtin(b) => match b {
+            BuiltinType::Bool | BuiltinType::I8 | BuiltinType::U8 => Some(1),
+            BuiltinType::I16 | BuiltinType::U16 => Some(2),
+            BuiltinType::I32 | BuiltinType::U32 | BuiltinType::F32 => Some(4),
+            BuiltinType::I64 | BuiltinType::U64 | BuiltinType::F64 => Some(8),
+            BuiltinType::I128 | BuiltinType::U128 => Some(16),
+            BuiltinType::String | BuiltinType::Array(_) | BuiltinType::Map(_) => None,
+        },
+    }
+}
@@ -0,0 +1,277 @@
+//! Hex and Base64 text forms of BSATN encodings,
+//! for pasting encoded values into a CLI or reading them out of logs.
+//!
+//! When decoding, ASCII whitespace anywhere in the text is ignored,
+//! so wrapped or indented text can be used as is.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::buffer::{DecodeError, ErrorKind};
+use crate::de::{DeserializeOwned, DeserializeSeed};
+use crate::ser::Serialize;
+use crate::{AlgebraicType, WithTypespace};
+
+use super::ser::BsatnError;
+use super::{from_slice, to_vec, Deserializer};
+
+/// An error decoding a BSATN encoding from its hex or Base64 text form.
+#[derive(thiserror::Error, Debug)]
+pub enum TextDecodeError {
+    /// The text is not valid hex.
+    #[cfg(feature = "hex")]
+    #[error("invalid hex: {0}")]
+    Hex(#[from] hex::FromHexError),
+    /// The text is not valid Base64.
+    #[cfg(feature = "base64")]
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    /// The text is valid `encoding`, but the bytes it encodes aren't valid BSATN for the expected type.
+    #[error("valid {encoding}, but invalid BSATN: {error}")]
+    Bsatn { encoding: &'static str, error: DecodeError },
+}
+
+/// Returns `s` without any ASCII whitespace.
+fn strip_whitespace(s: &str) -> String {
+    s.chars().filter(|c| !c.is_ascii_whitespace()).collect()
+}
+
+/// Checks that `bytes` are exactly the BSATN encoding of one value of type `ty`, with nothing left over.
+fn validate(bytes: &[u8], ty: WithTypespace<'_, AlgebraicType>) -> Result<(), DecodeError> {
+    let mut reader = bytes;
+    ty.deserialize(Deserializer::new(&mut reader))?;
+    if !reader.is_empty() {
+        let err = ErrorKind::Custom(format!("{} bytes left over after the value", reader.len()));
+        return Err(DecodeError::from(err).with_excerpt(reader));
+    }
+    Ok(())
+}
+
+/// Decodes a `T` from `bytes`, the result of decoding text in `encoding`.
+fn decode<T: DeserializeOwned>(bytes: &[u8], encoding: &'static str) -> Result<T, TextDecodeError> {
+    from_slice(bytes).map_err(|error| TextDecodeError::Bsatn { encoding, error })
+}
+
+/// Encodes `value` in BSATN as a lowercase hex string.
+#[cfg(feature = "hex")]
+pub fn to_hex<T: Serialize + ?Sized>(value: &T) -> Result<String, BsatnError> {
+    to_vec(value).map(hex::encode)
+}
+
+/// Decodes the hex string `s` into the BSATN bytes it encodes.
+///
+/// When `ty` is provided, the bytes must also be exactly the encoding of one value of that type.
+#[cfg(feature = "hex")]
+pub fn from_hex(s: &str, ty: Option<WithTypespace<'_, AlgebraicType>>) -> Result<Vec<u8>, TextDecodeError> {
+    let bytes = hex::decode(strip_whitespace(s))?;
+    if let Some(ty) = ty {
+        validate(&bytes, ty).map_err(|error| TextDecodeError::Bsatn { encoding: "hex", error })?;
+    }
+    Ok(bytes)
+}
+
+/// Encodes `value` in BSATN as a padded Base64 string, using the standard alphabet.
+#[cfg(feature = "base64")]
+pub fn to_base64<T: Serialize + ?Sized>(value: &T) -> Result<String, BsatnError> {
+    to_vec(value).map(|bytes| crate::codec::base64::array_value_to_base64(&bytes))
+}
+
+/// Decodes the padded Base64 string `s` into the BSATN bytes it encodes.
+///
+/// When `ty` is provided, the bytes must also be exactly the encoding of one value of that type.
+#[cfg(feature = "base64")]
+pub fn from_base64(s: &str, ty: Option<WithTypespace<'_, AlgebraicType>>) -> Result<Vec<u8>, TextDecodeError> {
+    use base64::Engine as _;
+
+    let bytes = base64::engine::general_purpose::STANDARD.decode(strip_whitespace(s))?;
+    if let Some(ty) = ty {
+        validate(&bytes, ty).map_err(|error| TextDecodeError::Bsatn {
+            encoding: "base64",
+            error,
+        })?;
+    }
+    Ok(bytes)
+}
+
+/// Defines a wrapper of a `T` that is displayed and parsed as the BSATN encoding of `T` in the text form `$encoding`,
+/// which is also the name of the feature enabling it.
+macro_rules! text_wrapper {
+    ($(#[$attr:meta])* $name:ident, $encoding:literal, $to_text:ident, $from_text:ident) => {
+        $(#[$attr])*
+        #[cfg(feature = $encoding)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name<T>(pub T);
+
+        #[cfg(feature = $encoding)]
+        impl<T: Serialize> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&$to_text(&self.0).map_err(|_| fmt::Error)?)
+            }
+        }
+
+        #[cfg(feature = $encoding)]
+        impl<T: DeserializeOwned> FromStr for $name<T> {
+            type Err = TextDecodeError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                decode(&$from_text(s, None)?, $encoding).map(Self)
+            }
+        }
+
+        #[cfg(all(feature = $encoding, feature = "serde"))]
+        impl<T: Serialize> ::serde::Serialize for $name<T> {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let text = $to_text(&self.0).map_err(::serde::ser::Error::custom)?;
+                serializer.serialize_str(&text)
+            }
+        }
+
+        #[cfg(all(feature = $encoding, feature = "serde"))]
+        impl<'de, T: DeserializeOwned> ::serde::Deserialize<'de> for $name<T> {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let text = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+                text.parse().map_err(::serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+text_wrapper!(
+    /// A `T` that is displayed and parsed as its BSATN encoding in hex,
+    /// e.g., for use as a CLI argument or a field of a config file.
+    HexBsatn,
+    "hex",
+    to_hex,
+    from_hex
+);
+
+text_wrapper!(
+    /// A `T` that is displayed and parsed as its BSATN encoding in Base64,
+    /// e.g., for use as a CLI argument or a field of a config file.
+    Base64Bsatn,
+    "base64",
+    to_base64,
+    from_base64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProductTypeElement, Typespace};
+
+    #[derive(crate::ser::Serialize, crate::de::Deserialize, Debug, PartialEq)]
+    #[sats(crate = crate)]
+    struct Row {
+        id: u32,
+        name: String,
+        flag: bool,
+    }
+
+    fn row() -> Row {
+        Row {
+            id: 7,
+            name: "it's".into(),
+            flag: true,
+        }
+    }
+
+    fn row_ty() -> AlgebraicType {
+        AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::Bool, "flag"),
+        ])
+    }
+
+    /// Returns `text` split over indented lines of at most 8 characters each, as if copied from a log.
+    fn wrap(text: &str) -> String {
+        let lines = text.as_bytes().chunks(8).map(|line| std::str::from_utf8(line).unwrap());
+        lines.map(|line| format!("  {line}\r\n")).collect()
+    }
+
+    #[cfg(feature = "hex")]
+    #[test]
+    fn hex() {
+        let ts = Typespace::new(vec![]);
+        let ty = row_ty();
+        let text = to_hex(&row()).unwrap();
+        assert_eq!(text, "07000000_04000000_69742773_01".replace('_', ""));
+        assert_eq!(
+            from_hex(&text, Some(WithTypespace::new(&ts, &ty))).unwrap(),
+            to_vec(&row()).unwrap()
+        );
+        assert_eq!(
+            from_hex(&wrap(&text.to_uppercase()), None).unwrap(),
+            to_vec(&row()).unwrap()
+        );
+
+        let parsed = wrap(&text).parse::<HexBsatn<Row>>().unwrap();
+        assert_eq!(parsed.0, row());
+        assert_eq!(parsed.to_string(), text);
+
+        assert!(matches!(from_hex("0g", None), Err(TextDecodeError::Hex(_))));
+        assert!(matches!("abc".parse::<HexBsatn<u8>>(), Err(TextDecodeError::Hex(_))));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn base64() {
+        let ts = Typespace::new(vec![]);
+        let ty = row_ty();
+        let text = to_base64(&row()).unwrap();
+        let bytes = from_base64(&wrap(&text), Some(WithTypespace::new(&ts, &ty))).unwrap();
+        assert_eq!(bytes, to_vec(&row()).unwrap());
+
+        let parsed = wrap(&text).parse::<Base64Bsatn<Row>>().unwrap();
+        assert_eq!(parsed.0, row());
+        assert_eq!(parsed.to_string(), text);
+
+        let err = "not base64!".parse::<Base64Bsatn<u8>>().unwrap_err();
+        assert!(matches!(err, TextDecodeError::Base64(_)), "{err}");
+        assert!(err.to_string().starts_with("invalid base64: "));
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn invalid_bsatn() {
+        let ts = Typespace::new(vec![]);
+        let ty = row_ty();
+        let bytes = to_vec(&row()).unwrap();
+
+        // Valid Base64 of a truncated payload.
+        let truncated = crate::codec::base64::array_value_to_base64(&bytes[..bytes.len() - 2]);
+        let err = from_base64(&truncated, Some(WithTypespace::new(&ts, &ty))).unwrap_err();
+        let TextDecodeError::Bsatn { encoding, error } = &err else {
+            panic!("unexpected error {err}")
+        };
+        assert_eq!(*encoding, "base64");
+        assert!(matches!(error.kind(), ErrorKind::Truncated { .. }), "{err}");
+        assert!(err
+            .to_string()
+            .starts_with("valid base64, but invalid BSATN: data too short"));
+        let err = truncated.parse::<Base64Bsatn<Row>>().unwrap_err();
+        assert!(matches!(err, TextDecodeError::Bsatn { .. }), "{err}");
+        // Without a type, the bytes aren't checked.
+        assert_eq!(from_base64(&truncated, None).unwrap(), bytes[..bytes.len() - 2]);
+
+        // Bytes left over after the value.
+        let mut extra = bytes.clone();
+        extra.push(0);
+        let extra = crate::codec::base64::array_value_to_base64(&extra);
+        let err = from_base64(&extra, Some(WithTypespace::new(&ts, &ty))).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "valid base64, but invalid BSATN: 1 bytes left over after the value (bytes: 00)"
+        );
+    }
+
+    #[cfg(all(feature = "hex", feature = "serde"))]
+    #[test]
+    fn serde_fields() {
+        let json = serde_json::to_string(&HexBsatn(5u16)).unwrap();
+        assert_eq!(json, r#""0500""#);
+        let HexBsatn(v) = serde_json::from_str::<HexBsatn<u16>>(&json).unwrap();
+        assert_eq!(v, 5);
+        assert!(serde_json::from_str::<HexBsatn<u16>>(r#""05""#).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+//! A variant of the BSATN format encoding integers as LEB128 varints,
+//! spending fewer bytes on small values than their fixed-width encoding.
+//!
+//! Unsigned integers are encoded as LEB128, seven bits per byte starting with the least significant,
+//! where the high bit of each byte is set if more bytes follow.
+//! Signed integers are first ZigZag encoded, mapping `0, -1, 1, -2, ...` to `0, 1, 2, 3, ...`,
+//! so that small negative values are small as well.
+//!
+//! Everything else, including the lengths of strings, arrays, and maps, and the tags of sums,
+//! is encoded as in BSATN.
+//! So are `u8`s and `i8`s, as LEB128 can only make them longer.
+//! The encoding is opted into per call, e.g., through [`to_vec_varint`].
+
+use super::ser::{put_len, BsatnError};
+use super::Serializer;
+use crate::buffer::BufWriter;
+use crate::ser::{self, Error, ForwardNamedToSeqProduct, Serialize, SerializeArray, SerializeMap, SerializeSeqProduct};
+
+/// Serialize `value` into the buffered writer `w` in the BSATN format with varint integers.
+pub fn to_writer_varint<W: BufWriter, T: Serialize + ?Sized>(w: &mut W, value: &T) -> Result<(), BsatnError> {
+    value.serialize(VarintBsatnSerializer::new(w))
+}
+
+/// Serialize `value` into a `Vec<u8>` in the BSATN format with varint integers.
+pub fn to_vec_varint<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, BsatnError> {
+    let mut v = Vec::new();
+    to_writer_varint(&mut v, value)?;
+    Ok(v)
+}
+
+/// Appends the LEB128 encoding of `val` to `buf`.
+pub fn leb128_encode_u64(val: u64, buf: &mut Vec<u8>) {
+    put_uleb128(buf, val.into());
+}
+
+/// Appends the LEB128 encoding of `val`, ZigZag encoded, to `buf`.
+pub fn leb128_encode_i64(val: i64, buf: &mut Vec<u8>) {
+    put_uleb128(buf, zigzag(val.into()));
+}
+
+/// Decodes a LEB128 encoded `u64` from the start of `bytes`,
+/// returning the value and the number of bytes it took up.
+///
+/// Errors if `bytes` ends before the encoding does
+/// or if the encoding doesn't fit in a `u64`.
+pub fn leb128_decode_u64(bytes: &[u8]) -> Result<(u64, usize), BsatnError> {
+    let mut val = 0u64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let shift = 7 * i as u32;
+        let low = u64::from(byte & 0x7f);
+        // The tenth byte may only hold the single bit left of a `u64`.
+        if shift >= u64::BITS || (shift > 0 && low >> (u64::BITS - shift) != 0) {
+            return Err(BsatnError::custom("LEB128 encoding overflows u64"));
+        }
+        val |= low << shift;
+        if byte & 0x80 == 0 {
+            return Ok((val, i + 1));
+        }
+    }
+    Err(BsatnError::custom("LEB128 encoding is truncated"))
+}
+
+/// Decodes a LEB128 encoded, ZigZag encoded `i64` from the start of `bytes`,
+/// returning the value and the number of bytes it took up.
+pub fn leb128_decode_i64(bytes: &[u8]) -> Result<(i64, usize), BsatnError> {
+    let (val, len) = leb128_decode_u64(bytes)?;
+    Ok(((val >> 1) as i64 ^ -((val & 1) as i64), len))
+}
+
+/// Writes the LEB128 encoding of `val` to `writer`.
+fn put_uleb128(writer: &mut impl BufWriter, mut val: u128) {
+    while val >= 0x80 {
+        writer.put_u8(val as u8 | 0x80);
+        val >>= 7;
+    }
+    writer.put_u8(val as u8);
+}
+
+/// Returns the ZigZag encoding of `val`.
+fn zigzag(val: i128) -> u128 {
+    ((val << 1) ^ (val >> 127)) as u128
+}
+
+/// Defines the BSATN serialization data format with integers encoded as LEB128 varints.
+pub struct VarintBsatnSerializer<'a, W> {
+    writer: &'a mut W,
+}
+
+impl<'a, W> VarintBsatnSerializer<'a, W> {
+    /// Returns a serializer using the given `writer`.
+    pub fn new(writer: &'a mut W) -> Self {
+        Self { writer }
+    }
+
+    /// Reborrows the serializer.
+    #[inline]
+    fn reborrow(&mut self) -> VarintBsatnSerializer<'_, W> {
+        VarintBsatnSerializer { writer: self.writer }
+    }
+
+    /// Returns the plain BSATN serializer for what isn't encoded differently.
+    fn plain(self) -> Serializer<'a, W> {
+        Serializer::new(self.writer)
+    }
+}
+
+impl<W: BufWriter> ser::Serializer for VarintBsatnSerializer<'_, W> {
+    type Ok = ();
+    type Error = BsatnError;
+    type SerializeArray = Self;
+    type SerializeMap = Self;
+    type SerializeSeqProduct = Self;
+    type SerializeNamedProduct = ForwardNamedToSeqProduct<Self>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_bool(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_u8(v)
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, v.into());
+        Ok(())
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, v.into());
+        Ok(())
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, v.into());
+        Ok(())
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, v);
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_i8(v)
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, zigzag(v.into()));
+        Ok(())
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, zigzag(v.into()));
+        Ok(())
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, zigzag(v.into()));
+        Ok(())
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        put_uleb128(self.writer, zigzag(v));
+        Ok(())
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_f32(v)
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_f64(v)
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_str(v)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.plain().serialize_bytes(v)
+    }
+    fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error> {
+        put_len(self.writer, len)?; // N.B. `len > u32::MAX` isn't allowed.
+        Ok(self)
+    }
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        put_len(self.writer, len)?; // N.B. `len > u32::MAX` isn't allowed.
+        Ok(self)
+    }
+    fn serialize_seq_product(self, _len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
+        Ok(self)
+    }
+    fn serialize_named_product(self, len: usize) -> Result<Self::SerializeNamedProduct, Self::Error> {
+        // Serialize named like unnamed.
+        ForwardNamedToSeqProduct::forward(self, len)
+    }
+    fn serialize_variant<T: Serialize + ?Sized>(
+        self,
+        tag: u8,
+        _name: Option<&str>,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.writer.put_u8(tag);
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+    fn serialize_unit_variant(self, tag: u8, _name: Option<&str>) -> Result<Self::Ok, Self::Error> {
+        self.writer.put_u8(tag);
+        Ok(())
+    }
+}
+
+impl<W: BufWriter> SerializeArray for VarintBsatnSerializer<'_, W> {
+    type Ok = ();
+    type Error = BsatnError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
+        elem.serialize(self.reborrow())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+impl<W: BufWriter> SerializeMap for VarintBsatnSerializer<'_, W> {
+    type Ok = ();
+    type Error = BsatnError;
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        key.serialize(self.reborrow())?;
+        value.serialize(self.reborrow())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+impl<W: BufWriter> SerializeSeqProduct for VarintBsatnSerializer<'_, W> {
+    type Ok = ();
+    type Error = BsatnError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
+        elem.serialize(self.reborrow())
+    }
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bsatn, product, AlgebraicValue};
+
+    fn encode_u64(val: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        leb128_encode_u64(val, &mut buf);
+        buf
+    }
+
+    fn encode_i64(val: i64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        leb128_encode_i64(val, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn length_boundaries() {
+        // The largest value of each length and the smallest value of the next.
+        let cases = [
+            (0, 1),
+            ((1 << 7) - 1, 1),
+            (1 << 7, 2),
+            ((1 << 14) - 1, 2),
+            (1 << 14, 3),
+            ((1 << 28) - 1, 4),
+            (1 << 28, 5),
+            (u32::MAX.into(), 5),
+            ((1 << 35) - 1, 5),
+            (1 << 35, 6),
+            ((1 << 63) - 1, 9),
+            (1 << 63, 10),
+            (u64::MAX, 10),
+        ];
+        for (val, len) in cases {
+            let buf = encode_u64(val);
+            assert_eq!(buf.len(), len, "{val:#x}");
+            assert_eq!(leb128_decode_u64(&buf).unwrap(), (val, len), "{val:#x}");
+        }
+        assert_eq!(encode_u64(127), [0x7f]);
+        assert_eq!(encode_u64(128), [0x80, 0x01]);
+        assert_eq!(
+            encode_u64(u64::MAX),
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        // Trailing bytes are left alone.
+        assert_eq!(leb128_decode_u64(&[0x80, 0x01, 0xff]).unwrap(), (128, 2));
+
+        for truncated in [&[][..], &[0x80], &[0xff, 0xff]] {
+            let err = leb128_decode_u64(truncated).unwrap_err();
+            assert_eq!(err.to_string(), "LEB128 encoding is truncated");
+        }
+        // `u64::MAX + 1` and an eleven byte encoding.
+        let too_big = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02];
+        let too_long = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+        for overflow in [&too_big[..], &too_long[..]] {
+            let err = leb128_decode_u64(overflow).unwrap_err();
+            assert_eq!(err.to_string(), "LEB128 encoding overflows u64");
+        }
+    }
+
+    #[test]
+    fn zigzag_is_shorter_for_negatives() {
+        for val in [0, -1, 1, -64, 63, -65, 64, i64::MIN, i64::MAX, -1_000_000] {
+            let buf = encode_i64(val);
+            assert_eq!(leb128_decode_i64(&buf).unwrap(), (val, buf.len()), "{val}");
+        }
+        assert_eq!(encode_i64(-1), [0x01]);
+        assert_eq!(encode_i64(1), [0x02]);
+        assert_eq!(encode_i64(i64::MIN).len(), 10);
+
+        // Two's complement sets the high bits of negative numbers, taking all ten bytes.
+        for val in [-1, -64, -1_000_000, i64::MIN / 2] {
+            let twos_complement = encode_u64(val as u64);
+            assert_eq!(twos_complement.len(), 10);
+            assert!(encode_i64(val).len() < twos_complement.len(), "{val}");
+        }
+    }
+
+    #[test]
+    fn serializer_shrinks_integers() {
+        let row = product![
+            1u32,
+            -2i64,
+            300u16,
+            7u8,
+            "seven",
+            AlgebraicValue::ArrayOf(vec![1u64, 2, 3])
+        ];
+        let varint = to_vec_varint(&row).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(varint, [
+            0x01,
+            0x03,
+            0xac, 0x02,
+            0x07,
+            5, 0, 0, 0, b's', b'e', b'v', b'e', b'n',
+            3, 0, 0, 0, 0x01, 0x02, 0x03,
+        ]);
+        assert!(varint.len() < bsatn::to_vec(&row).unwrap().len());
+    }
+}
@@ -0,0 +1,178 @@
+//! A pool of output buffers to reuse across BSATN encodings,
+//! sparing a fresh allocation for every value encoded.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use super::ser::BsatnError;
+use super::to_writer;
+use crate::ser::Serialize;
+
+/// The number of idle buffers a [`BsatnBufferPool`] retains by default.
+pub const DEFAULT_MAX_IDLE: usize = 64;
+
+/// A pool of `Vec<u8>` buffers to encode BSATN into.
+///
+/// Buffers are handed out by [`BsatnBufferPool::acquire`]
+/// and return to the pool, with their capacity intact, when dropped.
+/// The pool retains at most [`max_idle`](Self::with_max_idle) buffers, [`DEFAULT_MAX_IDLE`] by default,
+/// and frees those returned beyond that, so a burst of acquisitions doesn't keep its buffers around for good.
+/// The pool can be shared between threads.
+pub struct BsatnBufferPool {
+    /// The buffers not currently acquired, all of them empty.
+    free: Mutex<Vec<Vec<u8>>>,
+    /// The most buffers to keep in `free`.
+    max_idle: usize,
+}
+
+impl Default for BsatnBufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BsatnBufferPool {
+    /// Returns a new pool without any buffers, retaining at most [`DEFAULT_MAX_IDLE`] of them.
+    pub const fn new() -> Self {
+        Self {
+            free: Mutex::new(Vec::new()),
+            max_idle: DEFAULT_MAX_IDLE,
+        }
+    }
+
+    /// Returns this pool retaining at most `max_idle` buffers that aren't acquired.
+    ///
+    /// With a `max_idle` of `0`, buffers are never reused.
+    pub fn with_max_idle(self, max_idle: usize) -> Self {
+        Self { max_idle, ..self }
+    }
+
+    /// Returns an empty buffer from the pool, or a freshly allocated one if there are none.
+    pub fn acquire(&self) -> PooledBuffer<'_> {
+        let buf = self.free_list().pop().unwrap_or_default();
+        PooledBuffer { buf, pool: self }
+    }
+
+    /// Serialize `value` in the BSATN format into a buffer acquired from the pool.
+    pub fn to_buffer<T: Serialize + ?Sized>(&self, value: &T) -> Result<PooledBuffer<'_>, BsatnError> {
+        let mut buf = self.acquire();
+        to_writer(&mut *buf, value)?;
+        Ok(buf)
+    }
+
+    /// Returns the number of buffers in the pool that are not currently acquired.
+    pub fn idle(&self) -> usize {
+        self.free_list().len()
+    }
+
+    /// Locks the list of free buffers.
+    fn free_list(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+        // The list is valid even if another thread panicked while holding the lock.
+        self.free.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A buffer acquired from a [`BsatnBufferPool`], returned to the pool on drop.
+pub struct PooledBuffer<'a> {
+    /// The buffer itself.
+    buf: Vec<u8>,
+    /// The pool to return `buf` to.
+    pool: &'a BsatnBufferPool,
+}
+
+impl PooledBuffer<'_> {
+    /// Takes the buffer out of the pool for good, e.g., to send it elsewhere.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        let mut buf = std::mem::take(&mut self.buf);
+        // Don't pool the empty vector left behind by `into_inner`.
+        if buf.capacity() != 0 {
+            buf.clear();
+            let mut free = self.pool.free_list();
+            // Past the limit, `buf` is freed instead.
+            if free.len() < self.pool.max_idle {
+                free.push(buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bsatn, product};
+
+    #[test]
+    fn reuses_buffers() {
+        let pool = BsatnBufferPool::new();
+        let buf = pool
+            .to_buffer(&product![1u64, "a string that needs some room".to_owned()])
+            .unwrap();
+        let (ptr, capacity) = (buf.as_ptr(), buf.capacity());
+        assert!(capacity > 0);
+        assert_eq!(pool.idle(), 0);
+        drop(buf);
+        assert_eq!(pool.idle(), 1);
+
+        let buf = pool.acquire();
+        assert!(buf.is_empty());
+        assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, capacity));
+        assert_eq!(pool.idle(), 0);
+
+        // Taking the buffer out doesn't return it to the pool.
+        assert_eq!(buf.into_inner().capacity(), capacity);
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[test]
+    fn retains_at_most_max_idle() {
+        let pool = BsatnBufferPool::new().with_max_idle(2);
+        let bufs = (0..5u32).map(|i| pool.to_buffer(&i).unwrap()).collect::<Vec<_>>();
+        assert_eq!(pool.idle(), 0);
+        drop(bufs);
+        assert_eq!(pool.idle(), 2);
+
+        let pool = BsatnBufferPool::default().with_max_idle(0);
+        drop(pool.to_buffer(&1u32).unwrap());
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(BsatnBufferPool::default().max_idle, DEFAULT_MAX_IDLE);
+    }
+
+    #[test]
+    fn concurrent_use() {
+        let pool = BsatnBufferPool::new();
+        std::thread::scope(|s| {
+            for thread in 0..4u32 {
+                let pool = &pool;
+                s.spawn(move || {
+                    for i in 0..1000u32 {
+                        let row = product![thread, i];
+                        let buf = pool.to_buffer(&row).unwrap();
+                        assert_eq!(*buf, bsatn::to_vec(&row).unwrap());
+                    }
+                });
+            }
+        });
+        let idle = pool.idle();
+        assert!((1..=4).contains(&idle), "{idle} idle buffers");
+    }
+}
@@ -0,0 +1,180 @@
+//! Zero-copy access to arrays of fixed-width numbers in BSATN buffers.
+//!
+//! BSATN stores the elements of such an array as their little-endian bytes back to back.
+//! On little-endian targets, that is their in-memory representation,
+//! so when the bytes also happen to be aligned for the element type,
+//! they can be read as a slice of elements in place, without copying them.
+//! Otherwise, the bytes are copied into a vector in one go,
+//! and, on big-endian targets, the bytes of each element are then swapped.
+//!
+//! [`Deserializer::deserialize_slice`](super::Deserializer::deserialize_slice) decodes such arrays this way.
+//! Decoding into a `Vec` always copies, as with the `bytemuck` feature, which this feature enables.
+
+use std::borrow::Cow;
+use std::{iter, mem};
+
+use ::zerocopy::{FromBytes, Immutable, IntoBytes};
+
+use crate::buffer::{DecodeError, ErrorKind};
+
+/// Returns `bytes` as a slice of `u32`s in place,
+/// or `None` if they can't be read as one without a copy.
+///
+/// See [`try_cast_slice`] for when that is.
+pub fn try_cast_to_u32_slice(bytes: &[u8]) -> Option<&[u32]> {
+    try_cast_slice(bytes)
+}
+
+/// Returns `bytes`, the little-endian encodings of `T`s back to back, as a slice of `T`s in place,
+/// or `None` if they can't be read as one without a copy.
+///
+/// That is the case when the target is big-endian, when `bytes` is not aligned for `T`,
+/// or when the length of `bytes` is not a multiple of the size of `T`.
+pub fn try_cast_slice<T: FromBytes + Immutable>(bytes: &[u8]) -> Option<&[T]> {
+    if cfg!(target_endian = "big") {
+        return None;
+    }
+    <[T]>::ref_from_bytes(bytes).ok()
+}
+
+/// Decodes `bytes`, the little-endian encodings of `T`s back to back, into a slice of `T`s,
+/// borrowing from `bytes` when [`try_cast_slice`] can and copying them otherwise.
+///
+/// Errors if the length of `bytes` is not a multiple of the size of `T`.
+pub fn decode_slice<T: FromBytes + IntoBytes + Immutable + Clone>(bytes: &[u8]) -> Result<Cow<'_, [T]>, DecodeError> {
+    let width = mem::size_of::<T>();
+    if bytes.len() % width != 0 {
+        return Err(ErrorKind::WrongKind {
+            expected: format!("a multiple of {width} bytes"),
+            found: format!("{} bytes", bytes.len()),
+        }
+        .into());
+    }
+    if let Some(slice) = try_cast_slice(bytes) {
+        return Ok(Cow::Borrowed(slice));
+    }
+    let mut vec = iter::repeat_with(T::new_zeroed)
+        .take(bytes.len() / width)
+        .collect::<Vec<_>>();
+    let out = vec.as_mut_slice().as_mut_bytes();
+    out.copy_from_slice(bytes);
+    if cfg!(target_endian = "big") && width > 1 {
+        out.chunks_exact_mut(width).for_each(<[u8]>::reverse);
+    }
+    Ok(Cow::Owned(vec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn;
+    use crate::de::DeserializeSeed;
+    use crate::{AlgebraicType, AlgebraicValue, ArrayValue, Typespace, WithTypespace};
+
+    /// Returns the little-endian bytes of `v` back to back, as BSATN stores them,
+    /// in a buffer aligned for `u64`s, starting `offset` bytes in.
+    fn le_bytes(v: &[u32], offset: usize) -> (Vec<u64>, std::ops::Range<usize>) {
+        let bytes = v.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+        let mut buf = vec![0u64; (offset + bytes.len()) / 8 + 1];
+        let range = offset..offset + bytes.len();
+        buf.as_mut_slice().as_mut_bytes()[range.clone()].copy_from_slice(&bytes);
+        (buf, range)
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn aligned_bytes_are_borrowed() {
+        let v = [1, 0x0102_0304, u32::MAX];
+        let (buf, range) = le_bytes(&v, 0);
+        let bytes = &buf.as_bytes()[range];
+        let slice = try_cast_to_u32_slice(bytes).unwrap();
+        assert_eq!(slice, v);
+        assert_eq!(slice.as_ptr().cast::<u8>(), bytes.as_ptr());
+        assert!(matches!(decode_slice::<u32>(bytes).unwrap(), Cow::Borrowed(s) if s == v));
+    }
+
+    #[test]
+    #[cfg(target_endian = "big")]
+    fn bytes_are_never_borrowed() {
+        let v = [1, 0x0102_0304, u32::MAX];
+        let (buf, range) = le_bytes(&v, 0);
+        let bytes = &buf.as_bytes()[range];
+        assert_eq!(try_cast_to_u32_slice(bytes), None);
+        assert!(matches!(decode_slice::<u32>(bytes).unwrap(), Cow::Owned(s) if s == v));
+    }
+
+    #[test]
+    fn unaligned_bytes_are_copied() {
+        let v = [7, 0xdead_beef];
+        let (buf, range) = le_bytes(&v, 1);
+        let bytes = &buf.as_bytes()[range];
+        assert_eq!(try_cast_to_u32_slice(bytes), None);
+        assert!(matches!(decode_slice::<u32>(bytes).unwrap(), Cow::Owned(s) if s == v));
+
+        // A partial element is an error, and can't be cast either.
+        assert_eq!(try_cast_to_u32_slice(&[0; 6]), None);
+        assert!(decode_slice::<u32>(&[0; 6]).is_err());
+        assert!(decode_slice::<u32>(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn deserializer_borrows_aligned_arrays() {
+        let v = (0..1000u32).map(|i| i.wrapping_mul(0x9e37_79b9)).collect::<Vec<_>>();
+        let encoded = bsatn::to_vec(&v).unwrap();
+        // The encoding, `offset` bytes into a buffer aligned for `u64`s.
+        let at = |offset: usize| {
+            let mut buf = vec![0u64; (offset + encoded.len()) / 8 + 1];
+            buf.as_mut_slice().as_mut_bytes()[offset..offset + encoded.len()].copy_from_slice(&encoded);
+            buf
+        };
+
+        // After the 4-byte length, the elements of an aligned encoding are aligned for `u32`s.
+        let buf = at(0);
+        let bytes = &buf.as_bytes()[..encoded.len()];
+        let slice = bsatn::Deserializer::new(&mut &*bytes)
+            .deserialize_slice::<u32>()
+            .unwrap();
+        #[cfg(target_endian = "little")]
+        assert!(matches!(&slice, Cow::Borrowed(s) if s.as_ptr().cast::<u8>() == bytes[4..].as_ptr()));
+        assert_eq!(slice, v);
+
+        // Otherwise, they are copied.
+        let buf = at(1);
+        let bytes = &buf.as_bytes()[1..1 + encoded.len()];
+        let slice = bsatn::Deserializer::new(&mut &*bytes)
+            .deserialize_slice::<u32>()
+            .unwrap();
+        assert!(matches!(&slice, Cow::Owned(s) if *s == v));
+
+        // A truncated array errors rather than reading past the buffer.
+        let err = bsatn::Deserializer::new(&mut &encoded[..encoded.len() - 1])
+            .deserialize_slice::<u32>()
+            .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { .. }), "{err}");
+    }
+
+    #[test]
+    fn arrays_decode_with_the_feature() {
+        let v = (0..1000u32).map(|i| i.wrapping_mul(0x9e37_79b9)).collect::<Vec<_>>();
+        let bytes = bsatn::to_vec(&v).unwrap();
+        assert_eq!(bsatn::from_slice::<Vec<u32>>(&bytes).unwrap(), v);
+        // Each array starts after its 4-byte length, so its elements are usually unaligned for `u64`s.
+        let wide = v.iter().map(|&x| u64::from(x) << 20).collect::<Vec<_>>();
+        assert_eq!(
+            bsatn::from_slice::<Vec<u64>>(&bsatn::to_vec(&wide).unwrap()).unwrap(),
+            wide
+        );
+
+        // Arrays of values decode through it too.
+        let ty = AlgebraicType::array(AlgebraicType::U32);
+        let ts = Typespace::default();
+        let value = WithTypespace::new(&ts, &ty)
+            .deserialize(bsatn::Deserializer::new(&mut &bytes[..]))
+            .unwrap();
+        assert_eq!(value, AlgebraicValue::Array(ArrayValue::U32(v)));
+
+        // A truncated array errors rather than reading past the buffer.
+        let err = bsatn::from_slice::<Vec<u32>>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Truncated { .. }), "{err}");
+    }
+}
@@ -4,29 +4,177 @@
 
 use std::cell::Cell;
 use std::fmt;
+use std::io;
 use std::str::Utf8Error;
+use std::sync::Arc;
+
+use crate::ser::InvalidPath;
 
 /// An error that occurred when decoding.
+///
+/// Besides its [`ErrorKind`], the error records where in the value being decoded it occurred,
+/// as a [path](DecodeError::path) of fields and elements,
+/// and, where available, an [excerpt](DecodeError::excerpt) of the offending bytes.
+#[derive(Debug, Clone)]
+pub struct DecodeError(Box<ErrorRepr>);
+
+/// The contents of a [`DecodeError`], boxed to keep `Result<T, DecodeError>` small.
 #[derive(Debug, Clone)]
-pub enum DecodeError {
-    /// Not enough data was provided in the input.
-    BufferLength,
-    /// The tag does not exist for the sum.
-    InvalidTag,
-    /// Expected data to be UTF-8 but it wasn't.
-    InvalidUtf8,
-    /// Custom error not in the other variants of `DecodeError`.
-    Other(String),
+struct ErrorRepr {
+    /// What went wrong.
+    kind: ErrorKind,
+    /// The path to the part of the value where the error occurred.
+    /// The innermost segment comes first, as segments are added while the error propagates outwards.
+    path: Vec<PathSegment>,
+    /// At most [`EXCERPT_LEN`] bytes of the input from where the error occurred.
+    excerpt: Vec<u8>,
 }
 
-impl fmt::Display for DecodeError {
+/// The kinds of [`DecodeError`]s.
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+    /// A read of `needed` bytes was attempted when only `had` bytes were left in the input.
+    Truncated { needed: usize, had: usize },
+    /// The tag `got` does not identify a variant of the sum.
+    /// When known, `max` is the greatest valid tag.
+    InvalidTag { got: u8, max: Option<u8> },
+    /// Expected a value of one kind but found another, e.g., a `bool` byte that is neither `0` nor `1`.
+    WrongKind { expected: String, found: String },
+    /// Expected data to be UTF-8, but it isn't starting at byte `offset`.
+    Utf8 { offset: usize },
+    /// An element of an array decoded as a set is equal to an element before it.
+    DuplicateElement,
+    /// A string decoded as a path can't be one.
+    InvalidPath(InvalidPath),
+    /// Reading the input failed.
+    Io(Arc<io::Error>),
+    /// Custom error not in the other kinds.
+    Custom(String),
+}
+
+/// A step along the path from a value to the part of it where a [`DecodeError`] occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// The field at `index` of a product, with its `name` if it has one.
+    Field { index: usize, name: Option<String> },
+    /// The element at some index of an array.
+    Element(usize),
+}
+
+/// The maximum number of bytes of input kept as the [excerpt](DecodeError::excerpt) of an error.
+pub const EXCERPT_LEN: usize = 16;
+
+impl DecodeError {
+    /// Returns an error of `kind`, without a path or an excerpt.
+    pub fn new(kind: ErrorKind) -> Self {
+        Self(Box::new(ErrorRepr {
+            kind,
+            path: Vec::new(),
+            excerpt: Vec::new(),
+        }))
+    }
+
+    /// Returns an error for a read of `needed` bytes when only the bytes `rest` were left.
+    pub fn truncated(needed: usize, rest: &[u8]) -> Self {
+        Self::new(ErrorKind::Truncated {
+            needed,
+            had: rest.len(),
+        })
+        .with_excerpt(rest)
+    }
+
+    /// Returns an error for the bytes `bytes` that are not valid UTF-8 per `err`.
+    pub fn utf8(err: Utf8Error, bytes: &[u8]) -> Self {
+        let offset = err.valid_up_to();
+        Self::from(err).with_excerpt(&bytes[offset..])
+    }
+
+    /// Returns the kind of error this is.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0.kind
+    }
+
+    /// Returns the path to where in the decoded value the error occurred, outermost segment first.
+    ///
+    /// The path is empty when the error occurred at the top level
+    /// or the decoded type does not report paths.
+    pub fn path(&self) -> impl ExactSizeIterator<Item = &PathSegment> + DoubleEndedIterator {
+        self.0.path.iter().rev()
+    }
+
+    /// Returns the bytes of input, starting where the error occurred, if any were recorded.
+    pub fn excerpt(&self) -> &[u8] {
+        &self.0.excerpt
+    }
+
+    /// Records the start of `bytes` as the bytes of input where the error occurred.
+    pub fn with_excerpt(mut self, bytes: &[u8]) -> Self {
+        self.0.excerpt = bytes[..bytes.len().min(EXCERPT_LEN)].to_vec();
+        self
+    }
+
+    /// Records that the error occurred within the field at `index`, optionally with `name`, of a product.
+    pub fn in_field(mut self, index: usize, name: Option<&str>) -> Self {
+        let name = name.map(Into::into);
+        self.0.path.push(PathSegment::Field { index, name });
+        self
+    }
+
+    /// Records that the error occurred within the element at `index` of an array.
+    pub fn in_element(mut self, index: usize) -> Self {
+        self.0.path.push(PathSegment::Element(index));
+        self
+    }
+}
+
+impl From<ErrorKind> for DecodeError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DecodeError::BufferLength => f.write_str("data too short"),
-            DecodeError::InvalidTag => f.write_str("invalid tag for sum"),
-            DecodeError::InvalidUtf8 => f.write_str("invalid utf8"),
-            DecodeError::Other(err) => f.write_str(err),
+            ErrorKind::Truncated { needed, had } => write!(f, "data too short: needed {needed} bytes, had {had}"),
+            ErrorKind::InvalidTag { got, max: Some(max) } => {
+                write!(f, "invalid tag {got} for sum, expected at most {max}")
+            }
+            ErrorKind::InvalidTag { got, max: None } => write!(f, "invalid tag {got} for sum"),
+            ErrorKind::WrongKind { expected, found } => write!(f, "expected {expected}, found {found}"),
+            ErrorKind::Utf8 { offset } => write!(f, "invalid utf8 at byte {offset}"),
+            ErrorKind::DuplicateElement => f.write_str("duplicate element of a set"),
+            ErrorKind::InvalidPath(err) => write!(f, "{err}"),
+            ErrorKind::Io(err) => write!(f, "error reading input: {err}"),
+            ErrorKind::Custom(err) => f.write_str(err),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind().fmt(f)?;
+        if !self.0.path.is_empty() {
+            f.write_str(" in `")?;
+            for (i, segment) in self.path().enumerate() {
+                match segment {
+                    PathSegment::Field { name: Some(name), .. } if i == 0 => f.write_str(name)?,
+                    PathSegment::Field { name: Some(name), .. } => write!(f, ".{name}")?,
+                    PathSegment::Field { index, .. } if i == 0 => write!(f, "{index}")?,
+                    PathSegment::Field { index, .. } => write!(f, ".{index}")?,
+                    PathSegment::Element(index) => write!(f, "[{index}]")?,
+                }
+            }
+            f.write_str("`")?;
+        }
+        if let [first, rest @ ..] = self.excerpt() {
+            write!(f, " (bytes: {first:02x}")?;
+            for byte in rest {
+                write!(f, " {byte:02x}")?;
+            }
+            f.write_str(")")?;
         }
+        Ok(())
     }
 }
 impl From<DecodeError> for String {
@@ -34,11 +182,26 @@ impl From<DecodeError> for String {
         err.to_string()
     }
 }
-impl std::error::Error for DecodeError {}
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind() {
+            ErrorKind::Io(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
 
 impl From<Utf8Error> for DecodeError {
-    fn from(_: Utf8Error) -> Self {
-        DecodeError::InvalidUtf8
+    fn from(err: Utf8Error) -> Self {
+        Self::new(ErrorKind::Utf8 {
+            offset: err.valid_up_to(),
+        })
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(err: io::Error) -> Self {
+        Self::new(ErrorKind::Io(Arc::new(err)))
     }
 }
 
@@ -50,6 +213,13 @@ pub trait BufWriter {
     /// All other methods are provided.
     fn put_slice(&mut self, slice: &[u8]);
 
+    /// Reserves room for at least `additional` more bytes, when the buffer can grow.
+    ///
+    /// This is only a hint, and does nothing by default.
+    fn reserve(&mut self, additional: usize) {
+        let _ = additional;
+    }
+
     /// Writes a `u8` to the buffer in little-endian (LE) encoding.
     fn put_u8(&mut self, val: u8) {
         self.put_slice(&val.to_le_bytes())
@@ -203,6 +373,10 @@ impl BufWriter for Vec<u8> {
     fn put_slice(&mut self, slice: &[u8]) {
         self.extend_from_slice(slice);
     }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional);
+    }
 }
 
 impl BufWriter for &mut [u8] {
@@ -219,7 +393,7 @@ impl BufWriter for &mut [u8] {
 impl<'de> BufReader<'de> for &'de [u8] {
     fn get_slice(&mut self, size: usize) -> Result<&'de [u8], DecodeError> {
         if self.len() < size {
-            return Err(DecodeError::BufferLength);
+            return Err(DecodeError::truncated(size, self));
         }
         let (ret, rest) = self.split_at(size);
         *self = rest;
@@ -251,9 +425,8 @@ impl<I> Cursor<I> {
 impl<'de, I: AsRef<[u8]>> BufReader<'de> for &'de Cursor<I> {
     fn get_slice(&mut self, size: usize) -> Result<&'de [u8], DecodeError> {
         // "Read" the slice `buf[pos..size]`.
-        let ret = self.buf.as_ref()[self.pos.get()..]
-            .get(..size)
-            .ok_or(DecodeError::BufferLength)?;
+        let rest = &self.buf.as_ref()[self.pos.get()..];
+        let ret = rest.get(..size).ok_or_else(|| DecodeError::truncated(size, rest))?;
 
         // Advance the cursor by `size` bytes.
         self.pos.set(self.pos.get() + size);
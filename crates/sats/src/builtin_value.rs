@@ -1,118 +1,113 @@
+use crate::algebraic_value::cmp::{arrays_cmp, maps_cmp, products_cmp, sums_cmp};
 use crate::algebraic_value::AlgebraicValue;
 use crate::builtin_type::BuiltinType;
-use crate::{AlgebraicType, ArrayType};
-use enum_as_inner::EnumAsInner;
+use crate::product_value::{heap_size_of_slice, InvalidFieldError};
+use crate::{AlgebraicType, ArrayType, ProductValue, SumValue, Typespace, WithTypespace};
 use itertools::Itertools;
 use nonempty::NonEmpty;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::{fmt, mem};
+
+pub mod packed_strings;
+
+pub use packed_strings::PackedStrings;
 
 /// Totally ordered [`f32`] allowing all IEEE-754 floating point values.
+///
+/// Arithmetic, with the usual operators and with [`Sum`](std::iter::Sum) and [`Product`](std::iter::Product),
+/// is that of `f32`, as are [`Display`](fmt::Display) and [`FromStr`](std::str::FromStr),
+/// so a value formatted and parsed back is the same value.
+/// The predicates `is_nan` and `is_finite` come from the [`Nan`] and [`Infinite`] traits,
+/// while `abs` comes from the [`FloatExt`] trait, along with conversions to `ordered_float::OrderedFloat`.
+/// Other operations go through `f32`, as in `F32::from(f32::from(x).sqrt())`.
+///
+/// Unlike for `f32`, equality, ordering and hashing agree with each other, so these can be keys:
+///
+/// - All `NaN`s are equal to each other, whatever their bits, and greater than every other value,
+///   including positive infinity.
+/// - `-0.0` and `0.0` are equal, and hash the same.
+/// - Otherwise, values are ordered as numbers, from negative to positive infinity.
+///
+/// The ordering is also that of `min` and `max`, through [`Ord`], so a `NaN` is the maximum of any values,
+/// and of two signed zeros, `min` returns the first and `max` the second.
+/// Beware that `num_traits::Float`, also implemented by these types, has `min` and `max` methods of its own,
+/// which return a `NaN` when either value is one.
 pub type F32 = decorum::Total<f32>;
 
 /// Totally ordered [`f64`] allowing all IEEE-754 floating point values.
+///
+/// This is the `f64` counterpart of [`F32`], with the same semantics for equality, ordering and hashing.
 pub type F64 = decorum::Total<f64>;
 
-/// A built-in value of a [`BuiltinType`].
-#[derive(EnumAsInner, Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub enum BuiltinValue {
-    /// A [`bool`] value of type [`BuiltinType::Bool`].
-    Bool(bool),
-    /// An [`i8`] value of type [`BuiltinType::I8`].
-    I8(i8),
-    /// A [`u8`] value of type [`BuiltinType::U8`].
-    U8(u8),
-    /// An [`i16`] value of type [`BuiltinType::I16`].
-    I16(i16),
-    /// A [`u16`] value of type [`BuiltinType::U16`].
-    U16(u16),
-    /// An [`i32`] value of type [`BuiltinType::I32`].
-    I32(i32),
-    /// A [`u32`] value of type [`BuiltinType::U32`].
-    U32(u32),
-    /// An [`i64`] value of type [`BuiltinType::I64`].
-    I64(i64),
-    /// A [`u64`] value of type [`BuiltinType::U64`].
-    U64(u64),
-    /// An [`i128`] value of type [`BuiltinType::I128`].
-    I128(i128),
-    /// A [`u128`] value of type [`BuiltinType::U128`].
-    U128(u128),
-    /// A totally ordered [`F32`] value of type [`BuiltinType::F32`].
-    ///
-    /// All floating point values defined in IEEE-754 are supported.
-    /// However, unlike the primitive [`f32`], a [total order] is established.
-    ///
-    /// [total order]: https://docs.rs/decorum/0.3.1/decorum/#total-ordering
-    F32(F32),
-    /// A totally ordered [`F64`] value of type [`BuiltinType::F64`].
-    ///
-    /// All floating point values defined in IEEE-754 are supported.
-    /// However, unlike the primitive [`f64`], a [total order] is established.
-    ///
-    /// [total order]: https://docs.rs/decorum/0.3.1/decorum/#total-ordering
-    F64(F64),
-    /// A UTF-8 string value of type [`BuiltinType::String`].
-    ///
-    /// Uses Rust's standard representation of strings.
-    String(String),
-    /// A homogeneous array of `AlgebraicValue`s.
-    /// The array has the type [`BuiltinType::Array(elem_ty)`].
-    ///
-    /// The contained values are stored packed in a representation appropriate for their type.
-    /// See [`ArrayValue`] for details on the representation.
-    Array { val: ArrayValue },
-    /// An ordered map value of `key: AlgebraicValue`s mapped to `value: AlgebraicValue`s.
-    /// Each `key` must be of the same [`AlgebraicType`] as all the others
-    /// and the same applies to each `value`.
-    /// A map as a whole has the type [`BuiltinType::Map(key_ty, val_ty)`].
+/// The traits providing the float predicates of [`F32`] and [`F64`].
+pub use decorum::{Infinite, Nan};
+
+/// Operations on [`F32`] and [`F64`] not provided by their other traits.
+pub trait FloatExt: Copy {
+    /// The primitive float type wrapped, `f32` or `f64`.
+    type Primitive;
+
+    /// Returns the absolute value of `self`, clearing its sign bit, as for primitive floats.
     ///
-    /// Maps are implemented internally as [`BTreeMap<AlgebraicValue, AlgebraicValue>`].
-    /// This implies that key/values are ordered first by key and then value
-    /// as if they were a sorted slice `[(key, value)]`.
-    /// This order is observable as maps are exposed both directly
-    /// and indirectly via `Ord for `[`AlgebraicValue`].
-    /// The latter lets us observe that e.g., `{ a: 42 } < { b: 42 }`.
-    /// However, we cannot observe any difference between `{ a: 0, b: 0 }` and `{ b: 0, a: 0 }`,
-    /// as the natural order is used as opposed to insertion order.
-    /// Where insertion order is relevant,
-    /// a [`BuiltinValue::Array`] with `(key, value)` pairs can be used instead.
-    Map { val: MapValue },
+    /// So the absolute value of `-0.0` is `0.0`, and that of a `NaN` is a `NaN`.
+    fn abs(self) -> Self;
+
+    /// Converts `self` into an `OrderedFloat`, which orders and hashes the same as `self`:
+    /// `NaN`s are equal to each other and greater than everything else, and signed zeros are equal.
+    #[cfg(feature = "ordered-float")]
+    fn to_ordered_float(self) -> ordered_float::OrderedFloat<Self::Primitive>;
+
+    /// Converts `x` from an `OrderedFloat`, as [`to_ordered_float`](Self::to_ordered_float) does in reverse.
+    #[cfg(feature = "ordered-float")]
+    fn from_ordered_float(x: ordered_float::OrderedFloat<Self::Primitive>) -> Self;
 }
 
-/// A map value `AlgebraicValue` → `AlgebraicValue`.
-pub type MapValue = BTreeMap<AlgebraicValue, AlgebraicValue>;
+macro_rules! impl_float_ext {
+    ($($ty:ty => $prim:ty),*) => {
+        $(impl FloatExt for $ty {
+            type Primitive = $prim;
 
-impl crate::Value for MapValue {
-    type Type = crate::MapType;
+            fn abs(self) -> Self {
+                <$prim>::from(self).abs().into()
+            }
+
+            #[cfg(feature = "ordered-float")]
+            fn to_ordered_float(self) -> ordered_float::OrderedFloat<$prim> {
+                ordered_float::OrderedFloat(self.into())
+            }
+
+            #[cfg(feature = "ordered-float")]
+            fn from_ordered_float(x: ordered_float::OrderedFloat<$prim>) -> Self {
+                x.into_inner().into()
+            }
+        })*
+    };
 }
 
-impl BuiltinValue {
-    /// Returns the byte string `v` as a [`BuiltinValue`].
-    #[allow(non_snake_case)]
-    pub const fn Bytes(v: Vec<u8>) -> Self {
-        Self::Array { val: ArrayValue::U8(v) }
-    }
+impl_float_ext!(F32 => f32, F64 => f64);
 
-    /// Returns `self` as a borrowed byte string, if applicable.
-    pub fn as_bytes(&self) -> Option<&Vec<u8>> {
-        match self {
-            BuiltinValue::Array { val: ArrayValue::U8(v) } => Some(v),
-            _ => None,
-        }
-    }
+/// A built-in value of a [`BuiltinType`].
+///
+/// Builtin values are now variants of [`AlgebraicValue`] directly,
+/// sparing a level of tagging in every value.
+#[deprecated(note = "use `AlgebraicValue` instead")]
+pub type BuiltinValue = AlgebraicValue;
 
-    /// Converts `self` into a byte string, if applicable.
-    pub fn into_bytes(self) -> Result<Vec<u8>, Self> {
-        match self {
-            BuiltinValue::Array { val: ArrayValue::U8(v) } => Ok(v),
-            _ => Err(self),
-        }
-    }
+/// A map value `AlgebraicValue` → `AlgebraicValue`.
+pub type MapValue = BTreeMap<AlgebraicValue, AlgebraicValue>;
+
+impl crate::Value for MapValue {
+    type Type = crate::MapType;
 }
 
-impl crate::Value for BuiltinValue {
-    type Type = BuiltinType;
+/// Returns an estimate of the number of bytes the map `map` owns on the heap.
+pub(crate) fn map_heap_size_bytes(map: &MapValue) -> usize {
+    map.iter()
+        .map(|(k, v)| 2 * mem::size_of::<AlgebraicValue>() + k.heap_size_bytes() + v.heap_size_bytes())
+        .sum()
 }
 
 /// An array value in "monomorphized form".
@@ -121,7 +116,11 @@ impl crate::Value for BuiltinValue {
 /// rather than unnecessary indirections and tags of `Vec<AlgebraicValue>`.
 /// We can do this as we know statically that the type of each element is the same
 /// as arrays are homogenous dynamically sized product types.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+///
+/// Arrays of strings have two representations, [`ArrayValue::String`] and [`ArrayValue::StringPacked`],
+/// which are interchangeable: arrays of the same strings are equal, ordered and hashed the same,
+/// and serialized the same, whichever representation each of them is in.
+#[derive(Clone)]
 pub enum ArrayValue {
     /// An array of [`SumValue`](crate::SumValue)s.
     Sum(Vec<crate::SumValue>),
@@ -132,6 +131,10 @@ pub enum ArrayValue {
     /// An array of [`i8`]s.
     I8(Vec<i8>),
     /// An array of [`u8`]s.
+    ///
+    /// This is the one representation of byte blobs.
+    /// Bytes are always serialized with `serialize_bytes`,
+    /// and a decoded byte array, even an empty one, always lands here, in a single allocation.
     U8(Vec<u8>),
     /// An array of [`i16`]s.
     I16(Vec<i16>),
@@ -155,6 +158,13 @@ pub enum ArrayValue {
     F64(Vec<F64>),
     /// An array of UTF-8 strings.
     String(Vec<String>),
+    /// An array of UTF-8 strings packed back to back in one buffer,
+    /// taking a few allocations rather than one per string.
+    /// The strings are boxed to keep the size of an `ArrayValue` down.
+    ///
+    /// A BSATN [`Deserializer`](crate::bsatn::Deserializer) decodes string arrays into this representation
+    /// when [`with_packed_strings`](crate::bsatn::Deserializer::with_packed_strings) is set.
+    StringPacked(Box<PackedStrings>),
     /// An array of arrays.
     Array(Vec<ArrayValue>),
     /// An array of maps.
@@ -165,6 +175,34 @@ impl crate::Value for ArrayValue {
     type Type = ArrayType;
 }
 
+/// Evaluates `$body` with `$v` bound to the vector of elements of the array `$arr`, whichever kind they are,
+/// or, for an `ArrayValue::StringPacked`, `$packed_body` with `$p` bound to its [`PackedStrings`].
+macro_rules! on_elements {
+    ($arr:expr, $v:ident => $body:expr, $p:ident => $packed_body:expr) => {
+        match $arr {
+            ArrayValue::Sum($v) => $body,
+            ArrayValue::Product($v) => $body,
+            ArrayValue::Bool($v) => $body,
+            ArrayValue::I8($v) => $body,
+            ArrayValue::U8($v) => $body,
+            ArrayValue::I16($v) => $body,
+            ArrayValue::U16($v) => $body,
+            ArrayValue::I32($v) => $body,
+            ArrayValue::U32($v) => $body,
+            ArrayValue::I64($v) => $body,
+            ArrayValue::U64($v) => $body,
+            ArrayValue::I128($v) => $body,
+            ArrayValue::U128($v) => $body,
+            ArrayValue::F32($v) => $body,
+            ArrayValue::F64($v) => $body,
+            ArrayValue::String($v) => $body,
+            ArrayValue::StringPacked($p) => $packed_body,
+            ArrayValue::Array($v) => $body,
+            ArrayValue::Map($v) => $body,
+        }
+    };
+}
+
 impl ArrayValue {
     /// Determines (infers / synthesises) the type of the value.
     pub(crate) fn type_of(&self) -> ArrayType {
@@ -184,7 +222,7 @@ impl ArrayValue {
             ArrayValue::U128(_) => AlgebraicType::U128,
             ArrayValue::F32(_) => AlgebraicType::F32,
             ArrayValue::F64(_) => AlgebraicType::F64,
-            ArrayValue::String(_) => AlgebraicType::String,
+            ArrayValue::String(_) | ArrayValue::StringPacked(_) => AlgebraicType::String,
             ArrayValue::Array(v) => Self::first_type_of(v, |a| AlgebraicType::Builtin(BuiltinType::Array(a.type_of()))),
             ArrayValue::Map(v) => Self::first_type_of(v, AlgebraicValue::type_of_map),
         });
@@ -216,6 +254,7 @@ impl ArrayValue {
             ArrayValue::F32(v) => v.len(),
             ArrayValue::F64(v) => v.len(),
             ArrayValue::String(v) => v.len(),
+            ArrayValue::StringPacked(v) => v.len(),
             ArrayValue::Array(v) => v.len(),
             ArrayValue::Map(v) => v.len(),
         }
@@ -227,6 +266,43 @@ impl ArrayValue {
         self.len() == 0
     }
 
+    /// Returns an estimate of the number of bytes `self` owns on the heap.
+    ///
+    /// This is the capacity of the backing vector times the size of an element
+    /// plus, for non-primitive elements, what the elements own on the heap in turn.
+    pub fn heap_size_bytes(&self) -> usize {
+        /// Returns the heap bytes of a vector of primitives.
+        fn prim<T>(v: &Vec<T>) -> usize {
+            v.capacity() * mem::size_of::<T>()
+        }
+        /// Returns the heap bytes of a vector of elements owning heap memory per `f`.
+        fn nested<T>(v: &Vec<T>, f: impl Fn(&T) -> usize) -> usize {
+            heap_size_of_slice(v, v.capacity(), f)
+        }
+
+        match self {
+            ArrayValue::Sum(v) => nested(v, SumValue::heap_size_bytes),
+            ArrayValue::Product(v) => nested(v, ProductValue::heap_size_bytes),
+            ArrayValue::Bool(v) => prim(v),
+            ArrayValue::I8(v) => prim(v),
+            ArrayValue::U8(v) => prim(v),
+            ArrayValue::I16(v) => prim(v),
+            ArrayValue::U16(v) => prim(v),
+            ArrayValue::I32(v) => prim(v),
+            ArrayValue::U32(v) => prim(v),
+            ArrayValue::I64(v) => prim(v),
+            ArrayValue::U64(v) => prim(v),
+            ArrayValue::I128(v) => prim(v),
+            ArrayValue::U128(v) => prim(v),
+            ArrayValue::F32(v) => prim(v),
+            ArrayValue::F64(v) => prim(v),
+            ArrayValue::String(v) => nested(v, String::capacity),
+            ArrayValue::StringPacked(v) => v.heap_size_bytes(),
+            ArrayValue::Array(v) => nested(v, ArrayValue::heap_size_bytes),
+            ArrayValue::Map(v) => nested(v, map_heap_size_bytes),
+        }
+    }
+
     /// Returns a singleton array with `val` as its only element.
     ///
     /// Optionally allocates the backing `Vec<_>`s with `capacity`.
@@ -240,22 +316,22 @@ impl ArrayValue {
         match val {
             AlgebraicValue::Sum(x) => vec(x, capacity).into(),
             AlgebraicValue::Product(x) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::Bool(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::I8(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::U8(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::I16(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::U16(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::I32(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::U32(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::I64(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::U64(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::I128(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::U128(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::F32(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::F64(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::String(x)) => vec(x, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::Array { val }) => vec(val, capacity).into(),
-            AlgebraicValue::Builtin(BuiltinValue::Map { val }) => vec(val, capacity).into(),
+            AlgebraicValue::Bool(x) => vec(x, capacity).into(),
+            AlgebraicValue::I8(x) => vec(x, capacity).into(),
+            AlgebraicValue::U8(x) => vec(x, capacity).into(),
+            AlgebraicValue::I16(x) => vec(x, capacity).into(),
+            AlgebraicValue::U16(x) => vec(x, capacity).into(),
+            AlgebraicValue::I32(x) => vec(x, capacity).into(),
+            AlgebraicValue::U32(x) => vec(x, capacity).into(),
+            AlgebraicValue::I64(x) => vec(x, capacity).into(),
+            AlgebraicValue::U64(x) => vec(x, capacity).into(),
+            AlgebraicValue::I128(x) => vec(x, capacity).into(),
+            AlgebraicValue::U128(x) => vec(x, capacity).into(),
+            AlgebraicValue::F32(x) => vec(x, capacity).into(),
+            AlgebraicValue::F64(x) => vec(x, capacity).into(),
+            AlgebraicValue::String(x) => vec(String::from(x), capacity).into(),
+            AlgebraicValue::Array(val) => vec(val, capacity).into(),
+            AlgebraicValue::Map(val) => vec(val, capacity).into(),
         }
     }
 
@@ -268,28 +344,72 @@ impl ArrayValue {
         match (self, val) {
             (ArrayValue::Sum(v), AlgebraicValue::Sum(val)) => v.push(val),
             (ArrayValue::Product(v), AlgebraicValue::Product(val)) => v.push(val),
-            (ArrayValue::Bool(v), AlgebraicValue::Builtin(BuiltinValue::Bool(val))) => v.push(val),
-            (ArrayValue::I8(v), AlgebraicValue::Builtin(BuiltinValue::I8(val))) => v.push(val),
-            (ArrayValue::U8(v), AlgebraicValue::Builtin(BuiltinValue::U8(val))) => v.push(val),
-            (ArrayValue::I16(v), AlgebraicValue::Builtin(BuiltinValue::I16(val))) => v.push(val),
-            (ArrayValue::U16(v), AlgebraicValue::Builtin(BuiltinValue::U16(val))) => v.push(val),
-            (ArrayValue::I32(v), AlgebraicValue::Builtin(BuiltinValue::I32(val))) => v.push(val),
-            (ArrayValue::U32(v), AlgebraicValue::Builtin(BuiltinValue::U32(val))) => v.push(val),
-            (ArrayValue::I64(v), AlgebraicValue::Builtin(BuiltinValue::I64(val))) => v.push(val),
-            (ArrayValue::U64(v), AlgebraicValue::Builtin(BuiltinValue::U64(val))) => v.push(val),
-            (ArrayValue::I128(v), AlgebraicValue::Builtin(BuiltinValue::I128(val))) => v.push(val),
-            (ArrayValue::U128(v), AlgebraicValue::Builtin(BuiltinValue::U128(val))) => v.push(val),
-            (ArrayValue::F32(v), AlgebraicValue::Builtin(BuiltinValue::F32(val))) => v.push(val),
-            (ArrayValue::F64(v), AlgebraicValue::Builtin(BuiltinValue::F64(val))) => v.push(val),
-            (ArrayValue::String(v), AlgebraicValue::Builtin(BuiltinValue::String(val))) => v.push(val),
-            (ArrayValue::Array(v), AlgebraicValue::Builtin(BuiltinValue::Array { val })) => v.push(val),
-            (ArrayValue::Map(v), AlgebraicValue::Builtin(BuiltinValue::Map { val })) => v.push(val),
+            (ArrayValue::Bool(v), AlgebraicValue::Bool(val)) => v.push(val),
+            (ArrayValue::I8(v), AlgebraicValue::I8(val)) => v.push(val),
+            (ArrayValue::U8(v), AlgebraicValue::U8(val)) => v.push(val),
+            (ArrayValue::I16(v), AlgebraicValue::I16(val)) => v.push(val),
+            (ArrayValue::U16(v), AlgebraicValue::U16(val)) => v.push(val),
+            (ArrayValue::I32(v), AlgebraicValue::I32(val)) => v.push(val),
+            (ArrayValue::U32(v), AlgebraicValue::U32(val)) => v.push(val),
+            (ArrayValue::I64(v), AlgebraicValue::I64(val)) => v.push(val),
+            (ArrayValue::U64(v), AlgebraicValue::U64(val)) => v.push(val),
+            (ArrayValue::I128(v), AlgebraicValue::I128(val)) => v.push(val),
+            (ArrayValue::U128(v), AlgebraicValue::U128(val)) => v.push(val),
+            (ArrayValue::F32(v), AlgebraicValue::F32(val)) => v.push(val),
+            (ArrayValue::F64(v), AlgebraicValue::F64(val)) => v.push(val),
+            (ArrayValue::String(v), AlgebraicValue::String(val)) => v.push(val.into()),
+            (ArrayValue::StringPacked(v), AlgebraicValue::String(val)) => v.push(&val),
+            (ArrayValue::Array(v), AlgebraicValue::Array(val)) => v.push(val),
+            (ArrayValue::Map(v), AlgebraicValue::Map(val)) => v.push(val),
             (me, val) if me.is_empty() => *me = Self::from_one_with_capacity(val, capacity),
             (_, val) => return Err(val),
         }
         Ok(())
     }
 
+    /// Replaces the element at `index` of the array `self` with the value `val`
+    /// or returns back `Err(val)` if there was a type mismatch
+    /// between the base type of the array and `val`.
+    ///
+    /// Replacing a packed string moves the bytes of the strings after it.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, val: AlgebraicValue) -> Result<(), AlgebraicValue> {
+        let len = self.len();
+        assert!(
+            index < len,
+            "index {index} is out of bounds for an array of length {len}"
+        );
+        match (self, val) {
+            (ArrayValue::Sum(v), AlgebraicValue::Sum(val)) => v[index] = val,
+            (ArrayValue::Product(v), AlgebraicValue::Product(val)) => v[index] = val,
+            (ArrayValue::Bool(v), AlgebraicValue::Bool(val)) => v[index] = val,
+            (ArrayValue::I8(v), AlgebraicValue::I8(val)) => v[index] = val,
+            (ArrayValue::U8(v), AlgebraicValue::U8(val)) => v[index] = val,
+            (ArrayValue::I16(v), AlgebraicValue::I16(val)) => v[index] = val,
+            (ArrayValue::U16(v), AlgebraicValue::U16(val)) => v[index] = val,
+            (ArrayValue::I32(v), AlgebraicValue::I32(val)) => v[index] = val,
+            (ArrayValue::U32(v), AlgebraicValue::U32(val)) => v[index] = val,
+            (ArrayValue::I64(v), AlgebraicValue::I64(val)) => v[index] = val,
+            (ArrayValue::U64(v), AlgebraicValue::U64(val)) => v[index] = val,
+            (ArrayValue::I128(v), AlgebraicValue::I128(val)) => v[index] = val,
+            (ArrayValue::U128(v), AlgebraicValue::U128(val)) => v[index] = val,
+            (ArrayValue::F32(v), AlgebraicValue::F32(val)) => v[index] = val,
+            (ArrayValue::F64(v), AlgebraicValue::F64(val)) => v[index] = val,
+            (ArrayValue::String(v), AlgebraicValue::String(val)) => v[index] = val.into(),
+            (ArrayValue::StringPacked(v), AlgebraicValue::String(val)) => {
+                let rest = v.drain(index + 1..len);
+                v.truncate(index);
+                v.push(&val);
+                v.extend(&rest);
+            }
+            (ArrayValue::Array(v), AlgebraicValue::Array(val)) => v[index] = val,
+            (ArrayValue::Map(v), AlgebraicValue::Map(val)) => v[index] = val,
+            (_, val) => return Err(val),
+        }
+        Ok(())
+    }
+
     /// Returns a cloning iterator on the elements of `self` as `AlgebraicValue`s.
     pub fn iter_cloned(&self) -> ArrayValueIterCloned {
         match self {
@@ -309,10 +429,467 @@ impl ArrayValue {
             ArrayValue::F32(v) => ArrayValueIterCloned::F32(v.iter()),
             ArrayValue::F64(v) => ArrayValueIterCloned::F64(v.iter()),
             ArrayValue::String(v) => ArrayValueIterCloned::String(v.iter()),
+            ArrayValue::StringPacked(v) => ArrayValueIterCloned::StringPacked(v.iter()),
             ArrayValue::Array(v) => ArrayValueIterCloned::Array(v.iter()),
             ArrayValue::Map(v) => ArrayValueIterCloned::Map(v.iter()),
         }
     }
+
+    /// Sorts the elements of the array in place according to `comparator`.
+    ///
+    /// The sort is stable and the array keeps its monomorphized representation.
+    /// Elements of scalar kinds, e.g., numbers, are passed to `comparator` as values copied from the vector,
+    /// while those of other kinds are moved into `AlgebraicValue`s for the sort and back, without being cloned.
+    pub fn sort_in_place(&mut self, comparator: impl Fn(&AlgebraicValue, &AlgebraicValue) -> Ordering) {
+        on_elements!(self, v => SortAsValues::sort_as_values(v, &comparator), p => {
+            let mut strings = p.iter().map(AlgebraicValue::from).collect_vec();
+            strings.sort_by(&comparator);
+            **p = strings.iter().filter_map(AlgebraicValue::as_string).collect();
+        })
+    }
+
+    /// Sorts the elements of the array in place
+    /// according to their order as values of the element type `schema`,
+    /// directly in the vector of each kind of element rather than as `AlgebraicValue`s.
+    ///
+    /// Any type references in `schema` are resolved in `ts`.
+    /// See [`values_cmp`](crate::algebraic_value::cmp::values_cmp) for details on the order used.
+    /// Elements of kinds without any type to look into, e.g., numbers, are sorted as by [`ArrayValue::sort`],
+    /// as are those `schema` doesn't fit, or resolve for.
+    ///
+    /// The sort is stable, so equal elements keep their relative order.
+    pub fn sort_by_schema(&mut self, schema: &AlgebraicType, ts: &Typespace) {
+        let ty = WithTypespace::new(ts, schema);
+        match (self, ts.resolve_value_head(schema)) {
+            (ArrayValue::Sum(v), Ok(AlgebraicType::Sum(sty))) => v.sort_by(|a, b| sums_cmp(ty.with(sty), a, b)),
+            (ArrayValue::Product(v), Ok(AlgebraicType::Product(pty))) => {
+                v.sort_by(|a, b| products_cmp(ty.with(pty), a, b))
+            }
+            (ArrayValue::Array(v), Ok(AlgebraicType::Builtin(BuiltinType::Array(aty)))) => {
+                v.sort_by(|a, b| arrays_cmp(ty.with(aty), a, b))
+            }
+            (ArrayValue::Map(v), Ok(AlgebraicType::Builtin(BuiltinType::Map(mty)))) => {
+                v.sort_by(|a, b| maps_cmp(ty.with(mty), a, b))
+            }
+            (arr, _) => arr.sort(),
+        }
+    }
+
+    /// Sorts the elements of the array in place by their [`Ord`],
+    /// directly in the vector of each kind of element rather than as `AlgebraicValue`s.
+    ///
+    /// The sort is stable, so equal elements keep their relative order.
+    pub fn sort(&mut self) {
+        on_elements!(self, v => v.sort(), p => **p = p.iter().sorted().collect())
+    }
+
+    /// Sorts the elements of the array, which must be products, in place by the field at `key_path`,
+    /// a path of field indices, each into the product the one before leads to,
+    /// e.g., `[1]` for the second column of each row.
+    ///
+    /// The sort is stable, so elements with equal keys keep their relative order.
+    /// The empty path is the whole element, as with [`ArrayValue::sort`].
+    ///
+    /// Returns an [`InvalidFieldError`], leaving the array as it was,
+    /// if any element has no field at the path, including when the elements are not products.
+    pub fn sort_by_key_path(&mut self, key_path: &[usize]) -> Result<(), InvalidFieldError> {
+        let Some(&first) = key_path.first() else {
+            self.sort();
+            return Ok(());
+        };
+        let rows = match self {
+            ArrayValue::Product(rows) => rows,
+            arr if arr.is_empty() => return Ok(()),
+            _ => {
+                return Err(InvalidFieldError {
+                    col_pos: first,
+                    name: None,
+                })
+            }
+        };
+        rows.iter().try_for_each(|row| field_at_path(row, key_path).map(drop))?;
+        rows.sort_by(|a, b| {
+            let key = |row| field_at_path(row, key_path).expect("every row has the key path");
+            key(a).cmp(key(b))
+        });
+        Ok(())
+    }
+
+    /// Removes consecutive repeated elements of the array in place,
+    /// directly in the vector of each kind of element rather than as `AlgebraicValue`s.
+    ///
+    /// Only adjacent duplicates are removed, so [sort](ArrayValue::sort) first to remove all of them.
+    pub fn dedup(&mut self) {
+        on_elements!(self, v => v.dedup(), p => **p = p.iter().dedup().collect())
+    }
+
+    /// Returns a short name of the kind of elements of the array, e.g., `"U32"` for an `ArrayValue::U32`.
+    fn kind_name(&self) -> &'static str {
+        match self {
+            ArrayValue::Sum(_) => "Sum",
+            ArrayValue::Product(_) => "Product",
+            ArrayValue::Bool(_) => "Bool",
+            ArrayValue::I8(_) => "I8",
+            ArrayValue::U8(_) => "U8",
+            ArrayValue::I16(_) => "I16",
+            ArrayValue::U16(_) => "U16",
+            ArrayValue::I32(_) => "I32",
+            ArrayValue::U32(_) => "U32",
+            ArrayValue::I64(_) => "I64",
+            ArrayValue::U64(_) => "U64",
+            ArrayValue::I128(_) => "I128",
+            ArrayValue::U128(_) => "U128",
+            ArrayValue::F32(_) => "F32",
+            ArrayValue::F64(_) => "F64",
+            ArrayValue::String(_) | ArrayValue::StringPacked(_) => "String",
+            ArrayValue::Array(_) => "Array",
+            ArrayValue::Map(_) => "Map",
+        }
+    }
+
+    /// Returns an empty array of the kind of elements `elem_ty` has, seeing through newtypes,
+    /// or `None` if `elem_ty` is a `Ref`, which can't be resolved here.
+    fn empty_of_type(elem_ty: &AlgebraicType) -> Option<Self> {
+        let mut ty = elem_ty;
+        while let AlgebraicType::Newtype(nt) = ty {
+            ty = &nt.inner;
+        }
+        Some(match ty {
+            AlgebraicType::Sum(_) => ArrayValue::Sum(Vec::new()),
+            AlgebraicType::Product(_) => ArrayValue::Product(Vec::new()),
+            AlgebraicType::Builtin(b) => match b {
+                BuiltinType::Bool => ArrayValue::Bool(Vec::new()),
+                BuiltinType::I8 => ArrayValue::I8(Vec::new()),
+                BuiltinType::U8 => ArrayValue::U8(Vec::new()),
+                BuiltinType::I16 => ArrayValue::I16(Vec::new()),
+                BuiltinType::U16 => ArrayValue::U16(Vec::new()),
+                BuiltinType::I32 => ArrayValue::I32(Vec::new()),
+                BuiltinType::U32 => ArrayValue::U32(Vec::new()),
+                BuiltinType::I64 => ArrayValue::I64(Vec::new()),
+                BuiltinType::U64 => ArrayValue::U64(Vec::new()),
+                BuiltinType::I128 => ArrayValue::I128(Vec::new()),
+                BuiltinType::U128 => ArrayValue::U128(Vec::new()),
+                BuiltinType::F32 => ArrayValue::F32(Vec::new()),
+                BuiltinType::F64 => ArrayValue::F64(Vec::new()),
+                BuiltinType::String => ArrayValue::String(Vec::new()),
+                BuiltinType::Array(_) => ArrayValue::Array(Vec::new()),
+                BuiltinType::Map(_) => ArrayValue::Map(Vec::new()),
+            },
+            AlgebraicType::Ref(_) | AlgebraicType::Newtype(_) => return None,
+        })
+    }
+
+    /// Returns the elements of the array as a vector of `AlgebraicValue`s, cloning them.
+    pub fn to_values(&self) -> Vec<AlgebraicValue> {
+        self.iter_cloned().collect()
+    }
+
+    /// Returns the elements of the array as a vector of `AlgebraicValue`s.
+    pub fn into_values(self) -> Vec<AlgebraicValue> {
+        self.into_iter().collect()
+    }
+
+    /// Returns an array of the values `vals`, specialized to the kind of elements they all are,
+    /// undoing [`ArrayValue::to_values`].
+    ///
+    /// The kind is that of `elem_ty`, the type of the elements, if given, and otherwise that of the first value.
+    /// An empty `vals` is thus an empty array of the kind of `elem_ty`,
+    /// or, without it (or when it is a `Ref`, which is not resolved), the [default](ArrayValue::default) empty array.
+    ///
+    /// Returns an error naming the first value that is not of that kind.
+    pub fn try_from_values(
+        vals: Vec<AlgebraicValue>,
+        elem_ty: Option<&AlgebraicType>,
+    ) -> Result<ArrayValue, MixedElementsError> {
+        let capacity = Some(vals.len());
+        let typed = elem_ty.and_then(Self::empty_of_type);
+        let expected = match &typed {
+            Some(arr) => arr.kind_name(),
+            None => match vals.first() {
+                Some(first) => first.type_name(),
+                None => return Ok(ArrayValue::default()),
+            },
+        };
+        let mut arr = typed.unwrap_or_default();
+        for (index, val) in vals.into_iter().enumerate() {
+            let found = val.type_name();
+            if found != expected {
+                return Err(MixedElementsError { index, expected, found });
+            }
+            arr.push(val, capacity).expect("the value is of the kind of the array");
+        }
+        Ok(arr)
+    }
+
+    /// Checks that `range` is within the array.
+    fn check_range(&self, range: &Range<usize>) -> Result<(), ArrayOpError> {
+        let len = self.len();
+        if range.start > range.end || range.end > len {
+            let (start, end) = (range.start, range.end);
+            return Err(ArrayOpError::OutOfRange { start, end, len });
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the elements of the array in `range`, as an array of the same kind of elements.
+    ///
+    /// Only those elements are cloned, not the whole array.
+    /// Returns an error if `range` is not within the array.
+    pub fn slice(&self, range: Range<usize>) -> Result<ArrayValue, ArrayOpError> {
+        self.check_range(&range)?;
+        Ok(on_elements!(self, v => v[range].to_vec().into(), p => p.slice(range).into()))
+    }
+
+    /// Shortens the array to its first `len` elements, keeping the kind of elements.
+    ///
+    /// Does nothing if the array has no more than `len` elements.
+    pub fn truncate(&mut self, len: usize) {
+        on_elements!(self, v => v.truncate(len), p => p.truncate(len))
+    }
+
+    /// Removes the elements of the array in `range` and returns them,
+    /// as an array of the same kind of elements, without cloning them.
+    /// Returns an error, leaving the array as it was, if `range` is not within the array.
+    pub fn drain_range(&mut self, range: Range<usize>) -> Result<ArrayValue, ArrayOpError> {
+        self.check_range(&range)?;
+        Ok(on_elements!(
+            self,
+            v => v.drain(range).collect::<Vec<_>>().into(),
+            p => p.drain(range).into()
+        ))
+    }
+
+    /// Returns the elements of `a` followed by those of `b`, reusing the vector of `a`.
+    ///
+    /// The two arrays must have the same kind of elements,
+    /// except that an empty array, which may have been typed as any kind,
+    /// concatenates with an array of any kind, keeping the kind of the other array.
+    /// Returns an error if neither is empty and they have different kinds of elements.
+    pub fn concat(a: ArrayValue, b: ArrayValue) -> Result<ArrayValue, ArrayOpError> {
+        fn extend<T>(mut a: Vec<T>, b: Vec<T>) -> Vec<T> {
+            a.extend(b);
+            a
+        }
+
+        Ok(match (a, b) {
+            (ArrayValue::Sum(a), ArrayValue::Sum(b)) => extend(a, b).into(),
+            (ArrayValue::Product(a), ArrayValue::Product(b)) => extend(a, b).into(),
+            (ArrayValue::Bool(a), ArrayValue::Bool(b)) => extend(a, b).into(),
+            (ArrayValue::I8(a), ArrayValue::I8(b)) => extend(a, b).into(),
+            (ArrayValue::U8(a), ArrayValue::U8(b)) => extend(a, b).into(),
+            (ArrayValue::I16(a), ArrayValue::I16(b)) => extend(a, b).into(),
+            (ArrayValue::U16(a), ArrayValue::U16(b)) => extend(a, b).into(),
+            (ArrayValue::I32(a), ArrayValue::I32(b)) => extend(a, b).into(),
+            (ArrayValue::U32(a), ArrayValue::U32(b)) => extend(a, b).into(),
+            (ArrayValue::I64(a), ArrayValue::I64(b)) => extend(a, b).into(),
+            (ArrayValue::U64(a), ArrayValue::U64(b)) => extend(a, b).into(),
+            (ArrayValue::I128(a), ArrayValue::I128(b)) => extend(a, b).into(),
+            (ArrayValue::U128(a), ArrayValue::U128(b)) => extend(a, b).into(),
+            (ArrayValue::F32(a), ArrayValue::F32(b)) => extend(a, b).into(),
+            (ArrayValue::F64(a), ArrayValue::F64(b)) => extend(a, b).into(),
+            (ArrayValue::String(a), ArrayValue::String(b)) => extend(a, b).into(),
+            (ArrayValue::String(mut a), ArrayValue::StringPacked(b)) => {
+                a.extend(b.iter().map(str::to_owned));
+                a.into()
+            }
+            (ArrayValue::StringPacked(mut a), ArrayValue::String(b)) => {
+                a.extend(b);
+                ArrayValue::StringPacked(a)
+            }
+            (ArrayValue::StringPacked(mut a), ArrayValue::StringPacked(b)) => {
+                a.extend(b.iter());
+                ArrayValue::StringPacked(a)
+            }
+            (ArrayValue::Array(a), ArrayValue::Array(b)) => extend(a, b).into(),
+            (ArrayValue::Map(a), ArrayValue::Map(b)) => extend(a, b).into(),
+            (a, b) if b.is_empty() => a,
+            (a, b) if a.is_empty() => b,
+            (a, b) => {
+                let (left, right) = (a.kind_name(), b.kind_name());
+                return Err(ArrayOpError::KindMismatch { left, right });
+            }
+        })
+    }
+}
+
+/// An element type of an [`ArrayValue`], sorted as the [`AlgebraicValue`]s it is an array of
+/// by [`ArrayValue::sort_in_place`].
+trait SortAsValues: Sized {
+    /// Sorts `elems` in place, stably, according to `cmp` on them as `AlgebraicValue`s.
+    fn sort_as_values(elems: &mut Vec<Self>, cmp: impl Fn(&AlgebraicValue, &AlgebraicValue) -> Ordering);
+}
+
+/// Implements [`SortAsValues`] for scalars, passing copies of them to the comparator.
+macro_rules! sort_scalars_as_values {
+    ($($ty:ty => $variant:ident),*) => {
+        $(impl SortAsValues for $ty {
+            fn sort_as_values(elems: &mut Vec<Self>, cmp: impl Fn(&AlgebraicValue, &AlgebraicValue) -> Ordering) {
+                elems.sort_by(|a, b| cmp(&AlgebraicValue::$variant(*a), &AlgebraicValue::$variant(*b)))
+            }
+        })*
+    };
+}
+
+sort_scalars_as_values! {
+    bool => Bool, i8 => I8, u8 => U8, i16 => I16, u16 => U16, i32 => I32, u32 => U32,
+    i64 => I64, u64 => U64, i128 => I128, u128 => U128, F32 => F32, F64 => F64
+}
+
+/// Implements [`SortAsValues`] for elements owning heap data,
+/// moving them into `AlgebraicValue`s with `$wrap` for the sort and back out with `$unwrap`.
+macro_rules! sort_owned_as_values {
+    ($($ty:ty => $wrap:expr, $unwrap:expr;)*) => {
+        $(impl SortAsValues for $ty {
+            fn sort_as_values(elems: &mut Vec<Self>, cmp: impl Fn(&AlgebraicValue, &AlgebraicValue) -> Ordering) {
+                if elems.len() <= 1 {
+                    return;
+                }
+                let mut values = mem::take(elems).into_iter().map($wrap).collect_vec();
+                values.sort_by(cmp);
+                // The values were all wrapped the same way, so they all unwrap.
+                *elems = values.into_iter().filter_map(|v| $unwrap(v).ok()).collect();
+            }
+        })*
+    };
+}
+
+sort_owned_as_values! {
+    SumValue => AlgebraicValue::Sum, AlgebraicValue::into_sum;
+    ProductValue => AlgebraicValue::Product, AlgebraicValue::into_product;
+    String => AlgebraicValue::from, AlgebraicValue::into_string;
+    ArrayValue => AlgebraicValue::Array, AlgebraicValue::into_array;
+    MapValue => AlgebraicValue::Map, AlgebraicValue::into_map;
+}
+
+/// Returns the field at the path of field indices `path`, which must not be empty, into `row`.
+fn field_at_path<'a>(row: &'a ProductValue, path: &[usize]) -> Result<&'a AlgebraicValue, InvalidFieldError> {
+    let (&first, rest) = path.split_first().expect("the path is not empty");
+    rest.iter().try_fold(row.get_field(first, None)?, |field, &index| {
+        field
+            .as_product()
+            .ok_or(InvalidFieldError {
+                col_pos: index,
+                name: None,
+            })?
+            .get_field(index, None)
+    })
+}
+
+/// An error that occurs when slicing or concatenating arrays.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArrayOpError {
+    /// The range is not within the array.
+    #[error("Range {start}..{end} out of bounds for an array of {len} elements")]
+    OutOfRange { start: usize, end: usize, len: usize },
+    /// The arrays to concatenate have different kinds of elements.
+    #[error("Cannot concatenate an array of {left} elements with an array of {right} elements")]
+    KindMismatch { left: &'static str, right: &'static str },
+}
+
+/// An error that occurs when making an array of values that are not all of the same kind.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Element {index} is a {found} value, but the array is of {expected} elements")]
+pub struct MixedElementsError {
+    /// The index of the first value not of the kind of the array.
+    pub index: usize,
+    /// The kind of elements of the array.
+    pub expected: &'static str,
+    /// The kind of the value at `index`.
+    pub found: &'static str,
+}
+
+impl ArrayValue {
+    /// Returns the position of the kind of elements of the array among the kinds,
+    /// the same for both representations of string arrays,
+    /// so that arrays of different kinds are ordered by their kind.
+    fn kind_rank(&self) -> u8 {
+        match self {
+            ArrayValue::Sum(_) => 0,
+            ArrayValue::Product(_) => 1,
+            ArrayValue::Bool(_) => 2,
+            ArrayValue::I8(_) => 3,
+            ArrayValue::U8(_) => 4,
+            ArrayValue::I16(_) => 5,
+            ArrayValue::U16(_) => 6,
+            ArrayValue::I32(_) => 7,
+            ArrayValue::U32(_) => 8,
+            ArrayValue::I64(_) => 9,
+            ArrayValue::U64(_) => 10,
+            ArrayValue::I128(_) => 11,
+            ArrayValue::U128(_) => 12,
+            ArrayValue::F32(_) => 13,
+            ArrayValue::F64(_) => 14,
+            ArrayValue::String(_) | ArrayValue::StringPacked(_) => 15,
+            ArrayValue::Array(_) => 16,
+            ArrayValue::Map(_) => 17,
+        }
+    }
+}
+
+/// Evaluates `$body` with `$a` and `$b` bound to the elements of the arrays `$arrs`, a pair,
+/// if they are of the same kind and representation, or `$otherwise` if not.
+macro_rules! on_same_elements {
+    ($arrs:expr, ($a:ident, $b:ident) => $body:expr, _ => $otherwise:expr) => {
+        match $arrs {
+            (ArrayValue::Sum($a), ArrayValue::Sum($b)) => $body,
+            (ArrayValue::Product($a), ArrayValue::Product($b)) => $body,
+            (ArrayValue::Bool($a), ArrayValue::Bool($b)) => $body,
+            (ArrayValue::I8($a), ArrayValue::I8($b)) => $body,
+            (ArrayValue::U8($a), ArrayValue::U8($b)) => $body,
+            (ArrayValue::I16($a), ArrayValue::I16($b)) => $body,
+            (ArrayValue::U16($a), ArrayValue::U16($b)) => $body,
+            (ArrayValue::I32($a), ArrayValue::I32($b)) => $body,
+            (ArrayValue::U32($a), ArrayValue::U32($b)) => $body,
+            (ArrayValue::I64($a), ArrayValue::I64($b)) => $body,
+            (ArrayValue::U64($a), ArrayValue::U64($b)) => $body,
+            (ArrayValue::I128($a), ArrayValue::I128($b)) => $body,
+            (ArrayValue::U128($a), ArrayValue::U128($b)) => $body,
+            (ArrayValue::F32($a), ArrayValue::F32($b)) => $body,
+            (ArrayValue::F64($a), ArrayValue::F64($b)) => $body,
+            (ArrayValue::String($a), ArrayValue::String($b)) => $body,
+            (ArrayValue::StringPacked($a), ArrayValue::StringPacked($b)) => $body,
+            (ArrayValue::Array($a), ArrayValue::Array($b)) => $body,
+            (ArrayValue::Map($a), ArrayValue::Map($b)) => $body,
+            _ => $otherwise,
+        }
+    };
+}
+
+impl PartialEq for ArrayValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ArrayValue::String(a), ArrayValue::StringPacked(b))
+            | (ArrayValue::StringPacked(b), ArrayValue::String(a)) => **b == **a,
+            arrs => on_same_elements!(arrs, (a, b) => a == b, _ => false),
+        }
+    }
+}
+
+impl Eq for ArrayValue {}
+
+impl PartialOrd for ArrayValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArrayValue {
+    /// Orders arrays of the same kind of elements by their elements, lexicographically,
+    /// and arrays of different kinds by the order of the kinds, as listed in [`ArrayValue`].
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (ArrayValue::String(a), ArrayValue::StringPacked(b)) => a.iter().map(String::as_str).cmp(b.iter()),
+            (ArrayValue::StringPacked(a), ArrayValue::String(b)) => a.iter().cmp(b.iter().map(String::as_str)),
+            arrs => on_same_elements!(arrs, (a, b) => a.cmp(b), _ => self.kind_rank().cmp(&other.kind_rank())),
+        }
+    }
+}
+
+impl Hash for ArrayValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind_rank().hash(state);
+        on_elements!(self, v => v.hash(state), p => p.hash(state))
+    }
 }
 
 impl Default for ArrayValue {
@@ -351,6 +928,12 @@ impl_from_array!(String, String);
 impl_from_array!(ArrayValue, Array);
 impl_from_array!(MapValue, Map);
 
+impl From<PackedStrings> for ArrayValue {
+    fn from(v: PackedStrings) -> Self {
+        ArrayValue::StringPacked(Box::new(v))
+    }
+}
+
 impl<T: Clone> From<NonEmpty<T>> for ArrayValue
 where
     ArrayValue: From<Vec<T>>,
@@ -381,6 +964,7 @@ impl ArrayValue {
             Self::F32(v) => v,
             Self::F64(v) => v,
             Self::String(v) => v,
+            Self::StringPacked(v) => v,
             Self::Array(v) => v,
             Self::Map(v) => v,
         }
@@ -415,6 +999,7 @@ impl IntoIterator for ArrayValue {
             ArrayValue::F32(v) => ArrayValueIntoIter::F32(v.into_iter()),
             ArrayValue::F64(v) => ArrayValueIntoIter::F64(v.into_iter()),
             ArrayValue::String(v) => ArrayValueIntoIter::String(v.into_iter()),
+            ArrayValue::StringPacked(v) => ArrayValueIntoIter::StringPacked((*v).into_iter()),
             ArrayValue::Array(v) => ArrayValueIntoIter::Array(v.into_iter()),
             ArrayValue::Map(v) => ArrayValueIntoIter::Map(v.into_iter()),
         }
@@ -455,6 +1040,8 @@ pub enum ArrayValueIntoIter {
     F64(std::vec::IntoIter<F64>),
     /// An iterator on an array of UTF-8 strings.
     String(std::vec::IntoIter<String>),
+    /// An iterator on an array of packed UTF-8 strings.
+    StringPacked(packed_strings::IntoIter),
     /// An iterator on an array of arrays.
     Array(std::vec::IntoIter<ArrayValue>),
     /// An iterator on an array of maps.
@@ -482,6 +1069,7 @@ impl Iterator for ArrayValueIntoIter {
             ArrayValueIntoIter::F32(it) => it.next().map(|f| f32::from(f).into()),
             ArrayValueIntoIter::F64(it) => it.next().map(|f| f64::from(f).into()),
             ArrayValueIntoIter::String(it) => it.next().map(Into::into),
+            ArrayValueIntoIter::StringPacked(it) => it.next().map(Into::into),
             ArrayValueIntoIter::Array(it) => it.next().map(AlgebraicValue::ArrayOf),
             ArrayValueIntoIter::Map(it) => it.next().map(AlgebraicValue::map),
         }
@@ -505,6 +1093,7 @@ pub enum ArrayValueIterCloned<'a> {
     F32(std::slice::Iter<'a, F32>),
     F64(std::slice::Iter<'a, F64>),
     String(std::slice::Iter<'a, String>),
+    StringPacked(packed_strings::PackedStringsIter<'a>),
     Array(std::slice::Iter<'a, ArrayValue>),
     Map(std::slice::Iter<'a, MapValue>),
 }
@@ -530,8 +1119,511 @@ impl Iterator for ArrayValueIterCloned<'_> {
             ArrayValueIterCloned::F32(it) => it.next().map(|f| f32::from(*f).into()),
             ArrayValueIterCloned::F64(it) => it.next().map(|f| f64::from(*f).into()),
             ArrayValueIterCloned::String(it) => it.next().cloned().map(Into::into),
+            ArrayValueIterCloned::StringPacked(it) => it.next().map(Into::into),
             ArrayValueIterCloned::Array(it) => it.next().cloned().map(AlgebraicValue::ArrayOf),
             ArrayValueIterCloned::Map(it) => it.next().cloned().map(AlgebraicValue::map),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ArrayOpError, FloatExt, Infinite, MixedElementsError, Nan, PackedStrings, F32, F64};
+    use crate::de::DeserializeSeed;
+    use crate::{
+        bsatn, product, AlgebraicType, ArrayType, ArrayValue, ProductTypeElement, ProductValue, Typespace,
+        WithTypespace,
+    };
+    use crate::{AlgebraicTypeRef, AlgebraicValue, MapValue, SumValue};
+    use itertools::Itertools;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::mem;
+
+    fn hash_of(x: F64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        let (a, b) = (F64::from(1.5), F64::from(-4.0));
+        assert_eq!(a + b, F64::from(-2.5));
+        assert_eq!(a - b, F64::from(5.5));
+        assert_eq!(a * b, F64::from(-6.0));
+        assert_eq!(b / a, F64::from(-4.0 / 1.5));
+        assert_eq!(-a, F64::from(-1.5));
+        assert_eq!([a, b, a].into_iter().sum::<F64>(), F64::from(-1.0));
+        assert_eq!([a, b].into_iter().product::<F64>(), F64::from(-6.0));
+        assert_eq!(b.abs(), F64::from(4.0));
+        assert_eq!(a.abs(), a);
+        assert_eq!(F32::from(2.0) * F32::from(0.25), F32::from(0.5));
+
+        // Infinities and `NaN`s come out as they would for primitive floats.
+        let inf = F64::from(f64::INFINITY);
+        assert!(!inf.is_finite() && !inf.is_nan());
+        assert_eq!(a / F64::from(0.0), inf);
+        assert_eq!(a / F64::from(-0.0), -inf);
+        assert!((inf - inf).is_nan());
+        assert!((F64::from(0.0) / F64::from(0.0)).is_nan());
+        assert!(a.is_finite());
+    }
+
+    #[test]
+    fn float_display_and_parse() {
+        for x in [0.1, -0.0, 1e300, f64::MIN_POSITIVE, f64::INFINITY, f64::NEG_INFINITY] {
+            let x = F64::from(x);
+            let parsed: F64 = x.to_string().parse().unwrap();
+            assert_eq!(parsed, x);
+            assert_eq!(f64::from(parsed).to_bits(), f64::from(x).to_bits());
+        }
+        assert!("NaN".parse::<F64>().unwrap().is_nan());
+        assert_eq!(F32::from(0.1f32).to_string(), "0.1");
+        assert!("one".parse::<F32>().is_err());
+    }
+
+    #[test]
+    fn float_total_order() {
+        let nan = F64::from(f64::NAN);
+        let other_nan = F64::from(-f64::NAN);
+        let (zero, neg_zero) = (F64::from(0.0), F64::from(-0.0));
+        let inf = F64::from(f64::INFINITY);
+
+        // `NaN`s are equal to each other and greater than everything else.
+        assert_eq!(nan, other_nan);
+        assert_eq!(hash_of(nan), hash_of(other_nan));
+        assert!(nan > inf);
+        assert_eq!(nan.max(inf), nan);
+        assert_eq!(nan.min(inf), inf);
+
+        // Signed zeros are equal.
+        assert_eq!(zero, neg_zero);
+        assert_eq!(hash_of(zero), hash_of(neg_zero));
+
+        // Of equal signed zeros, `min` returns the first and `max` the second.
+        let bits = |x: F64| f64::from(x).to_bits();
+        assert_eq!(bits(zero.min(neg_zero)), bits(zero));
+        assert_eq!(bits(zero.max(neg_zero)), bits(neg_zero));
+
+        let mut xs = [nan, F64::from(1.0), -inf, zero, inf, F64::from(-1.0)];
+        xs.sort();
+        let expected = [-inf, F64::from(-1.0), zero, F64::from(1.0), inf];
+        assert_eq!(xs[..5], expected);
+        assert!(xs[5].is_nan());
+    }
+
+    #[test]
+    fn float_abs() {
+        let bits = |x: F32| f32::from(x).to_bits();
+        assert_eq!(bits(F32::from(-0.0).abs()), bits(F32::from(0.0)));
+        assert_eq!(F32::from(f32::NEG_INFINITY).abs(), F32::from(f32::INFINITY));
+        assert!(F32::from(-f32::NAN).abs().is_nan());
+        assert!(f32::from(F32::from(-f32::NAN).abs()).is_sign_positive());
+        assert_eq!(F64::from(-2.5).abs(), F64::from(2.5));
+    }
+
+    #[cfg(feature = "ordered-float")]
+    #[test]
+    fn float_ordered_float_interop() {
+        use ordered_float::OrderedFloat;
+
+        let xs = [f64::NAN, -f64::NAN, f64::INFINITY, -0.0, 0.0, -1.5, f64::MIN_POSITIVE];
+        for x in xs {
+            let total = F64::from(x);
+            let ordered = total.to_ordered_float();
+            assert_eq!(ordered.0.to_bits(), x.to_bits());
+            assert_eq!(f64::from(F64::from_ordered_float(ordered)).to_bits(), x.to_bits());
+            // The orderings agree, including on `NaN`s and signed zeros.
+            for y in xs {
+                assert_eq!(total.cmp(&F64::from(y)), ordered.cmp(&OrderedFloat(y)), "{x} {y}");
+            }
+        }
+        assert_eq!(F32::from(0.5).to_ordered_float(), OrderedFloat(0.5f32));
+        assert_eq!(F32::from_ordered_float(OrderedFloat(-0.0)), F32::from(0.0));
+    }
+
+    #[test]
+    fn sort_products_by_second_field() {
+        let row_ty = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::U32, "score"),
+        ]);
+        let mut arr = ArrayValue::from(vec![
+            product!["c".to_owned(), 7u32],
+            product!["a".to_owned(), 9u32],
+            product!["b".to_owned(), 1u32],
+        ]);
+        let second = |p: &crate::AlgebraicValue| p.as_product().unwrap().elements[1].clone();
+        arr.sort_in_place(|a, b| second(a).cmp(&second(b)));
+        let expected: Vec<ProductValue> = vec![
+            product!["b".to_owned(), 1u32],
+            product!["c".to_owned(), 7u32],
+            product!["a".to_owned(), 9u32],
+        ];
+        assert_eq!(arr, ArrayValue::from(expected));
+
+        // Sorting by the whole schema orders by the first field instead.
+        arr.sort_by_schema(&row_ty, &Typespace::default());
+        let expected: Vec<ProductValue> = vec![
+            product!["a".to_owned(), 9u32],
+            product!["b".to_owned(), 1u32],
+            product!["c".to_owned(), 7u32],
+        ];
+        assert_eq!(arr, ArrayValue::from(expected));
+    }
+
+    #[test]
+    fn sort_u8_numerically() {
+        let mut arr = ArrayValue::from(vec![200u8, 3, 100, 20, 3]);
+        arr.sort_by_schema(&AlgebraicType::U8, &Typespace::default());
+        assert_eq!(arr, ArrayValue::U8(vec![3, 3, 20, 100, 200]));
+    }
+
+    #[test]
+    fn sort_through_type_ref() {
+        let mut ts = Typespace::default();
+        let r = ts.add(AlgebraicType::I32);
+        let mut arr = ArrayValue::from(vec![5i32, -1, 0]);
+        arr.sort_by_schema(&AlgebraicType::Ref(r), &ts);
+        assert_eq!(arr, ArrayValue::I32(vec![-1, 0, 5]));
+    }
+
+    #[test]
+    fn sort_each_kind_of_element() {
+        let mut arrays = [
+            ArrayValue::from(vec![true, false, true]),
+            ArrayValue::from(vec![3i8, -1, 2]),
+            ArrayValue::from(vec![3u8, 1, 2]),
+            ArrayValue::from(vec![3i16, -1, 2]),
+            ArrayValue::from(vec![3u16, 1, 2]),
+            ArrayValue::from(vec![3i32, -1, 2]),
+            ArrayValue::from(vec![3u32, 1, 2]),
+            ArrayValue::from(vec![3i64, -1, 2]),
+            ArrayValue::from(vec![3u64, 1, 2]),
+            ArrayValue::from(vec![3i128, -1, 2]),
+            ArrayValue::from(vec![3u128, 1, 2]),
+            ArrayValue::from(vec![F32::from(3.0), F32::from(-0.5), F32::from(f32::NAN)]),
+            ArrayValue::from(vec![F64::from(f64::NAN), F64::from(-0.5), F64::from(2.0)]),
+            ArrayValue::from(vec!["b".to_owned(), "a".to_owned(), "B".to_owned()]),
+            ArrayValue::from(vec![ArrayValue::from(vec![2u8]), ArrayValue::from(vec![1u8, 9])]),
+        ];
+        for arr in &mut arrays {
+            let mut expected = arr.clone().into_iter().collect::<Vec<_>>();
+            expected.sort();
+            arr.sort();
+            assert!(arr.iter_cloned().eq(expected), "{arr:?}");
+        }
+        assert_eq!(arrays[5], ArrayValue::I32(vec![-1, 2, 3]));
+    }
+
+    #[test]
+    fn sort_in_place_keeps_the_representation() {
+        let descending = |a: &crate::AlgebraicValue, b: &crate::AlgebraicValue| b.cmp(a);
+        let mut numbers = ArrayValue::from(vec![3u32, 1, 2]);
+        numbers.sort_in_place(descending);
+        assert_eq!(numbers, ArrayValue::U32(vec![3, 2, 1]));
+
+        let mut strings = ArrayValue::from(vec!["b".to_owned(), "c".to_owned(), "a".to_owned()]);
+        strings.sort_in_place(descending);
+        assert_eq!(
+            strings,
+            ArrayValue::from(vec!["c".to_owned(), "b".to_owned(), "a".to_owned()])
+        );
+
+        let mut packed = ArrayValue::from(PackedStrings::from_iter(["b", "c", "a"]));
+        packed.sort_in_place(descending);
+        assert!(matches!(packed, ArrayValue::StringPacked(_)), "{packed:?}");
+        assert!(packed
+            .iter_cloned()
+            .eq(["c", "b", "a"].map(crate::AlgebraicValue::from)));
+    }
+
+    #[test]
+    fn sort_products_by_key_path() {
+        let row = |name: &str, score: u32, rank: u8| product![name.to_owned(), product![score, rank]];
+        let mut arr = ArrayValue::from(vec![row("c", 7, 2), row("a", 9, 1), row("b", 7, 3), row("d", 1, 3)]);
+        arr.sort_by_key_path(&[1, 0]).unwrap();
+        // Rows with equal scores keep their order.
+        let expected = vec![row("d", 1, 3), row("c", 7, 2), row("b", 7, 3), row("a", 9, 1)];
+        assert_eq!(arr, ArrayValue::from(expected));
+        arr.sort_by_key_path(&[1, 1]).unwrap();
+        let expected = vec![row("a", 9, 1), row("c", 7, 2), row("d", 1, 3), row("b", 7, 3)];
+        assert_eq!(arr, ArrayValue::from(expected.clone()));
+
+        // A path missing from a row is an error, and leaves the array as it was.
+        assert_eq!(arr.sort_by_key_path(&[2]).unwrap_err().col_pos, 2);
+        assert_eq!(arr.sort_by_key_path(&[0, 0]).unwrap_err().col_pos, 0);
+        assert_eq!(arr, ArrayValue::from(expected));
+        let mut numbers = ArrayValue::from(vec![2u8, 1]);
+        assert!(numbers.sort_by_key_path(&[0]).is_err());
+        numbers.sort_by_key_path(&[]).unwrap();
+        assert_eq!(numbers, ArrayValue::U8(vec![1, 2]));
+    }
+
+    #[test]
+    fn dedup_removes_adjacent_duplicates() {
+        let strings = |s: &[&str]| ArrayValue::from(s.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        let mut arr = strings(&["a", "a", "b", "c", "c", "c", "a"]);
+        arr.dedup();
+        assert_eq!(arr, strings(&["a", "b", "c", "a"]));
+        arr.sort();
+        arr.dedup();
+        assert_eq!(arr, strings(&["a", "b", "c"]));
+    }
+
+    /// Returns an array of four distinct elements of each kind.
+    fn arrays_of_each_kind() -> Vec<ArrayValue> {
+        let strings = |s: [&str; 4]| s.map(str::to_owned).to_vec();
+        let map = |k: u8| MapValue::from([(AlgebraicValue::U8(k), AlgebraicValue::Bool(k % 2 == 0))]);
+        vec![
+            (0..4)
+                .map(|i| SumValue {
+                    tag: i,
+                    value: Box::new(AlgebraicValue::U8(i)),
+                })
+                .collect_vec()
+                .into(),
+            (0..4u32).map(|i| product![i, format!("row {i}")]).collect_vec().into(),
+            vec![true, false, false, true].into(),
+            vec![1i8, -2, 3, -4].into(),
+            vec![1u8, 2, 3, 4].into(),
+            vec![1i16, -2, 3, -4].into(),
+            vec![1u16, 2, 3, 4].into(),
+            vec![1i32, -2, 3, -4].into(),
+            vec![1u32, 2, 3, 4].into(),
+            vec![1i64, -2, 3, -4].into(),
+            vec![1u64, 2, 3, 4].into(),
+            vec![1i128, -2, 3, -4].into(),
+            vec![1u128, 2, 3, 4].into(),
+            [1.0, 2.5, -3.0, 4.0].map(F32::from).to_vec().into(),
+            [1.0, 2.5, -3.0, 4.0].map(F64::from).to_vec().into(),
+            strings(["a", "b", "c", "d"]).into(),
+            vec![
+                ArrayValue::from(vec![1u8]),
+                ArrayValue::from(strings(["x", "y", "z", "w"])),
+                ArrayValue::from(Vec::<u8>::new()),
+                ArrayValue::from(vec![ArrayValue::from(vec![2u8])]),
+            ]
+            .into(),
+            (0..4).map(map).collect_vec().into(),
+        ]
+    }
+
+    #[test]
+    fn slices_keep_the_kind_of_elements() {
+        for arr in arrays_of_each_kind() {
+            let elems = arr.iter_cloned().collect_vec();
+            let middle = arr.slice(1..3).unwrap();
+            assert_eq!(mem::discriminant(&middle), mem::discriminant(&arr));
+            assert!(middle.iter_cloned().eq(elems[1..3].iter().cloned()), "{arr:?}");
+            let empty = arr.slice(4..4).unwrap();
+            assert_eq!(mem::discriminant(&empty), mem::discriminant(&arr));
+            assert!(empty.is_empty());
+            assert_eq!(arr.slice(0..4).unwrap(), arr);
+
+            // The two halves concatenate back into the whole.
+            let halves = (arr.slice(0..2).unwrap(), arr.slice(2..4).unwrap());
+            assert_eq!(ArrayValue::concat(halves.0, halves.1).unwrap(), arr);
+
+            let mut drained = arr.clone();
+            assert_eq!(drained.drain_range(1..3).unwrap(), middle);
+            assert!(drained.iter_cloned().eq([elems[0].clone(), elems[3].clone()]));
+            let mut truncated = arr.clone();
+            truncated.truncate(1);
+            assert_eq!(truncated, arr.slice(0..1).unwrap());
+            truncated.truncate(5);
+            assert_eq!(truncated.len(), 1);
+        }
+    }
+
+    #[test]
+    fn out_of_range_slices_are_errors() {
+        let mut arr = ArrayValue::from(vec![1u16, 2, 3]);
+        let err = ArrayOpError::OutOfRange {
+            start: 2,
+            end: 4,
+            len: 3,
+        };
+        assert_eq!(arr.slice(2..4), Err(err.clone()));
+        assert_eq!(arr.drain_range(2..4), Err(err));
+        #[allow(clippy::reversed_empty_ranges)]
+        let backwards = 2..1;
+        assert!(arr.slice(backwards).is_err());
+        assert_eq!(arr, ArrayValue::from(vec![1u16, 2, 3]));
+        assert_eq!(arr.slice(3..3).unwrap(), ArrayValue::U16(Vec::new()));
+    }
+
+    #[test]
+    fn concat_with_typed_empty_arrays() {
+        let numbers = ArrayValue::from(vec![1u32, 2]);
+        // An empty array of another kind takes the kind of the other array, on either side.
+        let empty_strings = ArrayValue::from(Vec::<String>::new());
+        assert_eq!(
+            ArrayValue::concat(empty_strings.clone(), numbers.clone()).unwrap(),
+            numbers
+        );
+        assert_eq!(
+            ArrayValue::concat(numbers.clone(), ArrayValue::default()).unwrap(),
+            numbers
+        );
+        let both_empty = ArrayValue::concat(ArrayValue::U32(Vec::new()), ArrayValue::U32(Vec::new())).unwrap();
+        assert_eq!(both_empty, ArrayValue::U32(Vec::new()));
+
+        let err = ArrayValue::concat(numbers, ArrayValue::from(vec!["a".to_owned()])).unwrap_err();
+        assert_eq!(
+            err,
+            ArrayOpError::KindMismatch {
+                left: "U32",
+                right: "String"
+            }
+        );
+        let nested = ArrayValue::from(vec![ArrayValue::from(vec![1u8])]);
+        let maps = ArrayValue::from(vec![MapValue::new()]);
+        assert!(ArrayValue::concat(nested, maps).is_err());
+    }
+
+    #[test]
+    fn values_specialize_back_into_arrays() {
+        for arr in arrays_of_each_kind() {
+            // `type_of` infers products for sums and maps, so those types are given here.
+            let elem_ty = match &arr {
+                ArrayValue::Sum(_) => AlgebraicType::sum((0..4).map(|_| AlgebraicType::U8.into()).collect()),
+                ArrayValue::Map(_) => AlgebraicType::map(AlgebraicType::U8, AlgebraicType::Bool),
+                _ => *arr.type_of().elem_ty,
+            };
+            let vals = arr.to_values();
+            assert_eq!(vals, arr.clone().into_values());
+            assert_eq!(ArrayValue::try_from_values(vals.clone(), None).unwrap(), arr);
+            assert_eq!(ArrayValue::try_from_values(vals, Some(&elem_ty)).unwrap(), arr);
+
+            // An empty array only keeps its kind when told the type of its elements.
+            let empty = arr.slice(0..0).unwrap();
+            assert_eq!(
+                ArrayValue::try_from_values(empty.to_values(), Some(&elem_ty)).unwrap(),
+                empty
+            );
+        }
+        let untyped = ArrayValue::try_from_values(Vec::new(), None).unwrap();
+        assert_eq!(untyped, ArrayValue::default());
+        let through_ref = ArrayValue::try_from_values(
+            vec![AlgebraicValue::U8(1)],
+            Some(&AlgebraicType::Ref(AlgebraicTypeRef(0))),
+        );
+        assert_eq!(through_ref.unwrap(), ArrayValue::U8(vec![1]));
+    }
+
+    #[test]
+    fn mixed_values_are_errors() {
+        let vals = vec![
+            AlgebraicValue::U8(1),
+            AlgebraicValue::U8(2),
+            AlgebraicValue::I8(3),
+            AlgebraicValue::Bool(true),
+        ];
+        let err = ArrayValue::try_from_values(vals.clone(), None).unwrap_err();
+        let expected = MixedElementsError {
+            index: 2,
+            expected: "U8",
+            found: "I8",
+        };
+        assert_eq!(err, expected);
+
+        // The type of the elements, if given, decides the kind, even against the first value.
+        let err = ArrayValue::try_from_values(vals, Some(&AlgebraicType::I8)).unwrap_err();
+        assert_eq!((err.index, err.expected, err.found), (0, "I8", "U8"));
+    }
+
+    #[test]
+    fn packed_strings_are_interchangeable_with_strings() {
+        let hash = |arr: &ArrayValue| {
+            let mut hasher = DefaultHasher::new();
+            arr.hash(&mut hasher);
+            hasher.finish()
+        };
+        let strings = ["b", "", "a", "b"].map(String::from).to_vec();
+        let unpacked = ArrayValue::String(strings.clone());
+        let packed = ArrayValue::from(PackedStrings::from(&*strings));
+        assert_eq!(packed, unpacked);
+        assert_eq!(unpacked, packed);
+        assert_eq!(hash(&packed), hash(&unpacked));
+        let greater = ArrayValue::from(PackedStrings::from_iter(["b", "", "a", "c"]));
+        assert!(packed < greater);
+        assert!(greater > unpacked);
+        // Arrays of strings still sort between arrays of floats and arrays of arrays.
+        assert!(ArrayValue::F64(vec![]) < packed && packed < ArrayValue::Array(vec![]));
+        assert_ne!(packed, ArrayValue::Array(vec![]));
+
+        assert_eq!(packed.type_of(), unpacked.type_of());
+        assert_eq!(packed.to_values(), unpacked.to_values());
+        assert_eq!(packed.clone().into_values(), unpacked.to_values());
+        assert_eq!(format!("{packed:?}"), format!("{unpacked:?}"));
+        assert_eq!(bsatn::to_vec(&packed).unwrap(), bsatn::to_vec(&unpacked).unwrap());
+        let ts = Typespace::default();
+        let ty = ArrayType {
+            elem_ty: Box::new(AlgebraicType::String),
+        };
+        let typed = |arr: &ArrayValue| bsatn::to_vec(&WithTypespace::new(&ts, &ty).with_value(arr)).unwrap();
+        assert_eq!(typed(&packed), typed(&unpacked));
+    }
+
+    #[test]
+    fn array_ops_keep_strings_packed() {
+        let strings = ["c", "a", "b", "a", "a"].map(String::from).to_vec();
+        let packed = || ArrayValue::from(PackedStrings::from(&*strings));
+        let unpacked = ArrayValue::String(strings.clone());
+        let check = |op: &dyn Fn(&mut ArrayValue)| {
+            let (mut p, mut u) = (packed(), unpacked.clone());
+            op(&mut p);
+            op(&mut u);
+            assert!(matches!(p, ArrayValue::StringPacked(_)), "{p:?}");
+            assert_eq!(p, u);
+        };
+        check(&ArrayValue::sort);
+        check(&ArrayValue::dedup);
+        check(&|arr| {
+            arr.sort();
+            arr.dedup()
+        });
+        check(&|arr| arr.truncate(2));
+        check(&|arr| *arr = arr.slice(1..4).unwrap());
+        check(&|arr| drop(arr.drain_range(1..3).unwrap()));
+        check(&|arr| *arr = arr.drain_range(1..3).unwrap());
+        check(&|arr| arr.push(AlgebraicValue::from("d"), None).unwrap());
+        check(&|arr| arr.set(1, AlgebraicValue::from("longer")).unwrap());
+        check(&|arr| arr.set(4, AlgebraicValue::from("")).unwrap());
+        let mut p = packed();
+        assert_eq!(p.set(0, AlgebraicValue::U8(1)), Err(AlgebraicValue::U8(1)));
+
+        let concatenated = ArrayValue::concat(packed(), unpacked.clone()).unwrap();
+        assert!(matches!(concatenated, ArrayValue::StringPacked(_)));
+        assert_eq!(concatenated.len(), 10);
+        assert_eq!(ArrayValue::concat(unpacked.clone(), packed()).unwrap(), concatenated);
+        assert_eq!(ArrayValue::concat(packed(), packed()).unwrap(), concatenated);
+        assert_eq!(ArrayValue::concat(packed(), ArrayValue::U8(vec![])).unwrap(), unpacked);
+    }
+
+    #[test]
+    fn bsatn_decodes_into_packed_strings() {
+        let strings = ArrayValue::String(["x", "", "yz"].map(String::from).to_vec());
+        let row = product![1u8, AlgebraicValue::Array(strings.clone())];
+        let ty = AlgebraicType::product(vec![
+            ProductTypeElement::new(AlgebraicType::U8, None),
+            ProductTypeElement::new(AlgebraicType::array(AlgebraicType::String), None),
+        ]);
+        let bytes = bsatn::to_vec(&row).unwrap();
+        let ts = Typespace::default();
+        let decode = |packed_strings| {
+            let mut bytes = &*bytes;
+            let de = bsatn::Deserializer::new(&mut bytes).with_packed_strings(packed_strings);
+            WithTypespace::new(&ts, &ty).deserialize(de).unwrap()
+        };
+        let (packed, unpacked) = (decode(true), decode(false));
+        let arr = |val: &AlgebraicValue| val.as_product().unwrap().elements[1].as_array().unwrap().clone();
+        assert!(matches!(arr(&packed), ArrayValue::StringPacked(_)));
+        assert!(matches!(arr(&unpacked), ArrayValue::String(_)));
+        assert_eq!(packed, unpacked);
+        assert_eq!(arr(&packed), strings);
+        assert_eq!(bsatn::to_vec(&packed).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,345 @@
+//! Strings packed back to back in one buffer, for large string arrays,
+//! which take two allocations this way rather than one per string.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use crate::de::{ArrayAccess, ArrayVisitor, Error};
+use crate::ser::SerializeArray;
+use crate::{impl_deserialize, impl_serialize};
+
+/// An array of strings stored as the UTF-8 bytes of all of them back to back, and the offset of each string's end.
+///
+/// Getting a string by index is O(1), and pushing a string is amortized O(1) in its length.
+/// Equality, ordering and hashing are those of the strings, as for a `Vec<String>`,
+/// and so is the encoding, so a packed array is interchangeable on the wire with an unpacked one.
+///
+/// The total length of the strings is limited to `u32::MAX` bytes.
+#[derive(Clone, Default)]
+pub struct PackedStrings {
+    /// The bytes of all the strings, back to back.
+    data: String,
+    /// The offset in `data` of the end of each string, which is also where the next one starts.
+    ends: Vec<u32>,
+}
+
+impl PackedStrings {
+    /// Returns an empty array of strings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an empty array with room for `strings` strings of `bytes` bytes in total.
+    pub fn with_capacity(strings: usize, bytes: usize) -> Self {
+        Self {
+            data: String::with_capacity(bytes),
+            ends: Vec::with_capacity(strings),
+        }
+    }
+
+    /// Returns the number of strings.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns whether there are no strings.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the string at `index`, if any.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let end = *self.ends.get(index)? as usize;
+        Some(&self.data[self.start_of(index)..end])
+    }
+
+    /// Returns the offset in `data` where the string at `index` starts,
+    /// or, for `index == self.len()`, where the data ends.
+    fn start_of(&self, index: usize) -> usize {
+        index.checked_sub(1).map_or(0, |prev| self.ends[prev] as usize)
+    }
+
+    /// Appends the string `s`.
+    ///
+    /// Panics if the strings would then be longer than `u32::MAX` bytes in total.
+    pub fn push(&mut self, s: &str) {
+        self.data.push_str(s);
+        let end = u32::try_from(self.data.len()).expect("packed strings are longer than `u32::MAX` bytes");
+        self.ends.push(end);
+    }
+
+    /// Returns an iterator over the strings, in order.
+    pub fn iter(&self) -> PackedStringsIter<'_> {
+        PackedStringsIter {
+            strings: self,
+            start: 0,
+            index: 0,
+        }
+    }
+
+    /// Shortens the array to its first `len` strings.
+    ///
+    /// Does nothing if there are no more than `len` strings.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len() {
+            self.ends.truncate(len);
+            self.data.truncate(self.ends.last().map_or(0, |&end| end as usize));
+        }
+    }
+
+    /// Returns a copy of the strings in `range`, copying just their bytes.
+    ///
+    /// Panics if `range` is not within the array.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let ends = &self.ends[range.clone()];
+        let start = self.start_of(range.start);
+        Self {
+            data: self.data[start..self.start_of(range.end)].to_owned(),
+            ends: ends.iter().map(|&end| end - start as u32).collect(),
+        }
+    }
+
+    /// Removes the strings in `range` and returns them.
+    ///
+    /// Panics if `range` is not within the array.
+    pub fn drain(&mut self, range: Range<usize>) -> Self {
+        let drained = self.slice(range.clone());
+        let rest = self.slice(range.end..self.len());
+        self.truncate(range.start);
+        self.extend(&rest);
+        drained
+    }
+
+    /// Returns the strings, each in an allocation of its own.
+    pub fn to_strings(&self) -> Vec<String> {
+        self.iter().map(str::to_owned).collect()
+    }
+
+    /// Returns an estimate of the number of bytes `self` owns on the heap.
+    pub fn heap_size_bytes(&self) -> usize {
+        self.data.capacity() + self.ends.capacity() * std::mem::size_of::<u32>()
+    }
+}
+
+/// An iterator over the strings of [`PackedStrings`].
+#[derive(Clone)]
+pub struct PackedStringsIter<'a> {
+    /// The strings iterated over.
+    strings: &'a PackedStrings,
+    /// The offset in the data where the next string starts.
+    start: usize,
+    /// The index of the next string.
+    index: usize,
+}
+
+impl<'a> Iterator for PackedStringsIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let end = *self.strings.ends.get(self.index)? as usize;
+        let s = &self.strings.data[self.start..end];
+        self.start = end;
+        self.index += 1;
+        Some(s)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.strings.len() - self.index;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for PackedStringsIter<'_> {}
+
+impl<'a> IntoIterator for &'a PackedStrings {
+    type Item = &'a str;
+    type IntoIter = PackedStringsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An owning iterator over the strings of [`PackedStrings`], allocating each of them as it goes.
+pub struct IntoIter {
+    /// The strings iterated over.
+    strings: PackedStrings,
+    /// The index of the next string.
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let s = self.strings.get(self.index)?.to_owned();
+        self.index += 1;
+        Some(s)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.strings.len() - self.index;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for PackedStrings {
+    type Item = String;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            strings: self,
+            index: 0,
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for PackedStrings {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        let mut strings = Self::new();
+        strings.extend(iter);
+        strings
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for PackedStrings {
+    fn extend<T: IntoIterator<Item = S>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.ends.reserve(iter.size_hint().0);
+        iter.for_each(|s| self.push(s.as_ref()));
+    }
+}
+
+impl From<&[String]> for PackedStrings {
+    fn from(strings: &[String]) -> Self {
+        let mut packed = Self::with_capacity(strings.len(), strings.iter().map(String::len).sum());
+        packed.extend(strings);
+        packed
+    }
+}
+
+impl PartialEq for PackedStrings {
+    fn eq(&self, other: &Self) -> bool {
+        self.ends == other.ends && self.data == other.data
+    }
+}
+
+impl Eq for PackedStrings {}
+
+impl PartialEq<[String]> for PackedStrings {
+    fn eq(&self, other: &[String]) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter().map(String::as_str))
+    }
+}
+
+impl PartialOrd for PackedStrings {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PackedStrings {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl Hash for PackedStrings {
+    /// Hashes the same as a `[String]` of the same strings.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|s| s.hash(state));
+    }
+}
+
+impl fmt::Debug for PackedStrings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl_serialize!([] PackedStrings, (self, ser) => {
+    let mut arr = ser.serialize_array(self.len())?;
+    for s in self {
+        arr.serialize_element(s)?;
+    }
+    arr.end()
+});
+impl_deserialize!([] PackedStrings, de => de.deserialize_array(PackedStringsVisitor));
+
+/// A visitor packing the strings of an array, borrowing each from the input if it can.
+struct PackedStringsVisitor;
+
+impl<'de> ArrayVisitor<'de, Cow<'de, str>> for PackedStringsVisitor {
+    type Output = PackedStrings;
+
+    fn visit<A: ArrayAccess<'de, Element = Cow<'de, str>>>(self, mut arr: A) -> Result<Self::Output, A::Error> {
+        let mut strings = PackedStrings::with_capacity(arr.size_hint().unwrap_or(0), 0);
+        while let Some(s) = arr.next_element()? {
+            if strings.data.len() + s.len() > u32::MAX as usize {
+                return Err(A::Error::custom("packed strings are longer than `u32::MAX` bytes"));
+            }
+            strings.push(&s);
+        }
+        Ok(strings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash_of(x: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        x.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn get_iter_and_push() {
+        let mut strings = ["", "héllo", "", "world"].into_iter().collect::<PackedStrings>();
+        assert_eq!(strings.len(), 4);
+        assert_eq!(strings.get(1), Some("héllo"));
+        assert_eq!(strings.get(2), Some(""));
+        assert_eq!(strings.get(4), None);
+        strings.push("!");
+        assert!(strings.iter().eq(["", "héllo", "", "world", "!"]));
+        assert_eq!(strings.iter().len(), 5);
+        assert!(strings.slice(1..4).iter().eq(["héllo", "", "world"]));
+        assert_eq!(strings.slice(2..2), PackedStrings::new());
+        let mut drained = strings.clone();
+        assert!(drained.drain(1..3).iter().eq(["héllo", ""]));
+        assert!(drained.into_iter().eq(["", "world", "!"]));
+        strings.truncate(2);
+        assert_eq!(strings.to_strings(), ["", "héllo"]);
+        strings.push("again");
+        assert_eq!(strings.get(2), Some("again"));
+        assert_eq!(format!("{strings:?}"), r#"["", "héllo", "again"]"#);
+    }
+
+    #[test]
+    fn behaves_like_vec_of_strings() {
+        let vec = ["b", "a", "c"].map(String::from).to_vec();
+        let packed = PackedStrings::from(&*vec);
+        assert_eq!(packed, *vec);
+        assert_eq!(hash_of(&packed), hash_of(&vec));
+        let (less, more) = (PackedStrings::from_iter(["a", "b"]), PackedStrings::from_iter(["ab"]));
+        assert_eq!(less.cmp(&more), ["a", "b"][..].cmp(&["ab"][..]));
+        assert_ne!(less, more);
+
+        let bytes = bsatn::to_vec(&packed).unwrap();
+        assert_eq!(bytes, bsatn::to_vec(&vec).unwrap());
+        assert_eq!(bsatn::from_slice::<PackedStrings>(&bytes).unwrap(), packed);
+        // An invalid string is an error, as for a `Vec<String>`.
+        let invalid = bsatn::to_vec(&vec![vec![0xffu8]]).unwrap();
+        assert!(bsatn::from_slice::<PackedStrings>(&invalid).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+//! Text encodings for binary data in SATS values.
+
+#[cfg(feature = "base64")]
+pub mod base64;
+
+/// How byte arrays are written in text formats, e.g., by
+/// [`SerdeSerializer::bytes_format`](crate::ser::serde::SerdeSerializer::bytes_format).
+///
+/// This is chosen per serializer rather than per build,
+/// so that enabling a feature in one crate doesn't change the encoding for every other crate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// Lowercase hex, two digits per byte.
+    #[default]
+    Hex,
+    /// Padded Base64 of the standard alphabet.
+    #[cfg(feature = "base64")]
+    Base64,
+}
@@ -0,0 +1,76 @@
+//! Base64 encoding of byte arrays, i.e., `ArrayValue::U8`, for text contexts such as JSON or CSV.
+//!
+//! The standard alphabet with `=` padding is used.
+
+use crate::buffer::{DecodeError, ErrorKind};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+
+/// Encodes the bytes `v` as a padded Base64 string.
+pub fn array_value_to_base64(v: &[u8]) -> String {
+    STANDARD.encode(v)
+}
+
+/// Decodes the padded Base64 string `s` into bytes.
+///
+/// Fails with `ErrorKind::Custom` when `s` is not valid Base64.
+pub fn base64_to_array_value(s: &str) -> Result<Vec<u8>, DecodeError> {
+    STANDARD
+        .decode(s)
+        .map_err(|e| ErrorKind::Custom(format!("invalid base64: {e}")).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::AlgebraicValue;
+
+    #[test]
+    fn round_trip_all_bytes() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        let encoded = array_value_to_base64(&bytes);
+        assert_eq!(base64_to_array_value(&encoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn padding() {
+        assert_eq!(array_value_to_base64(b""), "");
+        assert_eq!(array_value_to_base64(b"a"), "YQ==");
+        assert_eq!(array_value_to_base64(b"ab"), "YWI=");
+        assert_eq!(array_value_to_base64(b"abc"), "YWJj");
+
+        assert_eq!(base64_to_array_value("YQ==").unwrap(), b"a");
+        assert_eq!(base64_to_array_value("YWI=").unwrap(), b"ab");
+        assert!(base64_to_array_value("YQ").is_err());
+        assert!(base64_to_array_value("YQ=").is_err());
+        assert!(base64_to_array_value("not base64!").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_bytes_are_base64_on_request() {
+        use crate::codec::BytesFormat;
+        use crate::de::serde::SerdeDeserializer;
+        use crate::de::Deserialize;
+        use crate::ser::serde::WithBytesFormat;
+        use crate::ArrayValue;
+
+        let bytes = AlgebraicValue::Array(ArrayValue::U8(b"ab".to_vec()));
+        let nested = AlgebraicValue::product(vec![bytes.clone()]);
+
+        // Enabling the feature doesn't change the default, which stays hex.
+        assert_eq!(serde_json::to_string(&bytes).unwrap(), r#""6162""#);
+        let json = serde_json::to_string(&WithBytesFormat::new(&bytes, BytesFormat::Base64)).unwrap();
+        assert_eq!(json, r#""YWI=""#);
+        let json = serde_json::to_string(&WithBytesFormat::new(&nested, BytesFormat::Base64)).unwrap();
+        assert!(json.contains(r#""YWI=""#), "{json}");
+
+        let mut de = serde_json::Deserializer::from_str(r#""YWI=""#);
+        let de = SerdeDeserializer::new(&mut de).bytes_format(BytesFormat::Base64);
+        assert_eq!(Vec::<u8>::deserialize(de).map_err(|e| e.0).unwrap(), b"ab");
+        let mut de = serde_json::Deserializer::from_str(r#""6162""#);
+        let de = SerdeDeserializer::new(&mut de);
+        assert_eq!(Vec::<u8>::deserialize(de).map_err(|e| e.0).unwrap(), b"ab");
+    }
+}
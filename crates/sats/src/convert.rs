@@ -1,7 +1,6 @@
 use crate::algebraic_type::AlgebraicType;
 use crate::algebraic_value::AlgebraicValue;
 use crate::builtin_type::BuiltinType;
-use crate::builtin_value::BuiltinValue;
 use crate::{ProductType, ProductTypeElement, ProductValue};
 
 impl From<BuiltinType> for AlgebraicType {
@@ -10,12 +9,6 @@ impl From<BuiltinType> for AlgebraicType {
     }
 }
 
-impl From<BuiltinValue> for AlgebraicValue {
-    fn from(value: BuiltinValue) -> Self {
-        AlgebraicValue::Builtin(value)
-    }
-}
-
 impl crate::Value for AlgebraicValue {
     type Type = AlgebraicType;
 }
@@ -52,15 +45,9 @@ impl From<BuiltinType> for ProductTypeElement {
 
 macro_rules! built_in {
     ($native:ty, $kind:ident) => {
-        impl From<$native> for BuiltinValue {
-            fn from(x: $native) -> Self {
-                BuiltinValue::$kind(x)
-            }
-        }
-
         impl From<$native> for AlgebraicValue {
             fn from(x: $native) -> Self {
-                AlgebraicValue::Builtin(x.into())
+                AlgebraicValue::$kind(x)
             }
         }
     };
@@ -68,15 +55,9 @@ macro_rules! built_in {
 
 macro_rules! built_in_into {
     ($native:ty, $kind:ident) => {
-        impl From<$native> for BuiltinValue {
-            fn from(x: $native) -> Self {
-                BuiltinValue::$kind(x.into())
-            }
-        }
-
         impl From<$native> for AlgebraicValue {
             fn from(x: $native) -> Self {
-                AlgebraicValue::Builtin(x.into())
+                AlgebraicValue::$kind(x.into())
             }
         }
     };
@@ -95,6 +76,7 @@ built_in!(i128, I128);
 built_in!(u128, U128);
 built_in_into!(f32, F32);
 built_in_into!(f64, F64);
-built_in!(String, String);
+built_in!(Box<str>, String);
+built_in_into!(String, String);
 built_in_into!(&str, String);
 built_in_into!(&[u8], Bytes);
@@ -1,18 +1,25 @@
 // Some parts copyright Serde developers under the MIT / Apache-2.0 licenses at your option.
 // See `serde` version `v1.0.169` for the parts where MIT / Apache-2.0 applies.
 
+pub mod error;
 mod impls;
+mod in_place;
 #[cfg(feature = "serde")]
 pub mod serde;
 
+#[cfg(feature = "bumpalo")]
+pub(crate) use impls::TupleNameVisitor;
 #[doc(hidden)]
 pub use impls::{visit_named_product, visit_seq_product};
+pub use in_place::ValueSeed;
 
 use std::borrow::Borrow;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::marker::PhantomData;
 
+use crate::ser::InvalidPath;
+
 /// A **data format** that can deserialize any data structure supported by SATS.
 ///
 /// The `Deserializer` trait in SATS performs the same function as [`serde::Deserializer`] in [`serde`].
@@ -162,6 +169,36 @@ pub trait Deserializer<'de>: Sized {
         kseed: K,
         vseed: V,
     ) -> Result<Vi::Output, Self::Error>;
+
+    /// Deserializes an array of strings, converting each of them with `from_str`.
+    ///
+    /// Used in the `Deserialize for Vec<T>` implementations of the string types
+    /// so that formats storing strings contiguously can validate and convert them straight out of the input.
+    #[doc(hidden)]
+    fn __deserialize_str_vec<T: Deserialize<'de>>(self, from_str: fn(&'de str) -> T) -> Result<Vec<T>, Self::Error> {
+        let _ = from_str;
+        self.deserialize_array(BasicVecVisitor)
+    }
+
+    /// Deserializes a string array of an [`ArrayValue`](crate::ArrayValue),
+    /// an [`ArrayValue::String`](crate::ArrayValue::String) by default.
+    ///
+    /// The counterpart of [`Serializer::__serialize_string_array_value`](crate::ser::Serializer),
+    /// for formats encoding such arrays specially or decoding them into packed strings.
+    #[doc(hidden)]
+    fn __deserialize_string_array_value(self) -> Result<crate::ArrayValue, Self::Error> {
+        Vec::deserialize(self).map(crate::ArrayValue::String)
+    }
+
+    /// Deserializes an array of fixed-width numbers.
+    ///
+    /// Used in the `Deserialize for Vec<T>` implementations of the numeric types
+    /// so that formats storing such arrays as raw little-endian bytes can read them in one go.
+    #[cfg(feature = "bytemuck")]
+    #[doc(hidden)]
+    fn __deserialize_pod_vec<T: Deserialize<'de> + bytemuck::Pod>(self) -> Result<Vec<T>, Self::Error> {
+        self.deserialize_array(BasicVecVisitor)
+    }
 }
 
 /// The `Error` trait allows [`Deserialize`] implementations to create descriptive error messages
@@ -209,6 +246,38 @@ pub trait Error: Sized {
         }
     }
 
+    /// Expected a value of kind `expected`, e.g., a `bool`, but found `found` instead.
+    fn wrong_kind(expected: impl fmt::Display, found: impl fmt::Display) -> Self {
+        Self::custom(format_args!("expected {expected}, found {found}"))
+    }
+
+    /// The element at `index` of an array decoded as a set is equal to an element before it.
+    fn duplicate_element(index: usize) -> Self {
+        Self::custom(format_args!("duplicate element at index {index} of a set"))
+    }
+
+    /// A string decoded as a path can't be one.
+    fn invalid_path(err: InvalidPath) -> Self {
+        Self::custom(err)
+    }
+
+    /// Records that this error occurred within the field at `index`,
+    /// optionally with `field_name`, of a product.
+    ///
+    /// The provided implementation discards this information.
+    fn in_field(self, index: usize, field_name: Option<&str>) -> Self {
+        let _ = (index, field_name);
+        self
+    }
+
+    /// Records that this error occurred within the element at `index` of an array.
+    ///
+    /// The provided implementation discards this information.
+    fn in_element(self, index: usize) -> Self {
+        let _ = index;
+        self
+    }
+
     /// The `tag` does not specify a variant of the sum type.
     fn unknown_variant_tag<'de, T: SumVisitor<'de>>(tag: u8, expected: &T) -> Self {
         Self::custom(format_args!(
@@ -379,6 +448,25 @@ pub trait FieldNameVisitor<'de> {
     /// Provides the visitor the chance to add valid names into `names`.
     fn field_names(&self, names: &mut dyn ValidNames);
 
+    /// Returns whether `name` is one of the valid field names.
+    ///
+    /// Deserializers that tolerate unknown fields ask this before calling [`visit`](Self::visit).
+    fn is_field_name(&self, name: &str) -> bool {
+        /// An implementation of `ValidNames` that remembers whether `name` was pushed into it.
+        struct HasName<'a> {
+            name: &'a str,
+            found: bool,
+        }
+
+        impl ValidNames for HasName<'_> {
+            fn push(&mut self, s: &str) {
+                self.found |= self.name == s
+            }
+        }
+
+        HasName { name, found: false }.run(&|n| self.field_names(n)).found
+    }
+
     fn visit<E: Error>(self, name: &str) -> Result<Self::Output, E>;
 }
 
@@ -422,6 +510,13 @@ pub trait SumVisitor<'de> {
     /// Returns the name of the sum, if any.
     fn sum_name(&self) -> Option<&str>;
 
+    /// Returns the number of variants in the sum, if known.
+    ///
+    /// The provided implementation does not know.
+    fn variant_count(&self) -> Option<usize> {
+        None
+    }
+
     /// Returns whether an option is expected.
     ///
     /// The provided implementation does not.
@@ -590,6 +685,31 @@ pub trait DeserializeSeed<'de> {
     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error>;
 }
 
+/// `Seed` is the in-place form of [`DeserializeSeed`].
+///
+/// Rather than producing a fresh value, a `Seed` deserializes *into* the storage it holds,
+/// clearing and reusing the allocations, e.g., of `String`s and `Vec`s, left over from its previous use.
+/// This makes decoding many values of the same shape, e.g., the rows of a table, cheap after warmup.
+///
+/// For values of some [`AlgebraicType`](crate::AlgebraicType), see [`ValueSeed`].
+pub trait Seed<'de> {
+    /// Deserializes from the given `deserializer` into the storage of `self`.
+    ///
+    /// On success, `self` holds exactly the deserialized data, without any stale leftovers.
+    /// On error, `self` is left in a valid but unspecified state.
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, deserializer: D) -> Result<(), D::Error>;
+
+    /// Used in the `Seed for Vec<T>` impl to allow specializing refilling `Vec<T>` as bytes.
+    #[doc(hidden)]
+    #[inline(always)]
+    fn __deserialize_vec_in_place<D: Deserializer<'de>>(vec: &mut Vec<Self>, deserializer: D) -> Result<(), D::Error>
+    where
+        Self: Default,
+    {
+        in_place::refill_vec_plain(vec, deserializer)
+    }
+}
+
 use crate::de::impls::BorrowedSliceVisitor;
 pub use spacetimedb_bindings_macro::Deserialize;
 
@@ -643,7 +763,7 @@ impl<'de, T> ArrayVisitor<'de, T> for BasicVecVisitor {
 
     fn visit<A: ArrayAccess<'de, Element = T>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
         let mut v = Vec::with_capacity(vec.size_hint().unwrap_or(0));
-        while let Some(el) = vec.next_element()? {
+        while let Some(el) = vec.next_element().map_err(|e| e.in_element(v.len()))? {
             v.push(el)
         }
         Ok(v)
@@ -673,7 +793,7 @@ impl<'de, T, const N: usize> ArrayVisitor<'de, T> for BasicArrayVisitor<N> {
 
     fn visit<A: ArrayAccess<'de, Element = T>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
         let mut v = arrayvec::ArrayVec::<T, N>::new();
-        while let Some(el) = vec.next_element()? {
+        while let Some(el) = vec.next_element().map_err(|e| e.in_element(v.len()))? {
             v.try_push(el)
                 .map_err(|_| Error::custom("too many elements for array"))?
         }
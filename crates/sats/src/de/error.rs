@@ -0,0 +1,236 @@
+use std::fmt;
+
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::algebraic_value::cmp::resolve_value_head;
+use crate::buffer::DecodeError;
+pub use crate::buffer::PathSegment;
+use crate::{AlgebraicType, BuiltinType, WithTypespace};
+
+/// A deserialization error that is independent of the data format,
+/// recording which part of the value being deserialized it occurred in and, when known, the type expected there.
+///
+/// Its [`Display`](fmt::Display) leads with the path, e.g., `.users[2].email: expected String, got U32`.
+///
+/// Errors of formats that track paths themselves, such as a BSATN [`DecodeError`],
+/// convert into this type with their paths intact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeserializeError {
+    /// The path to the part of the value where the error occurred.
+    /// The innermost segment comes first, as segments are added while the error propagates outwards.
+    path: Vec<PathSegment>,
+    /// The type of the value expected where the error occurred, if known.
+    pub expected_type: Option<AlgebraicType>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl DeserializeError {
+    /// Returns an error with `message`, at the top level and of no particular expected type.
+    pub fn new(message: impl fmt::Display) -> Self {
+        Self {
+            path: Vec::new(),
+            expected_type: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Returns an error for when a value of type `expected` was wanted but `found` was encountered instead.
+    pub fn mismatch(expected: AlgebraicType, found: impl fmt::Display) -> Self {
+        Self {
+            expected_type: Some(expected),
+            ..Self::new(format_args!("got {found}"))
+        }
+    }
+
+    /// Returns the error of decoding a value of type `ty` from BSATN,
+    /// with the type expected where it occurred found by following its path through `ty`.
+    pub fn from_decode(err: DecodeError, ty: WithTypespace<'_, AlgebraicType>) -> Self {
+        let mut err = Self::from(err);
+        err.expected_type = type_at(ty, err.path()).map(|ty| ty.ty().clone());
+        err
+    }
+
+    /// Returns the path to the part of the value where the error occurred, outermost segment first.
+    pub fn path(&self) -> impl ExactSizeIterator<Item = &PathSegment> + DoubleEndedIterator {
+        self.path.iter().rev()
+    }
+
+    /// Runs `inner`, recording that any error it returns occurred within the field at `index`, named `name`.
+    ///
+    /// Nesting calls builds up the path from the outside in, e.g.:
+    ///
+    /// ```
+    /// # use spacetimedb_sats::de::error::DeserializeError;
+    /// # use spacetimedb_sats::AlgebraicType;
+    /// let err = DeserializeError::with_field_context(0, "user", || {
+    ///     DeserializeError::with_field_context(1, "email", || -> Result<(), _> {
+    ///         Err(DeserializeError::mismatch(AlgebraicType::String, "U32"))
+    ///     })
+    /// });
+    /// assert_eq!(err.unwrap_err().to_string(), ".user.email: expected String, got U32");
+    /// ```
+    pub fn with_field_context<T>(index: usize, name: &str, inner: impl FnOnce() -> Result<T, Self>) -> Result<T, Self> {
+        inner().map_err(|err| {
+            err.within(PathSegment::Field {
+                index,
+                name: Some(name.into()),
+            })
+        })
+    }
+
+    /// Records that the error occurred within `segment`, which is outside of all the segments recorded so far.
+    fn within(mut self, segment: PathSegment) -> Self {
+        self.path.push(segment);
+        self
+    }
+}
+
+/// Returns the type at `path` within `ty`, with its head resolved,
+/// or `None` if the path leads outside of `ty` or through a ref that couldn't be resolved.
+fn type_at<'a, 'p>(
+    mut ty: WithTypespace<'a, AlgebraicType>,
+    path: impl Iterator<Item = &'p PathSegment>,
+) -> Option<WithTypespace<'a, AlgebraicType>> {
+    for segment in path {
+        ty = resolve_value_head(ty).ok()?;
+        let inner = match (ty.ty(), segment) {
+            (AlgebraicType::Product(prod), &PathSegment::Field { index, .. }) => {
+                &prod.elements.get(index)?.algebraic_type
+            }
+            (AlgebraicType::Builtin(BuiltinType::Array(arr)), PathSegment::Element(_)) => &*arr.elem_ty,
+            _ => return None,
+        };
+        ty = ty.with(inner);
+    }
+    resolve_value_head(ty).ok()
+}
+
+impl super::Error for DeserializeError {
+    fn custom(msg: impl fmt::Display) -> Self {
+        Self::new(msg)
+    }
+
+    fn wrong_kind(expected: impl fmt::Display, found: impl fmt::Display) -> Self {
+        Self::new(format_args!("expected {expected}, got {found}"))
+    }
+
+    fn in_field(self, index: usize, field_name: Option<&str>) -> Self {
+        let name = field_name.map(Into::into);
+        self.within(PathSegment::Field { index, name })
+    }
+
+    fn in_element(self, index: usize) -> Self {
+        self.within(PathSegment::Element(index))
+    }
+}
+
+impl From<DecodeError> for DeserializeError {
+    fn from(err: DecodeError) -> Self {
+        Self {
+            // Both keep the innermost segment first.
+            path: err.path().rev().cloned().collect(),
+            ..Self::new(err.kind())
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.path() {
+            match segment {
+                PathSegment::Field { name: Some(name), .. } => write!(f, ".{name}")?,
+                PathSegment::Field { index, name: None } => write!(f, ".{index}")?,
+                PathSegment::Element(index) => write!(f, "[{index}]")?,
+            }
+        }
+        if !self.path.is_empty() {
+            f.write_str(": ")?;
+        }
+        if let Some(ty) = &self.expected_type {
+            write!(f, "expected {}, ", fmt_algebraic_type(ty))?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Error as _;
+    use crate::{bsatn, product, AlgebraicValue, ProductTypeElement, Typespace};
+
+    #[test]
+    fn display() {
+        let err = DeserializeError::mismatch(AlgebraicType::String, "U32")
+            .in_field(1, Some("email"))
+            .in_element(2)
+            .in_field(0, Some("users"));
+        assert_eq!(err.to_string(), ".users[2].email: expected String, got U32");
+        assert!(err.path().eq(&[
+            PathSegment::Field {
+                index: 0,
+                name: Some("users".into())
+            },
+            PathSegment::Element(2),
+            PathSegment::Field {
+                index: 1,
+                name: Some("email".into())
+            },
+        ]));
+
+        // Unnamed fields are shown by index, and without a path or type, only the message is.
+        let err = DeserializeError::custom("oops").in_field(3, None);
+        assert_eq!(err.to_string(), ".3: oops");
+        assert_eq!(DeserializeError::custom("oops").to_string(), "oops");
+        assert_eq!(
+            DeserializeError::wrong_kind("bool", "2").to_string(),
+            "expected bool, got 2"
+        );
+    }
+
+    #[test]
+    fn field_context() {
+        let ok = DeserializeError::with_field_context(0, "a", || Ok(1));
+        assert_eq!(ok, Ok(1));
+
+        let err = DeserializeError::with_field_context(0, "a", || {
+            DeserializeError::with_field_context(2, "b", || Err::<(), _>(DeserializeError::custom("bad").in_element(0)))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), ".a.b[0]: bad");
+    }
+
+    #[test]
+    fn from_bsatn() {
+        let user = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "email"),
+        ]);
+        let ty = AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::array(user), "users")]);
+        let users = (0..3).map(|i| product![i as u32, format!("{i}@x")]).collect::<Vec<_>>();
+        let mut bytes = bsatn::to_vec(&product![AlgebraicValue::ArrayOf(users)]).unwrap();
+        // Corrupt the last byte, in the email of the third user, to be invalid UTF-8.
+        *bytes.last_mut().unwrap() = 0xff;
+
+        let err: DeserializeError = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap_err().into();
+        assert_eq!(err.to_string(), ".users[2].email: invalid utf8 at byte 2");
+        assert_eq!(err.expected_type, None);
+
+        // Decoding with the type at hand, the type expected where the error occurred is known.
+        let mut ts = Typespace::new(vec![]);
+        let users = ts.add(ty);
+        let err = bsatn::decode_value(&bytes, &AlgebraicType::Ref(users), &ts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ".users[2].email: expected String, invalid utf8 at byte 2"
+        );
+        assert_eq!(err.expected_type, Some(AlgebraicType::String));
+        assert_eq!(err.path().len(), 3);
+
+        // At the top level, the expected type is the whole type.
+        let err = bsatn::decode_value(&[2], &AlgebraicType::Bool, &ts).unwrap_err();
+        assert_eq!(err.expected_type, Some(AlgebraicType::Bool));
+    }
+}
@@ -1,13 +1,20 @@
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use std::ffi::OsString;
 use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeInclusive};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
 
 // use crate::type_value::{ElementValue, EnumValue};
 // use crate::{ProductTypeElement, SumType, PrimitiveType, ReducerDef, ProductType, ProductValue, AlgebraicType, AlgebraicValue};
 
 use crate::builtin_value::{F32, F64};
+use crate::ser::{InvalidPath, LossyPath};
 use crate::{
-    AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, BuiltinValue, MapType, MapValue, ProductType,
+    AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, MapType, MapValue, ProductType,
     ProductTypeElement, ProductValue, SumType, SumValue, WithTypespace,
 };
 
@@ -16,24 +23,49 @@ use super::{
     ProductVisitor, SeqProductAccess, SliceVisitor, SumAccess, SumVisitor, VariantAccess, VariantVisitor,
 };
 
-/// Implements [`Deserialize`] for a type in a simplified manner.
+/// Implements [`Deserialize`] for a type in a simplified manner,
+/// the counterpart of [`impl_serialize!`](crate::impl_serialize).
+///
+/// The macro declares the `'de` lifetime of the input itself,
+/// so it can be used in the bounds and the type, e.g., of a type borrowing from the input.
 ///
 /// An example:
-/// ```ignore
+/// ```
+/// # use spacetimedb_sats::{bsatn, impl_deserialize, de::Deserialize};
+/// struct Celsius<T>(T);
 /// impl_deserialize!(
 /// //     Type parameters  Optional where  Impl type
 /// //            v               v             v
 /// //   ----------------  --------------- ----------
-///     [T: Deserialize<'de>] where [T: Copy] std::rc::Rc<T>,
+///     [T: Deserialize<'de>] where [T: Copy] Celsius<T>,
 /// //  The `deserialize` implementation where `de` is the `Deserializer<'de>`
 /// //  and the expression right of `=>` is the body of `deserialize`.
-///     de => T::deserialize(de).map(std::rc::Rc::new)
+///     (de) => T::deserialize(de).map(Celsius)
 /// );
+///
+/// // A type borrowing from the input.
+/// struct Label<'a>(&'a str);
+/// impl_deserialize!(['a] where ['de: 'a] Label<'a>, (de) => <&'de str>::deserialize(de).map(Label));
+///
+/// let bytes = bsatn::to_vec("warm").unwrap();
+/// assert_eq!(bsatn::from_slice::<Label>(&bytes).unwrap().0, "warm");
+/// assert_eq!(bsatn::from_slice::<Celsius<u8>>(&[21]).unwrap().0, 21);
 /// ```
+///
+/// As `'de` is already declared, declaring it again is an error,
+/// and, as for any impl, type parameters need the bounds the body relies on, e.g., `T: Deserialize<'de>`,
+/// as does an impl borrowing from the input, e.g., `'de: 'a`.
+/// The errors for these are checked in `tests/ui`.
 #[macro_export]
 macro_rules! impl_deserialize {
+    (['de $($generics:tt)*] $($rest:tt)*) => {
+        compile_error!("`impl_deserialize!` declares the `'de` lifetime itself, so remove it from the generics");
+    };
+    ([$($generics:tt)*] $(where [$($wc:tt)*])? $typ:ty, ($de:ident) => $body:expr) => {
+        $crate::impl_deserialize!([$($generics)*] $(where [$($wc)*])? $typ, $de => $body);
+    };
     ([$($generics:tt)*] $(where [$($wc:tt)*])? $typ:ty, $de:ident => $body:expr) => {
-        impl<'de, $($generics)*> $crate::de::Deserialize<'de> for $typ {
+        impl<'de, $($generics)*> $crate::de::Deserialize<'de> for $typ $(where $($wc)*)? {
             fn deserialize<D: $crate::de::Deserializer<'de>>($de: D) -> Result<Self, D::Error> { $body }
         }
     };
@@ -48,11 +80,31 @@ macro_rules! impl_prim {
     };
 }
 
-impl_prim! {
-    (bool, deserialize_bool) /*(u8, deserialize_u8)*/ (u16, deserialize_u16)
-    (u32, deserialize_u32) (u64, deserialize_u64) (u128, deserialize_u128) (i8, deserialize_i8)
-    (i16, deserialize_i16) (i32, deserialize_i32) (i64, deserialize_i64) (i128, deserialize_i128)
-    (f32, deserialize_f32) (f64, deserialize_f64)
+/// Implements [`Deserialize`] for a fixed-width numeric type,
+/// deserializing vectors of it through [`Deserializer::__deserialize_pod_vec`] with the `bytemuck` feature.
+///
+/// The `$method` is a parameterless method on `deserializer` to call.
+macro_rules! impl_num {
+    ($(($prim:ty, $method:ident))*) => {
+        $(impl<'de> Deserialize<'de> for $prim {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.$method()
+            }
+
+            #[cfg(feature = "bytemuck")]
+            fn __deserialize_vec<D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Self>, D::Error> {
+                deserializer.__deserialize_pod_vec()
+            }
+        })*
+    };
+}
+
+impl_prim! { (bool, deserialize_bool) }
+
+impl_num! {
+    /*(u8, deserialize_u8)*/ (u16, deserialize_u16) (u32, deserialize_u32) (u64, deserialize_u64)
+    (u128, deserialize_u128) (i8, deserialize_i8) (i16, deserialize_i16) (i32, deserialize_i32)
+    (i64, deserialize_i64) (i128, deserialize_i128) (f32, deserialize_f32) (f64, deserialize_f64)
 }
 
 impl_deserialize!([] (), de => de.deserialize_product(UnitVisitor));
@@ -96,13 +148,171 @@ impl<'de> Deserialize<'de> for u8 {
     }
 }
 
-impl_deserialize!([] F32, de => f32::deserialize(de).map(Into::into));
-impl_deserialize!([] F64, de => f64::deserialize(de).map(Into::into));
-impl_deserialize!([] String, de => de.deserialize_str(OwnedSliceVisitor));
+/// Implements [`Deserialize`] for the totally ordered float wrappers as the floats they wrap.
+macro_rules! impl_float {
+    ($(($float:ty, $prim:ty))*) => {
+        $(impl<'de> Deserialize<'de> for $float {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <$prim>::deserialize(deserializer).map(Into::into)
+            }
+
+            #[cfg(feature = "bytemuck")]
+            fn __deserialize_vec<D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Self>, D::Error> {
+                let floats = Vec::<$prim>::deserialize(deserializer)?;
+                Ok(floats.into_iter().map(Into::into).collect())
+            }
+        })*
+    };
+}
+
+impl_float! { (F32, f32) (F64, f64) }
+/// Implements [`Deserialize`] for string types,
+/// deserializing vectors of them through [`Deserializer::__deserialize_str_vec`].
+///
+/// The `$visitor` turns a string slice into the string type
+/// and `$from_str` does the same for a borrowed `&'de str`.
+macro_rules! impl_str {
+    ($(($str:ty, $visitor:expr, $from_str:expr))*) => {
+        $(impl<'de> Deserialize<'de> for $str {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_str($visitor)
+            }
+
+            fn __deserialize_vec<D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Self>, D::Error> {
+                deserializer.__deserialize_str_vec($from_str)
+            }
+        })*
+    };
+}
+
+impl_str! {
+    (String, OwnedSliceVisitor, String::from)
+    (&'de str, BorrowedSliceVisitor, |s| s)
+    (Cow<'de, str>, CowSliceVisitor, Cow::Borrowed)
+}
 impl_deserialize!([T: Deserialize<'de>] Vec<T>, de => T::__deserialize_vec(de));
 impl_deserialize!([T: Deserialize<'de>, const N: usize] [T; N], de => T::__deserialize_array(de));
-impl_deserialize!([] Box<str>, de => String::deserialize(de).map(|s| s.into_boxed_str()));
+impl_deserialize!([T: Deserialize<'de>] VecDeque<T>, de => Vec::deserialize(de).map(Into::into));
+impl_deserialize!([T: Deserialize<'de>] LinkedList<T>, de => Vec::deserialize(de).map(|elems| elems.into_iter().collect()));
+impl_deserialize!([T: Deserialize<'de> + Ord] BinaryHeap<T>, de => Vec::deserialize(de).map(Into::into));
+impl_deserialize!([T: Deserialize<'de> + Ord] BTreeSet<T>, de => de.deserialize_array(BTreeSetVisitor));
+#[cfg(feature = "smallvec")]
+impl_deserialize!(
+    [T: Deserialize<'de>, A: smallvec::Array<Item = T>] smallvec::SmallVec<A>,
+    de => Vec::deserialize(de).map(smallvec::SmallVec::from_vec)
+);
+impl_deserialize!(
+    [T: Deserialize<'de>, const N: usize] arrayvec::ArrayVec<T, N>,
+    de => de.deserialize_array(ArrayVecVisitor)
+);
+// `Bytes` can take over the allocation of the `Vec<u8>`.
+#[cfg(feature = "bytes")]
+impl_deserialize!([] bytes::Bytes, de => Vec::deserialize(de).map(bytes::Bytes::from));
+#[cfg(feature = "bytes")]
+impl_deserialize!([] bytes::BytesMut, de => de.deserialize_bytes(BytesMutVisitor));
+// See the `Serialize` impls for the layout of vectors and matrices.
+#[cfg(feature = "nalgebra")]
+impl_deserialize!([] nalgebra::Vector3<f32>, de => <[f32; 3]>::deserialize(de).map(Into::into));
+#[cfg(feature = "nalgebra")]
+impl_deserialize!([] nalgebra::Matrix4<f32>, de => <[f32; 16]>::deserialize(de).map(|m| nalgebra::Matrix4::from_column_slice(&m)));
+#[cfg(feature = "chrono")]
+impl_deserialize!([] chrono::DateTime<chrono::Utc>, de => {
+    use chrono::TimeZone;
+    let millis = de.deserialize_i64()?;
+    let out_of_range = || Error::custom(format_args!("timestamp of {millis} ms is out of range"));
+    chrono::Utc.timestamp_millis_opt(millis).single().ok_or_else(out_of_range)
+});
+#[cfg(feature = "chrono")]
+impl_deserialize!([] chrono::NaiveDate, de => {
+    let days = de.deserialize_i32()?;
+    let out_of_range = || Error::custom(format_args!("date {days} days from 1970-01-01 is out of range"));
+    chrono::NaiveDate::default().checked_add_signed(chrono::Duration::days(days.into())).ok_or_else(out_of_range)
+});
+impl_deserialize!([] PathBuf, de => {
+    let path = PathBuf::from(String::deserialize(de)?);
+    InvalidPath::check(&path).map_err(Error::invalid_path)?;
+    Ok(path)
+});
+impl_deserialize!([] OsString, de => PathBuf::deserialize(de).map(PathBuf::into_os_string));
+impl_deserialize!([] LossyPath, de => PathBuf::deserialize(de).map(LossyPath));
+impl_deserialize!([T: Deserialize<'de>] Cell<T>, de => T::deserialize(de).map(Cell::new));
+impl_deserialize!([T: Deserialize<'de>] RefCell<T>, de => T::deserialize(de).map(RefCell::new));
+impl_deserialize!([T: Deserialize<'de>] Mutex<T>, de => T::deserialize(de).map(Mutex::new));
+impl_deserialize!([T: Deserialize<'de>] RwLock<T>, de => T::deserialize(de).map(RwLock::new));
+impl_deserialize!([T: Deserialize<'de>] std::num::Wrapping<T>, de => T::deserialize(de).map(std::num::Wrapping));
+impl_deserialize!([] Box<str>, de => de.deserialize_str(PtrSliceVisitor(PhantomData)));
+impl_deserialize!([] Rc<str>, de => de.deserialize_str(PtrSliceVisitor(PhantomData)));
+impl_deserialize!([] Arc<str>, de => de.deserialize_str(PtrSliceVisitor(PhantomData)));
 impl_deserialize!([T: Deserialize<'de>] Box<[T]>, de => Vec::deserialize(de).map(|s| s.into_boxed_slice()));
+// Unlike for `Box<[T]>`, moving the elements into the allocation of an `Rc` or `Arc` needs a copy.
+impl_deserialize!([T: Deserialize<'de>] Rc<[T]>, de => Vec::deserialize(de).map(Into::into));
+impl_deserialize!([T: Deserialize<'de>] Arc<[T]>, de => Vec::deserialize(de).map(Into::into));
+
+/// The visitor converts the slice into the smart pointer `P`,
+/// directly from the slice when borrowed rather than first converting it to its owned version.
+struct PtrSliceVisitor<P>(PhantomData<P>);
+
+impl<T: ToOwned + ?Sized, P: for<'a> From<&'a T> + From<T::Owned>> SliceVisitor<'_, T> for PtrSliceVisitor<P> {
+    type Output = P;
+
+    fn visit<E: Error>(self, slice: &T) -> Result<Self::Output, E> {
+        Ok(slice.into())
+    }
+
+    fn visit_owned<E: Error>(self, buf: T::Owned) -> Result<Self::Output, E> {
+        Ok(buf.into())
+    }
+}
+
+/// The visitor collects the elements into a `BTreeSet<T>`,
+/// failing with [`Error::duplicate_element`] if any of them occurs more than once.
+struct BTreeSetVisitor;
+
+impl<'de, T: Ord> super::ArrayVisitor<'de, T> for BTreeSetVisitor {
+    type Output = BTreeSet<T>;
+
+    fn visit<A: super::ArrayAccess<'de, Element = T>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
+        let mut set = BTreeSet::new();
+        let mut index = 0;
+        while let Some(el) = vec.next_element().map_err(|e| e.in_element(index))? {
+            if !set.insert(el) {
+                return Err(A::Error::duplicate_element(index));
+            }
+            index += 1;
+        }
+        Ok(set)
+    }
+}
+
+/// The visitor collects the elements into an `ArrayVec<T, N>`,
+/// failing with a `CapacityError` if there are more than `N` of them.
+struct ArrayVecVisitor<const N: usize>;
+
+impl<'de, T, const N: usize> super::ArrayVisitor<'de, T> for ArrayVecVisitor<N> {
+    type Output = arrayvec::ArrayVec<T, N>;
+
+    fn visit<A: super::ArrayAccess<'de, Element = T>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
+        let mut v = arrayvec::ArrayVec::new();
+        while let Some(el) = vec.next_element().map_err(|e| e.in_element(v.len()))? {
+            v.try_push(el)
+                .map_err(|e| Error::custom(format_args!("{}: more than {N} elements", e.simplify())))?;
+        }
+        Ok(v)
+    }
+}
+
+/// The visitor copies the byte slice into a `BytesMut`.
+#[cfg(feature = "bytes")]
+struct BytesMutVisitor;
+
+#[cfg(feature = "bytes")]
+impl SliceVisitor<'_, [u8]> for BytesMutVisitor {
+    type Output = bytes::BytesMut;
+
+    fn visit<E: Error>(self, slice: &[u8]) -> Result<Self::Output, E> {
+        Ok(slice.into())
+    }
+}
 
 /// The visitor converts the slice to its owned version.
 struct OwnedSliceVisitor;
@@ -119,27 +329,45 @@ impl<T: ToOwned + ?Sized> SliceVisitor<'_, T> for OwnedSliceVisitor {
     }
 }
 
-/// The visitor will convert the byte slice to `[u8; N]`.
+/// The visitor will copy the byte slice into a `[u8; N]`.
 ///
-/// When `slice.len() != N` an error will be raised.
+/// When `slice.len() != N` an error naming both lengths will be raised.
 struct ByteArrayVisitor<const N: usize>;
 
 impl<const N: usize> SliceVisitor<'_, [u8]> for ByteArrayVisitor<N> {
     type Output = [u8; N];
 
     fn visit<E: Error>(self, slice: &[u8]) -> Result<Self::Output, E> {
-        slice.try_into().map_err(|_| {
-            Error::custom(if slice.len() > N {
-                "too many elements for array"
-            } else {
-                "too few elements for array"
-            })
-        })
+        slice.try_into().map_err(|_| byte_array_len_error(N, slice.len()))
     }
 }
 
-impl_deserialize!([] &'de str, de => de.deserialize_str(BorrowedSliceVisitor));
+/// Returns an error for a byte slice of `len` bytes where an array of `N` bytes was expected.
+fn byte_array_len_error<E: Error>(n: usize, len: usize) -> E {
+    E::custom(format_args!("expected an array of {n} bytes, got {len} bytes"))
+}
+
 impl_deserialize!([] &'de [u8], de => de.deserialize_bytes(BorrowedSliceVisitor));
+impl_deserialize!([const N: usize] &'de [u8; N], de => de.deserialize_bytes(BorrowedByteArrayVisitor));
+
+/// The visitor reinterprets the borrowed byte slice as a `&[u8; N]`.
+///
+/// When `slice.len() != N` an error naming both lengths will be raised.
+struct BorrowedByteArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> SliceVisitor<'de, [u8]> for BorrowedByteArrayVisitor<N> {
+    type Output = &'de [u8; N];
+
+    fn visit<E: Error>(self, _: &[u8]) -> Result<Self::Output, E> {
+        Err(E::custom("expected *borrowed* slice"))
+    }
+
+    fn visit_borrowed<E: Error>(self, borrowed_slice: &'de [u8]) -> Result<Self::Output, E> {
+        borrowed_slice
+            .try_into()
+            .map_err(|_| byte_array_len_error(N, borrowed_slice.len()))
+    }
+}
 
 /// The visitor returns the slice as-is and borrowed.
 pub(crate) struct BorrowedSliceVisitor;
@@ -156,7 +384,6 @@ impl<'de, T: ToOwned + ?Sized + 'de> SliceVisitor<'de, T> for BorrowedSliceVisit
     }
 }
 
-impl_deserialize!([] Cow<'de, str>, de => de.deserialize_str(CowSliceVisitor));
 impl_deserialize!([] Cow<'de, [u8]>, de => de.deserialize_bytes(CowSliceVisitor));
 
 /// The visitor works with either owned or borrowed versions to produce `Cow<'de, T>`.
@@ -182,6 +409,39 @@ impl_deserialize!(
     [K: Deserialize<'de> + Ord, V: Deserialize<'de>] BTreeMap<K, V>,
     de => de.deserialize_map(BasicMapVisitor)
 );
+// Entries and elements keep the order they were encoded in.
+// Like `IndexMap::from_iter`, a repeated key keeps its first position but its last value,
+// and a repeated element of a set is dropped.
+#[cfg(feature = "indexmap")]
+impl_deserialize!(
+    [K: Deserialize<'de> + Eq + std::hash::Hash, V: Deserialize<'de>, S: std::hash::BuildHasher + Default]
+    indexmap::IndexMap<K, V, S>,
+    de => de.deserialize_map(IndexMapVisitor(PhantomData))
+);
+#[cfg(feature = "indexmap")]
+impl_deserialize!(
+    [T: Deserialize<'de> + Eq + std::hash::Hash, S: std::hash::BuildHasher + Default] indexmap::IndexSet<T, S>,
+    de => Vec::deserialize(de).map(|elems| elems.into_iter().collect())
+);
+
+/// The visitor deserializes an `IndexMap<K, V, S>`.
+#[cfg(feature = "indexmap")]
+struct IndexMapVisitor<S>(PhantomData<S>);
+
+#[cfg(feature = "indexmap")]
+impl<'de, K: Eq + std::hash::Hash, V, S: std::hash::BuildHasher + Default> super::MapVisitor<'de, K, V>
+    for IndexMapVisitor<S>
+{
+    type Output = indexmap::IndexMap<K, V, S>;
+
+    fn visit<A: super::MapAccess<'de, Key = K, Value = V>>(self, mut map: A) -> Result<Self::Output, A::Error> {
+        let mut m = indexmap::IndexMap::with_capacity_and_hasher(map.size_hint().unwrap_or(0), S::default());
+        while let Some((k, v)) = map.next_entry()? {
+            m.insert(k, v);
+        }
+        Ok(m)
+    }
+}
 
 impl_deserialize!([T: Deserialize<'de>] Box<T>, de => T::deserialize(de).map(Box::new));
 impl_deserialize!([T: Deserialize<'de>] Option<T>, de => de.deserialize_sum(OptionVisitor(PhantomData)));
@@ -196,6 +456,10 @@ impl<'de, T: Deserialize<'de>> SumVisitor<'de> for OptionVisitor<T> {
         Some("option")
     }
 
+    fn variant_count(&self) -> Option<usize> {
+        Some(2)
+    }
+
     fn is_option(&self) -> bool {
         true
     }
@@ -258,6 +522,10 @@ impl<'de, T: Deserialize<'de>, E: Deserialize<'de>> SumVisitor<'de> for ResultVi
         Some("result")
     }
 
+    fn variant_count(&self) -> Option<usize> {
+        Some(2)
+    }
+
     fn is_option(&self) -> bool {
         false
     }
@@ -295,6 +563,148 @@ impl<'de, T: Deserialize<'de>, U: Deserialize<'de>> VariantVisitor for ResultVis
     }
 }
 
+impl_deserialize!([T: Deserialize<'de>] Range<T>, de => {
+    let (start, end) = de.deserialize_product(RangeVisitor(PhantomData))?;
+    Ok(start..end)
+});
+// Through `new`, so that the range is not exhausted.
+impl_deserialize!([T: Deserialize<'de>] RangeInclusive<T>, de => {
+    let (start, end) = de.deserialize_product(RangeVisitor(PhantomData))?;
+    Ok(RangeInclusive::new(start, end))
+});
+
+/// Visitor to deserialize the bounds of a range, the product `{ start: T, end: T }`.
+struct RangeVisitor<T>(PhantomData<T>);
+
+/// Field identified by the [`FieldNameVisitor`] for ranges.
+enum RangeField {
+    Start,
+    End,
+}
+
+impl<'de, T: Deserialize<'de>> ProductVisitor<'de> for RangeVisitor<T> {
+    type Output = (T, T);
+
+    fn product_name(&self) -> Option<&str> {
+        Some("range")
+    }
+
+    fn product_len(&self) -> usize {
+        2
+    }
+
+    fn visit_seq_product<A: SeqProductAccess<'de>>(self, mut prod: A) -> Result<Self::Output, A::Error> {
+        let mut next = |i, name| {
+            prod.next_element()
+                .map_err(|e: A::Error| e.in_field(i, Some(name)))?
+                .ok_or_else(|| Error::invalid_product_length(i, &self))
+        };
+        Ok((next(0, "start")?, next(1, "end")?))
+    }
+
+    fn visit_named_product<A: super::NamedProductAccess<'de>>(self, mut prod: A) -> Result<Self::Output, A::Error> {
+        let (mut start, mut end) = (None, None);
+        while let Some(field) = prod.get_field_ident(RangeVisitor::<T>(PhantomData))? {
+            let (slot, i, name) = match field {
+                RangeField::Start => (&mut start, 0, "start"),
+                RangeField::End => (&mut end, 1, "end"),
+            };
+            if slot.is_some() {
+                return Err(Error::duplicate_field(i, Some(name), &self));
+            }
+            *slot = Some(
+                prod.get_field_value()
+                    .map_err(|e: A::Error| e.in_field(i, Some(name)))?,
+            );
+        }
+        let start = start.ok_or_else(|| Error::missing_field(0, Some("start"), &self))?;
+        let end = end.ok_or_else(|| Error::missing_field(1, Some("end"), &self))?;
+        Ok((start, end))
+    }
+}
+
+impl<'de, T> FieldNameVisitor<'de> for RangeVisitor<T> {
+    type Output = RangeField;
+
+    fn field_names(&self, names: &mut dyn super::ValidNames) {
+        names.extend(["start", "end"])
+    }
+
+    fn visit<E: Error>(self, name: &str) -> Result<Self::Output, E> {
+        match name {
+            "start" => Ok(RangeField::Start),
+            "end" => Ok(RangeField::End),
+            _ => Err(E::unknown_field_name(name, &self)),
+        }
+    }
+}
+
+impl_deserialize!([T: Deserialize<'de>] Bound<T>, de => de.deserialize_sum(BoundVisitor(PhantomData)));
+
+/// Visitor to deserialize a `Bound<T>`.
+struct BoundVisitor<T>(PhantomData<T>);
+
+/// Variant determined by the [`VariantVisitor`] for `Bound<T>`.
+enum BoundVariant {
+    Included,
+    Excluded,
+    Unbounded,
+}
+
+impl<'de, T: Deserialize<'de>> SumVisitor<'de> for BoundVisitor<T> {
+    type Output = Bound<T>;
+
+    fn sum_name(&self) -> Option<&str> {
+        Some("bound")
+    }
+
+    fn variant_count(&self) -> Option<usize> {
+        Some(3)
+    }
+
+    fn is_option(&self) -> bool {
+        false
+    }
+
+    fn visit_sum<A: SumAccess<'de>>(self, data: A) -> Result<Self::Output, A::Error> {
+        let (variant, data) = data.variant(self)?;
+        Ok(match variant {
+            BoundVariant::Included => Bound::Included(data.deserialize()?),
+            BoundVariant::Excluded => Bound::Excluded(data.deserialize()?),
+            BoundVariant::Unbounded => {
+                data.deserialize::<()>()?;
+                Bound::Unbounded
+            }
+        })
+    }
+}
+
+impl<'de, T: Deserialize<'de>> VariantVisitor for BoundVisitor<T> {
+    type Output = BoundVariant;
+
+    fn variant_names(&self, names: &mut dyn super::ValidNames) {
+        names.extend(["included", "excluded", "unbounded"])
+    }
+
+    fn visit_tag<E: Error>(self, tag: u8) -> Result<Self::Output, E> {
+        match tag {
+            0 => Ok(BoundVariant::Included),
+            1 => Ok(BoundVariant::Excluded),
+            2 => Ok(BoundVariant::Unbounded),
+            _ => Err(E::unknown_variant_tag(tag, &self)),
+        }
+    }
+
+    fn visit_name<E: Error>(self, name: &str) -> Result<Self::Output, E> {
+        match name {
+            "included" => Ok(BoundVariant::Included),
+            "excluded" => Ok(BoundVariant::Excluded),
+            "unbounded" => Ok(BoundVariant::Unbounded),
+            _ => Err(E::unknown_variant_name(name, &self)),
+        }
+    }
+}
+
 impl<'de> DeserializeSeed<'de> for WithTypespace<'_, AlgebraicType> {
     type Output = AlgebraicValue;
 
@@ -302,37 +712,34 @@ impl<'de> DeserializeSeed<'de> for WithTypespace<'_, AlgebraicType> {
         match self.ty() {
             AlgebraicType::Sum(sum) => self.with(sum).deserialize(deserializer).map(AlgebraicValue::Sum),
             AlgebraicType::Product(prod) => self.with(prod).deserialize(deserializer).map(AlgebraicValue::Product),
-            AlgebraicType::Builtin(b) => self.with(b).deserialize(deserializer).map(AlgebraicValue::Builtin),
+            AlgebraicType::Builtin(b) => self.with(b).deserialize(deserializer),
             AlgebraicType::Ref(r) => self.resolve(*r).deserialize(deserializer),
+            AlgebraicType::Newtype(nt) => self.with(&*nt.inner).deserialize(deserializer),
         }
     }
 }
 
 impl<'de> DeserializeSeed<'de> for WithTypespace<'_, BuiltinType> {
-    type Output = BuiltinValue;
+    type Output = AlgebraicValue;
 
     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error> {
         Ok(match self.ty() {
-            BuiltinType::Bool => BuiltinValue::Bool(bool::deserialize(deserializer)?),
-            BuiltinType::I8 => BuiltinValue::I8(i8::deserialize(deserializer)?),
-            BuiltinType::U8 => BuiltinValue::U8(u8::deserialize(deserializer)?),
-            BuiltinType::I16 => BuiltinValue::I16(i16::deserialize(deserializer)?),
-            BuiltinType::U16 => BuiltinValue::U16(u16::deserialize(deserializer)?),
-            BuiltinType::I32 => BuiltinValue::I32(i32::deserialize(deserializer)?),
-            BuiltinType::U32 => BuiltinValue::U32(u32::deserialize(deserializer)?),
-            BuiltinType::I64 => BuiltinValue::I64(i64::deserialize(deserializer)?),
-            BuiltinType::U64 => BuiltinValue::U64(u64::deserialize(deserializer)?),
-            BuiltinType::I128 => BuiltinValue::I128(i128::deserialize(deserializer)?),
-            BuiltinType::U128 => BuiltinValue::U128(u128::deserialize(deserializer)?),
-            BuiltinType::F32 => BuiltinValue::F32(f32::deserialize(deserializer)?.into()),
-            BuiltinType::F64 => BuiltinValue::F64(f64::deserialize(deserializer)?.into()),
-            BuiltinType::String => BuiltinValue::String(String::deserialize(deserializer)?),
-            BuiltinType::Array(ty) => BuiltinValue::Array {
-                val: self.with(ty).deserialize(deserializer)?,
-            },
-            BuiltinType::Map(ty) => BuiltinValue::Map {
-                val: self.with(ty).deserialize(deserializer)?,
-            },
+            BuiltinType::Bool => AlgebraicValue::Bool(bool::deserialize(deserializer)?),
+            BuiltinType::I8 => AlgebraicValue::I8(i8::deserialize(deserializer)?),
+            BuiltinType::U8 => AlgebraicValue::U8(u8::deserialize(deserializer)?),
+            BuiltinType::I16 => AlgebraicValue::I16(i16::deserialize(deserializer)?),
+            BuiltinType::U16 => AlgebraicValue::U16(u16::deserialize(deserializer)?),
+            BuiltinType::I32 => AlgebraicValue::I32(i32::deserialize(deserializer)?),
+            BuiltinType::U32 => AlgebraicValue::U32(u32::deserialize(deserializer)?),
+            BuiltinType::I64 => AlgebraicValue::I64(i64::deserialize(deserializer)?),
+            BuiltinType::U64 => AlgebraicValue::U64(u64::deserialize(deserializer)?),
+            BuiltinType::I128 => AlgebraicValue::I128(i128::deserialize(deserializer)?),
+            BuiltinType::U128 => AlgebraicValue::U128(u128::deserialize(deserializer)?),
+            BuiltinType::F32 => AlgebraicValue::F32(f32::deserialize(deserializer)?.into()),
+            BuiltinType::F64 => AlgebraicValue::F64(f64::deserialize(deserializer)?.into()),
+            BuiltinType::String => AlgebraicValue::String(<Box<str>>::deserialize(deserializer)?),
+            BuiltinType::Array(ty) => AlgebraicValue::Array(self.with(ty).deserialize(deserializer)?),
+            BuiltinType::Map(ty) => AlgebraicValue::Map(self.with(ty).deserialize(deserializer)?),
         })
     }
 }
@@ -352,6 +759,10 @@ impl<'de> SumVisitor<'de> for WithTypespace<'_, SumType> {
         None
     }
 
+    fn variant_count(&self) -> Option<usize> {
+        Some(self.ty().variants.len())
+    }
+
     fn is_option(&self) -> bool {
         self.ty().as_option().is_some()
     }
@@ -431,19 +842,22 @@ impl<'de> DeserializeSeed<'de> for WithTypespace<'_, ArrayType> {
             de: D,
             map: impl FnOnce(Vec<T>) -> ArrayValue,
         ) -> Result<ArrayValue, D::Error> {
-            de.deserialize_array(BasicVecVisitor).map(map)
+            T::__deserialize_vec(de).map(map)
         }
 
         let mut ty = &*self.ty().elem_ty;
 
-        // Loop, resolving `Ref`s, until we reach a non-`Ref` type.
+        // Loop, resolving `Ref`s and unwrapping newtypes, until we reach a structural type.
         loop {
             break match ty {
                 AlgebraicType::Ref(r) => {
-                    // The only arm that will loop.
                     ty = self.resolve(*r).ty();
                     continue;
                 }
+                AlgebraicType::Newtype(nt) => {
+                    ty = &nt.inner;
+                    continue;
+                }
                 AlgebraicType::Sum(ty) => deserializer
                     .deserialize_array_seed(BasicVecVisitor, self.with(ty))
                     .map(ArrayValue::Sum),
@@ -465,7 +879,7 @@ impl<'de> DeserializeSeed<'de> for WithTypespace<'_, ArrayType> {
                 AlgebraicType::Builtin(BuiltinType::U128) => de_array(deserializer, ArrayValue::U128),
                 AlgebraicType::Builtin(BuiltinType::F32) => de_array(deserializer, ArrayValue::F32),
                 AlgebraicType::Builtin(BuiltinType::F64) => de_array(deserializer, ArrayValue::F64),
-                AlgebraicType::Builtin(BuiltinType::String) => de_array(deserializer, ArrayValue::String),
+                AlgebraicType::Builtin(BuiltinType::String) => deserializer.__deserialize_string_array_value(),
                 AlgebraicType::Builtin(BuiltinType::Array(ty)) => deserializer
                     .deserialize_array_seed(BasicVecVisitor, self.with(ty))
                     .map(ArrayValue::Array),
@@ -523,7 +937,8 @@ pub fn visit_seq_product<'de, A: SeqProductAccess<'de>>(
     mut tup: A,
 ) -> Result<ProductValue, A::Error> {
     let elements = elems.ty().iter().enumerate().map(|(i, el)| {
-        tup.next_element_seed(elems.with(&el.algebraic_type))?
+        tup.next_element_seed(elems.with(&el.algebraic_type))
+            .map_err(|e| e.in_field(i, el.name()))?
             .ok_or_else(|| Error::invalid_product_length(i, visitor))
     });
     let elements = elements.collect::<Result<_, _>>()?;
@@ -562,7 +977,10 @@ pub fn visit_named_product<'de, A: super::NamedProductAccess<'de>>(
         }
 
         // Deserialize the value for this field's type.
-        *slot = Some(tup.get_field_value_seed(elems_tys.with(&element.algebraic_type))?);
+        *slot = Some(
+            tup.get_field_value_seed(elems_tys.with(&element.algebraic_type))
+                .map_err(|e| e.in_field(index, element.name()))?,
+        );
     }
 
     // Get rid of the `Option<_>` layer.
@@ -576,11 +994,11 @@ pub fn visit_named_product<'de, A: super::NamedProductAccess<'de>>(
 }
 
 /// A visitor for extracting indices of field names in the elements of a [`ProductType`].
-struct TupleNameVisitor<'a> {
+pub(crate) struct TupleNameVisitor<'a> {
     /// The elements of a product type, in order.
-    elems: &'a [ProductTypeElement],
+    pub(crate) elems: &'a [ProductTypeElement],
     /// The kind of product this is.
-    kind: ProductKind,
+    pub(crate) kind: ProductKind,
 }
 
 impl FieldNameVisitor<'_> for TupleNameVisitor<'_> {
@@ -591,6 +1009,10 @@ impl FieldNameVisitor<'_> for TupleNameVisitor<'_> {
         names.extend(self.elems.iter().filter_map(|f| f.name()))
     }
 
+    fn is_field_name(&self, name: &str) -> bool {
+        self.elems.iter().any(|f| f.has_name(name))
+    }
+
     fn kind(&self) -> ProductKind {
         self.kind
     }
@@ -0,0 +1,679 @@
+//! Deserialization *into* existing storage.
+//!
+//! See [`Seed`] for the entry point.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+use crate::builtin_value::{F32, F64};
+use crate::{
+    AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, MapType, ProductType, ProductValue, SumType,
+    SumValue, Value, WithTypespace,
+};
+
+use super::impls::TupleNameVisitor;
+use super::{
+    ArrayAccess, ArrayVisitor, Deserialize, DeserializeSeed, Deserializer, Error, NamedProductAccess, ProductVisitor,
+    Seed, SeqProductAccess, SliceVisitor, SumAccess, SumVisitor, VariantAccess,
+};
+
+/// Implements [`Seed`] for a primitive type by overwriting the place wholesale.
+macro_rules! impl_seed_prim {
+    ($($prim:ty),*) => {
+        $(impl<'de> Seed<'de> for $prim {
+            fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+                *self = <$prim>::deserialize(de)?;
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_seed_prim!(
+    bool,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    u128,
+    i128,
+    f32,
+    f64,
+    F32,
+    F64,
+    ()
+);
+
+impl<'de> Seed<'de> for u8 {
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+        *self = de.deserialize_u8()?;
+        Ok(())
+    }
+
+    // Specialize `Vec<u8>` like `Deserialize` does, refilling the buffer from the byte slice.
+    fn __deserialize_vec_in_place<D: Deserializer<'de>>(vec: &mut Vec<Self>, de: D) -> Result<(), D::Error>
+    where
+        Self: Default,
+    {
+        de.deserialize_bytes(RefillBytes(vec))
+    }
+}
+
+impl<'de> Seed<'de> for String {
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+        de.deserialize_str(RefillString(self))
+    }
+}
+
+impl<'de> Seed<'de> for Box<str> {
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+        de.deserialize_str(RefillBoxedStr(self))
+    }
+}
+
+impl<'de, T: Seed<'de> + Default> Seed<'de> for Vec<T> {
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+        T::__deserialize_vec_in_place(self, de)
+    }
+}
+
+impl<'de, T: Seed<'de>> Seed<'de> for Box<T> {
+    fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+        (**self).deserialize_in_place(de)
+    }
+}
+
+/// Refills `vec` with the elements of an array in `de`,
+/// using the plain, type-directed, [`Seed`] of `T` for each element.
+pub(super) fn refill_vec_plain<'de, D: Deserializer<'de>, T: Seed<'de> + Default>(
+    vec: &mut Vec<T>,
+    de: D,
+) -> Result<(), D::Error> {
+    refill_vec(de, PlainFiller(PhantomData), vec)
+}
+
+/// The visitor refills a `String` from a string slice, reusing its capacity.
+struct RefillString<'a>(&'a mut String);
+
+impl SliceVisitor<'_, str> for RefillString<'_> {
+    type Output = ();
+
+    fn visit<E: Error>(self, slice: &str) -> Result<Self::Output, E> {
+        self.0.clear();
+        self.0.push_str(slice);
+        Ok(())
+    }
+
+    fn visit_owned<E: Error>(self, buf: String) -> Result<Self::Output, E> {
+        // Keep whichever buffer can hold the contents rather than always swapping.
+        if self.0.capacity() >= buf.len() {
+            self.visit(&buf)
+        } else {
+            *self.0 = buf;
+            Ok(())
+        }
+    }
+}
+
+/// The visitor refills a `Box<str>` from a string slice,
+/// reusing its allocation when the lengths match exactly.
+struct RefillBoxedStr<'a>(&'a mut Box<str>);
+
+impl SliceVisitor<'_, str> for RefillBoxedStr<'_> {
+    type Output = ();
+
+    fn visit<E: Error>(self, slice: &str) -> Result<Self::Output, E> {
+        if self.0.len() == slice.len() {
+            // SAFETY: Overwriting all of the bytes with those of `slice`,
+            // which is valid UTF-8, leaves valid UTF-8 behind.
+            unsafe { self.0.as_bytes_mut() }.copy_from_slice(slice.as_bytes());
+        } else {
+            *self.0 = slice.into();
+        }
+        Ok(())
+    }
+
+    fn visit_owned<E: Error>(self, buf: String) -> Result<Self::Output, E> {
+        *self.0 = buf.into_boxed_str();
+        Ok(())
+    }
+}
+
+/// The visitor refills a `Vec<u8>` from a byte slice, reusing its capacity.
+struct RefillBytes<'a>(&'a mut Vec<u8>);
+
+impl SliceVisitor<'_, [u8]> for RefillBytes<'_> {
+    type Output = ();
+
+    fn visit<E: Error>(self, slice: &[u8]) -> Result<Self::Output, E> {
+        self.0.clear();
+        self.0.extend_from_slice(slice);
+        Ok(())
+    }
+
+    fn visit_owned<E: Error>(self, buf: Vec<u8>) -> Result<Self::Output, E> {
+        if self.0.capacity() >= buf.len() {
+            self.visit(&buf)
+        } else {
+            *self.0 = buf;
+            Ok(())
+        }
+    }
+}
+
+/// A copyable description of how to deserialize into a `Place`.
+///
+/// This is the in-place counterpart of a `DeserializeSeed + Clone`
+/// as used for the elements of arrays.
+trait Filler<'de>: Copy {
+    /// The storage deserialized into.
+    type Place;
+
+    /// Returns a fresh place to deserialize into when there is nothing to reuse.
+    fn blank(self) -> Self::Place;
+
+    /// Deserializes from `de` into `place`, reusing its allocations where possible.
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut Self::Place) -> Result<(), D::Error>;
+}
+
+/// A [`Filler`] for any `T: Seed` which needs no type information.
+struct PlainFiller<T>(PhantomData<fn() -> T>);
+
+impl<T> Clone for PlainFiller<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for PlainFiller<T> {}
+
+impl<'de, T: Seed<'de> + Default> Filler<'de> for PlainFiller<T> {
+    type Place = T;
+
+    fn blank(self) -> T {
+        T::default()
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut T) -> Result<(), D::Error> {
+        place.deserialize_in_place(de)
+    }
+}
+
+/// Adapts a [`Filler`] and a borrowed place into a [`DeserializeSeed`].
+struct FillSeed<'p, 'de, F: Filler<'de>>(F, &'p mut F::Place, PhantomData<&'de ()>);
+
+impl<'de, F: Filler<'de>> DeserializeSeed<'de> for FillSeed<'_, 'de, F> {
+    type Output = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<(), D::Error> {
+        self.0.fill(de, self.1)
+    }
+}
+
+/// Returns a seed that deserializes with `filler` into `place`.
+fn fill_seed<'p, 'de, F: Filler<'de>>(filler: F, place: &'p mut F::Place) -> FillSeed<'p, 'de, F> {
+    FillSeed(filler, place, PhantomData)
+}
+
+/// The state shared by the seeds of [`refill_vec`].
+struct RefillState<T> {
+    /// The vector being refilled.
+    vec: Vec<T>,
+    /// How many elements have been refilled thus far.
+    filled: usize,
+}
+
+/// A clonable seed that refills the next element of the vector in the shared `state`.
+struct RefillElemSeed<'s, 'de, F: Filler<'de>> {
+    filler: F,
+    state: &'s RefCell<RefillState<F::Place>>,
+}
+
+impl<'de, F: Filler<'de>> Clone for RefillElemSeed<'_, 'de, F> {
+    fn clone(&self) -> Self {
+        Self {
+            filler: self.filler,
+            state: self.state,
+        }
+    }
+}
+
+impl<'de, F: Filler<'de>> DeserializeSeed<'de> for RefillElemSeed<'_, 'de, F> {
+    type Output = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<(), D::Error> {
+        let mut state = self.state.borrow_mut();
+        let RefillState { vec, filled } = &mut *state;
+        if *filled == vec.len() {
+            vec.push(self.filler.blank());
+        }
+        self.filler.fill(de, &mut vec[*filled])?;
+        *filled += 1;
+        Ok(())
+    }
+}
+
+/// The visitor drives an array of `()`s, as produced by [`RefillElemSeed`], to completion.
+struct DriveVisitor;
+
+impl<'de> ArrayVisitor<'de, ()> for DriveVisitor {
+    type Output = ();
+
+    fn visit<A: ArrayAccess<'de, Element = ()>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
+        let mut index = 0;
+        while vec.next_element().map_err(|e| e.in_element(index))?.is_some() {
+            index += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Refills `vec` with the elements of an array in `de`, using `filler` for each element.
+///
+/// Existing elements are refilled in place and, where the array is longer than `vec`,
+/// new elements are pushed, reusing the capacity of `vec`.
+/// Any elements left over beyond the array's length are dropped.
+fn refill_vec<'de, D: Deserializer<'de>, F: Filler<'de>>(
+    de: D,
+    filler: F,
+    vec: &mut Vec<F::Place>,
+) -> Result<(), D::Error> {
+    let state = RefCell::new(RefillState {
+        vec: std::mem::take(vec),
+        filled: 0,
+    });
+    let res = de.deserialize_array_seed(DriveVisitor, RefillElemSeed { filler, state: &state });
+    let RefillState {
+        vec: mut refilled,
+        filled,
+    } = state.into_inner();
+    refilled.truncate(filled);
+    *vec = refilled;
+    res
+}
+
+/// A [`Seed`] that deserializes values of a type `ty` into the owned storage `place`.
+///
+/// Using the same `ValueSeed` for many values of the same type,
+/// e.g., the rows of a table, reuses the allocations of the previous value.
+pub struct ValueSeed<'a, T: Value> {
+    /// The type, combined with its context, of the values to deserialize.
+    ty: WithTypespace<'a, T::Type>,
+    /// The storage the values are deserialized into.
+    place: T,
+}
+
+impl<'a, T: Value> ValueSeed<'a, T> {
+    /// Returns a seed for values of type `ty` that will reuse the allocations of `place`.
+    ///
+    /// The contents of `place` are irrelevant and will be overwritten.
+    pub fn new(ty: WithTypespace<'a, T::Type>, place: T) -> Self {
+        Self { ty, place }
+    }
+
+    /// Returns the most recently deserialized value.
+    pub fn value(&self) -> &T {
+        &self.place
+    }
+
+    /// Returns the most recently deserialized value, consuming the seed.
+    pub fn into_value(self) -> T {
+        self.place
+    }
+}
+
+macro_rules! impl_value_seed {
+    ($($val:ty),*) => {
+        $(impl<'de> Seed<'de> for ValueSeed<'_, $val> {
+            fn deserialize_in_place<D: Deserializer<'de>>(&mut self, de: D) -> Result<(), D::Error> {
+                self.ty.fill(de, &mut self.place)
+            }
+        })*
+    };
+}
+
+impl_value_seed!(AlgebraicValue, ProductValue, SumValue, ArrayValue);
+
+impl<'de> Filler<'de> for WithTypespace<'_, AlgebraicType> {
+    type Place = AlgebraicValue;
+
+    fn blank(self) -> AlgebraicValue {
+        AlgebraicValue::UNIT
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut AlgebraicValue) -> Result<(), D::Error> {
+        match self.ty() {
+            AlgebraicType::Ref(r) => self.resolve(*r).fill(de, place),
+            AlgebraicType::Newtype(nt) => self.with(&*nt.inner).fill(de, place),
+            AlgebraicType::Sum(ty) => {
+                if !place.is_sum() {
+                    *place = AlgebraicValue::Sum(self.with(ty).blank());
+                }
+                self.with(ty).fill(de, place.as_sum_mut().unwrap())
+            }
+            AlgebraicType::Product(ty) => {
+                if !place.is_product() {
+                    *place = AlgebraicValue::Product(self.with(ty).blank());
+                }
+                self.with(ty).fill(de, place.as_product_mut().unwrap())
+            }
+            AlgebraicType::Builtin(ty) => self.with(ty).fill(de, place),
+        }
+    }
+}
+
+impl<'de> Filler<'de> for WithTypespace<'_, SumType> {
+    type Place = SumValue;
+
+    fn blank(self) -> SumValue {
+        SumValue {
+            tag: 0,
+            value: Box::new(AlgebraicValue::UNIT),
+        }
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut SumValue) -> Result<(), D::Error> {
+        de.deserialize_sum(RefillSum { ty: self, place })
+    }
+}
+
+/// The visitor refills a sum value,
+/// reusing the allocations of the old variant's payload even if the tag changes.
+struct RefillSum<'a, 'p> {
+    /// The type of the sum value.
+    ty: WithTypespace<'a, SumType>,
+    /// The sum value to refill.
+    place: &'p mut SumValue,
+}
+
+impl<'de> SumVisitor<'de> for RefillSum<'_, '_> {
+    type Output = ();
+
+    fn sum_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_option(&self) -> bool {
+        self.ty.ty().as_option().is_some()
+    }
+
+    fn visit_sum<A: SumAccess<'de>>(self, data: A) -> Result<Self::Output, A::Error> {
+        let (tag, data) = data.variant(self.ty)?;
+        let variant_ty = self.ty.map(|ty| &ty.variants[tag as usize].algebraic_type);
+        self.place.tag = tag;
+        data.deserialize_seed(fill_seed(variant_ty, &mut *self.place.value))
+    }
+}
+
+impl<'de> Filler<'de> for WithTypespace<'_, ProductType> {
+    type Place = ProductValue;
+
+    fn blank(self) -> ProductValue {
+        ProductValue {
+            elements: Vec::with_capacity(self.ty().elements.len()),
+        }
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut ProductValue) -> Result<(), D::Error> {
+        de.deserialize_product(RefillProduct { ty: self, place })
+    }
+}
+
+/// The visitor refills the elements of a product value in place.
+struct RefillProduct<'a, 'p> {
+    /// The type of the product value.
+    ty: WithTypespace<'a, ProductType>,
+    /// The product value to refill.
+    place: &'p mut ProductValue,
+}
+
+impl RefillProduct<'_, '_> {
+    /// Adjusts the number of elements to match the product type,
+    /// keeping the elements that can be reused.
+    fn fit_elements(&mut self) {
+        let len = self.ty.ty().elements.len();
+        self.place.elements.truncate(len);
+        self.place.elements.resize_with(len, || AlgebraicValue::UNIT);
+    }
+}
+
+impl<'de> ProductVisitor<'de> for RefillProduct<'_, '_> {
+    type Output = ();
+
+    fn product_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn product_len(&self) -> usize {
+        self.ty.ty().elements.len()
+    }
+
+    fn visit_seq_product<A: SeqProductAccess<'de>>(mut self, mut tup: A) -> Result<Self::Output, A::Error> {
+        self.fit_elements();
+        let ty = self.ty;
+        let elems = ty.map(|ty| &*ty.elements);
+        for (i, (el, place)) in elems.ty().iter().zip(&mut self.place.elements).enumerate() {
+            tup.next_element_seed(fill_seed(elems.with(&el.algebraic_type), place))
+                .map_err(|e| e.in_field(i, el.name()))?
+                .ok_or_else(|| Error::invalid_product_length(i, &ty))?;
+        }
+        Ok(())
+    }
+
+    fn visit_named_product<A: NamedProductAccess<'de>>(mut self, mut tup: A) -> Result<Self::Output, A::Error> {
+        self.fit_elements();
+        let ty = self.ty;
+        let elems_tys = ty.map(|ty| &*ty.elements);
+        let elems = elems_tys.ty();
+        let kind = ty.product_kind();
+        let mut filled = vec![false; elems.len()];
+
+        // Like `visit_named_product`, fields may arrive in any order.
+        for _ in 0..elems.len() {
+            let index = tup.get_field_ident(TupleNameVisitor { elems, kind })?.ok_or_else(|| {
+                let missing = filled.iter().position(|f| !f).unwrap();
+                Error::missing_field(missing, elems[missing].name(), &ty)
+            })?;
+
+            if filled[index] {
+                return Err(Error::duplicate_field(index, elems[index].name(), &ty));
+            }
+            filled[index] = true;
+
+            let place = &mut self.place.elements[index];
+            tup.get_field_value_seed(fill_seed(elems_tys.with(&elems[index].algebraic_type), place))
+                .map_err(|e| e.in_field(index, elems[index].name()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Filler<'de> for WithTypespace<'_, BuiltinType> {
+    type Place = AlgebraicValue;
+
+    fn blank(self) -> AlgebraicValue {
+        AlgebraicValue::Bool(false)
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut AlgebraicValue) -> Result<(), D::Error> {
+        match self.ty() {
+            BuiltinType::String => {
+                if !place.is_string() {
+                    *place = AlgebraicValue::String(<Box<str>>::default());
+                }
+                place.as_string_mut().unwrap().deserialize_in_place(de)
+            }
+            BuiltinType::Array(ty) => {
+                if !place.is_array() {
+                    *place = AlgebraicValue::Array(self.with(ty).blank());
+                }
+                self.with(ty).fill(de, place.as_array_mut().unwrap())
+            }
+            // Other builtins have no allocations to reuse, or in the case of maps,
+            // none that can be reused easily, so we overwrite them wholesale.
+            _ => {
+                *place = self.deserialize(de)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'de> Filler<'de> for WithTypespace<'_, ArrayType> {
+    type Place = ArrayValue;
+
+    fn blank(self) -> ArrayValue {
+        ArrayValue::default()
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut ArrayValue) -> Result<(), D::Error> {
+        /// Makes sure `place` is of the given variant and then refills that variant's vector.
+        macro_rules! refill {
+            ($var:ident, $filler:expr) => {{
+                if !matches!(place, ArrayValue::$var(_)) {
+                    *place = ArrayValue::$var(Vec::new());
+                }
+                let ArrayValue::$var(vec) = place else {
+                    unreachable!()
+                };
+                refill_vec(de, $filler, vec)
+            }};
+            ($var:ident) => {
+                refill!($var, PlainFiller(PhantomData))
+            };
+        }
+
+        let mut ty = &*self.ty().elem_ty;
+
+        // Loop, resolving `Ref`s and unwrapping newtypes, until we reach a structural type.
+        loop {
+            break match ty {
+                AlgebraicType::Ref(r) => {
+                    ty = self.resolve(*r).ty();
+                    continue;
+                }
+                AlgebraicType::Newtype(nt) => {
+                    ty = &nt.inner;
+                    continue;
+                }
+                AlgebraicType::Sum(ty) => refill!(Sum, self.with(ty)),
+                AlgebraicType::Product(ty) => refill!(Product, self.with(ty)),
+                AlgebraicType::Builtin(BuiltinType::Bool) => refill!(Bool),
+                AlgebraicType::Builtin(BuiltinType::I8) => refill!(I8),
+                AlgebraicType::Builtin(BuiltinType::U8) => {
+                    if !matches!(place, ArrayValue::U8(_)) {
+                        *place = ArrayValue::U8(Vec::new());
+                    }
+                    let ArrayValue::U8(vec) = place else { unreachable!() };
+                    vec.deserialize_in_place(de)
+                }
+                AlgebraicType::Builtin(BuiltinType::I16) => refill!(I16),
+                AlgebraicType::Builtin(BuiltinType::U16) => refill!(U16),
+                AlgebraicType::Builtin(BuiltinType::I32) => refill!(I32),
+                AlgebraicType::Builtin(BuiltinType::U32) => refill!(U32),
+                AlgebraicType::Builtin(BuiltinType::I64) => refill!(I64),
+                AlgebraicType::Builtin(BuiltinType::U64) => refill!(U64),
+                AlgebraicType::Builtin(BuiltinType::I128) => refill!(I128),
+                AlgebraicType::Builtin(BuiltinType::U128) => refill!(U128),
+                AlgebraicType::Builtin(BuiltinType::F32) => refill!(F32),
+                AlgebraicType::Builtin(BuiltinType::F64) => refill!(F64),
+                AlgebraicType::Builtin(BuiltinType::String) => refill!(String),
+                AlgebraicType::Builtin(BuiltinType::Array(ty)) => refill!(Array, self.with(ty)),
+                AlgebraicType::Builtin(BuiltinType::Map(ty)) => refill!(Map, self.with(ty)),
+            };
+        }
+    }
+}
+
+impl<'de> Filler<'de> for WithTypespace<'_, MapType> {
+    type Place = crate::MapValue;
+
+    fn blank(self) -> crate::MapValue {
+        <_>::default()
+    }
+
+    fn fill<D: Deserializer<'de>>(self, de: D, place: &mut crate::MapValue) -> Result<(), D::Error> {
+        *place = self.deserialize(de)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValueSeed;
+    use crate::{
+        bsatn, product, AlgebraicType, ProductType, ProductTypeElement, ProductValue, Typespace, WithTypespace,
+    };
+
+    fn row_ty() -> ProductType {
+        ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::U64), "scores"),
+            ProductTypeElement::new_named(AlgebraicType::bytes(), "blob"),
+        ])
+    }
+
+    fn row(id: u32, name: &str, scores: Vec<u64>, blob: Vec<u8>) -> ProductValue {
+        product![
+            id,
+            name.to_owned(),
+            crate::AlgebraicValue::ArrayOf(scores),
+            crate::AlgebraicValue::Bytes(blob)
+        ]
+    }
+
+    #[test]
+    fn refill_product_value_matches_fresh_decode() {
+        let ts = Typespace::default();
+        let ty = row_ty();
+        let mut seed = ValueSeed::new(WithTypespace::new(&ts, &ty), ProductValue::new(&[]));
+
+        let rows = [
+            row(1, "a rather long name", vec![1, 2, 3, 4], vec![9; 16]),
+            row(2, "short", vec![5], vec![]),
+            row(3, "", vec![], vec![1, 2]),
+            row(4, "grown back to a longer name", vec![6, 7, 8, 9, 10, 11], vec![3; 32]),
+        ];
+        for row in &rows {
+            let bytes = bsatn::to_vec(row).unwrap();
+            bsatn::from_slice_seeded(&bytes, &mut seed).unwrap();
+            // No stale data from the previous, potentially longer, row may remain.
+            assert_eq!(seed.value(), row);
+        }
+    }
+
+    #[test]
+    fn refill_reuses_allocations() {
+        let mut name = String::with_capacity(64);
+        name.push_str("a value that will be overwritten");
+        let ptr = name.as_ptr();
+
+        let bytes = bsatn::to_vec("short").unwrap();
+        bsatn::from_slice_seeded(&bytes, &mut name).unwrap();
+        assert_eq!(name, "short");
+        assert_eq!(name.as_ptr(), ptr);
+
+        let mut names = vec![String::with_capacity(16), String::with_capacity(16)];
+        let ptrs = names.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+        let bytes = bsatn::to_vec(&["x".to_owned(), "y".to_owned()][..]).unwrap();
+        bsatn::from_slice_seeded(&bytes, &mut names).unwrap();
+        assert_eq!(names, ["x", "y"]);
+        assert_eq!(names.iter().map(|s| s.as_ptr()).collect::<Vec<_>>(), ptrs);
+
+        let bytes = bsatn::to_vec(&["z".to_owned()][..]).unwrap();
+        bsatn::from_slice_seeded(&bytes, &mut names).unwrap();
+        assert_eq!(names, ["z"]);
+    }
+
+    #[test]
+    fn refill_bytes() {
+        let mut buf = vec![0u8; 8];
+        let bytes = bsatn::to_vec(&[1u8, 2, 3][..]).unwrap();
+        bsatn::from_slice_seeded(&bytes, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3]);
+        assert!(buf.capacity() >= 8);
+    }
+}
@@ -1,7 +1,12 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::fmt;
 use std::marker::PhantomData;
+use std::rc::Rc;
 
 use super::Deserializer;
+use crate::codec::BytesFormat;
+use crate::{AlgebraicValue, MapValue};
 use ::serde::de as serde;
 
 /// Converts any [`serde::Deserializer`] to a SATS [`Deserializer`]
@@ -9,15 +14,113 @@ use ::serde::de as serde;
 pub struct SerdeDeserializer<D> {
     /// A deserialization data format in Serde.
     de: D,
+    /// What to do with fields unknown to a named product, and how byte arrays are read from text.
+    policy: Policy,
 }
 
 impl<D> SerdeDeserializer<D> {
     /// Wraps a Serde deserializer.
+    ///
+    /// Fields unknown to a named product are rejected, as with [`UnknownFields::Deny`].
     pub fn new(de: D) -> Self {
-        Self { de }
+        Self {
+            de,
+            policy: Policy {
+                unknown: Unknown::Deny,
+                bytes: BytesFormat::Hex,
+            },
+        }
+    }
+
+    /// Sets how fields of a named product that its type does not have are treated,
+    /// both in the value deserialized and in all values nested within it.
+    pub fn unknown_fields(mut self, mode: UnknownFields) -> Self {
+        self.policy.unknown = match mode {
+            UnknownFields::Deny => Unknown::Deny,
+            UnknownFields::Ignore => Unknown::Ignore,
+            UnknownFields::Collect => Unknown::Collect(CollectedFields::default()),
+        };
+        self
+    }
+
+    /// Sets how byte arrays written as strings are decoded,
+    /// both in the value deserialized and in all values nested within it.
+    ///
+    /// Defaults to [`BytesFormat::Hex`].
+    /// Byte arrays written as bytes or as sequences of numbers are read regardless.
+    pub fn bytes_format(mut self, bytes: BytesFormat) -> Self {
+        self.policy.bytes = bytes;
+        self
+    }
+
+    /// Returns a handle to the fields collected under [`UnknownFields::Collect`].
+    ///
+    /// Under any other mode, the handle stays empty.
+    pub fn collected_fields(&self) -> CollectedFields {
+        match &self.policy.unknown {
+            Unknown::Collect(fields) => fields.clone(),
+            _ => CollectedFields::default(),
+        }
     }
 }
 
+/// How a [`SerdeDeserializer`] treats a field of a named product
+/// that the type being deserialized does not have.
+///
+/// This only concerns self-describing formats such as JSON.
+/// BSATN is positional, so it has no notion of an unknown field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownFields {
+    /// Unknown fields are an error.
+    #[default]
+    Deny,
+    /// Unknown fields are skipped, along with their values.
+    Ignore,
+    /// Unknown fields are skipped, but their values are kept in
+    /// the [`CollectedFields`] of the deserializer.
+    Collect,
+}
+
+/// The unknown fields gathered by a [`SerdeDeserializer`] under [`UnknownFields::Collect`].
+///
+/// The handle shares its storage with the deserializer,
+/// so it can be read once the value has been deserialized.
+#[derive(Clone, Default)]
+pub struct CollectedFields(Rc<RefCell<MapValue>>);
+
+impl CollectedFields {
+    /// Takes out the fields collected so far, leaving the handle empty.
+    ///
+    /// The map is keyed by field name. As the formats are self-describing
+    /// but the field isn't typed, values are kept in the shape they were written in:
+    /// sequences as products, objects as maps, and `null` as the unit.
+    pub fn take(&self) -> MapValue {
+        self.0.take()
+    }
+
+    /// Records that the field `name` with `value` was found.
+    fn insert(&self, name: String, value: AlgebraicValue) {
+        self.0.borrow_mut().insert(AlgebraicValue::String(name.into()), value);
+    }
+}
+
+/// The options of a [`SerdeDeserializer`] threaded through the nested Serde visitors.
+#[derive(Clone)]
+struct Policy {
+    /// The [`UnknownFields`] mode.
+    unknown: Unknown,
+    /// How byte arrays are read from strings.
+    bytes: BytesFormat,
+}
+
+/// The [`UnknownFields`] mode, with the storage for the collected fields.
+#[derive(Clone)]
+enum Unknown {
+    Deny,
+    Ignore,
+    Collect(CollectedFields),
+}
+
 /// An error that occured when deserializing SATS to a Serde data format.
 #[repr(transparent)]
 pub struct SerdeError<E>(pub E);
@@ -46,17 +149,21 @@ impl<'de, D: serde::Deserializer<'de>> Deserializer<'de> for SerdeDeserializer<D
     type Error = SerdeError<D::Error>;
 
     fn deserialize_product<V: super::ProductVisitor<'de>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        let policy = self.policy;
         self.de
-            .deserialize_struct("", &[], TupleVisitor { visitor })
+            .deserialize_struct("", &[], TupleVisitor { visitor, policy })
             .map_err(SerdeError)
     }
 
     fn deserialize_sum<V: super::SumVisitor<'de>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        let policy = self.policy;
         if visitor.is_option() && self.de.is_human_readable() {
-            self.de.deserialize_any(OptionVisitor { visitor }).map_err(SerdeError)
+            self.de
+                .deserialize_any(OptionVisitor { visitor, policy })
+                .map_err(SerdeError)
         } else {
             self.de
-                .deserialize_enum("", &[], EnumVisitor { visitor })
+                .deserialize_enum("", &[], EnumVisitor { visitor, policy })
                 .map_err(SerdeError)
         }
     }
@@ -106,10 +213,15 @@ impl<'de, D: serde::Deserializer<'de>> Deserializer<'de> for SerdeDeserializer<D
     }
 
     fn deserialize_bytes<V: super::SliceVisitor<'de, [u8]>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        let bytes = self.policy.bytes;
         if self.de.is_human_readable() {
-            self.de.deserialize_any(BytesVisitor { visitor }).map_err(SerdeError)
+            self.de
+                .deserialize_any(BytesVisitor { visitor, bytes })
+                .map_err(SerdeError)
         } else {
-            self.de.deserialize_bytes(BytesVisitor { visitor }).map_err(SerdeError)
+            self.de
+                .deserialize_bytes(BytesVisitor { visitor, bytes })
+                .map_err(SerdeError)
         }
     }
 
@@ -118,8 +230,9 @@ impl<'de, D: serde::Deserializer<'de>> Deserializer<'de> for SerdeDeserializer<D
         visitor: V,
         seed: T,
     ) -> Result<V::Output, Self::Error> {
+        let policy = self.policy;
         self.de
-            .deserialize_seq(ArrayVisitor { visitor, seed })
+            .deserialize_seq(ArrayVisitor { visitor, seed, policy })
             .map_err(SerdeError)
     }
 
@@ -133,8 +246,14 @@ impl<'de, D: serde::Deserializer<'de>> Deserializer<'de> for SerdeDeserializer<D
         kseed: K,
         vseed: V,
     ) -> Result<Vi::Output, Self::Error> {
+        let policy = self.policy;
         self.de
-            .deserialize_map(MapVisitor { visitor, kseed, vseed })
+            .deserialize_map(MapVisitor {
+                visitor,
+                kseed,
+                vseed,
+                policy,
+            })
             .map_err(SerdeError)
     }
 }
@@ -158,7 +277,26 @@ impl<'de, T: super::DeserializeSeed<'de>> serde::DeserializeSeed<'de> for SeedWr
     where
         D: ::serde::Deserializer<'de>,
     {
-        self.0.deserialize(SerdeDeserializer { de }).map_err(unwrap_error)
+        self.0.deserialize(SerdeDeserializer::new(de)).map_err(unwrap_error)
+    }
+}
+
+/// Like [`SeedWrapper`], but keeps the [`Policy`] of the deserializer it came from.
+struct PolicySeed<T> {
+    /// The SATS seed.
+    seed: T,
+    /// Passed on to the nested deserializer.
+    policy: Policy,
+}
+
+impl<'de, T: super::DeserializeSeed<'de>> serde::DeserializeSeed<'de> for PolicySeed<T> {
+    type Value = T::Output;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+        let policy = self.policy;
+        self.seed
+            .deserialize(SerdeDeserializer { de, policy })
+            .map_err(unwrap_error)
     }
 }
 
@@ -166,6 +304,8 @@ impl<'de, T: super::DeserializeSeed<'de>> serde::DeserializeSeed<'de> for SeedWr
 struct TupleVisitor<V> {
     /// The `ProductVisitor` to convert.
     visitor: V,
+    /// What to do with unknown fields.
+    policy: Policy,
 }
 
 impl<'de, V: super::ProductVisitor<'de>> serde::Visitor<'de> for TupleVisitor<V> {
@@ -181,13 +321,19 @@ impl<'de, V: super::ProductVisitor<'de>> serde::Visitor<'de> for TupleVisitor<V>
 
     fn visit_map<A: serde::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
         self.visitor
-            .visit_named_product(NamedTupleAccess { map })
+            .visit_named_product(NamedTupleAccess {
+                map,
+                policy: self.policy,
+            })
             .map_err(unwrap_error)
     }
 
     fn visit_seq<A: serde::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
         self.visitor
-            .visit_seq_product(SeqTupleAccess { seq })
+            .visit_seq_product(SeqTupleAccess {
+                seq,
+                policy: self.policy,
+            })
             .map_err(unwrap_error)
     }
 }
@@ -197,6 +343,8 @@ impl<'de, V: super::ProductVisitor<'de>> serde::Visitor<'de> for TupleVisitor<V>
 struct NamedTupleAccess<A> {
     /// An implementation of `serde::MapAccess<'de>` to convert.
     map: A,
+    /// What to do with unknown fields.
+    policy: Policy,
 }
 
 impl<'de, A: serde::MapAccess<'de>> super::NamedProductAccess<'de> for NamedTupleAccess<A> {
@@ -206,11 +354,144 @@ impl<'de, A: serde::MapAccess<'de>> super::NamedProductAccess<'de> for NamedTupl
         &mut self,
         visitor: V,
     ) -> Result<Option<V::Output>, Self::Error> {
-        self.map.next_key_seed(FieldNameVisitor { visitor }).map_err(SerdeError)
+        if let Unknown::Deny = self.policy.unknown {
+            return self.map.next_key_seed(FieldNameVisitor { visitor }).map_err(SerdeError);
+        }
+
+        // Skip past unknown fields until we find one the visitor knows.
+        while let Some(name) = self.map.next_key_seed(FieldName).map_err(SerdeError)? {
+            if visitor.is_field_name(&name) {
+                return visitor.visit(&name).map(Some);
+            }
+            match &self.policy.unknown {
+                Unknown::Collect(fields) => {
+                    let value = self.map.next_value_seed(AnyValue).map_err(SerdeError)?;
+                    fields.insert(name.into_owned(), value);
+                }
+                _ => {
+                    self.map.next_value::<serde::IgnoredAny>().map_err(SerdeError)?;
+                }
+            }
+        }
+        Ok(None)
     }
 
     fn get_field_value_seed<T: super::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Output, Self::Error> {
-        self.map.next_value_seed(SeedWrapper(seed)).map_err(SerdeError)
+        let policy = self.policy.clone();
+        self.map
+            .next_value_seed(PolicySeed { seed, policy })
+            .map_err(SerdeError)
+    }
+}
+
+/// Deserializes a field name, borrowing it from the input if possible.
+struct FieldName;
+
+impl<'de> serde::DeserializeSeed<'de> for FieldName {
+    type Value = Cow<'de, str>;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> serde::Visitor<'de> for FieldName {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a tuple field")
+    }
+
+    fn visit_str<E: serde::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v.to_owned()))
+    }
+
+    fn visit_borrowed_str<E: serde::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(Cow::Borrowed(v))
+    }
+
+    fn visit_string<E: serde::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(v))
+    }
+}
+
+/// Deserializes any value of a self-describing format as an untyped `AlgebraicValue`.
+///
+/// Used to keep the values of unknown fields.
+struct AnyValue;
+
+impl<'de> serde::DeserializeSeed<'de> for AnyValue {
+    type Value = AlgebraicValue;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> serde::Visitor<'de> for AnyValue {
+    type Value = AlgebraicValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: serde::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::Bool(v))
+    }
+    fn visit_i64<E: serde::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::I64(v))
+    }
+    fn visit_i128<E: serde::Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::I128(v))
+    }
+    fn visit_u64<E: serde::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::U64(v))
+    }
+    fn visit_u128<E: serde::Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::U128(v))
+    }
+    fn visit_f64<E: serde::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::F64(v.into()))
+    }
+    fn visit_str<E: serde::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::String(v.into()))
+    }
+    fn visit_string<E: serde::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::String(v.into()))
+    }
+    fn visit_bytes<E: serde::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::Bytes(v.to_owned()))
+    }
+    fn visit_byte_buf<E: serde::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::Bytes(v))
+    }
+    fn visit_unit<E: serde::Error>(self) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::UNIT)
+    }
+    fn visit_none<E: serde::Error>(self) -> Result<Self::Value, E> {
+        Ok(AlgebraicValue::UNIT)
+    }
+    fn visit_some<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        serde::DeserializeSeed::deserialize(self, deserializer)
+    }
+    fn visit_newtype_struct<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        serde::DeserializeSeed::deserialize(self, deserializer)
+    }
+
+    fn visit_seq<A: serde::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut elements = Vec::with_capacity(std::cmp::min(seq.size_hint().unwrap_or(0), 4096));
+        while let Some(elem) = seq.next_element_seed(AnyValue)? {
+            elements.push(elem);
+        }
+        Ok(AlgebraicValue::product(elements))
+    }
+
+    fn visit_map<A: serde::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = MapValue::new();
+        while let Some((key, value)) = map.next_entry_seed(AnyValue, AnyValue)? {
+            entries.insert(key, value);
+        }
+        Ok(AlgebraicValue::map(entries))
     }
 }
 
@@ -249,13 +530,19 @@ impl<'de, V: super::FieldNameVisitor<'de>> serde::Visitor<'de> for FieldNameVisi
 struct SeqTupleAccess<A> {
     /// The `serde::SeqAccess` to convert.
     seq: A,
+    /// Passed on to the elements.
+    policy: Policy,
 }
 
 impl<'de, A: serde::SeqAccess<'de>> super::SeqProductAccess<'de> for SeqTupleAccess<A> {
     type Error = SerdeError<A::Error>;
 
     fn next_element_seed<T: super::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Output>, Self::Error> {
-        let res = self.seq.next_element_seed(SeedWrapper(seed)).map_err(SerdeError)?;
+        let policy = self.policy.clone();
+        let res = self
+            .seq
+            .next_element_seed(PolicySeed { seed, policy })
+            .map_err(SerdeError)?;
         Ok(res)
     }
 }
@@ -264,6 +551,8 @@ impl<'de, A: serde::SeqAccess<'de>> super::SeqProductAccess<'de> for SeqTupleAcc
 struct OptionVisitor<V> {
     /// The visitor to convert.
     visitor: V,
+    /// Passed on to the `some` value.
+    policy: Policy,
 }
 
 impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for OptionVisitor<V> {
@@ -274,7 +563,9 @@ impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for OptionVisitor<V> {
     }
 
     fn visit_map<A: serde::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
-        self.visitor.visit_sum(SomeAccess(map)).map_err(unwrap_error)
+        self.visitor
+            .visit_sum(SomeAccess(map, self.policy))
+            .map_err(unwrap_error)
     }
 
     fn visit_unit<E: serde::Error>(self) -> Result<Self::Value, E> {
@@ -284,7 +575,7 @@ impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for OptionVisitor<V> {
 
 /// Deserializes `some` variant of an optional value.
 /// Converts Serde's map deserialization to SATS.
-struct SomeAccess<A>(A);
+struct SomeAccess<A>(A, Policy);
 
 impl<'de, A: serde::MapAccess<'de>> super::SumAccess<'de> for SomeAccess<A> {
     type Error = SerdeError<A::Error>;
@@ -304,7 +595,11 @@ impl<'de, A: serde::MapAccess<'de>> super::VariantAccess<'de> for SomeAccess<A>
     type Error = SerdeError<A::Error>;
 
     fn deserialize_seed<T: super::DeserializeSeed<'de>>(mut self, seed: T) -> Result<T::Output, Self::Error> {
-        let ret = self.0.next_value_seed(SeedWrapper(seed)).map_err(SerdeError)?;
+        let policy = self.1;
+        let ret = self
+            .0
+            .next_value_seed(PolicySeed { seed, policy })
+            .map_err(SerdeError)?;
         self.0.next_key_seed(NothingVisitor).map_err(SerdeError)?;
         Ok(ret)
     }
@@ -351,6 +646,8 @@ impl<'de, E: super::Error> super::VariantAccess<'de> for NoneAccess<E> {
 struct EnumVisitor<V> {
     /// The `SumVisitor`.
     visitor: V,
+    /// Passed on to the variant's data.
+    policy: Policy,
 }
 
 impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for EnumVisitor<V> {
@@ -361,7 +658,10 @@ impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for EnumVisitor<V> {
     }
 
     fn visit_enum<A: serde::EnumAccess<'de>>(self, access: A) -> Result<Self::Value, A::Error> {
-        self.visitor.visit_sum(EnumAccess { access }).map_err(unwrap_error)
+        let policy = self.policy;
+        self.visitor
+            .visit_sum(EnumAccess { access, policy })
+            .map_err(unwrap_error)
     }
 }
 
@@ -369,6 +669,8 @@ impl<'de, V: super::SumVisitor<'de>> serde::Visitor<'de> for EnumVisitor<V> {
 struct EnumAccess<A> {
     /// The Serde `EnumAccess`.
     access: A,
+    /// Passed on to the variant's data.
+    policy: Policy,
 }
 
 impl<'de, A: serde::EnumAccess<'de>> super::SumAccess<'de> for EnumAccess<A> {
@@ -376,9 +678,10 @@ impl<'de, A: serde::EnumAccess<'de>> super::SumAccess<'de> for EnumAccess<A> {
     type Variant = VariantAccess<A::Variant>;
 
     fn variant<V: super::VariantVisitor>(self, visitor: V) -> Result<(V::Output, Self::Variant), Self::Error> {
+        let policy = self.policy;
         self.access
             .variant_seed(VariantVisitor { visitor })
-            .map(|(variant, access)| (variant, VariantAccess { access }))
+            .map(|(variant, access)| (variant, VariantAccess { access, policy }))
             .map_err(SerdeError)
     }
 }
@@ -427,13 +730,18 @@ impl<V: super::VariantVisitor> serde::Visitor<'_> for VariantVisitor<V> {
 struct VariantAccess<A> {
     // Implements `serde::VariantAccess`.
     access: A,
+    /// Passed on to the variant's data.
+    policy: Policy,
 }
 
 impl<'de, A: serde::VariantAccess<'de>> super::VariantAccess<'de> for VariantAccess<A> {
     type Error = SerdeError<A::Error>;
 
     fn deserialize_seed<T: super::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Output, Self::Error> {
-        self.access.newtype_variant_seed(SeedWrapper(seed)).map_err(SerdeError)
+        let policy = self.policy;
+        self.access
+            .newtype_variant_seed(PolicySeed { seed, policy })
+            .map_err(SerdeError)
     }
 }
 
@@ -469,6 +777,8 @@ impl<'de, V: super::SliceVisitor<'de, str>> serde::Visitor<'de> for StrVisitor<V
 struct BytesVisitor<V> {
     /// The `SliceVisitor<'de, [u8]>`.
     visitor: V,
+    /// How a byte array written as a string is decoded.
+    bytes: BytesFormat,
 }
 
 impl<'de, V: super::SliceVisitor<'de, [u8]>> serde::Visitor<'de> for BytesVisitor<V> {
@@ -491,7 +801,11 @@ impl<'de, V: super::SliceVisitor<'de, [u8]>> serde::Visitor<'de> for BytesVisito
     }
 
     fn visit_str<E: serde::Error>(self, v: &str) -> Result<Self::Value, E> {
-        let data = hex_string(v, &self)?;
+        let data = match self.bytes {
+            BytesFormat::Hex => hex_string(v, &self)?,
+            #[cfg(feature = "base64")]
+            BytesFormat::Base64 => base64_string(v, &self)?,
+        };
         self.visitor.visit_owned(data).map_err(unwrap_error)
     }
 
@@ -512,6 +826,13 @@ fn hex_string<T: hex::FromHex<Error = hex::FromHexError>, E: serde::Error>(
     T::from_hex(v).map_err(|_| serde::Error::invalid_value(serde::Unexpected::Str(v), exp))
 }
 
+/// Base64 decodes the string `v`.
+#[cfg(feature = "base64")]
+fn base64_string<E: serde::Error>(v: &str, exp: &dyn serde::Expected) -> Result<Vec<u8>, E> {
+    crate::codec::base64::base64_to_array_value(v)
+        .map_err(|_| serde::Error::invalid_value(serde::Unexpected::Str(v), exp))
+}
+
 // struct HashVisitor;
 
 // impl<'de> serde::Visitor<'de> for HashVisitor {
@@ -547,6 +868,8 @@ struct ArrayVisitor<V, T> {
     visitor: V,
     /// The seed value to provide to `DeserializeSeed`.
     seed: T,
+    /// Passed on to the elements.
+    policy: Policy,
 }
 
 impl<'de, T: super::DeserializeSeed<'de> + Clone, V: super::ArrayVisitor<'de, T::Output>> serde::Visitor<'de>
@@ -560,7 +883,11 @@ impl<'de, T: super::DeserializeSeed<'de> + Clone, V: super::ArrayVisitor<'de, T:
 
     fn visit_seq<A: serde::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
         self.visitor
-            .visit(ArrayAccess { seq, seed: self.seed })
+            .visit(ArrayAccess {
+                seq,
+                seed: self.seed,
+                policy: self.policy,
+            })
             .map_err(unwrap_error)
     }
 }
@@ -572,6 +899,8 @@ struct ArrayAccess<A, T> {
     seq: A,
     /// The seed to pass onto `DeserializeSeed`.
     seed: T,
+    /// Passed on to the elements.
+    policy: Policy,
 }
 
 impl<'de, A: serde::SeqAccess<'de>, T: super::DeserializeSeed<'de> + Clone> super::ArrayAccess<'de>
@@ -582,7 +911,10 @@ impl<'de, A: serde::SeqAccess<'de>, T: super::DeserializeSeed<'de> + Clone> supe
 
     fn next_element(&mut self) -> Result<Option<T::Output>, Self::Error> {
         self.seq
-            .next_element_seed(SeedWrapper(self.seed.clone()))
+            .next_element_seed(PolicySeed {
+                seed: self.seed.clone(),
+                policy: self.policy.clone(),
+            })
             .map_err(SerdeError)
     }
 
@@ -602,6 +934,8 @@ struct MapVisitor<Vi, K, V> {
     /// The seed value to provide to `DeserializeSeed` for deserializing values.
     /// As this is reused for every entry element, it will be `.cloned()`.
     vseed: V,
+    /// Passed on to the keys and values.
+    policy: Policy,
 }
 
 impl<
@@ -623,6 +957,7 @@ impl<
                 map,
                 kseed: self.kseed,
                 vseed: self.vseed,
+                policy: self.policy,
             })
             .map_err(unwrap_error)
     }
@@ -637,6 +972,8 @@ struct MapAccess<A, K, V> {
     /// The seed value to provide to `DeserializeSeed` for deserializing values.
     /// As this is reused for every entry element, it will be `.cloned()`.
     vseed: V,
+    /// Passed on to the keys and values.
+    policy: Policy,
 }
 
 impl<'de, A: serde::MapAccess<'de>, K: super::DeserializeSeed<'de> + Clone, V: super::DeserializeSeed<'de> + Clone>
@@ -648,7 +985,16 @@ impl<'de, A: serde::MapAccess<'de>, K: super::DeserializeSeed<'de> + Clone, V: s
 
     fn next_entry(&mut self) -> Result<Option<(Self::Key, Self::Value)>, Self::Error> {
         self.map
-            .next_entry_seed(SeedWrapper(self.kseed.clone()), SeedWrapper(self.vseed.clone()))
+            .next_entry_seed(
+                PolicySeed {
+                    seed: self.kseed.clone(),
+                    policy: self.policy.clone(),
+                },
+                PolicySeed {
+                    seed: self.vseed.clone(),
+                    policy: self.policy.clone(),
+                },
+            )
             .map_err(SerdeError)
     }
 
@@ -692,3 +1038,113 @@ macro_rules! delegate_serde {
 }
 
 delegate_serde!(crate::AlgebraicType, crate::ProductType, crate::SumType);
+
+#[cfg(test)]
+mod tests {
+    use super::{SerdeDeserializer, UnknownFields};
+    use crate::de::{Deserialize, DeserializeSeed};
+    use crate::{
+        product, AlgebraicType, AlgebraicValue, MapValue, ProductType, ProductTypeElement, Typespace, WithTypespace,
+    };
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[sats(crate = crate)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[sats(crate = crate)]
+    struct Line {
+        from: Point,
+        to: Point,
+    }
+
+    const FLAT: &str = r#"{"x": 1, "z": 3, "y": 2}"#;
+    const NESTED: &str = r#"{
+        "from": {"x": 1, "y": 2},
+        "extra": {"a": [1, {"b": [[2, "}"], {"c": null}]}], "d": true},
+        "to": {"x": 3, "y": 4, "w": -5}
+    }"#;
+
+    fn from_json<T: for<'de> Deserialize<'de>>(json: &str, mode: UnknownFields) -> serde_json::Result<(T, MapValue)> {
+        let mut de = serde_json::Deserializer::from_str(json);
+        let de = SerdeDeserializer::new(&mut de).unknown_fields(mode);
+        let collected = de.collected_fields();
+        let value = T::deserialize(de).map_err(|e| e.0)?;
+        Ok((value, collected.take()))
+    }
+
+    fn field(name: &str, value: AlgebraicValue) -> (AlgebraicValue, AlgebraicValue) {
+        (AlgebraicValue::String(name.into()), value)
+    }
+
+    #[test]
+    fn deny_rejects_unknown_fields() {
+        let err = from_json::<Point>(FLAT, UnknownFields::Deny).unwrap_err();
+        assert!(err.to_string().contains("unknown field `z`"), "{err}");
+        assert!(from_json::<Line>(NESTED, UnknownFields::Deny).is_err());
+        // The default is to deny.
+        let mut de = serde_json::Deserializer::from_str(FLAT);
+        assert!(Point::deserialize(SerdeDeserializer::new(&mut de)).is_err());
+    }
+
+    #[test]
+    fn ignore_skips_unknown_fields() {
+        let (point, collected) = from_json::<Point>(FLAT, UnknownFields::Ignore).unwrap();
+        assert_eq!(point, Point { x: 1, y: 2 });
+        assert!(collected.is_empty());
+
+        let (line, collected) = from_json::<Line>(NESTED, UnknownFields::Ignore).unwrap();
+        let (from, to) = (Point { x: 1, y: 2 }, Point { x: 3, y: 4 });
+        assert_eq!(line, Line { from, to });
+        assert!(collected.is_empty());
+    }
+
+    #[test]
+    fn collect_keeps_unknown_fields() {
+        let (point, collected) = from_json::<Point>(FLAT, UnknownFields::Collect).unwrap();
+        assert_eq!(point, Point { x: 1, y: 2 });
+        assert_eq!(collected, [field("z", AlgebraicValue::U64(3))].into());
+
+        let (line, collected) = from_json::<Line>(NESTED, UnknownFields::Collect).unwrap();
+        let (from, to) = (Point { x: 1, y: 2 }, Point { x: 3, y: 4 });
+        assert_eq!(line, Line { from, to });
+        let b = product![
+            product![2u64, "}".to_owned()],
+            AlgebraicValue::map([field("c", AlgebraicValue::UNIT)].into())
+        ];
+        let a = product![1u64, AlgebraicValue::map([field("b", b.into())].into())];
+        let extra = AlgebraicValue::map([field("a", a.into()), field("d", AlgebraicValue::Bool(true))].into());
+        let expected = [field("extra", extra), field("w", AlgebraicValue::I64(-5))].into();
+        assert_eq!(collected, expected);
+    }
+
+    #[test]
+    fn typed_products_honor_the_mode() {
+        let ts = Typespace::default();
+        let ty = AlgebraicType::Product(ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "x"),
+            ProductTypeElement::new_named(AlgebraicType::U32, "y"),
+        ]));
+        let decode = |mode| {
+            let mut de = serde_json::Deserializer::from_str(FLAT);
+            let de = SerdeDeserializer::new(&mut de).unknown_fields(mode);
+            let collected = de.collected_fields();
+            WithTypespace::new(&ts, &ty)
+                .deserialize(de)
+                .map(|value| (value, collected.take()))
+                .map_err(|e| e.0)
+        };
+
+        assert!(decode(UnknownFields::Deny).is_err());
+        let expected = AlgebraicValue::Product(product![1u32, 2u32]);
+        let (value, collected) = decode(UnknownFields::Ignore).unwrap();
+        assert_eq!(value, expected);
+        assert!(collected.is_empty());
+        let (value, collected) = decode(UnknownFields::Collect).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(collected, [field("z", AlgebraicValue::U64(3))].into());
+    }
+}
@@ -1,18 +1,28 @@
 pub mod algebraic_type;
 mod algebraic_type_ref;
 pub mod algebraic_value;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod bsatn;
 pub mod buffer;
 pub mod builtin_type;
 pub mod builtin_value;
+pub mod codec;
 pub mod convert;
 pub mod de;
 pub mod meta_type;
+mod net;
+pub mod newtype_type;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod product_type;
 pub mod product_type_element;
 pub mod product_value;
 mod resolve_refs;
 pub mod satn;
+pub mod schema;
 pub mod ser;
 pub mod sum_type;
 pub mod sum_type_variant;
@@ -23,7 +33,10 @@ pub use algebraic_type::AlgebraicType;
 pub use algebraic_type_ref::AlgebraicTypeRef;
 pub use algebraic_value::AlgebraicValue;
 pub use builtin_type::{ArrayType, BuiltinType, MapType};
-pub use builtin_value::{ArrayValue, BuiltinValue, MapValue};
+#[allow(deprecated)]
+pub use builtin_value::BuiltinValue;
+pub use builtin_value::{ArrayValue, MapValue};
+pub use newtype_type::NewtypeType;
 pub use product_type::ProductType;
 pub use product_type_element::ProductTypeElement;
 pub use product_value::ProductValue;
@@ -0,0 +1,124 @@
+//! Implementations for the IP and socket address types of `std::net`.
+//!
+//! Addresses are encoded as numbers rather than strings, so they're compact
+//! and so the order of their values is the order of the addresses, e.g., for range queries.
+//! The types they're encoded as, which client SDKs can mirror, are:
+//!
+//! - `Ipv4Addr` is a `U32`, the address as a big-endian number, i.e., `u32::from(addr)`.
+//! - `Ipv6Addr` is a `U128`, likewise, i.e., `u128::from(addr)`.
+//! - `IpAddr` is the sum `IpAddr { V4(Ipv4Addr), V6(Ipv6Addr) }`.
+//! - `SocketAddrV4` is the product `SocketAddrV4 { ip: Ipv4Addr, port: U16 }`.
+//! - `SocketAddrV6` is the product `SocketAddrV6 { ip: Ipv6Addr, port: U16, flowinfo: U32, scope_id: U32 }`.
+//! - `SocketAddr` is the sum `SocketAddr { V4(SocketAddrV4), V6(SocketAddrV6) }`.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use crate::de::Deserialize;
+use crate::ser::Serialize;
+use crate::{impl_deserialize, impl_serialize, impl_st, AlgebraicType, ProductTypeElement, SumTypeVariant};
+
+/// How an [`IpAddr`] is encoded.
+#[derive(Serialize, Deserialize)]
+#[sats(crate = crate, name = "IpAddr")]
+enum IpAddrRepr {
+    V4(Ipv4Addr),
+    V6(Ipv6Addr),
+}
+
+/// How a [`SocketAddrV4`] is encoded.
+#[derive(Serialize, Deserialize)]
+#[sats(crate = crate, name = "SocketAddrV4")]
+struct SocketAddrV4Repr {
+    ip: Ipv4Addr,
+    port: u16,
+}
+
+/// How a [`SocketAddrV6`] is encoded.
+#[derive(Serialize, Deserialize)]
+#[sats(crate = crate, name = "SocketAddrV6")]
+struct SocketAddrV6Repr {
+    ip: Ipv6Addr,
+    port: u16,
+    flowinfo: u32,
+    scope_id: u32,
+}
+
+/// How a [`SocketAddr`] is encoded.
+#[derive(Serialize, Deserialize)]
+#[sats(crate = crate, name = "SocketAddr")]
+enum SocketAddrRepr {
+    V4(SocketAddrV4),
+    V6(SocketAddrV6),
+}
+
+impl_serialize!([] Ipv4Addr, (self, ser) => ser.serialize_u32((*self).into()));
+impl_deserialize!([] Ipv4Addr, de => u32::deserialize(de).map(Ipv4Addr::from));
+impl_serialize!([] Ipv6Addr, (self, ser) => ser.serialize_u128((*self).into()));
+impl_deserialize!([] Ipv6Addr, de => u128::deserialize(de).map(Ipv6Addr::from));
+
+impl_serialize!([] IpAddr, (self, ser) => match *self {
+    IpAddr::V4(ip) => IpAddrRepr::V4(ip),
+    IpAddr::V6(ip) => IpAddrRepr::V6(ip),
+}.serialize(ser));
+impl_deserialize!([] IpAddr, de => Ok(match IpAddrRepr::deserialize(de)? {
+    IpAddrRepr::V4(ip) => IpAddr::V4(ip),
+    IpAddrRepr::V6(ip) => IpAddr::V6(ip),
+}));
+
+impl_serialize!([] SocketAddrV4, (self, ser) => SocketAddrV4Repr { ip: *self.ip(), port: self.port() }.serialize(ser));
+impl_deserialize!([] SocketAddrV4, de => {
+    let SocketAddrV4Repr { ip, port } = SocketAddrV4Repr::deserialize(de)?;
+    Ok(SocketAddrV4::new(ip, port))
+});
+
+impl_serialize!([] SocketAddrV6, (self, ser) => SocketAddrV6Repr {
+    ip: *self.ip(),
+    port: self.port(),
+    flowinfo: self.flowinfo(),
+    scope_id: self.scope_id(),
+}.serialize(ser));
+impl_deserialize!([] SocketAddrV6, de => {
+    let SocketAddrV6Repr { ip, port, flowinfo, scope_id } = SocketAddrV6Repr::deserialize(de)?;
+    Ok(SocketAddrV6::new(ip, port, flowinfo, scope_id))
+});
+
+impl_serialize!([] SocketAddr, (self, ser) => match *self {
+    SocketAddr::V4(addr) => SocketAddrRepr::V4(addr),
+    SocketAddr::V6(addr) => SocketAddrRepr::V6(addr),
+}.serialize(ser));
+impl_deserialize!([] SocketAddr, de => Ok(match SocketAddrRepr::deserialize(de)? {
+    SocketAddrRepr::V4(addr) => SocketAddr::V4(addr),
+    SocketAddrRepr::V6(addr) => SocketAddr::V6(addr),
+}));
+
+/// Returns the type of a socket address with an `ip` of the type `ip`;
+/// the IPv6 address type also has a `flowinfo` and a `scope_id`.
+fn socket_addr_type(ip: AlgebraicType, v6: bool) -> AlgebraicType {
+    let mut elements = vec![
+        ProductTypeElement::new_named(ip, "ip"),
+        ProductTypeElement::new_named(AlgebraicType::U16, "port"),
+    ];
+    if v6 {
+        elements.push(ProductTypeElement::new_named(AlgebraicType::U32, "flowinfo"));
+        elements.push(ProductTypeElement::new_named(AlgebraicType::U32, "scope_id"));
+    }
+    AlgebraicType::product(elements)
+}
+
+/// Returns the sum type of an IPv4 variant of the type `v4` and an IPv6 variant of the type `v6`.
+fn v4_or_v6(v4: AlgebraicType, v6: AlgebraicType) -> AlgebraicType {
+    AlgebraicType::sum(vec![
+        SumTypeVariant::new_named(v4, "V4"),
+        SumTypeVariant::new_named(v6, "V6"),
+    ])
+}
+
+impl_st!([] Ipv4Addr, _ts => AlgebraicType::U32);
+impl_st!([] Ipv6Addr, _ts => AlgebraicType::U128);
+impl_st!([] IpAddr, _ts => v4_or_v6(AlgebraicType::U32, AlgebraicType::U128));
+impl_st!([] SocketAddrV4, _ts => socket_addr_type(AlgebraicType::U32, false));
+impl_st!([] SocketAddrV6, _ts => socket_addr_type(AlgebraicType::U128, true));
+impl_st!([] SocketAddr, _ts => v4_or_v6(
+    socket_addr_type(AlgebraicType::U32, false),
+    socket_addr_type(AlgebraicType::U128, true),
+));
@@ -0,0 +1,37 @@
+use crate::meta_type::MetaType;
+use crate::{de::Deserialize, ser::Serialize};
+use crate::{AlgebraicType, AlgebraicTypeRef, ProductTypeElement};
+
+/// A nominal type `name` wrapping the structural type `inner`.
+///
+/// A newtype distinguishes e.g., `UserId(u64)` and `OrderId(u64)`
+/// for documentation, schema introspection, and code generation.
+/// Values of a newtype are just values of `inner`,
+/// and their encoding is the same as that of `inner` in every format.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[sats(crate = crate)]
+pub struct NewtypeType {
+    /// The nominal name of the type, e.g., `UserId`.
+    pub name: String,
+    /// The type wrapped by the newtype.
+    pub inner: Box<AlgebraicType>,
+}
+
+impl NewtypeType {
+    /// Returns a newtype named `name` wrapping `inner`.
+    pub fn new(name: impl Into<String>, inner: AlgebraicType) -> Self {
+        Self {
+            name: name.into(),
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl MetaType for NewtypeType {
+    fn meta_type() -> AlgebraicType {
+        AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+            ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(0)), "inner"),
+        ])
+    }
+}
@@ -0,0 +1,339 @@
+//! Writing tables of [`ProductValue`]s to Parquet files and reading them back,
+//! e.g., to archive query results.
+//!
+//! Rows are converted to and from Arrow as in [`crate::arrow`],
+//! so columns map to Parquet as their types map to Arrow,
+//! and option-typed columns are nullable.
+
+use std::fmt;
+use std::io;
+
+use ::arrow::datatypes::{DataType, Field};
+use ::arrow::error::ArrowError;
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use ::parquet::arrow::ArrowWriter;
+use ::parquet::basic::GzipLevel;
+use ::parquet::errors::ParquetError;
+use ::parquet::file::properties::{WriterProperties, DEFAULT_MAX_ROW_GROUP_SIZE};
+use ::parquet::file::reader::ChunkReader;
+
+use crate::arrow::{from_record_batch, to_record_batch, to_schema, ConvertError};
+use crate::{ProductType, ProductValue, Typespace};
+
+/// The compression applied to the pages of a Parquet file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// No compression.
+    None,
+    /// Snappy, which is fast but compresses less.
+    Snappy,
+    /// Gzip at its default level, which is slower but compresses more.
+    Gzip,
+}
+
+/// Options for [`write_table`].
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// The compression of the column data.
+    pub compression: Compression,
+    /// The most rows to put in a row group, the unit in which readers load a file.
+    pub max_row_group_size: usize,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression::Snappy,
+            max_row_group_size: DEFAULT_MAX_ROW_GROUP_SIZE,
+        }
+    }
+}
+
+/// An error writing or reading a table as Parquet.
+#[derive(thiserror::Error, Debug)]
+pub enum TableError {
+    /// The columns of a Parquet file didn't agree with the product type of the table.
+    #[error("Parquet file doesn't match the table type: {}", DisplayMismatches(.0))]
+    SchemaMismatch(Vec<ColumnMismatch>),
+    /// The rows couldn't be converted to or from Arrow.
+    #[error(transparent)]
+    Convert(#[from] ConvertError),
+    /// Arrow failed while reading the file.
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    /// Parquet failed while writing or reading the file.
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+}
+
+/// A disagreement between a column of a Parquet file and the field of the table type at its position.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ColumnMismatch {
+    /// The file has no column for the field.
+    #[error("column {index} {expected:?} is missing")]
+    Missing { index: usize, expected: String },
+    /// The file has a column beyond the fields of the table type.
+    #[error("column {index} {found:?} is not in the table type")]
+    Extra { index: usize, found: String },
+    /// The column is named other than the field.
+    #[error("column {index} is named {found:?}, expected {expected:?}")]
+    Name {
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    /// The column is of another type than the one the field's type maps to.
+    #[error("column {column:?} is of type {found}, expected {expected}")]
+    Type {
+        column: String,
+        expected: DataType,
+        found: DataType,
+    },
+}
+
+/// Displays a list of column mismatches separated by `; `.
+struct DisplayMismatches<'a>(&'a [ColumnMismatch]);
+
+impl fmt::Display for DisplayMismatches<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, mismatch) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            mismatch.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `rows`, of type `ty` in the typespace `ts`, as a Parquet file to `w`.
+pub fn write_table<W: io::Write + Send>(
+    rows: &[ProductValue],
+    ty: &ProductType,
+    ts: &Typespace,
+    w: W,
+    opts: &WriteOptions,
+) -> Result<(), TableError> {
+    let compression = match opts.compression {
+        Compression::None => ::parquet::basic::Compression::UNCOMPRESSED,
+        Compression::Snappy => ::parquet::basic::Compression::SNAPPY,
+        Compression::Gzip => ::parquet::basic::Compression::GZIP(GzipLevel::default()),
+    };
+    let max_row_group_size = opts.max_row_group_size.max(1);
+    let props = WriterProperties::builder()
+        .set_compression(compression)
+        .set_max_row_group_size(max_row_group_size)
+        .build();
+
+    let schema = to_schema(ty, ts)?.into();
+    let mut writer = ArrowWriter::try_new(w, schema, Some(props))?;
+    // Convert a row group's worth at a time rather than the whole table at once.
+    for chunk in rows.chunks(max_row_group_size) {
+        writer.write(&to_record_batch(chunk, ty, ts)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+/// Reads a Parquet file from `reader` into rows of type `ty` in the typespace `ts`.
+///
+/// The columns of the file must agree with the fields of `ty` by position, name, and type,
+/// except that any column may be nullable in the file.
+/// All disagreements are reported at once in [`TableError::SchemaMismatch`].
+pub fn read_table<R: ChunkReader + 'static>(
+    reader: R,
+    ty: &ProductType,
+    ts: &Typespace,
+) -> Result<Vec<ProductValue>, TableError> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?;
+    let expected = to_schema(ty, ts)?;
+    let mismatches = column_mismatches(expected.fields(), builder.schema().fields());
+    if !mismatches.is_empty() {
+        return Err(TableError::SchemaMismatch(mismatches));
+    }
+
+    let mut rows = Vec::with_capacity(builder.metadata().file_metadata().num_rows() as usize);
+    for batch in builder.build()? {
+        rows.extend(from_record_batch(&batch?, ty, ts)?);
+    }
+    Ok(rows)
+}
+
+/// Returns the disagreements between the `expected` columns and those `found` in a file.
+fn column_mismatches(expected: &[impl AsRef<Field>], found: &[impl AsRef<Field>]) -> Vec<ColumnMismatch> {
+    let mut mismatches = Vec::new();
+    for (index, (expected, found)) in expected.iter().zip(found).enumerate() {
+        let (expected, found) = (expected.as_ref(), found.as_ref());
+        if expected.name() != found.name() {
+            mismatches.push(ColumnMismatch::Name {
+                index,
+                expected: expected.name().clone(),
+                found: found.name().clone(),
+            });
+        } else if !same_type(expected.data_type(), found.data_type()) {
+            mismatches.push(ColumnMismatch::Type {
+                column: expected.name().clone(),
+                expected: expected.data_type().clone(),
+                found: found.data_type().clone(),
+            });
+        }
+    }
+    let missing = expected.iter().enumerate().skip(found.len());
+    mismatches.extend(missing.map(|(index, field)| ColumnMismatch::Missing {
+        index,
+        expected: field.as_ref().name().clone(),
+    }));
+    let extra = found.iter().enumerate().skip(expected.len());
+    mismatches.extend(extra.map(|(index, field)| ColumnMismatch::Extra {
+        index,
+        found: field.as_ref().name().clone(),
+    }));
+    mismatches
+}
+
+/// Returns whether the Arrow types `a` and `b` agree, regardless of the nullability of struct fields.
+fn same_type(a: &DataType, b: &DataType) -> bool {
+    match (a, b) {
+        (DataType::Struct(a), DataType::Struct(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| a.name() == b.name() && same_type(a.data_type(), b.data_type()))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use ::parquet::record::RowAccessor;
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{product, AlgebraicType, AlgebraicValue, ProductTypeElement};
+
+    fn table() -> (ProductType, Vec<ProductValue>) {
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "name"),
+            ProductTypeElement::new_named(AlgebraicType::bytes(), "blob"),
+            ProductTypeElement::new_named(
+                AlgebraicType::product(vec![
+                    ProductTypeElement::new_named(AlgebraicType::F64, "x"),
+                    ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::I64), "y"),
+                ]),
+                "point",
+            ),
+        ]);
+        let rows = (0..5u32)
+            .map(|i| {
+                let name = match i % 3 {
+                    0 => AlgebraicValue::OptionNone(),
+                    _ => AlgebraicValue::OptionSome(format!("row {i}").into()),
+                };
+                let y = match i % 2 {
+                    0 => AlgebraicValue::OptionSome((-i64::from(i)).into()),
+                    _ => AlgebraicValue::OptionNone(),
+                };
+                let blob = AlgebraicValue::Bytes(vec![i as u8; i as usize]);
+                product![i, name, blob, product![f64::from(i) / 2.0, y]]
+            })
+            .collect();
+        (ty, rows)
+    }
+
+    #[test]
+    fn round_trip() {
+        let ts = Typespace::new(vec![]);
+        let (ty, rows) = table();
+        for compression in [Compression::None, Compression::Snappy, Compression::Gzip] {
+            let opts = WriteOptions {
+                compression,
+                max_row_group_size: 2,
+            };
+            let mut file = Vec::new();
+            write_table(&rows, &ty, &ts, &mut file, &opts).unwrap();
+            assert_eq!(
+                read_table(Bytes::from(file), &ty, &ts).unwrap(),
+                rows,
+                "{compression:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn other_reader_opens_file() {
+        let ts = Typespace::new(vec![]);
+        let (ty, rows) = table();
+        let opts = WriteOptions {
+            max_row_group_size: 2,
+            ..WriteOptions::default()
+        };
+        let mut file = Vec::new();
+        write_table(&rows, &ty, &ts, &mut file, &opts).unwrap();
+
+        // Read the file with Parquet's own row reader, which knows nothing of Arrow.
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let meta = reader.metadata();
+        assert_eq!(meta.file_metadata().num_rows(), 5);
+        assert_eq!(meta.num_row_groups(), 3);
+        let schema = meta.file_metadata().schema_descr();
+        let columns = schema.columns().iter().map(|c| c.path().string()).collect::<Vec<_>>();
+        assert_eq!(columns, ["id", "name", "blob", "point.x", "point.y"]);
+
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[1].get_uint(0).unwrap(), 1);
+        assert_eq!(rows[1].get_string(1).unwrap(), "row 1");
+        assert!(rows[0].get_string(1).is_err());
+        assert_eq!(rows[3].get_bytes(2).unwrap().data(), [3, 3, 3]);
+    }
+
+    #[test]
+    fn reports_mismatches_per_column() {
+        let ts = Typespace::new(vec![]);
+        let (ty, rows) = table();
+        let mut file = Vec::new();
+        write_table(&rows, &ty, &ts, &mut file, &WriteOptions::default()).unwrap();
+
+        let other = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "label"),
+        ]);
+        let Err(TableError::SchemaMismatch(mismatches)) = read_table(Bytes::from(file), &other, &ts) else {
+            panic!("expected a schema mismatch");
+        };
+        assert_eq!(
+            mismatches,
+            [
+                ColumnMismatch::Type {
+                    column: "id".into(),
+                    expected: DataType::UInt64,
+                    found: DataType::UInt32,
+                },
+                ColumnMismatch::Name {
+                    index: 1,
+                    expected: "label".into(),
+                    found: "name".into(),
+                },
+                ColumnMismatch::Extra {
+                    index: 2,
+                    found: "blob".into(),
+                },
+                ColumnMismatch::Extra {
+                    index: 3,
+                    found: "point".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            TableError::SchemaMismatch(mismatches[..2].to_vec()).to_string(),
+            r#"Parquet file doesn't match the table type: column "id" is of type UInt32, expected UInt64; column 1 is named "name", expected "label""#
+        );
+    }
+}
@@ -2,7 +2,7 @@ use crate::algebraic_value::de::{ValueDeserializeError, ValueDeserializer};
 use crate::algebraic_value::ser::ValueSerializer;
 use crate::meta_type::MetaType;
 use crate::{de::Deserialize, ser::Serialize};
-use crate::{AlgebraicType, AlgebraicValue, ProductTypeElement};
+use crate::{AlgebraicType, AlgebraicValue, ArrayValue, ProductTypeElement, ProductValue};
 
 /// A structural product type  of the factors given by `elements`.
 ///
@@ -43,6 +43,124 @@ impl ProductType {
         Self { elements }
     }
 
+    /// Returns an iterator over the names of the fields, in order.
+    ///
+    /// Anonymous fields yield `None`.
+    pub fn field_names(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        self.elements.iter().map(|e| e.name())
+    }
+
+    /// Returns an iterator over the names of the named fields, in order,
+    /// skipping any anonymous fields.
+    pub fn named_fields(&self) -> impl Iterator<Item = &str> + '_ {
+        self.elements.iter().filter_map(|e| e.name())
+    }
+
+    /// Returns an iterator over the types of the fields, in order.
+    pub fn field_types(&self) -> impl Iterator<Item = &AlgebraicType> + '_ {
+        self.elements.iter().map(|e| &e.algebraic_type)
+    }
+
+    /// Returns a new product type with all the fields of `self`
+    /// followed by a field `name` of type `ty`.
+    pub fn with_extra_field(&self, name: Option<&str>, ty: AlgebraicType) -> ProductType {
+        let mut elements = Vec::with_capacity(self.elements.len() + 1);
+        elements.extend_from_slice(&self.elements);
+        elements.push(ProductTypeElement::new(ty, name.map(str::to_owned)));
+        Self::new(elements)
+    }
+
+    /// Returns a new product type with all the fields of `self`
+    /// except for the first field named `name`,
+    /// or an error if there is no such field.
+    pub fn without_field(&self, name: &str) -> Result<ProductType, FieldNotFound> {
+        let pos = self
+            .elements
+            .iter()
+            .position(|e| e.has_name(name))
+            .ok_or_else(|| FieldNotFound { name: name.to_owned() })?;
+        let mut elements = self.elements.clone();
+        elements.remove(pos);
+        Ok(Self::new(elements))
+    }
+
+    /// Returns a product type of the fields of `self` at `indices`, in the order given,
+    /// or an error for the first of the `indices` out of range.
+    ///
+    /// An index may be given more than once, in which case its field is repeated.
+    /// This is the type of [`ProductValue::project_fields`].
+    pub fn project_fields(&self, indices: &[usize]) -> Result<ProductType, FieldIndexOutOfRange> {
+        indices
+            .iter()
+            .map(|&index| {
+                self.elements
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| self.out_of_range(index))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+
+    /// Splits `self` into the product types of the fields before `index` and of those from `index` on,
+    /// or returns an error if `index` is past the end of `self`.
+    ///
+    /// These are the types of the halves of [`ProductValue::split_at`].
+    pub fn split_at(&self, index: usize) -> Result<(ProductType, ProductType), FieldIndexOutOfRange> {
+        if index > self.elements.len() {
+            return Err(self.out_of_range(index));
+        }
+        let (front, back) = self.elements.split_at(index);
+        Ok((Self::new(front.to_vec()), Self::new(back.to_vec())))
+    }
+
+    /// Returns the product type of the fields of `a` followed by those of `b`.
+    ///
+    /// This is the type of [`ProductValue::concat`].
+    pub fn concat(a: &ProductType, b: &ProductType) -> ProductType {
+        Self::new([&*a.elements, &*b.elements].concat())
+    }
+
+    /// Infers a product type of which all of `rows` are values,
+    /// from the types of the fields of the first row.
+    ///
+    /// Values don't carry the names of their fields,
+    /// so the fields are named after their positions, `field_0`, `field_1`, etc.
+    ///
+    /// Returns `None` if `rows` is empty, if a later row doesn't have fields of the same types as the first,
+    /// or if a field is, or has anywhere within it, a sum value,
+    /// as the variants of a sum type can't be told from one of its values.
+    pub fn infer_from_values(rows: &[ProductValue]) -> Option<ProductType> {
+        let (first, rest) = rows.split_first()?;
+        if rows.iter().any(|row| row.elements.iter().any(has_sum)) {
+            return None;
+        }
+        let types = first.elements.iter().map(AlgebraicValue::type_of).collect::<Vec<_>>();
+        let consistent = rest.iter().all(|row| {
+            row.elements.len() == types.len()
+                && row
+                    .elements
+                    .iter()
+                    .zip(&types)
+                    .all(|(field, ty)| field.type_of() == *ty)
+        });
+        consistent.then(|| {
+            types
+                .into_iter()
+                .enumerate()
+                .map(|(i, ty)| ProductTypeElement::new_named(ty, format!("field_{i}")))
+                .collect()
+        })
+    }
+
+    /// Returns the error for `index` being out of range for the fields of `self`.
+    fn out_of_range(&self, index: usize) -> FieldIndexOutOfRange {
+        FieldIndexOutOfRange {
+            index,
+            len: self.elements.len(),
+        }
+    }
+
     /// Returns whether this is the special case of `spacetimedb_lib::Identity`.
     pub fn is_identity(&self) -> bool {
         match &*self.elements {
@@ -71,6 +189,24 @@ impl ProductType {
     }
 }
 
+/// An error that occurs when a field to remove from a product type doesn't exist.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Field {name:?} not found")]
+pub struct FieldNotFound {
+    /// The name of the field that was looked for.
+    pub name: String,
+}
+
+/// An error that occurs when a field index is out of range for a product type.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Field index {index} out of range for a product of {len} fields")]
+pub struct FieldIndexOutOfRange {
+    /// The index that was out of range.
+    pub index: usize,
+    /// The number of fields of the product type.
+    pub len: usize,
+}
+
 impl<I: Into<ProductTypeElement>> FromIterator<I> for ProductType {
     fn from_iter<T: IntoIterator<Item = I>>(iter: T) -> Self {
         Self::new(iter.into_iter().map(Into::into).collect())
@@ -110,3 +246,206 @@ impl ProductType {
         Self::deserialize(ValueDeserializer::from_ref(value))
     }
 }
+
+/// Returns whether `value` is, or has anywhere within it, a sum value.
+fn has_sum(value: &AlgebraicValue) -> bool {
+    match value {
+        AlgebraicValue::Sum(_) => true,
+        AlgebraicValue::Product(prod) => prod.elements.iter().any(has_sum),
+        AlgebraicValue::Array(arr) => array_has_sum(arr),
+        AlgebraicValue::Map(map) => map.iter().any(|(key, val)| has_sum(key) || has_sum(val)),
+        _ => false,
+    }
+}
+
+/// Returns whether `arr` is of, or has anywhere within its elements, sum values,
+/// counting an empty array of sums, as its type is a sum type all the same.
+fn array_has_sum(arr: &ArrayValue) -> bool {
+    match arr {
+        ArrayValue::Sum(_) => true,
+        ArrayValue::Product(prods) => prods.iter().flat_map(|prod| &*prod.elements).any(has_sum),
+        ArrayValue::Array(arrs) => arrs.iter().any(array_has_sum),
+        ArrayValue::Map(maps) => maps.iter().flatten().any(|(key, val)| has_sum(key) || has_sum(val)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::DeserializeSeed;
+    use crate::{bsatn, product, Typespace, WithTypespace};
+
+    fn mixed() -> ProductType {
+        ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U8, "a"),
+            AlgebraicType::String.into(),
+            ProductTypeElement::new_named(AlgebraicType::Bool, "c"),
+        ])
+    }
+
+    #[test]
+    fn field_names() {
+        let ty = mixed();
+        assert_eq!(ty.field_names().collect::<Vec<_>>(), [Some("a"), None, Some("c")]);
+        assert_eq!(ty.named_fields().collect::<Vec<_>>(), ["a", "c"]);
+    }
+
+    #[test]
+    fn field_types() {
+        let ty = mixed();
+        let types = ty.field_types().cloned().collect::<Vec<_>>();
+        assert_eq!(types, [AlgebraicType::U8, AlgebraicType::String, AlgebraicType::Bool]);
+    }
+
+    #[test]
+    fn with_extra_field() {
+        let ty = mixed();
+        let extended = ty.with_extra_field(Some("d"), AlgebraicType::I64);
+        assert_eq!(extended.elements.len(), ty.elements.len() + 1);
+        assert_eq!(extended.elements[..3], ty.elements[..]);
+        assert_eq!(
+            extended.elements[3],
+            ProductTypeElement::new_named(AlgebraicType::I64, "d")
+        );
+
+        let extended = ty.with_extra_field(None, AlgebraicType::I64);
+        assert_eq!(extended.elements[3], AlgebraicType::I64.into());
+    }
+
+    #[test]
+    fn without_field() {
+        let ty = mixed();
+        let shrunk = ty.without_field("a").unwrap();
+        assert_eq!(shrunk.elements.len(), ty.elements.len() - 1);
+        assert_eq!(shrunk.elements[..], ty.elements[1..]);
+
+        let shrunk = ty.without_field("c").unwrap();
+        assert_eq!(shrunk.elements[..], ty.elements[..2]);
+
+        assert_eq!(ty.without_field("b").unwrap_err(), FieldNotFound { name: "b".into() });
+    }
+
+    #[test]
+    fn with_extra_element() {
+        let row = product![1u8, "x", true];
+        let extended = row.with_extra_element(AlgebraicValue::I64(-1));
+        assert_eq!(extended.elements.len(), row.elements.len() + 1);
+        assert_eq!(extended.elements[..3], row.elements[..]);
+        assert_eq!(extended.elements[3], AlgebraicValue::I64(-1));
+    }
+
+    /// Asserts that `val` is a value of `ty`, by decoding its encoding at `ty`.
+    fn check_type(ty: &ProductType, val: &ProductValue) {
+        let bytes = bsatn::to_vec(val).unwrap();
+        let reader = &mut &bytes[..];
+        let decoded = WithTypespace::new(&Typespace::default(), ty)
+            .deserialize(bsatn::Deserializer::new(reader))
+            .unwrap();
+        assert!(reader.is_empty(), "{val:?} is longer than a {ty:?}");
+        assert_eq!(&decoded, val);
+    }
+
+    #[test]
+    fn project_fields() {
+        let (ty, row) = (mixed(), product![1u8, "x", true]);
+        for indices in [&[2, 0][..], &[], &[1, 1, 0, 1]] {
+            let projected_ty = ty.project_fields(indices).unwrap();
+            let projected = row.project_fields(indices).unwrap();
+            check_type(&projected_ty, &projected);
+            assert_eq!(projected_ty.elements.len(), indices.len());
+            for (elem, &index) in projected.elements.iter().zip(indices) {
+                assert_eq!(elem, &row.elements[index]);
+            }
+            assert_eq!(row.clone().into_project_fields(indices).unwrap(), projected);
+        }
+
+        let out_of_range = FieldIndexOutOfRange { index: 3, len: 3 };
+        assert_eq!(ty.project_fields(&[0, 3, 4]).unwrap_err(), out_of_range);
+        assert_eq!(row.project_fields(&[0, 3, 4]).unwrap_err().col_pos, 3);
+        assert_eq!(row.into_project_fields(&[0, 3, 4]).unwrap_err().col_pos, 3);
+    }
+
+    #[test]
+    fn into_project_fields_moves() {
+        let ptrs = |row: &ProductValue| {
+            row.elements
+                .iter()
+                .map(|e| e.as_string().unwrap().as_ptr())
+                .collect::<Vec<_>>()
+        };
+        let row = product!["once", "twice"];
+        let before = ptrs(&row);
+        let after = ptrs(&row.into_project_fields(&[1, 0, 1]).unwrap());
+        // Only the first use of the field used twice is a clone.
+        assert_ne!(after[0], before[1]);
+        assert_eq!(after[1..], [before[0], before[1]]);
+    }
+
+    #[test]
+    fn split_at_and_concat() {
+        let (ty, row) = (mixed(), product![1u8, "x", true]);
+        for index in 0..=3 {
+            let (front_ty, back_ty) = ty.split_at(index).unwrap();
+            let (front, back) = row.clone().split_at(index).unwrap();
+            check_type(&front_ty, &front);
+            check_type(&back_ty, &back);
+            assert_eq!(front.elements[..], row.elements[..index]);
+
+            let joined_ty = ProductType::concat(&front_ty, &back_ty);
+            let joined = ProductValue::concat(front, back);
+            check_type(&joined_ty, &joined);
+            assert_eq!((joined_ty, joined), (ty.clone(), row.clone()));
+        }
+
+        assert_eq!(ty.split_at(4).unwrap_err(), FieldIndexOutOfRange { index: 4, len: 3 });
+        assert_eq!(row.split_at(4).unwrap_err().col_pos, 4);
+    }
+
+    #[test]
+    fn infer_from_values() {
+        let rows = (0..3u32).map(|i| product![i, i + 1]).collect::<Vec<_>>();
+        let ty = ProductType::infer_from_values(&rows).unwrap();
+        assert_eq!(
+            ty,
+            ProductType::new(vec![
+                ProductTypeElement::new_named(AlgebraicType::U32, "field_0"),
+                ProductTypeElement::new_named(AlgebraicType::U32, "field_1"),
+            ])
+        );
+        rows.iter().for_each(|row| check_type(&ty, row));
+
+        let rows = [product![1u8, "x", true], product![2u8, "y", false]];
+        let ty = ProductType::infer_from_values(&rows).unwrap();
+        assert!(ty.field_types().eq(mixed().field_types()));
+
+        assert_eq!(ProductType::infer_from_values(&[]), None);
+        // A later row with a field of another variant, or another number of fields.
+        assert_eq!(ProductType::infer_from_values(&[product![1u32], product![1u64]]), None);
+        assert_eq!(
+            ProductType::infer_from_values(&[product![1u32], product![1u32, 2u32]]),
+            None
+        );
+        assert_eq!(
+            ProductType::infer_from_values(&[product![AlgebraicValue::OptionNone()]]),
+            None
+        );
+        // Nor can a sum nested within a product, an array, or a map, nor one in a later row.
+        assert_eq!(
+            ProductType::infer_from_values(&[
+                product![product![1u8]],
+                product![AlgebraicValue::OptionSome(1u8.into())]
+            ]),
+            None
+        );
+        let some = AlgebraicValue::OptionSome(1u8.into());
+        let map = AlgebraicValue::Map([(1u8.into(), some.clone())].into());
+        for nested in [
+            product![some.clone()].into(),
+            AlgebraicValue::ArrayOf(vec![some.into_sum().unwrap()]),
+            map,
+        ] {
+            assert_eq!(ProductType::infer_from_values(&[product![nested]]), None);
+        }
+    }
+}
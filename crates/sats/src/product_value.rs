@@ -1,7 +1,9 @@
 use crate::algebraic_value::AlgebraicValue;
 use crate::product_type::ProductType;
-use crate::ArrayValue;
+use crate::{ArrayValue, ValueWithType};
 use nonempty::NonEmpty;
+use std::collections::BTreeMap;
+use std::mem;
 
 /// A product value is made of a a list of
 /// "elements" / "fields" / "factors" of other `AlgebraicValue`s.
@@ -32,6 +34,32 @@ impl ProductValue {
             elements: elements.into(),
         }
     }
+
+    /// Returns a new product value with all the elements of `self` followed by `val`.
+    pub fn with_extra_element(&self, val: AlgebraicValue) -> ProductValue {
+        let mut elements = Vec::with_capacity(self.elements.len() + 1);
+        elements.extend_from_slice(&self.elements);
+        elements.push(val);
+        Self { elements }
+    }
+}
+
+impl ProductValue {
+    /// Returns an estimate of the number of bytes `self` owns on the heap,
+    /// i.e., that of the allocation for its `elements` and what those own in turn.
+    pub fn heap_size_bytes(&self) -> usize {
+        heap_size_of_slice(
+            &self.elements,
+            self.elements.capacity(),
+            AlgebraicValue::heap_size_bytes,
+        )
+    }
+}
+
+/// Returns an estimate of the heap bytes of a vector with `capacity` holding `elems`,
+/// where `elem_heap_size` estimates the heap bytes each of its elements own.
+pub(crate) fn heap_size_of_slice<T>(elems: &[T], capacity: usize, elem_heap_size: impl Fn(&T) -> usize) -> usize {
+    capacity * std::mem::size_of::<T>() + elems.iter().map(elem_heap_size).sum::<usize>()
 }
 
 impl FromIterator<AlgebraicValue> for ProductValue {
@@ -155,12 +183,12 @@ impl ProductValue {
 
     /// Interprets the value at field of `self` identified by `index` as a string slice.
     pub fn field_as_str(&self, index: usize, named: Option<&'static str>) -> Result<&str, InvalidFieldError> {
-        self.extract_field(index, named, |f| f.as_string().map(|x| x.as_str()))
+        self.extract_field(index, named, |f| f.as_string())
     }
 
     /// Interprets the value at field of `self` identified by `index` as a byte slice.
     pub fn field_as_bytes(&self, index: usize, named: Option<&'static str>) -> Result<&[u8], InvalidFieldError> {
-        self.extract_field(index, named, |f| f.as_bytes().map(|x| x.as_slice()))
+        self.extract_field(index, named, |f| f.as_bytes())
     }
 
     /// Interprets the value at field of `self` identified by `index` as a array.
@@ -168,3 +196,230 @@ impl ProductValue {
         self.extract_field(index, named, |f| f.as_array())
     }
 }
+
+impl ProductValue {
+    /// Returns a product value of the fields of `self` at `indices`, in the order given,
+    /// or an [InvalidFieldError] for the first of the `indices` out of range.
+    ///
+    /// An index may be given more than once, in which case its field is repeated.
+    ///
+    /// See [`ProductType::project_fields`] for the type of the result.
+    pub fn project_fields(&self, indices: &[usize]) -> Result<ProductValue, InvalidFieldError> {
+        indices
+            .iter()
+            .map(|&index| self.get_field(index, None).cloned())
+            .collect()
+    }
+
+    /// Returns a product value of the fields of `self` at `indices`, as [`ProductValue::project_fields`] does,
+    /// but moves the fields out of `self`, cloning only those at an index given more than once.
+    pub fn into_project_fields(mut self, indices: &[usize]) -> Result<ProductValue, InvalidFieldError> {
+        // The position in `indices` at which each field is used last, and so can be moved.
+        let mut last_use = vec![None; self.elements.len()];
+        for (pos, &index) in indices.iter().enumerate() {
+            *last_use.get_mut(index).ok_or(InvalidFieldError {
+                col_pos: index,
+                name: None,
+            })? = Some(pos);
+        }
+        let elements = indices.iter().enumerate().map(|(pos, &index)| {
+            let field = &mut self.elements[index];
+            if last_use[index] == Some(pos) {
+                // An empty product doesn't allocate, so it is a free placeholder.
+                mem::replace(field, AlgebraicValue::product(Vec::new()))
+            } else {
+                field.clone()
+            }
+        });
+        Ok(elements.collect())
+    }
+
+    /// Splits `self` into the product values of the fields before `index` and of those from `index` on,
+    /// or returns an [InvalidFieldError] if `index` is past the end of `self`.
+    ///
+    /// See [`ProductType::split_at`] for the types of the halves.
+    pub fn split_at(mut self, index: usize) -> Result<(ProductValue, ProductValue), InvalidFieldError> {
+        if index > self.elements.len() {
+            return Err(InvalidFieldError {
+                col_pos: index,
+                name: None,
+            });
+        }
+        let back = self.elements.split_off(index);
+        Ok((self, Self { elements: back }))
+    }
+
+    /// Returns the product value of the fields of `a` followed by those of `b`.
+    ///
+    /// See [`ProductType::concat`] for the type of the result.
+    pub fn concat(mut a: ProductValue, b: ProductValue) -> ProductValue {
+        a.elements.extend(b.elements);
+        a
+    }
+}
+
+/// An error that occurs when a product value has a different number of elements than its product type.
+#[derive(thiserror::Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Product value has {value_len} elements but its type has {type_len}")]
+pub struct ArityMismatch {
+    /// The number of elements in the product value.
+    pub value_len: usize,
+    /// The number of elements in the product type.
+    pub type_len: usize,
+}
+
+impl ArityMismatch {
+    /// Returns an error if `value` and its type `ty` have a different number of elements.
+    fn check(value: &ProductValue, ty: &ProductType) -> Result<(), Self> {
+        let (value_len, type_len) = (value.elements.len(), ty.elements.len());
+        match value_len == type_len {
+            true => Ok(()),
+            false => Err(Self { value_len, type_len }),
+        }
+    }
+}
+
+impl ProductValue {
+    /// Returns an iterator over the elements of `self`, each with the name, if any, of its element in `ty`,
+    /// or an error if `self` and `ty` have a different number of elements.
+    pub fn iter_named<'a>(
+        &'a self,
+        ty: &'a ProductType,
+    ) -> Result<impl Iterator<Item = (Option<&'a str>, &'a AlgebraicValue)> + 'a, ArityMismatch> {
+        ArityMismatch::check(self, ty)?;
+        Ok(ty.field_names().zip(&self.elements))
+    }
+
+    /// Returns an iterator moving out the elements of `self`, each with the name, if any, of its element in `ty`,
+    /// or an error if `self` and `ty` have a different number of elements.
+    pub fn into_iter_named(
+        self,
+        ty: &ProductType,
+    ) -> Result<impl Iterator<Item = (Option<&str>, AlgebraicValue)>, ArityMismatch> {
+        ArityMismatch::check(&self, ty)?;
+        Ok(ty.field_names().zip(self.elements))
+    }
+
+    /// Returns the elements of `self` keyed by the names of their elements in `ty`,
+    /// or an error if `self` and `ty` have a different number of elements.
+    ///
+    /// Unnamed elements are keyed by their position, e.g., `"1"`, as they are when displayed.
+    /// Of several elements with the same name, the last one is kept.
+    pub fn to_named_map(&self, ty: &ProductType) -> Result<BTreeMap<String, AlgebraicValue>, ArityMismatch> {
+        let fields = self.iter_named(ty)?.enumerate();
+        Ok(fields
+            .map(|(idx, (name, val))| (name.map_or_else(|| idx.to_string(), str::to_owned), val.clone()))
+            .collect())
+    }
+}
+
+impl<'a> ValueWithType<'a, ProductValue> {
+    /// Returns the value of the first element named `name`, paired with its element type,
+    /// or `None` if the product type has no element named `name`.
+    pub fn field(&self, name: &str) -> Result<Option<ValueWithType<'a, AlgebraicValue>>, ArityMismatch> {
+        Ok(self.fields()?.find(|(n, _)| *n == Some(name)).map(|(_, val)| val))
+    }
+
+    /// Returns an iterator over the elements, each as its name, if any, and its value paired with its type.
+    pub fn fields(
+        &self,
+    ) -> Result<impl Iterator<Item = (Option<&'a str>, ValueWithType<'a, AlgebraicValue>)> + 'a, ArityMismatch> {
+        let this = *self;
+        let fields = this.value().iter_named(this.ty())?.zip(this.ty().field_types());
+        Ok(fields.map(move |((name, val), ty)| (name, this.with(ty, val))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, AlgebraicType, ProductTypeElement, Typespace, WithTypespace};
+
+    #[test]
+    fn fields_by_name() {
+        let ts = Typespace::new(vec![]);
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new(AlgebraicType::Bool, None),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ]);
+        let val = product![7u32, true, "Alice"];
+        let wt = ValueWithType::new(WithTypespace::new(&ts, &ty), &val);
+
+        let name = wt.field("name").unwrap().unwrap();
+        assert_eq!(name.value(), &AlgebraicValue::String("Alice".into()));
+        assert_eq!(name.ty(), &AlgebraicType::String);
+        assert!(std::ptr::eq(name.typespace(), &ts));
+        assert_eq!(wt.field("id").unwrap().unwrap().value(), &AlgebraicValue::U32(7));
+        assert!(wt.field("missing").unwrap().is_none());
+
+        let fields = wt.fields().unwrap().map(|(n, v)| (n, v.value(), v.ty()));
+        let expected = [
+            (Some("id"), &AlgebraicValue::U32(7), &AlgebraicType::U32),
+            (None, &AlgebraicValue::Bool(true), &AlgebraicType::Bool),
+            (
+                Some("name"),
+                &AlgebraicValue::String("Alice".into()),
+                &AlgebraicType::String,
+            ),
+        ];
+        assert!(fields.eq(expected));
+    }
+
+    #[test]
+    fn short_product() {
+        let ts = Typespace::new(vec![]);
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ]);
+        let val = product![7u32];
+        let wt = ValueWithType::new(WithTypespace::new(&ts, &ty), &val);
+
+        let mismatch = ArityMismatch {
+            value_len: 1,
+            type_len: 2,
+        };
+        assert_eq!(wt.field("id").err(), Some(mismatch));
+        assert_eq!(wt.fields().err(), Some(mismatch));
+    }
+
+    #[test]
+    fn iter_named() {
+        let ty = ProductType::new(vec![
+            ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+            ProductTypeElement::new(AlgebraicType::Bool, None),
+            ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ]);
+        let val = product![7u32, true, "Alice"];
+        let expected = [
+            (Some("id"), AlgebraicValue::U32(7)),
+            (None, AlgebraicValue::Bool(true)),
+            (Some("name"), AlgebraicValue::String("Alice".into())),
+        ];
+        assert!(val.iter_named(&ty).unwrap().eq(expected.iter().map(|(n, v)| (*n, v))));
+        assert!(val.clone().into_iter_named(&ty).unwrap().eq(expected.clone()));
+
+        let map = val.to_named_map(&ty).unwrap();
+        let keys = map.keys().map(String::as_str).collect::<Vec<_>>();
+        assert_eq!(keys, ["1", "id", "name"]);
+        assert_eq!(map["1"], AlgebraicValue::Bool(true));
+    }
+
+    #[test]
+    fn iter_named_checks_arity() {
+        let ty = ProductType::new(vec![ProductTypeElement::new_named(AlgebraicType::U32, "id")]);
+        let mismatch = |value_len, type_len| ArityMismatch { value_len, type_len };
+        let short = product![];
+        assert_eq!(short.iter_named(&ty).err(), Some(mismatch(0, 1)));
+        assert_eq!(short.to_named_map(&ty).err(), Some(mismatch(0, 1)));
+        let long = product![1u32, 2u32];
+        assert_eq!(long.clone().into_iter_named(&ty).err(), Some(mismatch(2, 1)));
+
+        // Empty products have no elements to name, but are fine.
+        let empty = ProductType::new(vec![]);
+        assert_eq!(short.iter_named(&empty).unwrap().count(), 0);
+        assert!(short.to_named_map(&empty).unwrap().is_empty());
+        assert_eq!(long.iter_named(&empty).err(), Some(mismatch(2, 0)));
+    }
+}
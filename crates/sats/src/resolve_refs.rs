@@ -69,6 +69,10 @@ impl ResolveRefs for AlgebraicType {
             AlgebraicType::Product(prod) => this.with(prod)._resolve_refs(state).map(Self::Product),
             AlgebraicType::Builtin(b) => this.with(b)._resolve_refs(state).map(Self::Builtin),
             AlgebraicType::Ref(r) => this.with(r)._resolve_refs(state),
+            AlgebraicType::Newtype(nt) => this
+                .with(&*nt.inner)
+                ._resolve_refs(state)
+                .map(|inner| Self::newtype(nt.name.clone(), inner)),
         }
     }
 }
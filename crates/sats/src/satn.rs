@@ -1,7 +1,12 @@
 use derive_more::{From, Into};
 use std::fmt::{self, Write as _};
 
-use crate::ser;
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::product_value::ArityMismatch;
+use crate::{
+    ser, AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, BuiltinType, MapType, MapValue, ProductType,
+    ProductValue, SumType, SumValue, Typespace, ValueWithType,
+};
 
 /// An extension trait for [`Serialize`](ser::Serialize) providing formatting methods.
 pub trait Satn: ser::Serialize {
@@ -293,7 +298,11 @@ impl<'a, 'b> ser::Serializer for SatnFormatter<'a, 'b> {
         write!(self, "{:?}", v)
     }
 
-    fn serialize_array(mut self, _len: usize) -> Result<Self::SerializeArray, Self::Error> {
+    fn serialize_array(self, _len: usize) -> Result<Self::SerializeArray, Self::Error> {
+        self.serialize_array_unknown()
+    }
+
+    fn serialize_array_unknown(mut self) -> Result<Self::SerializeArray, Self::Error> {
         write!(self, "[")?; // Closed via `.end()`.
         Ok(ArrayFormatter {
             f: EntryWrapper::new(self.f),
@@ -515,10 +524,18 @@ impl<'a, 'b> ser::Serializer for PsqlFormatter<'a, 'b> {
         self.0.serialize_array(len)
     }
 
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        self.0.serialize_array_unknown()
+    }
+
     fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
         self.0.serialize_map(len)
     }
 
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        self.0.serialize_map_unknown()
+    }
+
     fn serialize_seq_product(self, len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
         self.0.serialize_seq_product(len)
     }
@@ -536,3 +553,249 @@ impl<'a, 'b> ser::Serializer for PsqlFormatter<'a, 'b> {
         self.0.serialize_variant(tag, name, value)
     }
 }
+
+/// Formats the value with the names in its type,
+/// e.g., `Player { id: 42, state: dead({ at: 17 }) }` for a newtype `Player`,
+/// and, with the `#` flag, on multiple indented lines.
+///
+/// Products are formatted as `{ name: value, .. }`, using the index of any unnamed element as its name,
+/// and sums as the name of the variant, or its tag if unnamed, followed by the payload in parentheses,
+/// which is left out when it is the unit.
+/// Newtypes are prefixed by their names, and `Ref`s are resolved through the typespace.
+///
+/// Where the value does not match its type, a `<type mismatch: ..>` marker is written instead of panicking.
+impl fmt::Display for ValueWithType<'_, AlgebraicValue> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Writer::with(f, |mut f| fmt_typed(&mut f, self.typespace(), self.ty(), self.value()))
+    }
+}
+
+/// Writes a marker for a mismatch between a value and its type, described by `args`, to `f`.
+fn write_mismatch(f: &mut Writer<'_, '_>, args: fmt::Arguments) -> fmt::Result {
+    write!(f, "<type mismatch: {args}>")
+}
+
+/// Writes `val`, of type `ty` in the typespace `ts`, to `f` with the names in `ty`.
+fn fmt_typed<'a>(
+    f: &mut Writer<'_, '_>,
+    ts: &'a Typespace,
+    mut ty: &'a AlgebraicType,
+    val: &AlgebraicValue,
+) -> fmt::Result {
+    // Follow `Ref`s and newtypes, writing the names of the latter.
+    loop {
+        ty = match ty {
+            &AlgebraicType::Ref(r) => match ts.get(r) {
+                Some(ty) => ty,
+                None => return write_mismatch(f, format_args!("type ref &{} is not in the typespace", r.0)),
+            },
+            AlgebraicType::Newtype(nt) => {
+                write!(f, "{} ", nt.name)?;
+                &nt.inner
+            }
+            _ => break,
+        }
+    }
+
+    match (ty, val) {
+        (AlgebraicType::Sum(ty), AlgebraicValue::Sum(val)) => fmt_sum(f, ts, ty, val),
+        (AlgebraicType::Product(ty), AlgebraicValue::Product(val)) => fmt_product(f, ts, ty, val),
+        (AlgebraicType::Builtin(BuiltinType::Array(ty)), AlgebraicValue::Array(val)) => fmt_array(f, ts, ty, val),
+        (AlgebraicType::Builtin(BuiltinType::Map(ty)), AlgebraicValue::Map(val)) => fmt_map(f, ts, ty, val),
+        (AlgebraicType::Builtin(BuiltinType::Bool), AlgebraicValue::Bool(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::I8), AlgebraicValue::I8(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::U8), AlgebraicValue::U8(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::I16), AlgebraicValue::I16(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::U16), AlgebraicValue::U16(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::I32), AlgebraicValue::I32(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::U32), AlgebraicValue::U32(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::I64), AlgebraicValue::I64(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::U64), AlgebraicValue::U64(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::I128), AlgebraicValue::I128(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::U128), AlgebraicValue::U128(v)) => write!(f, "{v}"),
+        (AlgebraicType::Builtin(BuiltinType::F32), AlgebraicValue::F32(v)) => write!(f, "{:?}", f32::from(*v)),
+        (AlgebraicType::Builtin(BuiltinType::F64), AlgebraicValue::F64(v)) => write!(f, "{:?}", f64::from(*v)),
+        (AlgebraicType::Builtin(BuiltinType::String), AlgebraicValue::String(v)) => write!(f, "{v:?}"),
+        (ty, val) => write_mismatch(f, format_args!("expected {}, got {val:?}", fmt_algebraic_type(ty))),
+    }
+}
+
+/// Writes the sum `val` of type `ty` to `f` as its variant's name and payload.
+fn fmt_sum(f: &mut Writer<'_, '_>, ts: &Typespace, ty: &SumType, val: &SumValue) -> fmt::Result {
+    let Some(var_ty) = ty.variants.get(val.tag as usize) else {
+        let len = ty.variants.len();
+        return write_mismatch(f, format_args!("tag {} of a sum of {len} variants", val.tag));
+    };
+    match var_ty.name() {
+        Some(name) => f.write_str(name)?,
+        None => write!(f, "{}", val.tag)?,
+    }
+    if *val.value != AlgebraicValue::UNIT {
+        f.write_char('(')?;
+        fmt_typed(f, ts, &var_ty.algebraic_type, &val.value)?;
+        f.write_char(')')?;
+    }
+    Ok(())
+}
+
+/// Writes the product `val` of type `ty` to `f` as `{ name: value, .. }`.
+fn fmt_product(f: &mut Writer<'_, '_>, ts: &Typespace, ty: &ProductType, val: &ProductValue) -> fmt::Result {
+    let fields = match val.iter_named(ty) {
+        Ok(fields) => fields.zip(ty.field_types()),
+        Err(ArityMismatch { value_len, type_len }) => {
+            return write_mismatch(f, format_args!("{value_len} elements for a product of {type_len}"))
+        }
+    };
+    if val.elements.is_empty() {
+        return f.write_str("{}");
+    }
+    // Pad the braces on a single line, while entries go on their own lines otherwise.
+    let pad = if matches!(f, Writer::Normal(_)) { " " } else { "" };
+    write!(f, "{{{pad}")?;
+    let mut entries = EntryWrapper::<','>::new(f.as_mut());
+    for (idx, ((name, val), el_ty)) in fields.enumerate() {
+        entries.entry(|mut f| {
+            match name {
+                Some(name) => f.write_str(name)?,
+                None => write!(f, "{idx}")?,
+            }
+            f.write_str(": ")?;
+            fmt_typed(&mut f, ts, el_ty, val)
+        })?;
+    }
+    write!(f, "{pad}}}")
+}
+
+/// Writes the array `val` of type `ty` to `f` as `[elem, ..]`.
+fn fmt_array(f: &mut Writer<'_, '_>, ts: &Typespace, ty: &ArrayType, val: &ArrayValue) -> fmt::Result {
+    f.write_char('[')?;
+    let mut entries = EntryWrapper::<','>::new(f.as_mut());
+    for elem in val.iter_cloned() {
+        entries.entry(|mut f| fmt_typed(&mut f, ts, &ty.elem_ty, &elem))?;
+    }
+    f.write_char(']')
+}
+
+/// Writes the map `val` of type `ty` to `f` as `[key: value, ..]`, or `[:]` when empty.
+fn fmt_map(f: &mut Writer<'_, '_>, ts: &Typespace, ty: &MapType, val: &MapValue) -> fmt::Result {
+    f.write_char('[')?;
+    if val.is_empty() {
+        f.write_char(':')?;
+    }
+    let mut entries = EntryWrapper::<','>::new(f.as_mut());
+    for (key, val) in val {
+        entries.entry(|mut f| {
+            fmt_typed(&mut f, ts, &ty.key_ty, key)?;
+            f.write_str(": ")?;
+            fmt_typed(&mut f, ts, &ty.ty, val)
+        })?;
+    }
+    f.write_char(']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{product, AlgebraicTypeRef, ProductTypeElement, SumTypeVariant, WithTypespace};
+
+    /// Returns a typespace with `&0 = Player`, a newtype of a product, and `&1`, the sum of its states.
+    fn typespace() -> Typespace {
+        Typespace::new(vec![
+            AlgebraicType::newtype(
+                "Player",
+                AlgebraicType::product(vec![
+                    ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+                    ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(1)), "state"),
+                    ProductTypeElement::new_named(
+                        AlgebraicType::map(AlgebraicType::String, AlgebraicType::array(AlgebraicType::U8)),
+                        "items",
+                    ),
+                ]),
+            ),
+            AlgebraicType::sum(vec![
+                SumTypeVariant::new_named(AlgebraicType::UNIT_TYPE, "alive"),
+                SumTypeVariant::new_named(
+                    AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::U64, "at")]),
+                    "dead",
+                ),
+                SumTypeVariant::new(AlgebraicType::product(vec![AlgebraicType::I8.into()]), None),
+            ]),
+        ])
+    }
+
+    fn player() -> AlgebraicType {
+        AlgebraicType::Ref(AlgebraicTypeRef(0))
+    }
+
+    fn show(ty: &AlgebraicType, val: &AlgebraicValue) -> (String, String) {
+        let ts = typespace();
+        let val = ValueWithType::new(WithTypespace::new(&ts, ty), val);
+        (val.to_string(), format!("{val:#}"))
+    }
+
+    #[test]
+    fn nested_sums_and_maps() {
+        let items = AlgebraicValue::map(
+            [
+                ("gems".into(), AlgebraicValue::Bytes(vec![1, 2])),
+                ("keys".into(), AlgebraicValue::Bytes(vec![])),
+            ]
+            .into(),
+        );
+        let dead = product![42u32, AlgebraicValue::sum(1, product![17u64].into()), items].into();
+        let (line, pretty) = show(&player(), &dead);
+        assert_eq!(
+            line,
+            r#"Player { id: 42, state: dead({ at: 17 }), items: ["gems": [1, 2], "keys": []] }"#
+        );
+        assert_eq!(
+            pretty,
+            r#"Player {
+    id: 42,
+    state: dead({
+        at: 17,
+    }),
+    items: [
+        "gems": [
+            1,
+            2,
+        ],
+        "keys": [],
+    ],
+}"#
+        );
+
+        // The unit payload is left out, and an unnamed variant and element are shown by index.
+        let empty = AlgebraicValue::map(Default::default());
+        let alive = product![1u32, AlgebraicValue::sum(0, AlgebraicValue::UNIT), empty.clone()].into();
+        assert_eq!(show(&player(), &alive).0, "Player { id: 1, state: alive, items: [:] }");
+        let other = product![2u32, AlgebraicValue::sum(2, product![-1i8].into()), empty].into();
+        assert_eq!(
+            show(&player(), &other).0,
+            "Player { id: 2, state: 2({ 0: -1 }), items: [:] }"
+        );
+    }
+
+    #[test]
+    fn mismatched_values() {
+        let items = AlgebraicValue::map(Default::default());
+        let bad_state = product![42u32, AlgebraicValue::Bool(true), items.clone()].into();
+        assert_eq!(
+            show(&player(), &bad_state).0,
+            "Player { id: 42, state: <type mismatch: expected (alive: () | dead: (at: U64) | (0: I8)), got Bool(true)>, items: [:] }"
+        );
+        let bad_tag = product![42u32, AlgebraicValue::sum(7, AlgebraicValue::UNIT), items].into();
+        assert_eq!(
+            show(&player(), &bad_tag).0,
+            "Player { id: 42, state: <type mismatch: tag 7 of a sum of 3 variants>, items: [:] }"
+        );
+        assert_eq!(
+            show(&player(), &product![42u32].into()).0,
+            "Player <type mismatch: 1 elements for a product of 3>"
+        );
+        assert_eq!(
+            show(&AlgebraicType::Ref(AlgebraicTypeRef(9)), &AlgebraicValue::U8(1)).0,
+            "<type mismatch: type ref &9 is not in the typespace>"
+        );
+    }
+}
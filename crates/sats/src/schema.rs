@@ -0,0 +1,7 @@
+//! Transformations of whole schemas, i.e., of a [`Typespace`](crate::Typespace) and the types within it.
+
+pub mod codegen;
+pub mod document;
+pub mod graph;
+pub mod normalize;
+pub mod version;
@@ -0,0 +1,375 @@
+//! Generation of Rust definitions for the types of a [`Typespace`],
+//! e.g., for a client to (de)serialize the rows and arguments of a module with their own types.
+//!
+//! The definitions derive `Serialize` and `Deserialize`,
+//! and encode exactly like values of the types they were generated from.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use super::graph::TypeDependencyGraph;
+use crate::{AlgebraicType, AlgebraicTypeRef, BuiltinType, ProductType, SumType, Typespace};
+
+/// The strongly connected component of the type a definition is in,
+/// and whether the definition must be totally ordered, as it's within the key of a map.
+type Scope = (usize, bool);
+
+/// Returns Rust source code defining a struct for each product type of `ts`,
+/// an enum for each sum type, and a type alias for any other type,
+/// to be `include!`d in a crate depending on `spacetimedb_sats`.
+///
+/// Each type is named per `names`, or else per the [names](Typespace::names) of `ts`,
+/// or `Type{index}` when in neither.
+/// Fields and variants keep their names, as far as they are Rust identifiers,
+/// and unnamed ones are named by their position, e.g., `field_1` or `Variant1`,
+/// as the derives don't support tuple structs.
+///
+/// Products and sums nested within a type, other than the unit type and options,
+/// are defined as types of their own, named after the type and the field or variant they are in.
+/// Fields and variants referring back to their own type, directly or through options, are boxed.
+///
+/// Maps become `BTreeMap`s, so the types within their keys also derive `Ord`,
+/// with floats in them as the totally ordered [`F32`](crate::builtin_value::F32) and `F64`.
+pub fn generate_rust_structs(ts: &Typespace, names: &HashMap<AlgebraicTypeRef, String>) -> String {
+    let len = ts.types.len();
+    let graph = TypeDependencyGraph::from_typespace(ts);
+    let mut component = vec![0; len];
+    for (i, types) in graph.strongly_connected_components().into_iter().enumerate() {
+        for r in types {
+            component[r.idx()] = i;
+        }
+    }
+    let mut gen = Generator {
+        names,
+        type_names: Vec::with_capacity(len),
+        component,
+        ordered: ordered_types(ts, &graph),
+        used: HashSet::new(),
+        pending: VecDeque::new(),
+        out: String::new(),
+    };
+    for idx in 0..len {
+        let r = AlgebraicTypeRef(idx as u32);
+        let name = (names.get(&r).map(|name| &**name))
+            .or_else(|| ts.name_of(r))
+            .map_or_else(|| format!("Type{idx}"), type_ident);
+        let name = gen.unique(name);
+        gen.type_names.push(name);
+    }
+
+    gen.out.push_str(HEADER);
+    for (idx, ty) in ts.types.iter().enumerate() {
+        let scope = (gen.component[idx], gen.ordered[idx]);
+        gen.pending.push_back((gen.type_names[idx].clone(), ty, scope));
+        // Nested types are defined right after the type they are nested in.
+        while let Some((name, ty, scope)) = gen.pending.pop_front() {
+            gen.define(&name, ty, scope);
+        }
+    }
+    gen.out
+}
+
+/// The imports at the top of the generated code.
+const HEADER: &str = "\
+// Generated by `spacetimedb_sats::schema::codegen::generate_rust_structs`.
+
+#[allow(unused_imports)]
+use spacetimedb_sats::*;
+use spacetimedb_sats::{de::Deserialize, ser::Serialize};
+";
+
+/// The attributes of each generated struct and enum.
+const DERIVES: &str = "\
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[sats(crate = spacetimedb_sats)]
+";
+
+/// The attributes of each generated struct and enum within the key of a map.
+const ORDERED_DERIVES: &str = "\
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[sats(crate = spacetimedb_sats)]
+";
+
+/// Returns, for each type of `ts`, whether it is within the key of a map,
+/// directly or through the types referring to it, so it must be totally ordered.
+fn ordered_types(ts: &Typespace, graph: &TypeDependencyGraph) -> Vec<bool> {
+    let mut ordered = vec![false; ts.types.len()];
+    let mut stack = Vec::new();
+    for ty in &ts.types {
+        key_refs(ty, false, &mut stack);
+    }
+    while let Some(r) = stack.pop() {
+        if r.idx() < ordered.len() && !std::mem::replace(&mut ordered[r.idx()], true) {
+            stack.extend_from_slice(graph.dependencies(r));
+        }
+    }
+    ordered
+}
+
+/// Pushes the refs within the keys of the maps in `ty` to `refs`,
+/// or all the refs in `ty` if `in_key`, i.e., `ty` is itself within a key.
+fn key_refs(ty: &AlgebraicType, in_key: bool, refs: &mut Vec<AlgebraicTypeRef>) {
+    match ty {
+        AlgebraicType::Sum(sum) => (sum.variants.iter()).for_each(|var| key_refs(&var.algebraic_type, in_key, refs)),
+        AlgebraicType::Product(prod) => {
+            (prod.elements.iter()).for_each(|elem| key_refs(&elem.algebraic_type, in_key, refs))
+        }
+        AlgebraicType::Builtin(BuiltinType::Array(arr)) => key_refs(&arr.elem_ty, in_key, refs),
+        AlgebraicType::Builtin(BuiltinType::Map(map)) => {
+            key_refs(&map.key_ty, true, refs);
+            key_refs(&map.ty, in_key, refs);
+        }
+        AlgebraicType::Builtin(_) => {}
+        &AlgebraicType::Ref(r) if in_key => refs.push(r),
+        AlgebraicType::Ref(_) => {}
+        AlgebraicType::Newtype(nt) => key_refs(&nt.inner, in_key, refs),
+    }
+}
+
+/// The state of [`generate_rust_structs`].
+struct Generator<'a> {
+    /// The names of the types given by the caller.
+    names: &'a HashMap<AlgebraicTypeRef, String>,
+    /// The Rust name of each type of the typespace.
+    type_names: Vec<String>,
+    /// The strongly connected component each type of the typespace is in.
+    component: Vec<usize>,
+    /// Whether each type of the typespace is within the key of a map.
+    ordered: Vec<bool>,
+    /// The names of the types defined so far.
+    used: HashSet<String>,
+    /// The nested types yet to be defined, with their names and the scope of the type they are nested in.
+    pending: VecDeque<(String, &'a AlgebraicType, Scope)>,
+    /// The code generated so far.
+    out: String,
+}
+
+impl<'a> Generator<'a> {
+    /// Returns `name`, or, if a type of that name is already defined, `name` suffixed with a number,
+    /// and reserves it.
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n = 1;
+        while !self.used.insert(unique.clone()) {
+            n += 1;
+            unique = format!("{name}{n}");
+        }
+        unique
+    }
+
+    /// Appends the definition of the type `name` as `ty`, in `scope`.
+    fn define(&mut self, name: &str, ty: &'a AlgebraicType, scope: Scope) {
+        match ty {
+            AlgebraicType::Product(prod) => self.define_struct(name, prod, scope),
+            AlgebraicType::Sum(sum) => self.define_enum(name, sum, scope),
+            _ => {
+                let alias = self.rust_type(ty, name.to_owned(), scope, true);
+                writeln!(self.out, "\npub type {name} = {alias};").unwrap();
+            }
+        }
+    }
+
+    /// Appends the definition of the struct `name` for `prod`.
+    fn define_struct(&mut self, name: &str, prod: &'a ProductType, scope: Scope) {
+        let fields = (prod.elements.iter().enumerate())
+            .map(|(i, elem)| {
+                let field = elem.name().map_or_else(|| format!("field_{i}"), member_ident);
+                let hint = format!("{name}{}", pascal_case(&field));
+                (field, self.rust_type(&elem.algebraic_type, hint, scope, true))
+            })
+            .collect::<Vec<_>>();
+
+        let def = &mut self.out;
+        def.push('\n');
+        def.push_str(if scope.1 { ORDERED_DERIVES } else { DERIVES });
+        let lowercase_fields = fields.iter().any(|(field, _)| !is_snake_case(field));
+        push_allows(def, name, lowercase_fields, "non_snake_case");
+        writeln!(def, "pub struct {name} {{").unwrap();
+        for (field, ty) in fields {
+            writeln!(def, "    pub {field}: {ty},").unwrap();
+        }
+        def.push_str("}\n");
+    }
+
+    /// Appends the definition of the enum `name` for `sum`.
+    fn define_enum(&mut self, name: &str, sum: &'a SumType, scope: Scope) {
+        let variants = (sum.variants.iter().enumerate())
+            .map(|(i, var)| {
+                let variant = var.name().map_or_else(|| format!("Variant{i}"), member_ident);
+                let hint = format!("{name}{}", pascal_case(&variant));
+                let payload = (!var.is_unit()).then(|| self.rust_type(&var.algebraic_type, hint, scope, true));
+                (variant, payload)
+            })
+            .collect::<Vec<_>>();
+
+        let def = &mut self.out;
+        def.push('\n');
+        def.push_str(if scope.1 { ORDERED_DERIVES } else { DERIVES });
+        let lowercase_variants = variants.iter().any(|(variant, _)| !is_camel_case(variant));
+        push_allows(def, name, lowercase_variants, "non_camel_case_types");
+        writeln!(def, "pub enum {name} {{").unwrap();
+        for (variant, payload) in variants {
+            match payload {
+                Some(ty) => writeln!(def, "    {variant}({ty}),").unwrap(),
+                None => writeln!(def, "    {variant},").unwrap(),
+            }
+        }
+        def.push_str("}\n");
+    }
+
+    /// Returns the Rust type for `ty`, in a type of `scope`,
+    /// defining nested products and sums as types named `hint`.
+    ///
+    /// `direct` is whether a value of `ty` is stored inline in the value of the type,
+    /// i.e., not behind an array or a map, so a `Ref` back to the component needs a box.
+    fn rust_type(&mut self, ty: &'a AlgebraicType, hint: String, scope: Scope, direct: bool) -> String {
+        let (component, ordered) = scope;
+        match ty {
+            &AlgebraicType::Ref(r) => match self.type_names.get(r.idx()) {
+                Some(name) if direct && self.component[r.idx()] == component => format!("Box<{name}>"),
+                Some(name) => name.clone(),
+                // A ref outside of the typespace is left for the includer to define.
+                None => (self.names.get(&r)).map_or_else(|| format!("Type{}", r.idx()), |name| type_ident(name)),
+            },
+            AlgebraicType::Newtype(nt) => self.rust_type(&nt.inner, hint, scope, direct),
+            AlgebraicType::Product(prod) if prod.elements.is_empty() => "()".to_owned(),
+            AlgebraicType::Sum(sum) => match sum.as_option() {
+                Some(some) => format!("Option<{}>", self.rust_type(some, hint, scope, direct)),
+                None => self.nested(ty, hint, scope),
+            },
+            AlgebraicType::Product(_) => self.nested(ty, hint, scope),
+            AlgebraicType::Builtin(b) => match b {
+                BuiltinType::Bool => "bool".to_owned(),
+                BuiltinType::I8 => "i8".to_owned(),
+                BuiltinType::U8 => "u8".to_owned(),
+                BuiltinType::I16 => "i16".to_owned(),
+                BuiltinType::U16 => "u16".to_owned(),
+                BuiltinType::I32 => "i32".to_owned(),
+                BuiltinType::U32 => "u32".to_owned(),
+                BuiltinType::I64 => "i64".to_owned(),
+                BuiltinType::U64 => "u64".to_owned(),
+                BuiltinType::I128 => "i128".to_owned(),
+                BuiltinType::U128 => "u128".to_owned(),
+                BuiltinType::F32 if ordered => "spacetimedb_sats::builtin_value::F32".to_owned(),
+                BuiltinType::F64 if ordered => "spacetimedb_sats::builtin_value::F64".to_owned(),
+                BuiltinType::F32 => "f32".to_owned(),
+                BuiltinType::F64 => "f64".to_owned(),
+                BuiltinType::String => "String".to_owned(),
+                BuiltinType::Array(arr) => {
+                    let elem = self.rust_type(&arr.elem_ty, format!("{hint}Elem"), scope, false);
+                    format!("Vec<{elem}>")
+                }
+                BuiltinType::Map(map) => {
+                    let key = self.rust_type(&map.key_ty, format!("{hint}Key"), (component, true), false);
+                    let value = self.rust_type(&map.ty, format!("{hint}Value"), scope, false);
+                    format!("std::collections::BTreeMap<{key}, {value}>")
+                }
+            },
+        }
+    }
+
+    /// Schedules the definition of the nested type `ty` as a type named after `hint`, returning that name.
+    fn nested(&mut self, ty: &'a AlgebraicType, hint: String, scope: Scope) -> String {
+        let name = self.unique(hint);
+        self.pending.push_back((name.clone(), ty, scope));
+        name
+    }
+}
+
+/// Appends an `#[allow(..)]` to `def` for the lints the names of a type `name` and its members would trip,
+/// `members_lint` being that of the members if `members_unconventional`.
+fn push_allows(def: &mut String, name: &str, members_unconventional: bool, members_lint: &'static str) {
+    let mut lints = Vec::new();
+    if !is_camel_case(name) {
+        lints.push("non_camel_case_types");
+    }
+    if members_unconventional && !lints.contains(&members_lint) {
+        lints.push(members_lint);
+    }
+    if !lints.is_empty() {
+        writeln!(def, "#[allow({})]", lints.join(", ")).unwrap();
+    }
+}
+
+/// The keywords of Rust, which are only identifiers as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+    "override", "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+    "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Returns `name` as an identifier, with characters that can't be in one replaced by `_`.
+fn sanitize(name: &str) -> String {
+    let mut ident = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' })
+        .collect::<String>();
+    if !ident.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Returns `name` as the identifier of a field or variant.
+fn member_ident(name: &str) -> String {
+    let ident = sanitize(name);
+    match &*ident {
+        // These can't even be raw identifiers.
+        "_" | "crate" | "self" | "Self" | "super" => ident + "_",
+        _ if KEYWORDS.contains(&&*ident) => format!("r#{ident}"),
+        _ => ident,
+    }
+}
+
+/// Returns `name` as the identifier of a type.
+///
+/// Keywords, being lowercase, are capitalized rather than made raw identifiers,
+/// as types are capitalized anyway.
+fn type_ident(name: &str) -> String {
+    let ident = member_ident(name);
+    match ident.strip_prefix("r#") {
+        Some(keyword) => pascal_case(keyword),
+        None => ident,
+    }
+}
+
+/// Returns the identifier `ident` in `PascalCase`, e.g., `player_id` as `PlayerId`.
+fn pascal_case(ident: &str) -> String {
+    let ident = ident.strip_prefix("r#").unwrap_or(ident);
+    let mut out = String::with_capacity(ident.len());
+    for part in ident.split('_').filter(|part| !part.is_empty()) {
+        let mut chars = part.chars();
+        out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        out.extend(chars);
+    }
+    out
+}
+
+/// Returns whether the identifier `ident` is in `snake_case`, as fields should be.
+fn is_snake_case(ident: &str) -> bool {
+    !ident.chars().any(char::is_uppercase)
+}
+
+/// Returns whether the identifier `ident` is in `CamelCase`, as types and variants should be.
+fn is_camel_case(ident: &str) -> bool {
+    ident.starts_with(char::is_uppercase) && !ident.contains('_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(member_ident("player_id"), "player_id");
+        assert_eq!(member_ident("type"), "r#type");
+        assert_eq!(member_ident("self"), "self_");
+        assert_eq!(member_ident("2d pos"), "_2d_pos");
+        assert_eq!(type_ident("match"), "Match");
+        assert_eq!(pascal_case("r#type"), "Type");
+        assert_eq!(pascal_case("max_hp"), "MaxHp");
+        assert!(is_camel_case("Circle") && !is_camel_case("none") && !is_camel_case("Big_Circle"));
+        assert!(is_snake_case("max_hp") && !is_snake_case("maxHp"));
+    }
+}
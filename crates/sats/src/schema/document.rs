@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use crate::de::Deserialize;
+use crate::ser::Serialize;
+use crate::AlgebraicTypeRef;
+
+/// Documentation for the types of a [`Typespace`](crate::Typespace),
+/// and for the fields of its product types and the variants of its sum types,
+/// e.g., from the doc comments on the definitions the types were derived from.
+///
+/// Types, fields, and variants without documentation are simply absent,
+/// so a `SchemaDoc` is cheap for a mostly undocumented schema.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[sats(crate = crate)]
+pub struct SchemaDoc {
+    /// The documentation of each documented type, by ref.
+    types: BTreeMap<AlgebraicTypeRef, TypeDoc>,
+}
+
+/// The documentation of a type and of its fields or variants.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[sats(crate = crate)]
+struct TypeDoc {
+    /// The documentation of the type itself.
+    doc: Option<String>,
+    /// The documentation of each documented field or variant, by index.
+    fields: BTreeMap<u32, String>,
+}
+
+/// Converts the index of a field or variant to how it's stored.
+fn field_key(field_idx: usize) -> u32 {
+    field_idx.try_into().expect("field index does not fit in a `u32`")
+}
+
+impl SchemaDoc {
+    /// Returns documentation where nothing is documented.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the documentation of the type `r` to `doc`.
+    pub fn set_type_doc(&mut self, r: AlgebraicTypeRef, doc: &str) {
+        self.types.entry(r).or_default().doc = Some(doc.to_owned());
+    }
+
+    /// Sets the documentation of the field, or variant, at `field_idx` of the type `r` to `doc`.
+    pub fn set_field_doc(&mut self, r: AlgebraicTypeRef, field_idx: usize, doc: &str) {
+        let fields = &mut self.types.entry(r).or_default().fields;
+        fields.insert(field_key(field_idx), doc.to_owned());
+    }
+
+    /// Returns the documentation of the type `r`, if any.
+    pub fn get_type_doc(&self, r: AlgebraicTypeRef) -> Option<&str> {
+        self.types.get(&r)?.doc.as_deref()
+    }
+
+    /// Returns the documentation of the field, or variant, at `field_idx` of the type `r`, if any.
+    pub fn get_field_doc(&self, r: AlgebraicTypeRef, field_idx: usize) -> Option<&str> {
+        let key = u32::try_from(field_idx).ok()?;
+        self.types.get(&r)?.fields.get(&key).map(String::as_str)
+    }
+
+    /// Returns the documentation as JSON, e.g., for a language server to show on hover.
+    ///
+    /// The result is an object with a key per documented type, its ref as a string,
+    /// mapping to an object with the `"doc"` of the type, if any,
+    /// and the `"fields"` documented, mapping the index of each field or variant, as a string, to its doc:
+    ///
+    /// ```json
+    /// { "0": { "doc": "A player.", "fields": { "1": "The display name." } } }
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn export_json(&self) -> serde_json::Value {
+        let types = self.types.iter().map(|(r, ty)| {
+            let mut obj = serde_json::Map::new();
+            if let Some(doc) = &ty.doc {
+                obj.insert("doc".into(), doc.as_str().into());
+            }
+            let fields = ty.fields.iter().map(|(i, doc)| (i.to_string(), doc.as_str().into()));
+            obj.insert("fields".into(), serde_json::Value::Object(fields.collect()));
+            (r.0.to_string(), obj.into())
+        });
+        serde_json::Value::Object(types.collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn;
+
+    fn player_doc() -> SchemaDoc {
+        let mut docs = SchemaDoc::new();
+        docs.set_type_doc(AlgebraicTypeRef(0), "A player.");
+        docs.set_field_doc(AlgebraicTypeRef(0), 1, "The display name.");
+        // A variant of an undocumented sum type.
+        docs.set_field_doc(AlgebraicTypeRef(2), 0, "Not yet joined.");
+        docs
+    }
+
+    #[test]
+    fn get_and_set() {
+        let mut docs = player_doc();
+        assert_eq!(docs.get_type_doc(AlgebraicTypeRef(0)), Some("A player."));
+        assert_eq!(docs.get_field_doc(AlgebraicTypeRef(0), 1), Some("The display name."));
+        assert_eq!(docs.get_field_doc(AlgebraicTypeRef(2), 0), Some("Not yet joined."));
+
+        // Missing docs.
+        assert_eq!(docs.get_type_doc(AlgebraicTypeRef(1)), None);
+        assert_eq!(docs.get_type_doc(AlgebraicTypeRef(2)), None);
+        assert_eq!(docs.get_field_doc(AlgebraicTypeRef(0), 0), None);
+        assert_eq!(docs.get_field_doc(AlgebraicTypeRef(1), 0), None);
+        assert_eq!(docs.get_field_doc(AlgebraicTypeRef(0), usize::MAX), None);
+
+        docs.set_type_doc(AlgebraicTypeRef(0), "A player of the game.");
+        assert_eq!(docs.get_type_doc(AlgebraicTypeRef(0)), Some("A player of the game."));
+    }
+
+    #[test]
+    fn round_trip() {
+        let docs = player_doc();
+        let bytes = bsatn::to_vec(&docs).unwrap();
+        assert_eq!(bsatn::from_slice::<SchemaDoc>(&bytes).unwrap(), docs);
+        let empty = bsatn::to_vec(&SchemaDoc::new()).unwrap();
+        assert_eq!(bsatn::from_slice::<SchemaDoc>(&empty).unwrap(), SchemaDoc::new());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn export_json() {
+        let expected = serde_json::json!({
+            "0": { "doc": "A player.", "fields": { "1": "The display name." } },
+            "2": { "fields": { "0": "Not yet joined." } },
+        });
+        assert_eq!(player_doc().export_json(), expected);
+        assert_eq!(SchemaDoc::new().export_json(), serde_json::json!({}));
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use super::normalize::map_refs;
+use crate::{AlgebraicTypeRef, Typespace};
+
+/// The graph of which types of a [`Typespace`] refer to which,
+/// e.g., to process types in dependency order, each after the types it refers to.
+///
+/// There is an edge from each type to each type it has a `Ref` to, however deeply nested within it.
+/// `Ref`s outside of the typespace are not types of the graph, so they are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDependencyGraph {
+    /// The distinct types each type refers to, by ref, in the order they are first referred to.
+    deps: Vec<Vec<AlgebraicTypeRef>>,
+}
+
+/// An error for a typespace that can't be sorted in dependency order,
+/// as some of its types refer to each other, or to themselves, in a cycle.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("types {} refer to each other in a cycle", DisplayRefs(types))]
+pub struct CycleError {
+    /// The types of one cycle, in ascending order.
+    ///
+    /// These are a strongly connected component of the graph,
+    /// so every one of them can reach every other by following refs.
+    pub types: Vec<AlgebraicTypeRef>,
+}
+
+/// Displays refs separated by commas.
+struct DisplayRefs<'a>(&'a [AlgebraicTypeRef]);
+
+impl fmt::Display for DisplayRefs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, r) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{r}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TypeDependencyGraph {
+    /// Returns the graph of the refs between the types of `ts`.
+    pub fn from_typespace(ts: &Typespace) -> Self {
+        let len = ts.types.len();
+        let deps = (ts.types.iter())
+            .map(|ty| {
+                let mut deps = Vec::new();
+                map_refs(ty, &mut |r| {
+                    if r.idx() < len && !deps.contains(&r) {
+                        deps.push(r);
+                    }
+                    r
+                });
+                deps
+            })
+            .collect();
+        Self { deps }
+    }
+
+    /// Returns the number of types in the graph.
+    pub fn len(&self) -> usize {
+        self.deps.len()
+    }
+
+    /// Returns whether the graph has no types.
+    pub fn is_empty(&self) -> bool {
+        self.deps.is_empty()
+    }
+
+    /// Returns the distinct types `r` refers to, in the order they are first referred to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `r` is not a type of the graph.
+    pub fn dependencies(&self, r: AlgebraicTypeRef) -> &[AlgebraicTypeRef] {
+        &self.deps[r.idx()]
+    }
+
+    /// Returns every type of the graph ordered so that each comes after the types it refers to,
+    /// or an error with one cycle if there is no such order.
+    ///
+    /// The order is deterministic: types come in the order their dependencies are all sorted,
+    /// starting with the types without dependencies in the order of their refs.
+    pub fn topological_sort(&self) -> Result<Vec<AlgebraicTypeRef>, CycleError> {
+        // Kahn's algorithm, with the edges followed backwards, from each type to those referring to it.
+        let mut remaining = self.deps.iter().map(Vec::len).collect::<Vec<_>>();
+        let mut dependents = vec![Vec::new(); self.deps.len()];
+        for (i, deps) in self.deps.iter().enumerate() {
+            for dep in deps {
+                dependents[dep.idx()].push(i);
+            }
+        }
+
+        let mut ready = (0..self.deps.len())
+            .filter(|&i| remaining[i] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::with_capacity(self.deps.len());
+        while let Some(i) = ready.pop_front() {
+            order.push(AlgebraicTypeRef(i as u32));
+            for &dependent in &dependents[i] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+        }
+
+        if order.len() < self.deps.len() {
+            let types = (self.strongly_connected_components().into_iter())
+                .find(|scc| self.is_cycle(scc))
+                .expect("a graph that can't be sorted should have a cycle");
+            return Err(CycleError { types });
+        }
+        Ok(order)
+    }
+
+    /// Returns the strongly connected components of the graph,
+    /// i.e., the groups of types that can each reach every other type of their group by following refs.
+    ///
+    /// A type not in a cycle is a component of its own.
+    /// The types of each component are in ascending order,
+    /// and each component comes after the components its types refer to.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<AlgebraicTypeRef>> {
+        // Tarjan's algorithm, with an explicit stack rather than recursion, so deep chains of refs are fine.
+        const UNVISITED: usize = usize::MAX;
+        let len = self.deps.len();
+        // The order in which each type was first visited, and the lowest such order reachable from it.
+        let mut index = vec![UNVISITED; len];
+        let mut lowlink = vec![0; len];
+        // The visited types not yet assigned to a component.
+        let mut stack = Vec::new();
+        let mut on_stack = vec![false; len];
+        // The types being visited, each with the position of its next dependency to follow.
+        let mut calls = Vec::<(usize, usize)>::new();
+        let mut next_index = 0;
+        let mut components = Vec::new();
+
+        for root in 0..len {
+            if index[root] != UNVISITED {
+                continue;
+            }
+            calls.push((root, 0));
+            while let Some(&(v, pos)) = calls.last() {
+                if index[v] == UNVISITED {
+                    index[v] = next_index;
+                    lowlink[v] = next_index;
+                    next_index += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                }
+                if let Some(dep) = self.deps[v].get(pos) {
+                    calls.last_mut().unwrap().1 += 1;
+                    let w = dep.idx();
+                    if index[w] == UNVISITED {
+                        calls.push((w, 0));
+                    } else if on_stack[w] {
+                        lowlink[v] = lowlink[v].min(index[w]);
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[v]);
+                }
+                if lowlink[v] == index[v] {
+                    let mut component = Vec::new();
+                    loop {
+                        let w = stack.pop().expect("the root of a component should be on the stack");
+                        on_stack[w] = false;
+                        component.push(AlgebraicTypeRef(w as u32));
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Returns whether the strongly connected component `scc` has a cycle,
+    /// i.e., has more than one type or a type referring to itself.
+    fn is_cycle(&self, scc: &[AlgebraicTypeRef]) -> bool {
+        match scc {
+            [r] => self.dependencies(*r).contains(r),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlgebraicType, ProductTypeElement};
+
+    fn r(i: u32) -> AlgebraicTypeRef {
+        AlgebraicTypeRef(i)
+    }
+
+    /// Returns a product type with a field of each of the types `refs`.
+    fn refers_to(refs: &[u32]) -> AlgebraicType {
+        AlgebraicType::product(
+            (refs.iter())
+                .map(|&i| ProductTypeElement::new(AlgebraicType::Ref(r(i)), None))
+                .collect(),
+        )
+    }
+
+    fn refs(is: &[u32]) -> Vec<AlgebraicTypeRef> {
+        is.iter().copied().map(r).collect()
+    }
+
+    #[test]
+    fn linear_chain() {
+        // `&0 -> &1 -> &2 -> &3`, with refs nested in other types and repeated.
+        let ts = Typespace::new(vec![
+            AlgebraicType::option(AlgebraicType::array(AlgebraicType::Ref(r(1)))),
+            refers_to(&[2, 2]),
+            AlgebraicType::map(AlgebraicType::String, AlgebraicType::Ref(r(3))),
+            AlgebraicType::U8,
+        ]);
+        let graph = TypeDependencyGraph::from_typespace(&ts);
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.dependencies(r(1)), [r(2)]);
+        assert_eq!(graph.dependencies(r(3)), []);
+
+        assert_eq!(graph.topological_sort().unwrap(), refs(&[3, 2, 1, 0]));
+        let sccs = graph.strongly_connected_components();
+        assert_eq!(sccs, [refs(&[3]), refs(&[2]), refs(&[1]), refs(&[0])]);
+
+        let empty = TypeDependencyGraph::from_typespace(&Typespace::default());
+        assert!(empty.is_empty());
+        assert_eq!(empty.topological_sort().unwrap(), []);
+    }
+
+    #[test]
+    fn cycles_are_detected() {
+        // `&0 -> &1 -> &2 -> &0`, and `&3` depends on the cycle.
+        let ts = Typespace::new(vec![refers_to(&[1]), refers_to(&[2]), refers_to(&[0]), refers_to(&[0])]);
+        let err = TypeDependencyGraph::from_typespace(&ts).topological_sort().unwrap_err();
+        assert_eq!(err.types, refs(&[0, 1, 2]));
+        assert_eq!(err.to_string(), "types &0, &1, &2 refer to each other in a cycle");
+
+        // A type referring to itself is a cycle too.
+        let ts = Typespace::new(vec![AlgebraicType::U8, refers_to(&[0, 1])]);
+        let graph = TypeDependencyGraph::from_typespace(&ts);
+        assert_eq!(graph.topological_sort().unwrap_err().types, [r(1)]);
+        assert_eq!(graph.strongly_connected_components(), [refs(&[0]), refs(&[1])]);
+
+        // Refs outside of the typespace are left out.
+        let ts = Typespace::new(vec![refers_to(&[7])]);
+        let graph = TypeDependencyGraph::from_typespace(&ts);
+        assert_eq!(graph.dependencies(r(0)), []);
+        assert_eq!(graph.topological_sort().unwrap(), [r(0)]);
+    }
+
+    #[test]
+    fn multiple_components() {
+        // Components `{&1, &4}` and `{&2, &5, &6}`, the latter referring to the former,
+        // with `&0` referring to both, `&3` referred to by `&1`, and the unrelated `&7`.
+        let ts = Typespace::new(vec![
+            refers_to(&[2, 1]),
+            refers_to(&[4, 3]),
+            refers_to(&[5]),
+            AlgebraicType::String,
+            refers_to(&[1]),
+            refers_to(&[6, 4]),
+            refers_to(&[2]),
+            AlgebraicType::U32,
+        ]);
+        let graph = TypeDependencyGraph::from_typespace(&ts);
+        let sccs = graph.strongly_connected_components();
+        assert_eq!(sccs.len(), 5);
+        // Every component comes after those it refers to.
+        let position = |t: AlgebraicTypeRef| sccs.iter().position(|scc| scc.contains(&t)).unwrap();
+        for (i, scc) in sccs.iter().enumerate() {
+            for &t in scc {
+                assert!(graph.dependencies(t).iter().all(|&dep| position(dep) <= i));
+            }
+        }
+        let mut sorted = sccs.clone();
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            [refs(&[0]), refs(&[1, 4]), refs(&[2, 5, 6]), refs(&[3]), refs(&[7])]
+        );
+        assert!(graph.topological_sort().is_err());
+
+        // Without the edges `&4 -> &1` and `&6 -> &2`, there are no cycles,
+        // and each type is sorted after its dependencies.
+        let mut ts = ts;
+        ts.types[4] = AlgebraicType::UNIT_TYPE;
+        ts.types[6] = AlgebraicType::UNIT_TYPE;
+        let graph = TypeDependencyGraph::from_typespace(&ts);
+        let order = graph.topological_sort().unwrap();
+        assert_eq!(order, refs(&[3, 4, 6, 7, 1, 5, 2, 0]));
+        assert_eq!(graph.strongly_connected_components().len(), 8);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use super::graph::TypeDependencyGraph;
+use crate::{
+    AlgebraicType, AlgebraicTypeRef, ArrayType, BuiltinType, MapType, NewtypeType, ProductType, ProductTypeElement,
+    SumType, SumTypeVariant, Typespace,
+};
+
+/// Returns `ts` with structurally identical types merged,
+/// along with the mapping from each ref into `ts`, in order, to the ref of its canonical type in the result.
+///
+/// Two types are structurally identical when they are equal
+/// after replacing each `AlgebraicType::Ref` in them with the type it refers to, however deep.
+/// This includes recursive types, e.g., `&0 = { next: Option<&0> }` and `&1 = { next: Option<&1> }`.
+/// The canonical type of a group of identical types is the first of them in `ts`,
+/// and the canonical types keep their relative order in the result.
+/// All `Ref`s in the result are rewritten to refer to canonical types,
+/// so no two types in the result are structurally identical.
+///
+/// `Ref`s outside of `ts` are never merged,
+/// and are rewritten to remain outside of the result, by as much as they were outside of `ts`.
+///
+/// Named types are never merged with other types, whatever their structure,
+/// and keep their [names](Typespace::names) in the result.
+pub fn normalize_typespace(ts: &Typespace) -> (Typespace, Vec<(AlgebraicTypeRef, AlgebraicTypeRef)>) {
+    let len = ts.types.len();
+    // The index of the group of identical types each type belongs to.
+    // Starting with one group for the unnamed types and one for each named type,
+    // each round splits groups by the shape of their types,
+    // with `Ref`s replaced by the group of the type referred to, until no group is split.
+    // Groups are numbered in the order of their first type, so the final numbers are the new refs.
+    let mut seen = HashMap::new();
+    let mut group = (0..len)
+        .map(|i| {
+            let new = seen.len();
+            *seen.entry(ts.name_of(AlgebraicTypeRef(i as u32))).or_insert(new)
+        })
+        .collect::<Vec<_>>();
+    let mut num_groups = seen.len();
+    loop {
+        let mut seen = HashMap::new();
+        let next = (ts.types.iter().zip(&group))
+            .map(|(ty, &g)| {
+                let key = (g, map_refs(ty, &mut |r| group_ref(&group, r, len)));
+                let new = seen.len();
+                *seen.entry(key).or_insert(new)
+            })
+            .collect::<Vec<_>>();
+        group = next;
+        if seen.len() == num_groups {
+            break;
+        }
+        num_groups = seen.len();
+    }
+
+    // Keep the first type of each group, with `Ref`s rewritten to the groups.
+    let mut types = Vec::with_capacity(num_groups);
+    for (ty, &g) in ts.types.iter().zip(&group) {
+        if g == types.len() {
+            types.push(map_refs(ty, &mut |r| group_ref(&group, r, num_groups)));
+        }
+    }
+    let mapping = (0..len)
+        .map(|i| {
+            let old = AlgebraicTypeRef(i as u32);
+            (old, group_ref(&group, old, num_groups))
+        })
+        .collect();
+    let names = (ts.names())
+        .remap(|r| Some(group_ref(&group, r, num_groups)))
+        .expect("named types are never merged");
+    (Typespace::new(types).with_names(names), mapping)
+}
+
+/// Returns `ts` with only the types reachable from the named types or the types of `roots`,
+/// along with the mapping from each ref into `ts`, in order, to its ref in the result, if it was kept.
+///
+/// A type is reachable from another if it is that type or is referred to, however indirectly, by a `Ref` within it.
+/// The types kept keep their relative order, and their names, and all `Ref`s in the result are rewritten to them.
+/// As in [`normalize_typespace`], `Ref`s outside of `ts` remain outside of the result.
+pub fn prune_typespace(
+    ts: &Typespace,
+    roots: &[AlgebraicTypeRef],
+) -> (Typespace, Vec<(AlgebraicTypeRef, Option<AlgebraicTypeRef>)>) {
+    let len = ts.types.len();
+    let graph = TypeDependencyGraph::from_typespace(ts);
+    let mut reachable = vec![false; len];
+    let mut stack = (roots.iter().copied())
+        .chain(ts.names().iter().map(|(_, r)| r))
+        .filter(|r| r.idx() < len)
+        .collect::<Vec<_>>();
+    while let Some(r) = stack.pop() {
+        if !std::mem::replace(&mut reachable[r.idx()], true) {
+            stack.extend_from_slice(graph.dependencies(r));
+        }
+    }
+
+    let mut num_kept = 0;
+    let new_refs = (reachable.iter())
+        .map(|&keep| {
+            keep.then(|| {
+                num_kept += 1;
+                AlgebraicTypeRef(num_kept - 1)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut new_ref = |r: AlgebraicTypeRef| match new_refs.get(r.idx()) {
+        Some(new) => new.expect("a type kept only refers to types kept"),
+        None => AlgebraicTypeRef(num_kept + (r.idx() - len) as u32),
+    };
+    let types = (ts.types.iter().zip(&reachable))
+        .filter(|(_, &keep)| keep)
+        .map(|(ty, _)| map_refs(ty, &mut new_ref))
+        .collect();
+    let names = (ts.names())
+        .remap(|r| new_refs.get(r.idx()).copied().flatten())
+        .expect("types are never merged");
+    let mapping = (new_refs.iter().enumerate())
+        .map(|(i, &new)| (AlgebraicTypeRef(i as u32), new))
+        .collect();
+    (Typespace::new(types).with_names(names), mapping)
+}
+
+/// Returns a ref to the group of the type `r` refers to, per `group`,
+/// or, should `r` be outside of the typespace, a ref as far past `new_len` as `r` is past the typespace.
+fn group_ref(group: &[usize], r: AlgebraicTypeRef, new_len: usize) -> AlgebraicTypeRef {
+    let idx = match group.get(r.idx()) {
+        Some(&g) => g,
+        None => new_len + (r.idx() - group.len()),
+    };
+    AlgebraicTypeRef(idx as u32)
+}
+
+/// Returns `ty` with every `Ref` within it replaced per `f`.
+pub(crate) fn map_refs(ty: &AlgebraicType, f: &mut impl FnMut(AlgebraicTypeRef) -> AlgebraicTypeRef) -> AlgebraicType {
+    match ty {
+        AlgebraicType::Sum(sum) => AlgebraicType::Sum(SumType::new(
+            (sum.variants.iter())
+                .map(|var| SumTypeVariant::new(map_refs(&var.algebraic_type, f), var.name.clone()))
+                .collect(),
+        )),
+        AlgebraicType::Product(prod) => AlgebraicType::Product(ProductType::new(
+            (prod.elements.iter())
+                .map(|elem| ProductTypeElement::new(map_refs(&elem.algebraic_type, f), elem.name.clone()))
+                .collect(),
+        )),
+        AlgebraicType::Builtin(BuiltinType::Array(ArrayType { elem_ty })) => AlgebraicType::array(map_refs(elem_ty, f)),
+        AlgebraicType::Builtin(BuiltinType::Map(MapType { key_ty, ty })) => {
+            AlgebraicType::map(map_refs(key_ty, f), map_refs(ty, f))
+        }
+        AlgebraicType::Builtin(_) => ty.clone(),
+        &AlgebraicType::Ref(r) => AlgebraicType::Ref(f(r)),
+        AlgebraicType::Newtype(nt) => AlgebraicType::Newtype(NewtypeType::new(nt.name.clone(), map_refs(&nt.inner, f))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta_type::MetaType;
+
+    fn point() -> AlgebraicType {
+        AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::U32, "x")])
+    }
+
+    fn field(name: &str, ty: AlgebraicType) -> AlgebraicType {
+        AlgebraicType::product(vec![ProductTypeElement::new_named(ty, name)])
+    }
+
+    #[test]
+    fn merges_copies() {
+        let mut ts = Typespace::new(vec![point(); 10]);
+        // A type referring to every copy.
+        let uses = ts.add(AlgebraicType::product(
+            (0..10)
+                .map(|i| ProductTypeElement::new(AlgebraicType::Ref(AlgebraicTypeRef(i)), None))
+                .collect(),
+        ));
+        let (normal, mapping) = normalize_typespace(&ts);
+
+        assert_eq!(normal.types.len(), 2);
+        assert_eq!(normal.types[0], point());
+        let r0 = AlgebraicType::Ref(AlgebraicTypeRef(0));
+        let expected = AlgebraicType::product((0..10).map(|_| ProductTypeElement::new(r0.clone(), None)).collect());
+        assert_eq!(normal.types[1], expected);
+
+        let mut expected = (0..10)
+            .map(|i| (AlgebraicTypeRef(i), AlgebraicTypeRef(0)))
+            .collect::<Vec<_>>();
+        expected.push((uses, AlgebraicTypeRef(1)));
+        assert_eq!(mapping, expected);
+    }
+
+    #[test]
+    fn merges_through_refs() {
+        // `&1` and `&3` are identical only because `&0` and `&2` are.
+        // `&4` and `&5` are identical recursive types, each referring to itself.
+        let ts = Typespace::new(vec![
+            point(),
+            field("p", AlgebraicType::Ref(AlgebraicTypeRef(0))),
+            point(),
+            field("p", AlgebraicType::Ref(AlgebraicTypeRef(2))),
+            field("next", AlgebraicType::option(AlgebraicType::Ref(AlgebraicTypeRef(4)))),
+            field("next", AlgebraicType::option(AlgebraicType::Ref(AlgebraicTypeRef(5)))),
+            // Differs from `&1` by the field name.
+            field("q", AlgebraicType::Ref(AlgebraicTypeRef(2))),
+        ]);
+        let (normal, mapping) = normalize_typespace(&ts);
+
+        let r = |i| AlgebraicType::Ref(AlgebraicTypeRef(i));
+        let expected = [
+            point(),
+            field("p", r(0)),
+            field("next", AlgebraicType::option(r(2))),
+            field("q", r(0)),
+        ];
+        assert_eq!(normal.types, expected);
+        let targets = mapping.iter().map(|(_, new)| new.0).collect::<Vec<_>>();
+        assert_eq!(targets, [0, 1, 0, 1, 2, 2, 3]);
+        // Normalizing is idempotent.
+        assert_eq!(normalize_typespace(&normal).0.types, normal.types);
+    }
+
+    #[test]
+    fn distinct_types_are_kept() {
+        // Distinct types, one of them large.
+        let mut ts = Typespace::new(vec![AlgebraicType::meta_type(), AlgebraicType::String]);
+        // A dangling `Ref` is kept past the end.
+        ts.add(field("dangling", AlgebraicType::Ref(AlgebraicTypeRef(5))));
+        let (normal, mapping) = normalize_typespace(&ts);
+
+        assert_eq!(normal.types[..2], ts.types[..2]);
+        assert_eq!(
+            normal.types[2],
+            field("dangling", AlgebraicType::Ref(AlgebraicTypeRef(5)))
+        );
+        assert!(mapping.iter().all(|(old, new)| old == new));
+        assert_eq!(normalize_typespace(&Typespace::default()).0.types, []);
+    }
+
+    #[test]
+    fn names_follow_merged_types() {
+        // `&0` and `&2` are unnamed copies, while the named `&1` and `&3` are copies too.
+        let r = |i| AlgebraicType::Ref(AlgebraicTypeRef(i));
+        let mut ts = Typespace::new(vec![point(), point(), point(), point(), field("p", r(2))]);
+        ts.register_name("Point", AlgebraicTypeRef(1)).unwrap();
+        ts.register_name("Position", AlgebraicTypeRef(3)).unwrap();
+        ts.register_name("Holder", AlgebraicTypeRef(4)).unwrap();
+        let (normal, mapping) = normalize_typespace(&ts);
+
+        assert_eq!(normal.types, [point(), point(), point(), field("p", r(0))]);
+        let targets = mapping.iter().map(|(_, new)| new.0).collect::<Vec<_>>();
+        assert_eq!(targets, [0, 1, 0, 2, 3]);
+        assert_eq!(normal.resolve_name("Point"), Some(AlgebraicTypeRef(1)));
+        assert_eq!(normal.resolve_name("Position"), Some(AlgebraicTypeRef(2)));
+        assert_eq!(normal.name_of(AlgebraicTypeRef(3)), Some("Holder"));
+        assert_eq!(normal.name_of(AlgebraicTypeRef(0)), None);
+    }
+
+    #[test]
+    fn prunes_unreachable_types() {
+        let r = |i| AlgebraicType::Ref(AlgebraicTypeRef(i));
+        let mut ts = Typespace::new(vec![
+            point(),
+            field("p", r(0)),
+            AlgebraicType::String,
+            field("tree", AlgebraicType::array(r(3))),
+            // Unreachable, though it refers to a reachable type.
+            field("unused", r(1)),
+            field("far", r(9)),
+        ]);
+        ts.register_name("Holder", AlgebraicTypeRef(1)).unwrap();
+        ts.register_name("Unused", AlgebraicTypeRef(4)).unwrap();
+        let (pruned, _) = prune_typespace(&ts, &[AlgebraicTypeRef(3)]);
+        // Named types are roots, so `Unused` can't be pruned while it has a name.
+        assert_eq!(pruned.types.len(), 4);
+
+        ts = ts.with_names(Default::default());
+        ts.register_name("Holder", AlgebraicTypeRef(1)).unwrap();
+        let (pruned, mapping) = prune_typespace(&ts, &[AlgebraicTypeRef(3), AlgebraicTypeRef(5)]);
+        let expected = [
+            point(),
+            field("p", r(0)),
+            field("tree", AlgebraicType::array(r(2))),
+            field("far", r(7)),
+        ];
+        assert_eq!(pruned.types, expected);
+        let targets = mapping.iter().map(|(_, new)| new.map(|r| r.0)).collect::<Vec<_>>();
+        assert_eq!(targets, [Some(0), Some(1), None, Some(2), None, Some(3)]);
+        assert_eq!(pruned.resolve_name("Holder"), Some(AlgebraicTypeRef(1)));
+        assert_eq!(prune_typespace(&ts, &[]).0.types, [point(), field("p", r(0))]);
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+
+use crate::de::Deserialize;
+use crate::ser::Serialize;
+use crate::{AlgebraicType, BuiltinType, Typespace};
+
+/// The version of a schema, in the style of semantic versioning,
+/// e.g., of the schema of a module as it's deployed and then upgraded.
+///
+/// Versions are ordered by their `major`, then `minor`, then `patch` numbers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[sats(crate = crate)]
+pub struct SchemaVersion {
+    /// Bumped for changes that break reading data of the old schema as the new one.
+    pub major: u16,
+    /// Bumped for additions, after which data of the old schema can still be read as the new one.
+    pub minor: u16,
+    /// Bumped for upgrades without structural changes.
+    pub patch: u16,
+}
+
+impl SchemaVersion {
+    /// Returns the version `major.minor.patch`.
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// Returns whether upgrading from this version to `new` is backward compatible,
+    /// i.e., `new` is a later version with the same `major` number.
+    pub fn is_backward_compatible_upgrade(&self, new: &SchemaVersion) -> bool {
+        new.major == self.major && new > self
+    }
+
+    /// Returns this version bumped by `bump`, resetting the numbers below the one bumped,
+    /// or an error if the number to bump is already `u16::MAX`.
+    fn bumped(self, bump: Bump) -> Result<Self, UpgradeError> {
+        let inc = |n: u16| n.checked_add(1).ok_or(UpgradeError::VersionOverflow(self));
+        Ok(match bump {
+            Bump::Patch => Self::new(self.major, self.minor, inc(self.patch)?),
+            Bump::Minor => Self::new(self.major, inc(self.minor)?, 0),
+            Bump::Major => Self::new(inc(self.major)?, 0, 0),
+        })
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// An error for an upgrade whose new schema declares a version too low for its changes.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("the upgrade declares version {declared}, but {change}, which needs at least {required}")]
+pub struct IncompatibleUpgrade {
+    /// The version declared by the new schema.
+    pub declared: SchemaVersion,
+    /// The lowest version the changes of the upgrade allow.
+    pub required: SchemaVersion,
+    /// The change that needs the bump to `required`, e.g., `"type &2 changed incompatibly"`.
+    pub change: String,
+}
+
+/// An error checking an upgrade with [`check_upgrade_compatibility`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeError {
+    /// The new schema declares a version too low for its changes.
+    #[error(transparent)]
+    Incompatible(#[from] IncompatibleUpgrade),
+    /// The old version can't be bumped for the changes, as the number to bump is already `u16::MAX`.
+    #[error("the version {0} can't be bumped any further")]
+    VersionOverflow(SchemaVersion),
+}
+
+/// The part of a [`SchemaVersion`] that an upgrade bumps, from least to most significant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// Checks the upgrade from `old_schema` to `new_schema`,
+/// returning the version of `new_schema` after the upgrade.
+///
+/// The types of the schemas are compared by ref, as refs are stable across upgrades.
+/// An upgrade whose types are all unchanged bumps the patch number of the old version.
+/// One that only adds to the schema, by adding types or appending variants to sum types,
+/// so that data of the old schema can still be read as the new one, bumps the minor number.
+/// Any other change, e.g., adding a field to a product type, or removing or changing a type, bumps the major number.
+/// The old version is that of `old_schema`, or `0.0.0` if it has none.
+///
+/// If `new_schema` declares a version, it's returned as long as it's at least the bumped version,
+/// and an [`UpgradeError::Incompatible`] error otherwise.
+/// If it doesn't declare one, the bumped version is returned.
+/// Should the number to bump be `u16::MAX` already, the upgrade is an [`UpgradeError::VersionOverflow`] error.
+pub fn check_upgrade_compatibility(
+    old_schema: &Typespace,
+    new_schema: &Typespace,
+) -> Result<SchemaVersion, UpgradeError> {
+    let (bump, change) = upgrade_bump(old_schema, new_schema);
+    let required = old_schema.version().unwrap_or_default().bumped(bump)?;
+    match new_schema.version() {
+        Some(declared) if declared < required => Err(IncompatibleUpgrade {
+            declared,
+            required,
+            change: change.expect("a bump past a patch should have a change"),
+        }
+        .into()),
+        Some(declared) => Ok(declared),
+        None => Ok(required),
+    }
+}
+
+/// Returns the bump needed to upgrade from `old` to `new`,
+/// along with a description of the first change needing it, unless it's a patch.
+fn upgrade_bump(old: &Typespace, new: &Typespace) -> (Bump, Option<String>) {
+    if new.types.len() < old.types.len() {
+        return (Bump::Major, Some(format!("type &{} was removed", new.types.len())));
+    }
+    let mut bump = (Bump::Patch, None);
+    for (i, (old_ty, new_ty)) in old.types.iter().zip(&new.types).enumerate() {
+        match type_bump(old_ty, new_ty) {
+            Bump::Major => return (Bump::Major, Some(format!("type &{i} changed incompatibly"))),
+            Bump::Minor if bump.0 == Bump::Patch => bump = (Bump::Minor, Some(format!("type &{i} was extended"))),
+            _ => {}
+        }
+    }
+    if new.types.len() > old.types.len() && bump.0 == Bump::Patch {
+        bump = (Bump::Minor, Some(format!("type &{} was added", old.types.len())));
+    }
+    bump
+}
+
+/// Returns the bump needed to change the type `old` to `new`.
+fn type_bump(old: &AlgebraicType, new: &AlgebraicType) -> Bump {
+    use AlgebraicType::*;
+    match (old, new) {
+        _ if old == new => Bump::Patch,
+        // Appended variants don't change the tags of the old ones.
+        (Sum(old), Sum(new)) if new.variants.len() >= old.variants.len() => {
+            let old_variants = old.variants.iter().zip(&new.variants);
+            let bump = old_variants.fold(Bump::Patch, |bump, (old, new)| match old.name == new.name {
+                true => bump.max(type_bump(&old.algebraic_type, &new.algebraic_type)),
+                false => Bump::Major,
+            });
+            bump.max(if new.variants.len() > old.variants.len() {
+                Bump::Minor
+            } else {
+                Bump::Patch
+            })
+        }
+        // Products must keep their fields, as their encodings have no room for new ones.
+        (Product(old), Product(new)) if new.elements.len() == old.elements.len() => {
+            (old.elements.iter().zip(&new.elements)).fold(Bump::Patch, |bump, (old, new)| match old.name == new.name {
+                true => bump.max(type_bump(&old.algebraic_type, &new.algebraic_type)),
+                false => Bump::Major,
+            })
+        }
+        (Builtin(BuiltinType::Array(old)), Builtin(BuiltinType::Array(new))) => type_bump(&old.elem_ty, &new.elem_ty),
+        (Builtin(BuiltinType::Map(old)), Builtin(BuiltinType::Map(new))) => {
+            type_bump(&old.key_ty, &new.key_ty).max(type_bump(&old.ty, &new.ty))
+        }
+        (Newtype(old), Newtype(new)) if old.name == new.name => type_bump(&old.inner, &new.inner),
+        _ => Bump::Major,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bsatn, AlgebraicTypeRef, ProductTypeElement, SumTypeVariant};
+
+    fn player() -> AlgebraicType {
+        AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+            ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(1)), "status"),
+        ])
+    }
+
+    fn status(variants: &[&str]) -> AlgebraicType {
+        AlgebraicType::simple_enum(variants.iter().copied())
+    }
+
+    fn schema(version: SchemaVersion) -> Typespace {
+        Typespace::new(vec![player(), status(&["online", "offline"])]).with_version(version)
+    }
+
+    #[test]
+    fn versions() {
+        let v = SchemaVersion::new(1, 2, 3);
+        assert_eq!(v.to_string(), "1.2.3");
+        assert!(v < SchemaVersion::new(1, 3, 0));
+        assert!(v < SchemaVersion::new(2, 0, 0));
+        assert!(SchemaVersion::new(1, 10, 0) > SchemaVersion::new(1, 9, 9));
+
+        assert!(v.is_backward_compatible_upgrade(&SchemaVersion::new(1, 2, 4)));
+        assert!(v.is_backward_compatible_upgrade(&SchemaVersion::new(1, 5, 0)));
+        assert!(!v.is_backward_compatible_upgrade(&SchemaVersion::new(2, 0, 0)));
+        assert!(!v.is_backward_compatible_upgrade(&v));
+        assert!(!v.is_backward_compatible_upgrade(&SchemaVersion::new(1, 2, 2)));
+
+        // The version is kept alongside the typespace, not in its encoding.
+        let ts = schema(v);
+        assert_eq!(ts.version(), Some(v));
+        let decoded = bsatn::from_slice::<Typespace>(&bsatn::to_vec(&ts).unwrap()).unwrap();
+        assert_eq!(decoded.types, ts.types);
+        assert_eq!(decoded.version(), None);
+    }
+
+    #[test]
+    fn patch_bump() {
+        let old = schema(SchemaVersion::new(1, 2, 3));
+        let new = Typespace::new(old.types.clone());
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(1, 2, 4)));
+        // Without a version, the old schema is taken to be `0.0.0`.
+        assert_eq!(
+            check_upgrade_compatibility(&Typespace::new(old.types.clone()), &new),
+            Ok(SchemaVersion::new(0, 0, 1))
+        );
+    }
+
+    #[test]
+    fn minor_bump() {
+        let old = schema(SchemaVersion::new(1, 2, 3));
+
+        // A new variant, appended.
+        let mut new = Typespace::new(old.types.clone());
+        new.types[1] = status(&["online", "offline", "away"]);
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(1, 3, 0)));
+
+        // A new type.
+        let mut new = Typespace::new(old.types.clone());
+        new.add(AlgebraicType::String);
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(1, 3, 0)));
+
+        // A new variant within a builtin within a type.
+        let wrap = |status| AlgebraicType::array(AlgebraicType::option(status));
+        let old = Typespace::new(vec![wrap(status(&["a"]))]);
+        let new = Typespace::new(vec![wrap(status(&["a", "b"]))]);
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(0, 1, 0)));
+
+        // Declaring a higher version is fine, but a patch is not enough.
+        let new = new.with_version(SchemaVersion::new(0, 4, 0));
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(0, 4, 0)));
+        let new = new.with_version(SchemaVersion::new(0, 0, 9));
+        let UpgradeError::Incompatible(err) = check_upgrade_compatibility(&old, &new).unwrap_err() else {
+            panic!("the error should be an incompatible upgrade")
+        };
+        assert_eq!(err.required, SchemaVersion::new(0, 1, 0));
+        assert_eq!(
+            err.to_string(),
+            "the upgrade declares version 0.0.9, but type &0 was extended, which needs at least 0.1.0"
+        );
+    }
+
+    #[test]
+    fn major_bump() {
+        let old = schema(SchemaVersion::new(1, 2, 3));
+        let bumped = |new: &Typespace| check_upgrade_compatibility(&old, new);
+
+        // A new field.
+        let mut new = Typespace::new(old.types.clone());
+        let AlgebraicType::Product(fields) = &mut new.types[0] else {
+            unreachable!()
+        };
+        fields
+            .elements
+            .push(ProductTypeElement::new_named(AlgebraicType::String, "name"));
+        assert_eq!(bumped(&new), Ok(SchemaVersion::new(2, 0, 0)));
+
+        // A renamed or reordered variant.
+        let mut new = Typespace::new(old.types.clone());
+        new.types[1] = status(&["offline", "online"]);
+        assert_eq!(bumped(&new), Ok(SchemaVersion::new(2, 0, 0)));
+
+        // A changed builtin, even when a variant is also added elsewhere.
+        let mut new = Typespace::new(old.types.clone());
+        new.types[1] = status(&["online", "offline", "away"]);
+        new.types[0] = AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::U32, "id")]);
+        assert_eq!(bumped(&new), Ok(SchemaVersion::new(2, 0, 0)));
+
+        // A removed type.
+        let new = Typespace::new(vec![player()]);
+        assert_eq!(bumped(&new), Ok(SchemaVersion::new(2, 0, 0)));
+        let err = bumped(&new.with_version(SchemaVersion::new(1, 9, 0))).unwrap_err();
+        assert!(
+            matches!(err, UpgradeError::Incompatible(IncompatibleUpgrade { change, .. }) if change == "type &1 was removed")
+        );
+
+        // A variant with a changed payload.
+        let old = Typespace::new(vec![AlgebraicType::sum(vec![SumTypeVariant::new_named(
+            AlgebraicType::U8,
+            "a",
+        )])]);
+        let new = Typespace::new(vec![AlgebraicType::sum(vec![SumTypeVariant::new_named(
+            AlgebraicType::I8,
+            "a",
+        )])]);
+        assert_eq!(check_upgrade_compatibility(&old, &new), Ok(SchemaVersion::new(1, 0, 0)));
+    }
+
+    #[test]
+    fn version_overflow() {
+        let max = u16::MAX;
+        let old = schema(SchemaVersion::new(1, 2, max));
+        let new = Typespace::new(old.types.clone());
+        let err = check_upgrade_compatibility(&old, &new).unwrap_err();
+        assert_eq!(err, UpgradeError::VersionOverflow(SchemaVersion::new(1, 2, max)));
+        assert_eq!(
+            err.to_string(),
+            format!("the version 1.2.{max} can't be bumped any further")
+        );
+
+        // Nor can the major number be bumped, e.g., for a removed type.
+        let new = Typespace::new(vec![player()]);
+        let old = schema(SchemaVersion::new(max, max, max));
+        let err = check_upgrade_compatibility(&old, &new).unwrap_err();
+        assert_eq!(err, UpgradeError::VersionOverflow(SchemaVersion::new(max, max, max)));
+    }
+}
@@ -1,10 +1,13 @@
 // Some parts copyright Serde developers under the MIT / Apache-2.0 licenses at your option.
 // See `serde` version `v1.0.169` for the parts where MIT / Apache-2.0 applies.
 
+pub mod elision;
 mod impls;
 #[cfg(feature = "serde")]
 pub mod serde;
+pub mod trace_serializer;
 
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// A **data format** that can deserialize any data structure supported by SATs.
@@ -96,6 +99,17 @@ pub trait Serializer: Sized {
     /// The argument is the number of elements in the sequence.
     fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error>;
 
+    /// Begin to serialize a variably sized array whose length isn't known up front,
+    /// e.g., of the elements of a filtered iterator.
+    /// This call must be followed by zero or more calls to [`SerializeArray::serialize_element`],
+    /// then a call to [`SerializeArray::end`].
+    ///
+    /// Formats that need the length before the elements, and can't buffer them, don't support this.
+    /// By default, this is an error.
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        Err(Self::Error::custom("arrays of unknown length are not supported"))
+    }
+
     /// Begin to serialize a variably sized map.
     /// This call must be followed by zero or more calls to [`SerializeMap::serialize_element`],
     /// then a call to [`SerializeMap::end`].
@@ -103,6 +117,17 @@ pub trait Serializer: Sized {
     /// The argument is the number of elements in the map.
     fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error>;
 
+    /// Begin to serialize a variably sized map whose length isn't known up front,
+    /// e.g., of the entries of an iterator of pairs.
+    /// This call must be followed by zero or more calls to [`SerializeMap::serialize_entry`],
+    /// then a call to [`SerializeMap::end`].
+    ///
+    /// As with [`serialize_array_unknown`](Serializer::serialize_array_unknown),
+    /// not all formats support this, and by default, this is an error.
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        Err(Self::Error::custom("maps of unknown length are not supported"))
+    }
+
     /// Begin to serialize a product with unnamed fields.
     /// This call must be followed by zero or more calls to [`SerializeSeqProduct::serialize_element`],
     /// then a call to [`SerializeSeqProduct::end`].
@@ -124,6 +149,69 @@ pub trait Serializer: Sized {
         name: Option<&str>,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>;
+
+    /// Serialize the unit value, i.e., the empty product `()`.
+    ///
+    /// By default, this is serialized as a product of no fields.
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_seq_product(0)?.end()
+    }
+
+    /// Serialize a sum value of the variant with the chosen `tag` and `name` with a unit payload,
+    /// e.g., a variant of a C-like enum.
+    ///
+    /// By default, this is serialized as the variant with the payload `()`.
+    fn serialize_unit_variant(self, tag: u8, name: Option<&str>) -> Result<Self::Ok, Self::Error> {
+        self.serialize_variant(tag, name, &())
+    }
+
+    /// Hints that the value about to be serialized is exactly `size` bytes long when serialized in BSATN,
+    /// e.g., as for a product of fixed-width numbers.
+    ///
+    /// This is only a hint, which the BSATN serializer uses to reserve capacity, and does nothing by default.
+    fn hint_total_size(&mut self, size: usize) {
+        let _ = size;
+    }
+
+    /// Serialize a marker standing in for a value equal to its default,
+    /// as done by a [`DefaultElidingSerializer`](elision::DefaultElidingSerializer).
+    ///
+    /// By default, the marker is serialized as the unit variant [`DEFAULT_TAG`](elision::DEFAULT_TAG).
+    fn serialize_default_marker(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit_variant(elision::DEFAULT_TAG, None)
+    }
+
+    /// Serialize the strings of an [`ArrayValue::String`](crate::ArrayValue::String).
+    ///
+    /// Used so that formats can encode such arrays specially,
+    /// e.g., BSATN with a dictionary of their distinct strings.
+    #[doc(hidden)]
+    fn __serialize_string_array_value(self, v: &[String]) -> Result<Self::Ok, Self::Error> {
+        v.serialize(self)
+    }
+
+    /// Serialize the strings of an [`ArrayValue::StringPacked`](crate::ArrayValue::StringPacked).
+    ///
+    /// Like [`Serializer::__serialize_string_array_value`], which it must encode the same as.
+    #[doc(hidden)]
+    fn __serialize_packed_strings(self, v: &crate::builtin_value::PackedStrings) -> Result<Self::Ok, Self::Error> {
+        v.serialize(self)
+    }
+
+    /// Serialize an array of fixed-width numbers.
+    ///
+    /// Used in the `Serialize for [T]` implementations of the numeric types
+    /// so that formats storing such arrays as raw little-endian bytes can write them in one go.
+    #[cfg(feature = "bytemuck")]
+    #[doc(hidden)]
+    fn __serialize_pod_array<T: Serialize + bytemuck::Pod>(self, v: &[T]) -> Result<Self::Ok, Self::Error> {
+        let mut vec = self.serialize_array(v.len())?;
+        vec.hint_fixed_element_size(std::mem::size_of::<T>());
+        for elem in v {
+            vec.serialize_element(elem)?;
+        }
+        vec.end()
+    }
 }
 
 pub use spacetimedb_bindings_macro::Serialize;
@@ -138,6 +226,12 @@ pub use spacetimedb_bindings_macro::Serialize;
 /// [`serde::Serialize`]: ::serde::Serialize
 /// [`serde`]: https://crates.io/crates/serde
 pub trait Serialize {
+    /// The length of the BSATN encoding of every value of this type, if they all have the same length.
+    ///
+    /// Used by the derive to [hint](Serializer::hint_total_size) the size of products of such types.
+    #[doc(hidden)]
+    const __BSATN_STATIC_SIZE: Option<usize> = None;
+
     /// Serialize `self` in the data format of `S` using the provided `serializer`.
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
 
@@ -155,12 +249,170 @@ pub trait Serialize {
         }
         vec.end()
     }
+
+    /// Used in the `Serialize for VecDeque<T>` implementation
+    /// to serialize the two halves of a ring buffer as one array,
+    /// allowing the same specialization as `__serialize_array`.
+    #[doc(hidden)]
+    #[inline(always)]
+    fn __serialize_array_halves<S: Serializer>(front: &[Self], back: &[Self], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        Self: Sized,
+    {
+        if back.is_empty() {
+            return Self::__serialize_array(front, serializer);
+        }
+        let mut vec = serializer.serialize_array(front.len() + back.len())?;
+        for elem in front.iter().chain(back) {
+            vec.serialize_element(elem)?;
+        }
+        vec.end()
+    }
+}
+
+/// Serializes the elements of `iter` as an array with `serializer`,
+/// through [`Serializer::serialize_array_unknown`] unless the exact number of elements is known.
+///
+/// Like `collect`, this trusts the [`size_hint`](Iterator::size_hint) of the iterator,
+/// when exact, to be correct.
+pub fn serialize_iter<S: Serializer, I>(serializer: S, iter: I) -> Result<S::Ok, S::Error>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    let iter = iter.into_iter();
+    let mut arr = match iter.size_hint() {
+        (lo, Some(hi)) if lo == hi => serializer.serialize_array(lo)?,
+        _ => serializer.serialize_array_unknown()?,
+    };
+    for elem in iter {
+        arr.serialize_element(&elem)?;
+    }
+    arr.end()
+}
+
+/// Serializes the key-value pairs of `iter` as a map with `serializer`,
+/// through [`Serializer::serialize_map_unknown`] unless the exact number of pairs is known.
+///
+/// When `strict`, the keys must be in strictly ascending order, as they are in a `BTreeMap`,
+/// so that the map is encoded canonically, and otherwise this errors.
+pub fn serialize_map_iter<S: Serializer, I, K, V>(serializer: S, iter: I, strict: bool) -> Result<S::Ok, S::Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    let iter = iter.into_iter();
+    let mut map = match iter.size_hint() {
+        (lo, Some(hi)) if lo == hi => serializer.serialize_map(lo)?,
+        _ => serializer.serialize_map_unknown()?,
+    };
+    let mut prev = None;
+    for (i, (key, value)) in iter.enumerate() {
+        if strict && prev.as_ref().is_some_and(|prev| *prev >= key) {
+            return Err(S::Error::custom(format_args!(
+                "key {i} of the map is not greater than the key before it"
+            )));
+        }
+        map.serialize_entry(&key, &value)?;
+        if strict {
+            prev = Some(key);
+        }
+    }
+    map.end()
+}
+
+/// Returns the sum of `sizes`, or `None` if any of them is `None`.
+///
+/// Used by the derive to compute [`Serialize::__BSATN_STATIC_SIZE`] for a product of its fields.
+#[doc(hidden)]
+pub const fn __sum_static_sizes(sizes: &[Option<usize>]) -> Option<usize> {
+    let mut total = 0;
+    let mut i = 0;
+    while i < sizes.len() {
+        match sizes[i] {
+            Some(size) => total += size,
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(total)
 }
 
 /// The base trait serialization error types must implement.
 pub trait Error {
     /// Returns an error derived from `msg: impl Display`.
     fn custom<T: fmt::Display>(msg: T) -> Self;
+
+    /// The path can't be serialized as a string.
+    fn invalid_path(err: InvalidPath) -> Self
+    where
+        Self: Sized,
+    {
+        Self::custom(err)
+    }
+}
+
+/// An error for a path that can't be represented as a string,
+/// as it is not valid UTF-8 or contains a null byte, which no file system allows.
+///
+/// Paths must be representable as strings to be serialized or deserialized,
+/// rather than being converted lossily or cut short at the null byte.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid path {path:?}: {reason}")]
+pub struct InvalidPath {
+    /// The path, converted lossily.
+    pub path: String,
+    /// Why the path is invalid.
+    pub reason: InvalidPathReason,
+}
+
+/// Why a path is an [`InvalidPath`].
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidPathReason {
+    /// The path is not valid UTF-8.
+    #[error("not valid UTF-8")]
+    NotUtf8,
+    /// The path contains a null byte.
+    #[error("contains a null byte")]
+    NullByte,
+}
+
+impl InvalidPath {
+    /// Returns `path` as a string, or an error if it can't be represented as one.
+    pub fn check(path: &std::path::Path) -> Result<&str, Self> {
+        let invalid = |reason| Self {
+            path: path.to_string_lossy().into_owned(),
+            reason,
+        };
+        let s = path.to_str().ok_or_else(|| invalid(InvalidPathReason::NotUtf8))?;
+        if s.contains('\0') {
+            return Err(invalid(InvalidPathReason::NullByte));
+        }
+        Ok(s)
+    }
+}
+
+/// A path serialized as a string even when it is not valid UTF-8,
+/// by replacing any invalid sequences with `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// A [`PathBuf`](std::path::PathBuf) refuses to be serialized rather than being changed silently,
+/// so this wrapper is for when a lossy path is better than none, e.g., for display.
+/// A path with a null byte is still refused, as no lossy conversion can make sense of it.
+/// It deserializes as the path of the string.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LossyPath(pub std::path::PathBuf);
+
+impl From<std::path::PathBuf> for LossyPath {
+    fn from(path: std::path::PathBuf) -> Self {
+        Self(path)
+    }
+}
+
+impl From<LossyPath> for std::path::PathBuf {
+    fn from(path: LossyPath) -> Self {
+        path.0
+    }
 }
 
 impl Error for String {
@@ -190,6 +442,15 @@ pub trait SerializeArray {
     /// Serialize an array `element`.
     fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), Self::Error>;
 
+    /// Hints that each element of the array, when serialized in BSATN, is exactly `size` bytes long,
+    /// e.g., as for an array of fixed-width numbers.
+    ///
+    /// Called before the first element, if at all.
+    /// This is only a hint, which the BSATN serializer uses to reserve capacity, and does nothing by default.
+    fn hint_fixed_element_size(&mut self, size: usize) {
+        let _ = size;
+    }
+
     /// Consumes and finalizes the array serializer returning the `Self::Ok` data.
     fn end(self) -> Result<Self::Ok, Self::Error>;
 }
@@ -249,8 +510,30 @@ pub trait SerializeNamedProduct {
     type Error: Error;
 
     /// Serialize a named product `element` with `name`.
+    ///
+    /// The elements must be serialized in the order of the fields of the product.
+    /// This is the fast path, used by the derives.
     fn serialize_element<T: Serialize + ?Sized>(&mut self, name: Option<&str>, elem: &T) -> Result<(), Self::Error>;
 
+    /// Serialize the element at `index` among the fields of the product, with `name`,
+    /// when the elements may be serialized in any order, e.g., as they come in a JSON object.
+    ///
+    /// Each index must be serialized exactly once, which formats that care about order check by [`end`](Self::end).
+    ///
+    /// By default, this is [`serialize_element`](Self::serialize_element),
+    /// which suits formats that identify elements by name rather than by order.
+    /// Formats that care about order must buffer elements until those before them arrive,
+    /// as [`ForwardNamedToSeqProduct`] does.
+    fn serialize_element_at<T: Serialize + ?Sized>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        elem: &T,
+    ) -> Result<(), Self::Error> {
+        let _ = index;
+        self.serialize_element(name, elem)
+    }
+
     /// Consumes and finalizes the product serializer returning the `Self::Ok` data.
     fn end(self) -> Result<Self::Ok, Self::Error>;
 }
@@ -258,15 +541,32 @@ pub trait SerializeNamedProduct {
 /// Forwards the implementation of a named product value
 /// to the implementation of the unnamed kind,
 /// thereby ignoring any field names.
+///
+/// Elements serialized [out of order](SerializeNamedProduct::serialize_element_at)
+/// are buffered as [`AlgebraicValue`](crate::AlgebraicValue)s until all the elements before them are serialized.
 pub struct ForwardNamedToSeqProduct<S> {
     /// The unnamed product serializer.
     tup: S,
+    /// The number of fields of the product, if known.
+    len: Option<usize>,
+    /// The index of the next element to forward to `tup`.
+    next: usize,
+    /// The elements serialized ahead of `next`, by index.
+    ahead: BTreeMap<usize, crate::AlgebraicValue>,
+    /// Whether any element was serialized by index, so that `end` must check that none are missing.
+    by_index: bool,
 }
 
 impl<S> ForwardNamedToSeqProduct<S> {
     /// Returns a forwarder based on the provided unnamed product serializer.
     pub fn new(tup: S) -> Self {
-        Self { tup }
+        Self {
+            tup,
+            len: None,
+            next: 0,
+            ahead: BTreeMap::new(),
+            by_index: false,
+        }
     }
 
     /// Forwards the serialization of a named product of `len` fields
@@ -275,7 +575,9 @@ impl<S> ForwardNamedToSeqProduct<S> {
     where
         Ser: Serializer<SerializeSeqProduct = S>,
     {
-        ser.serialize_seq_product(len).map(Self::new)
+        let mut this = ser.serialize_seq_product(len).map(Self::new)?;
+        this.len = Some(len);
+        Ok(this)
     }
 }
 
@@ -284,10 +586,54 @@ impl<S: SerializeSeqProduct> SerializeNamedProduct for ForwardNamedToSeqProduct<
     type Error = S::Error;
 
     fn serialize_element<T: Serialize + ?Sized>(&mut self, _name: Option<&str>, elem: &T) -> Result<(), Self::Error> {
+        self.next += 1;
         self.tup.serialize_element(elem)
     }
 
+    fn serialize_element_at<T: Serialize + ?Sized>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        elem: &T,
+    ) -> Result<(), Self::Error> {
+        self.by_index = true;
+        let field = || match name {
+            Some(name) => format!("`{name}` ({index})"),
+            None => index.to_string(),
+        };
+        if self.len.map_or(false, |len| index >= len) {
+            return Err(Error::custom(format_args!(
+                "field {} is out of bounds for a product of {} fields",
+                field(),
+                self.len.unwrap_or_default()
+            )));
+        }
+        if index < self.next || self.ahead.contains_key(&index) {
+            return Err(Error::custom(format_args!("field {} serialized twice", field())));
+        }
+        if index > self.next {
+            let value = match elem.serialize(crate::algebraic_value::ser::ValueSerializer) {
+                Ok(value) => value,
+                Err(e) => match e {},
+            };
+            self.ahead.insert(index, value);
+            return Ok(());
+        }
+        self.serialize_element(name, elem)?;
+        while let Some(value) = self.ahead.remove(&self.next) {
+            self.serialize_element(None, &value)?;
+        }
+        Ok(())
+    }
+
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        let missing = !self.ahead.is_empty() || self.len.map_or(false, |len| self.next < len);
+        if self.by_index && missing {
+            return Err(Error::custom(format_args!(
+                "field {} of the product was never serialized",
+                self.next
+            )));
+        }
         self.tup.end()
     }
 }
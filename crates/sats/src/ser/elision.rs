@@ -0,0 +1,639 @@
+//! Serialization that elides values equal to a known default, to reduce the size of the output.
+//!
+//! A [`DefaultElidingSerializer`] compares the value serialized with a `baseline`, e.g., a row of default values.
+//! Each value equal to its baseline is replaced by a [default marker](Serializer::serialize_default_marker),
+//! which BSATN encodes in a single byte.
+//! Any other value is serialized as the variant [`PRESENT_TAG`] holding it,
+//! where the fields of a product are in turn elided against the fields of a product baseline.
+//! A row where most fields have their default values thus shrinks to little more than a byte per field.
+//!
+//! Since formats like BSATN don't describe the values they encode,
+//! the inverse, [`DefaultElidingDeserializer`], needs the type of the value as well as the same baseline.
+
+use std::convert::Infallible;
+
+use crate::algebraic_value::de::{ValueDeserializeError, ValueDeserializer};
+use crate::algebraic_value::ser::{SerializeArrayValue, SerializeMapValue, SerializeProductValue, ValueSerializer};
+use crate::de::{self, DeserializeSeed, Deserializer, Error as _, SeqProductAccess, VariantAccess as _};
+use crate::ser::{
+    Error as _, ForwardNamedToSeqProduct, Serialize, SerializeArray, SerializeMap, SerializeSeqProduct, Serializer,
+};
+use crate::typespace::UnresolvedRef;
+use crate::{impl_serialize, AlgebraicType, AlgebraicValue, ProductType, WithTypespace};
+
+/// The tag of the variant the [default marker](Serializer::serialize_default_marker) is serialized as.
+pub const DEFAULT_TAG: u8 = 0;
+
+/// The tag of the variant holding a value that differs from its baseline.
+pub const PRESENT_TAG: u8 = 1;
+
+/// A serializer that serializes values equal to `baseline` as a default marker with `inner`,
+/// and serializes any other value with `inner`, marked as present.
+///
+/// The value is first serialized to an [`AlgebraicValue`] to compare it with `baseline`.
+/// When `baseline` is a product, the value must be a product of as many fields,
+/// each of which is elided against the corresponding field of `baseline`.
+pub struct DefaultElidingSerializer<'a, S> {
+    /// The value compared with.
+    baseline: &'a AlgebraicValue,
+    /// The serializer the elided value is serialized with.
+    inner: S,
+}
+
+impl<'a, S: Serializer> DefaultElidingSerializer<'a, S> {
+    /// Returns a serializer eliding values equal to `baseline` when serializing with `inner`.
+    pub fn new(baseline: &'a AlgebraicValue, inner: S) -> Self {
+        Self { baseline, inner }
+    }
+
+    /// Serializes `value` elided against the baseline.
+    fn elide(self, value: AlgebraicValue) -> Result<S::Ok, S::Error> {
+        serialize_elided(&value, self.baseline, self.inner)
+    }
+}
+
+/// Serializes `value` with `ser`, elided against `baseline`.
+fn serialize_elided<S: Serializer>(
+    value: &AlgebraicValue,
+    baseline: &AlgebraicValue,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    if value == baseline {
+        return ser.serialize_default_marker();
+    }
+    match (value, baseline) {
+        (AlgebraicValue::Product(val), AlgebraicValue::Product(base)) if val.elements.len() == base.elements.len() => {
+            let fields = ElidedFields {
+                values: &val.elements,
+                baselines: &base.elements,
+            };
+            ser.serialize_variant(PRESENT_TAG, None, &fields)
+        }
+        (_, AlgebraicValue::Product(base)) => Err(S::Error::custom(format_args!(
+            "cannot elide {value:?} against a product of {} fields",
+            base.elements.len()
+        ))),
+        _ => ser.serialize_variant(PRESENT_TAG, None, value),
+    }
+}
+
+/// The fields of a product, each elided against its baseline.
+struct ElidedFields<'a> {
+    values: &'a [AlgebraicValue],
+    baselines: &'a [AlgebraicValue],
+}
+
+impl_serialize!([] ElidedFields<'_>, (self, ser) => {
+    let mut prod = ser.serialize_seq_product(self.values.len())?;
+    for (value, baseline) in self.values.iter().zip(self.baselines) {
+        prod.serialize_element(&Elided { value, baseline })?;
+    }
+    prod.end()
+});
+
+/// A value elided against its baseline.
+struct Elided<'a> {
+    value: &'a AlgebraicValue,
+    baseline: &'a AlgebraicValue,
+}
+
+impl_serialize!([] Elided<'_>, (self, ser) => serialize_elided(self.value, self.baseline, ser));
+
+/// Unwraps the result of the infallible [`ValueSerializer`].
+fn infallible<T>(res: Result<T, Infallible>) -> T {
+    match res {
+        Ok(v) => v,
+        Err(e) => match e {},
+    }
+}
+
+macro_rules! method {
+    ($name:ident -> $t:ty) => {
+        fn $name(self, v: $t) -> Result<Self::Ok, Self::Error> {
+            self.elide(v.into())
+        }
+    };
+}
+
+impl<'a, S: Serializer> Serializer for DefaultElidingSerializer<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    type SerializeArray = ElideCompound<'a, S, SerializeArrayValue>;
+    type SerializeMap = ElideCompound<'a, S, SerializeMapValue>;
+    type SerializeSeqProduct = ElideCompound<'a, S, SerializeProductValue>;
+    type SerializeNamedProduct = ForwardNamedToSeqProduct<ElideCompound<'a, S, SerializeProductValue>>;
+
+    method!(serialize_bool -> bool);
+    method!(serialize_u8 -> u8);
+    method!(serialize_u16 -> u16);
+    method!(serialize_u32 -> u32);
+    method!(serialize_u64 -> u64);
+    method!(serialize_u128 -> u128);
+    method!(serialize_i8 -> i8);
+    method!(serialize_i16 -> i16);
+    method!(serialize_i32 -> i32);
+    method!(serialize_i64 -> i64);
+    method!(serialize_i128 -> i128);
+    method!(serialize_f32 -> f32);
+    method!(serialize_f64 -> f64);
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.elide(AlgebraicValue::String(v.into()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.elide(AlgebraicValue::Bytes(v.to_owned()))
+    }
+
+    fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error> {
+        let compound = infallible(ValueSerializer.serialize_array(len));
+        Ok(ElideCompound { ser: self, compound })
+    }
+
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        let compound = infallible(ValueSerializer.serialize_array_unknown());
+        Ok(ElideCompound { ser: self, compound })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        let compound = infallible(ValueSerializer.serialize_map(len));
+        Ok(ElideCompound { ser: self, compound })
+    }
+
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        let compound = infallible(ValueSerializer.serialize_map_unknown());
+        Ok(ElideCompound { ser: self, compound })
+    }
+
+    fn serialize_seq_product(self, len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
+        let compound = infallible(ValueSerializer.serialize_seq_product(len));
+        Ok(ElideCompound { ser: self, compound })
+    }
+
+    fn serialize_named_product(self, len: usize) -> Result<Self::SerializeNamedProduct, Self::Error> {
+        ForwardNamedToSeqProduct::forward(self, len)
+    }
+
+    fn serialize_variant<T: Serialize + ?Sized>(
+        self,
+        tag: u8,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.elide(infallible(ValueSerializer.serialize_variant(tag, name, value)))
+    }
+}
+
+/// Builds a compound value with the [`ValueSerializer`] continuation `C`,
+/// then serializes it elided against the baseline of `ser`.
+pub struct ElideCompound<'a, S, C> {
+    /// The serializer of the complete value.
+    ser: DefaultElidingSerializer<'a, S>,
+    /// The continuation building the value.
+    compound: C,
+}
+
+impl<S: Serializer, C: SerializeArray<Ok = AlgebraicValue, Error = Infallible>> SerializeArray
+    for ElideCompound<'_, S, C>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
+        infallible(self.compound.serialize_element(elem));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.elide(infallible(self.compound.end()))
+    }
+}
+
+impl<S: Serializer, C: SerializeMap<Ok = AlgebraicValue, Error = Infallible>> SerializeMap for ElideCompound<'_, S, C> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        infallible(self.compound.serialize_entry(key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.elide(infallible(self.compound.end()))
+    }
+}
+
+impl<S: Serializer, C: SerializeSeqProduct<Ok = AlgebraicValue, Error = Infallible>> SerializeSeqProduct
+    for ElideCompound<'_, S, C>
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
+        infallible(self.compound.serialize_element(elem));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.ser.elide(infallible(self.compound.end()))
+    }
+}
+
+/// A deserializer of values serialized by a [`DefaultElidingSerializer`] with the same `baseline`,
+/// reading the elided value of type `ty` from `inner`.
+///
+/// The value is first read as an [`AlgebraicValue`],
+/// with each default marker replaced by its baseline,
+/// and then deserialized into whatever type is asked for.
+pub struct DefaultElidingDeserializer<'a, D> {
+    /// The type of the value.
+    ty: WithTypespace<'a, AlgebraicType>,
+    /// The value default markers stand in for.
+    baseline: &'a AlgebraicValue,
+    /// The deserializer the elided value is read from.
+    inner: D,
+}
+
+impl<'a, 'de, D: Deserializer<'de>> DefaultElidingDeserializer<'a, D> {
+    /// Returns a deserializer reading a value of type `ty`, elided against `baseline`, from `inner`.
+    pub fn new(ty: WithTypespace<'a, AlgebraicType>, baseline: &'a AlgebraicValue, inner: D) -> Self {
+        Self { ty, baseline, inner }
+    }
+
+    /// Reads the value, with all of its default markers replaced by their baselines.
+    pub fn deserialize_value(self) -> Result<AlgebraicValue, D::Error> {
+        ElidedSeed {
+            ty: self.ty,
+            baseline: self.baseline,
+        }
+        .deserialize(self.inner)
+    }
+
+    /// Reads the value and returns a [`ValueDeserializer`] of it.
+    fn read(self) -> Result<ValueDeserializer, D::Error> {
+        self.deserialize_value().map(ValueDeserializer::new)
+    }
+}
+
+/// Converts the error of a [`ValueDeserializer`] into the error `E` of the inner deserializer.
+fn value_err<T, E: de::Error>(res: Result<T, ValueDeserializeError>) -> Result<T, E> {
+    res.map_err(|err| match err {
+        ValueDeserializeError::MismatchedType => E::custom("the elided value does not match the type deserialized"),
+        ValueDeserializeError::Custom(msg) => E::custom(msg),
+    })
+}
+
+/// Reads an elided value of type `ty` with `baseline`.
+#[derive(Clone, Copy)]
+struct ElidedSeed<'a> {
+    ty: WithTypespace<'a, AlgebraicType>,
+    baseline: &'a AlgebraicValue,
+}
+
+impl<'de> DeserializeSeed<'de> for ElidedSeed<'_> {
+    type Output = AlgebraicValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error> {
+        deserializer.deserialize_sum(self)
+    }
+}
+
+impl<'de> de::SumVisitor<'de> for ElidedSeed<'_> {
+    type Output = AlgebraicValue;
+
+    fn sum_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn variant_count(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn visit_sum<A: de::SumAccess<'de>>(self, data: A) -> Result<Self::Output, A::Error> {
+        let (tag, data) = data.variant(PresenceVisitor)?;
+        if tag == DEFAULT_TAG {
+            data.deserialize::<()>()?;
+            return Ok(self.baseline.clone());
+        }
+        match self.baseline {
+            AlgebraicValue::Product(base) => {
+                let ty = resolve_product(self.ty).map_err(de::Error::custom)?.ok_or_else(|| {
+                    de::Error::custom(format_args!(
+                        "cannot elide against a product baseline of the type {}",
+                        crate::algebraic_type::fmt::fmt_algebraic_type(self.ty.ty())
+                    ))
+                })?;
+                let fields = ElidedFieldsSeed {
+                    ty,
+                    baselines: &base.elements,
+                };
+                data.deserialize_seed(fields).map(AlgebraicValue::product)
+            }
+            _ => data.deserialize_seed(self.ty),
+        }
+    }
+}
+
+/// Returns the product type `ty` is, looking through refs and newtypes, if it is one.
+fn resolve_product(
+    ty: WithTypespace<'_, AlgebraicType>,
+) -> Result<Option<WithTypespace<'_, ProductType>>, UnresolvedRef> {
+    Ok(match ty.typespace().resolve_value_head(ty.ty())? {
+        AlgebraicType::Product(prod) => Some(ty.with(prod)),
+        _ => None,
+    })
+}
+
+/// Accepts the tags [`DEFAULT_TAG`] and [`PRESENT_TAG`].
+struct PresenceVisitor;
+
+impl de::VariantVisitor for PresenceVisitor {
+    type Output = u8;
+
+    fn variant_names(&self, names: &mut dyn de::ValidNames) {
+        names.extend(["default", "present"])
+    }
+
+    fn visit_tag<E: de::Error>(self, tag: u8) -> Result<Self::Output, E> {
+        match tag {
+            DEFAULT_TAG | PRESENT_TAG => Ok(tag),
+            _ => Err(E::custom(format_args!("unknown presence tag {tag}"))),
+        }
+    }
+
+    fn visit_name<E: de::Error>(self, name: &str) -> Result<Self::Output, E> {
+        match name {
+            "default" => Ok(DEFAULT_TAG),
+            "present" => Ok(PRESENT_TAG),
+            _ => Err(E::custom(format_args!("unknown presence `{name}`"))),
+        }
+    }
+}
+
+/// Reads the fields of a product of type `ty`, each elided against its baseline.
+struct ElidedFieldsSeed<'a> {
+    ty: WithTypespace<'a, ProductType>,
+    baselines: &'a [AlgebraicValue],
+}
+
+impl<'de> DeserializeSeed<'de> for ElidedFieldsSeed<'_> {
+    type Output = Vec<AlgebraicValue>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error> {
+        deserializer.deserialize_product(self)
+    }
+}
+
+impl<'de> de::ProductVisitor<'de> for ElidedFieldsSeed<'_> {
+    type Output = Vec<AlgebraicValue>;
+
+    fn product_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn product_len(&self) -> usize {
+        self.baselines.len()
+    }
+
+    fn visit_seq_product<A: SeqProductAccess<'de>>(self, mut prod: A) -> Result<Self::Output, A::Error> {
+        let elements = &self.ty.ty().elements;
+        if elements.len() != self.baselines.len() {
+            return Err(de::Error::custom(format_args!(
+                "baseline has {} fields but its type has {}",
+                self.baselines.len(),
+                elements.len()
+            )));
+        }
+        let mut fields = Vec::with_capacity(elements.len());
+        for (i, (elem, baseline)) in elements.iter().zip(self.baselines).enumerate() {
+            let seed = ElidedSeed {
+                ty: self.ty.with(&elem.algebraic_type),
+                baseline,
+            };
+            let field = prod
+                .next_element_seed(seed)
+                .map_err(|e| e.in_field(i, elem.name()))?
+                .ok_or_else(|| de::Error::invalid_product_length(i, &self))?;
+            fields.push(field);
+        }
+        Ok(fields)
+    }
+
+    fn visit_named_product<A: de::NamedProductAccess<'de>>(self, _prod: A) -> Result<Self::Output, A::Error> {
+        Err(de::Error::custom("elided fields are never named"))
+    }
+}
+
+/// Defines a deserializer method that reads the value then forwards to the [`ValueDeserializer`] of it.
+macro_rules! forward {
+    ($($name:ident -> $t:ty),* $(,)?) => {
+        $(fn $name(self) -> Result<$t, Self::Error> {
+            value_err(self.read()?.$name())
+        })*
+    };
+}
+
+impl<'de, D: Deserializer<'de>> Deserializer<'de> for DefaultElidingDeserializer<'_, D> {
+    type Error = D::Error;
+
+    fn deserialize_product<V: de::ProductVisitor<'de>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        value_err(self.read()?.deserialize_product(visitor))
+    }
+
+    fn deserialize_sum<V: de::SumVisitor<'de>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        value_err(self.read()?.deserialize_sum(visitor))
+    }
+
+    forward!(
+        deserialize_bool -> bool,
+        deserialize_u8 -> u8,
+        deserialize_u16 -> u16,
+        deserialize_u32 -> u32,
+        deserialize_u64 -> u64,
+        deserialize_u128 -> u128,
+        deserialize_i8 -> i8,
+        deserialize_i16 -> i16,
+        deserialize_i32 -> i32,
+        deserialize_i64 -> i64,
+        deserialize_i128 -> i128,
+        deserialize_f32 -> f32,
+        deserialize_f64 -> f64,
+    );
+
+    fn deserialize_str<V: de::SliceVisitor<'de, str>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        value_err(self.read()?.deserialize_str(visitor))
+    }
+
+    fn deserialize_bytes<V: de::SliceVisitor<'de, [u8]>>(self, visitor: V) -> Result<V::Output, Self::Error> {
+        value_err(self.read()?.deserialize_bytes(visitor))
+    }
+
+    fn deserialize_array_seed<V: de::ArrayVisitor<'de, T::Output>, T: DeserializeSeed<'de> + Clone>(
+        self,
+        visitor: V,
+        seed: T,
+    ) -> Result<V::Output, Self::Error> {
+        value_err(self.read()?.deserialize_array_seed(visitor, seed))
+    }
+
+    fn deserialize_map_seed<
+        Vi: de::MapVisitor<'de, K::Output, V::Output>,
+        K: DeserializeSeed<'de> + Clone,
+        V: DeserializeSeed<'de> + Clone,
+    >(
+        self,
+        visitor: Vi,
+        kseed: K,
+        vseed: V,
+    ) -> Result<Vi::Output, Self::Error> {
+        value_err(self.read()?.deserialize_map_seed(visitor, kseed, vseed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Deserialize;
+    use crate::{bsatn, product, AlgebraicTypeRef, ProductTypeElement, ProductValue, Typespace};
+
+    /// Encodes `value` in BSATN, elided against `baseline`.
+    fn encode(value: &impl Serialize, baseline: &AlgebraicValue) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        value
+            .serialize(DefaultElidingSerializer::new(
+                baseline,
+                bsatn::Serializer::new(&mut bytes),
+            ))
+            .unwrap();
+        bytes
+    }
+
+    /// Decodes `bytes` as a value of type `ty`, elided against `baseline`.
+    fn decode(bytes: &[u8], ty: &AlgebraicType, baseline: &AlgebraicValue) -> AlgebraicValue {
+        let ts = Typespace::new(vec![]);
+        let mut reader = bytes;
+        let de = DefaultElidingDeserializer::new(
+            WithTypespace::new(&ts, ty),
+            baseline,
+            bsatn::Deserializer::new(&mut reader),
+        );
+        let value = de.deserialize_value().unwrap();
+        assert!(reader.is_empty());
+        value
+    }
+
+    #[test]
+    fn mostly_default_row() {
+        let ty = AlgebraicType::product(
+            (0..100)
+                .map(|i| ProductTypeElement::new_named(AlgebraicType::U64, format!("f{i}")))
+                .collect(),
+        );
+        let baseline: AlgebraicValue = ProductValue::new(&vec![AlgebraicValue::U64(0); 100]).into();
+        let mut row = vec![AlgebraicValue::U64(0); 100];
+        for i in [3, 17, 42, 64, 99] {
+            row[i] = AlgebraicValue::U64(i as u64 + 1);
+        }
+        let row: AlgebraicValue = ProductValue::new(&row).into();
+
+        let plain = bsatn::to_vec(&row).unwrap();
+        let elided = encode(&row, &baseline);
+        assert_eq!(plain.len(), 800);
+        // The present tag of the row, one byte per default field, and the present tag and value of the others.
+        assert_eq!(elided.len(), 1 + 95 + 5 * (1 + 8));
+        assert_eq!(decode(&elided, &ty, &baseline), row);
+
+        // A row equal to the baseline is a single marker.
+        assert_eq!(encode(&baseline, &baseline), [DEFAULT_TAG]);
+        assert_eq!(decode(&[DEFAULT_TAG], &ty, &baseline), baseline);
+    }
+
+    #[derive(crate::ser::Serialize, crate::de::Deserialize, Debug, PartialEq)]
+    #[sats(crate = crate)]
+    struct Profile {
+        nick: Option<String>,
+        score: u32,
+    }
+
+    #[derive(crate::ser::Serialize, crate::de::Deserialize, Debug, PartialEq)]
+    #[sats(crate = crate)]
+    struct User {
+        id: u8,
+        profile: Profile,
+    }
+
+    #[test]
+    fn nested_rust_values() {
+        let ty = AlgebraicType::product(vec![
+            ProductTypeElement::new_named(AlgebraicType::U8, "id"),
+            ProductTypeElement::new_named(
+                AlgebraicType::product(vec![
+                    ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "nick"),
+                    ProductTypeElement::new_named(AlgebraicType::U32, "score"),
+                ]),
+                "profile",
+            ),
+        ]);
+        let baseline = product![0u8, product![AlgebraicValue::OptionNone(), 0u32]].into();
+        let user = User {
+            id: 7,
+            profile: Profile { nick: None, score: 5 },
+        };
+
+        // The `nick` within the `profile` is elided.
+        let bytes = encode(&user, &baseline);
+        let (d, p) = (DEFAULT_TAG, PRESENT_TAG);
+        assert_eq!(bytes, [p, p, 7, p, d, p, 5, 0, 0, 0]);
+
+        let ts = Typespace::new(vec![]);
+        let mut reader = &bytes[..];
+        let de = DefaultElidingDeserializer::new(
+            WithTypespace::new(&ts, &ty),
+            &baseline,
+            bsatn::Deserializer::new(&mut reader),
+        );
+        assert_eq!(User::deserialize(de).unwrap(), user);
+    }
+
+    #[test]
+    fn cyclic_ref_is_an_error() {
+        // `&0 = &1` and `&1 = &0` never resolve to a type, so neither can be elided nor serialized against.
+        let ts = Typespace::new(vec![
+            AlgebraicType::Ref(AlgebraicTypeRef(1)),
+            AlgebraicType::Ref(AlgebraicTypeRef(0)),
+        ]);
+        let ty = AlgebraicType::Ref(AlgebraicTypeRef(0));
+        let baseline = product![0u8].into();
+        let mut reader = &[PRESENT_TAG][..];
+        let de = DefaultElidingDeserializer::new(
+            WithTypespace::new(&ts, &ty),
+            &baseline,
+            bsatn::Deserializer::new(&mut reader),
+        );
+        let err = de.deserialize_value().unwrap_err();
+        assert_eq!(err.to_string(), "The type ref &0 does not resolve in its typespace");
+
+        let err = bsatn::to_vec(&ts.with_value(&ty, &baseline)).unwrap_err();
+        assert_eq!(err.to_string(), "The type ref &0 does not resolve in its typespace");
+    }
+
+    #[test]
+    fn shape_mismatch() {
+        let baseline = product![0u8, 0u8].into();
+        let mut bytes = Vec::new();
+        let err = product![1u8]
+            .serialize(DefaultElidingSerializer::new(
+                &baseline,
+                bsatn::Serializer::new(&mut bytes),
+            ))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot elide Product(ProductValue { elements: [U8(1)] }) against a product of 2 fields"
+        );
+    }
+}
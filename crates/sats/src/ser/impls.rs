@@ -1,16 +1,43 @@
-use std::collections::BTreeMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::ops::{Bound, Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
 
 use crate::{
-    AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, BuiltinValue, MapType, MapValue, ProductValue, SumValue,
+    AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, MapType, MapValue, ProductValue, SumValue, Typespace,
     ValueWithType,
 };
 
-use super::{Serialize, SerializeArray, SerializeMap, SerializeNamedProduct, SerializeSeqProduct, Serializer};
+use super::{
+    Error, InvalidPath, LossyPath, Serialize, SerializeArray, SerializeMap, SerializeNamedProduct, SerializeSeqProduct,
+    Serializer,
+};
+
+/// Returns the type `ty` after following any `Ref`s and newtypes around it in `typespace`,
+/// or an error of `S` if they don't resolve.
+fn resolve_head<'a, S: Serializer>(
+    typespace: &'a Typespace,
+    ty: &'a AlgebraicType,
+) -> Result<&'a AlgebraicType, S::Error> {
+    typespace.resolve_value_head(ty).map_err(Error::custom)
+}
+
+/// Serializes a range from `start` to `end` as the product `{ start, end }`.
+fn serialize_range<S: Serializer, T: Serialize>(ser: S, start: &T, end: &T) -> Result<S::Ok, S::Error> {
+    let mut prod = ser.serialize_named_product(2)?;
+    prod.serialize_element(Some("start"), start)?;
+    prod.serialize_element(Some("end"), end)?;
+    prod.end()
+}
 
 /// Implements [`Serialize`] for a type in a simplified manner.
 ///
 /// An example:
-/// ```ignore
+/// ```
+/// # use spacetimedb_sats::{bsatn, impl_serialize, ser::{Serialize, SerializeSeqProduct}};
 /// struct Foo<'a, T: Copy>(&'a T, u8);
 /// impl_serialize!(
 /// //     Type parameters  Optional where  Impl type
@@ -26,7 +53,10 @@ use super::{Serialize, SerializeArray, SerializeMap, SerializeNamedProduct, Seri
 ///         prod.end()
 ///     }
 /// );
+/// assert_eq!(bsatn::to_vec(&Foo(&7u16, 1)).unwrap(), [7, 0, 1]);
 /// ```
+///
+/// See [`impl_deserialize!`](crate::impl_deserialize) for the counterpart.
 #[macro_export]
 macro_rules! impl_serialize {
     ([$($generics:tt)*] $(where [$($wc:tt)*])? $typ:ty, ($self:ident, $ser:ident) => $body:expr) => {
@@ -44,16 +74,66 @@ macro_rules! impl_prim {
     };
 }
 
-impl_serialize!([] (), (self, ser) => ser.serialize_seq_product(0)?.end());
+impl_serialize!([] (), (self, ser) => ser.serialize_unit());
+
+/// Implements [`Serialize`] for fixed-width numeric types,
+/// serializing arrays of them through [`Serializer::__serialize_pod_array`] with the `bytemuck` feature.
+macro_rules! impl_num {
+    ($(($prim:ty, $method:ident))*) => {
+        $(impl Serialize for $prim {
+            const __BSATN_STATIC_SIZE: Option<usize> = Some(std::mem::size_of::<$prim>());
+
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.$method(*self)
+            }
+
+            #[cfg(feature = "bytemuck")]
+            fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.__serialize_pod_array(this)
+            }
+
+            #[cfg(not(feature = "bytemuck"))]
+            fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_fixed_width_array(this, serializer)
+            }
+        })*
+    };
+}
+
+/// Serializes `this` as an array,
+/// hinting that each element is `size_of::<T>()` bytes long, as is the case for fixed-width numbers.
+fn serialize_fixed_width_array<T: Serialize, S: Serializer>(this: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut vec = serializer.serialize_array(this.len())?;
+    vec.hint_fixed_element_size(std::mem::size_of::<T>());
+    for elem in this {
+        vec.serialize_element(elem)?;
+    }
+    vec.end()
+}
+
+impl_prim! { (str, serialize_str) }
+
+impl Serialize for bool {
+    const __BSATN_STATIC_SIZE: Option<usize> = Some(1);
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bool(*self)
+    }
 
-impl_prim! {
-    (bool, serialize_bool) /*(u8, serialize_u8)*/ (u16, serialize_u16)
-    (u32, serialize_u32) (u64, serialize_u64) (u128, serialize_u128) (i8, serialize_i8)
-    (i16, serialize_i16) (i32, serialize_i32) (i64, serialize_i64) (i128, serialize_i128)
-    (f32, serialize_f32) (f64, serialize_f64) (str, serialize_str)
+    fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fixed_width_array(this, serializer)
+    }
+}
+
+impl_num! {
+    /*(u8, serialize_u8)*/ (u16, serialize_u16) (u32, serialize_u32) (u64, serialize_u64)
+    (u128, serialize_u128) (i8, serialize_i8) (i16, serialize_i16) (i32, serialize_i32)
+    (i64, serialize_i64) (i128, serialize_i128) (f32, serialize_f32) (f64, serialize_f64)
 }
 
 impl Serialize for u8 {
+    const __BSATN_STATIC_SIZE: Option<usize> = Some(1);
+
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_u8(*self)
     }
@@ -64,24 +144,177 @@ impl Serialize for u8 {
     {
         serializer.serialize_bytes(this)
     }
+
+    fn __serialize_array_halves<S: Serializer>(front: &[Self], back: &[Self], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        Self: Sized,
+    {
+        if back.is_empty() {
+            serializer.serialize_bytes(front)
+        } else {
+            serializer.serialize_bytes(&[front, back].concat())
+        }
+    }
 }
 
-impl_serialize!([] crate::builtin_value::F32, (self, ser) => f32::from(*self).serialize(ser));
-impl_serialize!([] crate::builtin_value::F64, (self, ser) => f64::from(*self).serialize(ser));
+/// Implements [`Serialize`] for the totally ordered float wrappers as the floats they wrap.
+macro_rules! impl_float {
+    ($(($float:ty, $prim:ty))*) => {
+        $(impl Serialize for $float {
+            const __BSATN_STATIC_SIZE: Option<usize> = <$prim>::__BSATN_STATIC_SIZE;
+
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                <$prim>::from(*self).serialize(serializer)
+            }
+
+            #[cfg(not(feature = "bytemuck"))]
+            fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_fixed_width_array(this, serializer)
+            }
+
+            #[cfg(feature = "bytemuck")]
+            fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+                // SAFETY: The wrapper is `#[repr(transparent)]` over the float,
+                // so a slice of the former has the same layout as one of the latter.
+                let floats = unsafe { std::slice::from_raw_parts(this.as_ptr().cast::<$prim>(), this.len()) };
+                serializer.__serialize_pod_array(floats)
+            }
+        })*
+    };
+}
+
+impl_float! { (crate::builtin_value::F32, f32) (crate::builtin_value::F64, f64) }
 impl_serialize!([T: Serialize] Vec<T>, (self, ser)  => (**self).serialize(ser));
 impl_serialize!([T: Serialize] [T], (self, ser) => T::__serialize_array(self, ser));
-impl_serialize!([T: Serialize, const N: usize] [T; N], (self, ser) => T::__serialize_array(self, ser));
+impl<T: Serialize, const N: usize> Serialize for [T; N] {
+    // The length prefix, then the elements.
+    const __BSATN_STATIC_SIZE: Option<usize> = match T::__BSATN_STATIC_SIZE {
+        Some(size) => Some(4 + N * size),
+        None => None,
+    };
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        T::__serialize_array(self, serializer)
+    }
+}
+impl_serialize!([T: Serialize] VecDeque<T>, (self, ser) => {
+    let (front, back) = self.as_slices();
+    T::__serialize_array_halves(front, back, ser)
+});
+// `LinkedList` should generally be avoided in favor of `Vec`, but is supported for completeness.
+// Its elements aren't contiguous, so they're serialized one by one.
+impl_serialize!([T: Serialize] LinkedList<T>, (self, ser) => {
+    let mut arr = ser.serialize_array(self.len())?;
+    for elem in self {
+        arr.serialize_element(elem)?;
+    }
+    arr.end()
+});
+// A heap iterates its elements in an arbitrary order that depends on the order they were pushed in,
+// so they're serialized in ascending order instead, for heaps of the same elements to encode the same.
+impl_serialize!([T: Serialize + Ord] BinaryHeap<T>, (self, ser) => {
+    let mut sorted = self.iter().collect::<Vec<_>>();
+    sorted.sort();
+    let mut arr = ser.serialize_array(sorted.len())?;
+    for elem in sorted {
+        arr.serialize_element(elem)?;
+    }
+    arr.end()
+});
+// A set iterates its elements in ascending order, so they're serialized in that order.
+impl_serialize!([T: Serialize] BTreeSet<T>, (self, ser) => {
+    let mut arr = ser.serialize_array(self.len())?;
+    for elem in self {
+        arr.serialize_element(elem)?;
+    }
+    arr.end()
+});
+#[cfg(feature = "smallvec")]
+impl_serialize!([A: smallvec::Array] where [A::Item: Serialize] smallvec::SmallVec<A>, (self, ser) => (**self).serialize(ser));
+impl_serialize!([T: Serialize, const N: usize] arrayvec::ArrayVec<T, N>, (self, ser) => T::__serialize_array(self.as_slice(), ser));
+#[cfg(feature = "bytes")]
+impl_serialize!([] bytes::Bytes, (self, ser) => u8::__serialize_array(self, ser));
+#[cfg(feature = "bytes")]
+impl_serialize!([] bytes::BytesMut, (self, ser) => u8::__serialize_array(self, ser));
+// Timestamps are milliseconds since the Unix epoch, truncating any finer precision,
+// and dates are days since 1970-01-01.
+#[cfg(feature = "chrono")]
+impl_serialize!([] chrono::DateTime<chrono::Utc>, (self, ser) => ser.serialize_i64(self.timestamp_millis()));
+// The `Default` of a `NaiveDate` is 1970-01-01, and all dates are within an `i32` of days from it.
+#[cfg(feature = "chrono")]
+impl_serialize!([] chrono::NaiveDate, (self, ser) => ser.serialize_i32((*self - chrono::NaiveDate::default()).num_days() as i32));
+// Vectors and matrices are arrays of their components, a `Vector3` as `[x, y, z]`,
+// and a `Matrix4` as its 16 components column by column, as nalgebra stores it,
+// i.e., `[m11, m21, m31, m41, m12, ..]`.
+#[cfg(feature = "nalgebra")]
+impl_serialize!([] nalgebra::Vector3<f32>, (self, ser) => f32::__serialize_array(self.as_slice(), ser));
+#[cfg(feature = "nalgebra")]
+impl_serialize!([] nalgebra::Matrix4<f32>, (self, ser) => f32::__serialize_array(self.as_slice(), ser));
 impl_serialize!([T: Serialize + ?Sized] Box<T>, (self, ser) => (**self).serialize(ser));
+impl_serialize!([T: Serialize + ?Sized] Rc<T>, (self, ser) => (**self).serialize(ser));
+impl_serialize!([T: Serialize + ?Sized] Arc<T>, (self, ser) => (**self).serialize(ser));
 impl_serialize!([T: Serialize + ?Sized] &T, (self, ser) => (**self).serialize(ser));
+impl_serialize!([T: Serialize + Copy] Cell<T>, (self, ser) => self.get().serialize(ser));
+// A `RefCell` that is mutably borrowed, e.g., by the caller, can't be read, so that's an error rather than a panic.
+impl_serialize!([T: Serialize + ?Sized] RefCell<T>, (self, ser) => self.try_borrow().map_err(Error::custom)?.serialize(ser));
+// Likewise, a lock held, e.g., by another thread, is an error rather than a wait that could deadlock,
+// as is a poisoned lock, whose contents may be inconsistent.
+impl_serialize!([T: Serialize + ?Sized] Mutex<T>, (self, ser) => self.try_lock().map_err(Error::custom)?.serialize(ser));
+impl_serialize!([T: Serialize + ?Sized] RwLock<T>, (self, ser) => self.try_read().map_err(Error::custom)?.serialize(ser));
+// Wrapping only says how arithmetic on the number behaves, so it's serialized as the number.
+impl<T: Serialize> Serialize for std::num::Wrapping<T> {
+    const __BSATN_STATIC_SIZE: Option<usize> = T::__BSATN_STATIC_SIZE;
+
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+
+    // Forwarded, so that, e.g., `Vec<Wrapping<u8>>` is still serialized as bytes.
+    fn __serialize_array<S: Serializer>(this: &[Self], serializer: S) -> Result<S::Ok, S::Error> {
+        T::__serialize_array(unwrap_wrapping_slice(this), serializer)
+    }
+
+    fn __serialize_array_halves<S: Serializer>(
+        front: &[Self],
+        back: &[Self],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        T::__serialize_array_halves(unwrap_wrapping_slice(front), unwrap_wrapping_slice(back), serializer)
+    }
+}
+
+/// Returns the numbers in `slice`, without their `Wrapping`.
+fn unwrap_wrapping_slice<T>(slice: &[std::num::Wrapping<T>]) -> &[T] {
+    // SAFETY: `Wrapping<T>` is `#[repr(transparent)]` over `T`,
+    // so a slice of the former has the same layout as one of the latter.
+    unsafe { std::slice::from_raw_parts(slice.as_ptr().cast::<T>(), slice.len()) }
+}
 impl_serialize!([] String, (self, ser) => ser.serialize_str(self));
+impl_serialize!([] Path, (self, ser) => ser.serialize_str(InvalidPath::check(self).map_err(Error::invalid_path)?));
+impl_serialize!([] PathBuf, (self, ser) => self.as_path().serialize(ser));
+impl_serialize!([] OsStr, (self, ser) => Path::new(self).serialize(ser));
+impl_serialize!([] OsString, (self, ser) => self.as_os_str().serialize(ser));
+impl_serialize!([] LossyPath, (self, ser) => {
+    let lossy = self.0.to_string_lossy();
+    ser.serialize_str(InvalidPath::check(Path::new(&*lossy)).map_err(Error::invalid_path)?)
+});
 impl_serialize!([T: Serialize] Option<T>, (self, ser) => match self {
     Some(v) => ser.serialize_variant(0, Some("some"), v),
-    None => ser.serialize_variant(1, Some("none"), &()),
+    None => ser.serialize_unit_variant(1, Some("none")),
 });
 impl_serialize!([T: Serialize, E: Serialize] Result<T, E>, (self, ser) => match self {
     Ok(v) => ser.serialize_variant(0, Some("ok"), v),
     Err(e) => ser.serialize_variant(1, Some("err"), e),
 });
+// Ranges are serialized as the product `{ start: T, end: T }`, as is, even when empty or reversed.
+// An exhausted `RangeInclusive` is serialized by its bounds, as its exhaustion is not observable through them.
+impl_serialize!([T: Serialize] Range<T>, (self, ser) => serialize_range(ser, &self.start, &self.end));
+impl_serialize!([T: Serialize] RangeInclusive<T>, (self, ser) => serialize_range(ser, self.start(), self.end()));
+impl_serialize!([T: Serialize] Bound<T>, (self, ser) => match self {
+    Bound::Included(v) => ser.serialize_variant(0, Some("included"), v),
+    Bound::Excluded(v) => ser.serialize_variant(1, Some("excluded"), v),
+    Bound::Unbounded => ser.serialize_unit_variant(2, Some("unbounded")),
+});
 impl_serialize!([K: Serialize, V: Serialize] BTreeMap<K, V>, (self, ser) => {
     let mut map = ser.serialize_map(self.len())?;
     for (k, v) in self {
@@ -89,12 +322,26 @@ impl_serialize!([K: Serialize, V: Serialize] BTreeMap<K, V>, (self, ser) => {
     }
     map.end()
 });
+// Entries and elements are serialized in their insertion order.
+#[cfg(feature = "indexmap")]
+impl_serialize!([K: Serialize, V: Serialize, H] indexmap::IndexMap<K, V, H>, (self, ser) => {
+    let mut map = ser.serialize_map(self.len())?;
+    for (k, v) in self {
+        map.serialize_entry(k, v)?;
+    }
+    map.end()
+});
+#[cfg(feature = "indexmap")]
+impl_serialize!([T: Serialize, H] indexmap::IndexSet<T, H>, (self, ser) => {
+    let mut arr = ser.serialize_array(self.len())?;
+    for elem in self {
+        arr.serialize_element(elem)?;
+    }
+    arr.end()
+});
 impl_serialize!([] AlgebraicValue, (self, ser) => match self {
     Self::Sum(sum) => sum.serialize(ser),
     Self::Product(prod) => prod.serialize(ser),
-    Self::Builtin(b) => b.serialize(ser),
-});
-impl_serialize!([] BuiltinValue, (self, ser) => match self {
     Self::Bool(v) => ser.serialize_bool(*v),
     Self::I8(v) => ser.serialize_i8(*v),
     Self::U8(v) => ser.serialize_u8(*v),
@@ -109,9 +356,8 @@ impl_serialize!([] BuiltinValue, (self, ser) => match self {
     Self::F32(v) => ser.serialize_f32((*v).into()),
     Self::F64(v) => ser.serialize_f64((*v).into()),
     Self::String(v) => ser.serialize_str(v),
-    // Self::Bytes(v) => ser.serialize_bytes(v),
-    Self::Array { val } => val.serialize(ser),
-    Self::Map { val } => val.serialize(ser),
+    Self::Array(val) => val.serialize(ser),
+    Self::Map(val) => val.serialize(ser),
 });
 impl_serialize!([] ProductValue, (self, ser) => {
     let mut tup = ser.serialize_seq_product(self.elements.len())?;
@@ -137,43 +383,37 @@ impl_serialize!([] ArrayValue, (self, ser) => match self {
     Self::U128(v) => v.serialize(ser),
     Self::F32(v) => v.serialize(ser),
     Self::F64(v) => v.serialize(ser),
-    Self::String(v) => v.serialize(ser),
+    Self::String(v) => ser.__serialize_string_array_value(v),
+    Self::StringPacked(v) => ser.__serialize_packed_strings(v),
     Self::Array(v) => v.serialize(ser),
     Self::Map(v) => v.serialize(ser),
 });
 impl_serialize!([] ValueWithType<'_, AlgebraicValue>, (self, ser) => {
-    let mut ty = self.ty();
-    loop { // We're doing this because of `Ref`s.
-        break match (self.value(), ty) {
-            (AlgebraicValue::Sum(val), AlgebraicType::Sum(ty)) => self.with(ty, val).serialize(ser),
-            (AlgebraicValue::Product(val), AlgebraicType::Product(ty)) => self.with(ty, val).serialize(ser),
-            (AlgebraicValue::Builtin(val), AlgebraicType::Builtin(ty)) => self.with(ty, val).serialize(ser),
-            (_, &AlgebraicType::Ref(r)) => {
-                ty = &self.typespace()[r];
-                continue;
-            }
-            _ => panic!("mismatched value and schema"),
-        };
-    }
-});
-impl_serialize!([] ValueWithType<'_, BuiltinValue>, (self, ser) => match (self.value(), self.ty()) {
-    (BuiltinValue::Bool(v), BuiltinType::Bool) => ser.serialize_bool(*v),
-    (BuiltinValue::I8(v), BuiltinType::I8) => ser.serialize_i8(*v),
-    (BuiltinValue::U8(v), BuiltinType::U8) => ser.serialize_u8(*v),
-    (BuiltinValue::I16(v), BuiltinType::I16) => ser.serialize_i16(*v),
-    (BuiltinValue::U16(v), BuiltinType::U16) => ser.serialize_u16(*v),
-    (BuiltinValue::I32(v), BuiltinType::I32) => ser.serialize_i32(*v),
-    (BuiltinValue::U32(v), BuiltinType::U32) => ser.serialize_u32(*v),
-    (BuiltinValue::I64(v), BuiltinType::I64) => ser.serialize_i64(*v),
-    (BuiltinValue::U64(v), BuiltinType::U64) => ser.serialize_u64(*v),
-    (BuiltinValue::I128(v), BuiltinType::I128) => ser.serialize_i128(*v),
-    (BuiltinValue::U128(v), BuiltinType::U128) => ser.serialize_u128(*v),
-    (BuiltinValue::F32(v), BuiltinType::F32) => ser.serialize_f32((*v).into()),
-    (BuiltinValue::F64(v), BuiltinType::F64) => ser.serialize_f64((*v).into()),
-    (BuiltinValue::String(s), BuiltinType::String) => ser.serialize_str(s),
-    (BuiltinValue::Array { val }, BuiltinType::Array(ty)) => self.with(ty, val).serialize(ser),
-    (BuiltinValue::Map { val }, BuiltinType::Map(ty)) => self.with(ty, val).serialize(ser),
-    (val, ty) => panic!("mismatched value and schema: {val:?} {ty:?}"),
+    match (self.value(), resolve_head::<S>(self.typespace(), self.ty())?) {
+        (AlgebraicValue::Sum(val), AlgebraicType::Sum(ty)) => self.with(ty, val).serialize(ser),
+        (AlgebraicValue::Product(val), AlgebraicType::Product(ty)) => self.with(ty, val).serialize(ser),
+        (AlgebraicValue::Bool(v), AlgebraicType::Builtin(BuiltinType::Bool)) => ser.serialize_bool(*v),
+        (AlgebraicValue::I8(v), AlgebraicType::Builtin(BuiltinType::I8)) => ser.serialize_i8(*v),
+        (AlgebraicValue::U8(v), AlgebraicType::Builtin(BuiltinType::U8)) => ser.serialize_u8(*v),
+        (AlgebraicValue::I16(v), AlgebraicType::Builtin(BuiltinType::I16)) => ser.serialize_i16(*v),
+        (AlgebraicValue::U16(v), AlgebraicType::Builtin(BuiltinType::U16)) => ser.serialize_u16(*v),
+        (AlgebraicValue::I32(v), AlgebraicType::Builtin(BuiltinType::I32)) => ser.serialize_i32(*v),
+        (AlgebraicValue::U32(v), AlgebraicType::Builtin(BuiltinType::U32)) => ser.serialize_u32(*v),
+        (AlgebraicValue::I64(v), AlgebraicType::Builtin(BuiltinType::I64)) => ser.serialize_i64(*v),
+        (AlgebraicValue::U64(v), AlgebraicType::Builtin(BuiltinType::U64)) => ser.serialize_u64(*v),
+        (AlgebraicValue::I128(v), AlgebraicType::Builtin(BuiltinType::I128)) => ser.serialize_i128(*v),
+        (AlgebraicValue::U128(v), AlgebraicType::Builtin(BuiltinType::U128)) => ser.serialize_u128(*v),
+        (AlgebraicValue::F32(v), AlgebraicType::Builtin(BuiltinType::F32)) => ser.serialize_f32((*v).into()),
+        (AlgebraicValue::F64(v), AlgebraicType::Builtin(BuiltinType::F64)) => ser.serialize_f64((*v).into()),
+        (AlgebraicValue::String(s), AlgebraicType::Builtin(BuiltinType::String)) => ser.serialize_str(s),
+        (AlgebraicValue::Array(val), AlgebraicType::Builtin(BuiltinType::Array(ty))) => {
+            self.with(ty, val).serialize(ser)
+        }
+        (AlgebraicValue::Map(val), AlgebraicType::Builtin(BuiltinType::Map(ty))) => {
+            self.with(ty, val).serialize(ser)
+        }
+        (val, ty) => panic!("mismatched value and schema: {val:?} {ty:?}"),
+    }
 });
 impl_serialize!(
     [T: crate::Value] where [for<'a> ValueWithType<'a, T>: Serialize]
@@ -192,15 +432,15 @@ impl_serialize!([] ValueWithType<'_, SumValue>, (self, ser) => {
     ser.serialize_variant(tag, var_ty.name(), &self.with(&var_ty.algebraic_type, &**value))
 });
 impl_serialize!([] ValueWithType<'_, ProductValue>, (self, ser) => {
-    let val = &self.value().elements;
-    assert_eq!(val.len(), self.ty().elements.len());
-    let mut prod = ser.serialize_named_product(val.len())?;
-    for (val, el_ty) in val.iter().zip(&self.ty().elements) {
-        prod.serialize_element(el_ty.name(), &self.with(&el_ty.algebraic_type, val))?
+    let fields = self.fields().map_err(Error::custom)?;
+    let mut prod = ser.serialize_named_product(self.value().elements.len())?;
+    for (name, val) in fields {
+        prod.serialize_element(name, &val)?
     }
     prod.end()
 });
-impl_serialize!([] ValueWithType<'_, ArrayValue>, (self, ser) => match (self.value(), &*self.ty().elem_ty) {
+// The element type is resolved once up front rather than once per element.
+impl_serialize!([] ValueWithType<'_, ArrayValue>, (self, ser) => match (self.value(), resolve_head::<S>(self.typespace(), &self.ty().elem_ty)?) {
     (ArrayValue::Sum(v), AlgebraicType::Sum(ty)) => self.with(ty, v).serialize(ser),
     (ArrayValue::Product(v), AlgebraicType::Product(ty)) => self.with(ty, v).serialize(ser),
     (ArrayValue::Bool(v), &AlgebraicType::Builtin(BuiltinType::Bool)) => v.serialize(ser),
@@ -216,7 +456,8 @@ impl_serialize!([] ValueWithType<'_, ArrayValue>, (self, ser) => match (self.val
     (ArrayValue::U128(v), &AlgebraicType::Builtin(BuiltinType::U128)) => v.serialize(ser),
     (ArrayValue::F32(v), &AlgebraicType::Builtin(BuiltinType::F32)) => v.serialize(ser),
     (ArrayValue::F64(v), &AlgebraicType::Builtin(BuiltinType::F64)) => v.serialize(ser),
-    (ArrayValue::String(v), &AlgebraicType::Builtin(BuiltinType::String)) => v.serialize(ser),
+    (ArrayValue::String(v), &AlgebraicType::Builtin(BuiltinType::String)) => ser.__serialize_string_array_value(v),
+    (ArrayValue::StringPacked(v), &AlgebraicType::Builtin(BuiltinType::String)) => ser.__serialize_packed_strings(v),
     (ArrayValue::Array(v), AlgebraicType::Builtin(BuiltinType::Array(ty))) => {
         self.with(ty, v).serialize(ser)
     }
@@ -227,9 +468,11 @@ impl_serialize!([] ValueWithType<'_, ArrayValue>, (self, ser) => match (self.val
 impl_serialize!([] ValueWithType<'_, MapValue>, (self, ser) => {
     let val = self.value();
     let MapType { key_ty, ty } = self.ty();
+    // Resolve the key and value types once rather than once per entry.
+    let (key_ty, ty) = (resolve_head::<S>(self.typespace(), key_ty)?, resolve_head::<S>(self.typespace(), ty)?);
     let mut map = ser.serialize_map(val.len())?;
     for (key, val) in val {
-        map.serialize_entry(&self.with(&**key_ty, key), &self.with(&**ty, val))?;
+        map.serialize_entry(&self.with(key_ty, key), &self.with(ty, val))?;
     }
     map.end()
 });
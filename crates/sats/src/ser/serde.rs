@@ -2,19 +2,37 @@ use std::fmt;
 
 use ::serde::ser as serde;
 
+use crate::algebraic_type::fmt::fmt_algebraic_type;
+use crate::codec::BytesFormat;
 use crate::ser::{self, Serializer};
+use crate::{
+    AlgebraicType, AlgebraicValue, ArrayValue, BuiltinType, MapType, MapValue, ProductValue, SumValue, Typespace,
+    ValueWithType,
+};
 
 /// Converts any [`serde::Serializer`] to a SATS [`Serializer`]
 /// so that Serde's data formats can be reused.
 pub struct SerdeSerializer<S> {
     /// A serialization data format in Serde.
     ser: S,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
 }
 
 impl<S: serde::Serializer> SerdeSerializer<S> {
-    /// Returns a wrapped serializer.
+    /// Returns a wrapped serializer, writing byte arrays as hex strings.
     pub fn new(ser: S) -> Self {
-        Self { ser }
+        Self {
+            ser,
+            bytes: BytesFormat::Hex,
+        }
+    }
+
+    /// Sets how byte arrays are written as text,
+    /// both in the value serialized and in all values nested within it.
+    pub fn bytes_format(mut self, bytes: BytesFormat) -> Self {
+        self.bytes = bytes;
+        self
     }
 }
 
@@ -78,28 +96,42 @@ impl<S: serde::Serializer> Serializer for SerdeSerializer<S> {
         self.ser.serialize_str(v).map_err(SerdeError)
     }
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let s = hex::encode(v);
+        let s = match self.bytes {
+            BytesFormat::Hex => hex::encode(v),
+            #[cfg(feature = "base64")]
+            BytesFormat::Base64 => crate::codec::base64::array_value_to_base64(v),
+        };
         self.ser.serialize_str(&s).map_err(SerdeError)
     }
 
     fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error> {
         let seq = self.ser.serialize_seq(Some(len)).map_err(SerdeError)?;
-        Ok(SerializeArray { seq })
+        Ok(SerializeArray { seq, bytes: self.bytes })
+    }
+
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        let seq = self.ser.serialize_seq(None).map_err(SerdeError)?;
+        Ok(SerializeArray { seq, bytes: self.bytes })
     }
 
     fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
         let map = self.ser.serialize_map(Some(len)).map_err(SerdeError)?;
-        Ok(SerializeMap { map })
+        Ok(SerializeMap { map, bytes: self.bytes })
+    }
+
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        let map = self.ser.serialize_map(None).map_err(SerdeError)?;
+        Ok(SerializeMap { map, bytes: self.bytes })
     }
 
     fn serialize_seq_product(self, len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
         let tup = self.ser.serialize_tuple(len).map_err(SerdeError)?;
-        Ok(SerializeSeqProduct { tup })
+        Ok(SerializeSeqProduct { tup, bytes: self.bytes })
     }
 
     fn serialize_named_product(self, len: usize) -> Result<Self::SerializeNamedProduct, Self::Error> {
         let map = self.ser.serialize_map(Some(len)).map_err(SerdeError)?;
-        Ok(SerializeNamedProduct { map })
+        Ok(SerializeNamedProduct { map, bytes: self.bytes })
     }
 
     fn serialize_variant<T: ser::Serialize + ?Sized>(
@@ -111,7 +143,7 @@ impl<S: serde::Serializer> Serializer for SerdeSerializer<S> {
         // can't use serialize_variant cause we're too dynamic :(
         use ::serde::ser::SerializeMap;
         let mut map = self.ser.serialize_map(Some(1)).map_err(SerdeError)?;
-        let value = SerializeWrapper::from_ref(value);
+        let value = &WithBytesFormat::new(value, self.bytes);
         if let Some(name) = name {
             map.serialize_entry(name, value).map_err(SerdeError)?;
         } else {
@@ -126,6 +158,8 @@ impl<S: serde::Serializer> Serializer for SerdeSerializer<S> {
 pub struct SerializeArray<S> {
     /// An implementation of `serde::SerializeSeq`.
     seq: S,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
 }
 
 impl<S: serde::SerializeSeq> ser::SerializeArray for SerializeArray<S> {
@@ -134,7 +168,7 @@ impl<S: serde::SerializeSeq> ser::SerializeArray for SerializeArray<S> {
 
     fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
         self.seq
-            .serialize_element(SerializeWrapper::from_ref(elem))
+            .serialize_element(&WithBytesFormat::new(elem, self.bytes))
             .map_err(SerdeError)
     }
 
@@ -147,6 +181,8 @@ impl<S: serde::SerializeSeq> ser::SerializeArray for SerializeArray<S> {
 pub struct SerializeMap<S> {
     /// An implementation of `serde::SerializeMap`.
     map: S,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
 }
 
 impl<S: serde::SerializeMap> ser::SerializeMap for SerializeMap<S> {
@@ -159,7 +195,10 @@ impl<S: serde::SerializeMap> ser::SerializeMap for SerializeMap<S> {
         value: &V,
     ) -> Result<(), Self::Error> {
         self.map
-            .serialize_entry(SerializeWrapper::from_ref(key), SerializeWrapper::from_ref(value))
+            .serialize_entry(
+                &WithBytesFormat::new(key, self.bytes),
+                &WithBytesFormat::new(value, self.bytes),
+            )
             .map_err(SerdeError)
     }
 
@@ -172,6 +211,8 @@ impl<S: serde::SerializeMap> ser::SerializeMap for SerializeMap<S> {
 pub struct SerializeSeqProduct<S> {
     /// An implementation of `serde::SerializeTuple`.
     tup: S,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
 }
 
 impl<S: serde::SerializeTuple> ser::SerializeSeqProduct for SerializeSeqProduct<S> {
@@ -180,7 +221,7 @@ impl<S: serde::SerializeTuple> ser::SerializeSeqProduct for SerializeSeqProduct<
 
     fn serialize_element<T: ser::Serialize + ?Sized>(&mut self, elem: &T) -> Result<(), Self::Error> {
         self.tup
-            .serialize_element(SerializeWrapper::from_ref(elem))
+            .serialize_element(&WithBytesFormat::new(elem, self.bytes))
             .map_err(SerdeError)
     }
 
@@ -193,6 +234,8 @@ impl<S: serde::SerializeTuple> ser::SerializeSeqProduct for SerializeSeqProduct<
 pub struct SerializeNamedProduct<S> {
     /// An implementation of `serde::SerializeMap`.
     map: S,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
 }
 
 impl<S: serde::SerializeMap> ser::SerializeNamedProduct for SerializeNamedProduct<S> {
@@ -206,7 +249,7 @@ impl<S: serde::SerializeMap> ser::SerializeNamedProduct for SerializeNamedProduc
     ) -> Result<(), Self::Error> {
         let name = name.ok_or_else(|| ser::Error::custom("tuple element has no name"))?;
         self.map
-            .serialize_entry(name, SerializeWrapper::from_ref(elem))
+            .serialize_entry(name, &WithBytesFormat::new(elem, self.bytes))
             .map_err(SerdeError)
     }
 
@@ -226,6 +269,33 @@ pub fn serialize_to<T: super::Serialize + ?Sized, S: serde::Serializer>(
         .map_err(|SerdeError(e)| e)
 }
 
+/// Makes a type serializable in SATS serializable in serde, as [`SerializeWrapper`] does,
+/// but with its byte arrays written as text per a [`BytesFormat`] of choice, e.g.:
+/// ```ignore
+/// serde_json::to_string(&WithBytesFormat::new(&value, BytesFormat::Base64))
+/// ```
+pub struct WithBytesFormat<'a, T: ?Sized> {
+    /// The value to serialize.
+    value: &'a T,
+    /// How byte arrays are written as text.
+    bytes: BytesFormat,
+}
+
+impl<'a, T: ?Sized> WithBytesFormat<'a, T> {
+    /// Wraps `value` to be serialized with its byte arrays written per `bytes`.
+    pub fn new(value: &'a T, bytes: BytesFormat) -> Self {
+        Self { value, bytes }
+    }
+}
+
+impl<T: ser::Serialize + ?Sized> serde::Serialize for WithBytesFormat<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.value)
+            .serialize(SerdeSerializer::new(serializer).bytes_format(self.bytes))
+            .map_err(|SerdeError(e)| e)
+    }
+}
+
 /// Turns a type serializable in SATS into one serializable in serde.
 ///
 /// That is, `T: sats::Serialize => SerializeWrapper<T>: serde::Serialize`.
@@ -271,3 +341,265 @@ delegate_serde! {
     crate::AlgebraicType, crate::ProductType, crate::ProductTypeElement, crate::SumType, crate::SumTypeVariant,
     crate::AlgebraicValue, crate::ProductValue, crate::SumValue
 }
+
+// The impls below serialize typed values to serde directly, rather than through `SerdeSerializer`,
+// so that byte arrays reach the data format as bytes
+// and a value not matching its type is an error of the data format rather than a panic.
+
+/// Returns `ty` with any `Ref`s and newtypes at its head resolved in `typespace`.
+fn resolve_head<'a, E: serde::Error>(typespace: &'a Typespace, ty: &'a AlgebraicType) -> Result<&'a AlgebraicType, E> {
+    typespace.resolve_value_head(ty).map_err(E::custom)
+}
+
+/// Returns an error for when `val` does not match the type `ty` it is serialized at.
+fn mismatch<E: serde::Error>(val: &impl fmt::Debug, ty: &AlgebraicType) -> E {
+    E::custom(format_args!(
+        "mismatched value and schema: {val:?} is not a {}",
+        fmt_algebraic_type(ty)
+    ))
+}
+
+/// Serializes the value at its type, with `Ref`s resolved through the typespace.
+///
+/// Products whose elements are all named become maps from the names to the elements,
+/// and other products become tuples.
+/// Sums become single entry maps from the name of the variant, or its tag if unnamed, to its payload,
+/// as in serde's newtype variant style.
+/// Arrays of `U8` are serialized as bytes, and other arrays as sequences.
+impl serde::Serialize for ValueWithType<'_, AlgebraicValue> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        match (self.value(), resolve_head(self.typespace(), self.ty())?) {
+            (AlgebraicValue::Sum(val), AlgebraicType::Sum(ty)) => self.with(ty, val).serialize(ser),
+            (AlgebraicValue::Product(val), AlgebraicType::Product(ty)) => self.with(ty, val).serialize(ser),
+            (AlgebraicValue::Bool(v), AlgebraicType::Builtin(BuiltinType::Bool)) => ser.serialize_bool(*v),
+            (AlgebraicValue::I8(v), AlgebraicType::Builtin(BuiltinType::I8)) => ser.serialize_i8(*v),
+            (AlgebraicValue::U8(v), AlgebraicType::Builtin(BuiltinType::U8)) => ser.serialize_u8(*v),
+            (AlgebraicValue::I16(v), AlgebraicType::Builtin(BuiltinType::I16)) => ser.serialize_i16(*v),
+            (AlgebraicValue::U16(v), AlgebraicType::Builtin(BuiltinType::U16)) => ser.serialize_u16(*v),
+            (AlgebraicValue::I32(v), AlgebraicType::Builtin(BuiltinType::I32)) => ser.serialize_i32(*v),
+            (AlgebraicValue::U32(v), AlgebraicType::Builtin(BuiltinType::U32)) => ser.serialize_u32(*v),
+            (AlgebraicValue::I64(v), AlgebraicType::Builtin(BuiltinType::I64)) => ser.serialize_i64(*v),
+            (AlgebraicValue::U64(v), AlgebraicType::Builtin(BuiltinType::U64)) => ser.serialize_u64(*v),
+            (AlgebraicValue::I128(v), AlgebraicType::Builtin(BuiltinType::I128)) => ser.serialize_i128(*v),
+            (AlgebraicValue::U128(v), AlgebraicType::Builtin(BuiltinType::U128)) => ser.serialize_u128(*v),
+            (AlgebraicValue::F32(v), AlgebraicType::Builtin(BuiltinType::F32)) => ser.serialize_f32((*v).into()),
+            (AlgebraicValue::F64(v), AlgebraicType::Builtin(BuiltinType::F64)) => ser.serialize_f64((*v).into()),
+            (AlgebraicValue::String(s), AlgebraicType::Builtin(BuiltinType::String)) => ser.serialize_str(s),
+            (AlgebraicValue::Array(val), AlgebraicType::Builtin(BuiltinType::Array(ty))) => {
+                self.with(ty, val).serialize(ser)
+            }
+            (AlgebraicValue::Map(val), AlgebraicType::Builtin(BuiltinType::Map(ty))) => {
+                self.with(ty, val).serialize(ser)
+            }
+            (val, ty) => Err(mismatch(val, ty)),
+        }
+    }
+}
+
+impl serde::Serialize for ValueWithType<'_, SumValue> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        use serde::SerializeMap;
+
+        // `serialize_newtype_variant` wants `&'static str` names, so build its output as a map instead.
+        let &SumValue { tag, ref value } = self.value();
+        let variants = &self.ty().variants;
+        let var_ty = variants.get(tag as usize).ok_or_else(|| {
+            serde::Error::custom(format_args!(
+                "tag {tag} is out of range for a sum of {} variants",
+                variants.len()
+            ))
+        })?;
+        let value = self.with(&var_ty.algebraic_type, &**value);
+        let mut map = ser.serialize_map(Some(1))?;
+        match var_ty.name() {
+            Some(name) => map.serialize_entry(name, &value)?,
+            None => map.serialize_entry(&tag, &value)?,
+        }
+        map.end()
+    }
+}
+
+impl serde::Serialize for ValueWithType<'_, ProductValue> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        use serde::{SerializeMap, SerializeTuple};
+
+        let fields = self.fields().map_err(serde::Error::custom)?;
+        let len = self.value().elements.len();
+        if self.ty().elements.iter().all(|el| el.name.is_some()) {
+            let mut map = ser.serialize_map(Some(len))?;
+            for (name, val) in fields {
+                map.serialize_entry(name.unwrap_or_default(), &val)?;
+            }
+            map.end()
+        } else {
+            let mut tup = ser.serialize_tuple(len)?;
+            for (_, val) in fields {
+                tup.serialize_element(&val)?;
+            }
+            tup.end()
+        }
+    }
+}
+
+impl serde::Serialize for ValueWithType<'_, ArrayValue> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        // The element type is resolved once up front rather than once per element.
+        match (self.value(), resolve_head(self.typespace(), &self.ty().elem_ty)?) {
+            (ArrayValue::Sum(v), AlgebraicType::Sum(ty)) => ser.collect_seq(v.iter().map(|v| self.with(ty, v))),
+            (ArrayValue::Product(v), AlgebraicType::Product(ty)) => ser.collect_seq(v.iter().map(|v| self.with(ty, v))),
+            (ArrayValue::Bool(v), AlgebraicType::Builtin(BuiltinType::Bool)) => ser.collect_seq(v),
+            (ArrayValue::I8(v), AlgebraicType::Builtin(BuiltinType::I8)) => ser.collect_seq(v),
+            (ArrayValue::U8(v), AlgebraicType::Builtin(BuiltinType::U8)) => ser.serialize_bytes(v),
+            (ArrayValue::I16(v), AlgebraicType::Builtin(BuiltinType::I16)) => ser.collect_seq(v),
+            (ArrayValue::U16(v), AlgebraicType::Builtin(BuiltinType::U16)) => ser.collect_seq(v),
+            (ArrayValue::I32(v), AlgebraicType::Builtin(BuiltinType::I32)) => ser.collect_seq(v),
+            (ArrayValue::U32(v), AlgebraicType::Builtin(BuiltinType::U32)) => ser.collect_seq(v),
+            (ArrayValue::I64(v), AlgebraicType::Builtin(BuiltinType::I64)) => ser.collect_seq(v),
+            (ArrayValue::U64(v), AlgebraicType::Builtin(BuiltinType::U64)) => ser.collect_seq(v),
+            (ArrayValue::I128(v), AlgebraicType::Builtin(BuiltinType::I128)) => ser.collect_seq(v),
+            (ArrayValue::U128(v), AlgebraicType::Builtin(BuiltinType::U128)) => ser.collect_seq(v),
+            (ArrayValue::F32(v), AlgebraicType::Builtin(BuiltinType::F32)) => {
+                ser.collect_seq(v.iter().map(|&v| f32::from(v)))
+            }
+            (ArrayValue::F64(v), AlgebraicType::Builtin(BuiltinType::F64)) => {
+                ser.collect_seq(v.iter().map(|&v| f64::from(v)))
+            }
+            (ArrayValue::String(v), AlgebraicType::Builtin(BuiltinType::String)) => ser.collect_seq(v),
+            (ArrayValue::StringPacked(v), AlgebraicType::Builtin(BuiltinType::String)) => ser.collect_seq(v.iter()),
+            (ArrayValue::Array(v), AlgebraicType::Builtin(BuiltinType::Array(ty))) => {
+                ser.collect_seq(v.iter().map(|v| self.with(ty, v)))
+            }
+            (ArrayValue::Map(v), AlgebraicType::Builtin(BuiltinType::Map(ty))) => {
+                ser.collect_seq(v.iter().map(|v| self.with(ty, v)))
+            }
+            (val, _) if val.is_empty() => ser.collect_seq(std::iter::empty::<()>()),
+            (val, ty) => Err(mismatch(val, ty)),
+        }
+    }
+}
+
+impl serde::Serialize for ValueWithType<'_, MapValue> {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let MapType { key_ty, ty } = self.ty();
+        // Resolve the key and value types once rather than once per entry.
+        let (key_ty, ty) = (
+            resolve_head(self.typespace(), key_ty)?,
+            resolve_head(self.typespace(), ty)?,
+        );
+        ser.collect_map(
+            self.value()
+                .iter()
+                .map(|(k, v)| (self.with(key_ty, k), self.with(ty, v))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{product, AlgebraicTypeRef, ProductTypeElement, SumTypeVariant, WithTypespace};
+
+    use super::*;
+
+    /// Returns a typespace with `&0 = User` and `&1 = Role`, the latter only referred to by `User`.
+    fn typespace() -> Typespace {
+        Typespace::new(vec![
+            AlgebraicType::product(vec![
+                ProductTypeElement::new_named(AlgebraicType::U32, "id"),
+                ProductTypeElement::new_named(AlgebraicType::String, "name"),
+                ProductTypeElement::new_named(AlgebraicType::bytes(), "avatar"),
+                ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(1)), "role"),
+                ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::F64), "score"),
+                ProductTypeElement::new_named(
+                    AlgebraicType::product(vec![
+                        ProductTypeElement::new(AlgebraicType::I8, None),
+                        ProductTypeElement::new(AlgebraicType::I8, None),
+                    ]),
+                    "pos",
+                ),
+            ]),
+            AlgebraicType::sum(vec![
+                SumTypeVariant::new_named(AlgebraicType::product(vec![]), "admin"),
+                SumTypeVariant::new_named(
+                    AlgebraicType::product(vec![ProductTypeElement::new_named(AlgebraicType::U64, "since")]),
+                    "guest",
+                ),
+            ]),
+        ])
+    }
+
+    fn to_json(ts: &Typespace, ty: &AlgebraicType, val: &AlgebraicValue) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&ValueWithType::new(WithTypespace::new(ts, ty), val))
+    }
+
+    #[test]
+    fn nested_value_to_json() {
+        let ts = typespace();
+        let users = AlgebraicType::array(AlgebraicType::Ref(AlgebraicTypeRef(0)));
+        let admin = product![
+            1u32,
+            "ana",
+            AlgebraicValue::Bytes(vec![1, 2, 255]),
+            AlgebraicValue::sum(0, product![].into()),
+            AlgebraicValue::OptionSome(AlgebraicValue::F64(0.5.into())),
+            product![-1i8, 2i8]
+        ];
+        let guest = product![
+            2u32,
+            "bo",
+            AlgebraicValue::Bytes(vec![]),
+            AlgebraicValue::sum(1, product![7u64].into()),
+            AlgebraicValue::OptionNone(),
+            product![0i8, 0i8]
+        ];
+        let users_val = AlgebraicValue::ArrayOf(vec![admin, guest]);
+
+        assert_eq!(
+            to_json(&ts, &users, &users_val).unwrap(),
+            concat!(
+                r#"[{"id":1,"name":"ana","avatar":[1,2,255],"role":{"admin":{}},"score":{"some":0.5},"pos":[-1,2]},"#,
+                r#"{"id":2,"name":"bo","avatar":[],"role":{"guest":{"since":7}},"score":{"none":{}},"pos":[0,0]}]"#,
+            )
+        );
+
+        let map_ty = AlgebraicType::map(AlgebraicType::String, AlgebraicType::array(AlgebraicType::U16));
+        let map_val = AlgebraicValue::map([("a".into(), AlgebraicValue::ArrayOf(vec![3u16, 4]))].into());
+        assert_eq!(to_json(&ts, &map_ty, &map_val).unwrap(), r#"{"a":[3,4]}"#);
+    }
+
+    #[test]
+    fn mismatches_are_errors() {
+        let ts = typespace();
+        let user = AlgebraicType::Ref(AlgebraicTypeRef(0));
+        let err = |ty: &AlgebraicType, val: AlgebraicValue| to_json(&ts, ty, &val).unwrap_err().to_string();
+
+        assert_eq!(
+            err(&AlgebraicType::String, AlgebraicValue::U8(1)),
+            "mismatched value and schema: U8(1) is not a String"
+        );
+        assert_eq!(
+            err(&user, product![1u32].into()),
+            "Product value has 1 elements but its type has 6"
+        );
+        assert_eq!(
+            err(
+                &AlgebraicType::Ref(AlgebraicTypeRef(1)),
+                AlgebraicValue::sum(2, product![].into())
+            ),
+            "tag 2 is out of range for a sum of 2 variants"
+        );
+        assert_eq!(
+            err(&AlgebraicType::Ref(AlgebraicTypeRef(9)), AlgebraicValue::U8(1)),
+            "The type ref &9 does not resolve in its typespace"
+        );
+        // Mismatches deep within a value are caught too.
+        let bad_role = AlgebraicValue::ArrayOf(vec![product![
+            1u32,
+            "ana",
+            AlgebraicValue::Bytes(vec![]),
+            AlgebraicValue::U8(0),
+            AlgebraicValue::OptionNone(),
+            product![0i8, 0i8]
+        ]]);
+        assert!(err(&AlgebraicType::array(user), bad_role).starts_with("mismatched value and schema: U8(0)"));
+    }
+}
@@ -0,0 +1,508 @@
+//! A serializer that records the calls made to it while forwarding them to another serializer.
+
+use std::cell::RefCell;
+
+use super::{Serialize, SerializeArray, SerializeMap, SerializeNamedProduct, SerializeSeqProduct, Serializer};
+use crate::impl_serialize;
+
+/// A call made to a [`Serializer`] or one of its compound serializers,
+/// as recorded by a [`TraceSerializer`].
+///
+/// The events carry all the arguments of the calls,
+/// so a trace is enough to make the same calls again and re-serialize the value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    SerializeBool(bool),
+    SerializeU8(u8),
+    SerializeU16(u16),
+    SerializeU32(u32),
+    SerializeU64(u64),
+    SerializeU128(u128),
+    SerializeI8(i8),
+    SerializeI16(i16),
+    SerializeI32(i32),
+    SerializeI64(i64),
+    SerializeI128(i128),
+    SerializeF32(f32),
+    SerializeF64(f64),
+    SerializeStr(String),
+    SerializeBytes(Vec<u8>),
+    /// An array of the given length, whose elements follow until [`TraceEvent::EndArray`].
+    BeginArray(usize),
+    /// An array of unknown length, whose elements follow until [`TraceEvent::EndArray`].
+    BeginArrayOfUnknownLen,
+    EndArray,
+    /// A map of the given length, whose keys and values follow,
+    /// alternating, until [`TraceEvent::EndMap`].
+    BeginMap(usize),
+    /// A map of unknown length, whose keys and values follow,
+    /// alternating, until [`TraceEvent::EndMap`].
+    BeginMapOfUnknownLen,
+    EndMap,
+    /// An unnamed product of the given length, whose elements follow until [`TraceEvent::EndProduct`].
+    BeginProduct(usize),
+    EndProduct,
+    /// A named product of the given length, whose elements follow,
+    /// each preceded by a [`TraceEvent::Field`], until [`TraceEvent::EndNamedProduct`].
+    BeginNamedProduct(usize),
+    /// The name of the next element of a named product.
+    Field(Option<String>),
+    EndNamedProduct,
+    /// A sum value of the variant with `tag` and `name`, whose value follows.
+    Variant {
+        tag: u8,
+        name: Option<String>,
+    },
+    /// The unit value.
+    Unit,
+    /// A sum value of the variant with `tag` and `name` with a unit payload.
+    UnitVariant {
+        tag: u8,
+        name: Option<String>,
+    },
+    /// A marker standing in for a value equal to its default.
+    DefaultMarker,
+    /// The strings of an [`ArrayValue::String`](crate::ArrayValue::String).
+    StringArray(Vec<String>),
+    /// The strings of an [`ArrayValue::StringPacked`](crate::ArrayValue::StringPacked).
+    PackedStrings(crate::builtin_value::PackedStrings),
+    /// An array of fixed-width numbers, each `elem_size` bytes long, as their `bytes` in memory.
+    #[cfg(feature = "bytemuck")]
+    PodArray {
+        elem_size: usize,
+        bytes: Vec<u8>,
+    },
+}
+
+/// A serializer that records each call made to it, and to the compound serializers it returns,
+/// in `trace` before forwarding the call to the `inner` serializer.
+///
+/// The calls of values nested in a compound value are recorded too,
+/// in the order they were made.
+pub struct TraceSerializer<'t, S> {
+    /// The serializer calls are forwarded to.
+    inner: S,
+    /// Where calls are recorded.
+    trace: &'t RefCell<Vec<TraceEvent>>,
+}
+
+impl<'t, S: Serializer> TraceSerializer<'t, S> {
+    /// Returns a serializer forwarding to `inner` that records calls in `trace`.
+    pub fn new(inner: S, trace: &'t RefCell<Vec<TraceEvent>>) -> Self {
+        Self { inner, trace }
+    }
+
+    /// Records `event` and returns the inner serializer to forward the call to.
+    fn record(self, event: TraceEvent) -> S {
+        self.trace.borrow_mut().push(event);
+        self.inner
+    }
+}
+
+/// Serializes `value` with `serializer`, returning, alongside the result, the calls made.
+pub fn trace<S: Serializer, T: Serialize + ?Sized>(
+    value: &T,
+    serializer: S,
+) -> Result<(S::Ok, Vec<TraceEvent>), S::Error> {
+    let trace = RefCell::new(Vec::new());
+    let ok = value.serialize(TraceSerializer::new(serializer, &trace))?;
+    Ok((ok, trace.into_inner()))
+}
+
+/// Serializes a value nested in a compound value with a [`TraceSerializer`]
+/// wrapping whatever serializer the compound serializer uses for it.
+struct Traced<'a, 't, T: ?Sized> {
+    /// The nested value.
+    value: &'a T,
+    /// Where calls are recorded.
+    trace: &'t RefCell<Vec<TraceEvent>>,
+}
+
+impl_serialize!([T: Serialize + ?Sized] Traced<'_, '_, T>, (self, ser) => {
+    self.value.serialize(TraceSerializer::new(ser, self.trace))
+});
+
+/// Records the calls to a compound serializer `inner`, e.g., of an array,
+/// and those made for its elements.
+pub struct TraceCompound<'t, S> {
+    /// The compound serializer calls are forwarded to.
+    inner: S,
+    /// Where calls are recorded.
+    trace: &'t RefCell<Vec<TraceEvent>>,
+}
+
+impl<'t, S> TraceCompound<'t, S> {
+    /// Wraps `value` so that it is traced when serialized.
+    fn traced<'a, T: ?Sized>(&self, value: &'a T) -> Traced<'a, 't, T> {
+        let trace = self.trace;
+        Traced { value, trace }
+    }
+
+    /// Records `event` and returns the inner compound serializer to forward the call to.
+    fn record(self, event: TraceEvent) -> S {
+        self.trace.borrow_mut().push(event);
+        self.inner
+    }
+}
+
+impl<'t, S: Serializer> Serializer for TraceSerializer<'t, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeArray = TraceCompound<'t, S::SerializeArray>;
+    type SerializeMap = TraceCompound<'t, S::SerializeMap>;
+    type SerializeSeqProduct = TraceCompound<'t, S::SerializeSeqProduct>;
+    type SerializeNamedProduct = TraceCompound<'t, S::SerializeNamedProduct>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeBool(v)).serialize_bool(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeU8(v)).serialize_u8(v)
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeU16(v)).serialize_u16(v)
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeU32(v)).serialize_u32(v)
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeU64(v)).serialize_u64(v)
+    }
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeU128(v)).serialize_u128(v)
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeI8(v)).serialize_i8(v)
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeI16(v)).serialize_i16(v)
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeI32(v)).serialize_i32(v)
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeI64(v)).serialize_i64(v)
+    }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeI128(v)).serialize_i128(v)
+    }
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeF32(v)).serialize_f32(v)
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeF64(v)).serialize_f64(v)
+    }
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeStr(v.to_owned())).serialize_str(v)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::SerializeBytes(v.to_owned())).serialize_bytes(v)
+    }
+
+    fn serialize_array(self, len: usize) -> Result<Self::SerializeArray, Self::Error> {
+        let trace = self.trace;
+        let inner = self.record(TraceEvent::BeginArray(len)).serialize_array(len)?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_array_unknown(self) -> Result<Self::SerializeArray, Self::Error> {
+        let trace = self.trace;
+        let inner = self
+            .record(TraceEvent::BeginArrayOfUnknownLen)
+            .serialize_array_unknown()?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_map(self, len: usize) -> Result<Self::SerializeMap, Self::Error> {
+        let trace = self.trace;
+        let inner = self.record(TraceEvent::BeginMap(len)).serialize_map(len)?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_map_unknown(self) -> Result<Self::SerializeMap, Self::Error> {
+        let trace = self.trace;
+        let inner = self.record(TraceEvent::BeginMapOfUnknownLen).serialize_map_unknown()?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_seq_product(self, len: usize) -> Result<Self::SerializeSeqProduct, Self::Error> {
+        let trace = self.trace;
+        let inner = self.record(TraceEvent::BeginProduct(len)).serialize_seq_product(len)?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_named_product(self, len: usize) -> Result<Self::SerializeNamedProduct, Self::Error> {
+        let trace = self.trace;
+        let inner = self
+            .record(TraceEvent::BeginNamedProduct(len))
+            .serialize_named_product(len)?;
+        Ok(TraceCompound { inner, trace })
+    }
+
+    fn serialize_variant<T: Serialize + ?Sized>(
+        self,
+        tag: u8,
+        name: Option<&str>,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        let trace = self.trace;
+        let name_ev = name.map(str::to_owned);
+        self.record(TraceEvent::Variant { tag, name: name_ev })
+            .serialize_variant(tag, name, &Traced { value, trace })
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::Unit).serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, tag: u8, name: Option<&str>) -> Result<Self::Ok, Self::Error> {
+        let name_ev = name.map(str::to_owned);
+        self.record(TraceEvent::UnitVariant { tag, name: name_ev })
+            .serialize_unit_variant(tag, name)
+    }
+
+    // Hints aren't calls that serialize anything, so they are forwarded without being recorded.
+    fn hint_total_size(&mut self, size: usize) {
+        self.inner.hint_total_size(size)
+    }
+
+    fn serialize_default_marker(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::DefaultMarker).serialize_default_marker()
+    }
+
+    fn __serialize_string_array_value(self, v: &[String]) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::StringArray(v.to_vec()))
+            .__serialize_string_array_value(v)
+    }
+
+    fn __serialize_packed_strings(self, v: &crate::builtin_value::PackedStrings) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::PackedStrings(v.clone()))
+            .__serialize_packed_strings(v)
+    }
+
+    #[cfg(feature = "bytemuck")]
+    fn __serialize_pod_array<T: Serialize + bytemuck::Pod>(self, v: &[T]) -> Result<Self::Ok, Self::Error> {
+        let elem_size = std::mem::size_of::<T>();
+        let bytes = bytemuck::cast_slice(v).to_vec();
+        self.record(TraceEvent::PodArray { elem_size, bytes })
+            .__serialize_pod_array(v)
+    }
+}
+
+impl<S: SerializeArray> SerializeArray for TraceCompound<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), Self::Error> {
+        self.inner.serialize_element(&self.traced(element))
+    }
+
+    fn hint_fixed_element_size(&mut self, size: usize) {
+        self.inner.hint_fixed_element_size(size)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::EndArray).end()
+    }
+}
+
+impl<S: SerializeMap> SerializeMap for TraceCompound<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(
+        &mut self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        self.inner.serialize_entry(&self.traced(key), &self.traced(value))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::EndMap).end()
+    }
+}
+
+impl<S: SerializeSeqProduct> SerializeSeqProduct for TraceCompound<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<(), Self::Error> {
+        self.inner.serialize_element(&self.traced(element))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::EndProduct).end()
+    }
+}
+
+impl<S: SerializeNamedProduct> SerializeNamedProduct for TraceCompound<'_, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, name: Option<&str>, elem: &T) -> Result<(), Self::Error> {
+        self.trace.borrow_mut().push(TraceEvent::Field(name.map(str::to_owned)));
+        self.inner.serialize_element(name, &self.traced(elem))
+    }
+
+    fn serialize_element_at<T: Serialize + ?Sized>(
+        &mut self,
+        index: usize,
+        name: Option<&str>,
+        elem: &T,
+    ) -> Result<(), Self::Error> {
+        self.trace.borrow_mut().push(TraceEvent::Field(name.map(str::to_owned)));
+        self.inner.serialize_element_at(index, name, &self.traced(elem))
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.record(TraceEvent::EndNamedProduct).end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{trace, TraceEvent::*, TraceSerializer};
+    use crate::ser::elision::DefaultElidingSerializer;
+    use crate::ser::Serialize;
+    use crate::{bsatn, product, AlgebraicValue};
+
+    /// Traces the BSATN serialization of `value`, checking that the bytes are unaffected by tracing.
+    fn trace_bsatn<T: Serialize + ?Sized>(value: &T) -> Vec<super::TraceEvent> {
+        let mut bytes = Vec::new();
+        let ((), events) = trace(value, bsatn::Serializer::new(&mut bytes)).unwrap();
+        assert_eq!(bytes, bsatn::to_vec(value).unwrap());
+        events
+    }
+
+    #[test]
+    fn two_field_product() {
+        #[derive(Serialize)]
+        #[sats(crate = crate)]
+        struct Point {
+            x: u32,
+            label: String,
+        }
+
+        let events = trace_bsatn(&Point {
+            x: 7,
+            label: "seven".into(),
+        });
+        assert_eq!(
+            events,
+            [
+                BeginNamedProduct(2),
+                Field(Some("x".into())),
+                SerializeU32(7),
+                Field(Some("label".into())),
+                SerializeStr("seven".into()),
+                EndNamedProduct,
+            ]
+        );
+    }
+
+    #[test]
+    fn option_u32() {
+        let some = Variant {
+            tag: 0,
+            name: Some("some".into()),
+        };
+        assert_eq!(trace_bsatn(&Some(5u32)), [some, SerializeU32(5)]);
+
+        let none = UnitVariant {
+            tag: 1,
+            name: Some("none".into()),
+        };
+        assert_eq!(trace_bsatn(&None::<u32>), [none]);
+    }
+
+    #[test]
+    fn nested_compounds() {
+        let row = product![
+            AlgebraicValue::ArrayOf(vec![1u16, 2]),
+            AlgebraicValue::Bytes(b"ab".to_vec())
+        ];
+        #[cfg(feature = "bytemuck")]
+        let u16s = vec![PodArray {
+            elem_size: 2,
+            bytes: [1u16, 2].iter().flat_map(|n| n.to_ne_bytes()).collect(),
+        }];
+        #[cfg(not(feature = "bytemuck"))]
+        let u16s = vec![BeginArray(2), SerializeU16(1), SerializeU16(2), EndArray];
+        let expected = [
+            vec![BeginArray(1), BeginProduct(2)],
+            u16s,
+            vec![SerializeBytes(b"ab".to_vec()), EndProduct, EndArray],
+        ];
+        assert_eq!(trace_bsatn(&vec![row]), expected.concat());
+    }
+
+    #[test]
+    fn special_calls_are_forwarded() {
+        #[derive(Serialize)]
+        #[sats(crate = crate)]
+        enum Status {
+            Online,
+            Away,
+        }
+
+        assert_eq!(trace_bsatn(&()), [Unit]);
+        let away = UnitVariant {
+            tag: 1,
+            name: Some("Away".into()),
+        };
+        let online = UnitVariant {
+            tag: 0,
+            name: Some("Online".into()),
+        };
+        assert_eq!(
+            trace_bsatn(&[Status::Online, Status::Away]),
+            [BeginArray(2), online, away, EndArray]
+        );
+
+        let strings = vec!["a".to_owned(), "b".to_owned(), "a".to_owned()];
+        let packed = crate::builtin_value::PackedStrings::from(&*strings);
+        let array = AlgebraicValue::ArrayOf(strings.clone());
+        assert_eq!(trace_bsatn(&array), [StringArray(strings.clone())]);
+        let packed_array = AlgebraicValue::Array(crate::ArrayValue::StringPacked(packed.clone().into()));
+        assert_eq!(trace_bsatn(&packed_array), [PackedStrings(packed)]);
+
+        // The special encodings of the inner serializer are kept, too.
+        #[cfg(feature = "compress")]
+        for value in [&array, &packed_array] {
+            let (mut traced, mut untraced) = (Vec::new(), Vec::new());
+            let inner = bsatn::Serializer::new(&mut traced).with_dictionary_strings(true);
+            trace(value, inner).unwrap();
+            value
+                .serialize(bsatn::Serializer::new(&mut untraced).with_dictionary_strings(true))
+                .unwrap();
+            assert_eq!(traced, untraced);
+        }
+
+        let baseline = AlgebraicValue::U32(5);
+        let elided = |value: &AlgebraicValue, traced: bool| {
+            let mut bytes = Vec::new();
+            let events = RefCell::new(Vec::new());
+            let inner = bsatn::Serializer::new(&mut bytes);
+            if traced {
+                value.serialize(DefaultElidingSerializer::new(
+                    &baseline,
+                    TraceSerializer::new(inner, &events),
+                ))
+            } else {
+                value.serialize(DefaultElidingSerializer::new(&baseline, inner))
+            }
+            .unwrap();
+            (bytes, events.into_inner())
+        };
+        let (traced, events) = elided(&AlgebraicValue::U32(5), true);
+        assert_eq!(events, [DefaultMarker]);
+        assert_eq!(traced, elided(&AlgebraicValue::U32(5), false).0);
+        assert_eq!(
+            elided(&AlgebraicValue::U32(6), true).0,
+            elided(&AlgebraicValue::U32(6), false).0
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::algebraic_value::cmp::{conform, TypeError};
 use crate::algebraic_value::AlgebraicValue;
 use crate::sum_type::SumType;
+use crate::{Typespace, WithTypespace};
 
 /// A value of a sum type chosing a specific variant of the type.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -11,6 +13,207 @@ pub struct SumValue {
     pub value: Box<AlgebraicValue>,
 }
 
+impl SumValue {
+    /// Returns the sum value with the same `tag` and its `value` transformed by `f`.
+    ///
+    /// The box holding the value is reused.
+    pub fn map_value(mut self, f: impl FnOnce(AlgebraicValue) -> AlgebraicValue) -> SumValue {
+        let value = std::mem::replace(&mut *self.value, AlgebraicValue::UNIT);
+        *self.value = f(value);
+        self
+    }
+
+    /// Returns the sum value with the same `tag` and its `value` transformed by `f`,
+    /// or the error of `f` if it fails.
+    pub fn try_map_value<E>(
+        mut self,
+        f: impl FnOnce(AlgebraicValue) -> Result<AlgebraicValue, E>,
+    ) -> Result<SumValue, E> {
+        let value = std::mem::replace(&mut *self.value, AlgebraicValue::UNIT);
+        *self.value = f(value)?;
+        Ok(self)
+    }
+
+    /// Returns an estimate of the number of bytes `self` owns on the heap,
+    /// including the box holding its `value`.
+    pub fn heap_size_bytes(&self) -> usize {
+        std::mem::size_of::<AlgebraicValue>() + self.value.heap_size_bytes()
+    }
+}
+
+impl SumValue {
+    /// Returns the value of the variant named `variant_name` of `ty` holding `payload`,
+    /// or an error if there is no such variant or `payload` isn't of its type.
+    ///
+    /// Of several variants with the same name, the first is chosen.
+    /// The type of the variant may not have any `Ref`s, see [`SumValue::make_in`] for those.
+    pub fn make(ty: &SumType, variant_name: &str, payload: AlgebraicValue) -> Result<SumValue, MakeSumError> {
+        Self::make_in(Typespace::default().with_type(ty), variant_name, payload)
+    }
+
+    /// Returns the value of the variant named `variant_name` of `ty` holding `payload`,
+    /// as [`SumValue::make`] does, resolving any `Ref`s in the type of the variant in the typespace of `ty`.
+    pub fn make_in(
+        ty: WithTypespace<'_, SumType>,
+        variant_name: &str,
+        payload: AlgebraicValue,
+    ) -> Result<SumValue, MakeSumError> {
+        let variants = &ty.ty().variants;
+        // Only the first 256 variants have a tag.
+        let (tag, variant) = (variants.iter().take(usize::from(u8::MAX) + 1).enumerate())
+            .find(|(_, v)| v.has_name(variant_name))
+            .ok_or_else(|| MakeSumError::UnknownVariant {
+                name: variant_name.to_owned(),
+                available: variants.iter().filter_map(|v| v.name()).map(str::to_owned).collect(),
+            })?;
+        conform(ty.with(&variant.algebraic_type), &payload)?;
+        Ok(SumValue {
+            tag: tag as u8,
+            value: Box::new(payload),
+        })
+    }
+
+    /// Returns the name of the variant of `ty` that `self` is a value of,
+    /// or `None` if that variant is unnamed or `ty` has no variant for the tag of `self`.
+    pub fn variant_name<'a>(&self, ty: &'a SumType) -> Option<&'a str> {
+        ty.variants.get(usize::from(self.tag))?.name()
+    }
+
+    /// Returns whether `self` is a value of the variant named `name` of `ty`.
+    pub fn is_variant(&self, ty: &SumType, name: &str) -> bool {
+        self.variant_name(ty) == Some(name)
+    }
+}
+
+/// An error constructing a [`SumValue`] by the name of its variant.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MakeSumError {
+    /// The sum type has no variant of the name.
+    #[error("No variant named {name:?}, the named variants are {available:?}")]
+    UnknownVariant {
+        /// The name that was looked for.
+        name: String,
+        /// The names of the named variants of the sum type, in order.
+        available: Vec<String>,
+    },
+    /// The payload is not of the type of the variant.
+    #[error(transparent)]
+    Payload(#[from] TypeError),
+}
+
 impl crate::Value for SumValue {
     type Type = SumType;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::DeserializeSeed;
+    use crate::{bsatn, AlgebraicType, SumTypeVariant};
+
+    /// A sum with both named and unnamed variants, `{ circle(F32) | U8 | square(String) | () }`.
+    fn shapes() -> SumType {
+        SumType::new(vec![
+            SumTypeVariant::new_named(AlgebraicType::F32, "circle"),
+            SumTypeVariant::new(AlgebraicType::U8, None),
+            SumTypeVariant::new_named(AlgebraicType::String, "square"),
+            SumTypeVariant::new(AlgebraicType::UNIT_TYPE, None),
+        ])
+    }
+
+    #[test]
+    fn make_by_variant_name() {
+        let ty = shapes();
+        let square = SumValue::make(&ty, "square", "big".into()).unwrap();
+        assert_eq!(square.tag, 2);
+        assert_eq!(square.variant_name(&ty), Some("square"));
+        assert!(square.is_variant(&ty, "square"));
+        assert!(!square.is_variant(&ty, "circle"));
+
+        let unnamed = SumValue {
+            tag: 1,
+            value: Box::new(AlgebraicValue::U8(3)),
+        };
+        assert_eq!(unnamed.variant_name(&ty), None);
+        assert_eq!(SumValue { tag: 9, ..unnamed }.variant_name(&ty), None);
+
+        // Constructed values encode at the sum type, and decode back to themselves.
+        let circle = SumValue::make(&ty, "circle", AlgebraicValue::F32(1.5.into())).unwrap();
+        let ts = Typespace::default();
+        for val in [square, circle] {
+            let bytes = bsatn::to_vec(&ts.with_type(&ty).with_value(&val)).unwrap();
+            let decoded = ts
+                .with_type(&AlgebraicType::Sum(ty.clone()))
+                .deserialize(bsatn::Deserializer::new(&mut &bytes[..]))
+                .unwrap();
+            assert_eq!(decoded, AlgebraicValue::Sum(val));
+        }
+    }
+
+    #[test]
+    fn make_errors() {
+        let ty = shapes();
+        let err = SumValue::make(&ty, "triangle", AlgebraicValue::UNIT).unwrap_err();
+        assert_eq!(
+            err,
+            MakeSumError::UnknownVariant {
+                name: "triangle".into(),
+                available: vec!["circle".into(), "square".into()],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"No variant named "triangle", the named variants are ["circle", "square"]"#
+        );
+
+        let err = SumValue::make(&ty, "circle", "round".into()).unwrap_err();
+        assert!(
+            matches!(err, MakeSumError::Payload(TypeError::NonConforming { .. })),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn map_value_keeps_tag() {
+        let sum = SumValue {
+            tag: 3,
+            value: Box::new(AlgebraicValue::U32(20)),
+        };
+        let mapped = sum.map_value(|v| AlgebraicValue::U32(*v.as_u32().unwrap() + 1));
+        assert_eq!(mapped.tag, 3);
+        assert_eq!(*mapped.value, AlgebraicValue::U32(21));
+
+        let unit = SumValue {
+            tag: 1,
+            value: Box::new(AlgebraicValue::UNIT),
+        };
+        let mapped = unit.map_value(|v| {
+            assert_eq!(v, AlgebraicValue::UNIT);
+            AlgebraicValue::Bool(true)
+        });
+        assert_eq!((mapped.tag, *mapped.value), (1, AlgebraicValue::Bool(true)));
+    }
+
+    #[test]
+    fn try_map_value_propagates_errors() {
+        let sum = SumValue {
+            tag: 2,
+            value: Box::new(AlgebraicValue::String("x".into())),
+        };
+        let err = sum.clone().try_map_value(|v| v.into_u32().map(AlgebraicValue::U32));
+        assert_eq!(err, Err(AlgebraicValue::String("x".into())));
+
+        let ok = sum.try_map_value(|v| Ok::<_, ()>(AlgebraicValue::from(format!("{}y", v.as_string().unwrap()))));
+        assert_eq!(
+            ok.map(|s| (s.tag, *s.value)),
+            Ok((2, AlgebraicValue::String("xy".into())))
+        );
+
+        let unit = SumValue {
+            tag: 0,
+            value: Box::new(AlgebraicValue::UNIT),
+        };
+        assert_eq!(unit.clone().try_map_value(|_| Err("nope")), Err("nope"));
+        assert_eq!(unit.clone().try_map_value(Ok::<_, ()>), Ok(unit));
+    }
+}
@@ -3,8 +3,16 @@ use std::ops::{Index, IndexMut};
 
 use crate::algebraic_type::AlgebraicType;
 use crate::algebraic_type_ref::AlgebraicTypeRef;
-use crate::WithTypespace;
+use crate::schema::version::SchemaVersion;
 use crate::{de::Deserialize, ser::Serialize};
+use crate::{
+    impl_deserialize, impl_serialize, ProductTypeElement, SumTypeVariant, Value, ValueWithType, WithTypespace,
+};
+
+pub mod builder;
+pub mod names;
+
+use names::{NameError, NameRegistry};
 
 /// A `Typespace` represents the typing context in SATS.
 ///
@@ -21,14 +29,44 @@ use crate::{de::Deserialize, ser::Serialize};
 /// e.g., `&0 = { Cons({ v: U8, t: &0 }), Nil }` represents a basic cons list
 /// where `&0` is the type reference at index `0`.
 ///
+/// A typespace may also have a [`SchemaVersion`], e.g., to check upgrades of the schema against.
+/// It may also have the names of its named types, in a [`NameRegistry`].
+/// Neither the version nor the names are part of the encoding of a typespace, only its `types` are.
+///
 /// [System F]: https://en.wikipedia.org/wiki/System_F
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[sats(crate = crate)]
+#[derive(Debug, Clone)]
 pub struct Typespace {
     /// The types in our typing context that can be referred to with [`AlgebraicTypeRef`]s.
     pub types: Vec<AlgebraicType>,
+    /// The version of the schema these types make up, if any.
+    version: Option<SchemaVersion>,
+    /// The names of the named types.
+    names: NameRegistry,
 }
 
+/// An error resolving the `Ref`s at the head of a type,
+/// as the ref is not in the typespace, or only refers to refs, and newtypes around them, in a cycle.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("The type ref {0} does not resolve in its typespace")]
+pub(crate) struct UnresolvedRef(pub(crate) AlgebraicTypeRef);
+
+/// How a [`Typespace`] is encoded, without its version.
+#[derive(Serialize)]
+#[sats(crate = crate, name = "Typespace")]
+struct TypespaceRef<'a> {
+    types: &'a Vec<AlgebraicType>,
+}
+
+/// How a [`Typespace`] is decoded, without a version.
+#[derive(Deserialize)]
+#[sats(crate = crate, name = "Typespace")]
+struct TypespaceRepr {
+    types: Vec<AlgebraicType>,
+}
+
+impl_serialize!([] Typespace, (self, ser) => TypespaceRef { types: &self.types }.serialize(ser));
+impl_deserialize!([] Typespace, de => TypespaceRepr::deserialize(de).map(|ts| Typespace::new(ts.types)));
+
 impl Default for Typespace {
     fn default() -> Self {
         Self::new(Vec::new())
@@ -51,7 +89,53 @@ impl IndexMut<AlgebraicTypeRef> for Typespace {
 impl Typespace {
     /// Returns a context ([`Typespace`]) with the given `types`.
     pub const fn new(types: Vec<AlgebraicType>) -> Self {
-        Self { types }
+        Self {
+            types,
+            version: None,
+            names: NameRegistry::new(),
+        }
+    }
+
+    /// Returns this typespace with the schema version `version`.
+    pub fn with_version(self, version: SchemaVersion) -> Self {
+        Self {
+            version: Some(version),
+            ..self
+        }
+    }
+
+    /// Returns the schema version of this typespace, if it has one.
+    pub fn version(&self) -> Option<SchemaVersion> {
+        self.version
+    }
+
+    /// Returns this typespace with the names of its named types `names`.
+    pub fn with_names(self, names: NameRegistry) -> Self {
+        Self { names, ..self }
+    }
+
+    /// Returns the names of the named types of this typespace.
+    pub fn names(&self) -> &NameRegistry {
+        &self.names
+    }
+
+    /// Registers `name` as the name of the type `r`, per [`NameRegistry::register_name`],
+    /// or returns an error if `r` is not in this typespace.
+    pub fn register_name(&mut self, name: &str, r: AlgebraicTypeRef) -> Result<(), NameError> {
+        if self.get(r).is_none() {
+            return Err(NameError::UnknownRef(r));
+        }
+        self.names.register_name(name, r)
+    }
+
+    /// Returns the ref of the type named `name`, if any.
+    pub fn resolve_name(&self, name: &str) -> Option<AlgebraicTypeRef> {
+        self.names.resolve_name(name)
+    }
+
+    /// Returns the name of the type `r`, if it has one.
+    pub fn name_of(&self, r: AlgebraicTypeRef) -> Option<&str> {
+        self.names.name_of(r)
     }
 
     /// Returns the [`AlgebraicType`] referred to by `r` within this context.
@@ -59,6 +143,38 @@ impl Typespace {
         self.types.get(r.idx())
     }
 
+    /// Returns `ty` with all the `Ref`s at its head resolved in this typespace.
+    pub(crate) fn resolve_head<'a>(&'a self, ty: &'a AlgebraicType) -> Result<&'a AlgebraicType, UnresolvedRef> {
+        self.resolve_head_through(ty, false)
+    }
+
+    /// Returns `ty` with all the `Ref`s and newtypes at its head resolved in this typespace,
+    /// i.e., the type that values of `ty` are encoded as.
+    pub(crate) fn resolve_value_head<'a>(&'a self, ty: &'a AlgebraicType) -> Result<&'a AlgebraicType, UnresolvedRef> {
+        self.resolve_head_through(ty, true)
+    }
+
+    /// Returns `ty` with all the `Ref`s, and if `newtypes`, all the newtypes, at its head resolved.
+    fn resolve_head_through<'a>(
+        &'a self,
+        mut ty: &'a AlgebraicType,
+        newtypes: bool,
+    ) -> Result<&'a AlgebraicType, UnresolvedRef> {
+        // Each step resolves a different ref, unless there's a cycle of refs, which this many steps would reveal.
+        let mut steps = self.types.len();
+        loop {
+            ty = match ty {
+                &AlgebraicType::Ref(r) => {
+                    let next = self.get(r).filter(|_| steps > 0).ok_or(UnresolvedRef(r))?;
+                    steps -= 1;
+                    next
+                }
+                AlgebraicType::Newtype(nt) if newtypes => &nt.inner,
+                _ => return Ok(ty),
+            }
+        }
+    }
+
     /// Inserts an `AlgebraicType` into the typespace
     /// and returns an `AlgebraicTypeRef` that refers to the inserted `AlgebraicType`.
     ///
@@ -84,6 +200,12 @@ impl Typespace {
     pub const fn with_type<'a, T: ?Sized>(&'a self, ty: &'a T) -> WithTypespace<'a, T> {
         WithTypespace::new(self, ty)
     }
+
+    /// Returns `val` of type `ty` combined with the context `self`,
+    /// short for `self.with_type(ty).with_value(val)`, e.g., to serialize `val` as `ty`.
+    pub fn with_value<'a, V: Value>(&'a self, ty: &'a V::Type, val: &'a V) -> ValueWithType<'a, V> {
+        self.with_type(ty).with_value(val)
+    }
 }
 
 /// A trait for types that can be represented as an `AlgebraicType`
@@ -108,6 +230,43 @@ pub trait TypespaceBuilder {
     ) -> AlgebraicType;
 }
 
+/// Returns the `AlgebraicType` of `T`, along with the typing context its references point into.
+pub(crate) fn type_of<T: SpacetimeType>() -> (Typespace, AlgebraicType) {
+    let mut builder = RefBuilder::default();
+    let ty = T::make_type(&mut builder);
+    (builder.typespace, ty)
+}
+
+/// A [`TypespaceBuilder`] adding each Rust type once, behind a reference, as needed for recursive types.
+#[derive(Default)]
+struct RefBuilder {
+    /// The types added so far.
+    typespace: Typespace,
+    /// The reference to each Rust type added.
+    refs: std::collections::HashMap<TypeId, AlgebraicTypeRef>,
+}
+
+impl TypespaceBuilder for RefBuilder {
+    fn add(
+        &mut self,
+        typeid: TypeId,
+        _name: Option<&'static str>,
+        make_ty: impl FnOnce(&mut Self) -> AlgebraicType,
+    ) -> AlgebraicType {
+        let r = match self.refs.get(&typeid) {
+            Some(r) => *r,
+            None => {
+                // Reserve the reference first, so that `T` may refer to itself.
+                let r = self.typespace.add(AlgebraicType::UNIT_TYPE);
+                self.refs.insert(typeid, r);
+                self.typespace[r] = make_ty(self);
+                r
+            }
+        };
+        AlgebraicType::Ref(r)
+    }
+}
+
 /// Implements [`SpacetimeType`] for a type in a simplified manner.
 ///
 /// An example:
@@ -157,7 +316,37 @@ impl_primitives! {
     String => String,
 }
 
+/// Returns the type of ranges of `ty`, the product `{ start: ty, end: ty }`.
+fn range_type(ty: AlgebraicType) -> AlgebraicType {
+    AlgebraicType::product(vec![
+        ProductTypeElement::new_named(ty.clone(), "start"),
+        ProductTypeElement::new_named(ty, "end"),
+    ])
+}
+
 impl_st!([] (), _ts => AlgebraicType::UNIT_TYPE);
 impl_st!([] &str, _ts => AlgebraicType::String);
+impl_st!([] std::path::PathBuf, _ts => AlgebraicType::String);
+impl_st!([] std::ffi::OsString, _ts => AlgebraicType::String);
+impl_st!([] crate::ser::LossyPath, _ts => AlgebraicType::String);
+impl_st!([T: SpacetimeType] std::num::Wrapping<T>, ts => T::make_type(ts));
 impl_st!([T: SpacetimeType] Vec<T>, ts => AlgebraicType::array(T::make_type(ts)));
 impl_st!([T: SpacetimeType] Option<T>, ts => AlgebraicType::option(T::make_type(ts)));
+impl_st!([T: SpacetimeType] std::ops::Range<T>, ts => range_type(T::make_type(ts)));
+impl_st!([T: SpacetimeType] std::ops::RangeInclusive<T>, ts => range_type(T::make_type(ts)));
+impl_st!([T: SpacetimeType] std::ops::Bound<T>, ts => {
+    let ty = T::make_type(ts);
+    AlgebraicType::sum(vec![
+        SumTypeVariant::new_named(ty.clone(), "included"),
+        SumTypeVariant::new_named(ty, "excluded"),
+        SumTypeVariant::unit("unbounded"),
+    ])
+});
+#[cfg(feature = "chrono")]
+impl_st!([] chrono::DateTime<chrono::Utc>, _ts => AlgebraicType::timestamp_ms());
+#[cfg(feature = "chrono")]
+impl_st!([] chrono::NaiveDate, _ts => AlgebraicType::I32);
+#[cfg(feature = "nalgebra")]
+impl_st!([] nalgebra::Vector3<f32>, _ts => AlgebraicType::array(AlgebraicType::F32));
+#[cfg(feature = "nalgebra")]
+impl_st!([] nalgebra::Matrix4<f32>, _ts => AlgebraicType::array(AlgebraicType::F32));
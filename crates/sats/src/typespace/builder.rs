@@ -0,0 +1,248 @@
+//! A builder for a [`Typespace`] of named types, which hands out the references to them,
+//! so that they needn't be tracked by hand.
+
+use std::collections::HashMap;
+
+use super::names::NameRegistry;
+use crate::{AlgebraicType, AlgebraicTypeRef, Typespace};
+
+/// The names of the named types of a typespace, as taken by
+/// [`generate_rust_structs`](crate::schema::codegen::generate_rust_structs).
+pub type NameMap = HashMap<AlgebraicTypeRef, String>;
+
+/// An error that occurs when building a typespace with a [`NamedTypespaceBuilder`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TypespaceBuildError {
+    /// A type was defined under a name that was never declared.
+    #[error("Type `{name}` was defined without being declared")]
+    Undeclared { name: String },
+    /// A type was declared but never defined.
+    #[error("Type `{name}` was declared but never defined")]
+    Undefined { name: String },
+    /// A type was defined again, differently.
+    #[error("Type `{name}` was defined twice, differently")]
+    Redefined { name: String },
+}
+
+/// A builder for a [`Typespace`], adding named types by name and getting references to them back, e.g.:
+/// ```
+/// # use spacetimedb_sats::typespace::builder::NamedTypespaceBuilder;
+/// # use spacetimedb_sats::{algebraic_type::builder::ProductTypeBuilder, AlgebraicType};
+/// let mut builder = NamedTypespaceBuilder::new();
+/// // A node refers to itself, declared before its definition.
+/// let node = builder.declare("Node");
+/// let def = ProductTypeBuilder::new()
+///     .field("value", AlgebraicType::U32)
+///     .field("next", AlgebraicType::option(AlgebraicType::Ref(node)))
+///     .build();
+/// builder.define("Node", AlgebraicType::Product(def)).unwrap();
+/// let list = builder.add_named("List", |b| AlgebraicType::option(AlgebraicType::Ref(b.get("Node").unwrap())));
+/// let (ts, names) = builder.finish().unwrap();
+/// assert_eq!(names[&list], "List");
+/// assert_eq!(ts.resolve_name("Node"), Some(node));
+/// assert!(ts[node].is_product());
+/// ```
+///
+/// Structurally identical types are added only once, except for types of different names.
+///
+/// Unlike the [`TypespaceBuilder`](crate::typespace::TypespaceBuilder) trait,
+/// through which [`SpacetimeType`](crate::SpacetimeType)s register themselves, this builds the typespace itself.
+#[derive(Debug, Clone, Default)]
+pub struct NamedTypespaceBuilder {
+    /// The types added so far, with `None` for those declared but not defined yet.
+    types: Vec<Option<AlgebraicType>>,
+    /// The names of the named types.
+    names: NameMap,
+    /// The reference to each named type, by its name.
+    refs: HashMap<String, AlgebraicTypeRef>,
+    /// The first reference to each type defined, so as to add structurally identical types once.
+    by_type: HashMap<AlgebraicType, AlgebraicTypeRef>,
+}
+
+impl NamedTypespaceBuilder {
+    /// Returns a builder for a typespace without any types yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the reference to the type `name`, if it has been declared.
+    pub fn get(&self, name: &str) -> Option<AlgebraicTypeRef> {
+        self.refs.get(name).copied()
+    }
+
+    /// Adds an entry for the type `ty`, or `None` for one declared but not defined yet.
+    fn push(&mut self, ty: Option<AlgebraicType>) -> AlgebraicTypeRef {
+        let index = self
+            .types
+            .len()
+            .try_into()
+            .expect("ran out of space for `AlgebraicTypeRef`s");
+        self.types.push(ty);
+        AlgebraicTypeRef(index)
+    }
+
+    /// Declares the type `name`, to be [defined](Self::define) later,
+    /// and returns the reference to it, e.g., for it or other types to refer to it before it's defined.
+    ///
+    /// Declaring a type more than once returns the same reference each time.
+    pub fn declare(&mut self, name: &str) -> AlgebraicTypeRef {
+        if let Some(r) = self.get(name) {
+            return r;
+        }
+        let r = self.push(None);
+        self.names.insert(r, name.to_owned());
+        self.refs.insert(name.to_owned(), r);
+        r
+    }
+
+    /// Defines the [declared](Self::declare) type `name` as `ty` and returns the reference to it.
+    ///
+    /// Defining a type again as the same type does nothing,
+    /// while defining it as another type is an error, as is defining an undeclared type.
+    pub fn define(&mut self, name: &str, ty: AlgebraicType) -> Result<AlgebraicTypeRef, TypespaceBuildError> {
+        let Some(r) = self.get(name) else {
+            return Err(TypespaceBuildError::Undeclared { name: name.to_owned() });
+        };
+        match &self.types[r.idx()] {
+            Some(defined) if *defined == ty => {}
+            Some(_) => return Err(TypespaceBuildError::Redefined { name: name.to_owned() }),
+            None => {
+                self.types[r.idx()] = Some(ty.clone());
+                self.by_type.entry(ty).or_insert(r);
+            }
+        }
+        Ok(r)
+    }
+
+    /// Declares the type `name` and defines it as the type `make_ty` returns, and returns the reference to it.
+    ///
+    /// `make_ty` gets the builder, to add the types that `name` is made of, or to [get](Self::get) references to them,
+    /// including, for a recursive type, to `name` itself.
+    ///
+    /// Panics if `name` was already defined as another type. See [`NamedTypespaceBuilder::define`] to handle that.
+    pub fn add_named(&mut self, name: &str, make_ty: impl FnOnce(&mut Self) -> AlgebraicType) -> AlgebraicTypeRef {
+        self.declare(name);
+        let ty = make_ty(self);
+        self.define(name, ty)
+            .unwrap_or_else(|e| panic!("invalid typespace: {e}"))
+    }
+
+    /// Adds the unnamed type `ty` and returns the reference to it,
+    /// which, if a structurally identical type was already defined, is the reference to that type.
+    pub fn add(&mut self, ty: AlgebraicType) -> AlgebraicTypeRef {
+        if let Some(&r) = self.by_type.get(&ty) {
+            return r;
+        }
+        let r = self.push(Some(ty.clone()));
+        self.by_type.insert(ty, r);
+        r
+    }
+
+    /// Returns the typespace of the types added, in order, with the [names](Typespace::names) of those that are named,
+    /// and those names again, or an error if a type was declared but never defined.
+    pub fn finish(self) -> Result<(Typespace, NameMap), TypespaceBuildError> {
+        let mut registry = NameRegistry::new();
+        for (name, &r) in &self.refs {
+            registry
+                .register_name(name, r)
+                .expect("each name is declared once, as one type");
+        }
+        let names = self.names;
+        let types = self
+            .types
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                // Only named types are ever declared without a definition.
+                ty.ok_or_else(|| TypespaceBuildError::Undefined {
+                    name: names[&AlgebraicTypeRef(i as u32)].clone(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((Typespace::new(types).with_names(registry), names))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_type::builder::{ProductTypeBuilder, SumTypeBuilder};
+    use crate::{bsatn, product, AlgebraicValue};
+
+    #[test]
+    fn recursive_pair() {
+        // A tree and a forest of trees, each referring to the other.
+        let mut builder = NamedTypespaceBuilder::new();
+        let forest = builder.declare("Forest");
+        let tree = builder.add_named("Tree", |_| {
+            let def = ProductTypeBuilder::new()
+                .field("value", AlgebraicType::U32)
+                .field_ref("children", forest)
+                .build();
+            AlgebraicType::Product(def)
+        });
+        let children = AlgebraicType::array(AlgebraicType::Ref(tree));
+        assert_eq!(builder.define("Forest", children.clone()), Ok(forest));
+        let (ts, names) = builder.finish().unwrap();
+
+        assert_eq!(ts.types.len(), 2);
+        assert_eq!(ts[forest], children);
+        assert_eq!(names, NameMap::from([(forest, "Forest".into()), (tree, "Tree".into())]));
+        assert_eq!(ts.names().to_name_map(), names);
+        let leaf = product![2u32, AlgebraicValue::ArrayOf(Vec::<crate::ProductValue>::new())];
+        let root = AlgebraicValue::Product(product![1u32, AlgebraicValue::ArrayOf(vec![leaf])]);
+        let ty = AlgebraicType::Ref(tree);
+        let bytes = bsatn::to_vec(&ts.with_value(&ty, &root)).unwrap();
+        assert_eq!(bytes, bsatn::to_vec(&root).unwrap());
+    }
+
+    #[test]
+    fn identical_types_are_added_once() {
+        let mut builder = NamedTypespaceBuilder::new();
+        let point = || {
+            let def = ProductTypeBuilder::new()
+                .field("x", AlgebraicType::F32)
+                .field("y", AlgebraicType::F32)
+                .build();
+            AlgebraicType::Product(def)
+        };
+        let named = builder.add_named("Point", |_| point());
+        assert_eq!(builder.add_named("Point", |_| point()), named);
+        assert_eq!(builder.add(point()), named);
+        let bytes = builder.add(AlgebraicType::bytes());
+        assert_eq!(builder.add(AlgebraicType::bytes()), bytes);
+        // Types of different names stay apart, whatever their structure.
+        let vector = builder.add_named("Vector", |_| point());
+        assert_ne!(vector, named);
+
+        let (ts, names) = builder.finish().unwrap();
+        assert_eq!(ts.types.len(), 3);
+        assert_eq!(names.len(), 2);
+        assert_eq!(ts[bytes], AlgebraicType::bytes());
+    }
+
+    #[test]
+    fn invalid_definitions_are_errors() {
+        let mut builder = NamedTypespaceBuilder::new();
+        let err = builder.define("Ghost", AlgebraicType::U8).unwrap_err();
+        assert_eq!(err, TypespaceBuildError::Undeclared { name: "Ghost".into() });
+        assert_eq!(err.to_string(), "Type `Ghost` was defined without being declared");
+
+        let status = SumTypeBuilder::new().unit_variant("on").unit_variant("off").build();
+        builder.add_named("Status", |_| AlgebraicType::Sum(status));
+        let err = builder.define("Status", AlgebraicType::Bool).unwrap_err();
+        assert_eq!(err, TypespaceBuildError::Redefined { name: "Status".into() });
+
+        builder.declare("Pending");
+        let err = builder.finish().unwrap_err();
+        assert_eq!(err, TypespaceBuildError::Undefined { name: "Pending".into() });
+    }
+
+    #[test]
+    #[should_panic = "invalid typespace: Type `Level` was defined twice, differently"]
+    fn add_named_panics_on_redefinition() {
+        let mut builder = NamedTypespaceBuilder::new();
+        builder.add_named("Level", |_| AlgebraicType::U8);
+        builder.add_named("Level", |_| AlgebraicType::U16);
+    }
+}
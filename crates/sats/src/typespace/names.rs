@@ -0,0 +1,216 @@
+//! A registry of the names of the types of a [`Typespace`](crate::Typespace),
+//! e.g., for codegen and printing to refer to `Player` rather than `&2`.
+
+use std::collections::BTreeMap;
+
+use super::builder::NameMap;
+use crate::de::{Deserialize, Error as _};
+use crate::ser::Serialize;
+use crate::{impl_deserialize, impl_serialize, AlgebraicTypeRef};
+
+/// An error registering a name, as each name is of one type and each type has one name.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    /// The name is already registered for another type.
+    #[error("The name `{name}` is already registered for the type {existing}")]
+    NameTaken { name: String, existing: AlgebraicTypeRef },
+    /// The type is already registered under another name.
+    #[error("The type {ty} is already registered as `{existing}`")]
+    AlreadyNamed { ty: AlgebraicTypeRef, existing: String },
+    /// The type is not in the typespace.
+    #[error("The type {0} is not in the typespace")]
+    UnknownRef(AlgebraicTypeRef),
+}
+
+/// The names of the named types of a typespace, mapping each name to the ref of its type and back.
+///
+/// Names are unique, and so are the types named, so each named type has exactly one name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameRegistry {
+    /// The ref of the type of each name.
+    names: BTreeMap<Box<str>, AlgebraicTypeRef>,
+    /// The name of each named type.
+    by_ref: BTreeMap<AlgebraicTypeRef, Box<str>>,
+}
+
+impl NameRegistry {
+    /// Returns a registry without any names.
+    pub const fn new() -> Self {
+        Self {
+            names: BTreeMap::new(),
+            by_ref: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of names.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns whether there are no names.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Registers `name` as the name of the type `r`.
+    ///
+    /// Registering a name again for the same type does nothing,
+    /// while registering it for another type, or another name for the type, is an error.
+    pub fn register_name(&mut self, name: &str, r: AlgebraicTypeRef) -> Result<(), NameError> {
+        if let Some(&existing) = self.names.get(name) {
+            if existing == r {
+                return Ok(());
+            }
+            return Err(NameError::NameTaken {
+                name: name.to_owned(),
+                existing,
+            });
+        }
+        if let Some(existing) = self.by_ref.get(&r) {
+            return Err(NameError::AlreadyNamed {
+                ty: r,
+                existing: existing.to_string(),
+            });
+        }
+        self.names.insert(name.into(), r);
+        self.by_ref.insert(r, name.into());
+        Ok(())
+    }
+
+    /// Returns the ref of the type named `name`, if any.
+    pub fn resolve_name(&self, name: &str) -> Option<AlgebraicTypeRef> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the name of the type `r`, if it has one.
+    pub fn name_of(&self, r: AlgebraicTypeRef) -> Option<&str> {
+        self.by_ref.get(&r).map(|name| &**name)
+    }
+
+    /// Returns an iterator over the names and the refs of their types, in the order of the names.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, AlgebraicTypeRef)> + '_ {
+        self.names.iter().map(|(name, &r)| (&**name, r))
+    }
+
+    /// Returns the registry with the ref of each name mapped per `remap`, e.g., after the types have moved,
+    /// and without the names of the types `remap` maps to `None`, e.g., as they were removed.
+    ///
+    /// It is an error for two named types to be mapped to the same type.
+    pub fn remap(
+        &self,
+        mut remap: impl FnMut(AlgebraicTypeRef) -> Option<AlgebraicTypeRef>,
+    ) -> Result<Self, NameError> {
+        let mut names = Self::new();
+        for (name, r) in self.iter() {
+            if let Some(r) = remap(r) {
+                names.register_name(name, r)?;
+            }
+        }
+        Ok(names)
+    }
+
+    /// Returns the names as taken by [`generate_rust_structs`](crate::schema::codegen::generate_rust_structs).
+    pub fn to_name_map(&self) -> NameMap {
+        self.by_ref.iter().map(|(&r, name)| (r, name.to_string())).collect()
+    }
+}
+
+/// A registered name, as the registry is encoded, i.e., as an array of these, in the order of the names.
+#[derive(Serialize, Deserialize)]
+#[sats(crate = crate)]
+struct NameEntry {
+    name: String,
+    ty: AlgebraicTypeRef,
+}
+
+impl_serialize!([] NameRegistry, (self, ser) => {
+    let entries = self.iter().map(|(name, ty)| NameEntry { name: name.to_owned(), ty });
+    entries.collect::<Vec<_>>().serialize(ser)
+});
+impl_deserialize!([] NameRegistry, de => {
+    let entries = Vec::<NameEntry>::deserialize(de)?;
+    let mut names = NameRegistry::new();
+    for NameEntry { name, ty } in entries {
+        names.register_name(&name, ty).map_err(D::Error::custom)?;
+    }
+    Ok(names)
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_type::fmt::fmt_algebraic_type_named;
+    use crate::{bsatn, AlgebraicType};
+
+    #[test]
+    fn names_are_unique() {
+        let mut names = NameRegistry::new();
+        let (player, shape) = (AlgebraicTypeRef(2), AlgebraicTypeRef(0));
+        names.register_name("Player", player).unwrap();
+        names.register_name("Shape", shape).unwrap();
+        assert_eq!(names.register_name("Player", player), Ok(()));
+        assert_eq!(names.len(), 2);
+
+        let err = names.register_name("Player", AlgebraicTypeRef(1)).unwrap_err();
+        assert_eq!(
+            err,
+            NameError::NameTaken {
+                name: "Player".into(),
+                existing: player
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "The name `Player` is already registered for the type &2"
+        );
+        let err = names.register_name("Polygon", shape).unwrap_err();
+        assert_eq!(
+            err,
+            NameError::AlreadyNamed {
+                ty: shape,
+                existing: "Shape".into()
+            }
+        );
+
+        assert_eq!(names.resolve_name("Player"), Some(player));
+        assert_eq!(names.resolve_name("Polygon"), None);
+        assert_eq!(names.name_of(shape), Some("Shape"));
+        assert_eq!(names.name_of(AlgebraicTypeRef(1)), None);
+        assert!(names.iter().eq([("Player", player), ("Shape", shape)]));
+        assert_eq!(names.to_name_map()[&player], "Player");
+
+        // Merging two named types is a collision, while removing one drops its name.
+        let merged = names.remap(|_| Some(AlgebraicTypeRef(0)));
+        assert!(matches!(merged, Err(NameError::AlreadyNamed { .. })));
+        let removed = names.remap(|r| (r != shape).then_some(AlgebraicTypeRef(0))).unwrap();
+        assert!(removed.iter().eq([("Player", AlgebraicTypeRef(0))]));
+    }
+
+    #[test]
+    fn round_trip_and_print() {
+        let mut names = NameRegistry::new();
+        names.register_name("Point", AlgebraicTypeRef(0)).unwrap();
+        names.register_name("Tree", AlgebraicTypeRef(3)).unwrap();
+        let bytes = bsatn::to_vec(&names).unwrap();
+        assert_eq!(bsatn::from_slice::<NameRegistry>(&bytes).unwrap(), names);
+        let empty = bsatn::to_vec(&NameRegistry::new()).unwrap();
+        assert_eq!(bsatn::from_slice::<NameRegistry>(&empty).unwrap(), NameRegistry::new());
+
+        // An encoding with a name twice is an error.
+        let twice = [("Point", 0u32), ("Point", 1)].map(|(name, ty)| NameEntry {
+            name: name.into(),
+            ty: AlgebraicTypeRef(ty),
+        });
+        let bytes = bsatn::to_vec(&Vec::from(twice)).unwrap();
+        assert!(bsatn::from_slice::<NameRegistry>(&bytes).is_err());
+
+        let ty = AlgebraicType::product(vec![
+            crate::ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(0)), "at"),
+            crate::ProductTypeElement::new_named(AlgebraicType::array(AlgebraicType::Ref(AlgebraicTypeRef(1))), "of"),
+        ]);
+        assert_eq!(
+            fmt_algebraic_type_named(&ty, &names).to_string(),
+            "(at: Point, of: Array<&1>)"
+        );
+    }
+}
@@ -0,0 +1,399 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use spacetimedb_sats::algebraic_value::de::ValueDeserializer;
+use spacetimedb_sats::algebraic_value::ser::ValueSerializer;
+use spacetimedb_sats::de::{Deserialize, DeserializeSeed, ValueSeed};
+use spacetimedb_sats::ser::Serialize;
+use spacetimedb_sats::{
+    bsatn, product, AlgebraicType, AlgebraicValue, ArrayValue, ProductType, ProductValue, Typespace, WithTypespace,
+};
+
+/// Counts the allocations made, and the bytes held live, on the current thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Adds `delta` to the live bytes of the current thread.
+fn track_bytes(delta: isize) {
+    LIVE_BYTES.with(|n| n.set(n.get() + delta));
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        track_bytes(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        track_bytes(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        track_bytes(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f`, returning its result and the number of allocations it made.
+fn count_allocs<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCS.with(Cell::get);
+    let ret = f();
+    (ret, ALLOCS.with(Cell::get) - before)
+}
+
+/// Runs `f`, returning its result and the number of bytes it left allocated.
+fn live_bytes<R>(f: impl FnOnce() -> R) -> (R, isize) {
+    let before = LIVE_BYTES.with(Cell::get);
+    let ret = f();
+    (ret, LIVE_BYTES.with(Cell::get) - before)
+}
+
+#[test]
+fn string_table_holds_no_excess_capacity() {
+    const ROWS: usize = 1_000_000;
+    // Strings built up piecewise, as from user input, often carry excess capacity.
+    let names = || {
+        (0..ROWS).map(|i| {
+            let mut name = String::with_capacity(16);
+            name.push_str(&format!("{i:08}"));
+            name
+        })
+    };
+
+    let (table, bytes) = live_bytes(|| names().map(AlgebraicValue::from).collect::<Vec<_>>());
+    let per_row = mem::size_of::<AlgebraicValue>() + 8;
+    assert_eq!(bytes, (ROWS * per_row) as isize);
+    assert!(table.iter().all(|v| v.heap_size_bytes() == 8));
+
+    // Holding onto the `String`s themselves keeps all of their capacity.
+    let (_strings, bytes) = live_bytes(|| names().collect::<Vec<_>>());
+    assert_eq!(bytes, (ROWS * (mem::size_of::<String>() + 16)) as isize);
+}
+
+#[test]
+fn scalar_row_allocates_once() {
+    let row: ProductValue = product![
+        42u64,
+        -7i32,
+        "short".to_owned(),
+        true,
+        AlgebraicValue::F64(2.5.into()),
+        AlgebraicValue::OptionSome(AlgebraicValue::String("name".into()))
+    ];
+    for to_vec in [bsatn::to_vec::<ProductValue>, bsatn::to_vec_exact] {
+        let (bytes, allocs) = count_allocs(|| to_vec(&row).unwrap());
+        assert_eq!(allocs, 1);
+        assert_eq!(bytes.capacity(), bytes.len());
+    }
+    assert_eq!(bsatn::to_vec(&row).unwrap(), bsatn::to_vec_exact(&row).unwrap());
+}
+
+#[test]
+fn exact_allocates_once_for_dynamic_values() {
+    let strings: Vec<String> = (0..100).map(|i| format!("element {i}")).collect();
+    let (exact, allocs) = count_allocs(|| bsatn::to_vec_exact(&strings).unwrap());
+    assert_eq!(allocs, 1);
+    assert_eq!(exact.capacity(), exact.len());
+    assert_eq!(exact, bsatn::to_vec(&strings).unwrap());
+
+    let row = product![1u8, AlgebraicValue::ArrayOf(strings)];
+    let (exact, allocs) = count_allocs(|| bsatn::to_vec_exact(&row).unwrap());
+    assert_eq!(allocs, 1);
+    assert_eq!(exact, bsatn::to_vec(&row).unwrap());
+}
+
+#[test]
+fn pointers_to_slices_encode_like_vecs() {
+    let string = "pointer \u{1f449}".to_owned();
+    let numbers = vec![1u32, 2, 3];
+    let bytes = vec![4u8, 5, 6];
+
+    let expected = count_allocs(|| bsatn::to_vec(&string).unwrap());
+    let rc: Rc<str> = string.as_str().into();
+    assert_eq!(count_allocs(|| bsatn::to_vec(&rc).unwrap()), expected);
+    let boxed = string.into_boxed_str();
+    assert_eq!(count_allocs(|| bsatn::to_vec(&boxed).unwrap()), expected);
+
+    let expected = count_allocs(|| bsatn::to_vec(&numbers).unwrap());
+    let arc: Arc<[u32]> = numbers.clone().into();
+    assert_eq!(count_allocs(|| bsatn::to_vec(&arc).unwrap()), expected);
+    let boxed = numbers.into_boxed_slice();
+    assert_eq!(count_allocs(|| bsatn::to_vec(&boxed).unwrap()), expected);
+
+    let expected = count_allocs(|| bsatn::to_vec(&bytes).unwrap());
+    let boxed = bytes.into_boxed_slice();
+    assert_eq!(count_allocs(|| bsatn::to_vec(&boxed).unwrap()), expected);
+}
+
+#[test]
+fn pointers_to_slices_decode_directly() {
+    let string = bsatn::to_vec("pointer \u{1f449}").unwrap();
+    let (expected, allocs) = count_allocs(|| bsatn::from_slice::<String>(&string).unwrap());
+    assert_eq!(allocs, 1);
+    let (boxed, allocs) = count_allocs(|| bsatn::from_slice::<Box<str>>(&string).unwrap());
+    assert_eq!((&*boxed, allocs), (&*expected, 1));
+    let (rc, allocs) = count_allocs(|| bsatn::from_slice::<Rc<str>>(&string).unwrap());
+    assert_eq!((&*rc, allocs), (&*expected, 1));
+    let (arc, allocs) = count_allocs(|| bsatn::from_slice::<Arc<str>>(&string).unwrap());
+    assert_eq!((&*arc, allocs), (&*expected, 1));
+
+    let bytes = bsatn::to_vec(&vec![4u8, 5, 6]).unwrap();
+    let (expected, allocs) = count_allocs(|| bsatn::from_slice::<Vec<u8>>(&bytes).unwrap());
+    let (boxed, boxed_allocs) = count_allocs(|| bsatn::from_slice::<Box<[u8]>>(&bytes).unwrap());
+    assert_eq!((&*boxed, boxed_allocs), (&*expected, allocs));
+
+    let numbers = bsatn::to_vec(&vec![1u32, 2, 3]).unwrap();
+    let (expected, allocs) = count_allocs(|| bsatn::from_slice::<Vec<u32>>(&numbers).unwrap());
+    let (boxed, boxed_allocs) = count_allocs(|| bsatn::from_slice::<Box<[u32]>>(&numbers).unwrap());
+    assert_eq!((&*boxed, boxed_allocs), (&*expected, allocs));
+    let arc = bsatn::from_slice::<Arc<[u32]>>(&numbers).unwrap();
+    assert_eq!(&*arc, &*expected);
+}
+
+#[test]
+fn boxed_slices_decode_into_exactly_their_contents() {
+    // One allocation each, holding exactly the contents, rather than a grown vector shrunk to fit.
+    let numbers = bsatn::to_vec(&vec![7u32; 9]).unwrap();
+    let ((boxed, allocs), bytes) = live_bytes(|| count_allocs(|| bsatn::from_slice::<Box<[u32]>>(&numbers).unwrap()));
+    assert_eq!((&*boxed, allocs, bytes), (&[7; 9][..], 1, 9 * 4));
+
+    let string = bsatn::to_vec("exactly").unwrap();
+    let ((boxed, allocs), bytes) = live_bytes(|| count_allocs(|| bsatn::from_slice::<Box<str>>(&string).unwrap()));
+    assert_eq!((&*boxed, allocs, bytes), ("exactly", 1, 7));
+
+    let blob = bsatn::to_vec(&vec![1u8, 2, 3]).unwrap();
+    let ((boxed, allocs), bytes) = live_bytes(|| count_allocs(|| bsatn::from_slice::<Box<[u8]>>(&blob).unwrap()));
+    assert_eq!((&*boxed, allocs, bytes), (&[1, 2, 3][..], 1, 3));
+}
+
+#[test]
+fn byte_arrays_decode_without_allocating() {
+    let digest: [u8; 16] = std::array::from_fn(|i| i as u8);
+    let bytes = bsatn::to_vec(&digest).unwrap();
+    assert_eq!(
+        count_allocs(|| bsatn::from_slice::<[u8; 16]>(&bytes).unwrap()),
+        (digest, 0)
+    );
+    assert_eq!(
+        count_allocs(|| bsatn::from_slice::<&[u8; 16]>(&bytes).unwrap()),
+        (&digest, 0)
+    );
+    let (boxed, allocs) = count_allocs(|| bsatn::from_slice::<Box<[u8; 16]>>(&bytes).unwrap());
+    assert_eq!((*boxed, allocs), (digest, 1));
+}
+
+#[test]
+fn string_arrays_decode_straight_into_the_output() {
+    let strings = (0..100).map(|i| format!("string #{i}")).collect::<Vec<_>>();
+    let bytes = bsatn::to_vec(&strings).unwrap();
+    // Only the output vector for borrowed strings, and one more per owned string.
+    let (borrowed, allocs) = count_allocs(|| bsatn::from_slice::<Vec<&str>>(&bytes).unwrap());
+    assert_eq!((borrowed, allocs), (strings.iter().map(String::as_str).collect(), 1));
+    let (owned, allocs) = count_allocs(|| bsatn::from_slice::<Vec<String>>(&bytes).unwrap());
+    assert_eq!((&owned, allocs), (&strings, 1 + strings.len()));
+}
+
+#[test]
+fn array_iter_skips_the_rest_without_allocating() {
+    let strings = (0..100).map(|i| format!("string #{i}")).collect::<Vec<_>>();
+    let bytes = bsatn::to_vec(&strings).unwrap();
+    let mut reader = &bytes[..];
+    let mut iter = bsatn::Deserializer::new(&mut reader)
+        .deserialize_array_iter::<String>()
+        .unwrap();
+    assert_eq!(iter.next().unwrap().unwrap(), strings[0]);
+    assert_eq!(count_allocs(|| iter.finish().unwrap()), ((), 0));
+    assert!(reader.is_empty());
+}
+
+#[derive(spacetimedb_sats::ser::Serialize)]
+#[sats(crate = spacetimedb_sats)]
+struct Stats {
+    fields: [u64; 8],
+    count: u32,
+    ratio: f64,
+    flags: [bool; 4],
+}
+
+/// Encodes `value` into a fresh, unreserved vector, returning the bytes and the allocations made.
+fn to_unreserved_vec<T: spacetimedb_sats::ser::Serialize + ?Sized>(value: &T) -> (Vec<u8>, usize) {
+    count_allocs(|| {
+        let mut bytes = Vec::new();
+        bsatn::to_writer(&mut bytes, value).unwrap();
+        bytes
+    })
+}
+
+#[test]
+fn static_size_hint_reserves_once() {
+    let stats = Stats {
+        fields: [7; 8],
+        count: 3,
+        ratio: 0.5,
+        flags: [true; 4],
+    };
+    let (hinted, allocs) = to_unreserved_vec(&stats);
+    assert_eq!(allocs, 1);
+    assert_eq!(hinted.capacity(), hinted.len());
+
+    // The same row as a `ProductValue`, which has no static size, encodes the same, but grows.
+    let fields = AlgebraicValue::ArrayOf(stats.fields.to_vec());
+    let flags = AlgebraicValue::ArrayOf(stats.flags.to_vec());
+    let row = product![fields, 3u32, AlgebraicValue::F64(0.5.into()), flags];
+    let (unhinted, unhinted_allocs) = to_unreserved_vec(&row);
+    assert_eq!(hinted, unhinted);
+    assert!(unhinted_allocs > allocs, "{unhinted_allocs} <= {allocs}");
+}
+
+#[test]
+fn primitive_array_hint_reserves_once() {
+    let numbers: Vec<u64> = (0..1000).collect();
+    let (hinted, allocs) = to_unreserved_vec(&AlgebraicValue::ArrayOf(numbers.clone()));
+    // One allocation for the length prefix, and at most one more for the elements.
+    assert!(allocs <= 2, "{allocs}");
+
+    // Elements of a sum or product type have no fixed size, so their array grows as encoded.
+    let values: Vec<AlgebraicValue> = numbers.into_iter().map(AlgebraicValue::U64).collect();
+    let (unhinted, unhinted_allocs) = to_unreserved_vec(&values);
+    assert_eq!(hinted, unhinted);
+    assert!(unhinted_allocs > allocs, "{unhinted_allocs} <= {allocs}");
+}
+
+#[test]
+fn megabyte_blob_decodes_with_one_copy() {
+    let blob: Vec<u8> = (0..1 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let value = AlgebraicValue::Bytes(blob.clone());
+    assert_eq!(value.as_bytes(), Some(&*blob));
+    let bytes = bsatn::to_vec(&value).unwrap();
+    assert_eq!(bytes, bsatn::to_vec(&blob).unwrap());
+
+    // Decoding copies the payload out of the buffer once, and does nothing else.
+    let (decoded, allocs) = count_allocs(|| bsatn::from_slice::<Vec<u8>>(&bytes).unwrap());
+    assert_eq!((&decoded, allocs), (&blob, 1));
+
+    // Decoding a value at the bytes type lands it in the one bytes representation.
+    let ty = AlgebraicType::bytes();
+    let ts = Typespace::default();
+    let (decoded, allocs) = count_allocs(|| {
+        WithTypespace::new(&ts, &ty)
+            .deserialize(bsatn::Deserializer::new(&mut &bytes[..]))
+            .unwrap()
+    });
+    assert_eq!(allocs, 1);
+    assert_eq!(decoded, value);
+    assert!(matches!(decoded, AlgebraicValue::Array(ArrayValue::U8(_))));
+
+    // Getting the bytes back out of the value moves them rather than copying them.
+    let ptr = decoded.as_bytes().unwrap().as_ptr();
+    let (moved, allocs) = count_allocs(|| Vec::<u8>::deserialize(ValueDeserializer::new(decoded)).unwrap());
+    assert_eq!((moved.as_ptr(), allocs), (ptr, 0));
+    assert_eq!(moved, blob);
+}
+
+#[test]
+fn packed_strings_decode_with_few_allocations() {
+    const STRINGS: usize = 100_000;
+    let strings = (0..STRINGS).map(|i| format!("message #{i}")).collect::<Vec<_>>();
+    let bytes = bsatn::to_vec(&strings).unwrap();
+    let ty = AlgebraicType::array(AlgebraicType::String);
+    let ts = Typespace::default();
+    let decode = |packed_strings| {
+        count_allocs(|| {
+            let mut reader = &*bytes;
+            let de = bsatn::Deserializer::new(&mut reader).with_packed_strings(packed_strings);
+            WithTypespace::new(&ts, &ty).deserialize(de).unwrap()
+        })
+    };
+
+    let (unpacked, unpacked_allocs) = decode(false);
+    let (packed, packed_allocs) = decode(true);
+    assert!(unpacked_allocs > STRINGS, "{unpacked_allocs}");
+    // The offsets are allocated up front, while the bytes grow as they're appended, by doubling.
+    assert!(packed_allocs < 32, "{packed_allocs}");
+    assert!(matches!(packed, AlgebraicValue::Array(ArrayValue::StringPacked(_))));
+    assert_eq!(packed, unpacked);
+    assert_eq!(bsatn::to_vec(&packed).unwrap(), bytes);
+}
+
+#[test]
+fn bytes_have_one_value() {
+    let hash = |v: &AlgebraicValue| {
+        let mut hasher = DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    };
+    let ty = AlgebraicType::bytes();
+    let ts = Typespace::default();
+    for blob in [vec![], vec![0, 1, 255]] {
+        let built = AlgebraicValue::Bytes(blob.clone());
+        let serialized = blob.serialize(ValueSerializer).unwrap();
+        let decoded = WithTypespace::new(&ts, &ty)
+            .deserialize(bsatn::Deserializer::new(&mut &*bsatn::to_vec(&blob).unwrap()))
+            .unwrap();
+        for value in [serialized, decoded] {
+            assert_eq!(value, built);
+            assert_eq!(hash(&value), hash(&built));
+        }
+    }
+}
+
+#[test]
+fn seeded_rows_decode_without_allocating_after_warmup() {
+    const ROWS: u32 = 1_000_000;
+    let ty = ProductType::from_iter([
+        AlgebraicType::U32,
+        AlgebraicType::I64,
+        AlgebraicType::String,
+        AlgebraicType::array(AlgebraicType::U16),
+        AlgebraicType::option(AlgebraicType::String),
+    ]);
+    // Rows of the same shape, i.e., with strings and arrays of the same lengths and sums of the same variants.
+    let row = |i: u32| {
+        let tag = AlgebraicValue::String(format!("tag {:04}", i % 10_000).into());
+        product![
+            i,
+            -i64::from(i),
+            format!("{i:08}"),
+            AlgebraicValue::ArrayOf(vec![i as u16; 3]),
+            AlgebraicValue::OptionSome(tag)
+        ]
+    };
+    let mut bytes = Vec::new();
+    for i in 0..ROWS {
+        bsatn::to_writer(&mut bytes, &row(i)).unwrap();
+    }
+
+    let ts = Typespace::default();
+    let ty = WithTypespace::new(&ts, &ty);
+    let mut reader = &*bytes;
+    let (_, fresh_allocs) = count_allocs(|| ty.deserialize(bsatn::Deserializer::new(&mut &*bytes)).unwrap());
+    assert!(fresh_allocs > 0);
+
+    // The first row allocates the storage all later rows reuse.
+    let mut seed = ValueSeed::new(ty, ProductValue::new(&[]));
+    bsatn::from_reader_seeded(&mut reader, &mut seed).unwrap();
+    let (_, allocs) = count_allocs(|| {
+        for _ in 1..ROWS {
+            bsatn::from_reader_seeded(&mut reader, &mut seed).unwrap();
+        }
+    });
+    assert_eq!(allocs, 0);
+    assert!(reader.is_empty());
+    // The reused storage holds the last row exactly.
+    assert_eq!(*seed.value(), row(ROWS - 1));
+}
@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, HashMap};
+
+use spacetimedb_sats::schema::codegen::generate_rust_structs;
+use spacetimedb_sats::{
+    bsatn, product, AlgebraicType, AlgebraicTypeRef, AlgebraicValue, ProductTypeElement, ProductValue, SumTypeVariant,
+    Typespace,
+};
+
+/// The code generated for [`typespace`], compiled into this test as is.
+mod generated {
+    include!("codegen/generated.rs");
+}
+
+/// Returns a typespace of players and the shapes they are, with the names of its types.
+fn typespace() -> (Typespace, HashMap<AlgebraicTypeRef, String>) {
+    let point = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::F32, "x"),
+        ProductTypeElement::new_named(AlgebraicType::F32, "y"),
+    ]);
+    let shape = AlgebraicType::sum(vec![
+        SumTypeVariant::new_named(AlgebraicType::F32, "Circle"),
+        SumTypeVariant::new_named(AlgebraicType::array(AlgebraicType::Ref(AlgebraicTypeRef(0))), "Polygon"),
+        SumTypeVariant::unit("Empty"),
+    ]);
+    let player = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::U64, "id"),
+        ProductTypeElement::new_named(AlgebraicType::String, "name"),
+        ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(0)), "position"),
+        ProductTypeElement::new_named(AlgebraicType::Ref(AlgebraicTypeRef(1)), "shape"),
+        ProductTypeElement::new_named(AlgebraicType::map(AlgebraicType::String, AlgebraicType::U32), "scores"),
+        ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::String), "nickname"),
+        ProductTypeElement::new_named(
+            AlgebraicType::product(vec![
+                ProductTypeElement::new_named(AlgebraicType::U32, "hp"),
+                ProductTypeElement::new_named(AlgebraicType::U32, "max_hp"),
+            ]),
+            "health",
+        ),
+        ProductTypeElement::new_named(AlgebraicType::bytes(), "type"),
+    ]);
+    // A recursive type, boxed where it refers to itself directly.
+    let tree = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::I32, "value"),
+        ProductTypeElement::new_named(
+            AlgebraicType::array(AlgebraicType::Ref(AlgebraicTypeRef(3))),
+            "children",
+        ),
+        ProductTypeElement::new_named(AlgebraicType::option(AlgebraicType::Ref(AlgebraicTypeRef(3))), "parent"),
+    ]);
+    let pair = AlgebraicType::product(vec![AlgebraicType::U8.into(), AlgebraicType::Bool.into()]);
+    let status = AlgebraicType::sum(vec![SumTypeVariant::unit("online"), SumTypeVariant::unit("away")]);
+    // Maps keyed by types that must then be ordered, with their floats totally ordered.
+    let cell = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::U16, "row"),
+        ProductTypeElement::new_named(AlgebraicType::U16, "col"),
+        ProductTypeElement::new_named(AlgebraicType::F32, "weight"),
+    ]);
+    let corner = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::I32, "x"),
+        ProductTypeElement::new_named(AlgebraicType::I32, "y"),
+    ]);
+    let grid = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(
+            AlgebraicType::map(AlgebraicType::Ref(AlgebraicTypeRef(7)), AlgebraicType::String),
+            "cells",
+        ),
+        ProductTypeElement::new_named(AlgebraicType::map(AlgebraicType::F64, AlgebraicType::U8), "thresholds"),
+        ProductTypeElement::new_named(AlgebraicType::map(corner, AlgebraicType::Bool), "corners"),
+    ]);
+    let ts = Typespace::new(vec![
+        point,
+        shape,
+        player,
+        tree,
+        pair,
+        status,
+        AlgebraicType::U16,
+        cell,
+        grid,
+    ]);
+    let names = ["Point", "Shape", "Player", "Tree"]
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| (AlgebraicTypeRef(i as u32), name.to_owned()))
+        .chain([(6, "Level"), (7, "Cell"), (8, "Grid")].map(|(i, name)| (AlgebraicTypeRef(i), name.to_owned())))
+        .collect();
+    (ts, names)
+}
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let (ts, names) = typespace();
+    let code = generate_rust_structs(&ts, &names);
+    assert_eq!(
+        code,
+        include_str!("codegen/generated.rs"),
+        "regenerate `tests/codegen/generated.rs` as printed:\n{code}"
+    );
+}
+
+#[test]
+fn names_registered_in_the_typespace_are_used() {
+    let (mut ts, names) = typespace();
+    for (&r, name) in &names {
+        ts.register_name(name, r).unwrap();
+    }
+    let code = generate_rust_structs(&ts, &HashMap::new());
+    assert_eq!(code, include_str!("codegen/generated.rs"));
+}
+
+#[test]
+fn generated_structs_encode_like_values() {
+    let point = |x: f32, y: f32| product![x, y];
+    let polygon = vec![point(0.0, 0.0), point(1.0, 0.5)];
+    let row: ProductValue = product![
+        7u64,
+        "ana".to_owned(),
+        point(1.5, -2.0),
+        AlgebraicValue::sum(1, AlgebraicValue::ArrayOf(polygon)),
+        AlgebraicValue::map([(AlgebraicValue::from("wins"), AlgebraicValue::U32(3))].into()),
+        AlgebraicValue::OptionSome(AlgebraicValue::from("an")),
+        product![80u32, 100u32],
+        AlgebraicValue::Bytes(vec![1, 2])
+    ];
+    let player = generated::Player {
+        id: 7,
+        name: "ana".into(),
+        position: generated::Point { x: 1.5, y: -2.0 },
+        shape: generated::Shape::Polygon(vec![
+            generated::Point { x: 0.0, y: 0.0 },
+            generated::Point { x: 1.0, y: 0.5 },
+        ]),
+        scores: BTreeMap::from([("wins".to_owned(), 3)]),
+        nickname: Some("an".into()),
+        health: generated::PlayerHealth { hp: 80, max_hp: 100 },
+        r#type: vec![1, 2],
+    };
+    let bytes = bsatn::to_vec(&row).unwrap();
+    assert_eq!(bsatn::to_vec(&player).unwrap(), bytes);
+    let decoded: generated::Player = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(bsatn::to_vec(&decoded).unwrap(), bytes);
+    assert_eq!(decoded.health.max_hp, 100);
+
+    let leaf = |value: i32| {
+        product![
+            value,
+            AlgebraicValue::ArrayOf(Vec::<ProductValue>::new()),
+            AlgebraicValue::OptionNone()
+        ]
+    };
+    let tree = product![
+        1i32,
+        AlgebraicValue::ArrayOf(vec![leaf(2)]),
+        AlgebraicValue::OptionSome(leaf(0).into())
+    ];
+    let leaf = |value| generated::Tree {
+        value,
+        children: Vec::new(),
+        parent: None,
+    };
+    let generated_tree = generated::Tree {
+        value: 1,
+        children: vec![leaf(2)],
+        parent: Some(Box::new(leaf(0))),
+    };
+    let bytes = bsatn::to_vec(&tree).unwrap();
+    assert_eq!(bsatn::to_vec(&generated_tree).unwrap(), bytes);
+    let decoded: generated::Tree = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(bsatn::to_vec(&decoded).unwrap(), bytes);
+
+    let pair = generated::Type4 {
+        field_0: 9,
+        field_1: true,
+    };
+    assert_eq!(
+        bsatn::to_vec(&pair).unwrap(),
+        bsatn::to_vec(&product![9u8, true]).unwrap()
+    );
+    let away = AlgebraicValue::sum(1, AlgebraicValue::UNIT);
+    assert_eq!(
+        bsatn::to_vec(&generated::Type5::away).unwrap(),
+        bsatn::to_vec(&away).unwrap()
+    );
+    let level: generated::Level = 3;
+    assert_eq!(bsatn::to_vec(&level).unwrap(), [3, 0]);
+
+    let cell = |row: u16, weight: f32| product![row, 0u16, weight];
+    let grid = product![
+        AlgebraicValue::map(
+            [cell(2, 0.5), cell(1, 1.5)]
+                .map(|cell| (cell.into(), AlgebraicValue::from("x")))
+                .into()
+        ),
+        AlgebraicValue::map([(AlgebraicValue::F64((-0.5).into()), AlgebraicValue::U8(1))].into()),
+        AlgebraicValue::map([(product![-1i32, 1i32].into(), AlgebraicValue::Bool(true))].into())
+    ];
+    let cell = |row, weight: f32| generated::Cell {
+        row,
+        col: 0,
+        weight: weight.into(),
+    };
+    let generated_grid = generated::Grid {
+        cells: [cell(2, 0.5), cell(1, 1.5)].map(|cell| (cell, "x".to_owned())).into(),
+        thresholds: BTreeMap::from([((-0.5).into(), 1)]),
+        corners: BTreeMap::from([(generated::GridCornersKey { x: -1, y: 1 }, true)]),
+    };
+    let bytes = bsatn::to_vec(&grid).unwrap();
+    assert_eq!(bsatn::to_vec(&generated_grid).unwrap(), bytes);
+    let decoded: generated::Grid = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(decoded.cells.keys().next(), Some(&cell(1, 1.5)));
+    assert_eq!(bsatn::to_vec(&decoded).unwrap(), bytes);
+}
@@ -5,8 +5,8 @@ use proptest::proptest;
 use spacetimedb_sats::buffer::DecodeError;
 use spacetimedb_sats::builtin_value::{F32, F64};
 use spacetimedb_sats::{
-    meta_type::MetaType, product, AlgebraicType, AlgebraicValue, BuiltinValue, ProductType, ProductTypeElement,
-    ProductValue,
+    bsatn, meta_type::MetaType, product, AlgebraicType, AlgebraicTypeRef, AlgebraicValue, ArrayValue, BuiltinType,
+    ProductType, ProductTypeElement, ProductValue, SumTypeVariant, Typespace, WithTypespace,
 };
 
 #[test]
@@ -72,7 +72,7 @@ fn builtin_values() -> impl Strategy<Value = AlgebraicValue> {
             let x = x.into_bytes();
             AlgebraicValue::Bytes(x)
         }),
-        ".*".prop_map(AlgebraicValue::String),
+        ".*".prop_map(AlgebraicValue::from),
     ]
 }
 
@@ -93,8 +93,7 @@ fn algebraic_values() -> impl Strategy<Value = AlgebraicValue> {
                         AlgebraicValue::OptionNone()
                     }
                 }),
-                prop::collection::btree_map(inner.clone(), inner.clone(), 1..2)
-                    .prop_map(|val| { BuiltinValue::Map { val }.into() }),
+                prop::collection::btree_map(inner.clone(), inner.clone(), 1..2).prop_map(AlgebraicValue::Map),
                 prop::collection::vec(inner, 0..10).prop_map(|val| {
                     let product = ProductValue::from_iter(val.into_iter());
                     AlgebraicValue::Product(product)
@@ -134,3 +133,108 @@ proptest! {
         prop_assert_eq!(original,parsed, "Original vs Parsed");
     }
 }
+
+proptest! {
+    #[test]
+    fn arrays_specialize_back_from_values(val in array_values()) {
+        let AlgebraicType::Builtin(BuiltinType::Array(ty)) = val.type_of() else {
+            panic!("not an array type")
+        };
+        let arr = val.into_array().unwrap();
+        let specialized = ArrayValue::try_from_values(arr.to_values(), Some(&ty.elem_ty));
+        prop_assert_eq!(specialized, Ok(arr.clone()));
+        // Without the type, only non-empty arrays know their kind.
+        if !arr.is_empty() {
+            prop_assert_eq!(ArrayValue::try_from_values(arr.clone().into_values(), None), Ok(arr));
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn string_values_behave_like_strings(a in ".*", b in ".*") {
+        let (va, vb) = (AlgebraicValue::from(a.clone()), AlgebraicValue::from(b.clone()));
+        prop_assert_eq!(va.as_string(), Some(&*a));
+        prop_assert_eq!(va.cmp(&vb), a.cmp(&b));
+        prop_assert_eq!(va.clone().into_string(), Ok(a.clone()));
+        prop_assert_eq!(AlgebraicValue::from(&*a), va.clone());
+
+        // The encoding is that of a `String`, both ways.
+        let bytes = bsatn::to_vec(&va).unwrap();
+        prop_assert_eq!(&bytes, &bsatn::to_vec(&a).unwrap());
+        prop_assert_eq!(AlgebraicValue::decode(&AlgebraicType::String, &mut &*bytes).unwrap(), va.clone());
+
+        // Decoding into an existing string value.
+        let mut slot = vb.clone();
+        bsatn::decode_into(&bytes, &AlgebraicType::String, &Typespace::default(), &mut slot).unwrap();
+        prop_assert_eq!(&slot, &va);
+
+        // Moving in and out of a string array.
+        let mut array = ArrayValue::from(vec![b]);
+        prop_assert!(array.push(va.clone(), None).is_ok());
+        let elems: Vec<_> = array.into_iter().collect();
+        prop_assert_eq!(elems, vec![vb, va]);
+    }
+}
+
+/// Returns types whose BSATN encodings sort, byte by byte, like their values,
+/// i.e., `bool`s, `u8`s, and products and sums of them.
+fn key_safe_types() -> impl Strategy<Value = AlgebraicType> {
+    prop_oneof![Just(AlgebraicType::Bool), Just(AlgebraicType::U8)].prop_recursive(4, 32, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(|elems| {
+                let elems = elems.into_iter().enumerate();
+                AlgebraicType::product(
+                    elems
+                        .map(|(i, ty)| ProductTypeElement::new_named(ty, format!("f{i}")))
+                        .collect(),
+                )
+            }),
+            prop::collection::vec(inner, 1..4).prop_map(|vars| {
+                let vars = vars.into_iter().enumerate();
+                AlgebraicType::sum(
+                    vars.map(|(i, ty)| SumTypeVariant::new_named(ty, format!("v{i}")))
+                        .collect(),
+                )
+            }),
+        ]
+    })
+}
+
+/// Returns values of the key-safe type `ty`.
+fn values_of(ty: &AlgebraicType) -> BoxedStrategy<AlgebraicValue> {
+    match ty {
+        AlgebraicType::Product(ty) => (ty.elements.iter())
+            .map(|el| values_of(&el.algebraic_type))
+            .collect::<Vec<_>>()
+            .prop_map(AlgebraicValue::product)
+            .boxed(),
+        AlgebraicType::Sum(ty) => {
+            let vars = ty.variants.iter().enumerate().map(|(tag, var)| {
+                let tag = tag as u8;
+                values_of(&var.algebraic_type).prop_map(move |v| AlgebraicValue::sum(tag, v))
+            });
+            prop::strategy::Union::new(vars).boxed()
+        }
+        &AlgebraicType::Bool => any::<bool>().prop_map(AlgebraicValue::Bool).boxed(),
+        &AlgebraicType::U8 => any::<u8>().prop_map(AlgebraicValue::U8).boxed(),
+        _ => unreachable!("not a key-safe type"),
+    }
+}
+
+proptest! {
+    #[test]
+    fn cmp_typed_is_bsatn_order_for_key_safe_types(
+        (ty, a, b) in key_safe_types().prop_flat_map(|ty| (Just(ty.clone()), values_of(&ty), values_of(&ty)))
+    ) {
+        // One side refers to the type through the typespace, the other has it inline.
+        let ts = Typespace::new(vec![ty.clone()]);
+        let by_ref = AlgebraicType::Ref(AlgebraicTypeRef(0));
+        let a_typed = WithTypespace::new(&ts, &by_ref).with_value(&a);
+        let b_typed = WithTypespace::new(&ts, &ty).with_value(&b);
+
+        let bytes_ord = bsatn::to_vec(&a).unwrap().cmp(&bsatn::to_vec(&b).unwrap());
+        prop_assert_eq!(a_typed.cmp_typed(&b_typed), Ok(bytes_ord));
+        prop_assert_eq!(a_typed.eq_typed(&b_typed), Ok(a == b));
+    }
+}
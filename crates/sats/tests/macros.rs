@@ -0,0 +1,5 @@
+#[test]
+fn impl_deserialize_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/impl_deserialize_*.rs");
+}
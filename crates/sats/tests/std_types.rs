@@ -0,0 +1,679 @@
+use std::collections::{BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use spacetimedb_sats::algebraic_value::de::{ValueDeserializeError, ValueDeserializer};
+use spacetimedb_sats::algebraic_value::ser::ValueSerializer;
+use spacetimedb_sats::bsatn::ser::BsatnError;
+use spacetimedb_sats::buffer::{ErrorKind, PathSegment};
+use spacetimedb_sats::de::Deserialize;
+use spacetimedb_sats::ser::{InvalidPath, InvalidPathReason, LossyPath, Serialize};
+use spacetimedb_sats::{
+    bsatn, de::DeserializeOwned, AlgebraicType, AlgebraicValue, ProductTypeElement, SumTypeVariant,
+};
+
+/// Encodes `val` in BSATN, checks that it decodes back to `val`, and returns the encoding.
+#[track_caller]
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(val: &T) -> Vec<u8> {
+    let bytes = bsatn::to_vec(val).unwrap();
+    let decoded: T = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(&decoded, val);
+    bytes
+}
+
+#[test]
+fn vec_deque_encodes_like_vec() {
+    let vec = vec![1u32, 2, 3, 4];
+    assert_eq!(round_trip(&VecDeque::from(vec.clone())), round_trip(&vec));
+
+    // Wrap the ring buffer around so that its elements are stored in two halves.
+    let mut deque = VecDeque::with_capacity(4);
+    deque.extend([0u8, 0, 1, 2]);
+    deque.drain(..2);
+    deque.extend([3, 4]);
+    assert!(!deque.as_slices().1.is_empty());
+    assert_eq!(round_trip(&deque), round_trip(&vec![1u8, 2, 3, 4]));
+
+    let strings = VecDeque::from(vec!["a".to_owned(), "b".to_owned()]);
+    assert_eq!(round_trip(&strings), round_trip(&Vec::from(strings.clone())));
+}
+
+#[test]
+fn linked_list_encodes_like_vec() {
+    for len in [0, 1, 5] {
+        let vec = (0..len).map(|x| x * 3).collect::<Vec<u64>>();
+        let list = vec.iter().copied().collect::<LinkedList<_>>();
+        assert_eq!(round_trip(&list), round_trip(&vec));
+    }
+    let strings = LinkedList::from(["a".to_owned(), "bc".to_owned()]);
+    assert_eq!(round_trip(&strings), round_trip(&vec!["a".to_owned(), "bc".to_owned()]));
+}
+
+#[test]
+fn binary_heap_encodes_sorted() {
+    let heap = |elems: &[i32]| elems.iter().copied().collect::<BinaryHeap<_>>();
+    let (a, b) = (heap(&[5, -1, 3, 3, 0]), heap(&[3, 0, 5, 3, -1]));
+    let bytes = bsatn::to_vec(&a).unwrap();
+    assert_eq!(bytes, bsatn::to_vec(&b).unwrap());
+    assert_eq!(bytes, bsatn::to_vec(&vec![-1, 0, 3, 3, 5]).unwrap());
+
+    let decoded: BinaryHeap<i32> = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(decoded.into_sorted_vec(), a.into_sorted_vec());
+    let strings = ["pear", "apple", "fig"]
+        .map(String::from)
+        .into_iter()
+        .collect::<BinaryHeap<_>>();
+    let decoded: BinaryHeap<String> = bsatn::from_slice(&bsatn::to_vec(&strings).unwrap()).unwrap();
+    assert_eq!(decoded.peek().map(String::as_str), Some("pear"));
+    assert_eq!(decoded.into_sorted_vec(), strings.into_sorted_vec());
+}
+
+#[test]
+fn btree_set_encodes_sorted() {
+    let set = BTreeSet::from([5i32, -1, 3, 0]);
+    assert_eq!(round_trip(&set), bsatn::to_vec(&vec![-1, 0, 3, 5]).unwrap());
+    let strings = BTreeSet::from(["pear", "apple", "fig"].map(String::from));
+    assert_eq!(round_trip(&strings), bsatn::to_vec(&["apple", "fig", "pear"]).unwrap());
+    round_trip(&BTreeSet::<u64>::new());
+
+    let bytes = bsatn::to_vec(&vec![1i32, 3, 1]).unwrap();
+    let err = bsatn::from_slice::<BTreeSet<i32>>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::DuplicateElement), "{err}");
+    assert!(err.path().eq([&PathSegment::Element(2)]));
+    assert_eq!(err.to_string(), "duplicate element of a set in `[2]`");
+    // Other formats report duplicates too.
+    let value = vec![7u8, 7].serialize(ValueSerializer).unwrap();
+    let err = BTreeSet::<u8>::deserialize(ValueDeserializer::new(value)).unwrap_err();
+    assert!(
+        matches!(&err, ValueDeserializeError::Custom(msg) if msg == "duplicate element at index 1 of a set"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn wrapping_encodes_like_its_number() {
+    use std::num::Wrapping;
+
+    assert_eq!(round_trip(&Wrapping(255u8)), round_trip(&255u8));
+    assert_eq!(round_trip(&Wrapping(u16::MAX)), round_trip(&u16::MAX));
+    assert_eq!(round_trip(&Wrapping(-1i32)), round_trip(&-1i32));
+    assert_eq!(round_trip(&Wrapping(u64::MAX)), round_trip(&u64::MAX));
+    assert_eq!(round_trip(&Wrapping(i128::MIN)), round_trip(&i128::MIN));
+    // Including within arrays.
+    let numbers = vec![Wrapping(1i8), Wrapping(-2)];
+    assert_eq!(round_trip(&numbers), round_trip(&vec![1i8, -2]));
+    let ticks = VecDeque::from([Wrapping(u32::MAX), Wrapping(0), Wrapping(1)]);
+    assert_eq!(round_trip(&ticks), round_trip(&vec![u32::MAX, 0, 1]));
+}
+
+#[test]
+fn wrapping_bytes_serialize_as_bytes() {
+    use spacetimedb_sats::ser::trace_serializer::{trace, TraceEvent};
+    use std::num::Wrapping;
+
+    let bytes = vec![Wrapping(1u8), Wrapping(2), Wrapping(255)];
+    assert_eq!(round_trip(&bytes), round_trip(&vec![1u8, 2, 255]));
+    let events = trace(&bytes, bsatn::Serializer::new(&mut Vec::new())).unwrap().1;
+    assert_eq!(events, [TraceEvent::SerializeBytes(vec![1, 2, 255])]);
+}
+
+#[test]
+fn cells_encode_like_their_contents() {
+    use std::cell::{Cell, RefCell};
+
+    assert_eq!(round_trip(&Cell::new(7u32)), round_trip(&7u32));
+    assert_eq!(round_trip(&RefCell::new(7u32)), round_trip(&7u32));
+    let strings = RefCell::new(vec!["a".to_owned(), "b".to_owned()]);
+    assert_eq!(round_trip(&strings), round_trip(&*strings.borrow()));
+    // A shared borrow can be read from at the same time.
+    let _reading = strings.borrow();
+    round_trip(&strings);
+}
+
+#[test]
+fn mutably_borrowed_ref_cell_is_an_error() {
+    use std::cell::RefCell;
+
+    let cell = RefCell::new(7u32);
+    let writing = cell.borrow_mut();
+    let err = bsatn::to_vec(&cell).unwrap_err();
+    assert!(err.to_string().contains("already mutably borrowed"), "{err}");
+    drop(writing);
+    assert_eq!(bsatn::to_vec(&cell).unwrap(), bsatn::to_vec(&7u32).unwrap());
+}
+
+#[test]
+fn locks_encode_like_their_contents() {
+    use std::sync::{Mutex, RwLock};
+
+    let bytes = bsatn::to_vec(&Mutex::new(7u64)).unwrap();
+    assert_eq!(bytes, round_trip(&7u64));
+    let decoded: Mutex<u64> = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(decoded.into_inner().unwrap(), 7);
+
+    let strings = vec!["a".to_owned(), "b".to_owned()];
+    let bytes = bsatn::to_vec(&RwLock::new(strings.clone())).unwrap();
+    assert_eq!(bytes, round_trip(&strings));
+    let decoded: RwLock<Vec<String>> = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(decoded.into_inner().unwrap(), strings);
+}
+
+#[test]
+fn held_locks_are_errors() {
+    use std::sync::{mpsc, Mutex, RwLock};
+    use std::thread;
+
+    let mutex = Mutex::new(7u64);
+    let lock = RwLock::new(7u64);
+    let (locked, wait_for_lock) = mpsc::channel();
+    let (checked, wait_for_check) = mpsc::channel();
+    thread::scope(|s| {
+        let (mutex, lock) = (&mutex, &lock);
+        s.spawn(move || {
+            let _guard = mutex.lock().unwrap();
+            let _writing = lock.write().unwrap();
+            locked.send(()).unwrap();
+            wait_for_check.recv().unwrap();
+        });
+        wait_for_lock.recv().unwrap();
+        let err = bsatn::to_vec(&mutex).unwrap_err();
+        assert!(err.to_string().contains("would block"), "{err}");
+        let err = bsatn::to_vec(&lock).unwrap_err();
+        assert!(err.to_string().contains("would block"), "{err}");
+        checked.send(()).unwrap();
+    });
+    assert_eq!(bsatn::to_vec(&mutex).unwrap(), bsatn::to_vec(&7u64).unwrap());
+
+    // A read lock held elsewhere doesn't stop the contents from being read.
+    let _reading = lock.read().unwrap();
+    assert_eq!(bsatn::to_vec(&lock).unwrap(), bsatn::to_vec(&7u64).unwrap());
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn small_vec_encodes_like_vec() {
+    use smallvec::SmallVec;
+
+    for len in [0, 3, 4, 5, 9] {
+        let vec = (0..len).map(|x| x * 7).collect::<Vec<u32>>();
+        let small = SmallVec::<[u32; 4]>::from_vec(vec.clone());
+        assert_eq!(small.spilled(), len > 4);
+        assert_eq!(round_trip(&small), round_trip(&vec));
+
+        // The decoded value stays inline when it fits.
+        let decoded: SmallVec<[u32; 4]> = bsatn::from_slice(&bsatn::to_vec(&vec).unwrap()).unwrap();
+        assert_eq!(decoded.spilled(), len > 4);
+    }
+
+    let bytes = SmallVec::<[u8; 4]>::from_slice(b"abcdef");
+    assert_eq!(round_trip(&bytes), round_trip(&b"abcdef".to_vec()));
+}
+
+#[test]
+fn array_vec_encodes_like_vec() {
+    use arrayvec::ArrayVec;
+
+    let vec = vec![1u32, 2, 3];
+    let array_vec = ArrayVec::<u32, 4>::from_iter(vec.iter().copied());
+    assert_eq!(round_trip(&array_vec), round_trip(&vec));
+
+    let bytes = ArrayVec::<u8, 4>::from_iter(*b"abc");
+    assert_eq!(round_trip(&bytes), round_trip(&b"abc".to_vec()));
+
+    let too_many = bsatn::to_vec(&vec![1u32, 2, 3, 4, 5]).unwrap();
+    let err = bsatn::from_slice::<ArrayVec<u32, 4>>(&too_many).unwrap_err();
+    assert!(err.to_string().contains("insufficient capacity"), "{err}");
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn index_maps_keep_insertion_order() {
+    use indexmap::{IndexMap, IndexSet};
+    use std::collections::BTreeMap;
+
+    let entries = [("b".to_owned(), 1u8), ("c".to_owned(), 2), ("a".to_owned(), 3)];
+    let map = IndexMap::<_, _>::from_iter(entries.clone());
+    let bytes = round_trip(&map);
+    let decoded: IndexMap<String, u8> = bsatn::from_slice(&bytes).unwrap();
+    assert!(decoded.keys().eq(["b", "c", "a"]));
+    // A `BTreeMap` of the same entries would be encoded in sorted order instead.
+    let sorted = BTreeMap::from_iter(entries);
+    assert_ne!(bsatn::to_vec(&sorted).unwrap(), bytes);
+    let decoded: BTreeMap<String, u8> = bsatn::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, sorted);
+
+    let set = IndexSet::<_>::from_iter([3u16, 1, 2]);
+    let bytes = round_trip(&set);
+    assert_eq!(bytes, bsatn::to_vec(&vec![3u16, 1, 2]).unwrap());
+    // Repeated elements are dropped, keeping the first.
+    let with_repeats = bsatn::to_vec(&vec![3u16, 1, 3, 2, 1]).unwrap();
+    let decoded: IndexSet<u16> = bsatn::from_slice(&with_repeats).unwrap();
+    assert!(decoded.iter().eq(&[3, 1, 2]));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_timestamps_are_millis() {
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let at = |secs, nanos| Utc.timestamp_opt(secs, nanos).unwrap();
+    assert_eq!(round_trip(&at(0, 0)), round_trip(&0i64));
+    // Before 1970, the milliseconds are negative.
+    assert_eq!(round_trip(&at(-86_400, 250_000_000)), round_trip(&-86_399_750i64));
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let now = at(now.as_secs() as i64, now.subsec_millis() * 1_000_000);
+    round_trip(&now);
+
+    // Precision finer than milliseconds is truncated, and only that.
+    let precise = at(1_700_000_000, 123_456_789);
+    let decoded: DateTime<Utc> = bsatn::from_slice(&bsatn::to_vec(&precise).unwrap()).unwrap();
+    assert_eq!(decoded, at(1_700_000_000, 123_000_000));
+    let decoded: DateTime<Utc> = bsatn::from_slice(&bsatn::to_vec(&at(-1, 999_999_999)).unwrap()).unwrap();
+    assert_eq!(decoded, at(-1, 999_000_000));
+
+    let err = bsatn::from_slice::<DateTime<Utc>>(&bsatn::to_vec(&i64::MAX).unwrap()).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_dates_are_days() {
+    use chrono::NaiveDate;
+
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    assert_eq!(round_trip(&date(1970, 1, 1)), round_trip(&0i32));
+    assert_eq!(round_trip(&date(1970, 2, 1)), round_trip(&31i32));
+    assert_eq!(round_trip(&date(1969, 12, 31)), round_trip(&-1i32));
+    round_trip(&NaiveDate::MIN);
+    round_trip(&NaiveDate::MAX);
+
+    let err = bsatn::from_slice::<NaiveDate>(&bsatn::to_vec(&i32::MAX).unwrap()).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn nalgebra_types_are_float_arrays() {
+    use nalgebra::{Matrix4, Vector3};
+    use spacetimedb_sats::{builtin_value::F32, ArrayValue};
+
+    let v = Vector3::new(1.0f32, 2.0, 3.0);
+    let floats = [1.0, 2.0, 3.0].map(F32::from).to_vec();
+    assert_eq!(
+        v.serialize(ValueSerializer).unwrap(),
+        AlgebraicValue::Array(ArrayValue::F32(floats))
+    );
+    assert_eq!(round_trip(&v), bsatn::to_vec(&[1.0f32, 2.0, 3.0]).unwrap());
+
+    // The components are column by column.
+    let m = Matrix4::from_fn(|row, col| (row * 4 + col) as f32 + 0.25);
+    let columns = std::array::from_fn::<f32, 16, _>(|i| m[(i % 4, i / 4)]);
+    assert_eq!(bsatn::to_vec(&m).unwrap(), bsatn::to_vec(&columns).unwrap());
+    let decoded: Matrix4<f32> = bsatn::from_slice(&bsatn::to_vec(&m).unwrap()).unwrap();
+    assert!(decoded.iter().zip(&m).all(|(a, b)| (a - b).abs() <= f32::EPSILON));
+
+    // Arrays of another length are not vectors or matrices.
+    assert!(bsatn::from_slice::<Vector3<f32>>(&bsatn::to_vec(&[1.0f32; 4]).unwrap()).is_err());
+    assert!(bsatn::from_slice::<Matrix4<f32>>(&bsatn::to_vec(&[1.0f32; 9]).unwrap()).is_err());
+}
+
+#[test]
+fn paths_encode_like_strings() {
+    for path in [
+        "/usr/share/assets/tree.png",
+        "assets/../tree.png",
+        "",
+        "données/木/🌲.png",
+    ] {
+        assert_eq!(round_trip(&PathBuf::from(path)), round_trip(&path.to_owned()));
+        assert_eq!(bsatn::to_vec(Path::new(path)).unwrap(), bsatn::to_vec(path).unwrap());
+    }
+
+    // A null byte is not silently cut off, either way.
+    let null_byte = InvalidPath {
+        path: "tree\0.png".into(),
+        reason: InvalidPathReason::NullByte,
+    };
+    let err = bsatn::to_vec(Path::new("tree\0.png")).unwrap_err();
+    assert!(matches!(&err, BsatnError::InvalidPath(e) if *e == null_byte), "{err:?}");
+    assert_eq!(err.to_string(), r#"invalid path "tree\0.png": contains a null byte"#);
+    let bytes = bsatn::to_vec("tree\0.png").unwrap();
+    let err = bsatn::from_slice::<PathBuf>(&bytes).unwrap_err();
+    assert!(
+        matches!(err.kind(), ErrorKind::InvalidPath(e) if *e == null_byte),
+        "{err:?}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths_are_invalid() {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    let not_utf8 = InvalidPath {
+        path: "tree\u{fffd}.png".into(),
+        reason: InvalidPathReason::NotUtf8,
+    };
+    let err = bsatn::to_vec(Path::new(OsStr::from_bytes(b"tree\xff.png"))).unwrap_err();
+    assert!(matches!(&err, BsatnError::InvalidPath(e) if *e == not_utf8), "{err:?}");
+    assert_eq!(err.to_string(), "invalid path \"tree\u{fffd}.png\": not valid UTF-8");
+    let err = bsatn::to_vec(&OsString::from_vec(b"tree\xff.png".to_vec())).unwrap_err();
+    assert!(matches!(&err, BsatnError::InvalidPath(e) if *e == not_utf8), "{err:?}");
+
+    // Unless asked to be lossy.
+    let lossy = LossyPath(PathBuf::from(OsStr::from_bytes(b"tree\xff.png")));
+    let bytes = bsatn::to_vec(&lossy).unwrap();
+    assert_eq!(bytes, bsatn::to_vec("tree\u{fffd}.png").unwrap());
+    let decoded = bsatn::from_slice::<LossyPath>(&bytes).unwrap();
+    assert_eq!(decoded.0, Path::new("tree\u{fffd}.png"));
+}
+
+#[cfg(windows)]
+#[test]
+fn non_utf8_paths_are_invalid() {
+    use std::os::windows::ffi::OsStringExt;
+
+    // An unpaired surrogate is valid in a Windows path, but not in UTF-8.
+    let wide = [u16::from(b't'), 0xd800, u16::from(b'x')];
+    let err = bsatn::to_vec(&OsString::from_wide(&wide)).unwrap_err();
+    assert!(
+        matches!(&err, BsatnError::InvalidPath(e) if e.reason == InvalidPathReason::NotUtf8),
+        "{err:?}"
+    );
+    assert_eq!(err.to_string(), "invalid path \"t\u{fffd}x\": not valid UTF-8");
+
+    // Unless asked to be lossy.
+    let lossy = LossyPath(OsString::from_wide(&wide).into());
+    let bytes = bsatn::to_vec(&lossy).unwrap();
+    assert_eq!(bytes, bsatn::to_vec("t\u{fffd}x").unwrap());
+    assert_eq!(
+        bsatn::from_slice::<LossyPath>(&bytes).unwrap().0,
+        PathBuf::from("t\u{fffd}x")
+    );
+}
+
+#[test]
+fn os_strings_and_lossy_paths_encode_like_strings() {
+    for s in [
+        "/usr/share/assets/tree.png",
+        r"C:\assets\tree.png",
+        "",
+        "données/木/🌲.png",
+    ] {
+        let expected = bsatn::to_vec(s).unwrap();
+        assert_eq!(round_trip(&OsString::from(s)), expected);
+        assert_eq!(bsatn::to_vec(OsStr::new(s)).unwrap(), expected);
+        assert_eq!(round_trip(&LossyPath(PathBuf::from(s))), expected);
+    }
+
+    // Even a lossy path can't have a null byte.
+    let err = bsatn::to_vec(&LossyPath(PathBuf::from("tree\0.png"))).unwrap_err();
+    assert!(err.to_string().contains("contains a null byte"), "{err}");
+    let bytes = bsatn::to_vec("tree\0.png").unwrap();
+    assert!(bsatn::from_slice::<OsString>(&bytes).is_err());
+    assert!(bsatn::from_slice::<LossyPath>(&bytes).is_err());
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn bytes_encode_like_byte_slices() {
+    use bytes::{Bytes, BytesMut};
+
+    let expected = bsatn::to_vec(b"hello" as &[u8]).unwrap();
+    assert_eq!(round_trip(&Bytes::from_static(b"hello")), expected);
+    assert_eq!(round_trip(&BytesMut::from(&b"hello"[..])), expected);
+    assert_eq!(round_trip(&Bytes::new()), round_trip(&Vec::<u8>::new()));
+}
+
+#[test]
+fn boxed_slices_encode_like_vecs() {
+    let string = "boxed \u{1f4e6}".to_owned();
+    assert_eq!(round_trip(&string.clone().into_boxed_str()), round_trip(&string));
+
+    let numbers = vec![1u32, 2, 3];
+    assert_eq!(round_trip(&numbers.clone().into_boxed_slice()), round_trip(&numbers));
+
+    // `Box<[u8]>` takes the same byte string path as `Vec<u8>`.
+    let bytes = vec![0xdeu8, 0xad, 0xbe, 0xef];
+    assert_eq!(round_trip(&bytes.clone().into_boxed_slice()), round_trip(&bytes));
+}
+
+/// A derived struct wrapping a fixed-size byte array.
+#[derive(spacetimedb_sats::ser::Serialize, spacetimedb_sats::de::Deserialize, Debug, PartialEq)]
+#[sats(crate = spacetimedb_sats)]
+struct Digest {
+    bytes: [u8; 16],
+}
+
+#[test]
+fn byte_arrays_serialize_as_bytes() {
+    use spacetimedb_sats::ser::trace_serializer::{trace, TraceEvent};
+
+    let array: [u8; 16] = std::array::from_fn(|i| i as u8);
+    assert_eq!(round_trip(&array), bsatn::to_vec(&array[..]).unwrap());
+    assert_eq!(round_trip(&[0u8; 0]), bsatn::to_vec(&[] as &[u8]).unwrap());
+    assert_eq!(round_trip(&Box::new(array)), round_trip(&array));
+
+    // Each shape makes a single `serialize_bytes` call, even within a derived struct.
+    fn calls<T: Serialize + ?Sized>(value: &T) -> Vec<TraceEvent> {
+        trace(value, bsatn::Serializer::new(&mut Vec::new())).unwrap().1
+    }
+    let bytes = [TraceEvent::SerializeBytes(array.to_vec())];
+    assert_eq!(calls(&array), bytes);
+    assert_eq!(calls(&&array), bytes);
+    assert_eq!(calls(&Box::new(array)), bytes);
+    let digest = Digest { bytes: array };
+    let events = calls(&digest);
+    assert_eq!(events.iter().filter(|e| **e == bytes[0]).count(), 1, "{events:?}");
+    assert!(
+        !events.iter().any(|e| matches!(e, TraceEvent::BeginArray(_))),
+        "{events:?}"
+    );
+    round_trip(&digest);
+
+    // `&[u8; N]` borrows straight from the input.
+    let encoded = bsatn::to_vec(&array).unwrap();
+    let borrowed: &[u8; 16] = bsatn::from_slice(&encoded).unwrap();
+    assert_eq!(borrowed, &array);
+}
+
+#[test]
+fn byte_arrays_check_their_length() {
+    let encoded = bsatn::to_vec(&[7u8; 15][..]).unwrap();
+    let err = bsatn::from_slice::<[u8; 16]>(&encoded).unwrap_err();
+    assert_eq!(err.to_string(), "expected an array of 16 bytes, got 15 bytes");
+    let err = bsatn::from_slice::<&[u8; 16]>(&encoded).unwrap_err();
+    assert_eq!(err.to_string(), "expected an array of 16 bytes, got 15 bytes");
+    let err = bsatn::from_slice::<[u8; 0]>(&encoded).unwrap_err();
+    assert_eq!(err.to_string(), "expected an array of 0 bytes, got 15 bytes");
+    let err = bsatn::from_slice::<Digest>(&encoded).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected an array of 16 bytes, got 15 bytes in `bytes`"
+    );
+}
+
+/// Checks that `val` round-trips through an `AlgebraicValue`, returning the value.
+#[track_caller]
+fn value_round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(val: &T) -> AlgebraicValue {
+    let value = val.serialize(ValueSerializer).unwrap();
+    assert_eq!(&T::deserialize(ValueDeserializer::from_ref(&value)).unwrap(), val);
+    value
+}
+
+/// A derived struct of query parameters with range fields.
+#[derive(spacetimedb_sats::ser::Serialize, spacetimedb_sats::de::Deserialize, Debug, PartialEq)]
+#[sats(crate = spacetimedb_sats)]
+struct TickQuery {
+    ticks: std::ops::Range<u64>,
+    levels: std::ops::RangeInclusive<u8>,
+    after: std::ops::Bound<u32>,
+}
+
+#[test]
+fn ranges_encode_as_products() {
+    use std::ops::{Bound, RangeInclusive};
+
+    // A range is `{ start, end }`.
+    let bytes = round_trip(&(3u64..7));
+    assert_eq!(
+        bytes,
+        [bsatn::to_vec(&3u64).unwrap(), bsatn::to_vec(&7u64).unwrap()].concat()
+    );
+    assert_eq!(round_trip(&(3u64..=7)), bytes);
+    let ty = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::U64, "start"),
+        ProductTypeElement::new_named(AlgebraicType::U64, "end"),
+    ]);
+    let decoded = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+    assert_eq!(decoded, AlgebraicValue::product([3u64.into(), 7u64.into()].into()));
+    assert_eq!(value_round_trip(&(3u64..7)), decoded);
+    assert_eq!(value_round_trip(&(3u64..=7)), decoded);
+
+    // Empty and reversed ranges are kept as they are.
+    for (start, end) in [(5i32, 5), (9, -2)] {
+        round_trip(&(start..end));
+        round_trip(&(start..=end));
+        value_round_trip(&(start..end));
+        value_round_trip(&(start..=end));
+    }
+
+    // An inclusive range comes back fresh, even if it was exhausted.
+    let mut exhausted = 1u8..=1;
+    exhausted.next();
+    assert!(exhausted.is_empty());
+    let decoded = bsatn::from_slice::<RangeInclusive<u8>>(&bsatn::to_vec(&exhausted).unwrap()).unwrap();
+    assert_eq!(decoded, 1..=1);
+    assert!(!decoded.is_empty());
+
+    // A bound is `(included: T | excluded: T | unbounded: ())`.
+    assert_eq!(round_trip(&Bound::Included(4u16)), [0, 4, 0]);
+    assert_eq!(round_trip(&Bound::Excluded(4u16)), [1, 4, 0]);
+    assert_eq!(round_trip(&Bound::<u16>::Unbounded), [2]);
+    value_round_trip(&Bound::Included(4u16));
+    value_round_trip(&Bound::<u16>::Unbounded);
+
+    let query = TickQuery {
+        ticks: 100..200,
+        levels: 1..=3,
+        after: Bound::Excluded(17),
+    };
+    round_trip(&query);
+    value_round_trip(&query);
+}
+
+#[test]
+fn bounds_encode_as_sums() {
+    use std::ops::Bound;
+
+    let ty = AlgebraicType::sum(vec![
+        SumTypeVariant::new_named(AlgebraicType::String, "included"),
+        SumTypeVariant::new_named(AlgebraicType::String, "excluded"),
+        SumTypeVariant::unit("unbounded"),
+    ]);
+    let name = || "ada".to_owned();
+    for (tag, bound) in [
+        (0, Bound::Included(name())),
+        (1, Bound::Excluded(name())),
+        (2, Bound::Unbounded),
+    ] {
+        let bytes = round_trip(&bound);
+        assert_eq!(bytes[0], tag);
+        // The encoding is that of a value of the sum type, with the payload of its variant.
+        let value = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+        let payload = match &bound {
+            Bound::Included(s) | Bound::Excluded(s) => AlgebraicValue::String(s.as_str().into()),
+            Bound::Unbounded => AlgebraicValue::UNIT,
+        };
+        assert_eq!(value, AlgebraicValue::sum(tag, payload));
+        assert_eq!(value_round_trip(&bound), value);
+    }
+
+    let err = bsatn::from_slice::<Bound<u8>>(&[3]).unwrap_err();
+    assert!(
+        matches!(err.kind(), bsatn::ErrorKind::InvalidTag { got: 3, .. }),
+        "{err}"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ranges_in_json() {
+    use spacetimedb_sats::ser::serde::SerializeWrapper;
+
+    let json = serde_json::to_value(SerializeWrapper::from_ref(&(3u64..7))).unwrap();
+    assert_eq!(json, serde_json::json!({ "start": 3, "end": 7 }));
+}
+
+#[test]
+fn ip_addresses_encode_as_numbers() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    let localhost = Ipv4Addr::new(127, 0, 0, 1);
+    assert_eq!(round_trip(&localhost), round_trip(&0x7f00_0001u32));
+    assert_eq!(round_trip(&Ipv4Addr::UNSPECIFIED), [0; 4]);
+    assert_eq!(round_trip(&Ipv6Addr::UNSPECIFIED), [0; 16]);
+    assert_eq!(round_trip(&Ipv6Addr::LOCALHOST), round_trip(&1u128));
+    let v6 = "2001:db8::ff00:42:8329".parse::<Ipv6Addr>().unwrap();
+    assert_eq!(round_trip(&v6), round_trip(&u128::from(v6)));
+
+    // The order of the values is the order of the addresses.
+    let [low, high] = [Ipv4Addr::new(10, 0, 0, 255), Ipv4Addr::new(10, 0, 1, 0)].map(|ip| value_round_trip(&ip));
+    assert!(low < high);
+
+    // An `IpAddr` is a sum of the two.
+    assert_eq!(
+        round_trip(&IpAddr::V4(localhost)),
+        [&[0][..], &round_trip(&localhost)].concat()
+    );
+    assert_eq!(round_trip(&IpAddr::V6(v6)), [&[1][..], &round_trip(&v6)].concat());
+    let ty = AlgebraicType::sum(vec![
+        SumTypeVariant::new_named(AlgebraicType::U32, "V4"),
+        SumTypeVariant::new_named(AlgebraicType::U128, "V6"),
+    ]);
+    let bytes = bsatn::to_vec(&IpAddr::V6(Ipv6Addr::UNSPECIFIED)).unwrap();
+    let value = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+    assert_eq!(value, value_round_trip(&IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+
+    // Invalid tags and truncated payloads are refused.
+    assert!(bsatn::from_slice::<IpAddr>(&[2, 127, 0, 0, 1]).is_err());
+    assert!(bsatn::from_slice::<IpAddr>(&[1, 127, 0, 0, 1]).is_err());
+}
+
+#[test]
+fn socket_addresses_encode_as_products() {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    let v4 = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 20), 8080);
+    let bytes = round_trip(&v4);
+    assert_eq!(bytes, [&round_trip(v4.ip())[..], &8080u16.to_le_bytes()].concat());
+    assert_eq!(round_trip(&SocketAddr::V4(v4)), [&[0][..], &bytes].concat());
+
+    // A link-local address, scoped to an interface.
+    let scoped = "[fe80::1%3]:443".parse::<SocketAddrV6>().unwrap();
+    assert_eq!(scoped.scope_id(), 3);
+    let bytes = round_trip(&scoped);
+    let ty = AlgebraicType::product(vec![
+        ProductTypeElement::new_named(AlgebraicType::U128, "ip"),
+        ProductTypeElement::new_named(AlgebraicType::U16, "port"),
+        ProductTypeElement::new_named(AlgebraicType::U32, "flowinfo"),
+        ProductTypeElement::new_named(AlgebraicType::U32, "scope_id"),
+    ]);
+    let value = AlgebraicValue::decode(&ty, &mut &*bytes).unwrap();
+    let fields = [u128::from(*scoped.ip()).into(), 443u16.into(), 0u32.into(), 3u32.into()];
+    assert_eq!(value, AlgebraicValue::product(fields.into()));
+    assert_eq!(value_round_trip(&scoped), value);
+
+    let unspecified = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, 0, 7, 0));
+    round_trip(&unspecified);
+    value_round_trip(&unspecified);
+
+    // A malformed 5-byte payload, one byte short of an IPv4 socket address.
+    let short = &bsatn::to_vec(&v4).unwrap()[..5];
+    let err = bsatn::from_slice::<SocketAddrV4>(short).unwrap_err();
+    assert!(matches!(err.kind(), bsatn::ErrorKind::Truncated { .. }), "{err}");
+    assert!(bsatn::from_slice::<SocketAddr>(&[0, 1, 2, 3, 4]).is_err());
+}
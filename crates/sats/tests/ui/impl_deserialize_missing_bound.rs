@@ -0,0 +1,8 @@
+use spacetimedb_sats::{de::Deserialize, impl_deserialize};
+
+struct Wrapper<T>(T);
+
+// Deserializing the `T` needs `T: Deserialize<'de>`.
+impl_deserialize!([T] Wrapper<T>, (de) => <T as Deserialize<'de>>::deserialize(de).map(Wrapper));
+
+fn main() {}
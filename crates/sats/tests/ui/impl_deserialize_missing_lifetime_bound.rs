@@ -0,0 +1,8 @@
+use spacetimedb_sats::{de::Deserialize, impl_deserialize};
+
+struct Label<'a>(&'a str);
+
+// Borrowing from the input for `'a` needs `where ['de: 'a]`.
+impl_deserialize!(['a] Label<'a>, (de) => <&'de str as Deserialize<'de>>::deserialize(de).map(Label));
+
+fn main() {}
@@ -0,0 +1,7 @@
+use spacetimedb_sats::impl_deserialize;
+
+struct Name<'a>(&'a str);
+
+impl_deserialize!(['de] Name<'de>, (de) => unimplemented!());
+
+fn main() {}
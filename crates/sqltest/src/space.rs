@@ -8,7 +8,7 @@ use spacetimedb_lib::identity::AuthCtx;
 use spacetimedb_lib::relation::MemTable;
 use spacetimedb_sats::meta_type::MetaType;
 use spacetimedb_sats::satn::Satn;
-use spacetimedb_sats::{AlgebraicType, AlgebraicValue, BuiltinType, BuiltinValue};
+use spacetimedb_sats::{AlgebraicType, AlgebraicValue, BuiltinType};
 use sqllogictest::{AsyncDB, ColumnType, DBOutput};
 use std::fs;
 use std::io::Write;
@@ -120,26 +120,23 @@ impl AsyncDB for SpaceDb {
 
             for value in row.data.elements {
                 let value = match value {
-                    AlgebraicValue::Builtin(x) => match x {
-                        BuiltinValue::Bool(x) => {
-                            //for compat with sqlite...
-                            if x { "1" } else { "0" }.to_string()
-                        }
-                        BuiltinValue::I8(x) => x.to_string(),
-                        BuiltinValue::U8(x) => x.to_string(),
-                        BuiltinValue::I16(x) => x.to_string(),
-                        BuiltinValue::U16(x) => x.to_string(),
-                        BuiltinValue::I32(x) => x.to_string(),
-                        BuiltinValue::U32(x) => x.to_string(),
-                        BuiltinValue::I64(x) => x.to_string(),
-                        BuiltinValue::U64(x) => x.to_string(),
-                        BuiltinValue::I128(x) => x.to_string(),
-                        BuiltinValue::U128(x) => x.to_string(),
-                        BuiltinValue::F32(x) => format!("{:?}", x.as_ref()),
-                        BuiltinValue::F64(x) => format!("{:?}", x.as_ref()),
-                        BuiltinValue::String(x) => format!("'{}'", x),
-                        x => x.to_satn(),
-                    },
+                    AlgebraicValue::Bool(x) => {
+                        //for compat with sqlite...
+                        if x { "1" } else { "0" }.to_string()
+                    }
+                    AlgebraicValue::I8(x) => x.to_string(),
+                    AlgebraicValue::U8(x) => x.to_string(),
+                    AlgebraicValue::I16(x) => x.to_string(),
+                    AlgebraicValue::U16(x) => x.to_string(),
+                    AlgebraicValue::I32(x) => x.to_string(),
+                    AlgebraicValue::U32(x) => x.to_string(),
+                    AlgebraicValue::I64(x) => x.to_string(),
+                    AlgebraicValue::U64(x) => x.to_string(),
+                    AlgebraicValue::I128(x) => x.to_string(),
+                    AlgebraicValue::U128(x) => x.to_string(),
+                    AlgebraicValue::F32(x) => format!("{:?}", x.as_ref()),
+                    AlgebraicValue::F64(x) => format!("{:?}", x.as_ref()),
+                    AlgebraicValue::String(x) => format!("'{}'", x),
                     x => x.to_satn(),
                 };
                 row_vec.push(value);
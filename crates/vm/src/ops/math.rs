@@ -5,31 +5,27 @@ use crate::functions::Args;
 use crate::ops::shared::bin_op;
 use crate::program::ProgramRef;
 use spacetimedb_sats::algebraic_value::AlgebraicValue;
-use spacetimedb_sats::builtin_value::BuiltinValue;
 
 macro_rules! math_op {
     ($name:ident, $op:path) => {
         pub(crate) fn $name(lhs: &AlgebraicValue, rhs: &AlgebraicValue) -> AlgebraicValue {
-            match (lhs.as_builtin(), rhs.as_builtin()) {
-                (Some(lhs), Some(rhs)) => match (lhs, rhs) {
-                    (BuiltinValue::U8(a), BuiltinValue::U8(b)) => bin_op::<u8, _>($op, *a, *b),
-                    (BuiltinValue::I8(a), BuiltinValue::I8(b)) => bin_op::<i8, _>($op, *a, *b),
-                    (BuiltinValue::U16(a), BuiltinValue::U16(b)) => bin_op::<u16, _>($op, *a, *b),
-                    (BuiltinValue::I16(a), BuiltinValue::I16(b)) => bin_op::<i16, _>($op, *a, *b),
-                    (BuiltinValue::U32(a), BuiltinValue::U32(b)) => bin_op::<u32, _>($op, *a, *b),
-                    (BuiltinValue::I32(a), BuiltinValue::I32(b)) => bin_op::<i32, _>($op, *a, *b),
-                    (BuiltinValue::U64(a), BuiltinValue::U64(b)) => bin_op::<u64, _>($op, *a, *b),
-                    (BuiltinValue::I64(a), BuiltinValue::I64(b)) => bin_op::<i64, _>($op, *a, *b),
-                    (BuiltinValue::U128(a), BuiltinValue::U128(b)) => bin_op::<u128, _>($op, *a, *b),
-                    (BuiltinValue::I128(a), BuiltinValue::I128(b)) => bin_op::<i128, _>($op, *a, *b),
-                    (BuiltinValue::F32(a), BuiltinValue::F32(b)) => {
-                        bin_op::<f32, _>($op, a.into_inner(), b.into_inner())
-                    }
-                    (BuiltinValue::F64(a), BuiltinValue::F64(b)) => {
-                        bin_op::<f64, _>($op, a.into_inner(), b.into_inner())
-                    }
-                    _ => unreachable!("Calling a math op with invalid param value"),
-                },
+            match (lhs, rhs) {
+                (AlgebraicValue::U8(a), AlgebraicValue::U8(b)) => bin_op::<u8, _>($op, *a, *b),
+                (AlgebraicValue::I8(a), AlgebraicValue::I8(b)) => bin_op::<i8, _>($op, *a, *b),
+                (AlgebraicValue::U16(a), AlgebraicValue::U16(b)) => bin_op::<u16, _>($op, *a, *b),
+                (AlgebraicValue::I16(a), AlgebraicValue::I16(b)) => bin_op::<i16, _>($op, *a, *b),
+                (AlgebraicValue::U32(a), AlgebraicValue::U32(b)) => bin_op::<u32, _>($op, *a, *b),
+                (AlgebraicValue::I32(a), AlgebraicValue::I32(b)) => bin_op::<i32, _>($op, *a, *b),
+                (AlgebraicValue::U64(a), AlgebraicValue::U64(b)) => bin_op::<u64, _>($op, *a, *b),
+                (AlgebraicValue::I64(a), AlgebraicValue::I64(b)) => bin_op::<i64, _>($op, *a, *b),
+                (AlgebraicValue::U128(a), AlgebraicValue::U128(b)) => bin_op::<u128, _>($op, *a, *b),
+                (AlgebraicValue::I128(a), AlgebraicValue::I128(b)) => bin_op::<i128, _>($op, *a, *b),
+                (AlgebraicValue::F32(a), AlgebraicValue::F32(b)) => {
+                    bin_op::<f32, _>($op, a.into_inner(), b.into_inner())
+                }
+                (AlgebraicValue::F64(a), AlgebraicValue::F64(b)) => {
+                    bin_op::<f64, _>($op, a.into_inner(), b.into_inner())
+                }
                 _ => unreachable!("Calling a math op with invalid param value"),
             }
         }
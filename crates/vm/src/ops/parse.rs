@@ -48,7 +48,7 @@ pub fn parse(value: &str, ty: &AlgebraicType) -> Result<AlgebraicValue, ErrorVm>
             BuiltinType::U128 => _parse::<u128>(value, ty),
             BuiltinType::F32 => _parse::<f32>(value, ty),
             BuiltinType::F64 => _parse::<f64>(value, ty),
-            BuiltinType::String => Ok(AlgebraicValue::String(value.to_string())),
+            BuiltinType::String => Ok(AlgebraicValue::String(value.into())),
             x => Err(ErrorVm::Unsupported(format!(
                 "Can't parse '{value}' to {}",
                 x.to_satn_pretty()